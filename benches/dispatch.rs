@@ -0,0 +1,113 @@
+//! Per-call overhead of the tool-calling hot path: schema compilation, argument
+//! validation, payload parsing and dispatch. Run with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use serde_json::{json, Value};
+use std::hint::black_box;
+use tool_calling::{bench, tool, ToolHandler};
+
+// Define the tools under measurement
+
+#[tool]
+/// Adds two integers
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+#[tool]
+/// Books a flight
+pub fn book_flight(
+    from: String,
+    to: String,
+    passengers: Vec<String>,
+    seats: Option<u32>,
+    window: Option<bool>,
+    notes: Option<String>,
+) -> String {
+    let _ = (seats, window, notes);
+    format!("{} to {} for {}", from, to, passengers.join(", "))
+}
+
+fn call(name: &str, arguments: Value) -> Value {
+    json!({ "type": "function", "function": { "name": name, "arguments": arguments } })
+}
+
+fn cases() -> [(&'static str, Value); 2] {
+    [
+        ("add", json!({ "a": 1, "b": 2 })),
+        (
+            "book_flight",
+            json!({
+                "from": "LHR",
+                "to": "JFK",
+                "passengers": ["Ada", "Grace"],
+                "seats": 2,
+                "notes": "aisle"
+            }),
+        ),
+    ]
+}
+
+fn schema_compilation(c: &mut Criterion) {
+    let handler = ToolHandler::default();
+    let mut group = c.benchmark_group("compile_schema");
+    for (name, _) in cases() {
+        group.bench_function(name, |b| {
+            b.iter(|| bench::compile_schema(&handler, black_box(name)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn validation(c: &mut Criterion) {
+    let handler = ToolHandler::default();
+    let mut group = c.benchmark_group("validate_arguments");
+    for (name, arguments) in cases() {
+        group.bench_function(name, |b| {
+            b.iter(|| bench::validate_arguments(&handler, name, black_box(&arguments)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn parsing(c: &mut Criterion) {
+    let handler = ToolHandler::default();
+    let mut group = c.benchmark_group("validate_tool_call");
+    for (name, arguments) in cases() {
+        let payload = call(name, arguments);
+        group.bench_function(name, |b| {
+            b.iter(|| handler.validate_tool_call(black_box(&payload)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn dispatch(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let handler = ToolHandler::default();
+    let mut group = c.benchmark_group("dispatch");
+    for (name, arguments) in cases() {
+        let args = handler
+            .validate_tool_call(&call(name, arguments))
+            .unwrap()
+            .args;
+        group.bench_function(name, |b| {
+            b.to_async(&runtime)
+                .iter(|| handler.call_with_values(name, black_box(args.as_slice())))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("call_tool");
+    for (name, arguments) in cases() {
+        let payload = call(name, arguments);
+        group.bench_function(name, |b| {
+            b.to_async(&runtime)
+                .iter(|| handler.call_tool(black_box(&payload)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, schema_compilation, validation, parsing, dispatch);
+criterion_main!(benches);
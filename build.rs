@@ -0,0 +1,14 @@
+fn main() {
+    // The gRPC service is generated only when the `grpc` feature is enabled
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/tool_calling.proto");
+        if std::env::var_os("PROTOC").is_none() {
+            let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc");
+            std::env::set_var("PROTOC", protoc);
+        }
+        tonic_prost_build::configure()
+            .compile_protos(&["proto/tool_calling.proto"], &["proto"])
+            .expect("failed to compile proto/tool_calling.proto");
+    }
+}
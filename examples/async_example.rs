@@ -115,7 +115,7 @@ async fn main() {
 
     // Rename call_tool_async to call_tool (await is already present)
     match tool_handler.call_tool(&example_call).await {
-        Ok(result) => println!("Result: {}", result),
+        Ok(result) => println!("Result: {}", result.output),
         // Print ToolError
         Err(err) => println!("Error calling tool: {}", err),
     }
@@ -77,7 +77,7 @@ async fn main() {
     );
 
     let res_no_opt = handler.call_tool(&call_no_opt).await.unwrap();
-    println!("Result: {}", res_no_opt);
+    println!("Result: {}", res_no_opt.output);
 
     /*
     Println output:
@@ -112,7 +112,7 @@ async fn main() {
     );
 
     let res_with_opt = handler.call_tool(&call_with_opt).await.unwrap();
-    println!("Result: {}", res_with_opt);
+    println!("Result: {}", res_with_opt.output);
 
     /*
     Println output:
@@ -115,7 +115,7 @@ async fn main() {
 
     // You can then call the tool like this, handling the error:
     match tool_handler.call_tool(&example_call).await {
-        Ok(result) => println!("Result: {}", result),
+        Ok(result) => println!("Result: {}", result.output),
         Err(err) => println!("Error calling tool: {}", err),
     }
 
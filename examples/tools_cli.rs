@@ -0,0 +1,26 @@
+use tool_calling::{cli, tool, ToolHandler};
+
+/*
+
+A command line for the tools defined in this binary:
+
+    cargo run --example tools_cli -- tools list
+    cargo run --example tools_cli -- tools describe get_user_info
+    cargo run --example tools_cli -- tools call get_user_info --json '{"user_id": 1}'
+
+*/
+
+#[tool]
+/// Get user info from database
+fn get_user_info(user_id: u32) -> String {
+    match user_id {
+        1 => "User 1 info: Name: John Doe, Email: john.doe@example.com".to_string(),
+        2 => "User 2 info: Name: Jane Smith, Email: jane.smith@example.com".to_string(),
+        _ => "User not found".to_string(),
+    }
+}
+
+#[tokio::main]
+async fn main() -> std::process::ExitCode {
+    cli::main(&ToolHandler::default()).await
+}
@@ -0,0 +1,165 @@
+use serde_json::{json, Map, Value};
+
+/// Reassembles tool calls from a streamed chat completion.
+///
+/// Streaming responses deliver tool calls in fragments: the first delta of a call carries
+/// its `index`, `id` and name, and the following ones append pieces of the `arguments`
+/// JSON string. Feed every chunk to [`ToolCallAccumulator::push`], which returns each call
+/// as soon as its arguments form a complete JSON object, in the payload shape accepted by
+/// [`ToolHandler::call_tool`](crate::ToolHandler::call_tool). Calls still incomplete when
+/// the stream ends are returned by [`ToolCallAccumulator::finish`].
+///
+/// Chunks may be OpenAI-style `chat.completion.chunk` objects (the first choice's
+/// `delta.tool_calls` are read), bare deltas or `tool_calls` arrays, or Ollama chunks, whose
+/// `message.tool_calls` arrive whole.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use tool_calling::ToolCallAccumulator;
+///
+/// let mut calls = ToolCallAccumulator::new();
+/// let chunk = |delta| json!({ "choices": [{ "index": 0, "delta": { "tool_calls": [delta] } }] });
+///
+/// let first = json!({ "index": 0, "id": "call_1", "type": "function", "function": { "name": "add", "arguments": "{\"a\": 1," } });
+/// assert!(calls.push(&chunk(first)).is_empty());
+///
+/// let rest = json!({ "index": 0, "function": { "arguments": " \"b\": 2}" } });
+/// let complete = calls.push(&chunk(rest));
+/// assert_eq!(
+///     complete,
+///     vec![json!({ "type": "function", "id": "call_1", "function": { "name": "add", "arguments": { "a": 1, "b": 2 } } })]
+/// );
+/// assert!(calls.finish().is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ToolCallAccumulator {
+    calls: Vec<PartialCall>,
+}
+
+#[derive(Debug, Clone)]
+struct PartialCall {
+    index: Option<u64>,
+    id: Option<String>,
+    name: String,
+    arguments: String,
+    complete: bool,
+}
+
+impl ToolCallAccumulator {
+    /// Creates an accumulator with no calls in progress.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ingests one streamed chunk and returns the calls it completed.
+    pub fn push(&mut self, chunk: &Value) -> Vec<Value> {
+        let mut completed = Vec::new();
+        for delta in deltas(chunk) {
+            let index = delta.get("index").and_then(Value::as_u64);
+            let position = match index
+                .and_then(|index| self.calls.iter().position(|call| call.index == Some(index)))
+            {
+                Some(position) => position,
+                None => {
+                    self.calls.push(PartialCall {
+                        index,
+                        id: None,
+                        name: String::new(),
+                        arguments: String::new(),
+                        complete: false,
+                    });
+                    self.calls.len() - 1
+                }
+            };
+            let call = &mut self.calls[position];
+            if call.complete {
+                continue;
+            }
+            if let Some(id) = delta.get("id").and_then(Value::as_str) {
+                if !id.is_empty() {
+                    call.id = Some(id.to_string());
+                }
+            }
+            let function = delta.get("function").unwrap_or(delta);
+            if let Some(name) = function.get("name").and_then(Value::as_str) {
+                call.name.push_str(name);
+            }
+            match function.get("arguments") {
+                Some(Value::String(fragment)) => call.arguments.push_str(fragment),
+                Some(arguments @ Value::Object(_)) => call.arguments = arguments.to_string(),
+                _ => {}
+            }
+            if let Some(arguments) = call.parsed_arguments() {
+                call.complete = true;
+                completed.push(call.payload(arguments));
+            }
+        }
+        completed
+    }
+
+    /// The number of calls started but not yet returned.
+    pub fn pending(&self) -> usize {
+        self.calls.iter().filter(|call| !call.complete).count()
+    }
+
+    /// Ends the stream, returning the calls that never completed, in the order they started.
+    ///
+    /// A call without arguments gets an empty object. Arguments that are not valid JSON are
+    /// returned as the received string, so calling the tool fails with
+    /// `ToolError::BadArgs` under the call's id.
+    pub fn finish(self) -> Vec<Value> {
+        self.calls
+            .into_iter()
+            .filter(|call| !call.complete)
+            .map(|call| {
+                let arguments = match call.arguments.trim() {
+                    "" => json!({}),
+                    _ => Value::String(call.arguments.clone()),
+                };
+                call.payload(arguments)
+            })
+            .collect()
+    }
+}
+
+impl PartialCall {
+    fn parsed_arguments(&self) -> Option<Value> {
+        if self.name.is_empty() {
+            return None;
+        }
+        serde_json::from_str::<Value>(&self.arguments)
+            .ok()
+            .filter(Value::is_object)
+    }
+
+    fn payload(&self, arguments: Value) -> Value {
+        let mut call = Map::new();
+        call.insert("type".into(), json!("function"));
+        if let Some(id) = &self.id {
+            call.insert("id".into(), json!(id));
+        }
+        call.insert(
+            "function".into(),
+            json!({ "name": self.name, "arguments": arguments }),
+        );
+        Value::Object(call)
+    }
+}
+
+// The tool call deltas carried by a chunk
+fn deltas(chunk: &Value) -> &[Value] {
+    let calls = if let Some(choices) = chunk.get("choices") {
+        &choices[0]["delta"]["tool_calls"]
+    } else if let Some(message) = chunk.get("message") {
+        &message["tool_calls"]
+    } else if let Some(calls) = chunk.get("tool_calls") {
+        calls
+    } else if chunk.get("function").is_some() {
+        return std::slice::from_ref(chunk);
+    } else {
+        chunk
+    };
+    calls.as_array().map(Vec::as_slice).unwrap_or_default()
+}
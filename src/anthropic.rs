@@ -0,0 +1,123 @@
+//! Anthropic's tool-use format.
+//!
+//! [`ToolHandler::anthropic_tools`] renders the `tools` of a Messages API request. The
+//! assistant's reply asks for tools with `tool_use` content blocks;
+//! [`ToolHandler::answer_tool_uses`] runs each of them and returns the `tool_result` blocks
+//! to send back in the next user message. Failed calls are reported with `is_error` set and
+//! the text of [`ToolHandler::error_message`].
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::{tool, ToolContext, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let handler = ToolHandler::default();
+//!     assert_eq!(handler.anthropic_tools()[0]["input_schema"]["type"], "object");
+//!
+//!     let content = json!([
+//!         { "type": "text", "text": "Let me add those." },
+//!         { "type": "tool_use", "id": "toolu_01", "name": "add", "input": { "a": 1, "b": 2 } }
+//!     ]);
+//!     let results = handler.answer_tool_uses(&content, &ToolContext::default()).await;
+//!     assert_eq!(
+//!         results,
+//!         vec![json!({ "type": "tool_result", "tool_use_id": "toolu_01", "content": "3" })]
+//!     );
+//! }
+//! ```
+
+use crate::{SchemaFlavor, ToolCallResult, ToolContext, ToolError, ToolHandler, ToolResultContent};
+use serde_json::{json, Value};
+
+/// Converts a `tool_use` content block into the call payload accepted by
+/// [`ToolHandler::call_tool`].
+pub fn parse_tool_use(block: &Value) -> Result<Value, ToolError> {
+    if block["type"] != "tool_use" {
+        return Err(ToolError::BadArgs(
+            "Expected a content block of type 'tool_use'".to_string(),
+        ));
+    }
+    let name = block["name"]
+        .as_str()
+        .ok_or_else(|| ToolError::BadArgs("Missing or invalid 'name' field".to_string()))?;
+    let input = match &block["input"] {
+        Value::Null => json!({}),
+        input => input.clone(),
+    };
+    let mut call = json!({ "type": "function", "function": { "name": name, "arguments": input } });
+    if let Some(id) = block["id"].as_str() {
+        call["id"] = json!(id);
+    }
+    Ok(call)
+}
+
+/// A `tool_result` content block answering the `tool_use` block with id `tool_use_id`.
+pub fn tool_result(tool_use_id: &str, content: &str, is_error: bool) -> Value {
+    let mut block =
+        json!({ "type": "tool_result", "tool_use_id": tool_use_id, "content": content });
+    if is_error {
+        block["is_error"] = json!(true);
+    }
+    block
+}
+
+/// A `tool_result` content block answering the `tool_use` block with id `tool_use_id` with
+/// rich content. A single text item is sent as a plain string, as [`tool_result`] does.
+pub fn tool_result_content(tool_use_id: &str, content: &[ToolResultContent]) -> Value {
+    if let [ToolResultContent::Text { text }] = content {
+        return tool_result(tool_use_id, text, false);
+    }
+    let blocks = content
+        .iter()
+        .map(ToolResultContent::to_anthropic)
+        .collect::<Vec<_>>();
+    json!({ "type": "tool_result", "tool_use_id": tool_use_id, "content": blocks })
+}
+
+impl ToolHandler {
+    /// The definition of every tool, in the shape of an Anthropic request's `tools`.
+    pub fn anthropic_tools(&self) -> Value {
+        Value::Array(
+            self.tools()
+                .map(|tool| self.render_tool(tool, SchemaFlavor::Anthropic))
+                .collect(),
+        )
+    }
+
+    /// Runs the call described by a `tool_use` content block, as
+    /// [`ToolHandler::call_tool_with_context`] does.
+    pub async fn call_tool_use(
+        &self,
+        block: &Value,
+        context: &ToolContext,
+    ) -> Result<ToolCallResult, ToolError> {
+        let call = parse_tool_use(block)?;
+        self.call_tool_with_context(&call, context).await
+    }
+
+    /// Runs every `tool_use` block in an assistant message's `content`, concurrently, and
+    /// returns a `tool_result` block for each, in order. Other blocks are skipped.
+    pub async fn answer_tool_uses(&self, content: &Value, context: &ToolContext) -> Vec<Value> {
+        let blocks = content
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|block| block["type"] == "tool_use");
+        let answers = blocks.map(|block| async move {
+            let id = block["id"].as_str().unwrap_or_default();
+            match self.call_tool_use(block, context).await {
+                Ok(result) => tool_result_content(id, &result.content()),
+                Err(err) => tool_result(id, &self.error_message(&err), true),
+            }
+        });
+        self.run_batch(answers).await
+    }
+}
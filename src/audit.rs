@@ -0,0 +1,363 @@
+//! Recording and querying the tool calls a handler executes.
+//!
+//! Attach an [`AuditSink`] with [`ToolHandler::with_audit`](crate::ToolHandler::with_audit)
+//! and every execution is recorded as an [`AuditRecord`]: when it started, which tool ran
+//! with which arguments, what it returned, how long it took, and the metadata of the
+//! caller set with [`ToolHandler::with_caller_metadata`](crate::ToolHandler::with_caller_metadata).
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use tool_calling::audit::{AuditQuery, AuditSink, MemoryAuditLog};
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let log = Arc::new(MemoryAuditLog::with_capacity(1000));
+//!     let handler = ToolHandler::default()
+//!         .with_audit(log.clone())
+//!         .with_caller_metadata("user", "alice");
+//!     handler.call_with_args("add", &["1".into(), "2".into()]).await.unwrap();
+//!
+//!     let records = log.query(&AuditQuery::new().tool("add").metadata("user", "alice"));
+//!     assert_eq!(records[0].arguments["a"], 1);
+//!     assert_eq!(records[0].result, Ok("3".into()));
+//! }
+//! ```
+
+use crate::{Tool, ToolError, ToolStream};
+use futures::{ready, Stream};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime};
+
+/// One executed tool call.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// When the call started.
+    pub timestamp: SystemTime,
+    /// The name of the tool that ran.
+    pub name: String,
+    /// The call id from the payload, for calls made through `call_tool`.
+    pub call_id: Option<String>,
+    /// The arguments the tool received, keyed by parameter name, with secret values
+    /// redacted.
+    pub arguments: Map<String, Value>,
+    /// The tool's output or error.
+    pub result: Result<String, ToolError>,
+    /// Time spent executing the tool.
+    pub duration: Duration,
+    /// Metadata describing the caller.
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// A destination for audit records.
+///
+/// Implement this to send records to a database, a log pipeline, or anywhere else.
+pub trait AuditSink: Send + Sync {
+    /// Stores a record. Called once per execution, after the tool finishes.
+    fn record(&self, record: &AuditRecord);
+
+    /// Returns the stored records matching `query`, oldest first.
+    ///
+    /// The default returns nothing, for sinks that cannot be read back.
+    fn query(&self, query: &AuditQuery) -> Vec<AuditRecord> {
+        let _ = query;
+        Vec::new()
+    }
+}
+
+/// Filters for [`AuditSink::query`]. Every condition that is set must match.
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    tool: Option<String>,
+    since: Option<SystemTime>,
+    until: Option<SystemTime>,
+    errors_only: bool,
+    metadata: Vec<(String, String)>,
+    limit: Option<usize>,
+}
+
+impl AuditQuery {
+    /// Creates a query matching every record.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only matches calls to the named tool.
+    pub fn tool(mut self, name: impl Into<String>) -> Self {
+        self.tool = Some(name.into());
+        self
+    }
+
+    /// Only matches calls started at or after `time`.
+    pub fn since(mut self, time: SystemTime) -> Self {
+        self.since = Some(time);
+        self
+    }
+
+    /// Only matches calls started before `time`.
+    pub fn until(mut self, time: SystemTime) -> Self {
+        self.until = Some(time);
+        self
+    }
+
+    /// Only matches calls that failed.
+    pub fn errors_only(mut self) -> Self {
+        self.errors_only = true;
+        self
+    }
+
+    /// Only matches calls whose caller metadata has `key` set to `value`.
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.push((key.into(), value.into()));
+        self
+    }
+
+    /// Returns at most the `limit` most recent matches.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Whether `record` satisfies every condition of the query, ignoring the limit.
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.tool.as_ref().is_none_or(|tool| record.name == *tool)
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && self.until.is_none_or(|until| record.timestamp < until)
+            && (!self.errors_only || record.result.is_err())
+            && self
+                .metadata
+                .iter()
+                .all(|(key, value)| record.metadata.get(key) == Some(value))
+    }
+
+    // Filter records given oldest first, keeping the most recent `limit`
+    fn apply<'a>(&self, records: impl Iterator<Item = &'a AuditRecord>) -> Vec<AuditRecord> {
+        let mut matched = records
+            .filter(|record| self.matches(record))
+            .cloned()
+            .collect::<Vec<_>>();
+        if let Some(limit) = self.limit {
+            let skip = matched.len().saturating_sub(limit);
+            matched.drain(..skip);
+        }
+        matched
+    }
+}
+
+/// Keeps the most recent records in memory, dropping the oldest beyond its capacity.
+#[derive(Debug)]
+pub struct MemoryAuditLog {
+    capacity: usize,
+    records: Mutex<VecDeque<AuditRecord>>,
+}
+
+impl MemoryAuditLog {
+    /// Creates a log holding at most `capacity` records.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            records: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// The number of records currently held.
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap().len()
+    }
+
+    /// Whether the log holds no records.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl AuditSink for MemoryAuditLog {
+    fn record(&self, record: &AuditRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut records = self.records.lock().unwrap();
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record.clone());
+    }
+
+    fn query(&self, query: &AuditQuery) -> Vec<AuditRecord> {
+        query.apply(self.records.lock().unwrap().iter())
+    }
+}
+
+/// Appends records to a file as JSON lines.
+#[derive(Debug)]
+pub struct FileAuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl FileAuditLog {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl AuditSink for FileAuditLog {
+    fn record(&self, record: &AuditRecord) {
+        let mut line = serde_json::to_string(record).expect("audit records serialize");
+        line.push('\n');
+        let mut file = self.file.lock().unwrap();
+        if let Err(err) = file.write_all(line.as_bytes()).and_then(|_| file.flush()) {
+            tracing::warn!(path = %self.path.display(), error = %err, "failed to write audit record");
+        }
+    }
+
+    // Reads the file back; lines that do not parse are skipped
+    fn query(&self, query: &AuditQuery) -> Vec<AuditRecord> {
+        let _guard = self.file.lock().unwrap();
+        let Ok(file) = File::open(&self.path) else {
+            return Vec::new();
+        };
+        let records = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<AuditRecord>(&line).ok())
+            .collect::<Vec<_>>();
+        query.apply(records.iter())
+    }
+}
+
+// Records into two sinks
+pub(crate) struct Tee(pub Arc<dyn AuditSink>, pub Arc<dyn AuditSink>);
+
+impl AuditSink for Tee {
+    fn record(&self, record: &AuditRecord) {
+        self.0.record(record);
+        self.1.record(record);
+    }
+
+    fn query(&self, query: &AuditQuery) -> Vec<AuditRecord> {
+        self.0.query(query)
+    }
+}
+
+// An execution in progress, recorded once it finishes
+pub(crate) struct PendingAudit {
+    sink: Arc<dyn AuditSink>,
+    record: AuditRecord,
+    started: Instant,
+}
+
+impl PendingAudit {
+    pub(crate) fn start(
+        sink: &Arc<dyn AuditSink>,
+        tool: &Tool,
+        args: &[Value],
+        call_id: Option<&str>,
+        metadata: &BTreeMap<String, String>,
+    ) -> Self {
+        let names = tool
+            .parameter_schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|props| props.keys().collect::<Vec<_>>())
+            .unwrap_or_default();
+        // Surplus positional arguments are keyed by their index
+        let arguments = args
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let key = names
+                    .get(i)
+                    .map_or_else(|| i.to_string(), |name| name.to_string());
+                (key, value.clone())
+            })
+            .collect();
+        let arguments = tool.redact(&arguments);
+        Self {
+            sink: Arc::clone(sink),
+            record: AuditRecord {
+                timestamp: SystemTime::now(),
+                name: tool.name.clone(),
+                call_id: call_id.map(str::to_owned),
+                arguments,
+                result: Ok(String::new()),
+                duration: Duration::ZERO,
+                metadata: metadata.clone(),
+            },
+            started: Instant::now(),
+        }
+    }
+
+    pub(crate) fn finish(mut self, result: &Result<String, ToolError>) {
+        self.record.result = result.clone();
+        self.record.duration = self.started.elapsed();
+        self.sink.record(&self.record);
+    }
+
+    // Record the stream once it ends, fails, or is dropped early
+    pub(crate) fn wrap_stream(self, inner: ToolStream) -> ToolStream {
+        Box::pin(AuditedStream {
+            inner,
+            output: String::new(),
+            pending: Some(self),
+        })
+    }
+}
+
+struct AuditedStream {
+    inner: ToolStream,
+    output: String,
+    pending: Option<PendingAudit>,
+}
+
+impl Stream for AuditedStream {
+    type Item = Result<String, ToolError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = ready!(self.inner.as_mut().poll_next(cx));
+        match &item {
+            Some(Ok(chunk)) => self.output.push_str(chunk),
+            Some(Err(err)) => {
+                if let Some(pending) = self.pending.take() {
+                    pending.finish(&Err(err.clone()));
+                }
+            }
+            None => {
+                if let Some(pending) = self.pending.take() {
+                    let output = std::mem::take(&mut self.output);
+                    pending.finish(&Ok(output));
+                }
+            }
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl Drop for AuditedStream {
+    fn drop(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            pending.finish(&Err(ToolError::Execution(
+                "stream dropped before completion".into(),
+            )));
+        }
+    }
+}
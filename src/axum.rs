@@ -0,0 +1,131 @@
+//! Mounting a handler in an axum application.
+//!
+//! [`router`] serves the two endpoints every agent backend needs:
+//!
+//! - `GET /tools` returns [`ToolHandler::all_tools_schema`], and `GET /tools/{name}` the
+//!   schema of one tool.
+//! - `POST /tools/{name}` runs the tool with the request's JSON object body as its arguments
+//!   and returns the [`ToolCallResult`](crate::ToolCallResult) as JSON.
+//!
+//! Failed calls answer `404 Not Found` for unknown tools, `422 Unprocessable Entity` for
+//! invalid arguments and `500 Internal Server Error` for failed executions, with a body of
+//! `{"error": {"kind", "message"}}` built from [`ToolError::kind`] and
+//! [`ToolHandler::error_message`]. Nest the router to serve it under a prefix.
+//!
+//! ```rust,no_run
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let app = axum::Router::new().nest("/api", tool_calling::axum::router(ToolHandler::default()));
+//!     let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await.unwrap();
+//!     axum::serve(listener, app).await.unwrap();
+//! }
+//! ```
+
+use crate::{ToolContext, ToolError, ToolHandler};
+use ::axum::extract::{Path, State};
+use ::axum::http::{HeaderMap, StatusCode};
+use ::axum::response::{IntoResponse, Response};
+use ::axum::routing::get;
+use ::axum::{Json, Router};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// A hook building the [`ToolContext`] of a call from its request headers.
+pub type HeaderContextFn = Arc<dyn Fn(&HeaderMap) -> ToolContext + Send + Sync>;
+
+struct Mounted {
+    handler: ToolHandler,
+    context: HeaderContextFn,
+}
+
+/// A router serving `GET /tools`, `GET /tools/{name}` and `POST /tools/{name}` for
+/// `handler`'s tools. Calls get an empty [`ToolContext`].
+pub fn router(handler: ToolHandler) -> Router {
+    router_with_context(handler, |_| ToolContext::default())
+}
+
+/// Like [`router`], building each call's [`ToolContext`] from the request headers, for
+/// example to carry an authenticated user id. The context also decides which tools
+/// `GET /tools` lists, as described in [`ToolHandler::with_tool_filter`].
+pub fn router_with_context<F>(handler: ToolHandler, context: F) -> Router
+where
+    F: Fn(&HeaderMap) -> ToolContext + Send + Sync + 'static,
+{
+    let mounted = Arc::new(Mounted {
+        handler,
+        context: Arc::new(context),
+    });
+    Router::new()
+        .route("/tools", get(list_tools))
+        .route("/tools/{name}", get(describe_tool).post(call_tool))
+        .with_state(mounted)
+}
+
+/// The HTTP status used for a failed call.
+pub fn status_for(err: &ToolError) -> StatusCode {
+    match err {
+        ToolError::NotFound(_) => StatusCode::NOT_FOUND,
+        ToolError::BadArgs(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        ToolError::Execution(_) | ToolError::Decode(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+async fn list_tools(State(mounted): State<Arc<Mounted>>, headers: HeaderMap) -> Json<Value> {
+    let context = (mounted.context)(&headers);
+    Json(mounted.handler.tools_schema_with_context(&context))
+}
+
+async fn describe_tool(
+    State(mounted): State<Arc<Mounted>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let context = (mounted.context)(&headers);
+    let schema = mounted.handler.tools_schema_with_context(&context);
+    let found = schema
+        .as_array()
+        .and_then(|tools| tools.iter().find(|tool| tool["function"]["name"] == name));
+    match found {
+        Some(tool) => Json(tool.clone()).into_response(),
+        None => error_response(&mounted.handler, &ToolError::NotFound(name)),
+    }
+}
+
+async fn call_tool(
+    State(mounted): State<Arc<Mounted>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Option<Json<Value>>,
+) -> Response {
+    let arguments = match body {
+        Some(Json(arguments @ Value::Object(_))) => arguments,
+        None | Some(Json(Value::Null)) => json!({}),
+        Some(Json(_)) => {
+            let err = ToolError::BadArgs("Request body must be a JSON object".to_string());
+            return error_response(&mounted.handler, &err);
+        }
+    };
+    let call = json!({ "type": "function", "function": { "name": name, "arguments": arguments } });
+    let context = (mounted.context)(&headers);
+    match mounted
+        .handler
+        .call_tool_with_context(&call, &context)
+        .await
+    {
+        Ok(result) => Json(result).into_response(),
+        Err(err) => error_response(&mounted.handler, &err),
+    }
+}
+
+fn error_response(handler: &ToolHandler, err: &ToolError) -> Response {
+    let body = json!({ "error": { "kind": err.kind(), "message": handler.error_message(err) } });
+    (status_for(err), Json(body)).into_response()
+}
@@ -0,0 +1,175 @@
+//! The tool format of the AWS Bedrock Converse API.
+//!
+//! [`ToolHandler::bedrock_tool_config`] renders the `toolConfig` of a `Converse` request:
+//! every tool as a `toolSpec`, and optionally a `toolChoice`. The model asks for tools with
+//! `toolUse` content blocks; [`ToolHandler::answer_bedrock_tool_uses`] runs each of them and
+//! returns the `toolResult` blocks to send back as the content of the next `user` message.
+//! Failed calls are reported with `status: "error"` and the text of
+//! [`ToolHandler::error_message`].
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::bedrock::ToolChoice;
+//! use tool_calling::{tool, ToolContext, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let handler = ToolHandler::default();
+//!     let config = handler.bedrock_tool_config(Some(ToolChoice::Any));
+//!     assert_eq!(config["tools"][0]["toolSpec"]["name"], "add");
+//!     assert_eq!(config["toolChoice"], json!({ "any": {} }));
+//!
+//!     let response = json!({
+//!         "output": { "message": { "role": "assistant", "content": [
+//!             { "toolUse": { "toolUseId": "tooluse_1", "name": "add", "input": { "a": 1, "b": 2 } } }
+//!         ] } },
+//!         "stopReason": "tool_use"
+//!     });
+//!     let results = handler.answer_bedrock_tool_uses(&response, &ToolContext::default()).await;
+//!     assert_eq!(
+//!         results,
+//!         vec![json!({ "toolResult": {
+//!             "toolUseId": "tooluse_1",
+//!             "content": [{ "text": "3" }],
+//!             "status": "success"
+//!         } })]
+//!     );
+//! }
+//! ```
+
+use crate::{SchemaFlavor, ToolCallResult, ToolContext, ToolError, ToolHandler, ToolResultContent};
+use serde_json::{json, Value};
+
+/// How a Converse request lets the model pick tools.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model decides whether to call a tool.
+    #[default]
+    Auto,
+    /// The model must call at least one tool.
+    Any,
+    /// The model must call the named tool.
+    Tool(String),
+}
+
+impl ToolChoice {
+    /// The `toolChoice` JSON of a `toolConfig`.
+    pub fn to_json(&self) -> Value {
+        match self {
+            ToolChoice::Auto => json!({ "auto": {} }),
+            ToolChoice::Any => json!({ "any": {} }),
+            ToolChoice::Tool(name) => json!({ "tool": { "name": name } }),
+        }
+    }
+}
+
+/// Converts a content block holding a `toolUse` (or the `toolUse` object itself) into the
+/// call payload accepted by [`ToolHandler::call_tool`], keeping its `toolUseId` as the id.
+pub fn parse_tool_use(block: &Value) -> Result<Value, ToolError> {
+    let tool_use = block.get("toolUse").unwrap_or(block);
+    let name = tool_use["name"]
+        .as_str()
+        .ok_or_else(|| ToolError::BadArgs("Missing or invalid 'name' field".to_string()))?;
+    let input = match &tool_use["input"] {
+        Value::Null => json!({}),
+        input => input.clone(),
+    };
+    let mut call = json!({ "type": "function", "function": { "name": name, "arguments": input } });
+    if let Some(id) = tool_use["toolUseId"].as_str() {
+        call["id"] = json!(id);
+    }
+    Ok(call)
+}
+
+/// The `toolUse` blocks of a Converse response, of its `message`, or of a `content` array,
+/// as payloads accepted by [`ToolHandler::call_tool`].
+pub fn parse_tool_uses(response: &Value) -> Result<Vec<Value>, ToolError> {
+    tool_use_blocks(response).map(parse_tool_use).collect()
+}
+
+/// A `toolResult` content block answering the `toolUse` block with id `tool_use_id`.
+pub fn tool_result(tool_use_id: &str, content: &str, is_error: bool) -> Value {
+    json!({
+        "toolResult": {
+            "toolUseId": tool_use_id,
+            "content": [{ "text": content }],
+            "status": if is_error { "error" } else { "success" }
+        }
+    })
+}
+
+/// A successful `toolResult` content block answering the `toolUse` block with id
+/// `tool_use_id` with rich content.
+pub fn tool_result_content(tool_use_id: &str, content: &[ToolResultContent]) -> Value {
+    let blocks = content
+        .iter()
+        .map(ToolResultContent::to_bedrock)
+        .collect::<Vec<_>>();
+    json!({ "toolResult": { "toolUseId": tool_use_id, "content": blocks, "status": "success" } })
+}
+
+fn tool_use_blocks(response: &Value) -> impl Iterator<Item = &Value> {
+    let content = if response.is_array() {
+        response
+    } else if let Some(content) = response.get("content") {
+        content
+    } else {
+        &response["output"]["message"]["content"]
+    };
+    content
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter(|block| block.get("toolUse").is_some())
+}
+
+impl ToolHandler {
+    /// The `toolConfig` of a Converse request, listing every tool as a `toolSpec`. The
+    /// `toolChoice` is left out when `choice` is `None`, as some models reject it.
+    pub fn bedrock_tool_config(&self, choice: Option<ToolChoice>) -> Value {
+        let tools = self
+            .tools()
+            .map(|tool| self.render_tool(tool, SchemaFlavor::Bedrock))
+            .collect::<Vec<_>>();
+        let mut config = json!({ "tools": tools });
+        if let Some(choice) = choice {
+            config["toolChoice"] = choice.to_json();
+        }
+        config
+    }
+
+    /// Runs the call described by a `toolUse` content block, as
+    /// [`ToolHandler::call_tool_with_context`] does.
+    pub async fn call_bedrock_tool_use(
+        &self,
+        block: &Value,
+        context: &ToolContext,
+    ) -> Result<ToolCallResult, ToolError> {
+        let call = parse_tool_use(block)?;
+        self.call_tool_with_context(&call, context).await
+    }
+
+    /// Runs every `toolUse` block of a Converse response, concurrently, and returns a
+    /// `toolResult` block for each, in order. Other blocks are skipped.
+    pub async fn answer_bedrock_tool_uses(
+        &self,
+        response: &Value,
+        context: &ToolContext,
+    ) -> Vec<Value> {
+        let answers = tool_use_blocks(response).map(|block| async move {
+            let id = block["toolUse"]["toolUseId"].as_str().unwrap_or_default();
+            match self.call_bedrock_tool_use(block, context).await {
+                Ok(result) => tool_result_content(id, &result.content()),
+                Err(err) => tool_result(id, &self.error_message(&err), true),
+            }
+        });
+        self.run_batch(answers).await
+    }
+}
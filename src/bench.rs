@@ -0,0 +1,74 @@
+//! Entry points into the stages of a tool call, for benchmarking the hot path.
+//!
+//! A call through [`ToolHandler::call_tool`] compiles the tool's schema once, then on every
+//! call validates the arguments against it, parses the payload into ordered arguments and
+//! dispatches them to the tool. The compiled schema is cached and validation happens inside
+//! parsing, so this module exposes those two stages on their own:
+//!
+//! - [`compile_schema`] compiles a tool's schema from scratch, as the first call does,
+//! - [`validate_arguments`] checks arguments against the cached schema and nothing else.
+//!
+//! The remaining stages are public already: [`ToolHandler::validate_tool_call`] parses and
+//! validates a payload, and [`ToolHandler::call_with_values`] dispatches parsed arguments.
+//! The crate's criterion benches, run with `cargo bench`, measure all four.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::{bench, tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! let handler = ToolHandler::default();
+//! bench::compile_schema(&handler, "add").unwrap();
+//! bench::validate_arguments(&handler, "add", &json!({ "a": 1, "b": 2 })).unwrap();
+//! assert!(bench::validate_arguments(&handler, "add", &json!({ "a": "one" })).is_err());
+//! ```
+
+use crate::{Tool, ToolError, ToolHandler};
+use serde_json::Value;
+
+/// Compiles the parameter schema of the tool `name` for the handler's schema draft,
+/// without caching the result.
+pub fn compile_schema(handler: &ToolHandler, name: &str) -> Result<(), ToolError> {
+    let tool = tool(handler, name)?;
+    tool.compile_schema(handler.schema_draft)
+        .map(drop)
+        .map_err(|e| {
+            ToolError::Execution(format!(
+                "Failed to compile schema for tool '{}': {}",
+                name, e
+            ))
+        })
+}
+
+/// Validates `arguments` against the parameter schema of the tool `name`, compiled on first
+/// use as in a call. Unlike [`ToolHandler::validate_tool_call`] the arguments are neither
+/// repaired, coerced nor checked for unknown names.
+pub fn validate_arguments(
+    handler: &ToolHandler,
+    name: &str,
+    arguments: &Value,
+) -> Result<(), ToolError> {
+    let tool = tool(handler, name)?;
+    let compiled = tool.compiled_schema(handler.schema_draft)?;
+    compiled.validate(arguments).map_err(|errors| {
+        let mut messages = errors.map(|e| e.to_string()).collect::<Vec<_>>().join("; ");
+        if let Some(arguments) = arguments.as_object() {
+            messages = tool.redact_message(arguments, messages);
+        }
+        ToolError::BadArgs(format!(
+            "Argument validation failed for tool '{}': {}",
+            name, messages
+        ))
+    })
+}
+
+fn tool<'a>(handler: &'a ToolHandler, name: &str) -> Result<&'a Tool, ToolError> {
+    handler
+        .get_tool(name)
+        .ok_or_else(|| ToolError::NotFound(name.to_string()))
+}
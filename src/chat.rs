@@ -0,0 +1,511 @@
+//! The conversation loop between a model and the handler's tools.
+//!
+//! A [`ChatProvider`] sends a conversation and the tools on offer to a model and returns its
+//! reply. [`ChatLoop`] drives any provider: while the model answers with tool calls, it runs
+//! them with the [`ToolHandler`], appends the assistant message and one `tool` message per
+//! call to the conversation, and asks again, until the model replies without calling
+//! tools. Supporting another backend only takes a [`ChatProvider`] implementation.
+//!
+//! Conversations use OpenAI-style messages throughout, so a provider for another API
+//! converts them on the way in and converts the reply on the way out. The tools are
+//! rendered in the provider's [`ChatProvider::flavor`]. The loop prints nothing; a hook set
+//! with [`ChatLoop::with_events`] sees each message, tool call and result as it happens.
+//!
+//! ```rust
+//! use futures::future::BoxFuture;
+//! use serde_json::{json, Value};
+//! use tool_calling::chat::{ChatError, ChatLoop, ChatProvider};
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! // A stand-in model that calls `add` once, then reports the result
+//! struct Scripted;
+//!
+//! impl ChatProvider for Scripted {
+//!     fn chat<'a>(&'a self, messages: &'a [Value], _tools: &'a [Value]) -> BoxFuture<'a, Result<Value, ChatError>> {
+//!         let reply = match messages.last() {
+//!             Some(last) if last["role"] == "tool" => json!({ "role": "assistant", "content": last["content"] }),
+//!             _ => json!({ "role": "assistant", "tool_calls": [
+//!                 { "id": "call_1", "type": "function", "function": { "name": "add", "arguments": "{\"a\":1,\"b\":2}" } }
+//!             ] }),
+//!         };
+//!         Box::pin(async move { Ok(reply) })
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let handler = ToolHandler::default();
+//!     let mut messages = vec![json!({ "role": "user", "content": "What is 1 + 2?" })];
+//!     let reply = ChatLoop::new(&handler, &Scripted).run(&mut messages).await.unwrap();
+//!     assert_eq!(reply["content"], "3");
+//!     assert_eq!(messages.len(), 3);
+//! }
+//! ```
+
+use crate::{compat, SchemaFlavor, ToolContext, ToolHandler};
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// The number of model requests a single [`ChatLoop::run`] may make unless configured
+/// otherwise.
+pub const DEFAULT_MAX_ROUNDS: usize = 8;
+
+/// Errors returned while running a conversation.
+#[derive(Debug, Clone, thiserror::Error, PartialEq)]
+pub enum ChatError {
+    #[error("request failed: {0}")]
+    Http(String),
+    #[error("provider returned HTTP {status}: {body}")]
+    Upstream { status: u16, body: String },
+    #[error("invalid response: {0}")]
+    BadResponse(String),
+    #[error("model still requested tools after {0} rounds")]
+    TooManyRounds(usize),
+    #[error("invalid configuration: {0}")]
+    Config(String),
+    #[error("tool '{name}' failed: {message}")]
+    ToolFailed { name: String, message: String },
+    #[error("model called '{name}' with the same arguments {times} times: {arguments}")]
+    RepeatedCall {
+        name: String,
+        arguments: String,
+        times: usize,
+    },
+}
+
+/// Something that happened while running a conversation, as passed to the hook set with
+/// [`ChatLoop::with_events`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChatEvent<'a> {
+    /// The model replied with this assistant message.
+    MessageReceived(&'a Value),
+    /// A tool call from the model is about to run.
+    ToolCalled(&'a Value),
+    /// A call finished, answered by this `tool` message.
+    ToolResult(&'a Value),
+}
+
+/// Whether, and which, tools the model must call, as set with [`ChatLoop::with_tool_choice`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model decides whether to call tools.
+    #[default]
+    Auto,
+    /// The model must answer without calling tools.
+    None,
+    /// The model must call at least one tool.
+    Required,
+    /// The model must call the named tool.
+    Tool(String),
+}
+
+impl ToolChoice {
+    /// The choice as a provider in `flavor` takes it: the `tool_choice` of an OpenAI,
+    /// Mistral, Anthropic, Cohere or LangChain request, the `tool_config` of a Gemini
+    /// request, or the `toolChoice` of a Bedrock `toolConfig`.
+    ///
+    /// `None` when the provider's default applies, as for [`ToolChoice::Auto`], or when it
+    /// has no way to express the choice: Ollama takes no tool choice, Bedrock can't forbid
+    /// tools and Cohere can't name one.
+    pub fn render(&self, flavor: SchemaFlavor) -> Option<Value> {
+        let choice = match (self, flavor) {
+            (ToolChoice::Auto, _) | (_, SchemaFlavor::Ollama) => return None,
+            (ToolChoice::None, SchemaFlavor::Bedrock) => return None,
+            (ToolChoice::Tool(_), SchemaFlavor::Cohere) => return None,
+            (ToolChoice::None, SchemaFlavor::Anthropic) => json!({ "type": "none" }),
+            (ToolChoice::Required, SchemaFlavor::Anthropic) => json!({ "type": "any" }),
+            (ToolChoice::Tool(name), SchemaFlavor::Anthropic) => {
+                json!({ "type": "tool", "name": name })
+            }
+            (ToolChoice::None, SchemaFlavor::Gemini) => {
+                json!({ "function_calling_config": { "mode": "NONE" } })
+            }
+            (ToolChoice::Required, SchemaFlavor::Gemini) => {
+                json!({ "function_calling_config": { "mode": "ANY" } })
+            }
+            (ToolChoice::Tool(name), SchemaFlavor::Gemini) => json!({
+                "function_calling_config": { "mode": "ANY", "allowed_function_names": [name] }
+            }),
+            (ToolChoice::None, SchemaFlavor::Cohere) => json!("NONE"),
+            (ToolChoice::Required, SchemaFlavor::Cohere) => json!("REQUIRED"),
+            (ToolChoice::Required, SchemaFlavor::Bedrock) => json!({ "any": {} }),
+            (ToolChoice::Tool(name), SchemaFlavor::Bedrock) => json!({ "tool": { "name": name } }),
+            (ToolChoice::Tool(name), SchemaFlavor::LangChain) => json!(name),
+            (ToolChoice::Required, SchemaFlavor::Mistral) => json!("any"),
+            (ToolChoice::None, _) => json!("none"),
+            (ToolChoice::Required, _) => json!("required"),
+            (ToolChoice::Tool(name), _) => {
+                json!({ "type": "function", "function": { "name": name } })
+            }
+        };
+        Some(choice)
+    }
+
+    // Whether `calls` satisfy the choice
+    fn allows(&self, calls: &[Value]) -> bool {
+        match self {
+            ToolChoice::Auto | ToolChoice::None => true,
+            ToolChoice::Required => !calls.is_empty(),
+            ToolChoice::Tool(name) => calls
+                .iter()
+                .any(|call| call["function"]["name"].as_str() == Some(name.as_str())),
+        }
+    }
+}
+
+/// A hook observing a conversation's [`ChatEvent`]s, as set with [`ChatLoop::with_events`].
+pub type ChatEventFn = Arc<dyn Fn(&ChatEvent<'_>) + Send + Sync>;
+
+/// A chat model backend.
+pub trait ChatProvider: Send + Sync {
+    /// Sends the conversation with the tools on offer and returns the model's reply: an
+    /// OpenAI-style assistant message, or a response holding one, as read by
+    /// [`reply_message`].
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+    ) -> BoxFuture<'a, Result<Value, ChatError>>;
+
+    /// Like [`ChatProvider::chat`], asking the model to follow `choice`, which is never
+    /// [`ToolChoice::Auto`]. Providers that can send a tool choice, e.g. as rendered by
+    /// [`ToolChoice::render`], override this; the default ignores it.
+    fn chat_with_choice<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+        choice: &'a ToolChoice,
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        let _ = choice;
+        self.chat(messages, tools)
+    }
+
+    /// The shape the provider takes its tools in. Defaults to OpenAI's.
+    fn flavor(&self) -> SchemaFlavor {
+        SchemaFlavor::OpenAi
+    }
+}
+
+/// Runs a conversation against a [`ChatProvider`], executing the model's tool calls with a
+/// handler until it answers.
+pub struct ChatLoop<'a> {
+    handler: &'a ToolHandler,
+    provider: &'a dyn ChatProvider,
+    context: ToolContext,
+    external_tools: Vec<Value>,
+    max_rounds: usize,
+    events: Option<ChatEventFn>,
+    fail_on_tool_errors: bool,
+    tool_choice: ToolChoice,
+    max_repeats: Option<usize>,
+}
+
+impl<'a> ChatLoop<'a> {
+    /// Creates a loop offering the tools of `handler` to the model behind `provider`.
+    pub fn new(handler: &'a ToolHandler, provider: &'a dyn ChatProvider) -> Self {
+        Self {
+            handler,
+            provider,
+            context: ToolContext::default(),
+            external_tools: Vec::new(),
+            max_rounds: DEFAULT_MAX_ROUNDS,
+            events: None,
+            fail_on_tool_errors: false,
+            tool_choice: ToolChoice::Auto,
+            max_repeats: None,
+        }
+    }
+
+    /// Runs every tool call with this request-scoped context, which also decides the
+    /// tools offered when the handler has a tool filter.
+    pub fn with_context(mut self, context: ToolContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Offers these tool definitions, already in the provider's shape, next to the
+    /// handler's. They take precedence over handler tools of the same name, and a reply
+    /// calling any of them ends the loop so the caller can run them.
+    pub fn with_external_tools(mut self, tools: Vec<Value>) -> Self {
+        self.external_tools = tools;
+        self
+    }
+
+    /// Fails with [`ChatError::TooManyRounds`] when the model still asks for tools after
+    /// `rounds` requests.
+    pub fn with_max_rounds(mut self, rounds: usize) -> Self {
+        self.max_rounds = rounds.max(1);
+        self
+    }
+
+    /// Ends the conversation with [`ChatError::ToolFailed`] as soon as a tool call fails,
+    /// instead of telling the model about the error and letting it try again.
+    pub fn fail_on_tool_errors(mut self) -> Self {
+        self.fail_on_tool_errors = true;
+        self
+    }
+
+    /// Fails with [`ChatError::RepeatedCall`] when the model asks for the same tool with the
+    /// same arguments more than `times` times, as a model stuck in a loop does. The calls
+    /// are not run. Repeats are allowed without limit unless this is set.
+    pub fn with_max_repeated_calls(mut self, times: usize) -> Self {
+        self.max_repeats = Some(times.max(1));
+        self
+    }
+
+    /// Asks the model to follow `choice` in its first reply; later rounds leave the choice to
+    /// the model so that it can answer with the results. On that first request,
+    /// [`ToolChoice::None`] offers no tools and [`ToolChoice::Tool`] only the named one, so
+    /// the choice holds even for providers that can't send it, and a first reply that
+    /// doesn't call the required tool fails with [`ChatError::BadResponse`].
+    pub fn with_tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.tool_choice = choice;
+        self
+    }
+
+    /// Calls `hook` with every [`ChatEvent`] of the conversation, e.g. to show progress.
+    /// Nothing is reported without one.
+    pub fn with_events(mut self, hook: ChatEventFn) -> Self {
+        self.events = Some(hook);
+        self
+    }
+
+    /// Runs the conversation, appending every assistant message that called tools and the
+    /// `tool` messages answering it to `messages`, and returns the model's final reply as
+    /// the provider returned it.
+    pub async fn run(&self, messages: &mut Vec<Value>) -> Result<Value, ChatError> {
+        let external = self
+            .external_tools
+            .iter()
+            .filter_map(tool_name)
+            .collect::<HashSet<_>>();
+        let mut tools = self.external_tools.clone();
+        tools.extend(
+            self.handler
+                .tools_with_context(&self.context)
+                .filter(|tool| !external.contains(tool.name.as_str()))
+                .map(|tool| self.handler.render_tool(tool, self.provider.flavor())),
+        );
+
+        let mut seen: Vec<(String, Value, usize)> = Vec::new();
+        for round in 0..self.max_rounds {
+            let reply = if round > 0 || self.tool_choice == ToolChoice::Auto {
+                self.provider.chat(messages, &tools).await?
+            } else {
+                let offered = match &self.tool_choice {
+                    ToolChoice::None => Vec::new(),
+                    ToolChoice::Tool(name) => tools
+                        .iter()
+                        .filter(|tool| tool_name(tool) == Some(name.as_str()))
+                        .cloned()
+                        .collect(),
+                    _ => tools.clone(),
+                };
+                self.provider
+                    .chat_with_choice(messages, &offered, &self.tool_choice)
+                    .await?
+            };
+            let message = reply_message(&reply);
+            let message = if self.handler.tolerant_parsing {
+                compat::normalize_message(message)
+            } else {
+                message.clone()
+            };
+            self.emit(ChatEvent::MessageReceived(&message));
+            let calls = crate::tool_calls(&message);
+            if round == 0 && !self.tool_choice.allows(calls) {
+                return Err(ChatError::BadResponse(format!(
+                    "model did not follow the tool choice {:?}",
+                    self.tool_choice
+                )));
+            }
+            let is_external = |call: &Value| {
+                call["function"]["name"]
+                    .as_str()
+                    .is_some_and(|name| external.contains(name))
+            };
+            if calls.is_empty() || calls.iter().any(is_external) {
+                return Ok(reply);
+            }
+
+            if let Some(limit) = self.max_repeats {
+                for call in calls {
+                    let name = call["function"]["name"].as_str().unwrap_or_default();
+                    let arguments = call_arguments(call);
+                    let times = match seen.iter_mut().find(|(seen_name, seen_arguments, _)| {
+                        seen_name == name && *seen_arguments == arguments
+                    }) {
+                        Some((_, _, times)) => {
+                            *times += 1;
+                            *times
+                        }
+                        None => {
+                            seen.push((name.to_string(), arguments.clone(), 1));
+                            1
+                        }
+                    };
+                    if times > limit {
+                        return Err(ChatError::RepeatedCall {
+                            name: name.to_string(),
+                            arguments: arguments.to_string(),
+                            times,
+                        });
+                    }
+                }
+            }
+            for call in calls {
+                self.emit(ChatEvent::ToolCalled(call));
+            }
+            let outcomes = self
+                .handler
+                .call_all_with_context(&message, &self.context)
+                .await;
+            if self.fail_on_tool_errors {
+                let failed = outcomes.iter().zip(calls).find(|((_, r), _)| r.is_err());
+                if let Some(((_, Err(err)), call)) = failed {
+                    return Err(ChatError::ToolFailed {
+                        name: call["function"]["name"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                        message: self.handler.error_message(err),
+                    });
+                }
+            }
+            let results = outcomes
+                .into_iter()
+                .zip(calls)
+                .map(|((id, result), call)| match self.provider.flavor() {
+                    SchemaFlavor::Ollama => {
+                        let name = call["function"]["name"].as_str().unwrap_or_default();
+                        self.handler.ollama_tool_message(&id, name, result)
+                    }
+                    _ => self.handler.tool_message(&id, result),
+                })
+                .collect::<Vec<_>>();
+            for result in &results {
+                self.emit(ChatEvent::ToolResult(result));
+            }
+            messages.push(message);
+            messages.extend(results);
+        }
+        Err(ChatError::TooManyRounds(self.max_rounds))
+    }
+
+    fn emit(&self, event: ChatEvent<'_>) {
+        if let Some(hook) = &self.events {
+            hook(&event);
+        }
+    }
+}
+
+// Passes requests on to another provider, adding up what `read` finds in its responses,
+// such as their token usage
+#[cfg(any(feature = "ollama", feature = "openai"))]
+pub(crate) struct Metered<'a, U> {
+    inner: &'a dyn ChatProvider,
+    usage: std::sync::Mutex<U>,
+    read: fn(&Value) -> U,
+    // Requests sent, failed ones included
+    requests: std::sync::atomic::AtomicUsize,
+}
+
+#[cfg(any(feature = "ollama", feature = "openai"))]
+impl<'a, U: Default + std::ops::AddAssign> Metered<'a, U> {
+    pub(crate) fn new(inner: &'a dyn ChatProvider, read: fn(&Value) -> U) -> Self {
+        Self {
+            inner,
+            usage: Default::default(),
+            read,
+            requests: Default::default(),
+        }
+    }
+
+    pub(crate) fn requests(&self) -> usize {
+        self.requests.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub(crate) fn into_usage(self) -> U {
+        self.usage.into_inner().unwrap()
+    }
+
+    fn sent(&self) {
+        self.requests
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn record(&self, response: Result<Value, ChatError>) -> Result<Value, ChatError> {
+        if let Ok(response) = &response {
+            *self.usage.lock().unwrap() += (self.read)(response);
+        }
+        response
+    }
+}
+
+#[cfg(any(feature = "ollama", feature = "openai"))]
+impl<U: Default + std::ops::AddAssign + Send> ChatProvider for Metered<'_, U> {
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        Box::pin(async move {
+            self.sent();
+            self.record(self.inner.chat(messages, tools).await)
+        })
+    }
+
+    fn chat_with_choice<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+        choice: &'a ToolChoice,
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        Box::pin(async move {
+            self.sent();
+            self.record(self.inner.chat_with_choice(messages, tools, choice).await)
+        })
+    }
+
+    fn flavor(&self) -> SchemaFlavor {
+        self.inner.flavor()
+    }
+}
+
+/// The assistant message of a provider's reply: the first choice's of a chat completion
+/// response, the `message` of an Ollama chat response, or the reply itself.
+pub fn reply_message(reply: &Value) -> &Value {
+    if reply.get("choices").is_some() {
+        &reply["choices"][0]["message"]
+    } else if reply.get("message").is_some_and(Value::is_object) {
+        &reply["message"]
+    } else {
+        reply
+    }
+}
+
+// The arguments of a call, parsed when they were sent as a string
+fn call_arguments(call: &Value) -> Value {
+    match &call["function"]["arguments"] {
+        Value::String(arguments) => {
+            serde_json::from_str(arguments).unwrap_or_else(|_| Value::String(arguments.clone()))
+        }
+        arguments => arguments.clone(),
+    }
+}
+
+// The name of a tool definition in any of the rendered shapes
+fn tool_name(tool: &Value) -> Option<&str> {
+    tool["function"]["name"]
+        .as_str()
+        .or_else(|| tool["name"].as_str())
+        .or_else(|| tool["toolSpec"]["name"].as_str())
+}
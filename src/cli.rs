@@ -0,0 +1,134 @@
+//! A command line for listing, describing and calling the tools of a handler.
+//!
+//! The tools live in the binary that defines them, so the command line is a module to
+//! embed rather than a binary of its own: call [`main`] from a `main` function, or from a
+//! subcommand of an existing one, to debug tools without writing a scratch program.
+//!
+//! ```text
+//! tools list                            List every tool with its description
+//! tools describe <name>                 Print the JSON schema of a tool
+//! tools call <name> [--json '{...}']    Call a tool with JSON arguments and print its output
+//! ```
+//!
+//! ```rust,no_run
+//! use tool_calling::{cli, tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> std::process::ExitCode {
+//!     // cargo run -- tools call add --json '{"a": 1, "b": 2}'
+//!     cli::main(&ToolHandler::default()).await
+//! }
+//! ```
+
+use crate::{ToolError, ToolHandler};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::ExitCode;
+
+/// How to use the command line, as printed by `help`.
+pub const USAGE: &str = "\
+Usage:
+  tools list                            List every tool with its description
+  tools describe <name>                 Print the JSON schema of a tool
+  tools call <name> [--json '{...}']    Call a tool with JSON arguments and print its output
+";
+
+/// Errors returned by [`run`].
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    /// The command line could not be understood.
+    #[error("{0}\n\n{USAGE}")]
+    Usage(String),
+    /// The tool was not found, its arguments were invalid, or it failed.
+    #[error(transparent)]
+    Tool(#[from] ToolError),
+    /// Writing the output failed.
+    #[error("failed to write output: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Runs the command in `args`, without the program name, against `handler`, writing its
+/// output to `out`.
+pub async fn run<I, S>(
+    handler: &ToolHandler,
+    args: I,
+    out: &mut (dyn Write + Send),
+) -> Result<(), CliError>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let args = args.into_iter().map(Into::into).collect::<Vec<String>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args.as_slice() {
+        [] | ["help" | "--help" | "-h"] | ["tools"] => {
+            out.write_all(USAGE.as_bytes())?;
+        }
+        ["tools", "list"] => {
+            let width = handler
+                .tools()
+                .map(|tool| tool.name.len())
+                .max()
+                .unwrap_or(0);
+            for tool in handler.tools() {
+                let summary = tool.description.lines().next().unwrap_or_default();
+                writeln!(out, "{:width$}  {}", tool.name, summary, width = width)?;
+            }
+        }
+        ["tools", "describe", name] => {
+            if handler.get_tool(name).is_none() {
+                return Err(ToolError::NotFound(name.to_string()).into());
+            }
+            let schema = &handler.tools_schema_for(&[name])[0];
+            writeln!(out, "{}", serde_json::to_string_pretty(schema).unwrap())?;
+        }
+        ["tools", "call", name, rest @ ..] => {
+            let arguments = match rest {
+                [] => "{}",
+                ["--json", arguments] => arguments,
+                _ => {
+                    return Err(CliError::Usage(format!(
+                        "unexpected arguments: {}",
+                        rest.join(" ")
+                    )))
+                }
+            };
+            let arguments: Value = serde_json::from_str(arguments)
+                .map_err(|err| CliError::Usage(format!("--json is not valid JSON: {}", err)))?;
+            let call =
+                json!({ "type": "function", "function": { "name": name, "arguments": arguments } });
+            let result = handler.call_tool(&call).await?;
+            writeln!(out, "{}", result.output)?;
+        }
+        _ => {
+            return Err(CliError::Usage(format!(
+                "unknown command: {}",
+                args.join(" ")
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// Runs the command given to the process against `handler`, printing its output to stdout
+/// and any error to stderr. Exits with `2` when the command line could not be understood
+/// and `1` when the command failed.
+pub async fn main(handler: &ToolHandler) -> ExitCode {
+    let mut stdout = std::io::stdout();
+    match run(handler, std::env::args().skip(1), &mut stdout).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            match err {
+                CliError::Usage(_) => ExitCode::from(2),
+                _ => ExitCode::FAILURE,
+            }
+        }
+    }
+}
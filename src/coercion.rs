@@ -0,0 +1,129 @@
+use serde_json::{Map, Number, Value};
+
+/// Rules for coercing loosely-typed JSON arguments before schema validation.
+///
+/// Small models frequently quote numbers or booleans; enabling the relevant rule lets
+/// such arguments satisfy the tool's parameter schema instead of failing validation.
+/// The default is [`Coercion::strict`], which performs no coercion.
+///
+/// # Examples
+///
+/// ```rust
+/// use tool_calling::{tool, Coercion, ToolHandler};
+/// use serde_json::json;
+///
+/// #[tool]
+/// fn add(a: i32, b: i32) -> String {
+///     (a + b).to_string()
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let handler = ToolHandler::default().with_coercion(Coercion::lenient());
+///     let payload = json!({
+///         "type": "function",
+///         "function": { "name": "add", "arguments": { "a": "1", "b": 2 } }
+///     });
+///     assert_eq!(handler.call_tool(&payload).await.unwrap().output, "3");
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Coercion {
+    /// Accept numeric strings (`"1"`, `"2.5"`) for `integer` and `number` parameters.
+    pub string_to_number: bool,
+    /// Accept numbers for `string` parameters.
+    pub number_to_string: bool,
+    /// Accept `"true"`/`"false"`, `"1"`/`"0"`, and `1`/`0` for `boolean` parameters.
+    pub to_boolean: bool,
+}
+
+impl Coercion {
+    /// No coercion; arguments must match the schema exactly.
+    pub fn strict() -> Self {
+        Self::default()
+    }
+
+    /// Enables every coercion rule.
+    pub fn lenient() -> Self {
+        Self {
+            string_to_number: true,
+            number_to_string: true,
+            to_boolean: true,
+        }
+    }
+
+    /// Coerces each argument in place according to the matching property schema.
+    pub(crate) fn apply(&self, parameter_schema: &Value, args: &mut Map<String, Value>) {
+        if *self == Self::strict() {
+            return;
+        }
+        let Some(properties) = parameter_schema
+            .get("properties")
+            .and_then(|p| p.as_object())
+        else {
+            return;
+        };
+        for (name, value) in args.iter_mut() {
+            let types = properties.get(name).map(schema_types).unwrap_or_default();
+            if types.is_empty() || types.iter().any(|t| matches_type(value, t)) {
+                continue;
+            }
+            if let Some(coerced) = self.coerce(value, &types) {
+                *value = coerced;
+            }
+        }
+    }
+
+    // Tries each allowed type in schema order, returning the first successful coercion
+    fn coerce(&self, value: &Value, types: &[&str]) -> Option<Value> {
+        types.iter().find_map(|ty| match (*ty, value) {
+            ("integer", Value::String(s)) if self.string_to_number => {
+                s.trim().parse::<i64>().ok().map(Value::from)
+            }
+            ("number", Value::String(s)) if self.string_to_number => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number),
+            ("string", Value::Number(n)) if self.number_to_string => {
+                Some(Value::String(n.to_string()))
+            }
+            ("boolean", Value::String(s)) if self.to_boolean => {
+                match s.trim().to_ascii_lowercase().as_str() {
+                    "true" | "1" => Some(Value::Bool(true)),
+                    "false" | "0" => Some(Value::Bool(false)),
+                    _ => None,
+                }
+            }
+            ("boolean", Value::Number(n)) if self.to_boolean => match n.as_i64() {
+                Some(1) => Some(Value::Bool(true)),
+                Some(0) => Some(Value::Bool(false)),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+}
+
+// Reads the `type` keyword of a property schema, which may be a string or an array
+fn schema_types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(|t| t.as_str()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn matches_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => false,
+    }
+}
@@ -0,0 +1,139 @@
+//! Cohere's tool-use format.
+//!
+//! [`ToolHandler::cohere_tools`] renders the `tools` of a Cohere v1 chat request, with each
+//! tool's parameters described as `parameter_definitions`. The model answers with a
+//! `tool_calls` list of `{name, parameters}` objects, often after a `text` laying out its
+//! tool plan; [`ToolHandler::answer_cohere_tool_calls`] runs every call and returns the
+//! `tool_results` to send back in the next request. Outputs are reported as
+//! `{"output": ...}` and failed calls as `{"error": ...}`, with the text of
+//! [`ToolHandler::error_message`].
+//!
+//! Responses of the v2 API, whose `message` carries a `tool_plan` and OpenAI-style
+//! `tool_calls`, are read as well; answer those by passing the `message` to
+//! [`ToolHandler::answer_tool_calls`].
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::{tool, ToolContext, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let handler = ToolHandler::default();
+//!     let definitions = &handler.cohere_tools()[0]["parameter_definitions"];
+//!     assert_eq!(definitions["a"], json!({ "type": "int", "required": true }));
+//!
+//!     let response = json!({
+//!         "text": "I will add the numbers.",
+//!         "tool_calls": [{ "name": "add", "parameters": { "a": 1, "b": 2 } }]
+//!     });
+//!     let results = handler.answer_cohere_tool_calls(&response, &ToolContext::default()).await;
+//!     assert_eq!(
+//!         results,
+//!         vec![json!({
+//!             "call": { "name": "add", "parameters": { "a": 1, "b": 2 } },
+//!             "outputs": [{ "output": "3" }]
+//!         })]
+//!     );
+//! }
+//! ```
+
+use crate::{SchemaFlavor, ToolCallResult, ToolContext, ToolError, ToolHandler};
+use serde_json::{json, Value};
+
+/// Converts a Cohere tool call into the call payload accepted by [`ToolHandler::call_tool`].
+///
+/// Both the v1 `{name, parameters}` shape and the v2 OpenAI-style shape are accepted.
+pub fn parse_tool_call(call: &Value) -> Result<Value, ToolError> {
+    if call.get("function").is_some() {
+        let mut call = call.clone();
+        call["type"] = json!("function");
+        return Ok(call);
+    }
+    let name = call["name"]
+        .as_str()
+        .ok_or_else(|| ToolError::BadArgs("Missing or invalid 'name' field".to_string()))?;
+    let parameters = match &call["parameters"] {
+        Value::Null => json!({}),
+        parameters => parameters.clone(),
+    };
+    Ok(json!({ "type": "function", "function": { "name": name, "arguments": parameters } }))
+}
+
+/// The tool calls of a chat response (v1) or of its `message` (v2), as payloads accepted by
+/// [`ToolHandler::call_tool`].
+pub fn parse_tool_calls(response: &Value) -> Result<Vec<Value>, ToolError> {
+    raw_tool_calls(response)
+        .iter()
+        .map(parse_tool_call)
+        .collect()
+}
+
+/// The model's plan for its tool calls: the v2 `message.tool_plan`, or the v1 `text` of a
+/// response that calls tools.
+pub fn tool_plan(response: &Value) -> Option<&str> {
+    if let Some(plan) = response["message"]["tool_plan"].as_str() {
+        return Some(plan);
+    }
+    if raw_tool_calls(response).is_empty() {
+        return None;
+    }
+    response["text"].as_str().filter(|text| !text.is_empty())
+}
+
+/// A `tool_results` entry answering `call` with `outputs`, a list of JSON objects.
+pub fn tool_result(call: &Value, outputs: Vec<Value>) -> Value {
+    json!({ "call": call, "outputs": outputs })
+}
+
+fn raw_tool_calls(response: &Value) -> &[Value] {
+    response
+        .get("tool_calls")
+        .or_else(|| response["message"].get("tool_calls"))
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+impl ToolHandler {
+    /// The definition of every tool, in the shape of a Cohere v1 request's `tools`.
+    pub fn cohere_tools(&self) -> Value {
+        Value::Array(
+            self.tools()
+                .map(|tool| self.render_tool(tool, SchemaFlavor::Cohere))
+                .collect(),
+        )
+    }
+
+    /// Runs a Cohere tool call, as [`ToolHandler::call_tool_with_context`] does.
+    pub async fn call_cohere_tool_call(
+        &self,
+        call: &Value,
+        context: &ToolContext,
+    ) -> Result<ToolCallResult, ToolError> {
+        let call = parse_tool_call(call)?;
+        self.call_tool_with_context(&call, context).await
+    }
+
+    /// Runs every call in a v1 chat response's `tool_calls`, concurrently, and returns a
+    /// `tool_results` entry for each, in order.
+    pub async fn answer_cohere_tool_calls(
+        &self,
+        response: &Value,
+        context: &ToolContext,
+    ) -> Vec<Value> {
+        let answers = raw_tool_calls(response).iter().map(|call| async move {
+            let output = match self.call_cohere_tool_call(call, context).await {
+                Ok(result) => json!({ "output": result.output }),
+                Err(err) => json!({ "error": self.error_message(&err) }),
+            };
+            tool_result(call, vec![output])
+        });
+        self.run_batch(answers).await
+    }
+}
@@ -0,0 +1,253 @@
+//! Tolerant reading of responses from OpenAI-compatible servers such as llama.cpp and vLLM.
+//!
+//! These servers mostly follow OpenAI's chat completion format, but depending on the model
+//! and its chat template they may
+//!
+//! - leave the calls as JSON in the message `content` instead of `tool_calls`, in the shape
+//!   the model was trained on (`{"name", "arguments"}`, `{"name", "parameters"}`, a list of
+//!   either, or `{"tool_calls": [...]}`), or in [`crate::hermes`] `<tool_call>` blocks,
+//! - send the legacy single `function_call` instead of `tool_calls`,
+//! - leave out call ids and `type`, or send arguments as an object, an empty string or a
+//!   JSON-encoded string,
+//! - finish with `stop`, `eos` or no reason at all after calling tools.
+//!
+//! [`normalize_message`] and [`normalize_response`] rewrite such output into the shape
+//! OpenAI returns, and [`ToolHandler::with_tolerant_parsing`] makes
+//! [`ToolHandler::call_all`] and [`ToolHandler::answer_tool_calls`] do so before running the
+//! calls.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::compat::normalize_response;
+//!
+//! let response = json!({
+//!     "choices": [{
+//!         "index": 0,
+//!         "message": { "role": "assistant", "content": "{\"name\": \"add\", \"parameters\": {\"a\": 1}}" },
+//!         "finish_reason": "stop"
+//!     }]
+//! });
+//! let response = normalize_response(&response);
+//! let choice = &response["choices"][0];
+//! assert_eq!(choice["finish_reason"], "tool_calls");
+//! assert_eq!(choice["message"]["content"], json!(null));
+//! assert_eq!(
+//!     choice["message"]["tool_calls"],
+//!     json!([{ "id": "call_0", "type": "function", "function": { "name": "add", "arguments": "{\"a\":1}" } }])
+//! );
+//! ```
+
+use crate::hermes;
+use serde_json::{json, Value};
+
+/// Normalizes an assistant message, or the first choice's message of a chat completion
+/// response, into OpenAI's shape.
+///
+/// Calls found in the `content` or a `function_call` are moved into `tool_calls`, and the
+/// `content` keeps only the text around them, or is cleared when there is none. Every call gets a `type`, an id
+/// (`call_{index}` when missing) and its arguments as a JSON-encoded object.
+pub fn normalize_message(message: &Value) -> Value {
+    let mut message = match message {
+        Value::Array(calls) => json!({ "role": "assistant", "content": null, "tool_calls": calls }),
+        _ if message.get("choices").is_some() => message["choices"][0]["message"].clone(),
+        _ => message.clone(),
+    };
+    if !message.is_object() {
+        return message;
+    }
+    if message.get("role").is_none() {
+        message["role"] = json!("assistant");
+    }
+
+    let mut calls = match message.get("tool_calls") {
+        Some(Value::Array(calls)) if !calls.is_empty() => calls.clone(),
+        _ => Vec::new(),
+    };
+    if calls.is_empty() {
+        if let Some(call) = message.get("function_call").filter(|call| call.is_object()) {
+            calls.push(json!({ "function": call }));
+        }
+    }
+    if calls.is_empty() {
+        if let Some(found) = message["content"].as_str().and_then(content_calls) {
+            calls = found;
+            message["content"] = Value::Null;
+        }
+    }
+    if calls.is_empty() {
+        let extraction = message["content"]
+            .as_str()
+            .and_then(|content| hermes::extract_tool_calls(content).ok());
+        if let Some(extraction) = extraction.filter(|found| !found.tool_calls.is_empty()) {
+            calls = extraction.tool_calls;
+            message["content"] = match extraction.text.as_str() {
+                "" => Value::Null,
+                text => json!(text),
+            };
+        }
+    }
+    if let Some(message) = message.as_object_mut() {
+        message.remove("function_call");
+    }
+    if calls.is_empty() {
+        return message;
+    }
+
+    message["tool_calls"] = Value::Array(
+        calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, call)| normalize_call(call, index))
+            .collect(),
+    );
+    message
+}
+
+/// Normalizes every choice of a chat completion response with [`normalize_message`].
+///
+/// A choice whose message calls tools finishes with `tool_calls`, whatever the server
+/// reported. Otherwise `eos` and `stop_sequence` become `stop`, and `max_tokens` becomes
+/// `length`.
+pub fn normalize_response(response: &Value) -> Value {
+    let mut response = response.clone();
+    let Some(choices) = response.get_mut("choices").and_then(Value::as_array_mut) else {
+        return response;
+    };
+    for choice in choices {
+        let message = normalize_message(&choice["message"]);
+        let calls_tools = message["tool_calls"]
+            .as_array()
+            .is_some_and(|calls| !calls.is_empty());
+        let reason = if calls_tools {
+            json!("tool_calls")
+        } else {
+            match choice["finish_reason"].as_str() {
+                Some("eos" | "eos_token" | "stop_sequence" | "end_turn") => json!("stop"),
+                Some("max_tokens") => json!("length"),
+                Some("function_call" | "tool_call") => json!("tool_calls"),
+                _ => choice["finish_reason"].clone(),
+            }
+        };
+        choice["message"] = message;
+        choice["finish_reason"] = reason;
+    }
+    response
+}
+
+/// Splits the `<think>` blocks reasoning models such as DeepSeek-R1, Qwen3 or Cogito write
+/// out of `content`, returning the answer and the reasoning, if there was any.
+///
+/// An unclosed block runs to the end of the content, and a closing tag with no opening one
+/// ends reasoning that started before the content, as when the chat template opens the
+/// block itself.
+///
+/// ```rust
+/// use tool_calling::compat::split_thinking;
+///
+/// let (answer, thinking) = split_thinking("<think>2 + 2 is 4.</think>\n\nIt's 4.");
+/// assert_eq!(answer, "It's 4.");
+/// assert_eq!(thinking.as_deref(), Some("2 + 2 is 4."));
+/// ```
+pub fn split_thinking(content: &str) -> (String, Option<String>) {
+    let mut answer = String::new();
+    let mut thinking = Vec::new();
+    let mut rest = content;
+    if let Some(end) = rest.find(THINK_END) {
+        if !rest[..end].contains(THINK_START) {
+            thinking.push(rest[..end].trim());
+            rest = &rest[end + THINK_END.len()..];
+        }
+    }
+    while let Some(start) = rest.find(THINK_START) {
+        answer.push_str(&rest[..start]);
+        rest = &rest[start + THINK_START.len()..];
+        match rest.find(THINK_END) {
+            Some(end) => {
+                thinking.push(rest[..end].trim());
+                rest = &rest[end + THINK_END.len()..];
+            }
+            None => {
+                thinking.push(rest.trim());
+                rest = "";
+            }
+        }
+    }
+    answer.push_str(rest);
+    thinking.retain(|part| !part.is_empty());
+    let thinking = (!thinking.is_empty()).then(|| thinking.join("\n"));
+    (answer.trim().to_string(), thinking)
+}
+
+pub(crate) const THINK_START: &str = "<think>";
+pub(crate) const THINK_END: &str = "</think>";
+
+// Calls written as JSON in a message's content, if that is all the content holds
+fn content_calls(content: &str) -> Option<Vec<Value>> {
+    // Llama 3.1 prefixes built-in tool calls with this token
+    let content = content.trim().trim_start_matches("<|python_tag|>").trim();
+    if !content.starts_with(['{', '[']) {
+        return None;
+    }
+    let calls = match serde_json::from_str::<Value>(content).ok()? {
+        Value::Object(mut object) => match object.remove("tool_calls") {
+            Some(Value::Array(calls)) => calls,
+            _ => vec![Value::Object(object)],
+        },
+        Value::Array(calls) => calls,
+        _ => return None,
+    };
+    let is_call = |call: &Value| {
+        let call = call.get("function").unwrap_or(call);
+        call["name"].is_string()
+            && (call.get("arguments").is_some() || call.get("parameters").is_some())
+    };
+    (!calls.is_empty() && calls.iter().all(is_call)).then_some(calls)
+}
+
+fn normalize_call(call: Value, index: usize) -> Value {
+    let mut call = if call.get("function").is_some() {
+        call
+    } else {
+        // A bare `{"name", "arguments"}` object, as models write it
+        let mut wrapped = json!({ "function": { "name": call["name"] } });
+        if let Some(id) = call.get("id") {
+            wrapped["id"] = id.clone();
+        }
+        wrapped["function"]["arguments"] = call
+            .get("arguments")
+            .or_else(|| call.get("parameters"))
+            .cloned()
+            .unwrap_or(Value::Null);
+        wrapped
+    };
+    if call["id"].as_str().is_none_or(str::is_empty) {
+        call["id"] = json!(format!("call_{}", index));
+    }
+    call["type"] = json!("function");
+    if call["function"].get("arguments").is_none() {
+        if let Some(parameters) = call["function"].get("parameters").cloned() {
+            call["function"]["arguments"] = parameters;
+        }
+    }
+    if let Some(function) = call["function"].as_object_mut() {
+        function.remove("parameters");
+    }
+    let arguments = normalize_arguments(&call["function"]["arguments"]);
+    call["function"]["arguments"] = arguments;
+    call
+}
+
+// Arguments as a JSON-encoded string, unwrapping strings that were encoded twice
+fn normalize_arguments(arguments: &Value) -> Value {
+    match arguments {
+        Value::Null => json!("{}"),
+        Value::String(text) if text.trim().is_empty() => json!("{}"),
+        Value::String(text) => match serde_json::from_str::<Value>(text) {
+            Ok(Value::String(inner)) if serde_json::from_str::<Value>(&inner).is_ok() => {
+                json!(inner)
+            }
+            _ => arguments.clone(),
+        },
+        _ => json!(arguments.to_string()),
+    }
+}
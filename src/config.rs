@@ -0,0 +1,417 @@
+//! Tools declared in configuration files.
+//!
+//! A config file lists tools with a name, description, parameter schema and an executor:
+//! either a `command` run without a shell, or a `url` the arguments are sent to. Load one
+//! with [`ToolHandler::load_config`].
+//!
+//! ```toml
+//! [[tools]]
+//! name = "git_log"
+//! description = "Show the most recent commits"
+//! command = "git log --oneline -n {count}"
+//! timeout_secs = 10
+//!
+//! [tools.parameters]
+//! type = "object"
+//! properties = { count = { type = "integer" } }
+//! required = ["count"]
+//!
+//! [[tools]]
+//! name = "weather"
+//! description = "Current weather for a city"
+//! url = "https://weather.example.com/now"
+//! method = "GET"
+//! headers = { Authorization = "Bearer ${WEATHER_TOKEN}" }
+//! parameters = { type = "object", properties = { city = { type = "string" } } }
+//! ```
+//!
+//! In a command, `{name}` is replaced by the argument of that name and `{args}` by every
+//! argument in parameter order; braces around anything but a name, like the `{}` of
+//! `find -exec`, are kept as written. The command is split into words before substitution, in a
+//! single pass, so arguments can never add words or shell syntax of their own. The program
+//! itself can't contain placeholders, and a call fails if an argument would make a word
+//! start with `-`, so arguments can't add flags either; put such values after a fixed
+//! prefix like `--count={count}` instead. `GET` and `DELETE`
+//! requests send the arguments as query parameters, other methods as a JSON body, and
+//! `${VAR}` in a header value is read from the environment.
+
+use crate::{Tool, ToolError, ToolFn, ToolFuture, ToolHandler};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Errors returned while loading a config file.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("config io error for {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse config: {0}")]
+    Parse(String),
+    #[error("invalid definition for tool '{tool}': {reason}")]
+    Invalid { tool: String, reason: String },
+}
+
+/// The contents of a config file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ToolConfig {
+    #[serde(default)]
+    pub tools: Vec<ToolDefinition>,
+}
+
+/// A single tool declared in a config file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ToolDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "empty_parameters")]
+    pub parameters: Value,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Fails the call if it takes longer than this.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(flatten)]
+    pub executor: Executor,
+}
+
+/// How a declared tool is executed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Executor {
+    /// Runs a command and returns its standard output.
+    Command { command: String },
+    /// Sends the arguments to an HTTP endpoint and returns the response body.
+    Http {
+        url: String,
+        #[serde(default = "default_method")]
+        method: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+}
+
+fn empty_parameters() -> Value {
+    json!({ "type": "object", "properties": {}, "required": [] })
+}
+
+fn default_method() -> String {
+    "POST".into()
+}
+
+impl ToolConfig {
+    /// Parses a config from TOML.
+    pub fn from_toml(source: &str) -> Result<Self, ConfigError> {
+        toml::from_str(source).map_err(|err| ConfigError::Parse(err.to_string()))
+    }
+
+    /// Parses a config from YAML.
+    pub fn from_yaml(source: &str) -> Result<Self, ConfigError> {
+        serde_yaml::from_str(source).map_err(|err| ConfigError::Parse(err.to_string()))
+    }
+
+    /// Reads a config file, choosing the format from its extension (`.toml`, `.yaml` or
+    /// `.yml`).
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml(&source),
+            Some("yaml" | "yml") => Self::from_yaml(&source),
+            _ => Err(ConfigError::Parse(format!(
+                "unsupported config format for {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Builds the declared tools.
+    pub fn into_tools(self) -> Result<Vec<Tool>, ConfigError> {
+        self.tools
+            .into_iter()
+            .map(ToolDefinition::into_tool)
+            .collect()
+    }
+}
+
+impl ToolDefinition {
+    /// Builds the tool, checking that its executor only refers to declared parameters.
+    pub fn into_tool(self) -> Result<Tool, ConfigError> {
+        let invalid = |reason: String| ConfigError::Invalid {
+            tool: self.name.clone(),
+            reason,
+        };
+        let parameter_names = self
+            .parameters
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|props| props.keys().cloned().collect::<Vec<_>>())
+            .ok_or_else(|| invalid("parameters must be an object schema with properties".into()))?;
+        let timeout = self.timeout_secs.map(Duration::from_secs);
+
+        let function = match &self.executor {
+            Executor::Command { command } => {
+                let words = shell_words::split(command).map_err(|err| invalid(err.to_string()))?;
+                let Some((program, rest)) = words.split_first().filter(|(p, _)| !p.is_empty())
+                else {
+                    return Err(invalid("command is empty".into()));
+                };
+                if placeholders(program).next().is_some() {
+                    return Err(invalid(
+                        "the program to run can't contain placeholders".into(),
+                    ));
+                }
+                for word in rest {
+                    for placeholder in placeholders(word) {
+                        if placeholder != "args"
+                            && !parameter_names.iter().any(|p| p == placeholder)
+                        {
+                            return Err(invalid(format!(
+                                "unknown placeholder {{{}}}",
+                                placeholder
+                            )));
+                        }
+                    }
+                }
+                command_tool(words, parameter_names, timeout)
+            }
+            Executor::Http {
+                url,
+                method,
+                headers,
+            } => {
+                let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+                    .map_err(|_| invalid(format!("invalid method '{}'", method)))?;
+                http_tool(
+                    url.clone(),
+                    method,
+                    headers.clone(),
+                    parameter_names,
+                    timeout,
+                )
+            }
+        };
+        Ok(Tool::new(self.name, self.description, self.parameters, function).with_tags(self.tags))
+    }
+}
+
+impl ToolHandler {
+    /// Registers every tool declared in the config file at `path`.
+    ///
+    /// Returns the names of the registered tools. Nothing is registered if any
+    /// definition is invalid.
+    pub fn load_config(&mut self, path: impl AsRef<Path>) -> Result<Vec<String>, ConfigError> {
+        let tools = ToolConfig::from_file(path)?.into_tools()?;
+        let names = tools.iter().map(|tool| tool.name.clone()).collect();
+        for tool in tools {
+            self.register(tool);
+        }
+        Ok(names)
+    }
+}
+
+// Pair the positional arguments with their parameter names, skipping omitted optionals
+fn named_args(parameter_names: &[String], args: Vec<Value>) -> Vec<(String, Value)> {
+    parameter_names
+        .iter()
+        .zip(args)
+        .filter(|(_, value)| !value.is_null())
+        .map(|(name, value)| (name.clone(), value))
+        .collect()
+}
+
+fn arg_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// A piece of a command word: literal text or a `{name}` placeholder
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+// Only braces around an identifier make a placeholder, so `find -exec rm {} +` keeps its `{}`
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parts(word: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = word;
+    while let Some(close) = rest.find('}') {
+        match rest[..close]
+            .rfind('{')
+            .filter(|&open| is_identifier(&rest[open + 1..close]))
+        {
+            Some(open) => {
+                parts.push(Part::Text(&rest[..open]));
+                parts.push(Part::Placeholder(&rest[open + 1..close]));
+            }
+            None => parts.push(Part::Text(&rest[..=close])),
+        }
+        rest = &rest[close + 1..];
+    }
+    parts.push(Part::Text(rest));
+    parts
+}
+
+fn placeholders(word: &str) -> impl Iterator<Item = &str> {
+    parts(word).into_iter().filter_map(|part| match part {
+        Part::Placeholder(name) => Some(name),
+        Part::Text(_) => None,
+    })
+}
+
+// Substitute every placeholder of `word` at once, so values are never expanded again
+fn expand_word(word: &str, args: &[(String, Value)]) -> Result<Vec<String>, ToolError> {
+    let lookup = |placeholder: &str| {
+        args.iter()
+            .find(|(name, _)| name == placeholder)
+            .map(|(_, value)| arg_text(value))
+    };
+    let words = if word == "{args}" {
+        args.iter().map(|(_, value)| arg_text(value)).collect()
+    } else if let [Part::Text(""), Part::Placeholder(placeholder), Part::Text("")] = parts(word)[..]
+    {
+        // A word that is only an omitted argument disappears entirely
+        lookup(placeholder).into_iter().collect()
+    } else {
+        let mut expanded = String::new();
+        for part in parts(word) {
+            match part {
+                Part::Text(text) => expanded.push_str(text),
+                Part::Placeholder(placeholder) => {
+                    expanded.push_str(&lookup(placeholder).unwrap_or_default())
+                }
+            }
+        }
+        vec![expanded]
+    };
+    match words.iter().find(|expanded| expanded.starts_with('-')) {
+        Some(flag) if !word.starts_with('-') => Err(ToolError::BadArgs(format!(
+            "argument '{}' would be read as a flag",
+            flag
+        ))),
+        _ => Ok(words),
+    }
+}
+
+async fn with_timeout<F>(timeout: Option<Duration>, future: F) -> Result<String, ToolError>
+where
+    F: std::future::Future<Output = Result<String, ToolError>>,
+{
+    match timeout {
+        Some(limit) => tokio::time::timeout(limit, future)
+            .await
+            .map_err(|_| ToolError::Execution(format!("timed out after {}s", limit.as_secs())))?,
+        None => future.await,
+    }
+}
+
+fn command_tool(
+    words: Vec<String>,
+    parameter_names: Vec<String>,
+    timeout: Option<Duration>,
+) -> ToolFn {
+    // Checked when loading: there is a program, and it has no placeholders
+    let mut words = words.into_iter();
+    let program = Arc::new(words.next().unwrap_or_default());
+    let words = Arc::new(words.collect::<Vec<_>>());
+    ToolFn::Async(Box::new(move |args: Vec<Value>| -> ToolFuture {
+        let args = named_args(&parameter_names, args);
+        let argv = words
+            .iter()
+            .map(|word| expand_word(word, &args))
+            .collect::<Result<Vec<_>, _>>();
+        let program = program.clone();
+        Box::pin(with_timeout(timeout, async move {
+            let output = tokio::process::Command::new(program.as_str())
+                .args(argv?.concat())
+                .stdin(std::process::Stdio::null())
+                .kill_on_drop(true)
+                .output()
+                .await
+                .map_err(|err| ToolError::Execution(format!("{}: {}", program, err)))?;
+            if output.status.success() {
+                Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+            } else {
+                Err(ToolError::Execution(format!(
+                    "{} exited with {}: {}",
+                    program,
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )))
+            }
+        }))
+    }))
+}
+
+fn http_tool(
+    url: String,
+    method: reqwest::Method,
+    headers: BTreeMap<String, String>,
+    parameter_names: Vec<String>,
+    timeout: Option<Duration>,
+) -> ToolFn {
+    let client = reqwest::Client::new();
+    ToolFn::Async(Box::new(move |args: Vec<Value>| -> ToolFuture {
+        let args = named_args(&parameter_names, args);
+        let mut request = client.request(method.clone(), &url);
+        for (name, value) in &headers {
+            request = request.header(name, expand_env(value));
+        }
+        request = if method == reqwest::Method::GET || method == reqwest::Method::DELETE {
+            let query = args
+                .iter()
+                .map(|(name, value)| (name.clone(), arg_text(value)))
+                .collect::<Vec<_>>();
+            request.query(&query)
+        } else {
+            request.json(&args.into_iter().collect::<Map<_, _>>())
+        };
+        Box::pin(with_timeout(timeout, async move {
+            let response = request
+                .send()
+                .await
+                .map_err(|err| ToolError::Execution(err.to_string()))?;
+            let status = response.status();
+            let body = response
+                .text()
+                .await
+                .map_err(|err| ToolError::Execution(err.to_string()))?;
+            if status.is_success() {
+                Ok(body)
+            } else {
+                Err(ToolError::Execution(format!("HTTP {}: {}", status, body)))
+            }
+        }))
+    }))
+}
+
+fn expand_env(value: &str) -> String {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&std::env::var(&rest[start + 2..start + end]).unwrap_or_default());
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// The key of the JSON object a tool output encodes its content under
+const ENVELOPE: &str = "tool_result_content";
+
+/// One piece of a tool's result: text, a JSON value, an image or a link to a resource.
+///
+/// Tool functions return a `String`; a tool with richer results returns the encoding made
+/// by [`ToolResultContent::to_output`], and [`ToolCallResult::content`](crate::ToolCallResult::content)
+/// decodes it again. Any other output is a single [`ToolResultContent::Text`]. The answers
+/// built for MCP, Anthropic and Bedrock carry the content as their own content blocks;
+/// providers that only take text get [`ToolResultContent::to_text`]. Outputs cut down by an
+/// [`OutputLimit`](crate::OutputLimit) no longer decode and are passed on as text.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use tool_calling::ToolResultContent;
+///
+/// let output = ToolResultContent::to_output(&[
+///     ToolResultContent::text("Rendered the chart."),
+///     ToolResultContent::image("iVBORw0KGgo=", "image/png"),
+/// ]);
+/// let content = ToolResultContent::from_output(&output);
+/// assert_eq!(content[1].to_anthropic()["source"]["media_type"], "image/png");
+/// assert_eq!(content[1].to_mcp(), json!({ "type": "image", "data": "iVBORw0KGgo=", "mimeType": "image/png" }));
+/// assert_eq!(ToolResultContent::from_output("plain"), vec![ToolResultContent::text("plain")]);
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolResultContent {
+    /// Plain text.
+    Text {
+        /// The text.
+        text: String,
+    },
+    /// A JSON value.
+    Json {
+        /// The value.
+        value: Value,
+    },
+    /// A base64-encoded image.
+    Image {
+        /// The image bytes, base64-encoded.
+        data: String,
+        /// The image's MIME type, such as `image/png`.
+        mime_type: String,
+    },
+    /// A link to a resource the client can fetch.
+    Resource {
+        /// The resource's URI.
+        uri: String,
+        /// A display name for the resource.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        /// The resource's MIME type, if known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mime_type: Option<String>,
+    },
+}
+
+impl ToolResultContent {
+    /// A text item.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text { text: text.into() }
+    }
+
+    /// A JSON item.
+    pub fn json(value: Value) -> Self {
+        Self::Json { value }
+    }
+
+    /// An image item from base64 `data` of the given MIME type.
+    pub fn image(data: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self::Image {
+            data: data.into(),
+            mime_type: mime_type.into(),
+        }
+    }
+
+    /// A resource link to `uri`, with no name or MIME type.
+    pub fn resource(uri: impl Into<String>) -> Self {
+        Self::Resource {
+            uri: uri.into(),
+            name: None,
+            mime_type: None,
+        }
+    }
+
+    /// Encodes `content` as a tool output, to be returned from a tool function.
+    pub fn to_output(content: &[ToolResultContent]) -> String {
+        json!({ ENVELOPE: content }).to_string()
+    }
+
+    /// Decodes a tool output made by [`ToolResultContent::to_output`]; any other output is
+    /// a single text item.
+    pub fn from_output(output: &str) -> Vec<ToolResultContent> {
+        serde_json::from_str::<Value>(output)
+            .ok()
+            .and_then(|mut value| value.get_mut(ENVELOPE).map(Value::take))
+            .and_then(|content| serde_json::from_value(content).ok())
+            .unwrap_or_else(|| vec![Self::text(output)])
+    }
+
+    /// This item as text, for providers whose tool results are strings. Images are
+    /// described by their MIME type, and resources by their URI.
+    pub fn to_text(&self) -> String {
+        match self {
+            Self::Text { text } => text.clone(),
+            Self::Json { value } => value.to_string(),
+            Self::Image { mime_type, .. } => format!("[image: {}]", mime_type),
+            Self::Resource { uri, .. } => uri.clone(),
+        }
+    }
+
+    /// This item as an MCP content block.
+    pub fn to_mcp(&self) -> Value {
+        match self {
+            Self::Image { data, mime_type } => {
+                json!({ "type": "image", "data": data, "mimeType": mime_type })
+            }
+            Self::Resource {
+                uri,
+                name,
+                mime_type,
+            } => {
+                let mut block = json!({
+                    "type": "resource_link",
+                    "uri": uri,
+                    "name": name.as_deref().unwrap_or(uri)
+                });
+                if let Some(mime_type) = mime_type {
+                    block["mimeType"] = json!(mime_type);
+                }
+                block
+            }
+            _ => json!({ "type": "text", "text": self.to_text() }),
+        }
+    }
+
+    /// This item as a block of an Anthropic `tool_result`'s `content`.
+    pub fn to_anthropic(&self) -> Value {
+        match self {
+            Self::Image { data, mime_type } => json!({
+                "type": "image",
+                "source": { "type": "base64", "media_type": mime_type, "data": data }
+            }),
+            _ => json!({ "type": "text", "text": self.to_text() }),
+        }
+    }
+
+    /// This item as a block of a Bedrock `toolResult`'s `content`.
+    pub fn to_bedrock(&self) -> Value {
+        match self {
+            Self::Json { value } => json!({ "json": value }),
+            Self::Image { data, mime_type } => {
+                let format = mime_type.strip_prefix("image/").unwrap_or(mime_type);
+                json!({ "image": { "format": format, "source": { "bytes": data } } })
+            }
+            _ => json!({ "text": self.to_text() }),
+        }
+    }
+
+    // Reads an MCP content block, for the kinds that aren't text
+    pub(crate) fn from_mcp(block: &Value) -> Option<Self> {
+        let text = |key: &str| block[key].as_str().map(str::to_string);
+        match block["type"].as_str()? {
+            "image" => Some(Self::image(text("data")?, text("mimeType")?)),
+            "resource_link" => Some(Self::Resource {
+                uri: text("uri")?,
+                name: text("name"),
+                mime_type: text("mimeType"),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The text of `content` for providers whose tool results are strings, one item per line.
+pub(crate) fn to_text(content: &[ToolResultContent]) -> String {
+    content
+        .iter()
+        .map(ToolResultContent::to_text)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The base64 data of the images in `content`, as an Ollama message's `images`.
+pub(crate) fn images(content: &[ToolResultContent]) -> Vec<String> {
+    content
+        .iter()
+        .filter_map(|item| match item {
+            ToolResultContent::Image { data, .. } => Some(data.clone()),
+            _ => None,
+        })
+        .collect()
+}
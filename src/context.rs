@@ -0,0 +1,307 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::session::Session;
+use crate::ToolError;
+
+// Values shared by type, such as the handler's resources
+pub(crate) type TypeMap = HashMap<TypeId, Arc<dyn Any + Send + Sync>>;
+
+tokio::task_local! {
+    static CURRENT: ToolContext;
+}
+
+/// Request-scoped data available to a tool while it runs.
+///
+/// Pass a context to [`ToolHandler::call_tool_with_context`](crate::ToolHandler::call_tool_with_context);
+/// tools receive it through a `#[param(context)]` parameter, which is filled in by the
+/// handler and left out of the tool's schema. Code called from a tool can also read it with
+/// [`ToolContext::current`].
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use tool_calling::{tool, ToolContext, ToolHandler};
+///
+/// struct Locale(&'static str);
+///
+/// #[tool]
+/// fn whoami(#[param(context)] ctx: ToolContext) -> String {
+///     let locale = ctx.get::<Locale>().map_or("en", |locale| locale.0);
+///     format!("{} ({})", ctx.user_id.unwrap_or_default(), locale)
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let handler = ToolHandler::default();
+///     let ctx = ToolContext::new()
+///         .with_user_id("ada")
+///         .with_extension(Locale("fr"));
+///     let call = json!({ "type": "function", "function": { "name": "whoami", "arguments": {} } });
+///     let res = handler.call_tool_with_context(&call, &ctx).await.unwrap();
+///     assert_eq!(res.output, "ada (fr)");
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct ToolContext {
+    /// The user the call is made on behalf of.
+    pub user_id: Option<String>,
+    /// The conversation the call belongs to.
+    pub conversation_id: Option<String>,
+    /// The call fails with `ToolError::Execution` if it is still running at this instant.
+    pub deadline: Option<Instant>,
+    extensions: TypeMap,
+    session: Option<Session>,
+    resources: Arc<TypeMap>,
+    progress: Option<ProgressReporter>,
+}
+
+impl std::fmt::Debug for ToolContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolContext")
+            .field("user_id", &self.user_id)
+            .field("conversation_id", &self.conversation_id)
+            .field("deadline", &self.deadline)
+            .field("extensions", &self.extensions.len())
+            .field("session", &self.session)
+            .field("resources", &self.resources.len())
+            .finish()
+    }
+}
+
+impl ToolContext {
+    /// Creates an empty context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the user the call is made on behalf of.
+    pub fn with_user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    /// Sets the conversation the call belongs to.
+    pub fn with_conversation_id(mut self, conversation_id: impl Into<String>) -> Self {
+        self.conversation_id = Some(conversation_id.into());
+        self
+    }
+
+    /// Sets the instant by which the call must finish.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the deadline to `timeout` from now.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Stores a value of any type, replacing an earlier value of the same type.
+    pub fn with_extension<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.extensions.insert(TypeId::of::<T>(), Arc::new(value));
+        self
+    }
+
+    /// Returns the stored value of type `T`, if any.
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.extensions
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// Time left until the deadline, or `None` without one. Zero once it has passed.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// The session of the conversation, set by the handler when the context has a
+    /// conversation id. See [`session`](crate::session).
+    pub fn session(&self) -> Option<&Session> {
+        self.session.as_ref()
+    }
+
+    pub(crate) fn with_session(mut self, session: Session) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// The handler's resource of type `T`, if one was provided.
+    ///
+    /// See [`ToolHandler::provide`](crate::ToolHandler::provide).
+    pub fn resource<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.resources
+            .get(&TypeId::of::<T>())
+            .and_then(|resource| resource.clone().downcast().ok())
+    }
+
+    pub(crate) fn with_resources(mut self, resources: Arc<TypeMap>) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    /// Reports how far the running tool has got, as a `fraction` between 0 and 1, to the
+    /// handler's progress subscribers. Does nothing outside a tool call.
+    ///
+    /// See [`ToolHandler::subscribe_progress`](crate::ToolHandler::subscribe_progress).
+    pub fn progress(&self, fraction: f64, message: impl Into<String>) {
+        if let Some(reporter) = &self.progress {
+            reporter.report(fraction, message.into());
+        }
+    }
+
+    pub(crate) fn with_progress(mut self, reporter: ProgressReporter) -> Self {
+        self.progress = Some(reporter);
+        self
+    }
+
+    /// The context of the tool call running on this task, or an empty context outside one.
+    pub fn current() -> Self {
+        CURRENT.try_with(Clone::clone).unwrap_or_default()
+    }
+
+    // Run `future` with this context as the current one
+    pub(crate) fn scope<F: Future>(&self, future: F) -> impl Future<Output = F::Output> {
+        CURRENT.scope(self.clone(), future)
+    }
+
+    // Run `f` with this context as the current one
+    pub(crate) fn sync_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        CURRENT.sync_scope(self.clone(), f)
+    }
+
+    // Poll `stream` with this context as the current one
+    pub(crate) fn scope_stream<S>(&self, stream: S) -> ScopedStream<S> {
+        ScopedStream {
+            inner: Box::pin(stream),
+            context: self.clone(),
+        }
+    }
+}
+
+pub(crate) struct ScopedStream<S> {
+    inner: Pin<Box<S>>,
+    context: ToolContext,
+}
+
+impl<S: Stream> Stream for ScopedStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let inner = &mut this.inner;
+        CURRENT.sync_scope(this.context.clone(), || inner.as_mut().poll_next(cx))
+    }
+}
+
+/// A progress update reported by a running tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    /// The name of the reporting tool.
+    pub tool: String,
+    /// The call id from the payload's top-level `id` field, if present.
+    pub call_id: Option<String>,
+    /// The conversation the call belongs to, from its context.
+    pub conversation_id: Option<String>,
+    /// How far the tool has got, between 0 and 1.
+    pub fraction: f64,
+    /// What the tool is doing.
+    pub message: String,
+}
+
+// Sends a call's progress updates to the handler's subscribers
+#[derive(Clone)]
+pub(crate) struct ProgressReporter {
+    sender: broadcast::Sender<ProgressEvent>,
+    tool: String,
+    call_id: Option<String>,
+    conversation_id: Option<String>,
+}
+
+impl ProgressReporter {
+    pub(crate) fn new(
+        sender: broadcast::Sender<ProgressEvent>,
+        tool: &str,
+        call_id: Option<&str>,
+        context: &ToolContext,
+    ) -> Self {
+        Self {
+            sender,
+            tool: tool.to_string(),
+            call_id: call_id.map(str::to_string),
+            conversation_id: context.conversation_id.clone(),
+        }
+    }
+
+    fn report(&self, fraction: f64, message: String) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.sender.send(ProgressEvent {
+            tool: self.tool.clone(),
+            call_id: self.call_id.clone(),
+            conversation_id: self.conversation_id.clone(),
+            fraction: fraction.clamp(0.0, 1.0),
+            message,
+        });
+    }
+}
+
+/// A value that an `#[inject]` tool parameter can be resolved to at call time.
+///
+/// Implemented for `Arc<T>`, which resolves to the resource given to
+/// [`ToolHandler::provide`](crate::ToolHandler::provide). Implement it for other types to
+/// derive them from the context, for example to clone a configuration out of a resource.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use tool_calling::{tool, ToolHandler};
+///
+/// struct Greeting(String);
+///
+/// #[tool]
+/// fn greet(name: String, #[inject] greeting: Arc<Greeting>) -> String {
+///     format!("{}, {}!", greeting.0, name)
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut handler = ToolHandler::default();
+///     handler.provide(Greeting("Hello".into()));
+///     let res = handler.call_with_args("greet", &["Ada".into()]).await.unwrap();
+///     assert_eq!(res, "Hello, Ada!");
+/// }
+/// ```
+pub trait Inject: Sized {
+    /// Resolves the value from the call's context, or `None` if it is unavailable.
+    fn inject(context: &ToolContext) -> Option<Self>;
+}
+
+impl<T: Send + Sync + 'static> Inject for Arc<T> {
+    fn inject(context: &ToolContext) -> Option<Self> {
+        context.resource()
+    }
+}
+
+#[doc(hidden)]
+pub fn __inject<T: Inject>(param: &str) -> Result<T, ToolError> {
+    T::inject(&ToolContext::current()).ok_or_else(|| {
+        ToolError::Execution(format!(
+            "no `{}` was provided for parameter '{}'",
+            std::any::type_name::<T>(),
+            param
+        ))
+    })
+}
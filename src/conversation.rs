@@ -0,0 +1,284 @@
+//! Conversation history: messages, trimming, and tool results.
+//!
+//! [`Conversation`] keeps the [`ChatMessage`]s of a chat in order. It serializes as the
+//! JSON array of its messages, so it can be stored and loaded again, and
+//! [`Conversation::to_json`] gives the `messages` of a request. Appending the answers to an
+//! assistant message's tool calls takes one call to [`Conversation::answer_tool_calls`].
+//!
+//! Long conversations outgrow the context window. [`Conversation::trim`] drops the oldest
+//! messages until the history fits a [`TrimStrategy`], keeping leading system messages and
+//! never separating an assistant message from the `tool` messages answering its calls.
+//!
+//! ```rust
+//! use tool_calling::conversation::{ChatMessage, Conversation, TrimStrategy};
+//!
+//! let mut conversation = Conversation::new();
+//! conversation.push(ChatMessage::system("You are terse."));
+//! for turn in 0..10 {
+//!     conversation.push(ChatMessage::user(format!("Question {}", turn)));
+//!     conversation.push(ChatMessage::assistant(format!("Answer {}", turn)));
+//! }
+//! let removed = conversation.trim(&TrimStrategy::MaxMessages(5));
+//! assert_eq!(removed, 16);
+//! assert_eq!(conversation.messages()[0].text(), Some("You are terse."));
+//! assert_eq!(conversation.messages()[1].text(), Some("Question 8"));
+//!
+//! let json = serde_json::to_string(&conversation).unwrap();
+//! assert_eq!(serde_json::from_str::<Conversation>(&json).unwrap(), conversation);
+//! ```
+
+use crate::tokens::{HeuristicTokenizer, ModelFamily, Tokenizer};
+use crate::{ToolContext, ToolHandler, ToolResultContent};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// One message of a chat, in the OpenAI shape.
+///
+/// Fields other than the ones below, such as a tool message's `name`, are kept in
+/// [`ChatMessage::extra`] so that messages round-trip unchanged.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ChatMessage {
+    /// `system`, `user`, `assistant` or `tool`.
+    pub role: String,
+    /// The text of the message, a list of content parts, or `null`.
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub content: Value,
+    /// The tool calls of an assistant message.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<Value>,
+    /// The id of the call a tool message answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// Base64-encoded images attached to the message, as Ollama's vision models take them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+    /// Any other fields of the message.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ChatMessage {
+    /// A message with `role` and text `content`.
+    pub fn new(role: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            role: role.into(),
+            content: Value::String(content.into()),
+            ..Self::default()
+        }
+    }
+
+    /// A system message.
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new("system", content)
+    }
+
+    /// A user message.
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new("user", content)
+    }
+
+    /// An assistant message.
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new("assistant", content)
+    }
+
+    /// A tool message answering the call with id `tool_call_id`.
+    pub fn tool(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::new("tool", content)
+        }
+    }
+
+    /// Attaches a base64-encoded image.
+    pub fn with_image(mut self, data: impl Into<String>) -> Self {
+        self.images.push(data.into());
+        self
+    }
+
+    /// Attaches base64-encoded images.
+    pub fn with_images<I, S>(mut self, images: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.images.extend(images.into_iter().map(Into::into));
+        self
+    }
+
+    /// Attaches the images among a tool's rich result content, e.g. to show a screenshot a
+    /// tool took to the model in the next message.
+    pub fn with_result_images(mut self, content: &[ToolResultContent]) -> Self {
+        self.images.extend(crate::content::images(content));
+        self
+    }
+
+    /// The message's text, when its content is a string.
+    pub fn text(&self) -> Option<&str> {
+        self.content.as_str()
+    }
+
+    /// Reads a message from its JSON form.
+    pub fn from_json(message: &Value) -> Result<Self, serde_json::Error> {
+        Self::deserialize(message)
+    }
+
+    /// The message's JSON form.
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_else(|_| json!({ "role": self.role }))
+    }
+}
+
+/// How [`Conversation::trim`] decides that a history is short enough.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrimStrategy {
+    /// At most this many messages, system messages included.
+    MaxMessages(usize),
+    /// At most this many tokens, as estimated for `family`.
+    MaxTokens {
+        /// The token budget for the messages.
+        limit: usize,
+        /// The family whose tokenizer the estimate is for.
+        family: ModelFamily,
+    },
+}
+
+/// The messages of a chat, oldest first.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(transparent)]
+pub struct Conversation {
+    messages: Vec<ChatMessage>,
+}
+
+impl Conversation {
+    /// An empty conversation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a conversation from the JSON messages of a request.
+    pub fn from_json(messages: &[Value]) -> Result<Self, serde_json::Error> {
+        let messages = messages
+            .iter()
+            .map(ChatMessage::from_json)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { messages })
+    }
+
+    /// The messages as JSON, ready to send as a request's `messages`.
+    pub fn to_json(&self) -> Vec<Value> {
+        self.messages.iter().map(ChatMessage::to_json).collect()
+    }
+
+    /// The messages, oldest first.
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    /// The number of messages.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Whether there are no messages.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Appends a message.
+    pub fn push(&mut self, message: ChatMessage) {
+        self.messages.push(message);
+    }
+
+    /// Appends a message given as JSON, such as the assistant message of a response.
+    pub fn push_json(&mut self, message: &Value) -> Result<(), serde_json::Error> {
+        self.messages.push(ChatMessage::from_json(message)?);
+        Ok(())
+    }
+
+    /// Appends a tool message answering the call with id `tool_call_id`.
+    pub fn push_tool_result(
+        &mut self,
+        tool_call_id: impl Into<String>,
+        content: impl Into<String>,
+    ) {
+        self.messages.push(ChatMessage::tool(tool_call_id, content));
+    }
+
+    /// Runs the tool calls of the last message, if it is an assistant message calling tools,
+    /// and appends a `tool` message answering each, as
+    /// [`ToolHandler::answer_tool_calls`] builds them. Returns the number of messages added.
+    pub async fn answer_tool_calls(
+        &mut self,
+        handler: &ToolHandler,
+        context: &ToolContext,
+    ) -> usize {
+        let Some(last) = self.messages.last() else {
+            return 0;
+        };
+        if last.role != "assistant" || last.tool_calls.is_empty() {
+            return 0;
+        }
+        let answers = handler.answer_tool_calls(&last.to_json(), context).await;
+        let added = answers.len();
+        for answer in &answers {
+            if let Ok(message) = ChatMessage::from_json(answer) {
+                self.messages.push(message);
+            }
+        }
+        added
+    }
+
+    /// Drops the oldest messages until the conversation fits `strategy`, estimating tokens
+    /// with [`HeuristicTokenizer`]. Returns the number of messages removed.
+    ///
+    /// Leading system messages are always kept, and so is the newest message with the
+    /// `tool` messages answering it. An assistant message calling tools is dropped together
+    /// with its answers, so the history never starts with an unanswered call or an orphaned
+    /// result.
+    pub fn trim(&mut self, strategy: &TrimStrategy) -> usize {
+        self.trim_with(strategy, &HeuristicTokenizer)
+    }
+
+    /// Like [`Conversation::trim`], estimating tokens with `tokenizer`.
+    pub fn trim_with(&mut self, strategy: &TrimStrategy, tokenizer: &dyn Tokenizer) -> usize {
+        let pinned = self
+            .messages
+            .iter()
+            .take_while(|message| message.role == "system")
+            .count();
+        let cost = |message: &ChatMessage| match strategy {
+            TrimStrategy::MaxMessages(_) => 1,
+            TrimStrategy::MaxTokens { family, .. } => {
+                tokenizer.count_tokens(&message.to_json().to_string(), *family)
+            }
+        };
+        let limit = match strategy {
+            TrimStrategy::MaxMessages(limit) => *limit,
+            TrimStrategy::MaxTokens { limit, .. } => *limit,
+        };
+        let mut total = self.messages.iter().map(cost).sum::<usize>();
+        let mut start = pinned;
+        while start < self.messages.len() {
+            let end = self.group_end(start);
+            if total <= limit || end >= self.messages.len() {
+                break;
+            }
+            total -= self.messages[start..end].iter().map(cost).sum::<usize>();
+            start = end;
+        }
+        self.messages.drain(pinned..start).count()
+    }
+
+    // The end of the group starting at `start`: an assistant message with the tool messages
+    // that follow it, or any other single message
+    fn group_end(&self, start: usize) -> usize {
+        let mut end = start + 1;
+        if self.messages[start].role == "assistant" {
+            while end < self.messages.len() && self.messages[end].role == "tool" {
+                end += 1;
+            }
+        }
+        end
+    }
+}
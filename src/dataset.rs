@@ -0,0 +1,251 @@
+//! Fine-tuning datasets built from real tool traffic.
+//!
+//! A [`DatasetExporter`] turns recorded conversations, or the [`AuditRecord`]s of an audit
+//! log, into training examples in OpenAI's chat fine-tuning format or the ShareGPT format
+//! read by tools such as LLaMA-Factory and Axolotl. Every example carries the handler's tool
+//! definitions, the assistant's calls and the tools' results. [`write_jsonl`] writes the
+//! examples one per line.
+//!
+//! Audit records do not hold the user's prompt, so examples made from them only show
+//! calls and results; records sharing a `conversation_id` become one example. Recording
+//! whole conversations gives complete examples.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::dataset::{DatasetExporter, DatasetFormat};
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! let handler = ToolHandler::default();
+//! let messages = [
+//!     json!({ "role": "user", "content": "What is 1 + 2?" }),
+//!     json!({ "role": "assistant", "tool_calls": [
+//!         { "id": "call_1", "type": "function", "function": { "name": "add", "arguments": { "a": 1, "b": 2 } } }
+//!     ] }),
+//!     json!({ "role": "tool", "tool_call_id": "call_1", "content": "3" }),
+//!     json!({ "role": "assistant", "content": "1 + 2 = 3." }),
+//! ];
+//! let example = DatasetExporter::new(&handler, DatasetFormat::ShareGpt).conversation(&messages);
+//! assert_eq!(
+//!     example["conversations"],
+//!     json!([
+//!         { "from": "human", "value": "What is 1 + 2?" },
+//!         { "from": "function_call", "value": "{\"name\":\"add\",\"arguments\":{\"a\":1,\"b\":2}}" },
+//!         { "from": "observation", "value": "3" },
+//!         { "from": "gpt", "value": "1 + 2 = 3." }
+//!     ])
+//! );
+//! ```
+
+use crate::audit::AuditRecord;
+use crate::ToolHandler;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::Path;
+
+/// The layout of exported examples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetFormat {
+    /// `{"messages", "tools"}`, OpenAI's chat fine-tuning format.
+    OpenAi,
+    /// `{"conversations", "system", "tools"}`, with `human`, `gpt`, `function_call` and
+    /// `observation` turns.
+    ShareGpt,
+}
+
+/// Builds fine-tuning examples for the tools of a handler.
+#[derive(Clone)]
+pub struct DatasetExporter<'a> {
+    handler: &'a ToolHandler,
+    format: DatasetFormat,
+    system_prompt: Option<String>,
+    include_errors: bool,
+}
+
+impl<'a> DatasetExporter<'a> {
+    /// Creates an exporter writing `format` examples that list the tools of `handler`.
+    pub fn new(handler: &'a ToolHandler, format: DatasetFormat) -> Self {
+        Self {
+            handler,
+            format,
+            system_prompt: None,
+            include_errors: false,
+        }
+    }
+
+    /// Starts every example with this system prompt, unless the conversation has its own.
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Keeps failed calls in examples made from audit records, answered with the text of
+    /// [`ToolHandler::error_message`]. They are left out by default, so the model is not
+    /// trained on calls that did not work.
+    pub fn include_errors(mut self) -> Self {
+        self.include_errors = true;
+        self
+    }
+
+    /// An example made from a conversation of OpenAI-style `system`, `user`, `assistant`
+    /// and `tool` messages.
+    pub fn conversation(&self, messages: &[Value]) -> Value {
+        let mut messages = messages.iter().map(normalize_message).collect::<Vec<_>>();
+        if let Some(prompt) = &self.system_prompt {
+            if messages
+                .first()
+                .is_none_or(|message| message["role"] != "system")
+            {
+                messages.insert(0, json!({ "role": "system", "content": prompt }));
+            }
+        }
+        match self.format {
+            DatasetFormat::OpenAi => json!({
+                "messages": messages,
+                "tools": self.handler.all_tools_schema()
+            }),
+            DatasetFormat::ShareGpt => self.share_gpt(&messages),
+        }
+    }
+
+    /// Examples made from audit records: one per `conversation_id` in the records'
+    /// metadata, in order of first appearance, and one for each record without one. Each
+    /// call becomes an assistant turn answered by the tool's result.
+    pub fn audit_records(&self, records: &[AuditRecord]) -> Vec<Value> {
+        let mut groups: Vec<(Option<&str>, Vec<&AuditRecord>)> = Vec::new();
+        for record in records {
+            if record.result.is_err() && !self.include_errors {
+                continue;
+            }
+            let conversation = record.metadata.get("conversation_id").map(String::as_str);
+            match groups
+                .iter_mut()
+                .find(|(id, _)| conversation.is_some() && *id == conversation)
+            {
+                Some((_, group)) => group.push(record),
+                None => groups.push((conversation, vec![record])),
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(_, group)| {
+                let mut messages = Vec::new();
+                for (i, record) in group.into_iter().enumerate() {
+                    let id = record
+                        .call_id
+                        .clone()
+                        .unwrap_or_else(|| format!("call_{}", i));
+                    let arguments = Value::Object(record.arguments.clone()).to_string();
+                    messages.push(json!({
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": id,
+                            "type": "function",
+                            "function": { "name": record.name, "arguments": arguments }
+                        }]
+                    }));
+                    let content = match &record.result {
+                        Ok(output) => output.clone(),
+                        Err(err) => self.handler.error_message(err),
+                    };
+                    messages
+                        .push(json!({ "role": "tool", "tool_call_id": id, "content": content }));
+                }
+                self.conversation(&messages)
+            })
+            .collect()
+    }
+
+    fn share_gpt(&self, messages: &[Value]) -> Value {
+        let mut system = None;
+        let mut turns = Vec::new();
+        let mut messages = messages.iter().peekable();
+        while let Some(message) = messages.next() {
+            let content = message["content"].as_str().unwrap_or_default();
+            match message["role"].as_str() {
+                Some("system") => system = Some(content),
+                Some("user") => turns.push(json!({ "from": "human", "value": content })),
+                Some("assistant") => match message["tool_calls"].as_array() {
+                    Some(calls) if !calls.is_empty() => {
+                        let calls = calls
+                            .iter()
+                            .map(|call| {
+                                let function = &call["function"];
+                                let arguments = function["arguments"]
+                                    .as_str()
+                                    .and_then(|encoded| serde_json::from_str(encoded).ok())
+                                    .unwrap_or_else(|| function["arguments"].clone());
+                                json!({ "name": function["name"], "arguments": arguments })
+                            })
+                            .collect::<Vec<_>>();
+                        // Parallel calls are written as one list
+                        let value = match calls.as_slice() {
+                            [call] => call.to_string(),
+                            _ => Value::Array(calls).to_string(),
+                        };
+                        turns.push(json!({ "from": "function_call", "value": value }));
+                    }
+                    _ => turns.push(json!({ "from": "gpt", "value": content })),
+                },
+                Some("tool") => {
+                    // And so are their results
+                    let mut results = vec![content];
+                    while let Some(next) = messages.next_if(|next| next["role"] == "tool") {
+                        results.push(next["content"].as_str().unwrap_or_default());
+                    }
+                    let value = match results.as_slice() {
+                        [result] => result.to_string(),
+                        _ => json!(results).to_string(),
+                    };
+                    turns.push(json!({ "from": "observation", "value": value }));
+                }
+                _ => {}
+            }
+        }
+        let tools = self
+            .handler
+            .all_tools_schema()
+            .as_array()
+            .map(|tools| tools.iter().map(|tool| tool["function"].clone()).collect())
+            .unwrap_or_default();
+        let mut example = json!({
+            "conversations": turns,
+            "tools": Value::Array(tools).to_string()
+        });
+        if let Some(system) = system {
+            example["system"] = json!(system);
+        }
+        example
+    }
+}
+
+/// Writes `examples` to `path` as JSON lines, the format fine-tuning services expect.
+pub fn write_jsonl(examples: &[Value], path: impl AsRef<Path>) -> std::io::Result<()> {
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for example in examples {
+        serde_json::to_writer(&mut file, example)?;
+        file.write_all(b"\n")?;
+    }
+    file.flush()
+}
+
+// Fills in the `type` of calls and encodes their arguments, as OpenAI expects
+fn normalize_message(message: &Value) -> Value {
+    let mut message = message.clone();
+    if let Some(calls) = message.get_mut("tool_calls").and_then(Value::as_array_mut) {
+        for call in calls {
+            call["type"] = json!("function");
+            let arguments = &mut call["function"]["arguments"];
+            if !arguments.is_string() {
+                *arguments = json!(arguments.to_string());
+            }
+        }
+    }
+    message
+}
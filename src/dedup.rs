@@ -0,0 +1,64 @@
+use crate::ToolError;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::watch;
+
+// The output of a call and the fallback that produced it, if any
+pub(crate) type Outcome = Result<(String, Option<String>), ToolError>;
+
+// Calls currently executing, by key, shared by a handler's clones so identical concurrent
+// calls can wait for the first one instead of executing again
+#[derive(Default)]
+pub(crate) struct InFlight {
+    calls: Mutex<HashMap<String, watch::Receiver<Option<Outcome>>>>,
+}
+
+pub(crate) enum Slot<'a> {
+    // No identical call is running; this one executes and publishes its outcome
+    Leader(Leader<'a>),
+    // An identical call is running; wait for its outcome
+    Follower(watch::Receiver<Option<Outcome>>),
+}
+
+pub(crate) struct Leader<'a> {
+    in_flight: &'a InFlight,
+    key: String,
+    sender: watch::Sender<Option<Outcome>>,
+}
+
+impl InFlight {
+    pub(crate) fn join(&self, key: String) -> Slot<'_> {
+        let mut calls = self.calls.lock().unwrap();
+        if let Some(receiver) = calls.get(&key) {
+            return Slot::Follower(receiver.clone());
+        }
+        let (sender, receiver) = watch::channel(None);
+        calls.insert(key.clone(), receiver);
+        Slot::Leader(Leader {
+            in_flight: self,
+            key,
+            sender,
+        })
+    }
+}
+
+impl Leader<'_> {
+    pub(crate) fn finish(self, outcome: &Outcome) {
+        self.sender.send_replace(Some(outcome.clone()));
+    }
+}
+
+// A cancelled leader leaves without an outcome, and its followers start over
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        self.in_flight.calls.lock().unwrap().remove(&self.key);
+    }
+}
+
+// Waits for the leader's outcome; `None` if the leader was cancelled
+pub(crate) async fn leader_outcome(
+    mut receiver: watch::Receiver<Option<Outcome>>,
+) -> Option<Outcome> {
+    let outcome = receiver.wait_for(Option::is_some).await.ok()?;
+    outcome.clone()
+}
@@ -0,0 +1,390 @@
+//! Comparing two versions of a tool registry's schema for compatibility.
+//!
+//! [`diff_schemas`] compares the tool definitions of an old and a new registry, as rendered
+//! by [`ToolHandler::all_tools_schema`](crate::ToolHandler::all_tools_schema), and lists
+//! every change to a tool or parameter. Each [`SchemaChange`] is either breaking, when calls
+//! valid against the old schema may fail against the new one, or additive:
+//!
+//! - removing a tool or a parameter, adding a required parameter, making a parameter
+//!   required, changing a type to one that doesn't accept every old value, and removing
+//!   `enum` values or adding an `enum` are breaking,
+//! - adding a tool or an optional parameter, making a parameter optional, widening a type,
+//!   adding `enum` values or removing an `enum`, and changing descriptions are additive.
+//!
+//! Parameters of nested objects are compared too, named by their path, as are array items,
+//! named with a trailing `[]`. Other keywords, such as bounds and patterns, are not compared.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::diff::{diff_schemas, ChangeKind};
+//!
+//! let old = json!([{ "type": "function", "function": {
+//!     "name": "search",
+//!     "description": "Searches the web",
+//!     "parameters": { "type": "object", "properties": { "query": { "type": "string" } }, "required": ["query"] }
+//! }}]);
+//! let new = json!([{ "type": "function", "function": {
+//!     "name": "search",
+//!     "description": "Searches the web",
+//!     "parameters": {
+//!         "type": "object",
+//!         "properties": { "query": { "type": "string" }, "limit": { "type": "integer" } },
+//!         "required": ["query", "limit"]
+//!     }
+//! }}]);
+//! let diff = diff_schemas(&old, &new);
+//! assert!(diff.is_breaking());
+//! assert_eq!(diff.changes[0].parameter.as_deref(), Some("limit"));
+//! assert_eq!(diff.changes[0].kind, ChangeKind::ParameterAdded { required: true });
+//! ```
+
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// What changed about a tool or parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeKind {
+    /// The tool was added.
+    ToolAdded,
+    /// The tool was removed.
+    ToolRemoved,
+    /// The parameter was added.
+    ParameterAdded {
+        /// Whether the new parameter is required.
+        required: bool,
+    },
+    /// The parameter was removed.
+    ParameterRemoved,
+    /// The optional parameter became required.
+    BecameRequired,
+    /// The required parameter became optional.
+    BecameOptional,
+    /// The `type` changed, from and to the values given, `null` when absent.
+    TypeChanged { old: Value, new: Value },
+    /// Values were added to the `enum`.
+    EnumValuesAdded(Vec<Value>),
+    /// Values were removed from the `enum`.
+    EnumValuesRemoved(Vec<Value>),
+    /// The parameter, which accepted any value of its type, was restricted to an `enum` of
+    /// the values given.
+    RestrictedToEnum(Vec<Value>),
+    /// The `enum` was removed, so the parameter accepts any value of its type.
+    EnumLifted,
+    /// The description changed.
+    DescriptionChanged,
+}
+
+impl ChangeKind {
+    /// Whether calls valid against the old schema may fail against the new one.
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            ChangeKind::ToolRemoved
+            | ChangeKind::ParameterRemoved
+            | ChangeKind::BecameRequired
+            | ChangeKind::EnumValuesRemoved(_)
+            | ChangeKind::RestrictedToEnum(_) => true,
+            ChangeKind::ParameterAdded { required } => *required,
+            ChangeKind::TypeChanged { old, new } => !accepts_types(new, old),
+            ChangeKind::ToolAdded
+            | ChangeKind::BecameOptional
+            | ChangeKind::EnumValuesAdded(_)
+            | ChangeKind::EnumLifted
+            | ChangeKind::DescriptionChanged => false,
+        }
+    }
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::ToolAdded => f.write_str("added"),
+            ChangeKind::ToolRemoved => f.write_str("removed"),
+            ChangeKind::ParameterAdded { required: true } => f.write_str("added as required"),
+            ChangeKind::ParameterAdded { required: false } => f.write_str("added as optional"),
+            ChangeKind::ParameterRemoved => f.write_str("removed"),
+            ChangeKind::BecameRequired => f.write_str("now required"),
+            ChangeKind::BecameOptional => f.write_str("now optional"),
+            ChangeKind::TypeChanged { old, new } => {
+                write!(f, "type changed from {} to {}", old, new)
+            }
+            ChangeKind::EnumValuesAdded(values) => write!(f, "enum values added: {}", list(values)),
+            ChangeKind::EnumValuesRemoved(values) => {
+                write!(f, "enum values removed: {}", list(values))
+            }
+            ChangeKind::RestrictedToEnum(values) => {
+                write!(f, "restricted to enum values: {}", list(values))
+            }
+            ChangeKind::EnumLifted => f.write_str("no longer restricted to enum values"),
+            ChangeKind::DescriptionChanged => f.write_str("description changed"),
+        }
+    }
+}
+
+/// A change to a tool, or to one of its parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaChange {
+    /// The name of the tool.
+    pub tool: String,
+    /// The path of the parameter, such as `options.limit` or `tags[]`, or `None` for a
+    /// change to the tool itself.
+    pub parameter: Option<String>,
+    /// What changed.
+    pub kind: ChangeKind,
+}
+
+impl SchemaChange {
+    /// Whether calls valid against the old schema may fail against the new one.
+    pub fn is_breaking(&self) -> bool {
+        self.kind.is_breaking()
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = if self.is_breaking() {
+            "breaking"
+        } else {
+            "additive"
+        };
+        match &self.parameter {
+            Some(parameter) => write!(
+                f,
+                "{}: tool '{}': parameter '{}' {}",
+                severity, self.tool, parameter, self.kind
+            ),
+            None => write!(f, "{}: tool '{}' {}", severity, self.tool, self.kind),
+        }
+    }
+}
+
+/// The outcome of [`diff_schemas`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaDiff {
+    /// Every change, tool by tool in the order of the old registry, then the added tools.
+    pub changes: Vec<SchemaChange>,
+}
+
+impl SchemaDiff {
+    /// Whether the two registries describe the same tools.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Whether any change is breaking.
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(SchemaChange::is_breaking)
+    }
+
+    /// The breaking changes.
+    pub fn breaking(&self) -> impl Iterator<Item = &SchemaChange> {
+        self.changes.iter().filter(|change| change.is_breaking())
+    }
+
+    /// The additive changes.
+    pub fn additive(&self) -> impl Iterator<Item = &SchemaChange> {
+        self.changes.iter().filter(|change| !change.is_breaking())
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the tool definitions of the registries `old` and `new`.
+///
+/// Each registry is an array of tool definitions, such as those returned by
+/// [`ToolHandler::all_tools_schema`](crate::ToolHandler::all_tools_schema) or one of its
+/// provider-specific variants: `{"type": "function", "function": {...}}` wrappers, or
+/// definitions with `parameters`, `input_schema` or `inputSchema` at the top level. Tools are
+/// matched by name.
+pub fn diff_schemas(old: &Value, new: &Value) -> SchemaDiff {
+    let old_tools = definitions(old);
+    let new_tools = definitions(new);
+    let mut changes = Vec::new();
+    for (name, old_tool) in &old_tools {
+        let Some((_, new_tool)) = new_tools.iter().find(|(other, _)| other == name) else {
+            changes.push(change(name, None, ChangeKind::ToolRemoved));
+            continue;
+        };
+        if old_tool.get("description") != new_tool.get("description") {
+            changes.push(change(name, None, ChangeKind::DescriptionChanged));
+        }
+        compare_object(
+            name,
+            "",
+            parameters(old_tool),
+            parameters(new_tool),
+            &mut changes,
+        );
+    }
+    for (name, _) in &new_tools {
+        if !old_tools.iter().any(|(other, _)| other == name) {
+            changes.push(change(name, None, ChangeKind::ToolAdded));
+        }
+    }
+    SchemaDiff { changes }
+}
+
+// The name and definition of each tool in a registry
+fn definitions(registry: &Value) -> Vec<(&str, &Map<String, Value>)> {
+    registry
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|tool| {
+            let tool = tool.get("function").unwrap_or(tool).as_object()?;
+            Some((tool.get("name")?.as_str()?, tool))
+        })
+        .collect()
+}
+
+fn parameters(tool: &Map<String, Value>) -> &Value {
+    ["parameters", "input_schema", "inputSchema"]
+        .iter()
+        .find_map(|key| tool.get(*key))
+        .unwrap_or(&Value::Null)
+}
+
+fn change(tool: &str, parameter: Option<&str>, kind: ChangeKind) -> SchemaChange {
+    SchemaChange {
+        tool: tool.to_string(),
+        parameter: parameter.map(str::to_string),
+        kind,
+    }
+}
+
+// Compares the properties of two object schemas, naming them below `prefix`
+fn compare_object(
+    tool: &str,
+    prefix: &str,
+    old: &Value,
+    new: &Value,
+    changes: &mut Vec<SchemaChange>,
+) {
+    let empty = Map::new();
+    let old_properties = old["properties"].as_object().unwrap_or(&empty);
+    let new_properties = new["properties"].as_object().unwrap_or(&empty);
+    let is_required = |schema: &Value, name: &str| {
+        schema["required"]
+            .as_array()
+            .is_some_and(|required| required.iter().any(|r| r == name))
+    };
+    for (name, old_property) in old_properties {
+        let path = format!("{}{}", prefix, name);
+        let Some(new_property) = new_properties.get(name) else {
+            changes.push(change(tool, Some(&path), ChangeKind::ParameterRemoved));
+            continue;
+        };
+        match (is_required(old, name), is_required(new, name)) {
+            (false, true) => changes.push(change(tool, Some(&path), ChangeKind::BecameRequired)),
+            (true, false) => changes.push(change(tool, Some(&path), ChangeKind::BecameOptional)),
+            _ => {}
+        }
+        compare_value(tool, &path, old_property, new_property, changes);
+    }
+    for name in new_properties.keys() {
+        if !old_properties.contains_key(name) {
+            let required = is_required(new, name);
+            let path = format!("{}{}", prefix, name);
+            changes.push(change(
+                tool,
+                Some(&path),
+                ChangeKind::ParameterAdded { required },
+            ));
+        }
+    }
+}
+
+// Compares the schemas of one parameter
+fn compare_value(
+    tool: &str,
+    path: &str,
+    old: &Value,
+    new: &Value,
+    changes: &mut Vec<SchemaChange>,
+) {
+    if old["type"] != new["type"] {
+        let kind = ChangeKind::TypeChanged {
+            old: old["type"].clone(),
+            new: new["type"].clone(),
+        };
+        changes.push(change(tool, Some(path), kind));
+    }
+    match (old["enum"].as_array(), new["enum"].as_array()) {
+        (Some(old_values), Some(new_values)) => {
+            let missing = |from: &[Value], to: &[Value]| {
+                from.iter()
+                    .filter(|value| !to.contains(value))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            };
+            let removed = missing(old_values, new_values);
+            let added = missing(new_values, old_values);
+            if !removed.is_empty() {
+                changes.push(change(
+                    tool,
+                    Some(path),
+                    ChangeKind::EnumValuesRemoved(removed),
+                ));
+            }
+            if !added.is_empty() {
+                changes.push(change(tool, Some(path), ChangeKind::EnumValuesAdded(added)));
+            }
+        }
+        (None, Some(values)) => changes.push(change(
+            tool,
+            Some(path),
+            ChangeKind::RestrictedToEnum(values.clone()),
+        )),
+        (Some(_), None) => changes.push(change(tool, Some(path), ChangeKind::EnumLifted)),
+        (None, None) => {}
+    }
+    if old.get("description") != new.get("description") {
+        changes.push(change(tool, Some(path), ChangeKind::DescriptionChanged));
+    }
+    if old.get("properties").is_some() || new.get("properties").is_some() {
+        compare_object(tool, &format!("{}.", path), old, new, changes);
+    }
+    if let (Some(old_items), Some(new_items)) = (old.get("items"), new.get("items")) {
+        compare_value(tool, &format!("{}[]", path), old_items, new_items, changes);
+    }
+}
+
+// Whether a schema of type `new` accepts every value of type `old`. An absent type accepts
+// anything, and `number` accepts integers.
+fn accepts_types(new: &Value, old: &Value) -> bool {
+    let types = |kind: &Value| -> Option<Vec<String>> {
+        match kind {
+            Value::String(kind) => Some(vec![kind.clone()]),
+            Value::Array(kinds) => Some(
+                kinds
+                    .iter()
+                    .filter_map(|kind| kind.as_str().map(str::to_string))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    };
+    let Some(new) = types(new) else {
+        return true;
+    };
+    let Some(old) = types(old) else {
+        return false;
+    };
+    old.iter().all(|kind| {
+        new.contains(kind) || (kind == "integer" && new.iter().any(|new| new == "number"))
+    })
+}
+
+fn list(values: &[Value]) -> String {
+    values
+        .iter()
+        .map(Value::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,190 @@
+//! Picking the tools most relevant to a message by embedding similarity.
+//!
+//! Attach an [`Embedder`] with
+//! [`ToolHandler::with_embedder`](crate::ToolHandler::with_embedder) and call
+//! [`ToolHandler::top_k_tools`](crate::ToolHandler::top_k_tools) with the user's message to
+//! send the model only the best-matching schemas. Each tool is embedded once, from its name,
+//! description and tags, and re-embedded only if those change.
+//!
+//! [`HashingEmbedder`] works offline with no model; plug in a real embedding model by
+//! implementing [`Embedder`].
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use tool_calling::embedding::HashingEmbedder;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Get the weather forecast for a city
+//! fn forecast(city: String) -> String {
+//!     format!("sunny in {}", city)
+//! }
+//!
+//! #[tool]
+//! /// Convert an amount between currencies
+//! fn convert_currency(amount: f64, from: String, to: String) -> String {
+//!     format!("{} {} in {}", amount, from, to)
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let handler = ToolHandler::default().with_embedder(Arc::new(HashingEmbedder::new(256)));
+//!     let tools = handler.top_k_tools("what's the weather forecast in Paris?", 1).await.unwrap();
+//!     assert_eq!(tools[0].name, "forecast");
+//!
+//!     let names = tools.iter().map(|tool| tool.name.as_str()).collect::<Vec<_>>();
+//!     let schema = handler.tools_schema_for(&names);
+//!     assert_eq!(schema.as_array().unwrap().len(), 1);
+//! }
+//! ```
+
+use crate::{Tool, ToolHandler};
+use futures::future::{self, BoxFuture};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+/// Errors raised while selecting tools by embedding.
+#[derive(Debug, Clone, thiserror::Error, PartialEq)]
+pub enum EmbeddingError {
+    #[error("no embedder is configured; see ToolHandler::with_embedder")]
+    NotConfigured,
+    #[error("embedding failed: {0}")]
+    Embedder(String),
+}
+
+/// Turns texts into embedding vectors.
+pub trait Embedder: Send + Sync {
+    /// Embeds each text, returning one vector per text in the same order.
+    fn embed(&self, texts: Vec<String>) -> BoxFuture<'_, Result<Vec<Vec<f32>>, EmbeddingError>>;
+}
+
+/// An embedder that hashes words into a fixed number of buckets.
+///
+/// It needs no model and matches on shared words only, which is often enough to narrow a
+/// large tool set down by keywords.
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    dimensions: usize,
+}
+
+impl HashingEmbedder {
+    /// Creates an embedder producing vectors with `dimensions` entries.
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions: dimensions.max(1),
+        }
+    }
+
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0; self.dimensions];
+        let words = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty());
+        for word in words {
+            let mut hasher = DefaultHasher::new();
+            word.to_lowercase().hash(&mut hasher);
+            vector[(hasher.finish() % self.dimensions as u64) as usize] += 1.0;
+        }
+        vector
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn embed(&self, texts: Vec<String>) -> BoxFuture<'_, Result<Vec<Vec<f32>>, EmbeddingError>> {
+        let vectors = texts.iter().map(|text| self.embed_one(text)).collect();
+        Box::pin(future::ready(Ok(vectors)))
+    }
+}
+
+// An embedder and the cached embedding of each tool, shared by a handler's clones
+pub(crate) struct EmbeddingIndex {
+    embedder: Arc<dyn Embedder>,
+    // Tool name to the text that was embedded and its vector
+    cache: Mutex<HashMap<String, (String, Vec<f32>)>>,
+}
+
+// The text a tool is embedded from
+fn tool_text(tool: &Tool) -> String {
+    let mut text = format!("{}: {}", tool.name.replace('_', " "), tool.description);
+    if !tool.tags.is_empty() {
+        text.push_str(&format!(" ({})", tool.tags.join(", ")));
+    }
+    text
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+impl ToolHandler {
+    /// Uses `embedder` to rank tools in [`ToolHandler::top_k_tools`].
+    ///
+    /// See the [`embedding`](crate::embedding) module.
+    pub fn with_embedder(mut self, embedder: Arc<dyn Embedder>) -> Self {
+        self.embeddings = Some(Arc::new(EmbeddingIndex {
+            embedder,
+            cache: Mutex::default(),
+        }));
+        self
+    }
+
+    /// Returns up to `k` tools most similar to `query`, best match first.
+    ///
+    /// Tools not yet embedded, or whose name, description or tags changed, are embedded
+    /// in one batch first.
+    pub async fn top_k_tools(&self, query: &str, k: usize) -> Result<Vec<&Tool>, EmbeddingError> {
+        let index = self
+            .embeddings
+            .as_ref()
+            .ok_or(EmbeddingError::NotConfigured)?;
+
+        let texts = self.tools().map(tool_text).collect::<Vec<_>>();
+        let stale = {
+            let cache = index.cache.lock().unwrap();
+            self.tools()
+                .zip(&texts)
+                .filter(|(tool, text)| cache.get(&tool.name).is_none_or(|(seen, _)| seen != *text))
+                .map(|(tool, text)| (tool.name.clone(), text.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        // The query is embedded in the same batch as the stale tools
+        let mut batch = stale
+            .iter()
+            .map(|(_, text)| text.clone())
+            .collect::<Vec<_>>();
+        batch.push(query.to_string());
+        let mut vectors = index.embedder.embed(batch).await?;
+        if vectors.len() != stale.len() + 1 {
+            return Err(EmbeddingError::Embedder(format!(
+                "expected {} embeddings, got {}",
+                stale.len() + 1,
+                vectors.len()
+            )));
+        }
+        let query = vectors.pop().unwrap_or_default();
+
+        let mut cache = index.cache.lock().unwrap();
+        for ((name, text), vector) in stale.into_iter().zip(vectors) {
+            cache.insert(name, (text, vector));
+        }
+        let mut scored = self
+            .tools()
+            .filter_map(|tool| {
+                let (_, vector) = cache.get(&tool.name)?;
+                Some((cosine(&query, vector), tool))
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(scored.into_iter().take(k).map(|(_, tool)| tool).collect())
+    }
+}
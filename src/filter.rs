@@ -0,0 +1,120 @@
+use crate::Tool;
+use std::sync::Arc;
+
+/// Decides which tools a request may see and call.
+///
+/// Returned by the hook installed with
+/// [`ToolHandler::with_tool_filter`](crate::ToolHandler::with_tool_filter), so each request
+/// can get a different effective tool set.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use tool_calling::{tool, ToolContext, ToolError, ToolFilter, ToolHandler};
+///
+/// #[tool(tags("destructive"))]
+/// fn drop_table(table: String) -> String {
+///     format!("dropped {}", table)
+/// }
+///
+/// #[tool]
+/// fn list_tables() -> String {
+///     "users, orders".to_string()
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let handler = ToolHandler::default().with_tool_filter(|ctx: &ToolContext| {
+///         match ctx.user_id.as_deref() {
+///             Some("admin") => ToolFilter::all(),
+///             _ => ToolFilter::untagged("destructive"),
+///         }
+///     });
+///
+///     let guest = ToolContext::new().with_user_id("guest");
+///     let schema = handler.tools_schema_with_context(&guest);
+///     assert_eq!(schema.as_array().unwrap().len(), 1);
+///
+///     let call = json!({
+///         "type": "function",
+///         "function": { "name": "drop_table", "arguments": { "table": "users" } }
+///     });
+///     let err = handler.call_tool_with_context(&call, &guest).await.unwrap_err();
+///     assert_eq!(err, ToolError::NotFound("drop_table".into()));
+///
+///     let admin = ToolContext::new().with_user_id("admin");
+///     let res = handler.call_tool_with_context(&call, &admin).await.unwrap();
+///     assert_eq!(res.output, "dropped users");
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ToolFilter(Arc<dyn Fn(&Tool) -> bool + Send + Sync>);
+
+impl std::fmt::Debug for ToolFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ToolFilter")
+    }
+}
+
+impl ToolFilter {
+    /// Allows the tools for which `predicate` returns `true`.
+    pub fn new<F>(predicate: F) -> Self
+    where
+        F: Fn(&Tool) -> bool + Send + Sync + 'static,
+    {
+        Self(Arc::new(predicate))
+    }
+
+    /// Allows every tool.
+    pub fn all() -> Self {
+        Self::new(|_| true)
+    }
+
+    /// Allows no tools.
+    pub fn none() -> Self {
+        Self::new(|_| false)
+    }
+
+    /// Allows only the named tools.
+    pub fn only<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let names = names.into_iter().map(Into::into).collect::<Vec<String>>();
+        Self::new(move |tool| names.contains(&tool.name))
+    }
+
+    /// Allows every tool except the named ones.
+    pub fn except<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let names = names.into_iter().map(Into::into).collect::<Vec<String>>();
+        Self::new(move |tool| !names.contains(&tool.name))
+    }
+
+    /// Allows only tools with `tag`.
+    pub fn tagged(tag: impl Into<String>) -> Self {
+        let tag = tag.into();
+        Self::new(move |tool| tool.tags.contains(&tag))
+    }
+
+    /// Allows only tools without `tag`.
+    pub fn untagged(tag: impl Into<String>) -> Self {
+        let tag = tag.into();
+        Self::new(move |tool| !tool.tags.contains(&tag))
+    }
+
+    /// Allows the tools allowed by both filters.
+    pub fn and(self, other: ToolFilter) -> Self {
+        Self::new(move |tool| self.allows(tool) && other.allows(tool))
+    }
+
+    /// Whether `tool` is allowed.
+    pub fn allows(&self, tool: &Tool) -> bool {
+        (self.0)(tool)
+    }
+}
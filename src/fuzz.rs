@@ -0,0 +1,640 @@
+//! Fuzzing tools with arguments generated from their parameter schemas.
+//!
+//! A [`Fuzzer`] generates random arguments for each tool of a handler, some conforming to
+//! the tool's parameter schema and some deliberately violating it, and checks that:
+//!
+//! - conforming arguments pass validation,
+//! - arguments that pass validation can be parsed by the tool, which must not panic,
+//! - [`ToolHandler::call_tool`] and [`ToolHandler::call_with_args`] agree on whether a call
+//!   succeeds,
+//! - violating arguments fail validation.
+//!
+//! Every violation is reported as a [`FuzzFinding`]. Generation is seeded, so a run can be
+//! repeated exactly. The tools really run, twice per conforming case, so fuzz tools without
+//! side effects or [stub](ToolHandler::stub) the others. Handlers that coerce or repair
+//! arguments accept some violating arguments by design.
+//!
+//! ```rust
+//! use tool_calling::fuzz::Fuzzer;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Repeats a word
+//! fn repeat(word: String, times: u8) -> String {
+//!     word.repeat(times as usize)
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let report = Fuzzer::new().with_cases(100).run(&ToolHandler::default()).await;
+//!     // `times` is any integer to the schema, but the tool only parses a `u8`
+//!     assert!(!report.is_clean());
+//!     for finding in &report.findings {
+//!         println!("{}", finding);
+//!     }
+//! }
+//! ```
+
+use crate::{ToolError, ToolHandler};
+use serde_json::{json, Map, Number, Value};
+use std::fmt;
+
+/// The number of conforming and of violating cases generated per tool unless configured
+/// otherwise.
+pub const DEFAULT_CASES: usize = 64;
+
+/// What a [`FuzzFinding`] found wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindingKind {
+    /// The tool panicked.
+    Panic,
+    /// Arguments conforming to the schema failed validation.
+    RejectedValid,
+    /// Arguments violating the schema passed validation.
+    AcceptedInvalid,
+    /// Arguments that passed validation could not be parsed by the tool.
+    Unparseable,
+    /// `call_tool` and `call_with_args` disagreed on whether the call succeeds.
+    Inconsistent,
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FindingKind::Panic => "panic",
+            FindingKind::RejectedValid => "valid arguments rejected",
+            FindingKind::AcceptedInvalid => "invalid arguments accepted",
+            FindingKind::Unparseable => "validated arguments not parsed",
+            FindingKind::Inconsistent => "call_tool and call_with_args disagree",
+        })
+    }
+}
+
+/// A problem found while fuzzing a tool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzFinding {
+    /// The name of the tool.
+    pub tool: String,
+    /// What went wrong.
+    pub kind: FindingKind,
+    /// The arguments that triggered it.
+    pub arguments: Value,
+    /// The error, or the results that disagreed.
+    pub message: String,
+}
+
+impl fmt::Display for FuzzFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} for {}: {}",
+            self.tool, self.kind, self.arguments, self.message
+        )
+    }
+}
+
+/// The outcome of [`Fuzzer::run`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FuzzReport {
+    /// The number of cases with arguments conforming to the schema.
+    pub valid_cases: usize,
+    /// The number of cases with arguments violating the schema.
+    pub invalid_cases: usize,
+    /// Everything found wrong, in the order it was found.
+    pub findings: Vec<FuzzFinding>,
+}
+
+impl FuzzReport {
+    /// Whether nothing was found wrong.
+    pub fn is_clean(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// The findings of the tool `name`.
+    pub fn findings_for<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a FuzzFinding> {
+        self.findings
+            .iter()
+            .filter(move |finding| finding.tool == name)
+    }
+}
+
+/// Calls tools with generated arguments and reports what goes wrong; see the
+/// [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Fuzzer {
+    seed: u64,
+    cases: usize,
+    tools: Option<Vec<String>>,
+}
+
+impl Default for Fuzzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fuzzer {
+    /// Creates a fuzzer generating [`DEFAULT_CASES`] cases of each kind per tool, seeded
+    /// with `0`.
+    pub fn new() -> Self {
+        Self {
+            seed: 0,
+            cases: DEFAULT_CASES,
+            tools: None,
+        }
+    }
+
+    /// Seeds generation with `seed`; the same seed generates the same arguments.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Generates `cases` conforming and `cases` violating argument sets per tool.
+    pub fn with_cases(mut self, cases: usize) -> Self {
+        self.cases = cases;
+        self
+    }
+
+    /// Only fuzzes the tools named in `names`.
+    pub fn with_tools<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tools = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Fuzzes the tools of `handler`.
+    pub async fn run(&self, handler: &ToolHandler) -> FuzzReport {
+        let mut rng = Rng::new(self.seed);
+        let mut report = FuzzReport::default();
+        let tools = handler
+            .tools()
+            .filter(|tool| {
+                self.tools
+                    .as_ref()
+                    .is_none_or(|names| names.contains(&tool.name))
+            })
+            .map(|tool| (tool.name.clone(), tool.parameter_schema.clone()))
+            .collect::<Vec<_>>();
+        for (name, schema) in tools {
+            let exact = supported(&schema);
+            for _ in 0..self.cases {
+                let arguments = generate(&schema, &mut rng, 0);
+                report.valid_cases += 1;
+                check_valid(handler, &name, &schema, arguments, exact, &mut report).await;
+            }
+            for _ in 0..self.cases {
+                let valid = generate(&schema, &mut rng, 0);
+                let Some(arguments) = violate(&schema, valid, &mut rng) else {
+                    // Nothing in the schema can be violated
+                    break;
+                };
+                report.invalid_cases += 1;
+                if handler.validate_tool_call(&call(&name, &arguments)).is_ok() {
+                    report.findings.push(FuzzFinding {
+                        tool: name.clone(),
+                        kind: FindingKind::AcceptedInvalid,
+                        arguments,
+                        message: "passed validation".to_string(),
+                    });
+                }
+            }
+        }
+        report
+    }
+}
+
+fn call(name: &str, arguments: &Value) -> Value {
+    json!({ "type": "function", "function": { "name": name, "arguments": arguments } })
+}
+
+// Validate and run conforming arguments both ways, recording what goes wrong
+async fn check_valid(
+    handler: &ToolHandler,
+    name: &str,
+    schema: &Value,
+    arguments: Value,
+    exact: bool,
+    report: &mut FuzzReport,
+) {
+    let mut find = |kind, message: String, arguments: &Value| {
+        report.findings.push(FuzzFinding {
+            tool: name.to_string(),
+            kind,
+            arguments: arguments.clone(),
+            message,
+        })
+    };
+    let payload = call(name, &arguments);
+    if let Err(err) = handler.validate_tool_call(&payload) {
+        // Values for keywords the generator ignores, such as `format`, may rightly fail
+        if exact {
+            find(FindingKind::RejectedValid, err.to_string(), &arguments);
+        }
+        return;
+    }
+    let by_json = handler
+        .call_tool(&payload)
+        .await
+        .map(|result| result.output);
+    let panicked = format!("tool '{}' panicked", name);
+    match &by_json {
+        Err(ToolError::Execution(message)) if message.starts_with(&panicked) => {
+            find(FindingKind::Panic, message.clone(), &arguments)
+        }
+        Err(ToolError::BadArgs(message)) => {
+            find(FindingKind::Unparseable, message.clone(), &arguments)
+        }
+        _ => {}
+    }
+    let Some(positional) = positional(schema, &arguments) else {
+        return;
+    };
+    let by_args = handler.call_with_args(name, &positional).await;
+    let outcome = |result: &Result<String, ToolError>| match result {
+        Ok(_) => "ok",
+        Err(ToolError::NotFound(_)) => "not found",
+        Err(ToolError::BadArgs(_)) => "invalid arguments",
+        Err(ToolError::Execution(_)) => "failed",
+        Err(ToolError::Decode(_)) => "undecodable",
+    };
+    if outcome(&by_json) != outcome(&by_args) {
+        let show = |result: &Result<String, ToolError>| match result {
+            Ok(output) => format!("returned {:?}", output),
+            Err(err) => format!("failed with {}", err),
+        };
+        let message = format!(
+            "call_tool {}, call_with_args {}",
+            show(&by_json),
+            show(&by_args)
+        );
+        find(FindingKind::Inconsistent, message, &arguments);
+    }
+}
+
+// The string arguments `call_with_args` takes for `arguments`, when they can express them:
+// omitted parameters must all come last, and only string parameters may hold strings
+fn positional(schema: &Value, arguments: &Value) -> Option<Vec<String>> {
+    let properties = schema["properties"].as_object()?;
+    let mut values = properties
+        .iter()
+        .map(|(name, property)| (property, arguments.get(name)))
+        .collect::<Vec<_>>();
+    while values.last().is_some_and(|(_, value)| value.is_none()) {
+        values.pop();
+    }
+    values
+        .into_iter()
+        .map(|(property, value)| match value? {
+            Value::String(text) if allows(property, "string") => Some(text.clone()),
+            Value::String(_) => None,
+            value => Some(value.to_string()),
+        })
+        .collect()
+}
+
+// Whether `schema` declares `kind` among its types
+fn allows(schema: &Value, kind: &str) -> bool {
+    match &schema["type"] {
+        Value::String(t) => t == kind,
+        Value::Array(types) => types.iter().any(|t| t == kind),
+        _ => false,
+    }
+}
+
+// The keywords `generate` honours; values for schemas using others may not conform
+const SUPPORTED: [&str; 20] = [
+    "type",
+    "enum",
+    "const",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "minLength",
+    "maxLength",
+    "items",
+    "minItems",
+    "maxItems",
+    "properties",
+    "required",
+    "additionalProperties",
+    "anyOf",
+    "description",
+    "title",
+    "default",
+    "examples",
+];
+
+fn supported(schema: &Value) -> bool {
+    let Some(schema) = schema.as_object() else {
+        return schema.is_boolean();
+    };
+    schema.iter().all(|(keyword, value)| {
+        SUPPORTED.contains(&keyword.as_str())
+            && match keyword.as_str() {
+                "items" | "additionalProperties" => supported(value),
+                "properties" => value
+                    .as_object()
+                    .is_some_and(|properties| properties.values().all(supported)),
+                "anyOf" => value
+                    .as_array()
+                    .is_some_and(|branches| branches.iter().all(supported)),
+                _ => true,
+            }
+    })
+}
+
+// Past this depth arrays and objects are generated as small as allowed
+const MAX_DEPTH: usize = 4;
+
+const INTEGERS: [i64; 20] = [
+    0,
+    1,
+    -1,
+    2,
+    7,
+    42,
+    127,
+    128,
+    -129,
+    255,
+    256,
+    65535,
+    65536,
+    i32::MAX as i64,
+    i32::MIN as i64,
+    i32::MAX as i64 + 1,
+    u32::MAX as i64,
+    u32::MAX as i64 + 1,
+    i64::MAX,
+    i64::MIN,
+];
+
+const FLOATS: [f64; 8] = [0.0, 0.5, -1.5, 3.0, 1e-10, -2.5e8, 1e300, f64::MIN_POSITIVE];
+
+const STRINGS: [&str; 10] = [
+    "",
+    "a",
+    "null",
+    "42",
+    "true",
+    "{}",
+    "[1,2]",
+    " ",
+    "\"quoted\"",
+    "line\nbreak",
+];
+
+const CHARS: [char; 16] = [
+    'a', 'b', 'z', 'A', 'Q', '0', '7', ' ', '-', '_', '"', '\\', '\n', 'é', '日', '🦀',
+];
+
+// Generate a value conforming to `schema`
+fn generate(schema: &Value, rng: &mut Rng, depth: usize) -> Value {
+    if let Some(options) = schema["enum"].as_array().filter(|o| !o.is_empty()) {
+        return options[rng.below(options.len())].clone();
+    }
+    if let Some(value) = schema.get("const") {
+        return value.clone();
+    }
+    if let Some(branches) = schema["anyOf"].as_array().filter(|b| !b.is_empty()) {
+        return generate(&branches[rng.below(branches.len())], rng, depth);
+    }
+    let kind = match &schema["type"] {
+        Value::String(kind) => kind.as_str(),
+        Value::Array(kinds) if !kinds.is_empty() => {
+            kinds[rng.below(kinds.len())].as_str().unwrap_or("null")
+        }
+        _ if schema.get("properties").is_some() => "object",
+        // Unconstrained: any JSON value
+        _ => [
+            "null", "boolean", "integer", "number", "string", "array", "object",
+        ][rng.below(7)],
+    };
+    match kind {
+        "null" => Value::Null,
+        "boolean" => Value::Bool(rng.below(2) == 0),
+        "integer" => json!(integer(schema, rng)),
+        "number" => number(schema, rng),
+        "string" => Value::String(string(schema, rng)),
+        "array" => {
+            let min = schema["minItems"].as_u64().unwrap_or(0) as usize;
+            let max = schema["maxItems"]
+                .as_u64()
+                .map_or(min + 4, |max| max as usize);
+            let len = if depth >= MAX_DEPTH {
+                min
+            } else {
+                min + rng.below(max.saturating_sub(min) + 1)
+            };
+            let items = schema.get("items").unwrap_or(&Value::Bool(true));
+            (0..len).map(|_| generate(items, rng, depth + 1)).collect()
+        }
+        "object" => {
+            let required = required(schema);
+            let mut object = Map::new();
+            if let Some(properties) = schema["properties"].as_object() {
+                for (name, property) in properties {
+                    if required.contains(&name.as_str()) || rng.below(2) == 0 {
+                        object.insert(name.clone(), generate(property, rng, depth + 1));
+                    }
+                }
+            }
+            if let Some(extra) = schema.get("additionalProperties").filter(|e| e.is_object()) {
+                if depth < MAX_DEPTH {
+                    for i in 0..rng.below(3) {
+                        object.insert(format!("key{}", i), generate(extra, rng, depth + 1));
+                    }
+                }
+            }
+            Value::Object(object)
+        }
+        _ => Value::Null,
+    }
+}
+
+fn required(schema: &Value) -> Vec<&str> {
+    schema["required"]
+        .as_array()
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+fn integer(schema: &Value, rng: &mut Rng) -> i64 {
+    let min = match (
+        schema["minimum"].as_f64(),
+        schema["exclusiveMinimum"].as_f64(),
+    ) {
+        (_, Some(bound)) => Some(bound.floor() as i64 + 1),
+        (Some(bound), None) => Some(bound.ceil() as i64),
+        (None, None) => None,
+    };
+    let max = match (
+        schema["maximum"].as_f64(),
+        schema["exclusiveMaximum"].as_f64(),
+    ) {
+        (_, Some(bound)) => Some(bound.ceil() as i64 - 1),
+        (Some(bound), None) => Some(bound.floor() as i64),
+        (None, None) => None,
+    };
+    // Random values are drawn from a window of 1000 next to a bound, or around zero
+    let low = match (min, max) {
+        (Some(min), _) => min,
+        (None, Some(max)) => max.saturating_sub(1000),
+        (None, None) => -500,
+    };
+    let (min, max) = (min.unwrap_or(i64::MIN), max.unwrap_or(i64::MAX));
+    if min >= max {
+        return min;
+    }
+    match rng.below(4) {
+        // A bound, or a boundary of the common integer types
+        0 => [min, max][rng.below(2)],
+        1 => INTEGERS[rng.below(INTEGERS.len())].clamp(min, max),
+        _ => {
+            let value = low.saturating_add((rng.next() % 1001) as i64);
+            value.clamp(min, max)
+        }
+    }
+}
+
+fn number(schema: &Value, rng: &mut Rng) -> Value {
+    let min = schema["minimum"].as_f64();
+    let max = schema["maximum"].as_f64();
+    let exclusive_min = schema["exclusiveMinimum"].as_f64();
+    let exclusive_max = schema["exclusiveMaximum"].as_f64();
+    if min.or(max).or(exclusive_min).or(exclusive_max).is_none() {
+        return match rng.below(3) {
+            0 => json!(INTEGERS[rng.below(INTEGERS.len())]),
+            _ => json!(FLOATS[rng.below(FLOATS.len())]),
+        };
+    }
+    let low = min.or(exclusive_min).unwrap_or(-1e6);
+    let high = max.or(exclusive_max).unwrap_or(low + 1e6);
+    let mut value = low + (high - low) * (rng.next() % 1000) as f64 / 1000.0;
+    if exclusive_min == Some(value) || exclusive_max == Some(value) {
+        value = (low + high) / 2.0;
+    }
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+fn string(schema: &Value, rng: &mut Rng) -> String {
+    let min = schema["minLength"].as_u64().unwrap_or(0) as usize;
+    let max = schema["maxLength"].as_u64().map(|max| max as usize);
+    if rng.below(3) == 0 {
+        let special = STRINGS[rng.below(STRINGS.len())];
+        let len = special.chars().count();
+        if len >= min && max.is_none_or(|max| len <= max) {
+            return special.to_string();
+        }
+    }
+    let max = max.unwrap_or(min + 12);
+    let len = min + rng.below(max.saturating_sub(min) + 1);
+    (0..len).map(|_| CHARS[rng.below(CHARS.len())]).collect()
+}
+
+// Break `valid` arguments so they no longer conform to `schema`, if it constrains anything
+fn violate(schema: &Value, mut valid: Value, rng: &mut Rng) -> Option<Value> {
+    let object = valid.as_object_mut()?;
+    // Drop a required argument, or give one a value its schema rejects
+    let mut ways = required(schema)
+        .into_iter()
+        .map(|name| (name.to_string(), None))
+        .collect::<Vec<_>>();
+    for (name, property) in schema["properties"].as_object().into_iter().flatten() {
+        if let Some(value) = invalid_value(property, rng) {
+            ways.push((name.clone(), Some(value)));
+        }
+    }
+    if ways.is_empty() {
+        return None;
+    }
+    match ways.swap_remove(rng.below(ways.len())) {
+        (name, None) => object.remove(&name),
+        (name, Some(value)) => object.insert(name, value),
+    };
+    Some(valid)
+}
+
+// A value `schema` rejects, if there is one
+fn invalid_value(schema: &Value, rng: &mut Rng) -> Option<Value> {
+    let mut options = Vec::new();
+    let kinds: Vec<&str> = match &schema["type"] {
+        Value::String(kind) => vec![kind.as_str()],
+        Value::Array(kinds) => kinds.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !kinds.is_empty() && schema.get("anyOf").is_none() {
+        let numeric = kinds.contains(&"number") || kinds.contains(&"integer");
+        let candidates = [
+            ("string", json!(STRINGS[rng.below(STRINGS.len())])),
+            ("boolean", json!(rng.below(2) == 0)),
+            ("null", Value::Null),
+            ("array", json!([1, "two"])),
+            ("object", json!({ "nested": true })),
+            ("number", json!(1.5)),
+            ("integer", json!(INTEGERS[rng.below(INTEGERS.len())])),
+        ];
+        for (kind, value) in candidates {
+            let accepted = kinds.contains(&kind)
+                // Every integer is a number, and whole numbers are integers
+                || (kind == "integer" && numeric)
+                || (kind == "number" && kinds.contains(&"number"));
+            if !accepted {
+                options.push(value);
+            }
+        }
+    }
+    if let Some(allowed) = schema["enum"].as_array() {
+        let outside = json!("\u{0}not one of the options");
+        if !allowed.contains(&outside) {
+            options.push(outside);
+        }
+    }
+    if allows(schema, "integer") || allows(schema, "number") {
+        if let Some(min) = schema["minimum"].as_f64() {
+            options.push(json!(min - 1.0));
+        }
+        if let Some(max) = schema["maximum"].as_f64() {
+            options.push(json!(max + 1.0));
+        }
+    }
+    if allows(schema, "string") && kinds.len() == 1 {
+        if let Some(min) = schema["minLength"].as_u64().filter(|&min| min > 0) {
+            options.push(json!("x".repeat(min as usize - 1)));
+        }
+        if let Some(max) = schema["maxLength"].as_u64() {
+            options.push(json!("x".repeat(max as usize + 1)));
+        }
+    }
+    match options.len() {
+        0 => None,
+        n => Some(options.swap_remove(rng.below(n))),
+    }
+}
+
+// SplitMix64: small, fast and good enough to pick test values
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // A number in `0..n`
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+}
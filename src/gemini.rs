@@ -0,0 +1,112 @@
+//! Google Gemini's function-calling format.
+//!
+//! [`ToolHandler::gemini_tools`] renders the `tools` of a `generateContent` request, with
+//! every tool listed under `functionDeclarations` and its parameters converted to Gemini's
+//! OpenAPI subset. The model asks for tools with `functionCall` parts;
+//! [`ToolHandler::answer_function_calls`] runs each of them and returns the
+//! `functionResponse` parts to send back in the next turn. Outputs are reported under
+//! `output` and failed calls under `error`, with the text of [`ToolHandler::error_message`].
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::{tool, ToolContext, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let handler = ToolHandler::default();
+//!     let declarations = &handler.gemini_tools()[0]["functionDeclarations"];
+//!     assert_eq!(declarations[0]["parameters"]["type"], "OBJECT");
+//!
+//!     let parts = json!([
+//!         { "functionCall": { "name": "add", "args": { "a": 1, "b": 2 } } }
+//!     ]);
+//!     let responses = handler.answer_function_calls(&parts, &ToolContext::default()).await;
+//!     assert_eq!(
+//!         responses,
+//!         vec![json!({ "functionResponse": { "name": "add", "response": { "output": "3" } } })]
+//!     );
+//! }
+//! ```
+
+use crate::{SchemaFlavor, ToolCallResult, ToolContext, ToolError, ToolHandler};
+use serde_json::{json, Value};
+
+/// Converts a part holding a `functionCall` (or the `functionCall` object itself) into the
+/// call payload accepted by [`ToolHandler::call_tool`].
+pub fn parse_function_call(part: &Value) -> Result<Value, ToolError> {
+    let call = part.get("functionCall").unwrap_or(part);
+    let name = call["name"]
+        .as_str()
+        .ok_or_else(|| ToolError::BadArgs("Missing or invalid 'name' field".to_string()))?;
+    let args = match &call["args"] {
+        Value::Null => json!({}),
+        args => args.clone(),
+    };
+    let mut payload =
+        json!({ "type": "function", "function": { "name": name, "arguments": args } });
+    if let Some(id) = call["id"].as_str() {
+        payload["id"] = json!(id);
+    }
+    Ok(payload)
+}
+
+/// A `functionResponse` part answering a call to `name`. `response` must be a JSON object;
+/// Gemini reads a tool's result from its `output` key and a failure from its `error` key.
+pub fn function_response(name: &str, id: Option<&str>, response: Value) -> Value {
+    let mut part = json!({ "functionResponse": { "name": name, "response": response } });
+    if let Some(id) = id {
+        part["functionResponse"]["id"] = json!(id);
+    }
+    part
+}
+
+impl ToolHandler {
+    /// The `tools` of a Gemini request: a single entry listing every tool under
+    /// `functionDeclarations`.
+    pub fn gemini_tools(&self) -> Value {
+        let declarations = self
+            .tools()
+            .map(|tool| tool.render_schema(SchemaFlavor::Gemini))
+            .collect::<Vec<_>>();
+        json!([{ "functionDeclarations": declarations }])
+    }
+
+    /// Runs the call described by a `functionCall` part, as
+    /// [`ToolHandler::call_tool_with_context`] does.
+    pub async fn call_function_call(
+        &self,
+        part: &Value,
+        context: &ToolContext,
+    ) -> Result<ToolCallResult, ToolError> {
+        let call = parse_function_call(part)?;
+        self.call_tool_with_context(&call, context).await
+    }
+
+    /// Runs every `functionCall` in a candidate's content `parts`, concurrently, and returns
+    /// a `functionResponse` part for each, in order. Other parts are skipped.
+    pub async fn answer_function_calls(&self, parts: &Value, context: &ToolContext) -> Vec<Value> {
+        let calls = parts
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|part| part.get("functionCall").is_some());
+        let answers = calls.map(|part| async move {
+            let call = &part["functionCall"];
+            let name = call["name"].as_str().unwrap_or_default();
+            let id = call["id"].as_str();
+            let response = match self.call_function_call(part, context).await {
+                Ok(result) => json!({ "output": result.output }),
+                Err(err) => json!({ "error": self.error_message(&err) }),
+            };
+            function_response(name, id, response)
+        });
+        self.run_batch(answers).await
+    }
+}
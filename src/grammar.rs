@@ -0,0 +1,322 @@
+//! Grammars that constrain a model's output to valid calls of the registered tools.
+//!
+//! Local inference servers can restrict sampling to a grammar, so a model can only write
+//! a call to a tool that exists, with arguments of the right shape.
+//! [`ToolHandler::tool_call_grammar`] generates a llama.cpp GBNF grammar for that, and
+//! [`ToolHandler::tool_call_schema`] a single JSON Schema for servers that take a schema
+//! instead (such as vLLM's guided decoding or llama.cpp's `json_schema`).
+//!
+//! Both describe one `{"name": ..., "arguments": {...}}` object, which
+//! [`ToolHandler::call_tool`] accepts as it is. Object properties are written in schema
+//! order, and optional ones may be left out. Keywords a grammar cannot express well, such
+//! as `pattern`, `format` and numeric bounds, are left to argument validation, which still
+//! runs when the call is made.
+//!
+//! ```rust
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! let handler = ToolHandler::default();
+//! let grammar = handler.tool_call_grammar();
+//! assert!(grammar.starts_with("root ::= add-call\n"));
+//! assert!(grammar.contains(r#"add-args ::= "{" space "\"a\"" space ":" space integer "," space "\"b\"" space ":" space integer "}" space"#));
+//! ```
+
+use crate::ToolHandler;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+// The rules every grammar shares, in the style of llama.cpp's JSON grammars
+const PRIMITIVES: &[(&str, &str)] = &[
+    ("space", r#"| " " | "\n" [ \t]{0,20}"#),
+    ("boolean", r#"("true" | "false") space"#),
+    ("null", r#""null" space"#),
+    ("integral-part", r#"[0] | [1-9] [0-9]{0,15}"#),
+    ("decimal-part", r#"[0-9]{1,16}"#),
+    ("integer", r#"("-"? integral-part) space"#),
+    (
+        "number",
+        r#"("-"? integral-part) ("." decimal-part)? ([eE] [-+]? integral-part)? space"#,
+    ),
+    (
+        "char",
+        r#"[^"\\\x7F\x00-\x1F] | [\\] (["\\bfnrt] | "u" [0-9a-fA-F]{4})"#,
+    ),
+    ("string", r#""\"" char* "\"" space"#),
+    (
+        "value",
+        r#"object | array | string | number | boolean | null"#,
+    ),
+    (
+        "object",
+        r#""{" space (string ":" space value ("," space string ":" space value)*)? "}" space"#,
+    ),
+    (
+        "array",
+        r#""[" space (value ("," space value)*)? "]" space"#,
+    ),
+];
+
+// How deep `$ref`s are followed before falling back to any value, which keeps recursive
+// definitions finite
+const MAX_REF_DEPTH: usize = 8;
+
+struct Grammar<'a> {
+    root: &'a Value,
+    rules: BTreeMap<String, String>,
+    depth: usize,
+}
+
+impl Grammar<'_> {
+    // Adds a rule, renaming it if the name is taken by a different rule
+    fn add(&mut self, name: &str, body: String) -> String {
+        let mut unique = name.to_string();
+        let mut n = 1;
+        while let Some(existing) = self.rules.get(&unique) {
+            if *existing == body {
+                return unique;
+            }
+            n += 1;
+            unique = format!("{}{}", name, n);
+        }
+        self.rules.insert(unique.clone(), body);
+        unique
+    }
+
+    // The expression matching `schema`, adding rules for objects named after `name`
+    fn expression(&mut self, schema: &Value, name: &str) -> String {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            return match self.resolve(reference) {
+                Some(target) if self.depth < MAX_REF_DEPTH => {
+                    self.depth += 1;
+                    let expression = self.expression(&target, name);
+                    self.depth -= 1;
+                    expression
+                }
+                _ => "value".to_string(),
+            };
+        }
+        if let Some(value) = schema.get("const") {
+            return literal(value);
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            return alternatives(values.iter().map(literal).collect());
+        }
+        for keyword in ["anyOf", "oneOf"] {
+            if let Some(options) = schema.get(keyword).and_then(Value::as_array) {
+                let options = options
+                    .iter()
+                    .enumerate()
+                    .map(|(i, option)| self.expression(option, &format!("{}-{}", name, i)))
+                    .collect();
+                return alternatives(options);
+            }
+        }
+        match &schema["type"] {
+            Value::String(kind) => self.typed(schema, kind, name),
+            Value::Array(kinds) => {
+                let options = kinds
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|kind| self.typed(schema, kind, name))
+                    .collect();
+                alternatives(options)
+            }
+            _ if schema.get("properties").is_some() => self.typed(schema, "object", name),
+            _ => "value".to_string(),
+        }
+    }
+
+    fn typed(&mut self, schema: &Value, kind: &str, name: &str) -> String {
+        match kind {
+            "string" | "integer" | "number" | "boolean" | "null" => kind.to_string(),
+            "array" => match schema.get("items").filter(|items| items.is_object()) {
+                Some(items) => {
+                    let item = self.expression(items, &format!("{}-item", name));
+                    let body = format!(r#""[" space ({} ("," space {})*)? "]" space"#, item, item);
+                    self.add(name, body)
+                }
+                None => "array".to_string(),
+            },
+            "object" => match schema.get("properties").and_then(Value::as_object) {
+                Some(properties) if properties.is_empty() => r#""{" space "}" space"#.to_string(),
+                Some(properties) => self.object(schema, properties, name),
+                None => "object".to_string(),
+            },
+            _ => "value".to_string(),
+        }
+    }
+
+    // An object with its properties in schema order, where optional ones may be left out
+    fn object(&mut self, schema: &Value, properties: &Map<String, Value>, name: &str) -> String {
+        let required = schema["required"]
+            .as_array()
+            .map(|required| {
+                required
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let pairs = properties
+            .iter()
+            .map(|(key, property)| {
+                let value = self.expression(property, &format!("{}-{}", name, rule_name(key)));
+                let pair = format!(r#"{} space ":" space {}"#, literal_text(&json!(key)), value);
+                (pair, required.contains(&key.as_str()))
+            })
+            .collect::<Vec<_>>();
+
+        // Sequences of the remaining pairs, built from the last one backwards: `after`
+        // follows a written property, `start` begins the object
+        let mut after = String::new();
+        let mut start = String::new();
+        for (i, (pair, is_required)) in pairs.iter().enumerate().rev() {
+            if !is_required && after.contains(' ') {
+                // Used twice below, so named to keep the grammar linear in size
+                after = self.add(&format!("{}-{}", name, i + 1), after);
+            }
+            let rest = if after.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", after)
+            };
+            let with_comma = format!(r#""," space {}{}"#, pair, rest);
+            let first = format!("{}{}", pair, rest);
+            (after, start) = if *is_required {
+                (with_comma, first)
+            } else if after.is_empty() {
+                (format!("({})?", with_comma), format!("({})?", first))
+            } else {
+                (
+                    format!("({} | {})", with_comma, after),
+                    format!("({} | {})", first, start),
+                )
+            };
+        }
+        let body = format!(r#""{{" space {} "}}" space"#, start);
+        self.add(name, body)
+    }
+
+    fn resolve(&self, reference: &str) -> Option<Value> {
+        let pointer = reference.strip_prefix('#')?;
+        self.root.pointer(pointer).cloned()
+    }
+}
+
+// A GBNF rule name for `text`: letters, digits and dashes
+fn rule_name(text: &str) -> String {
+    let name = text
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>();
+    if name.is_empty() {
+        "x".to_string()
+    } else {
+        name
+    }
+}
+
+// A GBNF literal matching the JSON text of `value`
+fn literal_text(value: &Value) -> String {
+    let text = value.to_string();
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn literal(value: &Value) -> String {
+    format!("{} space", literal_text(value))
+}
+
+fn alternatives(options: Vec<String>) -> String {
+    match options.len() {
+        0 => "value".to_string(),
+        1 => options.into_iter().next().unwrap_or_default(),
+        _ => format!("({})", options.join(" | ")),
+    }
+}
+
+impl ToolHandler {
+    /// A llama.cpp GBNF grammar matching one call to any registered tool, written as
+    /// `{"name": ..., "arguments": {...}}`.
+    ///
+    /// See the [module documentation](crate::grammar) for what the grammar enforces.
+    pub fn tool_call_grammar(&self) -> String {
+        let mut root = Vec::new();
+        let mut rules = BTreeMap::new();
+        for tool in self.tools() {
+            let mut grammar = Grammar {
+                root: &tool.parameter_schema,
+                rules: std::mem::take(&mut rules),
+                depth: 0,
+            };
+            let prefix = rule_name(&tool.name);
+            let mut arguments = tool.parameter_schema.clone();
+            if arguments.get("type").is_none() {
+                arguments["type"] = json!("object");
+            }
+            let args = grammar.expression(&arguments, &format!("{}-args", prefix));
+            let call = format!(
+                r#""{{" space "\"name\"" space ":" space {} "," space "\"arguments\"" space ":" space {} "}}" space"#,
+                literal(&json!(tool.name)),
+                args
+            );
+            root.push(grammar.add(&format!("{}-call", prefix), call));
+            rules = grammar.rules;
+        }
+        let mut out = format!(
+            "root ::= {}\n",
+            if root.is_empty() {
+                "object".to_string()
+            } else {
+                root.join(" | ")
+            }
+        );
+        for (name, body) in &rules {
+            out.push_str(&format!("{} ::= {}\n", name, body));
+        }
+        for (name, body) in PRIMITIVES {
+            out.push_str(&format!("{} ::= {}\n", name, body));
+        }
+        out
+    }
+
+    /// A JSON Schema matching one call to any registered tool, written as
+    /// `{"name": ..., "arguments": {...}}`, with a `oneOf` branch per tool.
+    pub fn tool_call_schema(&self) -> Value {
+        let calls = self
+            .tools()
+            .map(|tool| {
+                let parameters = match self.schema_draft {
+                    Some(draft) => tool.schema_for_draft(draft),
+                    None => tool.parameter_schema.clone(),
+                };
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "name": { "const": tool.name },
+                        "arguments": parameters
+                    },
+                    "required": ["name", "arguments"],
+                    "additionalProperties": false
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({ "oneOf": calls })
+    }
+}
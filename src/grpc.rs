@@ -0,0 +1,179 @@
+//! Serving the tool registry over gRPC.
+//!
+//! A [`ToolRegistryService`] implements the `tool_calling.v1.ToolRegistry` service from
+//! `proto/tool_calling.proto` on top of a [`ToolHandler`]: `ListTools` returns every tool
+//! with its parameter schema, `CallTool` runs a tool and returns its output, and
+//! `CallToolStream` streams the output of `#[tool(stream)]` tools chunk by chunk. Arguments
+//! and schemas travel as JSON strings, so clients in any language can use them as-is.
+//!
+//! Tool errors map onto gRPC status codes: `NOT_FOUND` for unknown tools,
+//! `INVALID_ARGUMENT` for invalid arguments and `INTERNAL` for failed executions, with the
+//! text of [`ToolHandler::error_message`] as the message.
+//!
+//! ```rust,no_run
+//! use tool_calling::grpc::ToolRegistryService;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     ToolRegistryService::new(ToolHandler::default())
+//!         .serve("127.0.0.1:50051".parse().unwrap())
+//!         .await
+//!         .unwrap();
+//! }
+//! ```
+
+use crate::{ToolContext, ToolError, ToolHandler};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use tonic::{Request, Response, Status};
+
+/// Message and service types generated from `proto/tool_calling.proto`.
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("tool_calling.v1");
+}
+
+use proto::tool_registry_server::{ToolRegistry, ToolRegistryServer};
+use proto::{
+    CallToolChunk, CallToolRequest, CallToolResponse, ListToolsRequest, ListToolsResponse,
+};
+
+/// Implements the `ToolRegistry` gRPC service with the tools of a [`ToolHandler`].
+pub struct ToolRegistryService {
+    handler: ToolHandler,
+}
+
+impl ToolRegistryService {
+    /// Creates a service exposing every tool registered on `handler`.
+    pub fn new(handler: ToolHandler) -> Self {
+        Self { handler }
+    }
+
+    /// The handler whose tools are served.
+    pub fn handler(&self) -> &ToolHandler {
+        &self.handler
+    }
+
+    /// Wraps the service for use with a [`tonic::transport::Server`], next to other
+    /// services.
+    pub fn into_server(self) -> ToolRegistryServer<Self> {
+        ToolRegistryServer::new(self)
+    }
+
+    /// Serves the service alone on `addr` until the process exits.
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+        tonic::transport::Server::builder()
+            .add_service(self.into_server())
+            .serve(addr)
+            .await
+    }
+
+    // The handler payload and context of a call request
+    fn parse(&self, request: &CallToolRequest) -> Result<(Value, ToolContext), Status> {
+        let arguments = match request.arguments_json.trim() {
+            "" => json!({}),
+            text => serde_json::from_str::<Value>(text).map_err(|err| {
+                Status::invalid_argument(format!("arguments_json is not valid JSON: {}", err))
+            })?,
+        };
+        let mut call = json!({
+            "type": "function",
+            "function": { "name": request.name, "arguments": arguments }
+        });
+        if !request.id.is_empty() {
+            call["id"] = json!(request.id);
+        }
+        let mut context = ToolContext::new();
+        if !request.user_id.is_empty() {
+            context = context.with_user_id(&request.user_id);
+        }
+        if !request.conversation_id.is_empty() {
+            context = context.with_conversation_id(&request.conversation_id);
+        }
+        Ok((call, context))
+    }
+
+    fn status(&self, err: &ToolError) -> Status {
+        to_status(err, self.handler.error_message(err))
+    }
+}
+
+fn to_status(err: &ToolError, message: String) -> Status {
+    match err {
+        ToolError::NotFound(_) => Status::not_found(message),
+        ToolError::BadArgs(_) => Status::invalid_argument(message),
+        ToolError::Execution(_) | ToolError::Decode(_) => Status::internal(message),
+    }
+}
+
+#[tonic::async_trait]
+impl ToolRegistry for ToolRegistryService {
+    type CallToolStreamStream = futures::stream::BoxStream<'static, Result<CallToolChunk, Status>>;
+
+    async fn list_tools(
+        &self,
+        _request: Request<ListToolsRequest>,
+    ) -> Result<Response<ListToolsResponse>, Status> {
+        let tools = self
+            .handler
+            .tools()
+            .map(|tool| proto::Tool {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                parameters_json: tool.parameter_schema.to_string(),
+                tags: tool.tags.clone(),
+            })
+            .collect();
+        Ok(Response::new(ListToolsResponse { tools }))
+    }
+
+    async fn call_tool(
+        &self,
+        request: Request<CallToolRequest>,
+    ) -> Result<Response<CallToolResponse>, Status> {
+        let (call, context) = self.parse(request.get_ref())?;
+        let result = self
+            .handler
+            .call_tool_with_context(&call, &context)
+            .await
+            .map_err(|err| self.status(&err))?;
+        Ok(Response::new(CallToolResponse {
+            name: result.name,
+            id: result.id.unwrap_or_default(),
+            output: result.output,
+            duration_micros: result.duration.as_micros() as u64,
+            truncated: result.truncated,
+        }))
+    }
+
+    async fn call_tool_stream(
+        &self,
+        request: Request<CallToolRequest>,
+    ) -> Result<Response<Self::CallToolStreamStream>, Status> {
+        let (call, context) = self.parse(request.get_ref())?;
+        let stream = self
+            .handler
+            .call_tool_streaming_with_context(&call, &context)
+            .map_err(|err| self.status(&err))?;
+        // The stream outlives `self`, so it keeps its own handle on the error mapper
+        let mapper = self.handler.error_mapper.clone();
+        let chunks = stream.map(move |chunk| {
+            chunk.map(|chunk| CallToolChunk { chunk }).map_err(|err| {
+                let message = match &mapper {
+                    Some(mapper) => mapper(&err),
+                    None => format!("Error: {}", err),
+                };
+                to_status(&err, message)
+            })
+        });
+        Ok(Response::new(chunks.boxed()))
+    }
+}
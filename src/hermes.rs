@@ -0,0 +1,92 @@
+//! Hermes-style tool calls, written as `<tool_call>{json}</tool_call>` in the content.
+//!
+//! Many local fine-tunes (Hermes, Qwen and others trained on the same template) call tools
+//! by writing a `<tool_call>` block holding a `{"name", "arguments"}` object into their
+//! reply, often after some text. [`extract_tool_calls`] pulls those blocks out as
+//! executable calls and keeps the rest of the text, and [`tool_response`] renders the
+//! `<tool_response>` block that answers a call.
+//!
+//! [`crate::compat::normalize_message`], and so [`crate::ToolHandler::with_tolerant_parsing`],
+//! read these blocks as well.
+//!
+//! ```rust
+//! use tool_calling::hermes::extract_tool_calls;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let handler = ToolHandler::default();
+//!     let reply = "Let me add those.\n<tool_call>\n{\"name\": \"add\", \"arguments\": {\"a\": 1, \"b\": 2}}\n</tool_call>";
+//!     let extracted = extract_tool_calls(reply).unwrap();
+//!     assert_eq!(extracted.text, "Let me add those.");
+//!     let result = handler.call_tool(&extracted.tool_calls[0]).await.unwrap();
+//!     assert_eq!(result.output, "3");
+//! }
+//! ```
+
+use crate::ToolError;
+use serde_json::{json, Value};
+
+const OPEN: &str = "<tool_call>";
+const CLOSE: &str = "</tool_call>";
+
+/// The calls found in a reply, and the text around them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extraction {
+    /// Call payloads accepted by [`crate::ToolHandler::call_tool`], in order.
+    pub tool_calls: Vec<Value>,
+    /// The reply with every `<tool_call>` block removed, trimmed.
+    pub text: String,
+}
+
+/// Extracts every `<tool_call>` block of `content`.
+///
+/// A block left unclosed at the end of the content, as happens when generation stops at
+/// the closing tag, still counts. Content without blocks is returned as text, with no
+/// calls; a block that does not hold a `{"name", "arguments"}` object is an error.
+pub fn extract_tool_calls(content: &str) -> Result<Extraction, ToolError> {
+    let mut tool_calls = Vec::new();
+    let mut text = String::new();
+    let mut rest = content;
+    while let Some(start) = rest.find(OPEN) {
+        text.push_str(&rest[..start]);
+        let block = &rest[start + OPEN.len()..];
+        let (body, after) = match block.find(CLOSE) {
+            Some(end) => (&block[..end], &block[end + CLOSE.len()..]),
+            None => (block, ""),
+        };
+        tool_calls.push(parse_block(body)?);
+        rest = after;
+    }
+    text.push_str(rest);
+    Ok(Extraction {
+        tool_calls,
+        text: text.trim().to_string(),
+    })
+}
+
+/// The `<tool_response>` block answering a call to `name` with `content`, sent back in a
+/// `tool` message.
+pub fn tool_response(name: &str, content: &str) -> String {
+    let response = json!({ "name": name, "content": content });
+    format!("<tool_response>\n{}\n</tool_response>", response)
+}
+
+fn parse_block(body: &str) -> Result<Value, ToolError> {
+    let call: Value = serde_json::from_str(body.trim())
+        .map_err(|err| ToolError::BadArgs(format!("Invalid tool call JSON: {}", err)))?;
+    let name = call["name"]
+        .as_str()
+        .ok_or_else(|| ToolError::BadArgs("Missing or invalid 'name' field".to_string()))?;
+    let arguments = match call.get("arguments").or_else(|| call.get("parameters")) {
+        None | Some(Value::Null) => json!({}),
+        Some(arguments) => arguments.clone(),
+    };
+    Ok(json!({ "type": "function", "function": { "name": name, "arguments": arguments } }))
+}
@@ -0,0 +1,235 @@
+//! Running long tool calls in the background and polling for their results.
+//!
+//! [`ToolHandler::start_job`] validates a call, starts it on the Tokio runtime and returns
+//! at once with a [`JobId`]. While the job runs, [`Job::to_result`] gives a synthetic tool
+//! result telling the model the work is still in progress; once it finishes, the same call
+//! returns the real output.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::jobs::JobStatus;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Render a video, which takes a while
+//! async fn render(scene: String) -> String {
+//!     tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+//!     format!("rendered {}", scene)
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let handler = ToolHandler::default();
+//!     let call = json!({
+//!         "type": "function",
+//!         "id": "call_1",
+//!         "function": { "name": "render", "arguments": { "scene": "intro" } }
+//!     });
+//!     let id = handler.start_job(&call).unwrap();
+//!     assert!(handler.job(id).unwrap().to_result().output.contains("running"));
+//!
+//!     let job = handler.wait_job(id).await.unwrap();
+//!     assert!(matches!(job.status, JobStatus::Succeeded(_)));
+//!     assert_eq!(job.to_result().output, "rendered intro");
+//! }
+//! ```
+
+use crate::{ToolCallResult, ToolContext, ToolError, ToolHandler};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::AbortHandle;
+
+/// Identifies a job started by [`ToolHandler::start_job`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct JobId(pub u64);
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "job-{}", self.0)
+    }
+}
+
+/// The state of a job.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobStatus {
+    /// The tool is still executing.
+    Running,
+    /// The tool finished with this result.
+    Succeeded(ToolCallResult),
+    /// The tool failed with this error.
+    Failed(ToolError),
+    /// The job was stopped with [`ToolHandler::cancel_job`].
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job has stopped, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, JobStatus::Running)
+    }
+}
+
+/// A snapshot of a job.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    /// The job's id.
+    pub id: JobId,
+    /// The name of the tool being executed.
+    pub name: String,
+    /// The call id from the payload's top-level `id` field, if present.
+    pub call_id: Option<String>,
+    /// Time since the job started.
+    pub elapsed: Duration,
+    /// The job's state when the snapshot was taken.
+    pub status: JobStatus,
+}
+
+impl Job {
+    /// The tool result to report to the model for this job.
+    ///
+    /// A succeeded job returns the tool's own result. Otherwise the output is a JSON object
+    /// with the job id and status, such as
+    /// `{"job_id":"job-1","status":"running","message":"..."}`, so the model can be told the
+    /// work is in progress or why it stopped.
+    pub fn to_result(&self) -> ToolCallResult {
+        let (status, message) = match &self.status {
+            JobStatus::Succeeded(result) => return result.clone(),
+            JobStatus::Running => (
+                "running",
+                format!(
+                    "The '{}' tool is still running; its result will be reported when it finishes.",
+                    self.name
+                ),
+            ),
+            JobStatus::Failed(err) => ("failed", err.to_string()),
+            JobStatus::Cancelled => ("cancelled", "The job was cancelled.".to_string()),
+        };
+        let output = json!({ "job_id": self.id.to_string(), "status": status, "message": message });
+        ToolCallResult {
+            name: self.name.clone(),
+            id: self.call_id.clone(),
+            output: output.to_string(),
+            duration: self.elapsed,
+            fallback: None,
+            truncated: false,
+            deduplicated: false,
+            repairs: Vec::new(),
+        }
+    }
+}
+
+struct JobEntry {
+    name: String,
+    call_id: Option<String>,
+    started: Instant,
+    status: watch::Receiver<JobStatus>,
+    abort: AbortHandle,
+}
+
+impl JobEntry {
+    fn snapshot(&self, id: JobId) -> Job {
+        Job {
+            id,
+            name: self.name.clone(),
+            call_id: self.call_id.clone(),
+            elapsed: self.started.elapsed(),
+            status: self.status.borrow().clone(),
+        }
+    }
+}
+
+// Jobs started by a handler and its clones
+#[derive(Default)]
+pub(crate) struct JobRegistry {
+    next_id: AtomicU64,
+    jobs: Mutex<HashMap<JobId, JobEntry>>,
+}
+
+impl ToolHandler {
+    /// Validates a function-call payload and starts executing it in the background.
+    ///
+    /// Bad payloads, unknown tools and invalid arguments are reported immediately; errors
+    /// raised while the tool runs are reported by the job's status. Must be called from
+    /// within a Tokio runtime.
+    pub fn start_job(&self, input: &Value) -> Result<JobId, ToolError> {
+        self.check_visible(input, &ToolContext::default())?;
+        let call = self.parse_tool_call(input, false)?;
+        if self.get_tool(&call.name).is_none() {
+            return Err(ToolError::NotFound(call.name));
+        }
+
+        let id = JobId(self.jobs.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let (sender, receiver) = watch::channel(JobStatus::Running);
+        let handler = self.clone();
+        let input = input.clone();
+        let task = tokio::spawn(async move {
+            let status = match handler.call_tool(&input).await {
+                Ok(result) => JobStatus::Succeeded(result),
+                Err(err) => JobStatus::Failed(err),
+            };
+            let _ = sender.send(status);
+        });
+
+        let entry = JobEntry {
+            name: call.name,
+            call_id: call.id,
+            started: Instant::now(),
+            status: receiver,
+            abort: task.abort_handle(),
+        };
+        self.jobs.jobs.lock().unwrap().insert(id, entry);
+        Ok(id)
+    }
+
+    /// A snapshot of the job, or `None` if no job has this id.
+    pub fn job(&self, id: JobId) -> Option<Job> {
+        let jobs = self.jobs.jobs.lock().unwrap();
+        jobs.get(&id).map(|entry| entry.snapshot(id))
+    }
+
+    /// Snapshots of every job that has not been removed, ordered by id.
+    pub fn jobs(&self) -> Vec<Job> {
+        let jobs = self.jobs.jobs.lock().unwrap();
+        let mut snapshots = jobs
+            .iter()
+            .map(|(id, entry)| entry.snapshot(*id))
+            .collect::<Vec<_>>();
+        snapshots.sort_by_key(|job| job.id);
+        snapshots
+    }
+
+    /// Waits for the job to finish and returns its final snapshot.
+    pub async fn wait_job(&self, id: JobId) -> Option<Job> {
+        let mut status = self.jobs.jobs.lock().unwrap().get(&id)?.status.clone();
+        // The sender only goes away once the final status is sent, or the task is aborted
+        let _ = status.wait_for(JobStatus::is_finished).await;
+        self.job(id)
+    }
+
+    /// Stops a running job. Returns `false` if no job has this id or it already finished.
+    pub fn cancel_job(&self, id: JobId) -> bool {
+        let mut jobs = self.jobs.jobs.lock().unwrap();
+        let Some(entry) = jobs.get_mut(&id) else {
+            return false;
+        };
+        if entry.status.borrow().is_finished() {
+            return false;
+        }
+        entry.abort.abort();
+        let (_, cancelled) = watch::channel(JobStatus::Cancelled);
+        entry.status = cancelled;
+        true
+    }
+
+    /// Forgets the job, returning its last snapshot. A running job keeps running.
+    pub fn remove_job(&self, id: JobId) -> Option<Job> {
+        let entry = self.jobs.jobs.lock().unwrap().remove(&id)?;
+        Some(entry.snapshot(id))
+    }
+}
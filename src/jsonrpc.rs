@@ -0,0 +1,235 @@
+//! Calling tools over JSON-RPC 2.0.
+//!
+//! A [`JsonRpcServer`] treats each request's `method` as a tool name and its `params` as
+//! the tool's arguments, given by name as an object or by position as an array. The tool's
+//! output is the response's `result`, as a string. Batches are answered with an array of
+//! responses, and notifications run the tool without answering.
+//!
+//! Failures become JSON-RPC errors: unknown tools are `-32601` (method not found), invalid
+//! arguments are `-32602` (invalid params) and failed executions are [`EXECUTION_ERROR`],
+//! each with the text of [`ToolHandler::error_message`] as the message and the
+//! [`ToolError::kind`] under `data`.
+//!
+//! [`JsonRpcServer::serve`] reads newline-delimited messages from any reader and writer,
+//! and [`JsonRpcServer::handle_message`] answers single messages for other transports.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::jsonrpc::JsonRpcServer;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let server = JsonRpcServer::new(ToolHandler::default());
+//!     let request = json!({ "jsonrpc": "2.0", "id": 7, "method": "add", "params": [1, 2] });
+//!     assert_eq!(
+//!         server.handle_message(&request).await,
+//!         Some(json!({ "jsonrpc": "2.0", "id": 7, "result": "3" }))
+//!     );
+//! }
+//! ```
+
+use crate::{ToolContext, ToolError, ToolHandler};
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Invalid JSON was received.
+pub const PARSE_ERROR: i64 = -32700;
+/// The message is not a valid request object.
+pub const INVALID_REQUEST: i64 = -32600;
+/// The method does not exist.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The parameters are invalid for the method.
+pub const INVALID_PARAMS: i64 = -32602;
+/// The tool's output could not be decoded.
+pub const INTERNAL_ERROR: i64 = -32603;
+/// The tool failed while running; a code from the range reserved for server errors.
+pub const EXECUTION_ERROR: i64 = -32000;
+
+/// A hook building the [`ToolContext`] of a request from the request message.
+pub type JsonRpcContextFn = Arc<dyn Fn(&Value) -> ToolContext + Send + Sync>;
+
+/// Answers JSON-RPC requests by calling the tools of a [`ToolHandler`].
+pub struct JsonRpcServer {
+    handler: ToolHandler,
+    context: JsonRpcContextFn,
+}
+
+impl JsonRpcServer {
+    /// Creates a server exposing every tool registered on `handler` as a method.
+    pub fn new(handler: ToolHandler) -> Self {
+        Self {
+            handler,
+            context: Arc::new(|_| ToolContext::default()),
+        }
+    }
+
+    /// Builds the [`ToolContext`] of each call from its request message. By default calls
+    /// get an empty context.
+    pub fn with_context<F>(mut self, context: F) -> Self
+    where
+        F: Fn(&Value) -> ToolContext + Send + Sync + 'static,
+    {
+        self.context = Arc::new(context);
+        self
+    }
+
+    /// The handler whose tools are served.
+    pub fn handler(&self) -> &ToolHandler {
+        &self.handler
+    }
+
+    /// Serves requests read from stdin, writing responses to stdout, until stdin closes.
+    pub async fn serve_stdio(&self) -> std::io::Result<()> {
+        let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+        self.serve(stdin, tokio::io::stdout()).await
+    }
+
+    /// Serves newline-delimited messages from `reader`, writing one line per response to
+    /// `writer`, until `reader` is exhausted. Messages are answered in the order they arrive.
+    pub async fn serve<R, W>(&self, reader: R, mut writer: W) -> std::io::Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_str(&line).await {
+                let mut line = response;
+                line.push('\n');
+                writer.write_all(line.as_bytes()).await?;
+                writer.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answers a serialized message, returning the serialized response, if any.
+    pub async fn handle_str(&self, message: &str) -> Option<String> {
+        let response = match serde_json::from_str::<Value>(message) {
+            Ok(message) => self.handle_message(&message).await,
+            Err(err) => Some(error_response(Value::Null, PARSE_ERROR, err.to_string())),
+        };
+        response.map(|response| response.to_string())
+    }
+
+    /// Answers a request, notification or batch. Returns `None` when nothing needs an
+    /// answer: for notifications, and for batches made only of notifications.
+    pub async fn handle_message(&self, message: &Value) -> Option<Value> {
+        match message {
+            Value::Array(batch) if batch.is_empty() => {
+                Some(error_response(Value::Null, INVALID_REQUEST, "Empty batch"))
+            }
+            Value::Array(batch) => {
+                let responses = self
+                    .handler
+                    .run_batch(batch.iter().map(|m| self.handle_one(m)))
+                    .await
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>();
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            message => self.handle_one(message).await,
+        }
+    }
+
+    async fn handle_one(&self, message: &Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let Some(method) = message["method"]
+            .as_str()
+            .filter(|_| message["jsonrpc"] == "2.0")
+        else {
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "Invalid request",
+            ));
+        };
+        let context = (self.context)(message);
+        let outcome = self.call(method, &message["params"], &context).await;
+        let id = id?;
+        Some(match outcome {
+            Ok(output) => json!({ "jsonrpc": "2.0", "id": id, "result": output }),
+            Err(err) => {
+                let code = match err {
+                    ToolError::NotFound(_) => METHOD_NOT_FOUND,
+                    ToolError::BadArgs(_) => INVALID_PARAMS,
+                    ToolError::Execution(_) => EXECUTION_ERROR,
+                    ToolError::Decode(_) => INTERNAL_ERROR,
+                };
+                let mut response = error_response(id, code, self.handler.error_message(&err));
+                response["error"]["data"] = json!({ "kind": err.kind() });
+                response
+            }
+        })
+    }
+
+    async fn call(
+        &self,
+        method: &str,
+        params: &Value,
+        context: &ToolContext,
+    ) -> Result<String, ToolError> {
+        let arguments = match params {
+            Value::Null => json!({}),
+            Value::Object(_) => params.clone(),
+            // Positional parameters follow the order of the tool's schema properties
+            Value::Array(values) => {
+                let tool = self
+                    .handler
+                    .get_tool(method)
+                    .ok_or_else(|| ToolError::NotFound(method.to_string()))?;
+                let names = tool
+                    .parameter_schema
+                    .get("properties")
+                    .and_then(Value::as_object)
+                    .map(|props| props.keys().cloned().collect::<Vec<_>>())
+                    .unwrap_or_default();
+                if values.len() > names.len() {
+                    return Err(ToolError::BadArgs(format!(
+                        "Expected at most {} positional parameters, got {}",
+                        names.len(),
+                        values.len()
+                    )));
+                }
+                Value::Object(
+                    names
+                        .into_iter()
+                        .zip(values.iter().cloned())
+                        .collect::<Map<_, _>>(),
+                )
+            }
+            _ => {
+                return Err(ToolError::BadArgs(
+                    "'params' must be an object or an array".to_string(),
+                ))
+            }
+        };
+        let call =
+            json!({ "type": "function", "function": { "name": method, "arguments": arguments } });
+        self.handler
+            .call_tool_with_context(&call, context)
+            .await
+            .map(|result| result.output)
+    }
+}
+
+/// A JSON-RPC 2.0 error response.
+pub fn error_response(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.into() }
+    })
+}
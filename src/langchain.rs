@@ -0,0 +1,45 @@
+//! Tool definitions for Python orchestrators such as LangChain and LlamaIndex.
+//!
+//! [`ToolHandler::langchain_tools`] renders every tool as `{"name", "description",
+//! "args_schema"}`, the JSON form of a LangChain structured tool. `args_schema` is the
+//! parameter schema with the `title`s pydantic would generate, so it can be handed to
+//! LangChain's `StructuredTool.from_function(args_schema=...)` or LlamaIndex's
+//! `FunctionTool` metadata as it is. [`ToolHandler::write_langchain_tools`] writes the list
+//! to a file for the Python side to load, which then calls back into the handler (over
+//! HTTP, JSON-RPC or MCP) to run the tools.
+//!
+//! ```rust
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Get user info from database
+//! fn get_user_info(user_id: u32) -> String {
+//!     format!("user {}", user_id)
+//! }
+//!
+//! let tools = ToolHandler::default().langchain_tools();
+//! assert_eq!(tools[0]["name"], "get_user_info");
+//! assert_eq!(tools[0]["args_schema"]["title"], "get_user_info");
+//! assert_eq!(tools[0]["args_schema"]["properties"]["user_id"]["title"], "User Id");
+//! ```
+
+use crate::{SchemaFlavor, ToolHandler};
+use serde_json::Value;
+use std::path::Path;
+
+impl ToolHandler {
+    /// The definition of every tool, as LangChain tool JSON.
+    pub fn langchain_tools(&self) -> Value {
+        Value::Array(
+            self.tools()
+                .map(|tool| self.render_tool(tool, SchemaFlavor::LangChain))
+                .collect(),
+        )
+    }
+
+    /// Writes [`ToolHandler::langchain_tools`] to `path` as pretty-printed JSON.
+    pub fn write_langchain_tools(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.langchain_tools())?;
+        std::fs::write(path, json)
+    }
+}
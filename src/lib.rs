@@ -1,11 +1,105 @@
-use futures::future::BoxFuture;
+use audit::{AuditSink, PendingAudit, Tee};
+use context::{ProgressReporter, TypeMap};
+use dedup::{leader_outcome, InFlight, Outcome, Slot};
+use embedding::EmbeddingIndex;
+use futures::future::{self, BoxFuture, FutureExt};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use jobs::JobRegistry;
 use jsonschema::JSONSchema;
 use linkme::distributed_slice;
-use once_cell::sync::Lazy;
-use serde::Serialize;
-use serde_json::{json, Value};
+use once_cell::sync::{Lazy, OnceCell};
+use repair::Repair;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use session::SessionStore;
+use std::any::{Any, TypeId};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokens::TokenBudget;
+use tokio::sync::broadcast;
+
+mod accumulator;
+pub mod anthropic;
+pub mod audit;
+#[cfg(feature = "axum")]
+pub mod axum;
+pub mod bedrock;
+pub mod bench;
+pub mod chat;
+pub mod cli;
+mod coercion;
+pub mod cohere;
+pub mod compat;
+#[cfg(feature = "config")]
+pub mod config;
+mod content;
+mod context;
+pub mod conversation;
+pub mod dataset;
+mod dedup;
+pub mod diff;
+pub mod embedding;
+mod filter;
+pub mod fuzz;
+pub mod gemini;
+pub mod grammar;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hermes;
+pub mod jobs;
+pub mod jsonrpc;
+pub mod langchain;
+pub mod mcp;
+#[cfg(feature = "prometheus")]
+pub mod metrics;
+pub mod mistral;
+#[cfg(feature = "ollama")]
+pub mod ollama;
+#[cfg(feature = "openai")]
+pub mod openai;
+pub mod openapi;
+pub mod pipeline;
+pub mod plugin;
+#[cfg(feature = "proptest")]
+pub mod proptest;
+pub mod pythonic;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod repair;
+#[cfg(feature = "repl")]
+pub mod repl;
+#[cfg(feature = "sandbox")]
+pub mod sandbox;
+mod schema;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod session;
+pub mod structured;
+pub mod tenant;
+pub mod testing;
+pub mod tokens;
+mod truncate;
+pub mod typescript;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use accumulator::ToolCallAccumulator;
+pub use coercion::Coercion;
+pub use content::ToolResultContent;
+#[doc(hidden)]
+pub use context::__inject;
+pub use context::{Inject, ProgressEvent, ToolContext};
+pub use filter::ToolFilter;
+#[cfg(feature = "repl")]
+pub use repl::repl;
+pub use schema::{SchemaDraft, SchemaFlavor};
+pub use truncate::{OutputLimit, TruncationStrategy};
+
 /// Attribute to specify handling of `Option<T>` parameters in tools.
 ///
 /// You can manually unwrap `Option<T>` parameters using `unwrap_or` or `unwrap_or_else`.
@@ -56,7 +150,7 @@ pub use tool_calling_macros::tool;
 ///     assert_eq!(err, ToolError::NotFound("unknown".to_string()));
 /// }
 /// ```
-#[derive(Debug, thiserror::Error, PartialEq)]
+#[derive(Debug, Clone, thiserror::Error, PartialEq, Serialize, Deserialize)]
 pub enum ToolError {
     #[error("tool not found: {0}")]
     NotFound(String),
@@ -64,59 +158,133 @@ pub enum ToolError {
     BadArgs(String),
     #[error("execution failed: {0}")]
     Execution(String),
+    #[error("failed to decode tool output: {0}")]
+    Decode(String),
+}
+
+impl ToolError {
+    /// A short machine-readable name for the variant: `not_found`, `bad_args`,
+    /// `execution` or `decode`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ToolError::NotFound(_) => "not_found",
+            ToolError::BadArgs(_) => "bad_args",
+            ToolError::Execution(_) => "execution",
+            ToolError::Decode(_) => "decode",
+        }
+    }
+
+    /// The message carried by the error, without the variant's prefix.
+    pub fn message(&self) -> &str {
+        match self {
+            ToolError::NotFound(msg)
+            | ToolError::BadArgs(msg)
+            | ToolError::Execution(msg)
+            | ToolError::Decode(msg) => msg,
+        }
+    }
 }
 
+/// The future returned by an async tool function.
+pub type ToolFuture = BoxFuture<'static, Result<String, ToolError>>;
+
+/// A stream of incremental output chunks produced by a streaming tool.
+pub type ToolStream = BoxStream<'static, Result<String, ToolError>>;
+
+/// A boxed async tool function, as stored in [`ToolFn::Async`].
+pub type AsyncToolFn = Box<dyn Fn(Vec<Value>) -> ToolFuture + Send + Sync>;
+
+/// A boxed streaming tool function, as stored in [`ToolFn::Stream`].
+pub type StreamToolFn = Box<dyn Fn(Vec<Value>) -> ToolStream + Send + Sync>;
+
+/// A comparison function used to order a handler's tools.
+pub type ToolOrdering = Arc<dyn Fn(&Tool, &Tool) -> std::cmp::Ordering + Send + Sync>;
+
+/// A hook choosing the tools a request may see, as set with [`ToolHandler::with_tool_filter`].
+pub type ToolFilterHook = Arc<dyn Fn(&ToolContext) -> ToolFilter + Send + Sync>;
+
+/// A hook turning a failed call into the message sent back to the model, as set with
+/// [`ToolHandler::with_error_mapper`].
+pub type ErrorMapper = Arc<dyn Fn(&ToolError) -> String + Send + Sync>;
+
+/// A synchronous tool function accepted by [`wrap_sync`].
+pub type SyncToolFn = Arc<dyn Fn(&[Value]) -> Result<String, ToolError> + Send + Sync>;
+
 /// Represents the wrapped function of a tool, always async.
 ///
-/// The `Async` variant holds a boxed async function that takes string arguments and returns a `Result<String, ToolError>`.
+/// Tool functions receive their arguments as JSON values in parameter order, with
+/// omitted optional parameters passed as `null` (or left off the end). The arguments are
+/// passed by value, so a tool can move them into its future or stream without copying.
+///
+/// The `Async` variant holds a boxed async function that takes those arguments and returns a `Result<String, ToolError>`.
+/// The `Stream` variant holds a function returning a [`ToolStream`] of output chunks, used by tools
+/// declared with `#[tool(stream)]`.
 pub enum ToolFn {
-    Async(Box<dyn Fn(&[String]) -> BoxFuture<'static, Result<String, ToolError>> + Send + Sync>),
+    Async(AsyncToolFn),
+    Stream(StreamToolFn),
 }
 
 // Implement Debug manually as Box<dyn Fn...> doesn't auto-derive Debug
 impl std::fmt::Debug for ToolFn {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("Async").field(&"...").finish() // Don't print the function itself
+        // Don't print the function itself
+        match self {
+            ToolFn::Async(_) => f.debug_tuple("Async").field(&"...").finish(),
+            ToolFn::Stream(_) => f.debug_tuple("Stream").field(&"...").finish(),
+        }
     }
 }
 
 /// Wraps a synchronous tool function into the async tool function signature.
 ///
-/// This helper catches panics and converts them into a `ToolError::Execution` if the tool panics.
+/// Panics are not caught here: [`ToolHandler`] catches a panicking tool, sync or async,
+/// and reports it as a `ToolError::Execution` naming the tool and the panic message.
 ///
 /// # Examples
 ///
 /// ```rust
 /// use tool_calling::{wrap_sync, ToolError};
+/// use serde_json::{json, Value};
 /// use std::sync::Arc;
 /// use futures::executor::block_on;
 ///
-/// let sync_fn = Arc::new(|args: &[String]| -> Result<String, ToolError> {
-///     Ok(args.join(","))
+/// let sync_fn = Arc::new(|args: &[Value]| -> Result<String, ToolError> {
+///     Ok(args.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(","))
 /// });
 /// let async_fn = wrap_sync(sync_fn);
-/// let result = block_on(async_fn(&vec!["a".into(), "b".into()])).unwrap();
-/// assert_eq!(result, "a,b");
+/// let result = block_on(async_fn(vec![json!("a"), json!(1)])).unwrap();
+/// assert_eq!(result, "\"a\",1");
 /// ```
-pub fn wrap_sync(
-    f: Arc<dyn Fn(&[String]) -> Result<String, ToolError> + Send + Sync>,
-) -> Box<dyn Fn(&[String]) -> BoxFuture<'static, Result<String, ToolError>> + Send + Sync> {
-    // Wrap synchronous function to catch panics and return Execution error
+pub fn wrap_sync(f: SyncToolFn) -> AsyncToolFn {
     Box::new(move |args| {
         let f_clone = Arc::clone(&f);
-        let owned_args = args.to_vec();
-        Box::pin(async move {
-            match catch_unwind(AssertUnwindSafe(|| f_clone(&owned_args))) {
-                Ok(res) => res,
-                Err(_) => Err(ToolError::Execution("panic in tool".into())),
-            }
-        })
+        Box::pin(async move { f_clone(&args) })
+    })
+}
+
+// Used by `#[tool]` to parse an argument moved out of the call, so its strings, arrays and
+// objects are never copied
+#[doc(hidden)]
+pub fn __parse_argument<T: DeserializeOwned>(value: Value) -> Result<T, serde_json::Error> {
+    serde_json::from_value(value)
+}
+
+// Describe a caught panic, including its message when the payload is a string
+fn panic_error(tool: &str, payload: Box<dyn Any + Send>) -> ToolError {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned());
+    ToolError::Execution(match message {
+        Some(message) => format!("tool '{}' panicked: {}", tool, message),
+        None => format!("tool '{}' panicked", tool),
     })
 }
 
 /// Represents metadata for a registered tool function.
 ///
 /// Contains its name, description, parameter schema, and the execution function.
+/// Tools are normally generated by `#[tool]`; use [`Tool::new`] to build one at runtime.
 #[derive(Serialize, Debug)]
 pub struct Tool {
     /// The unique name of the tool.
@@ -125,20 +293,291 @@ pub struct Tool {
     pub description: String,
     /// JSON Schema describing tool parameters.
     pub parameter_schema: Value,
+    /// Keywords used by [`ToolHandler::find_tools`], set with `#[tool(tags(...))]`.
+    pub tags: Vec<String>,
+    /// Parameters whose values are masked in error messages and audit records, set with
+    /// `#[param(secret)]`.
+    #[serde(skip)]
+    pub secret_params: Vec<String>,
     /// The internal function pointer for executing the tool. Not serialized.
     #[serde(skip)]
     pub function: ToolFn,
+    // Compiled on first validation and reused for every later call: the schema as written,
+    // then the schema rewritten for each `SchemaDraft`
+    #[serde(skip)]
+    compiled_schemas: [OnceCell<Result<JSONSchema, String>>; 6],
+    // The parameters in schema order, read from `parameter_schema` at registration so calls
+    // don't walk the schema again
+    #[serde(skip)]
+    parameters: OnceCell<Result<Vec<Parameter>, ToolError>>,
+}
+
+// A parameter of a tool, as listed in its schema's `properties`
+#[derive(Debug)]
+struct Parameter {
+    name: String,
+    required: bool,
+    // Whether the schema's `type` allows a string, so string arguments pass through as is
+    accepts_string: bool,
+}
+
+impl Tool {
+    /// Creates a tool from its parts, with no tags.
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameter_schema: Value,
+        function: ToolFn,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            parameter_schema,
+            tags: Vec::new(),
+            secret_params: Vec::new(),
+            function,
+            compiled_schemas: Default::default(),
+            parameters: OnceCell::new(),
+        }
+    }
+
+    // Returns the parameters in schema order, reading them on first use
+    fn parameters(&self) -> Result<&[Parameter], ToolError> {
+        self.parameters
+            .get_or_init(|| {
+                let schema = self.parameter_schema.as_object().ok_or_else(|| {
+                    ToolError::Execution(format!(
+                        "Invalid parameter schema format for tool '{}'",
+                        self.name
+                    ))
+                })?;
+                let required = schema
+                    .get("required")
+                    .and_then(|r| r.as_array())
+                    .ok_or_else(|| {
+                        ToolError::Execution(format!(
+                            "Schema for tool '{}' missing 'required' array",
+                            self.name
+                        ))
+                    })?;
+                let properties = schema
+                    .get("properties")
+                    .and_then(|p| p.as_object())
+                    .ok_or_else(|| {
+                        ToolError::Execution(format!(
+                            "Schema for tool '{}' missing 'properties'",
+                            self.name
+                        ))
+                    })?;
+                Ok(properties
+                    .iter()
+                    .map(|(name, property)| Parameter {
+                        name: name.clone(),
+                        required: required.iter().any(|r| r == name.as_str()),
+                        accepts_string: match property.get("type") {
+                            Some(Value::String(t)) => t == "string",
+                            Some(Value::Array(ts)) => ts.iter().any(|t| t == "string"),
+                            _ => false,
+                        },
+                    })
+                    .collect())
+            })
+            .as_ref()
+            .map(Vec::as_slice)
+            .map_err(Clone::clone)
+    }
+
+    // Returns the validator for `parameter_schema`, compiling it on first use. Without a
+    // draft the schema is used as written, with Draft 7 semantics.
+    fn compiled_schema(&self, draft: Option<SchemaDraft>) -> Result<&JSONSchema, ToolError> {
+        let slot = draft.map_or(0, |draft| draft as usize + 1);
+        self.compiled_schemas[slot]
+            .get_or_init(|| self.compile_schema(draft))
+            .as_ref()
+            .map_err(|e| {
+                ToolError::Execution(format!(
+                    "Failed to compile schema for tool '{}': {}",
+                    self.name, e
+                ))
+            })
+    }
+
+    // Compiles a fresh validator for `parameter_schema`, bypassing the cache
+    fn compile_schema(&self, draft: Option<SchemaDraft>) -> Result<JSONSchema, String> {
+        let schema = match draft {
+            Some(draft) => Cow::Owned(self.schema_for_draft(draft)),
+            None => Cow::Borrowed(&self.parameter_schema),
+        };
+        JSONSchema::options()
+            .with_draft(draft.unwrap_or(SchemaDraft::Draft7).validator())
+            .compile(&schema)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Sets the tool's search tags.
+    pub fn with_tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Marks parameters as secret, so their values never appear in error messages or
+    /// audit records.
+    pub fn with_secret_params<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.secret_params = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether `param` is marked as secret.
+    pub fn is_secret(&self, param: &str) -> bool {
+        self.secret_params.iter().any(|secret| secret == param)
+    }
+
+    /// Returns `arguments` with the values of secret parameters replaced by [`REDACTED`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use tool_calling::{tool, ToolHandler, REDACTED};
+    ///
+    /// #[tool]
+    /// fn login(user: String, #[param(secret)] password: String) -> String {
+    ///     format!("welcome, {}", user)
+    /// }
+    ///
+    /// let handler = ToolHandler::default();
+    /// let tool = handler.get_tool("login").unwrap();
+    /// let args = json!({ "user": "ada", "password": "hunter2" });
+    /// let shown = tool.redact(args.as_object().unwrap());
+    /// assert_eq!(shown["password"], REDACTED);
+    /// assert_eq!(shown["user"], "ada");
+    /// ```
+    pub fn redact(&self, arguments: &Map<String, Value>) -> Map<String, Value> {
+        arguments
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.is_secret(name) {
+                    Value::String(REDACTED.into())
+                } else {
+                    value.clone()
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+
+    // Mask the JSON form of every secret argument that appears in `message`
+    fn redact_message(&self, arguments: &Map<String, Value>, message: String) -> String {
+        arguments
+            .iter()
+            .filter(|(name, _)| self.is_secret(name))
+            .fold(message, |message, (_, value)| {
+                message.replace(&value.to_string(), REDACTED)
+            })
+    }
+}
+
+/// The text that replaces the values of secret parameters.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// A `#[tool]` function's name and the function building its [`Tool`], as collected in
+/// [`TOOL_FACTORIES`].
+#[derive(Debug, Clone, Copy)]
+pub struct ToolFactory {
+    /// The name of the tool.
+    pub name: &'static str,
+    /// Builds the tool.
+    pub build: fn() -> Tool,
 }
 
-// collect all the tool factory functions emitted by the proc-macro
+// collect all the tool factories emitted by the proc-macro
 #[distributed_slice]
-pub static TOOL_FACTORIES: [fn() -> Tool] = [..];
+pub static TOOL_FACTORIES: [ToolFactory] = [..];
+
+// Each factory's tool, built on first lookup, in the order of `TOOL_FACTORIES`. Handlers
+// share these rather than building their own
+static BUILT_TOOLS: Lazy<Vec<OnceCell<Arc<Tool>>>> =
+    Lazy::new(|| TOOL_FACTORIES.iter().map(|_| OnceCell::new()).collect());
+
+// The position of each factory in `TOOL_FACTORIES` by tool name
+static TOOL_INDEX: Lazy<HashMap<&'static str, usize>> = Lazy::new(|| {
+    TOOL_FACTORIES
+        .iter()
+        .enumerate()
+        .map(|(i, factory)| (factory.name, i))
+        .collect()
+});
+
+// Every tool, sorted by name, built when first enumerated
+static ALL_TOOLS: Lazy<Vec<&'static Tool>> = Lazy::new(|| {
+    let mut tools: Vec<&'static Tool> = (0..TOOL_FACTORIES.len())
+        .map(|index| built_tool(index).as_ref())
+        .collect();
+    // linkme's collection order is unspecified, so sort for deterministic output
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    tools
+});
+
+fn built_tool(index: usize) -> &'static Arc<Tool> {
+    BUILT_TOOLS[index].get_or_init(|| Arc::new((TOOL_FACTORIES[index].build)()))
+}
+
+// A handler's tool, or one of the `#[tool]`s by its position in `TOOL_FACTORIES`, built on
+// first lookup
+#[derive(Clone)]
+enum ToolSlot {
+    Built(Arc<Tool>),
+    Registry(usize),
+}
+
+impl ToolSlot {
+    fn name(&self) -> &str {
+        match self {
+            ToolSlot::Built(tool) => &tool.name,
+            ToolSlot::Registry(index) => TOOL_FACTORIES[*index].name,
+        }
+    }
+
+    fn tool(&self) -> &Arc<Tool> {
+        match self {
+            ToolSlot::Built(tool) => tool,
+            ToolSlot::Registry(index) => built_tool(*index),
+        }
+    }
+}
 
-// Use once_cell::sync::Lazy for the global tool registry
-static ALL_TOOLS: Lazy<Vec<Tool>> =
-    Lazy::new(|| TOOL_FACTORIES.iter().map(|factory| factory()).collect());
+/// Returns the `#[tool]` named `name`, building it on first lookup without building any
+/// other tool.
+///
+/// # Examples
+///
+/// ```rust
+/// use tool_calling::{get_tool, tool};
+///
+/// #[tool]
+/// /// Says hello
+/// fn hello() -> String {
+///     "hi".to_string()
+/// }
+///
+/// assert_eq!(get_tool("hello").unwrap().description, "Says hello");
+/// assert!(get_tool("goodbye").is_none());
+/// ```
+pub fn get_tool(name: &str) -> Option<&'static Tool> {
+    TOOL_INDEX
+        .get(name)
+        .map(|&index| built_tool(index).as_ref())
+}
 
-/// Returns a slice of all registered tools.
+/// Returns all registered tools, sorted by name, building those not looked up yet.
 ///
 /// Each `Tool` includes its name, description, and parameter schema.
 ///
@@ -155,15 +594,82 @@ static ALL_TOOLS: Lazy<Vec<Tool>> =
 /// let all = tools();
 /// assert!(all.iter().any(|t| t.name == "example_tool"));
 /// ```
-pub fn tools() -> &'static [Tool] {
+pub fn tools() -> &'static [&'static Tool] {
     &ALL_TOOLS
 }
 
+/// A tool call that has been resolved and validated but not yet executed.
+///
+/// Returned by [`ToolHandler::validate_tool_call`].
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ValidatedCall {
+    /// The name of the resolved tool.
+    pub name: String,
+    /// The call id from the payload's top-level `id` field, if present.
+    pub id: Option<String>,
+    /// The validated arguments, keyed by parameter name.
+    pub arguments: serde_json::Map<String, Value>,
+    /// The arguments in parameter order, as passed to the tool function.
+    pub args: Vec<Value>,
+    /// The repairs made to malformed arguments before they parsed.
+    ///
+    /// See [`ToolHandler::with_argument_repair`].
+    pub repairs: Vec<Repair>,
+}
+
+/// The outcome of a tool call made through [`ToolHandler::call_tool`].
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ToolCallResult {
+    /// The name of the tool that was executed.
+    pub name: String,
+    /// The call id from the payload's top-level `id` field, if present.
+    pub id: Option<String>,
+    /// The tool's output.
+    pub output: String,
+    /// Time spent executing the tool, excluding parsing and validation.
+    pub duration: Duration,
+    /// The fallback tool that produced the output after `name` failed, if any.
+    ///
+    /// See [`ToolHandler::with_fallback`].
+    pub fallback: Option<String>,
+    /// Whether the output was cut down to the tool's [`OutputLimit`].
+    ///
+    /// See [`ToolHandler::with_output_limit`].
+    pub truncated: bool,
+    /// Whether the output was shared from an identical call that was already running.
+    ///
+    /// See [`ToolHandler::with_deduplication`].
+    pub deduplicated: bool,
+    /// The repairs made to malformed arguments before they parsed.
+    ///
+    /// See [`ToolHandler::with_argument_repair`].
+    pub repairs: Vec<Repair>,
+}
+
+impl ToolCallResult {
+    /// The output as content items, decoded as [`ToolResultContent::from_output`] does.
+    pub fn content(&self) -> Vec<ToolResultContent> {
+        ToolResultContent::from_output(&self.output)
+    }
+}
+
+// Progress updates buffered per subscriber
+const PROGRESS_CAPACITY: usize = 256;
+
 /// Handler for discovering and invoking registered tools.
 ///
 /// Use `ToolHandler` to list tools, call them by name with arguments,
 /// or execute calls via JSON payloads.
 ///
+/// Each handler owns its own tool list. `ToolHandler::default()` is seeded with every
+/// `#[tool]` function in the binary, each built the first time it is looked up or listed;
+/// `ToolHandler::empty()` starts with none, and tools
+/// can be added or removed at runtime with [`ToolHandler::register`] and
+/// [`ToolHandler::unregister`].
+///
+/// Tools are kept sorted by name, so schemas and listings are identical across builds.
+/// Use [`ToolHandler::with_ordering`] to choose a different order.
+///
 /// # Examples
 ///
 /// ```rust
@@ -177,69 +683,344 @@ pub fn tools() -> &'static [Tool] {
 ///     }
 /// }
 /// ```
+#[derive(Clone)]
 pub struct ToolHandler {
-    // Store a reference to the static tool list instead of cloning
-    // This requires a lifetime, or we can just reference the static Lazy directly in methods.
-    // Let's keep it simple and reference ALL_TOOLS directly in methods that need it.
-    // No need to store anything here if we always use the static.
-    _private: (), // Struct needs at least one field
+    // Tools are shared so handlers can be cloned cheaply and tools handed out by reference
+    tools: Vec<ToolSlot>,
+    // The position of each tool in `tools` by name, rebuilt whenever the list changes
+    index: HashMap<String, usize>,
+    // Tools are ordered by name when unset, which needs no tool built
+    ordering: Option<ToolOrdering>,
+    unknown_args: UnknownArgs,
+    coercion: Coercion,
+    audit: Option<Arc<dyn AuditSink>>,
+    caller_metadata: BTreeMap<String, String>,
+    sessions: Arc<SessionStore>,
+    resources: Arc<TypeMap>,
+    jobs: Arc<JobRegistry>,
+    progress: broadcast::Sender<ProgressEvent>,
+    fallbacks: BTreeMap<String, String>,
+    tool_filter: Option<ToolFilterHook>,
+    embeddings: Option<Arc<EmbeddingIndex>>,
+    token_budget: TokenBudget,
+    metrics: Option<Arc<dyn AuditSink>>,
+    output_limit: Option<OutputLimit>,
+    tool_output_limits: BTreeMap<String, OutputLimit>,
+    error_mapper: Option<ErrorMapper>,
+    // Replacement functions for tools, by name
+    stubs: BTreeMap<String, SyncToolFn>,
+    in_flight: Option<Arc<InFlight>>,
+    // Most calls of a batch run at once, unbounded when unset
+    max_concurrent_calls: Option<usize>,
+    tolerant_parsing: bool,
+    repair_arguments: bool,
+    schema_draft: Option<SchemaDraft>,
 }
 
-// Implement Default using the Lazy static
+// Seed the handler with every `#[tool]` in the binary. The tools are the ones `get_tool`
+// returns, built once per process on first lookup and shared by every handler
 impl Default for ToolHandler {
     fn default() -> Self {
-        // Ensure the Lazy is initialized
-        Lazy::force(&ALL_TOOLS);
-        Self { _private: () }
+        let mut handler = Self::empty();
+        handler.tools = (0..TOOL_FACTORIES.len()).map(ToolSlot::Registry).collect();
+        handler.sort_tools();
+        handler
+    }
+}
+
+// Convert string arguments to JSON values using each parameter's schema type
+fn string_args_to_values(tool: &Tool, args: &[String]) -> Vec<Value> {
+    let parameters = tool.parameters().unwrap_or_default();
+    args.iter()
+        .enumerate()
+        .map(|(i, arg)| {
+            let accepts_string = parameters.get(i).is_some_and(|param| param.accepts_string);
+            if accepts_string {
+                Value::String(arg.clone())
+            } else {
+                serde_json::from_str(arg).unwrap_or_else(|_| Value::String(arg.clone()))
+            }
+        })
+        .collect()
+}
+
+// `normalize_call` for an input that may already be owned, keeping it as is when nothing
+// changes
+fn normalized(input: Cow<'_, Value>) -> Result<Cow<'_, Value>, ToolError> {
+    let owned = match normalize_call(&input)? {
+        Cow::Owned(normalized) => Some(normalized),
+        Cow::Borrowed(_) => None,
+    };
+    Ok(owned.map_or(input, Cow::Owned))
+}
+
+// Take the argument object out of a call already checked to have one, copying it only when
+// the call is borrowed
+fn take_arguments(input: Cow<'_, Value>) -> Map<String, Value> {
+    let arguments = match input {
+        Cow::Owned(mut input) => input
+            .pointer_mut("/function/arguments")
+            .map(Value::take)
+            .unwrap_or_default(),
+        Cow::Borrowed(input) => input["function"]["arguments"].clone(),
+    };
+    match arguments {
+        Value::Object(arguments) => arguments,
+        _ => Map::new(),
     }
 }
 
+// Bring the call shapes OpenAI-compatible servers send into the canonical
+// `{"type": "function", "function": {"name", "arguments"}}` form: a bare `tool_calls[].function`
+// object is wrapped, and `arguments` encoded as a JSON string is decoded
+fn normalize_call(input: &Value) -> Result<Cow<'_, Value>, ToolError> {
+    let mut input = Cow::Borrowed(input);
+    if input.get("type").is_none() && input.get("function").is_none() && input.get("name").is_some()
+    {
+        let mut function = input.as_object().cloned().unwrap_or_default();
+        let mut call = Map::new();
+        call.insert("type".into(), "function".into());
+        if let Some(id) = function.remove("id") {
+            call.insert("id".into(), id);
+        }
+        call.insert("function".into(), Value::Object(function));
+        input = Cow::Owned(Value::Object(call));
+    }
+    if let Some(Value::String(encoded)) = input.pointer("/function/arguments") {
+        let arguments = if encoded.trim().is_empty() {
+            Value::Object(Map::new())
+        } else {
+            serde_json::from_str(encoded)
+                .map_err(|e| ToolError::BadArgs(format!("'arguments' is not valid JSON: {}", e)))?
+        };
+        input.to_mut()["function"]["arguments"] = arguments;
+    }
+    Ok(input)
+}
+
+// The `tool_calls` of an assistant message, of a chat completion response's first choice, or
+// the array itself
+fn tool_calls(message: &Value) -> &[Value] {
+    let calls = match message {
+        Value::Array(_) => message,
+        _ if message.get("choices").is_some() => &message["choices"][0]["message"]["tool_calls"],
+        _ => &message["tool_calls"],
+    };
+    calls.as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+// Relevance of a tool for a set of lowercase search terms; zero means no match
+fn search_score(tool: &Tool, terms: &[String]) -> u32 {
+    let name = tool.name.to_lowercase();
+    let description = tool.description.to_lowercase();
+    terms
+        .iter()
+        .map(|term| {
+            let mut score = 0;
+            if name == *term {
+                score += 10;
+            } else if name.contains(term.as_str()) {
+                score += 5;
+            }
+            if tool.tags.iter().any(|tag| tag.to_lowercase() == *term) {
+                score += 4;
+            } else if tool
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(term.as_str()))
+            {
+                score += 2;
+            }
+            if description.contains(term.as_str()) {
+                score += 1;
+            }
+            score
+        })
+        .sum()
+}
+
+/// Policy for arguments that do not match any parameter of the called tool.
+///
+/// Applies to both entry points: unknown keys in a JSON payload passed to
+/// [`ToolHandler::call_tool`], and surplus positional arguments passed to
+/// [`ToolHandler::call_with_args`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownArgs {
+    /// Reject the call with `ToolError::BadArgs`.
+    #[default]
+    Fail,
+    /// Silently drop the unknown arguments.
+    Strip,
+    /// Drop the unknown arguments and emit a `tracing` warning.
+    Warn,
+}
+
 impl ToolHandler {
-    /// Retrieves a reference to a tool by its name.
+    /// Creates a handler with no tools registered.
     ///
-    /// Returns `None` if no tool with the given name is registered.
-    pub fn get_tool(&self, name: &str) -> Option<&Tool> {
-        // Access the static list directly
-        ALL_TOOLS.iter().find(|tool| tool.name == name)
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use serde_json::{json, Value};
+    /// use tool_calling::{wrap_sync, Tool, ToolFn, ToolHandler};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let shout = Tool::new(
+    ///         "shout",
+    ///         "Uppercase the input",
+    ///         json!({
+    ///             "type": "object",
+    ///             "properties": { "text": { "type": "string" } },
+    ///             "required": ["text"]
+    ///         }),
+    ///         ToolFn::Async(wrap_sync(Arc::new(|args: &[Value]| {
+    ///             Ok(args[0].as_str().unwrap_or_default().to_uppercase())
+    ///         }))),
+    ///     );
+    ///     let handler = ToolHandler::empty().with_tool(shout);
+    ///     let res = handler.call_with_args("shout", &["hi".into()]).await;
+    ///     assert_eq!(res, Ok("HI".into()));
+    /// }
+    /// ```
+    pub fn empty() -> Self {
+        Self {
+            tools: Vec::new(),
+            index: HashMap::new(),
+            ordering: None,
+            unknown_args: UnknownArgs::default(),
+            coercion: Coercion::default(),
+            audit: None,
+            caller_metadata: BTreeMap::new(),
+            sessions: Arc::default(),
+            resources: Arc::default(),
+            jobs: Arc::default(),
+            progress: broadcast::channel(PROGRESS_CAPACITY).0,
+            fallbacks: BTreeMap::new(),
+            tool_filter: None,
+            embeddings: None,
+            token_budget: TokenBudget::default(),
+            metrics: None,
+            output_limit: None,
+            tool_output_limits: BTreeMap::new(),
+            error_mapper: None,
+            stubs: BTreeMap::new(),
+            in_flight: None,
+            max_concurrent_calls: None,
+            tolerant_parsing: false,
+            repair_arguments: false,
+            schema_draft: None,
+        }
     }
 
-    /// Call a tool by name with pre-parsed string arguments.
-    /// All tool calls are inherently async now.
-    pub async fn call_with_args(&self, name: &str, args: &[String]) -> Result<String, ToolError> {
-        let tool = self
-            .get_tool(name)
-            .ok_or_else(|| ToolError::NotFound(name.to_string()))?;
+    /// Registers a tool, replacing and returning any tool with the same name.
+    pub fn register(&mut self, tool: impl Into<Arc<Tool>>) -> Option<Arc<Tool>> {
+        let tool = tool.into();
+        // Read the parameter list now rather than on the first call; a malformed schema
+        // is reported by each call instead
+        let _ = tool.parameters();
+        let previous = match self.index.get(&tool.name) {
+            Some(&idx) => Some(
+                std::mem::replace(&mut self.tools[idx], ToolSlot::Built(tool))
+                    .tool()
+                    .clone(),
+            ),
+            None => {
+                self.tools.push(ToolSlot::Built(tool));
+                None
+            }
+        };
+        self.sort_tools();
+        previous
+    }
+
+    /// Sets the order in which tools are listed and rendered into schemas.
+    ///
+    /// The default orders tools by name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tool_calling::{tool, ToolHandler};
+    ///
+    /// #[tool]
+    /// fn alpha() -> String {
+    ///     "a".into()
+    /// }
+    ///
+    /// #[tool]
+    /// fn beta() -> String {
+    ///     "b".into()
+    /// }
+    ///
+    /// let handler = ToolHandler::default().with_ordering(|a, b| b.name.cmp(&a.name));
+    /// let names: Vec<_> = handler.tools().map(|t| t.name.as_str()).collect();
+    /// assert_eq!(names, vec!["beta", "alpha"]);
+    /// ```
+    pub fn with_ordering<F>(mut self, ordering: F) -> Self
+    where
+        F: Fn(&Tool, &Tool) -> std::cmp::Ordering + Send + Sync + 'static,
+    {
+        self.ordering = Some(Arc::new(ordering));
+        self.sort_tools();
+        self
+    }
 
-        match &tool.function {
-            ToolFn::Async(func) => func(args).await,
+    // Restore the configured order after the tool list changes
+    fn sort_tools(&mut self) {
+        match &self.ordering {
+            Some(ordering) => self.tools.sort_by(|a, b| ordering(a.tool(), b.tool())),
+            None => self.tools.sort_by(|a, b| a.name().cmp(b.name())),
         }
+        self.reindex();
     }
 
-    /// Produce a JSON schema for the LLM describing all available tools
-    pub fn all_tools_schema(&self) -> Value {
-        let funcs: Vec<_> = ALL_TOOLS // Access the static list directly
+    // Rebuild the name index after the tool list changes
+    fn reindex(&mut self) {
+        self.index = self
+            .tools
             .iter()
-            .map(|tool| {
-                json!({
-                    "type": "function",
-                    "function": {
-                        "name": tool.name,
-                        "description": tool.description,
-                        "parameters": tool.parameter_schema
-                    }
-                })
-            })
+            .enumerate()
+            .map(|(idx, tool)| (tool.name().to_string(), idx))
             .collect();
+    }
 
-        Value::Array(funcs)
+    /// Registers a tool, builder style. See [`ToolHandler::register`].
+    pub fn with_tool(mut self, tool: impl Into<Arc<Tool>>) -> Self {
+        self.register(tool);
+        self
     }
 
-    /// Parses a JSON payload and executes the corresponding tool asynchronously.
+    /// Removes and returns the tool with the given name, if registered.
+    pub fn unregister(&mut self, name: &str) -> Option<Arc<Tool>> {
+        let idx = *self.index.get(name)?;
+        let removed = self.tools.remove(idx);
+        self.reindex();
+        Some(removed.tool().clone())
+    }
+
+    /// Keeps only the tools for which `predicate` returns `true`.
+    pub fn retain<F>(&mut self, predicate: F)
+    where
+        F: Fn(&Tool) -> bool,
+    {
+        self.tools.retain(|tool| predicate(tool.tool()));
+        self.reindex();
+    }
+
+    /// Iterates over the tools registered on this handler, building any `#[tool]` not looked
+    /// up yet.
+    pub fn tools(&self) -> impl Iterator<Item = &Tool> + '_ {
+        self.tools.iter().map(|tool| tool.tool().as_ref())
+    }
+
+    /// Sets the policy for unknown or surplus arguments.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use tool_calling::{tool, ToolHandler, ToolError};
+    /// use tool_calling::{tool, ToolHandler, UnknownArgs};
     /// use serde_json::json;
     ///
     /// #[tool]
@@ -249,123 +1030,1380 @@ impl ToolHandler {
     ///
     /// #[tokio::main]
     /// async fn main() {
-    ///     let handler = ToolHandler::default();
+    ///     let handler = ToolHandler::default().with_unknown_args(UnknownArgs::Strip);
     ///     let payload = json!({
     ///         "type": "function",
-    ///         "function": {
-    ///             "name": "add",
-    ///         "arguments": { "a": 1, "b": 2 }
-    ///         }
+    ///         "function": { "name": "add", "arguments": { "a": 1, "b": 2, "c": 3 } }
     ///     });
-    ///     let res = handler.call_tool(&payload).await.unwrap();
-    ///     assert_eq!(res, "3");
+    ///     assert_eq!(handler.call_tool(&payload).await.unwrap().output, "3");
     /// }
     /// ```
-    pub async fn call_tool(&self, input: &Value) -> Result<String, ToolError> {
-        let (name, args) = self.parse_tool_call(input)?;
-        self.call_with_args(&name, &args).await
+    pub fn with_unknown_args(mut self, policy: UnknownArgs) -> Self {
+        self.unknown_args = policy;
+        self
     }
 
-    // Helper method to parse tool calls, validate against schema, and extract ordered args
-    fn parse_tool_call(&self, input: &Value) -> Result<(String, Vec<String>), ToolError> {
-        let obj = input
-            .as_object()
-            .ok_or_else(|| ToolError::BadArgs("Expected JSON object".to_string()))?;
-        let input_type = obj
-            .get("type")
-            .and_then(|t| t.as_str())
-            .ok_or_else(|| ToolError::BadArgs("Missing or invalid 'type' field".to_string()))?;
-        if input_type != "function" {
-            return Err(ToolError::BadArgs(format!(
-                "Invalid input type: expected 'function', got '{}'",
-                input_type
-            )));
-        }
-        let function = obj
-            .get("function")
-            .and_then(|f| f.as_object())
-            .ok_or_else(|| ToolError::BadArgs("Missing or invalid 'function' field".to_string()))?;
-        let name = function
-            .get("name")
-            .and_then(|n| n.as_str())
-            .ok_or_else(|| ToolError::BadArgs("Missing or invalid 'function.name'".to_string()))?;
-        let args_obj = function
-            .get("arguments")
-            .and_then(|a| a.as_object())
-            .ok_or_else(|| {
-                ToolError::BadArgs("Missing or invalid 'arguments' field".to_string())
-            })?;
+    /// Retrieves a reference to a tool by its name.
+    ///
+    /// Returns `None` if no tool with the given name is registered.
+    pub fn get_tool(&self, name: &str) -> Option<&Tool> {
+        self.index
+            .get(name)
+            .map(|&idx| self.tools[idx].tool().as_ref())
+    }
 
-        // --- Schema Validation ---
-        let tool = self
-            .get_tool(name)
-            .ok_or_else(|| ToolError::NotFound(name.to_string()))?;
-        let compiled_schema = JSONSchema::options()
-            .with_draft(jsonschema::Draft::Draft7)
-            .compile(&tool.parameter_schema)
-            .map_err(|e| {
-                ToolError::Execution(format!(
-                    "Failed to compile schema for tool '{}': {}",
-                    name, e
-                ))
-            })?;
-        let input_args_val = Value::Object(args_obj.clone());
-        if let Err(errors) = compiled_schema.validate(&input_args_val) {
-            let error_messages = errors.map(|e| e.to_string()).collect::<Vec<_>>().join("; ");
-            return Err(ToolError::BadArgs(format!(
-                "Argument validation failed for tool '{}': {}",
-                name, error_messages
-            )));
-        }
-        // --- End Schema Validation ---
+    /// Sets the coercion rules applied to JSON arguments before schema validation.
+    ///
+    /// See [`Coercion`] for the available rules.
+    pub fn with_coercion(mut self, coercion: Coercion) -> Self {
+        self.coercion = coercion;
+        self
+    }
 
-        // Prepare lists of required field names
-        let tool_schema_obj = tool.parameter_schema.as_object().ok_or_else(|| {
-            ToolError::Execution(format!(
-                "Invalid parameter schema format for tool '{}'",
-                name
-            ))
-        })?;
-        let required_arr = tool_schema_obj
-            .get("required")
-            .and_then(|r| r.as_array())
-            .ok_or_else(|| {
-                ToolError::Execution(format!(
-                    "Schema for tool '{}' missing 'required' array",
-                    name
-                ))
-            })?;
-        let required_names = required_arr
-            .iter()
-            .filter_map(|v| v.as_str())
-            .collect::<Vec<_>>();
+    /// Records every tool execution into `sink`.
+    ///
+    /// See the [`audit`] module for the available sinks and querying.
+    pub fn with_audit(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.audit = Some(sink);
+        self
+    }
 
-        // Extract arguments in order, only error if a required param is missing
-        let properties = tool_schema_obj
-            .get("properties")
-            .and_then(|p| p.as_object())
-            .ok_or_else(|| {
-                ToolError::Execution(format!("Schema for tool '{}' missing 'properties'", name))
-            })?;
-
-        let mut ordered_args: Vec<String> = Vec::new();
-        for (param_name, _param_schema) in properties {
-            if let Some(val) = args_obj.get(param_name) {
-                let arg_str = match val {
-                    Value::String(s) => s.clone(),
-                    _ => val.to_string(),
-                };
-                ordered_args.push(arg_str);
-            } else if required_names.contains(&param_name.as_str()) {
-                return Err(ToolError::BadArgs(format!(
-                    "Missing argument for parameter '{}'",
-                    param_name
-                )));
-            } else {
-                // Optional parameter omitted: skip pushing
-            }
-        }
+    /// Attaches caller metadata, such as a user or session id, to this handler's audit
+    /// records.
+    ///
+    /// Handlers are cheap to clone, so a clone per caller can carry its own metadata.
+    pub fn with_caller_metadata(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.caller_metadata.insert(key.into(), value.into());
+        self
+    }
 
-        Ok((name.to_string(), ordered_args))
+    /// Uses `store` for conversation sessions, for example to share them between handlers.
+    ///
+    /// See the [`session`] module.
+    pub fn with_session_store(mut self, store: Arc<SessionStore>) -> Self {
+        self.sessions = store;
+        self
+    }
+
+    /// The store holding the sessions of conversations this handler has served.
+    pub fn session_store(&self) -> &Arc<SessionStore> {
+        &self.sessions
+    }
+
+    /// Runs `fallback` with the same arguments when `tool` fails during execution.
+    ///
+    /// The result of a call answered by a fallback keeps the requested tool's name and
+    /// names the fallback in [`ToolCallResult::fallback`]. Fallbacks can have fallbacks of
+    /// their own; if every tool in the chain fails, the call fails with the first tool's
+    /// error. Errors in the payload itself, such as invalid arguments, do not trigger a
+    /// fallback, and neither do streaming calls.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use tool_calling::{tool, ToolHandler};
+    ///
+    /// #[tool]
+    /// fn search_web(query: String) -> String {
+    ///     panic!("network unreachable while searching for {}", query)
+    /// }
+    ///
+    /// #[tool]
+    /// fn search_cache(query: String) -> String {
+    ///     format!("cached results for {}", query)
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let handler = ToolHandler::default().with_fallback("search_web", "search_cache");
+    ///     let call = json!({
+    ///         "type": "function",
+    ///         "function": { "name": "search_web", "arguments": { "query": "rust" } }
+    ///     });
+    ///     let res = handler.call_tool(&call).await.unwrap();
+    ///     assert_eq!(res.output, "cached results for rust");
+    ///     assert_eq!(res.fallback.as_deref(), Some("search_cache"));
+    /// }
+    /// ```
+    pub fn with_fallback(mut self, tool: impl Into<String>, fallback: impl Into<String>) -> Self {
+        self.fallbacks.insert(tool.into(), fallback.into());
+        self
+    }
+
+    /// Cuts the output of every tool down to `limit`.
+    ///
+    /// Applies to [`ToolHandler::call_tool`], [`ToolHandler::call_with_args`] and
+    /// [`ToolHandler::call_with_values`]; [`ToolCallResult::truncated`] reports whether an
+    /// output was cut. Streamed output is not limited. See [`OutputLimit`] for an example.
+    pub fn with_output_limit(mut self, limit: OutputLimit) -> Self {
+        self.output_limit = Some(limit);
+        self
+    }
+
+    /// Cuts the output of `tool` down to `limit`, overriding the handler-wide limit.
+    pub fn with_tool_output_limit(mut self, tool: impl Into<String>, limit: OutputLimit) -> Self {
+        self.tool_output_limits.insert(tool.into(), limit);
+        self
+    }
+
+    /// Sets how failed calls are described to the model by [`ToolHandler::error_message`].
+    ///
+    /// Use it to rephrase errors, return structured JSON, or hide internal details such as
+    /// panic messages.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use tool_calling::{ToolError, ToolHandler};
+    ///
+    /// let handler = ToolHandler::default().with_error_mapper(|err: &ToolError| {
+    ///     let message = match err {
+    ///         ToolError::Execution(_) => "the tool failed; try something else",
+    ///         other => other.message(),
+    ///     };
+    ///     json!({ "error": { "kind": err.kind(), "message": message } }).to_string()
+    /// });
+    ///
+    /// let err = ToolError::Execution("tool 'query' panicked: connection reset".into());
+    /// assert_eq!(
+    ///     handler.error_message(&err),
+    ///     r#"{"error":{"kind":"execution","message":"the tool failed; try something else"}}"#
+    /// );
+    /// ```
+    pub fn with_error_mapper<F>(mut self, mapper: F) -> Self
+    where
+        F: Fn(&ToolError) -> String + Send + Sync + 'static,
+    {
+        self.error_mapper = Some(Arc::new(mapper));
+        self
+    }
+
+    /// The message to send back to the model for a failed call.
+    ///
+    /// Uses the mapper set with [`ToolHandler::with_error_mapper`], or `"Error: {err}"` by
+    /// default.
+    pub fn error_message(&self, err: &ToolError) -> String {
+        match &self.error_mapper {
+            Some(mapper) => mapper(err),
+            None => format!("Error: {}", err),
+        }
+    }
+
+    /// Coalesces identical calls made while one is already running onto that execution.
+    ///
+    /// A call made through [`ToolHandler::call_tool`] or
+    /// [`ToolHandler::call_tool_with_context`] with the same tool, arguments, user id and
+    /// conversation id as a running one waits for it and shares its output or error, with
+    /// [`ToolCallResult::deduplicated`] set. Context extensions are not compared. Clones of
+    /// the handler coalesce with each other. Tools with side effects that must happen once
+    /// per call should not be used with deduplication.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use tool_calling::{tool, ToolHandler};
+    ///
+    /// static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// #[tool]
+    /// async fn lookup(id: u32) -> String {
+    ///     LOOKUPS.fetch_add(1, Ordering::SeqCst);
+    ///     tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    ///     format!("record {}", id)
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let handler = ToolHandler::default().with_deduplication();
+    ///     let call = json!({ "type": "function", "function": { "name": "lookup", "arguments": { "id": 7 } } });
+    ///     let (a, b) = tokio::join!(handler.call_tool(&call), handler.call_tool(&call));
+    ///     assert_eq!(a.unwrap().output, b.unwrap().output);
+    ///     assert_eq!(LOOKUPS.load(Ordering::SeqCst), 1);
+    /// }
+    /// ```
+    pub fn with_deduplication(mut self) -> Self {
+        self.in_flight = Some(Arc::default());
+        self
+    }
+
+    // Apply the output limit of the tool that produced `output`, if any
+    fn limit_output(&self, tool: &str, output: String) -> (String, bool) {
+        match self
+            .tool_output_limits
+            .get(tool)
+            .or(self.output_limit.as_ref())
+        {
+            Some(limit) => limit.apply(output),
+            None => (output, false),
+        }
+    }
+
+    /// Installs a hook choosing, per request, which tools are visible.
+    ///
+    /// The hook receives the request's [`ToolContext`] and returns a [`ToolFilter`].
+    /// Hidden tools are left out of [`ToolHandler::tools_schema_with_context`], and calls to
+    /// them through [`ToolHandler::call_tool_with_context`], [`ToolHandler::call_tool`],
+    /// [`ToolHandler::call_tool_streaming`] and [`ToolHandler::start_job`] fail with
+    /// `ToolError::NotFound`, as if they were not registered. Calls without a context see
+    /// the filter for an empty context. Direct calls by name, such as
+    /// [`ToolHandler::call_with_args`], and the other schema methods are not filtered.
+    ///
+    /// See [`ToolFilter`] for an example.
+    pub fn with_tool_filter<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ToolContext) -> ToolFilter + Send + Sync + 'static,
+    {
+        self.tool_filter = Some(Arc::new(hook));
+        self
+    }
+
+    /// The tools visible to a request, after the hook set with
+    /// [`ToolHandler::with_tool_filter`].
+    pub fn tools_with_context<'a>(
+        &'a self,
+        context: &ToolContext,
+    ) -> impl Iterator<Item = &'a Tool> + 'a {
+        let filter = self.tool_filter.as_ref().map(|hook| hook(context));
+        self.tools()
+            .filter(move |tool| filter.as_ref().is_none_or(|filter| filter.allows(tool)))
+    }
+
+    // Reject payloads naming a tool the request may not see, before validating them
+    pub(crate) fn check_visible(
+        &self,
+        input: &Value,
+        context: &ToolContext,
+    ) -> Result<(), ToolError> {
+        let name = input["function"]["name"]
+            .as_str()
+            .or(input["name"].as_str());
+        let (Some(hook), Some(name)) = (&self.tool_filter, name) else {
+            return Ok(());
+        };
+        match self.get_tool(name) {
+            Some(tool) if !hook(context).allows(tool) => Err(ToolError::NotFound(name.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Subscribes to the progress updates tools report with [`ToolContext::progress`].
+    ///
+    /// Updates from every call made through this handler and its clones are delivered;
+    /// a subscriber that falls more than 256 updates behind misses the oldest ones.
+    /// Updates sent while nobody is subscribed are dropped.
+    pub fn subscribe_progress(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.progress.subscribe()
+    }
+
+    /// Shares `resource` with every tool, replacing an earlier resource of the same type.
+    ///
+    /// Tools receive it through an `#[inject]` parameter of type `Arc<T>`, or from
+    /// [`ToolContext::resource`]. Calls to a tool whose resource was not provided fail with
+    /// `ToolError::Execution`. See [`Inject`].
+    pub fn provide<T: Send + Sync + 'static>(&mut self, resource: T) {
+        self.provide_shared(Arc::new(resource));
+    }
+
+    /// Like [`ToolHandler::provide`], for a resource that is already shared.
+    pub fn provide_shared<T: Send + Sync + 'static>(&mut self, resource: Arc<T>) {
+        Arc::make_mut(&mut self.resources).insert(TypeId::of::<T>(), resource);
+    }
+
+    /// Replaces the execution of the tool `name` with `stub`, leaving it registered.
+    ///
+    /// Calls are still resolved and validated against the tool's schema, then run `stub`
+    /// with the positional arguments instead of the tool, so conversation flows can be
+    /// developed without the services a tool depends on. Replaces any earlier stub.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde_json::{json, Value};
+    /// use tool_calling::{tool, ToolError, ToolHandler};
+    ///
+    /// #[tool]
+    /// /// Get the current weather for a city
+    /// fn get_weather(city: String) -> String {
+    ///     unimplemented!("calls the weather service")
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut handler = ToolHandler::default();
+    ///     handler.stub("get_weather", |args: &[Value]| {
+    ///         Ok(format!("sunny in {}", args[0].as_str().unwrap_or_default()))
+    ///     });
+    ///
+    ///     let call = json!({
+    ///         "type": "function",
+    ///         "function": { "name": "get_weather", "arguments": { "city": "Oslo" } }
+    ///     });
+    ///     assert_eq!(handler.call_tool(&call).await.unwrap().output, "sunny in Oslo");
+    ///
+    ///     let bad = json!({ "type": "function", "function": { "name": "get_weather", "arguments": {} } });
+    ///     assert!(matches!(handler.call_tool(&bad).await, Err(ToolError::BadArgs(_))));
+    /// }
+    /// ```
+    pub fn stub<F>(&mut self, name: impl Into<String>, stub: F)
+    where
+        F: Fn(&[Value]) -> Result<String, ToolError> + Send + Sync + 'static,
+    {
+        self.stubs.insert(name.into(), Arc::new(stub));
+    }
+
+    /// Removes the stub of the tool `name`, returning whether there was one.
+    pub fn unstub(&mut self, name: &str) -> bool {
+        self.stubs.remove(name).is_some()
+    }
+
+    /// Searches tools by keyword, returning matches ranked by relevance.
+    ///
+    /// Each whitespace-separated term in `query` is matched case-insensitively against
+    /// tool names, tags, and descriptions; name matches rank highest, then tags, then
+    /// descriptions. Tools matching no term are omitted; ties are ordered by name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tool_calling::{tool, ToolHandler};
+    ///
+    /// #[tool(tags("math"))]
+    /// /// Adds two integers
+    /// fn add(a: i32, b: i32) -> String {
+    ///     (a + b).to_string()
+    /// }
+    ///
+    /// let handler = ToolHandler::default();
+    /// let found = handler.find_tools("math");
+    /// assert_eq!(found[0].name, "add");
+    /// ```
+    pub fn find_tools(&self, query: &str) -> Vec<&Tool> {
+        let terms = query
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+        let mut scored = self
+            .tools()
+            .map(|tool| (search_score(tool, &terms), tool))
+            .filter(|(score, _)| *score > 0)
+            .collect::<Vec<_>>();
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score.cmp(a_score).then_with(|| a.name.cmp(&b.name))
+        });
+        scored.into_iter().map(|(_, tool)| tool).collect()
+    }
+
+    /// Call a tool by name with pre-parsed string arguments.
+    /// All tool calls are inherently async now.
+    ///
+    /// Each string is converted to a JSON value: parameters whose schema accepts a string
+    /// receive it verbatim, others receive it parsed as JSON (so `"3"` becomes `3` and
+    /// `"[1,2]"` becomes an array). Streaming tools are drained and their chunks
+    /// concatenated into a single result.
+    pub async fn call_with_args(&self, name: &str, args: &[String]) -> Result<String, ToolError> {
+        let tool = self
+            .get_tool(name)
+            .ok_or_else(|| ToolError::NotFound(name.to_string()))?;
+        let values = string_args_to_values(tool, args);
+        let output = self
+            .execute(tool, values, None, &ToolContext::default())
+            .await?;
+        Ok(self.limit_output(name, output).0)
+    }
+
+    /// Call a tool by name with JSON arguments in parameter order.
+    ///
+    /// Unlike [`ToolHandler::call_with_args`] no string conversion takes place, so nested
+    /// arrays and objects reach the tool intact. Arguments are not validated against the
+    /// tool's schema; use [`ToolHandler::call_tool`] for that. A `Vec` of arguments is moved
+    /// to the tool as is; borrowed arguments are copied.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use tool_calling::{tool, ToolHandler};
+    ///
+    /// #[tool]
+    /// fn total(values: Vec<i64>) -> String {
+    ///     values.iter().sum::<i64>().to_string()
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let handler = ToolHandler::default();
+    ///     let res = handler.call_with_values("total", &[json!([1, 2, 3])]).await;
+    ///     assert_eq!(res, Ok("6".into()));
+    /// }
+    /// ```
+    pub async fn call_with_values(
+        &self,
+        name: &str,
+        args: impl Into<Vec<Value>>,
+    ) -> Result<String, ToolError> {
+        let tool = self
+            .get_tool(name)
+            .ok_or_else(|| ToolError::NotFound(name.to_string()))?;
+        let output = self
+            .execute(tool, args.into(), None, &ToolContext::default())
+            .await?;
+        Ok(self.limit_output(name, output).0)
+    }
+
+    // Run a resolved tool to completion
+    async fn execute(
+        &self,
+        tool: &Tool,
+        args: Vec<Value>,
+        call_id: Option<&str>,
+        context: &ToolContext,
+    ) -> Result<String, ToolError> {
+        let context = &self.call_context(context, tool, call_id);
+        let args = self.apply_unknown_args_to_positional(tool, args);
+        let audit = self.start_audit(tool, &args, call_id, context);
+
+        let stub = self.stubs.get(&tool.name);
+        let run = async move {
+            if let Some(stub) = stub {
+                return stub(&args);
+            }
+            match &tool.function {
+                ToolFn::Async(func) => func(args).await,
+                ToolFn::Stream(func) => {
+                    func(args)
+                        .try_fold(String::new(), |mut acc, chunk| async move {
+                            acc.push_str(&chunk);
+                            Ok(acc)
+                        })
+                        .await
+                }
+            }
+        };
+        // Panics while starting or polling the tool fail the call instead of the caller
+        let run = AssertUnwindSafe(context.scope(run)).catch_unwind();
+        let outcome = match context.deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline.into(), run)
+                .await
+                .unwrap_or_else(|_| {
+                    Ok(Err(ToolError::Execution(format!(
+                        "tool '{}' exceeded its deadline",
+                        tool.name
+                    ))))
+                }),
+            None => run.await,
+        };
+        let result = outcome.unwrap_or_else(|payload| Err(panic_error(&tool.name, payload)));
+        if let Some(audit) = audit {
+            audit.finish(&result);
+        }
+        result
+    }
+
+    // Run a resolved tool as a stream of chunks
+    fn execute_streaming(
+        &self,
+        tool: &Tool,
+        args: Vec<Value>,
+        call_id: Option<&str>,
+        context: &ToolContext,
+    ) -> ToolStream {
+        let context = &self.call_context(context, tool, call_id);
+        let args = self.apply_unknown_args_to_positional(tool, args);
+        let audit = self.start_audit(tool, &args, call_id, context);
+
+        let started = catch_unwind(AssertUnwindSafe(|| {
+            context.sync_scope(|| match (self.stubs.get(&tool.name), &tool.function) {
+                (Some(stub), _) => stream::once(future::ready(stub(&args))).boxed(),
+                (None, ToolFn::Async(func)) => stream::once(func(args)).boxed(),
+                (None, ToolFn::Stream(func)) => func(args),
+            })
+        }));
+        let name = tool.name.clone();
+        let stream = match started {
+            Ok(stream) => context
+                .scope_stream(AssertUnwindSafe(stream).catch_unwind())
+                .map(move |item| item.unwrap_or_else(|payload| Err(panic_error(&name, payload))))
+                .boxed(),
+            Err(payload) => stream::once(future::ready(Err(panic_error(&name, payload)))).boxed(),
+        };
+        match audit {
+            Some(audit) => audit.wrap_stream(stream),
+            None => stream,
+        }
+    }
+
+    // Attach the handler's resources, a progress reporter for this call, and the session of
+    // the context's conversation
+    fn call_context(
+        &self,
+        context: &ToolContext,
+        tool: &Tool,
+        call_id: Option<&str>,
+    ) -> ToolContext {
+        let reporter = ProgressReporter::new(self.progress.clone(), &tool.name, call_id, context);
+        let context = context
+            .clone()
+            .with_resources(self.resources.clone())
+            .with_progress(reporter);
+        match &context.conversation_id {
+            Some(id) => {
+                let session = self.sessions.session(id);
+                context.with_session(session)
+            }
+            None => context,
+        }
+    }
+
+    // Caller metadata for the audit log combines the handler's with the call's context
+    fn start_audit(
+        &self,
+        tool: &Tool,
+        args: &[Value],
+        call_id: Option<&str>,
+        context: &ToolContext,
+    ) -> Option<PendingAudit> {
+        // Metrics are recorded through the same path as the audit log
+        let sink = match (&self.audit, &self.metrics) {
+            (Some(audit), Some(metrics)) => Arc::new(Tee(audit.clone(), metrics.clone())),
+            (Some(sink), None) | (None, Some(sink)) => sink.clone(),
+            (None, None) => return None,
+        };
+        let mut metadata = self.caller_metadata.clone();
+        if let Some(user_id) = &context.user_id {
+            metadata.insert("user_id".into(), user_id.clone());
+        }
+        if let Some(conversation_id) = &context.conversation_id {
+            metadata.insert("conversation_id".into(), conversation_id.clone());
+        }
+        Some(PendingAudit::start(&sink, tool, args, call_id, &metadata))
+    }
+
+    /// Call a tool by name and deserialize its JSON output into `T`.
+    ///
+    /// Returns `ToolError::Decode` if the output is not valid JSON for `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tool_calling::{tool, ToolHandler};
+    ///
+    /// #[tool]
+    /// fn range(n: u32) -> String {
+    ///     serde_json::to_string(&(0..n).collect::<Vec<_>>()).unwrap()
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let handler = ToolHandler::default();
+    ///     let values: Vec<u32> = handler.call_typed("range", &["3".into()]).await.unwrap();
+    ///     assert_eq!(values, vec![0, 1, 2]);
+    /// }
+    /// ```
+    pub async fn call_typed<T: DeserializeOwned>(
+        &self,
+        name: &str,
+        args: &[String],
+    ) -> Result<T, ToolError> {
+        let output = self.call_with_args(name, args).await?;
+        serde_json::from_str(&output).map_err(|e| {
+            ToolError::Decode(format!("output of tool '{}' is not valid: {}", name, e))
+        })
+    }
+
+    /// Call a tool by name and receive its output as a stream of chunks.
+    ///
+    /// Streaming tools yield each chunk as it is produced; other tools yield their
+    /// whole result as a single chunk.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures::{Stream, StreamExt};
+    /// use tool_calling::{tool, ToolError, ToolHandler};
+    ///
+    /// #[tool(stream)]
+    /// fn count_to(n: u32) -> impl Stream<Item = Result<String, ToolError>> + Send {
+    ///     futures::stream::iter((1..=n).map(|i| Ok(i.to_string())))
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let handler = ToolHandler::default();
+    ///     let chunks: Vec<_> = handler
+    ///         .call_streaming("count_to", &["3".into()])
+    ///         .unwrap()
+    ///         .collect()
+    ///         .await;
+    ///     assert_eq!(chunks, vec![Ok("1".into()), Ok("2".into()), Ok("3".into())]);
+    /// }
+    /// ```
+    pub fn call_streaming(&self, name: &str, args: &[String]) -> Result<ToolStream, ToolError> {
+        let tool = self
+            .get_tool(name)
+            .ok_or_else(|| ToolError::NotFound(name.to_string()))?;
+        let values = string_args_to_values(tool, args);
+        Ok(self.execute_streaming(tool, values, None, &ToolContext::default()))
+    }
+
+    // Render tools as the JSON array of function definitions sent to the LLM
+    fn tools_schema<'a>(&self, tools: impl Iterator<Item = &'a Tool>) -> Value {
+        let funcs: Vec<_> = tools
+            .map(|tool| self.render_tool(tool, SchemaFlavor::OpenAi))
+            .collect();
+
+        Value::Array(funcs)
+    }
+
+    // Render a tool's definition in the handler's schema draft, if one was chosen
+    pub(crate) fn render_tool(&self, tool: &Tool, flavor: SchemaFlavor) -> Value {
+        match self.schema_draft {
+            Some(draft) => tool.render_schema_for_draft(flavor, draft),
+            None => tool.render_schema(flavor),
+        }
+    }
+
+    /// Produce a JSON schema for the LLM describing all available tools
+    pub fn all_tools_schema(&self) -> Value {
+        self.tools_schema(self.tools())
+    }
+
+    /// Like [`ToolHandler::all_tools_schema`], with each tool compacted for
+    /// token-constrained prompts as described in [`Tool::render_compact_schema`].
+    pub fn all_tools_schema_compact(&self) -> Value {
+        Value::Array(
+            self.tools()
+                .map(|tool| tool.render_compact_schema(SchemaFlavor::OpenAi))
+                .collect(),
+        )
+    }
+
+    /// Like [`ToolHandler::all_tools_schema`], ready to pass as the `tools` of an OpenAI
+    /// request, with strict mode as described in [`Tool::render_openai_schema`].
+    pub fn all_tools_schema_openai(&self, strict: bool) -> Value {
+        Value::Array(
+            self.tools()
+                .map(|tool| tool.render_openai_schema(strict))
+                .collect(),
+        )
+    }
+
+    /// Produce a JSON schema describing the tools visible to a request.
+    ///
+    /// See [`ToolHandler::with_tool_filter`].
+    pub fn tools_schema_with_context(&self, context: &ToolContext) -> Value {
+        self.tools_schema(self.tools_with_context(context))
+    }
+
+    /// Produce a JSON schema describing only the named tools.
+    ///
+    /// Tools appear in registry order; names that are not registered are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tool_calling::{tool, ToolHandler};
+    ///
+    /// #[tool]
+    /// fn add(a: i32, b: i32) -> String {
+    ///     (a + b).to_string()
+    /// }
+    ///
+    /// #[tool]
+    /// fn sub(a: i32, b: i32) -> String {
+    ///     (a - b).to_string()
+    /// }
+    ///
+    /// let handler = ToolHandler::default();
+    /// let schema = handler.tools_schema_for(&["add"]);
+    /// assert_eq!(schema.as_array().unwrap().len(), 1);
+    /// assert_eq!(schema[0]["function"]["name"], "add");
+    /// ```
+    pub fn tools_schema_for(&self, names: &[&str]) -> Value {
+        self.tools_schema_matching(|tool| names.contains(&tool.name.as_str()))
+    }
+
+    /// Produce a JSON schema describing the tools for which `predicate` returns `true`.
+    pub fn tools_schema_matching<F>(&self, predicate: F) -> Value
+    where
+        F: Fn(&Tool) -> bool,
+    {
+        self.tools_schema(self.tools().filter(|tool| predicate(tool)))
+    }
+
+    /// Parses a JSON payload and executes the corresponding tool asynchronously.
+    ///
+    /// The payload is a `{"type": "function", "function": {"name", "arguments"}}` object, as
+    /// found in the `tool_calls` of an OpenAI-style response, or just its inner `function`
+    /// object. `arguments` may be a JSON object or a string encoding one, as OpenAI and most
+    /// compatible servers send it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tool_calling::{tool, ToolHandler, ToolError};
+    /// use serde_json::json;
+    ///
+    /// #[tool]
+    /// fn add(a: i32, b: i32) -> String {
+    ///     (a + b).to_string()
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let handler = ToolHandler::default();
+    ///     let payload = json!({
+    ///         "type": "function",
+    ///         "function": {
+    ///             "name": "add",
+    ///         "arguments": { "a": 1, "b": 2 }
+    ///         }
+    ///     });
+    ///     let res = handler.call_tool(&payload).await.unwrap();
+    ///     assert_eq!(res.name, "add");
+    ///     assert_eq!(res.output, "3");
+    /// }
+    /// ```
+    pub async fn call_tool(&self, input: &Value) -> Result<ToolCallResult, ToolError> {
+        self.call_tool_with_context(input, &ToolContext::default())
+            .await
+    }
+
+    /// Like [`ToolHandler::call_tool`], running the tool with a request-scoped context.
+    ///
+    /// The context fills the tool's `#[param(context)]` parameters, is returned by
+    /// [`ToolContext::current`] while the tool runs, and its user and conversation ids are
+    /// added to audit records. A call still running at the context's deadline fails with
+    /// `ToolError::Execution`.
+    pub async fn call_tool_with_context(
+        &self,
+        input: &Value,
+        context: &ToolContext,
+    ) -> Result<ToolCallResult, ToolError> {
+        let (input, repairs) = self.repair_arguments(input)?;
+        let input = normalized(input)?;
+        self.check_visible(&input, context)?;
+        let tool = self.resolve_call(&input)?;
+        // Only a fallback reads the payload again; otherwise its arguments move to the tool
+        let (mut call, input) = if self.fallbacks.contains_key(&tool.name) {
+            let call = self.parse_normalized(tool, Cow::Borrowed(&*input), repairs, false)?;
+            (call, input)
+        } else {
+            let call = self.parse_normalized(tool, input, repairs, false)?;
+            (call, Cow::Owned(Value::Null))
+        };
+        let input = &*input;
+        let args = std::mem::take(&mut call.args);
+        let started = Instant::now();
+        let (output, fallback, deduplicated) = match &self.in_flight {
+            Some(in_flight) => {
+                self.run_deduplicated(in_flight, tool, &call, args, input, context)
+                    .await?
+            }
+            None => {
+                let (output, fallback) = self.run_call(tool, &call, args, input, context).await?;
+                (output, fallback, false)
+            }
+        };
+        let (output, truncated) =
+            self.limit_output(fallback.as_deref().unwrap_or(&call.name), output);
+        Ok(ToolCallResult {
+            name: call.name,
+            id: call.id,
+            output,
+            duration: started.elapsed(),
+            fallback,
+            truncated,
+            deduplicated,
+            repairs: call.repairs,
+        })
+    }
+
+    /// Runs every entry of an assistant message's `tool_calls`, returning each call's id with
+    /// its output or error, in the order of the calls.
+    ///
+    /// `message` is an OpenAI-style assistant message, a whole chat completion response (the
+    /// first choice's message is used) or the `tool_calls` array itself. The calls run
+    /// concurrently, at most [`ToolHandler::with_max_concurrent_calls`] at a time, and their
+    /// results keep the order of the calls. Calls without an
+    /// `id`, as Ollama sends them, get an empty id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tool_calling::{tool, ToolHandler};
+    /// use serde_json::json;
+    ///
+    /// #[tool]
+    /// fn add(a: i32, b: i32) -> String {
+    ///     (a + b).to_string()
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let handler = ToolHandler::default();
+    ///     let message = json!({
+    ///         "role": "assistant",
+    ///         "tool_calls": [
+    ///             { "id": "call_1", "type": "function", "function": { "name": "add", "arguments": "{\"a\":1,\"b\":2}" } },
+    ///             { "id": "call_2", "type": "function", "function": { "name": "add", "arguments": "{\"a\":3,\"b\":4}" } }
+    ///         ]
+    ///     });
+    ///     let results = handler.call_all(&message).await;
+    ///     assert_eq!(results[0], ("call_1".to_string(), Ok("3".to_string())));
+    ///     assert_eq!(results[1], ("call_2".to_string(), Ok("7".to_string())));
+    /// }
+    /// ```
+    pub async fn call_all(&self, message: &Value) -> Vec<(String, Result<String, ToolError>)> {
+        self.call_all_with_context(message, &ToolContext::default())
+            .await
+    }
+
+    /// Like [`ToolHandler::call_all`], running every call with a request-scoped context.
+    pub async fn call_all_with_context(
+        &self,
+        message: &Value,
+        context: &ToolContext,
+    ) -> Vec<(String, Result<String, ToolError>)> {
+        let message = self.batch_message(message);
+        self.call_batch(tool_calls(&message), context).await
+    }
+
+    // The assistant message whose calls a batch runs, normalized when parsing is tolerant
+    pub(crate) fn batch_message<'a>(&self, message: &'a Value) -> Cow<'a, Value> {
+        if self.tolerant_parsing {
+            Cow::Owned(compat::normalize_message(message))
+        } else {
+            Cow::Borrowed(message)
+        }
+    }
+
+    // Runs `calls` as one batch, returning each call's id and result in order
+    pub(crate) async fn call_batch(
+        &self,
+        calls: &[Value],
+        context: &ToolContext,
+    ) -> Vec<(String, Result<String, ToolError>)> {
+        self.run_batch(calls.iter().map(|call| self.call_with_id(call, context)))
+            .await
+    }
+
+    // Awaits `calls` with at most `max_concurrent_calls` in flight, returning their outputs
+    // in the order of the calls rather than the order they finish in
+    pub(crate) async fn run_batch<F: Future>(
+        &self,
+        calls: impl IntoIterator<Item = F>,
+    ) -> Vec<F::Output> {
+        let calls = calls.into_iter().collect::<Vec<_>>();
+        let limit = self.max_concurrent_calls.unwrap_or(calls.len()).max(1);
+        let mut results = stream::iter(calls.into_iter().enumerate())
+            .map(|(i, call)| call.map(move |output| (i, output)))
+            .buffer_unordered(limit)
+            .collect::<Vec<_>>()
+            .await;
+        results.sort_unstable_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, output)| output).collect()
+    }
+
+    /// Runs the calls of an assistant message as [`ToolHandler::call_all_with_context`] does
+    /// and returns a `tool` message answering each, ready to append to the conversation.
+    /// Failed calls are answered with the text of [`ToolHandler::error_message`], and
+    /// [`ToolResultContent`] outputs with their text.
+    pub async fn answer_tool_calls(&self, message: &Value, context: &ToolContext) -> Vec<Value> {
+        self.call_all_with_context(message, context)
+            .await
+            .into_iter()
+            .map(|(id, result)| self.tool_message(&id, result))
+            .collect()
+    }
+
+    // The `tool` message answering the call with id `id`
+    pub(crate) fn tool_message(&self, id: &str, result: Result<String, ToolError>) -> Value {
+        let content = match result {
+            Ok(output) => content::to_text(&ToolResultContent::from_output(&output)),
+            Err(err) => self.error_message(&err),
+        };
+        json!({ "role": "tool", "tool_call_id": id, "content": content })
+    }
+
+    // The `tool` message answering the call of tool `name` with id `id` as Ollama takes it:
+    // named with `tool_name`, since Ollama's calls usually have no id, and with the images
+    // of a rich result in its `images`
+    pub(crate) fn ollama_tool_message(
+        &self,
+        id: &str,
+        name: &str,
+        result: Result<String, ToolError>,
+    ) -> Value {
+        let images = match &result {
+            Ok(output) => content::images(&ToolResultContent::from_output(output)),
+            Err(_) => Vec::new(),
+        };
+        let mut message = self.tool_message(id, result);
+        message["tool_name"] = json!(name);
+        if id.is_empty() {
+            message
+                .as_object_mut()
+                .map(|message| message.remove("tool_call_id"));
+        }
+        if !images.is_empty() {
+            message["images"] = json!(images);
+        }
+        message
+    }
+
+    /// Validates arguments against, and renders parameter schemas in, the given JSON Schema
+    /// draft, rewriting draft-specific keywords as described in [`SchemaDraft`].
+    ///
+    /// Without a draft, schemas are rendered as written and validated with Draft 7
+    /// semantics.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tool_calling::{SchemaDraft, ToolHandler};
+    ///
+    /// let handler = ToolHandler::default().with_schema_draft(SchemaDraft::Draft202012);
+    /// assert_eq!(handler.schema_draft(), SchemaDraft::Draft202012);
+    /// ```
+    pub fn with_schema_draft(mut self, draft: SchemaDraft) -> Self {
+        self.schema_draft = Some(draft);
+        self
+    }
+
+    /// The JSON Schema draft arguments are validated against.
+    pub fn schema_draft(&self) -> SchemaDraft {
+        self.schema_draft.unwrap_or(SchemaDraft::Draft7)
+    }
+
+    /// Makes [`ToolHandler::call_all`] run the calls of a message one after another, in
+    /// order, for tools whose side effects must not interleave. The same as
+    /// `with_max_concurrent_calls(1)`.
+    pub fn with_sequential_calls(self) -> Self {
+        self.with_max_concurrent_calls(1)
+    }
+
+    /// Caps how many calls of one batch run at once: the calls of a message in
+    /// [`ToolHandler::call_all`] and the provider `answer_*` methods, and the requests of a
+    /// JSON-RPC batch. Further calls start as earlier ones finish, and results still come
+    /// back in the order of the calls. A limit of 0 is taken as 1.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tool_calling::ToolHandler;
+    ///
+    /// let handler = ToolHandler::default().with_max_concurrent_calls(4);
+    /// assert_eq!(handler.max_concurrent_calls(), Some(4));
+    /// ```
+    pub fn with_max_concurrent_calls(mut self, limit: usize) -> Self {
+        self.max_concurrent_calls = Some(limit.max(1));
+        self
+    }
+
+    /// The most calls of one batch that run at once, or `None` when they all run together.
+    pub fn max_concurrent_calls(&self) -> Option<usize> {
+        self.max_concurrent_calls
+    }
+
+    /// Makes [`ToolHandler::call_all`] and [`ToolHandler::answer_tool_calls`] read messages
+    /// with [`compat::normalize_message`] first, for OpenAI-compatible servers such as
+    /// llama.cpp and vLLM that put calls in the content or leave out their ids.
+    pub fn with_tolerant_parsing(mut self) -> Self {
+        self.tolerant_parsing = true;
+        self
+    }
+
+    /// Repairs call arguments that are not valid JSON with [`repair::repair_json`] instead
+    /// of rejecting them, for small models that write trailing commas, single quotes or
+    /// unquoted keys. The repairs made are logged and reported in
+    /// [`ToolCallResult::repairs`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tool_calling::repair::Repair;
+    /// use tool_calling::{tool, ToolHandler};
+    /// use serde_json::json;
+    ///
+    /// #[tool]
+    /// fn add(a: i32, b: i32) -> String {
+    ///     (a + b).to_string()
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let handler = ToolHandler::default().with_argument_repair();
+    ///     let payload = json!({
+    ///         "type": "function",
+    ///         "function": { "name": "add", "arguments": "{'a': 1, 'b': 2,}" }
+    ///     });
+    ///     let result = handler.call_tool(&payload).await.unwrap();
+    ///     assert_eq!(result.output, "3");
+    ///     assert_eq!(result.repairs, vec![Repair::SingleQuotes, Repair::TrailingCommas]);
+    /// }
+    /// ```
+    pub fn with_argument_repair(mut self) -> Self {
+        self.repair_arguments = true;
+        self
+    }
+
+    // Replaces string arguments that are not valid JSON with their repaired value, when
+    // argument repair is on
+    fn repair_arguments<'a>(
+        &self,
+        input: &'a Value,
+    ) -> Result<(Cow<'a, Value>, Vec<Repair>), ToolError> {
+        let pointer = if input.get("function").is_some() {
+            "/function/arguments"
+        } else {
+            "/arguments"
+        };
+        let malformed = match input.pointer(pointer) {
+            Some(Value::String(encoded)) if self.repair_arguments && !encoded.trim().is_empty() => {
+                serde_json::from_str::<Value>(encoded)
+                    .is_err()
+                    .then_some(encoded)
+            }
+            _ => None,
+        };
+        let Some(encoded) = malformed else {
+            return Ok((Cow::Borrowed(input), Vec::new()));
+        };
+        let repaired = repair::repair_json(encoded)?;
+        let name = input
+            .pointer("/function/name")
+            .or_else(|| input.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        tracing::warn!(tool = name, repairs = ?repaired.repairs, "repaired malformed tool arguments");
+        let mut input = input.clone();
+        if let Some(arguments) = input.pointer_mut(pointer) {
+            *arguments = repaired.value;
+        }
+        Ok((Cow::Owned(input), repaired.repairs))
+    }
+
+    async fn call_with_id(
+        &self,
+        call: &Value,
+        context: &ToolContext,
+    ) -> (String, Result<String, ToolError>) {
+        let id = call.get("id").and_then(Value::as_str).unwrap_or_default();
+        // Ollama leaves out the `type` of its calls
+        let mut call = Cow::Borrowed(call);
+        if call.get("type").is_none() && call.get("function").is_some() {
+            call.to_mut()["type"] = json!("function");
+        }
+        let result = self.call_tool_with_context(&call, context).await;
+        (id.to_string(), result.map(|result| result.output))
+    }
+
+    // Execute a validated call, falling back to other tools if it fails
+    async fn run_call(
+        &self,
+        tool: &Tool,
+        call: &ValidatedCall,
+        args: Vec<Value>,
+        input: &Value,
+        context: &ToolContext,
+    ) -> Outcome {
+        match self.execute(tool, args, call.id.as_deref(), context).await {
+            Ok(output) => Ok((output, None)),
+            Err(err) => match self.run_fallback(input, &call.name, context).await {
+                Some((fallback, output)) => Ok((output, Some(fallback))),
+                None => Err(err),
+            },
+        }
+    }
+
+    // Execute a validated call, or wait for an identical one already running and share its
+    // outcome, reporting which happened
+    async fn run_deduplicated(
+        &self,
+        in_flight: &InFlight,
+        tool: &Tool,
+        call: &ValidatedCall,
+        args: Vec<Value>,
+        input: &Value,
+        context: &ToolContext,
+    ) -> Result<(String, Option<String>, bool), ToolError> {
+        let key = json!([call.name, args, context.user_id, context.conversation_id]).to_string();
+        loop {
+            match in_flight.join(key.clone()) {
+                Slot::Leader(leader) => {
+                    let outcome = self.run_call(tool, call, args, input, context).await;
+                    leader.finish(&outcome);
+                    let (output, fallback) = outcome?;
+                    return Ok((output, fallback, false));
+                }
+                Slot::Follower(receiver) => {
+                    if let Some(outcome) = leader_outcome(receiver).await {
+                        let (output, fallback) = outcome?;
+                        return Ok((output, fallback, true));
+                    }
+                }
+            }
+        }
+    }
+
+    // Try the fallback chain of a failed tool with the call's original arguments, returning
+    // the first fallback that succeeds and its output
+    async fn run_fallback(
+        &self,
+        input: &Value,
+        failed: &str,
+        context: &ToolContext,
+    ) -> Option<(String, String)> {
+        let mut tried = vec![failed];
+        let mut current = failed;
+        while let Some(next) = self.fallbacks.get(current) {
+            if tried.contains(&next.as_str()) {
+                break;
+            }
+            tried.push(next);
+            current = next;
+
+            let mut payload = input.clone();
+            payload["function"]["name"] = Value::String(next.clone());
+            // The fallback validates the arguments against its own schema
+            let Ok(call) = self.parse_tool_call(&payload, false) else {
+                continue;
+            };
+            let Some(tool) = self.get_tool(next) else {
+                continue;
+            };
+            if let Ok(output) = self
+                .execute(tool, call.args, call.id.as_deref(), context)
+                .await
+            {
+                return Some((next.clone(), output));
+            }
+        }
+        None
+    }
+
+    /// Resolves and validates a JSON payload without executing the tool.
+    ///
+    /// Performs the same name resolution, schema validation, and argument ordering as
+    /// [`ToolHandler::call_tool`], returning the normalized call so it can be shown to a
+    /// user (or logged) before it runs.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tool_calling::{tool, ToolHandler};
+    /// use serde_json::json;
+    ///
+    /// #[tool]
+    /// fn add(a: i32, b: i32) -> String {
+    ///     (a + b).to_string()
+    /// }
+    ///
+    /// let handler = ToolHandler::default();
+    /// let payload = json!({
+    ///     "type": "function",
+    ///     "function": { "name": "add", "arguments": { "b": 2, "a": 1 } }
+    /// });
+    /// let call = handler.validate_tool_call(&payload).unwrap();
+    /// assert_eq!(call.name, "add");
+    /// assert_eq!(call.args, vec![json!(1), json!(2)]);
+    /// ```
+    pub fn validate_tool_call(&self, input: &Value) -> Result<ValidatedCall, ToolError> {
+        self.parse_tool_call(input, true)
+    }
+
+    /// Parses a JSON payload and executes the corresponding tool, streaming its output.
+    ///
+    /// See [`ToolHandler::call_streaming`] for how non-streaming tools are handled.
+    pub fn call_tool_streaming(&self, input: &Value) -> Result<ToolStream, ToolError> {
+        self.call_tool_streaming_with_context(input, &ToolContext::default())
+    }
+
+    /// Like [`ToolHandler::call_tool_streaming`], running the tool with a request-scoped
+    /// context.
+    pub fn call_tool_streaming_with_context(
+        &self,
+        input: &Value,
+        context: &ToolContext,
+    ) -> Result<ToolStream, ToolError> {
+        self.check_visible(input, context)?;
+        let call = self.parse_tool_call(input, false)?;
+        let tool = self
+            .get_tool(&call.name)
+            .ok_or_else(|| ToolError::NotFound(call.name.clone()))?;
+        Ok(self.execute_streaming(tool, call.args, call.id.as_deref(), context))
+    }
+
+    // Helper method to drop surplus positional arguments according to the unknown-args policy.
+    // Under `Fail` the arguments are passed through so the tool's own length check reports them.
+    fn apply_unknown_args_to_positional(&self, tool: &Tool, mut args: Vec<Value>) -> Vec<Value> {
+        let param_count = tool.parameters().map_or(args.len(), <[_]>::len);
+        if args.len() <= param_count {
+            return args;
+        }
+        match self.unknown_args {
+            UnknownArgs::Fail => args,
+            UnknownArgs::Strip => {
+                args.truncate(param_count);
+                args
+            }
+            UnknownArgs::Warn => {
+                tracing::warn!(
+                    tool = tool.name.as_str(),
+                    surplus = args.len() - param_count,
+                    "ignoring surplus tool arguments"
+                );
+                args.truncate(param_count);
+                args
+            }
+        }
+    }
+
+    // Helper method to parse tool calls, validate against schema, and extract ordered args.
+    // The arguments keyed by name are only collected with `keep_arguments`; otherwise each
+    // value moves straight into the ordered arguments
+    fn parse_tool_call(
+        &self,
+        input: &Value,
+        keep_arguments: bool,
+    ) -> Result<ValidatedCall, ToolError> {
+        let (input, repairs) = self.repair_arguments(input)?;
+        let input = normalized(input)?;
+        let tool = self.resolve_call(&input)?;
+        self.parse_normalized(tool, input, repairs, keep_arguments)
+    }
+
+    // Check the shape of a repaired, normalized call and find the tool it names
+    fn resolve_call(&self, input: &Value) -> Result<&Tool, ToolError> {
+        let obj = input
+            .as_object()
+            .ok_or_else(|| ToolError::BadArgs("Expected JSON object".to_string()))?;
+        let input_type = obj
+            .get("type")
+            .and_then(|t| t.as_str())
+            .ok_or_else(|| ToolError::BadArgs("Missing or invalid 'type' field".to_string()))?;
+        if input_type != "function" {
+            return Err(ToolError::BadArgs(format!(
+                "Invalid input type: expected 'function', got '{}'",
+                input_type
+            )));
+        }
+        let function = obj
+            .get("function")
+            .and_then(|f| f.as_object())
+            .ok_or_else(|| ToolError::BadArgs("Missing or invalid 'function' field".to_string()))?;
+        let name = function
+            .get("name")
+            .and_then(|n| n.as_str())
+            .ok_or_else(|| ToolError::BadArgs("Missing or invalid 'function.name'".to_string()))?;
+        if !function.get("arguments").is_some_and(Value::is_object) {
+            return Err(ToolError::BadArgs(
+                "Missing or invalid 'arguments' field".to_string(),
+            ));
+        }
+        self.get_tool(name)
+            .ok_or_else(|| ToolError::NotFound(name.to_string()))
+    }
+
+    // Validate and order the arguments of a call `resolve_call` found `tool` for
+    fn parse_normalized(
+        &self,
+        tool: &Tool,
+        input: Cow<'_, Value>,
+        repairs: Vec<Repair>,
+        keep_arguments: bool,
+    ) -> Result<ValidatedCall, ToolError> {
+        let name = tool.name.as_str();
+        let id = input["id"].as_str().map(str::to_string);
+        let mut args_obj = take_arguments(input);
+
+        let parameters = tool.parameters()?;
+
+        // --- Unknown Arguments ---
+        let is_known = |key: &str| parameters.iter().any(|param| param.name == key);
+        let unknown = args_obj
+            .keys()
+            .filter(|key| !is_known(key))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            match self.unknown_args {
+                UnknownArgs::Fail => {
+                    return Err(ToolError::BadArgs(format!(
+                        "Unknown argument(s) for tool '{}': {}",
+                        name,
+                        unknown.join(", ")
+                    )));
+                }
+                UnknownArgs::Warn => {
+                    tracing::warn!(tool = name, unknown = ?unknown, "ignoring unknown tool arguments");
+                }
+                UnknownArgs::Strip => {}
+            }
+            args_obj.retain(|key, _| is_known(key));
+        }
+        // --- End Unknown Arguments ---
+
+        self.coercion.apply(&tool.parameter_schema, &mut args_obj);
+
+        // --- Schema Validation ---
+        let compiled_schema = tool.compiled_schema(self.schema_draft)?;
+        let input_args_val = Value::Object(args_obj);
+        let error_messages = compiled_schema
+            .validate(&input_args_val)
+            .err()
+            .map(|errors| errors.map(|e| e.to_string()).collect::<Vec<_>>().join("; "));
+        let mut args_obj = match (input_args_val, error_messages) {
+            (Value::Object(args_obj), None) => args_obj,
+            (Value::Object(args_obj), Some(error_messages)) => {
+                let error_messages = tool.redact_message(&args_obj, error_messages);
+                return Err(ToolError::BadArgs(format!(
+                    "Argument validation failed for tool '{}': {}",
+                    name, error_messages
+                )));
+            }
+            _ => unreachable!("the arguments were wrapped as an object above"),
+        };
+        // --- End Schema Validation ---
+
+        // Extract arguments in order, only error if a required param is missing
+        let mut ordered_args: Vec<Value> = Vec::with_capacity(parameters.len());
+        let mut arguments = serde_json::Map::new();
+        for param in parameters {
+            if let Some(val) = args_obj.remove(&param.name) {
+                if keep_arguments {
+                    arguments.insert(param.name.clone(), val.clone());
+                }
+                ordered_args.push(val);
+            } else if param.required {
+                return Err(ToolError::BadArgs(format!(
+                    "Missing argument for parameter '{}'",
+                    param.name
+                )));
+            } else {
+                // Optional parameter omitted: hold its position so later arguments line up
+                ordered_args.push(Value::Null);
+            }
+        }
+        // Trailing omitted optionals can simply be left off
+        while ordered_args.last() == Some(&Value::Null) {
+            ordered_args.pop();
+        }
+
+        Ok(ValidatedCall {
+            name: name.to_string(),
+            id,
+            arguments,
+            args: ordered_args,
+            repairs,
+        })
     }
 }
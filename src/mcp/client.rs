@@ -0,0 +1,354 @@
+use super::PROTOCOL_VERSION;
+use crate::jsonrpc::{error_response, METHOD_NOT_FOUND};
+use crate::{Tool, ToolError, ToolFn, ToolFuture, ToolHandler, ToolResultContent};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::oneshot;
+
+/// Errors returned while talking to an MCP server.
+#[derive(Debug, Clone, thiserror::Error, PartialEq)]
+pub enum McpError {
+    #[error("MCP transport failed: {0}")]
+    Io(String),
+    #[error("invalid MCP message: {0}")]
+    Protocol(String),
+    #[error("MCP server returned error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("MCP connection closed")]
+    Closed,
+}
+
+type Reply = oneshot::Sender<Result<Value, McpError>>;
+
+// The shared end of a connection; dropping the last reference closes the writer and kills
+// a spawned server
+struct Connection {
+    writer: tokio::sync::Mutex<Box<dyn AsyncWrite + Send + Unpin>>,
+    // Requests awaiting a response, or `None` once the server has gone away
+    pending: Arc<Mutex<Option<HashMap<u64, Reply>>>>,
+    next_id: AtomicU64,
+    _child: Option<Child>,
+}
+
+impl Connection {
+    async fn send(&self, message: &Value) -> Result<(), McpError> {
+        let mut line = message.to_string();
+        line.push('\n');
+        let mut writer = self.writer.lock().await;
+        writer
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|err| McpError::Io(err.to_string()))?;
+        writer
+            .flush()
+            .await
+            .map_err(|err| McpError::Io(err.to_string()))
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value, McpError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        match self.pending.lock().unwrap().as_mut() {
+            Some(pending) => pending.insert(id, tx),
+            None => return Err(McpError::Closed),
+        };
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(err) = self.send(&message).await {
+            if let Some(pending) = self.pending.lock().unwrap().as_mut() {
+                pending.remove(&id);
+            }
+            return Err(err);
+        }
+        rx.await.unwrap_or(Err(McpError::Closed))
+    }
+
+    async fn notify(&self, method: &str) -> Result<(), McpError> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method }))
+            .await
+    }
+}
+
+/// A connection to an MCP server whose tools can be registered on a [`ToolHandler`].
+///
+/// Clones share the connection. It stays open while any clone, or any proxy tool created
+/// from one, is alive; a server started with [`McpClient::spawn`] is killed once the last
+/// of them is dropped.
+#[derive(Clone)]
+pub struct McpClient {
+    connection: Arc<Connection>,
+    server_info: Value,
+    instructions: Option<String>,
+    prefix: String,
+}
+
+impl McpClient {
+    /// Starts `command` as an MCP server speaking over its stdin and stdout, and connects to
+    /// it. The server's stderr is inherited.
+    pub async fn spawn(mut command: Command) -> Result<Self, McpError> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| McpError::Io(err.to_string()))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Self::start(stdout, Box::new(stdin), Some(child)).await
+    }
+
+    /// Connects to an MCP server exchanging newline-delimited messages over `reader` and
+    /// `writer`, such as a socket or an in-process [`McpServer`](super::McpServer).
+    pub async fn connect<R, W>(reader: R, writer: W) -> Result<Self, McpError>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        Self::start(reader, Box::new(writer), None).await
+    }
+
+    async fn start<R>(
+        reader: R,
+        writer: Box<dyn AsyncWrite + Send + Unpin>,
+        child: Option<Child>,
+    ) -> Result<Self, McpError>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        let connection = Arc::new(Connection {
+            writer: tokio::sync::Mutex::new(writer),
+            pending: Arc::new(Mutex::new(Some(HashMap::new()))),
+            next_id: AtomicU64::new(1),
+            _child: child,
+        });
+        tokio::spawn(read_messages(
+            BufReader::new(reader),
+            Arc::clone(&connection.pending),
+            Arc::downgrade(&connection),
+        ));
+
+        let init = connection
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION")
+                    }
+                }),
+            )
+            .await?;
+        connection.notify("notifications/initialized").await?;
+        Ok(Self {
+            connection,
+            server_info: init["serverInfo"].clone(),
+            instructions: init["instructions"].as_str().map(str::to_string),
+            prefix: String::new(),
+        })
+    }
+
+    /// Registers proxies under `prefix` followed by the server's tool name, to keep tools
+    /// from different servers apart.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// The `serverInfo` the server reported when the connection was initialized.
+    pub fn server_info(&self) -> &Value {
+        &self.server_info
+    }
+
+    /// The `instructions` the server sent when the connection was initialized, if any.
+    pub fn instructions(&self) -> Option<&str> {
+        self.instructions.as_deref()
+    }
+
+    /// Lists the server's tools, following pagination, and builds a proxy for each.
+    pub async fn tools(&self) -> Result<Vec<Tool>, McpError> {
+        let mut tools = Vec::new();
+        let mut cursor = None;
+        loop {
+            let params = match cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let page = self.connection.request("tools/list", params).await?;
+            let entries = page["tools"]
+                .as_array()
+                .ok_or_else(|| McpError::Protocol("tools/list result has no 'tools'".into()))?;
+            for entry in entries {
+                tools.push(self.proxy(entry)?);
+            }
+            match page["nextCursor"].as_str() {
+                Some(next) => cursor = Some(next.to_string()),
+                None => return Ok(tools),
+            }
+        }
+    }
+
+    /// Lists the server's tools and registers their proxies on `handler`, replacing tools
+    /// with the same name.
+    ///
+    /// Returns the names of the registered tools.
+    pub async fn load(&self, handler: &mut ToolHandler) -> Result<Vec<String>, McpError> {
+        let tools = self.tools().await?;
+        let names = tools.iter().map(|tool| tool.name.clone()).collect();
+        for tool in tools {
+            handler.register(tool);
+        }
+        Ok(names)
+    }
+
+    /// Calls the server's tool `name` directly, returning its text content.
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<String, ToolError> {
+        let result = self
+            .connection
+            .request(
+                "tools/call",
+                json!({ "name": name, "arguments": arguments }),
+            )
+            .await
+            .map_err(|err| ToolError::Execution(err.to_string()))?;
+        let text = content_text(&result);
+        if result["isError"] == true {
+            Err(ToolError::Execution(text))
+        } else {
+            Ok(text)
+        }
+    }
+
+    fn proxy(&self, entry: &Value) -> Result<Tool, McpError> {
+        let name = entry["name"]
+            .as_str()
+            .ok_or_else(|| McpError::Protocol("tool without a 'name'".into()))?
+            .to_string();
+        let description = entry["description"].as_str().unwrap_or_default();
+        let parameters = match &entry["inputSchema"] {
+            Value::Null => json!({ "type": "object", "properties": {}, "required": [] }),
+            schema => schema.clone(),
+        };
+        let parameter_names = parameters
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|props| props.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        let client = self.clone();
+        let remote_name = name.clone();
+        let function = ToolFn::Async(Box::new(move |args: Vec<Value>| -> ToolFuture {
+            let arguments = parameter_names
+                .iter()
+                .zip(args)
+                .filter(|(_, value)| !value.is_null())
+                .map(|(name, value)| (name.clone(), value))
+                .collect::<Map<_, _>>();
+            let client = client.clone();
+            let name = remote_name.clone();
+            Box::pin(async move { client.call_tool(&name, Value::Object(arguments)).await })
+        }));
+        Ok(Tool::new(
+            format!("{}{}", self.prefix, name),
+            description,
+            parameters,
+            function,
+        ))
+    }
+}
+
+// The text blocks of a `tools/call` result joined by newlines; other blocks are rendered
+// as JSON, and `structuredContent` stands in for empty content. Results with images or
+// resource links are kept whole, as encoded `ToolResultContent`
+fn content_text(result: &Value) -> String {
+    let blocks = result["content"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    if blocks.is_empty() {
+        if let Some(structured) = result.get("structuredContent") {
+            return structured.to_string();
+        }
+    }
+    if blocks
+        .iter()
+        .any(|block| ToolResultContent::from_mcp(block).is_some())
+    {
+        let content = blocks
+            .iter()
+            .map(|block| {
+                ToolResultContent::from_mcp(block).unwrap_or_else(|| match block["text"].as_str() {
+                    Some(text) if block["type"] == "text" => ToolResultContent::text(text),
+                    _ => ToolResultContent::json(block.clone()),
+                })
+            })
+            .collect::<Vec<_>>();
+        return ToolResultContent::to_output(&content);
+    }
+    blocks
+        .iter()
+        .map(|block| match (&block["type"], block["text"].as_str()) {
+            (ty, Some(text)) if ty == "text" => text.to_string(),
+            _ => block.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Routes responses to their waiting requests and answers the server's own requests, until
+// the server closes its end
+async fn read_messages<R>(
+    reader: BufReader<R>,
+    pending: Arc<Mutex<Option<HashMap<u64, Reply>>>>,
+    connection: Weak<Connection>,
+) where
+    R: AsyncRead + Unpin,
+{
+    let mut lines = reader.lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if let Some(method) = message["method"].as_str() {
+            // Only requests need an answer; notifications are ignored
+            let (Some(id), Some(connection)) = (message.get("id"), connection.upgrade()) else {
+                continue;
+            };
+            let response = match method {
+                "ping" => json!({ "jsonrpc": "2.0", "id": id, "result": {} }),
+                _ => error_response(
+                    id.clone(),
+                    METHOD_NOT_FOUND,
+                    format!("Method not found: {}", method),
+                ),
+            };
+            let _ = connection.send(&response).await;
+            continue;
+        }
+        let Some(id) = message["id"].as_u64() else {
+            continue;
+        };
+        let Some(reply) = pending
+            .lock()
+            .unwrap()
+            .as_mut()
+            .and_then(|pending| pending.remove(&id))
+        else {
+            continue;
+        };
+        let outcome = match message.get("error") {
+            Some(error) => Err(McpError::Rpc {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            }),
+            None => Ok(message["result"].clone()),
+        };
+        let _ = reply.send(outcome);
+    }
+    // Dropping the remaining replies fails their requests with `Closed`
+    pending.lock().unwrap().take();
+}
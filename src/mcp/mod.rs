@@ -0,0 +1,74 @@
+//! The Model Context Protocol, in both directions.
+//!
+//! An [`McpServer`] speaks the server side: it answers `initialize`, `ping`, `tools/list`
+//! and `tools/call` requests, each a JSON-RPC 2.0 message, so MCP-capable clients such as
+//! Claude Desktop or editor extensions can call tools defined with `#[tool]`.
+//! [`McpServer::serve_stdio`] runs the stdio transport, one message per line;
+//! [`McpServer::serve`] does the same over any reader and writer, and
+//! [`McpServer::handle_message`] answers a single message for other transports, such as SSE
+//! endpoints of an existing web server.
+//!
+//! A call that fails while running is answered with an `isError` result carrying the text
+//! of [`ToolHandler::error_message`](crate::ToolHandler::error_message), so the model can
+//! see what went wrong; unknown tools and malformed requests are answered with JSON-RPC
+//! errors.
+//!
+//! ```rust,no_run
+//! use tool_calling::mcp::McpServer;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     McpServer::new(ToolHandler::default())
+//!         .with_server_info("calculator", "1.0.0")
+//!         .serve_stdio()
+//!         .await
+//!         .unwrap();
+//! }
+//! ```
+//!
+//! An [`McpClient`] is the other side: it connects to an MCP server, lists its tools and
+//! registers a proxy [`Tool`](crate::Tool) for each on a handler, so third-party MCP tools
+//! sit next to local ones. Calling a proxy sends `tools/call` to the server; an `isError`
+//! result or JSON-RPC error fails the call with
+//! [`ToolError::Execution`](crate::ToolError::Execution).
+//!
+//! ```rust,no_run
+//! use tokio::process::Command;
+//! use tool_calling::mcp::McpClient;
+//! use tool_calling::ToolHandler;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let mut handler = ToolHandler::default();
+//!     let mut command = Command::new("npx");
+//!     command.args(["-y", "@modelcontextprotocol/server-filesystem", "/tmp"]);
+//!     let names = McpClient::spawn(command)
+//!         .await
+//!         .unwrap()
+//!         .with_prefix("fs_")
+//!         .load(&mut handler)
+//!         .await
+//!         .unwrap();
+//!     println!("imported {:?}", names);
+//! }
+//! ```
+
+mod client;
+mod server;
+
+pub use client::{McpClient, McpError};
+pub use server::{McpContextFn, McpServer};
+
+/// The newest protocol revision implemented here, offered to peers that ask for one it
+/// does not know.
+pub const PROTOCOL_VERSION: &str = "2025-06-18";
+
+// Revisions whose tool messages are handled unchanged
+const SUPPORTED_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", PROTOCOL_VERSION];
@@ -0,0 +1,187 @@
+use super::{PROTOCOL_VERSION, SUPPORTED_VERSIONS};
+use crate::jsonrpc::{
+    error_response, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR,
+};
+use crate::{ToolContext, ToolError, ToolHandler, ToolResultContent};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+/// A hook building the [`ToolContext`] of a `tools/call` request from its `params`.
+pub type McpContextFn = Arc<dyn Fn(&Value) -> ToolContext + Send + Sync>;
+
+/// Serves a [`ToolHandler`]'s tools over the Model Context Protocol.
+pub struct McpServer {
+    handler: ToolHandler,
+    name: String,
+    version: String,
+    instructions: Option<String>,
+    context: McpContextFn,
+}
+
+impl McpServer {
+    /// Creates a server exposing every tool registered on `handler`.
+    pub fn new(handler: ToolHandler) -> Self {
+        Self {
+            handler,
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            instructions: None,
+            context: Arc::new(|_| ToolContext::default()),
+        }
+    }
+
+    /// Sets the `serverInfo` reported to clients; defaults to this crate's name and version.
+    pub fn with_server_info(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.name = name.into();
+        self.version = version.into();
+        self
+    }
+
+    /// Sets the `instructions` returned from `initialize`, a hint on how to use the tools.
+    pub fn with_instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Builds the [`ToolContext`] of each `tools/call` from the request's `params`, for
+    /// example to read a user id from its `_meta`. By default calls get an empty context.
+    pub fn with_context<F>(mut self, context: F) -> Self
+    where
+        F: Fn(&Value) -> ToolContext + Send + Sync + 'static,
+    {
+        self.context = Arc::new(context);
+        self
+    }
+
+    /// The handler whose tools are served.
+    pub fn handler(&self) -> &ToolHandler {
+        &self.handler
+    }
+
+    /// Serves requests read from stdin, writing responses to stdout, until stdin closes.
+    pub async fn serve_stdio(&self) -> std::io::Result<()> {
+        let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+        self.serve(stdin, tokio::io::stdout()).await
+    }
+
+    /// Serves newline-delimited messages from `reader`, writing one line per response to
+    /// `writer`, until `reader` is exhausted. Requests are answered in the order they arrive.
+    pub async fn serve<R, W>(&self, reader: R, mut writer: W) -> std::io::Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle_message(&message).await,
+                Err(err) => Some(error_response(Value::Null, PARSE_ERROR, err.to_string())),
+            };
+            if let Some(response) = response {
+                let mut line = response.to_string();
+                line.push('\n');
+                writer.write_all(line.as_bytes()).await?;
+                writer.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answers one JSON-RPC message. Notifications and responses from the client yield
+    /// `None`; requests yield a response or error object.
+    pub async fn handle_message(&self, message: &Value) -> Option<Value> {
+        let Some(method) = message["method"].as_str() else {
+            // A response to a request we never sent, or not JSON-RPC at all
+            return match message.get("id") {
+                Some(_) if message.get("result").is_some() || message.get("error").is_some() => {
+                    None
+                }
+                id => Some(error_response(
+                    id.cloned().unwrap_or(Value::Null),
+                    INVALID_REQUEST,
+                    "Missing 'method' field",
+                )),
+            };
+        };
+        let id = message.get("id")?.clone();
+        let params = &message["params"];
+        let result = match method {
+            "initialize" => Ok(self.initialize(params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(self.list_tools()),
+            "tools/call" => self.call(params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, message),
+        })
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let requested = params["protocolVersion"].as_str().unwrap_or_default();
+        let version = if SUPPORTED_VERSIONS.contains(&requested) {
+            requested
+        } else {
+            PROTOCOL_VERSION
+        };
+        let mut result = json!({
+            "protocolVersion": version,
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": { "name": self.name, "version": self.version }
+        });
+        if let Some(instructions) = &self.instructions {
+            result["instructions"] = json!(instructions);
+        }
+        result
+    }
+
+    fn list_tools(&self) -> Value {
+        let tools = self
+            .handler
+            .tools()
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "inputSchema": tool.parameter_schema
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({ "tools": tools })
+    }
+
+    async fn call(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params["name"].as_str().ok_or((
+            INVALID_PARAMS,
+            "Missing or invalid 'name' field".to_string(),
+        ))?;
+        let arguments = match &params["arguments"] {
+            Value::Null => json!({}),
+            arguments => arguments.clone(),
+        };
+        let call =
+            json!({ "type": "function", "function": { "name": name, "arguments": arguments } });
+        let context = (self.context)(params);
+        match self.handler.call_tool_with_context(&call, &context).await {
+            Ok(result) => {
+                let content = result.content();
+                let blocks = content.iter().map(ToolResultContent::to_mcp).collect();
+                Ok(json!({ "content": Value::Array(blocks), "isError": false }))
+            }
+            Err(ToolError::NotFound(name)) => {
+                Err((INVALID_PARAMS, format!("Unknown tool: {}", name)))
+            }
+            Err(err) => Ok(tool_result(&self.handler.error_message(&err), true)),
+        }
+    }
+}
+
+// A `tools/call` result holding one text block
+fn tool_result(text: &str, is_error: bool) -> Value {
+    json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+}
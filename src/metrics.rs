@@ -0,0 +1,179 @@
+//! Prometheus metrics for tool calls.
+//!
+//! Attach a [`ToolMetrics`] with
+//! [`ToolHandler::with_metrics`](crate::ToolHandler::with_metrics) and serve
+//! [`ToolMetrics::render`] from your `/metrics` endpoint. It tracks, per tool:
+//!
+//! - `tool_calls_total`: executions, successful or not;
+//! - `tool_call_errors_total`: failed executions, labelled with the error `kind`;
+//! - `tool_call_duration_seconds`: a histogram of execution times.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use tool_calling::metrics::ToolMetrics;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let metrics = Arc::new(ToolMetrics::new());
+//!     let handler = ToolHandler::default().with_metrics(metrics.clone());
+//!     handler.call_with_args("add", &["1".into(), "2".into()]).await.unwrap();
+//!
+//!     let text = metrics.render();
+//!     assert!(text.contains("tool_calls_total{tool=\"add\"} 1"));
+//! }
+//! ```
+
+use crate::audit::{AuditRecord, AuditSink};
+use crate::ToolHandler;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+// Upper bounds, in seconds, of the default latency buckets
+const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Default)]
+struct ToolStats {
+    calls: u64,
+    errors: BTreeMap<&'static str, u64>,
+    // Observations per bucket, not cumulative; the last entry counts those above every bound
+    buckets: Vec<u64>,
+    duration_sum: f64,
+}
+
+/// Counters and latency histograms of tool calls, rendered in the Prometheus text format.
+pub struct ToolMetrics {
+    buckets: Vec<f64>,
+    stats: Mutex<BTreeMap<String, ToolStats>>,
+}
+
+impl Default for ToolMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ToolMetrics {
+    /// Creates empty metrics with latency buckets from 5ms to 10s.
+    pub fn new() -> Self {
+        Self::with_buckets(DEFAULT_BUCKETS.to_vec())
+    }
+
+    /// Creates empty metrics with the given latency bucket bounds, in seconds.
+    pub fn with_buckets(mut buckets: Vec<f64>) -> Self {
+        buckets.retain(|bound| bound.is_finite());
+        buckets.sort_by(f64::total_cmp);
+        buckets.dedup();
+        Self {
+            buckets,
+            stats: Mutex::default(),
+        }
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let stats = self.stats.lock().unwrap();
+        let mut out = String::new();
+
+        out.push_str("# HELP tool_calls_total Tool executions, successful or not.\n");
+        out.push_str("# TYPE tool_calls_total counter\n");
+        for (tool, stats) in stats.iter() {
+            let _ = writeln!(
+                out,
+                "tool_calls_total{{tool=\"{}\"}} {}",
+                escape(tool),
+                stats.calls
+            );
+        }
+
+        out.push_str("# HELP tool_call_errors_total Failed tool executions.\n");
+        out.push_str("# TYPE tool_call_errors_total counter\n");
+        for (tool, stats) in stats.iter() {
+            for (kind, count) in &stats.errors {
+                let _ = writeln!(
+                    out,
+                    "tool_call_errors_total{{tool=\"{}\",kind=\"{}\"}} {}",
+                    escape(tool),
+                    kind,
+                    count
+                );
+            }
+        }
+
+        out.push_str("# HELP tool_call_duration_seconds Tool execution time.\n");
+        out.push_str("# TYPE tool_call_duration_seconds histogram\n");
+        for (tool, stats) in stats.iter() {
+            let tool = escape(tool);
+            let mut cumulative = 0;
+            for (bound, count) in self.buckets.iter().zip(&stats.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "tool_call_duration_seconds_bucket{{tool=\"{}\",le=\"{}\"}} {}",
+                    tool, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "tool_call_duration_seconds_bucket{{tool=\"{}\",le=\"+Inf\"}} {}",
+                tool, stats.calls
+            );
+            let _ = writeln!(
+                out,
+                "tool_call_duration_seconds_sum{{tool=\"{}\"}} {}",
+                tool, stats.duration_sum
+            );
+            let _ = writeln!(
+                out,
+                "tool_call_duration_seconds_count{{tool=\"{}\"}} {}",
+                tool, stats.calls
+            );
+        }
+        out
+    }
+}
+
+impl ToolHandler {
+    /// Records every execution into `metrics`, alongside any audit sink.
+    pub fn with_metrics(mut self, metrics: Arc<ToolMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+impl AuditSink for ToolMetrics {
+    fn record(&self, record: &AuditRecord) {
+        let mut stats = self.stats.lock().unwrap();
+        let stats = stats.entry(record.name.clone()).or_default();
+        stats.calls += 1;
+        if let Err(err) = &record.result {
+            *stats.errors.entry(err.kind()).or_default() += 1;
+        }
+
+        let seconds = record.duration.as_secs_f64();
+        stats.duration_sum += seconds;
+        stats.buckets.resize(self.buckets.len() + 1, 0);
+        let bucket = self
+            .buckets
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(self.buckets.len());
+        stats.buckets[bucket] += 1;
+    }
+}
+
+// Escape a label value as the text format requires
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
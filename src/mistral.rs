@@ -0,0 +1,229 @@
+//! Mistral's function-calling format.
+//!
+//! Mistral's chat API takes the same `tools` as OpenAI, returned by
+//! [`ToolHandler::mistral_tools`], and its assistant messages carry OpenAI-style
+//! `tool_calls` that [`ToolHandler::call_tool`] accepts as-is. It is stricter about ids: a
+//! `tool_call_id` must be exactly nine letters or digits, so ids from other providers are
+//! rejected when a conversation is replayed. [`normalize_tool_call_id`] maps any id onto a
+//! valid one, the same way every time, and [`ToolHandler::answer_mistral_tool_calls`]
+//! returns `tool` messages that carry the tool's `name` next to a valid id.
+//!
+//! Mistral models run locally without a chat template emit calls as raw text instead:
+//! [`parse_raw_tool_calls`] reads the `[TOOL_CALLS]` section of their output,
+//! [`ToolHandler::mistral_available_tools`] renders the `[AVAILABLE_TOOLS]` prompt section
+//! and [`raw_tool_results`] the `[TOOL_RESULTS]` section answering a call.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::mistral::parse_raw_tool_calls;
+//! use tool_calling::{tool, ToolContext, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let handler = ToolHandler::default();
+//!     let message = json!({
+//!         "role": "assistant",
+//!         "tool_calls": [{ "id": "call_abc123def456", "function": { "name": "add", "arguments": "{\"a\": 1, \"b\": 2}" } }]
+//!     });
+//!     let answers = handler.answer_mistral_tool_calls(&message, &ToolContext::default()).await;
+//!     assert_eq!(answers[0]["role"], "tool");
+//!     assert_eq!(answers[0]["name"], "add");
+//!     assert_eq!(answers[0]["content"], "3");
+//!     assert_eq!(answers[0]["tool_call_id"].as_str().unwrap().len(), 9);
+//!
+//!     let calls = parse_raw_tool_calls(r#"[TOOL_CALLS][{"name": "add", "arguments": {"a": 4, "b": 5}}]"#).unwrap();
+//!     assert_eq!(handler.call_tool(&calls[0]).await.unwrap().output, "9");
+//! }
+//! ```
+
+use crate::{SchemaFlavor, ToolContext, ToolError, ToolHandler};
+use serde_json::{json, Value};
+
+/// The length Mistral requires of a `tool_call_id`.
+pub const TOOL_CALL_ID_LEN: usize = 9;
+
+const TOOL_CALLS: &str = "[TOOL_CALLS]";
+const ID_ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Whether Mistral accepts `id` as a `tool_call_id`: exactly nine ASCII letters or digits.
+pub fn is_valid_tool_call_id(id: &str) -> bool {
+    id.len() == TOOL_CALL_ID_LEN && id.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// Maps `id` onto a valid Mistral `tool_call_id`.
+///
+/// Valid ids are kept. Others are replaced by nine letters and digits derived from a hash
+/// of the id, so a call and the `tool` message answering it stay paired when both are
+/// normalized.
+pub fn normalize_tool_call_id(id: &str) -> String {
+    if is_valid_tool_call_id(id) {
+        return id.to_string();
+    }
+    // FNV-1a, stable across builds unlike `DefaultHasher`
+    let mut hash = id.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    (0..TOOL_CALL_ID_LEN)
+        .map(|_| {
+            let digit = ID_ALPHABET[(hash % ID_ALPHABET.len() as u64) as usize];
+            hash /= ID_ALPHABET.len() as u64;
+            digit as char
+        })
+        .collect()
+}
+
+/// Rewrites the `tool_calls[].id` and `tool_call_id` fields of a conversation with
+/// [`normalize_tool_call_id`], so a history recorded with another provider can be sent to
+/// Mistral. Calls without an id get one derived from their position in the message, the
+/// same one [`ToolHandler::answer_mistral_tool_calls`] answers them with.
+pub fn normalize_tool_call_ids(messages: &mut [Value]) {
+    for message in messages {
+        if let Some(calls) = message.get_mut("tool_calls").and_then(Value::as_array_mut) {
+            for (index, call) in calls.iter_mut().enumerate() {
+                let id = call.get("id").and_then(Value::as_str).unwrap_or_default();
+                call["id"] = json!(answer_id(id, index));
+            }
+        }
+        if let Some(id) = message.get("tool_call_id").and_then(Value::as_str) {
+            message["tool_call_id"] = json!(normalize_tool_call_id(id));
+        }
+    }
+}
+
+// The normalized id of the call at `index` of an assistant message. Calls without an id
+// hash their position instead, so each call of a message gets its own
+fn answer_id(id: &str, index: usize) -> String {
+    if id.is_empty() {
+        normalize_tool_call_id(&format!("\0{}", index))
+    } else {
+        normalize_tool_call_id(id)
+    }
+}
+
+/// A `tool` message answering the call `tool_call_id` to the tool `name`, with the id
+/// normalized for Mistral.
+pub fn tool_message(tool_call_id: &str, name: &str, content: &str) -> Value {
+    json!({
+        "role": "tool",
+        "tool_call_id": normalize_tool_call_id(tool_call_id),
+        "name": name,
+        "content": content
+    })
+}
+
+/// Extracts the calls from the raw output of a Mistral model, as payloads accepted by
+/// [`ToolHandler::call_tool`].
+///
+/// Both encodings are read: a JSON array of `{"name", "arguments", "id"}` objects after
+/// `[TOOL_CALLS]`, and the newer `[TOOL_CALLS]name[CALL_ID]id[ARGS]{...}` form, repeated
+/// for each call. Text without `[TOOL_CALLS]` has no calls.
+pub fn parse_raw_tool_calls(content: &str) -> Result<Vec<Value>, ToolError> {
+    let Some(start) = content.find(TOOL_CALLS) else {
+        return Ok(Vec::new());
+    };
+    let mut calls = Vec::new();
+    for section in content[start..].split(TOOL_CALLS).skip(1) {
+        let section = section.trim();
+        if section.starts_with('[') || section.starts_with('{') {
+            let entries = match first_json(section)? {
+                Value::Array(entries) => entries,
+                entry => vec![entry],
+            };
+            for entry in entries {
+                let name = entry["name"].as_str().ok_or_else(|| {
+                    ToolError::BadArgs("Missing or invalid 'name' field".to_string())
+                })?;
+                calls.push(payload(name, &entry["arguments"], entry["id"].as_str()));
+            }
+        } else {
+            let (head, arguments) = section.split_once("[ARGS]").ok_or_else(|| {
+                ToolError::BadArgs(format!("Missing [ARGS] after {}", TOOL_CALLS))
+            })?;
+            let (name, id) = match head.split_once("[CALL_ID]") {
+                Some((name, id)) => (name, Some(id.trim())),
+                None => (head, None),
+            };
+            calls.push(payload(name.trim(), &first_json(arguments)?, id));
+        }
+    }
+    Ok(calls)
+}
+
+/// The `[TOOL_RESULTS]` prompt section answering the call `call_id` with `content`.
+pub fn raw_tool_results(call_id: &str, content: &str) -> String {
+    let result = json!({ "content": content, "call_id": call_id });
+    format!("[TOOL_RESULTS]{}[/TOOL_RESULTS]", result)
+}
+
+// The first JSON value of `text`, ignoring whatever follows it
+fn first_json(text: &str) -> Result<Value, ToolError> {
+    serde_json::Deserializer::from_str(text.trim())
+        .into_iter::<Value>()
+        .next()
+        .unwrap_or_else(|| serde_json::from_str(""))
+        .map_err(|err| ToolError::BadArgs(format!("Invalid tool call JSON: {}", err)))
+}
+
+fn payload(name: &str, arguments: &Value, id: Option<&str>) -> Value {
+    let arguments = match arguments {
+        Value::Null => json!({}),
+        arguments => arguments.clone(),
+    };
+    let mut call =
+        json!({ "type": "function", "function": { "name": name, "arguments": arguments } });
+    if let Some(id) = id {
+        call["id"] = json!(id);
+    }
+    call
+}
+
+impl ToolHandler {
+    /// The definition of every tool, in the shape of a Mistral request's `tools`.
+    pub fn mistral_tools(&self) -> Value {
+        Value::Array(
+            self.tools()
+                .map(|tool| self.render_tool(tool, SchemaFlavor::Mistral))
+                .collect(),
+        )
+    }
+
+    /// The `[AVAILABLE_TOOLS]` prompt section listing every tool, for prompting Mistral
+    /// models directly.
+    pub fn mistral_available_tools(&self) -> String {
+        format!(
+            "[AVAILABLE_TOOLS]{}[/AVAILABLE_TOOLS]",
+            self.mistral_tools()
+        )
+    }
+
+    /// Runs every call of a Mistral assistant message, as
+    /// [`ToolHandler::call_all_with_context`] does, and returns a `tool` message answering
+    /// each, in order, as [`ToolHandler::answer_tool_calls`] does. Ids are normalized with
+    /// [`normalize_tool_call_id`], and calls without one get the id
+    /// [`normalize_tool_call_ids`] gives them.
+    pub async fn answer_mistral_tool_calls(
+        &self,
+        message: &Value,
+        context: &ToolContext,
+    ) -> Vec<Value> {
+        let message = self.batch_message(message);
+        let calls = crate::tool_calls(&message);
+        let results = self.call_batch(calls, context).await;
+        calls
+            .iter()
+            .zip(results)
+            .enumerate()
+            .map(|(index, (call, (id, result)))| {
+                let mut answer = self.tool_message(&answer_id(&id, index), result);
+                answer["name"] = json!(call["function"]["name"].as_str().unwrap_or_default());
+                answer
+            })
+            .collect()
+    }
+}
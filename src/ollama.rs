@@ -0,0 +1,1201 @@
+//! Conversations with a model served by Ollama.
+//!
+//! [`Ollama`] is a [`ChatProvider`] for Ollama's `/api/chat` endpoint. [`ChatSession`] owns
+//! a conversation with it: each [`ChatSession::send`] appends the user's message, runs the
+//! model's tool calls with the session's [`ToolHandler`] as [`ChatLoop`] does, and returns
+//! the model's answer as a [`Reply`]. The whole history, tool messages included, stays in
+//! the session for the next turn. [`ChatSession::send_streaming`] streams the replies
+//! instead, passing their text to a callback as it arrives; [`Ollama::chat_stream`] yields
+//! the raw chunks of a single request.
+//!
+//! The module, and the `reqwest` HTTP client it uses, is only compiled with the `ollama`
+//! feature, so applications that only run tools don't depend on an HTTP stack or TLS.
+//!
+//! ```rust,no_run
+//! use tool_calling::ollama::ChatSession;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Looks up the weather in a city
+//! fn weather(city: String) -> String {
+//!     format!("Sunny in {}", city)
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let mut session = ChatSession::builder()
+//!         .model("llama3.1")
+//!         .system("You answer in one sentence.")
+//!         .tools(ToolHandler::default())
+//!         .build()
+//!         .unwrap();
+//!     let reply = session.send("What's the weather in Oslo?").await.unwrap();
+//!     println!("{}", reply.content);
+//! }
+//! ```
+
+use crate::chat::{
+    reply_message, ChatError, ChatEvent, ChatEventFn, ChatLoop, ChatProvider, Metered, ToolChoice,
+    DEFAULT_MAX_ROUNDS,
+};
+use crate::compat::{split_thinking, THINK_END, THINK_START};
+use crate::conversation::ChatMessage;
+use crate::{SchemaFlavor, ToolContext, ToolHandler};
+use futures::future::BoxFuture;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The address Ollama listens on unless configured otherwise.
+pub const DEFAULT_HOST: &str = "http://localhost:11434";
+
+/// Sampling and context settings sent as a request's `options`.
+///
+/// Unset fields are left to the model's defaults. Tool calling tends to be more reliable
+/// at a low `temperature`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ModelOptions {
+    /// Randomness of sampling; `0.0` picks the likeliest token every time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// The size of the context window, in tokens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    /// The random seed, for reproducible replies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    /// Sequences that end the reply when the model writes them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    /// Any other options, such as `top_p` or `num_predict`.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ModelOptions {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Errors returned by a [`ChatSession`].
+#[derive(Debug, Clone, thiserror::Error, PartialEq)]
+pub enum OllamaError {
+    #[error("request to Ollama failed: {0}")]
+    Transport(String),
+    #[error("Ollama returned HTTP {status}: {body}")]
+    Status { status: u16, body: String },
+    #[error("invalid response from Ollama: {0}")]
+    Deserialize(String),
+    #[error("tool '{name}' failed: {message}")]
+    ToolExecution { name: String, message: String },
+    #[error("model still requested tools after {0} rounds")]
+    MaxIterations(usize),
+    #[error("invalid configuration: {0}")]
+    Config(String),
+    #[error("model called '{name}' with the same arguments {times} times: {arguments}")]
+    LoopDetected {
+        name: String,
+        arguments: String,
+        times: usize,
+    },
+    #[error(
+        "model '{model}' does not support tool calling; installed models that do: {}",
+        if compatible.is_empty() { "none".to_string() } else { compatible.join(", ") }
+    )]
+    ToolsUnsupported {
+        model: String,
+        compatible: Vec<String>,
+    },
+}
+
+impl From<ChatError> for OllamaError {
+    fn from(err: ChatError) -> Self {
+        match err {
+            ChatError::Http(message) => OllamaError::Transport(message),
+            ChatError::Upstream { status, body } => OllamaError::Status { status, body },
+            ChatError::BadResponse(message) => OllamaError::Deserialize(message),
+            ChatError::TooManyRounds(rounds) => OllamaError::MaxIterations(rounds),
+            ChatError::Config(message) => OllamaError::Config(message),
+            ChatError::ToolFailed { name, message } => OllamaError::ToolExecution { name, message },
+            ChatError::RepeatedCall {
+                name,
+                arguments,
+                times,
+            } => OllamaError::LoopDetected {
+                name,
+                arguments,
+                times,
+            },
+        }
+    }
+}
+
+/// How failed requests to Ollama are retried.
+///
+/// Requests that fail in transport, such as a reset connection or a timeout, and `502`,
+/// `503` and `504` responses are sent again after a growing delay. Other errors, and
+/// anything the model wrote, are returned at once; a stream that breaks after its first
+/// chunk is not restarted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// The number of retries after the first attempt.
+    pub max_retries: u32,
+    /// The delay before the first retry.
+    pub initial_backoff: Duration,
+    /// The longest delay between two attempts.
+    pub max_backoff: Duration,
+    /// The factor the delay grows by after each retry.
+    pub multiplier: f64,
+}
+
+impl RetryPolicy {
+    /// Never retries. The default of an [`Ollama`] provider.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Retries up to `max_retries` times, with the default delays.
+    pub fn retries(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    /// The delay before retry number `retry`, counting from zero.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let delay = self.initial_backoff.as_secs_f64() * self.multiplier.powi(retry as i32);
+        Duration::from_secs_f64(delay.min(self.max_backoff.as_secs_f64()))
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Three retries, waiting 250ms, then 500ms, then 1s, and never more than 4s.
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(4),
+            multiplier: 2.0,
+        }
+    }
+}
+
+/// How long Ollama keeps a model in memory after a request, as its `keep_alive` says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepAlive {
+    /// Unloads the model this long after the last request; zero unloads it right away.
+    For(Duration),
+    /// Keeps the model loaded until the server stops.
+    Forever,
+}
+
+impl KeepAlive {
+    /// The `keep_alive` of a request.
+    pub fn to_json(&self) -> Value {
+        match self {
+            KeepAlive::For(duration) => json!(format!("{}ms", duration.as_millis())),
+            KeepAlive::Forever => json!(-1),
+        }
+    }
+}
+
+/// A model installed on an Ollama server, as listed by [`Ollama::list_models`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ModelInfo {
+    /// The model's name and tag, e.g. `llama3.1:latest`.
+    pub name: String,
+    /// Its size on disk, in bytes.
+    #[serde(default)]
+    pub size: u64,
+    /// When it was last modified, as an RFC 3339 timestamp.
+    #[serde(default)]
+    pub modified_at: String,
+    /// The digest of its weights.
+    #[serde(default)]
+    pub digest: String,
+    /// Its format, family, parameter size and quantization, as Ollama reports them.
+    #[serde(default)]
+    pub details: Value,
+}
+
+/// The models installed on the Ollama server at [`DEFAULT_HOST`]; see
+/// [`Ollama::list_models`] for other servers.
+pub async fn list_models() -> Result<Vec<ModelInfo>, OllamaError> {
+    Ollama::new("").list_models().await
+}
+
+fn supports_tools(capabilities: &[String]) -> bool {
+    capabilities.iter().any(|capability| capability == "tools")
+}
+
+/// A [`ChatProvider`] sending conversations to a model served by Ollama.
+#[derive(Debug, Clone)]
+pub struct Ollama {
+    client: reqwest::Client,
+    host: String,
+    model: String,
+    options: ModelOptions,
+    api_key: Option<String>,
+    headers: Vec<(String, String)>,
+    retry: RetryPolicy,
+    keep_alive: Option<KeepAlive>,
+    think: Option<bool>,
+}
+
+impl Ollama {
+    /// Creates a provider for `model`, served at [`DEFAULT_HOST`].
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            host: DEFAULT_HOST.to_string(),
+            model: model.into(),
+            options: ModelOptions::default(),
+            api_key: None,
+            headers: Vec::new(),
+            retry: RetryPolicy::none(),
+            keep_alive: None,
+            think: None,
+        }
+    }
+
+    /// Sends requests to the Ollama server at `host` instead, e.g. `http://gpu-box:11434`.
+    pub fn with_host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Sends requests with `client`, e.g. one with its own timeouts, proxy or TLS roots.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Authenticates every request with `api_key` as a bearer token, as a reverse proxy in
+    /// front of Ollama may require.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Adds a header sent with every request.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Retries failed requests as `policy` describes.
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Sends these `options` with every request.
+    pub fn with_options(mut self, options: ModelOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Asks Ollama to keep the model loaded for `keep_alive` after every request, instead
+    /// of the server's default of five minutes.
+    pub fn with_keep_alive(mut self, keep_alive: KeepAlive) -> Self {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+
+    /// Asks a reasoning model to think before answering, or not to, with Ollama's `think`.
+    /// Its reasoning then comes in the message's `thinking` rather than in `<think>` blocks
+    /// in the content.
+    pub fn with_think(mut self, think: bool) -> Self {
+        self.think = Some(think);
+        self
+    }
+
+    /// Loads the model into memory without asking it anything, so that the first real
+    /// request doesn't wait for it. The model then stays loaded as the provider's
+    /// [`KeepAlive`] says.
+    pub async fn preload_model(&self) -> Result<(), OllamaError> {
+        let request = self.request(&[], &[], false);
+        self.post(&request).await?;
+        Ok(())
+    }
+
+    /// Unloads the model from memory now.
+    pub async fn unload_model(&self) -> Result<(), OllamaError> {
+        let mut request = self.request(&[], &[], false);
+        request["keep_alive"] = KeepAlive::For(Duration::ZERO).to_json();
+        self.post(&request).await?;
+        Ok(())
+    }
+
+    /// The models installed on the server, from its `/api/tags`.
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>, OllamaError> {
+        let response = self.fetch("/api/tags", None).await?;
+        serde_json::from_value(response["models"].clone())
+            .map_err(|err| OllamaError::Deserialize(err.to_string()))
+    }
+
+    /// What `model` can do, such as `completion`, `tools` or `vision`, as `/api/show`
+    /// reports it. `None` for servers too old to report capabilities.
+    pub async fn model_capabilities(
+        &self,
+        model: &str,
+    ) -> Result<Option<Vec<String>>, OllamaError> {
+        let response = self
+            .fetch("/api/show", Some(&json!({ "model": model })))
+            .await?;
+        match response.get("capabilities") {
+            Some(capabilities) => serde_json::from_value(capabilities.clone())
+                .map(Some)
+                .map_err(|err| OllamaError::Deserialize(err.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Checks that the provider's model can call tools, failing with
+    /// [`OllamaError::ToolsUnsupported`], which lists the installed models that can, when
+    /// it can't. Models without it answer as if no tools were offered. Passes when the
+    /// server doesn't report capabilities.
+    pub async fn check_tool_support(&self) -> Result<(), OllamaError> {
+        match self.model_capabilities(&self.model).await? {
+            Some(capabilities) if !supports_tools(&capabilities) => {}
+            _ => return Ok(()),
+        }
+        let mut compatible = Vec::new();
+        for model in self.list_models().await? {
+            if self
+                .model_capabilities(&model.name)
+                .await?
+                .is_some_and(|c| supports_tools(&c))
+            {
+                compatible.push(model.name);
+            }
+        }
+        Err(OllamaError::ToolsUnsupported {
+            model: self.model.clone(),
+            compatible,
+        })
+    }
+
+    /// The model requests are sent to.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Sends the conversation with `stream: true` and yields every chunk Ollama sends, as
+    /// JSON. Each chunk's `message` holds the next piece of `content`, or whole
+    /// `tool_calls`; the last one has `"done": true`.
+    pub fn chat_stream<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+    ) -> BoxStream<'a, Result<Value, OllamaError>> {
+        self.stream(messages, tools)
+            .map_err(OllamaError::from)
+            .boxed()
+    }
+
+    fn stream<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+    ) -> BoxStream<'a, Result<Value, ChatError>> {
+        let request = self.request(messages, tools, true);
+        stream::once(async move { self.post(&request).await })
+            .map_ok(chunks)
+            .try_flatten()
+            .boxed()
+    }
+
+    fn request(&self, messages: &[Value], tools: &[Value], stream: bool) -> Value {
+        let mut request = json!({ "model": self.model, "messages": messages, "stream": stream });
+        if !tools.is_empty() {
+            request["tools"] = json!(tools);
+        }
+        if !self.options.is_empty() {
+            request["options"] = json!(self.options);
+        }
+        if let Some(keep_alive) = &self.keep_alive {
+            request["keep_alive"] = keep_alive.to_json();
+        }
+        if let Some(think) = self.think {
+            request["think"] = json!(think);
+        }
+        request
+    }
+
+    // Posts a `/api/chat` request, failing unless it succeeds, with retries
+    async fn post(&self, request: &Value) -> Result<reqwest::Response, ChatError> {
+        self.retrying(|| self.post_once(request)).await
+    }
+
+    // Runs `attempt` until it succeeds, fails for good, or the retries are used up
+    async fn retrying<T, F, Fut>(&self, mut attempt: F) -> Result<T, ChatError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ChatError>>,
+    {
+        let mut retries = 0;
+        loop {
+            match attempt().await {
+                Err(err) if retries < self.retry.max_retries && is_transient(&err) => {
+                    tracing::warn!(error = %err, retry = retries + 1, "retrying Ollama request");
+                    tokio::time::sleep(self.retry.delay(retries)).await;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn post_once(&self, request: &Value) -> Result<reqwest::Response, ChatError> {
+        self.send_once("/api/chat", Some(request)).await
+    }
+
+    // Sends `request` to `path` and reads the JSON response, with retries
+    async fn fetch(&self, path: &str, request: Option<&Value>) -> Result<Value, ChatError> {
+        let body = self
+            .retrying(|| async {
+                self.send_once(path, request)
+                    .await?
+                    .text()
+                    .await
+                    .map_err(|err| ChatError::Http(err.to_string()))
+            })
+            .await?;
+        serde_json::from_str(&body).map_err(|err| ChatError::BadResponse(err.to_string()))
+    }
+
+    // Posts `request` to `path`, or gets it without one, failing unless the server succeeds
+    async fn send_once(
+        &self,
+        path: &str,
+        request: Option<&Value>,
+    ) -> Result<reqwest::Response, ChatError> {
+        let url = format!("{}{}", self.host.trim_end_matches('/'), path);
+        let mut builder = match request {
+            Some(request) => self.client.post(url).json(request),
+            None => self.client.get(url),
+        };
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        let response = builder
+            .send()
+            .await
+            .map_err(|err| ChatError::Http(err.to_string()))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response
+                .text()
+                .await
+                .map_err(|err| ChatError::Http(err.to_string()))?;
+            return Err(ChatError::Upstream {
+                status: status.as_u16(),
+                body,
+            });
+        }
+        Ok(response)
+    }
+}
+
+impl ChatProvider for Ollama {
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        Box::pin(async move {
+            let request = self.request(messages, tools, false);
+            let body = self
+                .retrying(|| async {
+                    self.post_once(&request)
+                        .await?
+                        .text()
+                        .await
+                        .map_err(|err| ChatError::Http(err.to_string()))
+                })
+                .await?;
+            let response: Value = serde_json::from_str(&body)
+                .map_err(|err| ChatError::BadResponse(err.to_string()))?;
+            if !response["message"].is_object() {
+                return Err(ChatError::BadResponse(
+                    "response has no 'message'".to_string(),
+                ));
+            }
+            Ok(response)
+        })
+    }
+
+    fn flavor(&self) -> SchemaFlavor {
+        SchemaFlavor::Ollama
+    }
+}
+
+// Failures worth another attempt: the request never completed, or the server was briefly
+// unavailable
+fn is_transient(err: &ChatError) -> bool {
+    match err {
+        ChatError::Http(_) => true,
+        ChatError::Upstream { status, .. } => matches!(status, 502..=504),
+        _ => false,
+    }
+}
+
+// The newline-delimited JSON chunks of a streamed response
+fn chunks(response: reqwest::Response) -> BoxStream<'static, Result<Value, ChatError>> {
+    let state = (response, Vec::new(), false);
+    stream::try_unfold(state, |(mut response, mut buffer, mut done)| async move {
+        loop {
+            if let Some(end) = buffer.iter().position(|&byte| byte == b'\n') {
+                let line = buffer.drain(..=end).collect::<Vec<_>>();
+                if let Some(chunk) = parse_chunk(&line)? {
+                    return Ok(Some((chunk, (response, buffer, done))));
+                }
+                continue;
+            }
+            if done {
+                let line = std::mem::take(&mut buffer);
+                return Ok(parse_chunk(&line)?.map(|chunk| (chunk, (response, buffer, done))));
+            }
+            match response.chunk().await {
+                Ok(Some(bytes)) => buffer.extend_from_slice(&bytes),
+                Ok(None) => done = true,
+                Err(err) => return Err(ChatError::Http(err.to_string())),
+            }
+        }
+    })
+    .boxed()
+}
+
+fn parse_chunk(line: &[u8]) -> Result<Option<Value>, ChatError> {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+    let chunk: Value =
+        serde_json::from_slice(line).map_err(|err| ChatError::BadResponse(err.to_string()))?;
+    match chunk["error"].as_str() {
+        Some(error) => Err(ChatError::BadResponse(error.to_string())),
+        None => Ok(Some(chunk)),
+    }
+}
+
+// Streams each reply from Ollama, passing the content to a callback as it arrives, and
+// returns the response the chunks add up to
+struct Streaming<F> {
+    ollama: Ollama,
+    on_token: Mutex<F>,
+}
+
+impl<F: FnMut(&str) + Send> ChatProvider for Streaming<F> {
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        Box::pin(async move {
+            let mut chunks = self.ollama.stream(messages, tools);
+            let mut content = ThinkFilter::default();
+            let mut thinking = String::new();
+            let mut tool_calls = Vec::new();
+            let mut done = json!({ "done": true });
+            let emit = |token: &str| {
+                if !token.is_empty() {
+                    (self.on_token.lock().unwrap())(token);
+                }
+            };
+            while let Some(chunk) = chunks.try_next().await? {
+                if chunk["done"] == true {
+                    done = chunk.clone();
+                }
+                let message = &chunk["message"];
+                if let Some(token) = message["content"].as_str() {
+                    emit(&content.push(token));
+                }
+                if let Some(token) = message["thinking"].as_str() {
+                    thinking.push_str(token);
+                }
+                if let Some(calls) = message["tool_calls"].as_array() {
+                    tool_calls.extend(calls.iter().cloned());
+                }
+            }
+            emit(&content.finish());
+            thinking.push_str(&content.thinking);
+            let mut message = json!({ "role": "assistant", "content": content.answer });
+            if !thinking.trim().is_empty() {
+                message["thinking"] = json!(thinking.trim());
+            }
+            if !tool_calls.is_empty() {
+                message["tool_calls"] = Value::Array(tool_calls);
+            }
+            // The final chunk carries the reason and the usage of the whole response
+            done["message"] = message;
+            Ok(done)
+        })
+    }
+
+    fn flavor(&self) -> SchemaFlavor {
+        SchemaFlavor::Ollama
+    }
+}
+
+// Separates the `<think>` blocks of streamed content from the answer, holding back the end
+// of a token that may be the start of a tag split across tokens
+#[derive(Default)]
+struct ThinkFilter {
+    answer: String,
+    thinking: String,
+    inside: bool,
+    pending: String,
+}
+
+impl ThinkFilter {
+    // Takes the next token, returning the text of the answer it completes
+    fn push(&mut self, token: &str) -> String {
+        self.pending.push_str(token);
+        let before = self.answer.len();
+        loop {
+            let tag = if self.inside { THINK_END } else { THINK_START };
+            if let Some(at) = self.pending.find(tag) {
+                let text = self.pending[..at].to_string();
+                self.pending.drain(..at + tag.len());
+                self.take(&text);
+                self.inside = !self.inside;
+                continue;
+            }
+            let keep = (1..tag.len())
+                .rev()
+                .find(|&len| self.pending.ends_with(&tag[..len]))
+                .unwrap_or(0);
+            let text = self.pending[..self.pending.len() - keep].to_string();
+            self.pending.drain(..text.len());
+            self.take(&text);
+            break;
+        }
+        self.answer[before..].to_string()
+    }
+
+    // Flushes whatever was held back, returning the text of the answer it completes
+    fn finish(&mut self) -> String {
+        let before = self.answer.len();
+        let text = std::mem::take(&mut self.pending);
+        self.take(&text);
+        self.answer[before..].to_string()
+    }
+
+    fn take(&mut self, text: &str) {
+        if self.inside {
+            self.thinking.push_str(text);
+        } else if self.answer.is_empty() {
+            // The answer starts after the blank lines that follow a block
+            self.answer.push_str(text.trim_start());
+        } else {
+            self.answer.push_str(text);
+        }
+    }
+}
+
+/// The tokens and time Ollama reports spending on responses.
+///
+/// Counts and durations add up, so the usage of several responses is their sum.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// The number of prompt tokens evaluated (`prompt_eval_count`).
+    pub prompt_eval_count: u64,
+    /// The number of tokens generated (`eval_count`).
+    pub eval_count: u64,
+    /// The time spent on the whole request (`total_duration`).
+    pub total_duration: Duration,
+    /// The time spent loading the model (`load_duration`).
+    pub load_duration: Duration,
+    /// The time spent evaluating the prompt (`prompt_eval_duration`).
+    pub prompt_eval_duration: Duration,
+    /// The time spent generating tokens (`eval_duration`).
+    pub eval_duration: Duration,
+}
+
+impl Usage {
+    /// Reads the usage of a chat response, or of the final chunk of a streamed one. Missing
+    /// fields count as zero.
+    pub fn from_response(response: &Value) -> Self {
+        let count = |field: &str| response[field].as_u64().unwrap_or_default();
+        Self {
+            prompt_eval_count: count("prompt_eval_count"),
+            eval_count: count("eval_count"),
+            total_duration: Duration::from_nanos(count("total_duration")),
+            load_duration: Duration::from_nanos(count("load_duration")),
+            prompt_eval_duration: Duration::from_nanos(count("prompt_eval_duration")),
+            eval_duration: Duration::from_nanos(count("eval_duration")),
+        }
+    }
+
+    /// Prompt and generated tokens together.
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_eval_count + self.eval_count
+    }
+
+    /// Generated tokens per second of generation, if any time was reported.
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let seconds = self.eval_duration.as_secs_f64();
+        (seconds > 0.0).then(|| self.eval_count as f64 / seconds)
+    }
+}
+
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_eval_count += other.prompt_eval_count;
+        self.eval_count += other.eval_count;
+        self.total_duration += other.total_duration;
+        self.load_duration += other.load_duration;
+        self.prompt_eval_duration += other.prompt_eval_duration;
+        self.eval_duration += other.eval_duration;
+    }
+}
+
+/// The model's answer to a [`ChatSession::send`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    /// The text of the answer.
+    pub content: String,
+    /// The assistant message as Ollama returned it, without any `<think>` blocks.
+    pub message: Value,
+    /// The number of tool calls run before the model answered.
+    pub tool_calls: usize,
+    /// The number of requests made to the model, one per round of tool calls plus the
+    /// answer.
+    pub rounds: usize,
+    /// Why the model stopped writing the answer, as Ollama reports it: `stop` when it
+    /// finished, `length` when it ran out of tokens.
+    pub done_reason: Option<String>,
+    /// The tokens and time spent on every request of this turn.
+    pub usage: Usage,
+    /// What a reasoning model thought before answering, from the message's `thinking` or
+    /// the `<think>` blocks of its content, which are left out of [`Reply::content`].
+    pub thinking: Option<String>,
+}
+
+/// Builds a [`ChatSession`]; see [`ChatSession::builder`].
+#[derive(Default)]
+pub struct ChatSessionBuilder {
+    model: Option<String>,
+    host: Option<String>,
+    client: Option<reqwest::Client>,
+    api_key: Option<String>,
+    headers: Vec<(String, String)>,
+    retry: Option<RetryPolicy>,
+    keep_alive: Option<KeepAlive>,
+    think: Option<bool>,
+    options: ModelOptions,
+    system: Option<String>,
+    examples: Vec<Value>,
+    history: Vec<Value>,
+    handler: Option<ToolHandler>,
+    context: ToolContext,
+    max_rounds: Option<usize>,
+    events: Option<ChatEventFn>,
+    fail_on_tool_errors: bool,
+    tool_choice: ToolChoice,
+    check_tool_support: bool,
+    max_session_rounds: Option<usize>,
+    max_repeated_calls: Option<usize>,
+}
+
+impl ChatSessionBuilder {
+    /// The Ollama model to talk to, e.g. `llama3.1`. Required.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// The base URL of the Ollama server to use instead of [`DEFAULT_HOST`], which may
+    /// include a path, e.g. `https://gateway.example.com/ollama` behind a reverse proxy.
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+
+    /// Sends requests with `client`; see [`Ollama::with_client`].
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Authenticates every request with `api_key` as a bearer token.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Adds a header sent with every request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Retries failed requests as `policy` describes; by default they fail at once.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Asks a reasoning model to think before answering, or not to; see
+    /// [`Ollama::with_think`]. Either way, [`Reply::thinking`] holds what it thought.
+    pub fn think(mut self, think: bool) -> Self {
+        self.think = Some(think);
+        self
+    }
+
+    /// Keeps the model loaded for `keep_alive` after every request; see
+    /// [`Ollama::with_keep_alive`].
+    pub fn keep_alive(mut self, keep_alive: KeepAlive) -> Self {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+
+    /// Sends these model `options` with every request, replacing any set before.
+    pub fn options(mut self, options: ModelOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Samples with this `temperature`.
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.options.temperature = Some(temperature);
+        self
+    }
+
+    /// Gives the model a context window of `num_ctx` tokens.
+    pub fn num_ctx(mut self, num_ctx: u32) -> Self {
+        self.options.num_ctx = Some(num_ctx);
+        self
+    }
+
+    /// Seeds sampling, for reproducible replies.
+    pub fn seed(mut self, seed: i64) -> Self {
+        self.options.seed = Some(seed);
+        self
+    }
+
+    /// Ends replies at any of these sequences.
+    pub fn stop<S: Into<String>>(mut self, stop: impl IntoIterator<Item = S>) -> Self {
+        self.options.stop = stop.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Starts the conversation with this system message. Without one the model's own
+    /// default applies.
+    pub fn system(mut self, prompt: impl Into<String>) -> Self {
+        self.system = Some(prompt.into());
+        self
+    }
+
+    /// Adds a few-shot example: a user message and the answer the model should give to it.
+    /// Examples follow the system message, in the order they were added.
+    pub fn example(mut self, user: impl Into<String>, assistant: impl Into<String>) -> Self {
+        self.examples
+            .push(json!({ "role": "user", "content": user.into() }));
+        self.examples
+            .push(json!({ "role": "assistant", "content": assistant.into() }));
+        self
+    }
+
+    /// Continues an earlier conversation: `messages` follow the system message and the
+    /// examples, as if they had been sent in this session.
+    pub fn history(mut self, messages: Vec<Value>) -> Self {
+        self.history = messages;
+        self
+    }
+
+    /// Offers the tools of `handler` to the model and runs its calls with it. Without one
+    /// the model is offered no tools.
+    pub fn tools(mut self, handler: ToolHandler) -> Self {
+        self.handler = Some(handler);
+        self
+    }
+
+    /// Runs every tool call with this request-scoped context.
+    pub fn context(mut self, context: ToolContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Fails a [`ChatSession::send`] with [`OllamaError::MaxIterations`] when the model still
+    /// asks for tools after `rounds` requests. Defaults to [`DEFAULT_MAX_ROUNDS`].
+    pub fn max_rounds(mut self, rounds: usize) -> Self {
+        self.max_rounds = Some(rounds);
+        self
+    }
+
+    /// Fails any message sent once the session as a whole has made `rounds` requests to the
+    /// model, with [`OllamaError::MaxIterations`]. Unlike
+    /// [`ChatSessionBuilder::max_rounds`], which limits each message, this bounds the whole
+    /// conversation.
+    pub fn max_session_rounds(mut self, rounds: usize) -> Self {
+        self.max_session_rounds = Some(rounds);
+        self
+    }
+
+    /// Fails a [`ChatSession::send`] with [`OllamaError::LoopDetected`] when the model asks
+    /// for the same tool with the same arguments more than `times` times; see
+    /// [`ChatLoop::with_max_repeated_calls`].
+    pub fn max_repeated_calls(mut self, times: usize) -> Self {
+        self.max_repeated_calls = Some(times);
+        self
+    }
+
+    /// Calls `hook` with every [`ChatEvent`] of the conversation, e.g. to show progress in
+    /// a UI. Sessions report nothing without one.
+    pub fn on_event(mut self, hook: impl Fn(&ChatEvent<'_>) + Send + Sync + 'static) -> Self {
+        self.events = Some(Arc::new(hook));
+        self
+    }
+
+    /// Fails a [`ChatSession::send`] with [`OllamaError::ToolExecution`] as soon as a tool
+    /// call fails. By default the error is reported to the model, which may try again.
+    pub fn fail_on_tool_errors(mut self) -> Self {
+        self.fail_on_tool_errors = true;
+        self
+    }
+
+    /// Asks the model to follow `choice` in its first reply to every message, e.g. to make
+    /// it call a `search` tool before answering. Ollama takes no tool choice itself, so the
+    /// session offers only the named tool, or none, and fails with
+    /// [`OllamaError::Deserialize`] if the model answers without the required call.
+    pub fn tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.tool_choice = choice;
+        self
+    }
+
+    /// Checks that the model can call tools before the first message is sent, failing it
+    /// with [`OllamaError::ToolsUnsupported`] if not; see [`Ollama::check_tool_support`].
+    pub fn check_tool_support(mut self) -> Self {
+        self.check_tool_support = true;
+        self
+    }
+
+    /// Creates the session, failing with [`OllamaError::Config`] when no model was given.
+    pub fn build(self) -> Result<ChatSession, OllamaError> {
+        let model = self
+            .model
+            .ok_or_else(|| OllamaError::Config("a model is required".to_string()))?;
+        let mut provider = Ollama::new(model).with_options(self.options);
+        if let Some(host) = self.host {
+            provider = provider.with_host(host);
+        }
+        if let Some(client) = self.client {
+            provider = provider.with_client(client);
+        }
+        if let Some(api_key) = self.api_key {
+            provider = provider.with_api_key(api_key);
+        }
+        for (name, value) in self.headers {
+            provider = provider.with_header(name, value);
+        }
+        if let Some(policy) = self.retry {
+            provider = provider.with_retry(policy);
+        }
+        if let Some(keep_alive) = self.keep_alive {
+            provider = provider.with_keep_alive(keep_alive);
+        }
+        if let Some(think) = self.think {
+            provider = provider.with_think(think);
+        }
+        let messages = self
+            .system
+            .map(|prompt| json!({ "role": "system", "content": prompt }))
+            .into_iter()
+            .chain(self.examples)
+            .chain(self.history)
+            .collect();
+        Ok(ChatSession {
+            provider,
+            handler: self.handler.unwrap_or_else(ToolHandler::empty),
+            context: self.context,
+            max_rounds: self.max_rounds.unwrap_or(DEFAULT_MAX_ROUNDS),
+            events: self.events,
+            fail_on_tool_errors: self.fail_on_tool_errors,
+            tool_choice: self.tool_choice,
+            check_tool_support: self.check_tool_support,
+            max_session_rounds: self.max_session_rounds,
+            max_repeated_calls: self.max_repeated_calls,
+            rounds_used: 0,
+            messages,
+            usage: Usage::default(),
+        })
+    }
+}
+
+/// A conversation with an Ollama model that runs its tool calls.
+pub struct ChatSession {
+    provider: Ollama,
+    handler: ToolHandler,
+    context: ToolContext,
+    max_rounds: usize,
+    events: Option<ChatEventFn>,
+    fail_on_tool_errors: bool,
+    tool_choice: ToolChoice,
+    check_tool_support: bool,
+    max_session_rounds: Option<usize>,
+    max_repeated_calls: Option<usize>,
+    rounds_used: usize,
+    messages: Vec<Value>,
+    usage: Usage,
+}
+
+impl ChatSession {
+    /// Starts building a session.
+    pub fn builder() -> ChatSessionBuilder {
+        ChatSessionBuilder::default()
+    }
+
+    /// Sends a user message and returns the model's answer. Every round of tool calls the
+    /// model makes is run and answered, until it replies without calling tools or the
+    /// session's [`ChatSessionBuilder::max_rounds`] are used up. The message, the tool rounds
+    /// and the answer are added to the history; a turn that fails leaves it as it was, so the
+    /// message can be sent again.
+    pub async fn send(&mut self, user_msg: impl Into<String>) -> Result<Reply, OllamaError> {
+        self.send_message(ChatMessage::user(user_msg)).await
+    }
+
+    /// Like [`ChatSession::send`], sending a message built with [`ChatMessage`], e.g. one
+    /// with images attached for a vision model:
+    /// `ChatMessage::user("What's in this picture?").with_image(base64)`.
+    pub async fn send_message(&mut self, message: ChatMessage) -> Result<Reply, OllamaError> {
+        let provider = self.provider.clone();
+        self.converse(message.to_json(), &provider).await
+    }
+
+    /// Like [`ChatSession::send`], streaming every reply and passing its text to `on_token`
+    /// as it arrives, so it can be shown while the model is still writing.
+    pub async fn send_streaming(
+        &mut self,
+        user_msg: impl Into<String>,
+        on_token: impl FnMut(&str) + Send,
+    ) -> Result<Reply, OllamaError> {
+        let provider = Streaming {
+            ollama: self.provider.clone(),
+            on_token: Mutex::new(on_token),
+        };
+        self.converse(ChatMessage::user(user_msg).to_json(), &provider)
+            .await
+    }
+
+    async fn converse(
+        &mut self,
+        message: Value,
+        provider: &dyn ChatProvider,
+    ) -> Result<Reply, OllamaError> {
+        if self.check_tool_support {
+            self.provider.check_tool_support().await?;
+            self.check_tool_support = false;
+        }
+        let mut max_rounds = self.max_rounds;
+        if let Some(cap) = self.max_session_rounds {
+            if self.rounds_used >= cap {
+                return Err(OllamaError::MaxIterations(cap));
+            }
+            max_rounds = max_rounds.min(cap - self.rounds_used);
+        }
+        let start = self.messages.len();
+        self.messages.push(message);
+        let before = self.messages.len();
+        let provider = Metered::new(provider, Usage::from_response);
+        let mut chat = ChatLoop::new(&self.handler, &provider)
+            .with_context(self.context.clone())
+            .with_max_rounds(max_rounds)
+            .with_tool_choice(self.tool_choice.clone());
+        if let Some(events) = &self.events {
+            chat = chat.with_events(Arc::clone(events));
+        }
+        if self.fail_on_tool_errors {
+            chat = chat.fail_on_tool_errors();
+        }
+        if let Some(times) = self.max_repeated_calls {
+            chat = chat.with_max_repeated_calls(times);
+        }
+        let response = chat.run(&mut self.messages).await;
+        // A round counts once its request is sent, whether or not it got an answer
+        let rounds = provider.requests();
+        let usage = provider.into_usage();
+        self.usage += usage;
+        self.rounds_used += rounds;
+        let response = match response {
+            Ok(response) => response,
+            Err(err) => {
+                self.messages.truncate(start);
+                return Err(match (err, self.max_session_rounds) {
+                    (ChatError::TooManyRounds(_), Some(cap)) if self.rounds_used >= cap => {
+                        OllamaError::MaxIterations(cap)
+                    }
+                    (err, _) => err.into(),
+                });
+            }
+        };
+        let mut message = reply_message(&response).clone();
+        let mut content = message["content"].as_str().unwrap_or_default().to_string();
+        let mut thinking = message["thinking"]
+            .as_str()
+            .map(str::trim)
+            .filter(|thinking| !thinking.is_empty())
+            .map(str::to_string);
+        if let (answer, Some(inline)) = split_thinking(&content) {
+            content = answer;
+            message["content"] = json!(content);
+            thinking = Some(match thinking {
+                Some(thinking) => format!("{}\n{}", thinking, inline),
+                None => inline,
+            });
+        }
+        let tool_calls = self.messages[before..]
+            .iter()
+            .filter(|message| message["role"] == "tool")
+            .count();
+        self.messages.push(message.clone());
+        Ok(Reply {
+            content,
+            message,
+            tool_calls,
+            rounds,
+            done_reason: response["done_reason"].as_str().map(str::to_string),
+            usage,
+            thinking,
+        })
+    }
+
+    /// The number of requests made to the model in this session so far, failed turns
+    /// included.
+    pub fn rounds_used(&self) -> usize {
+        self.rounds_used
+    }
+
+    /// The tokens and time spent on every request of the session so far, failed turns
+    /// included.
+    pub fn usage(&self) -> Usage {
+        self.usage
+    }
+
+    /// Loads the session's model ahead of the first message; see [`Ollama::preload_model`].
+    pub async fn preload_model(&self) -> Result<(), OllamaError> {
+        self.provider.preload_model().await
+    }
+
+    /// Every message of the conversation so far, oldest first.
+    pub fn history(&self) -> &[Value] {
+        &self.messages
+    }
+
+    /// The handler running the model's tool calls.
+    pub fn handler(&self) -> &ToolHandler {
+        &self.handler
+    }
+}
@@ -0,0 +1,457 @@
+//! Conversations with a model behind any OpenAI-compatible chat completions API.
+//!
+//! [`OpenAi`] is a [`ChatProvider`] for `POST {base_url}/chat/completions`, as served by
+//! OpenAI and by most hosted and local servers (vLLM, llama.cpp, LM Studio, OpenRouter,
+//! Groq, Ollama's `/v1`, ...). [`ChatSession`] owns a conversation with it: each
+//! [`ChatSession::send`] appends the user's message, runs the model's `tool_calls` with the
+//! session's [`ToolHandler`] as [`ChatLoop`] does, answering each with a `tool` message
+//! carrying its `tool_call_id`, and returns the model's answer as a [`Reply`].
+//!
+//! ```rust,no_run
+//! use tool_calling::openai::ChatSession;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Looks up the weather in a city
+//! fn weather(city: String) -> String {
+//!     format!("Sunny in {}", city)
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let mut session = ChatSession::builder()
+//!         .model("gpt-4o-mini")
+//!         .api_key(std::env::var("OPENAI_API_KEY").unwrap())
+//!         .tools(ToolHandler::default())
+//!         .build()
+//!         .unwrap();
+//!     let reply = session.send("What's the weather in Oslo?").await.unwrap();
+//!     println!("{}", reply.content);
+//! }
+//! ```
+
+use crate::chat::{
+    reply_message, ChatError, ChatEvent, ChatEventFn, ChatLoop, ChatProvider, Metered, ToolChoice,
+    DEFAULT_MAX_ROUNDS,
+};
+use crate::conversation::ChatMessage;
+use crate::{SchemaFlavor, ToolContext, ToolHandler};
+use futures::future::BoxFuture;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+
+/// The API requests go to unless configured otherwise.
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// A [`ChatProvider`] sending conversations to an OpenAI-compatible chat completions API.
+#[derive(Debug, Clone)]
+pub struct OpenAi {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    headers: Vec<(String, String)>,
+    params: Map<String, Value>,
+}
+
+impl OpenAi {
+    /// Creates a provider for `model`, served at [`DEFAULT_BASE_URL`].
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            model: model.into(),
+            api_key: None,
+            headers: Vec::new(),
+            params: Map::new(),
+        }
+    }
+
+    /// Sends requests to the API at `base_url` instead, e.g. `http://localhost:8000/v1`.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Authenticates every request with `api_key` as a bearer token.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Sends requests with `client`, e.g. one with its own timeouts, proxy or TLS roots.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Adds a header sent with every request.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sends `value` as the request field `name` with every request, e.g. `temperature`,
+    /// `max_tokens` or `seed`.
+    pub fn with_param(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.params.insert(name.into(), value);
+        self
+    }
+
+    /// The model requests are sent to.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn request(&self, messages: &[Value], tools: &[Value], choice: Option<&ToolChoice>) -> Value {
+        let mut request = Value::Object(self.params.clone());
+        request["model"] = json!(self.model);
+        request["messages"] = json!(messages);
+        if !tools.is_empty() {
+            request["tools"] = json!(tools);
+        }
+        if let Some(choice) = choice.and_then(|choice| choice.render(SchemaFlavor::OpenAi)) {
+            request["tool_choice"] = choice;
+        }
+        request
+    }
+
+    async fn post(&self, request: &Value) -> Result<Value, ChatError> {
+        let url = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
+        let mut builder = self.client.post(url).json(request);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        let response = builder
+            .send()
+            .await
+            .map_err(|err| ChatError::Http(err.to_string()))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|err| ChatError::Http(err.to_string()))?;
+        if !status.is_success() {
+            return Err(ChatError::Upstream {
+                status: status.as_u16(),
+                body,
+            });
+        }
+        let response: Value =
+            serde_json::from_str(&body).map_err(|err| ChatError::BadResponse(err.to_string()))?;
+        if !response["choices"][0]["message"].is_object() {
+            return Err(ChatError::BadResponse(
+                "response has no 'choices[0].message'".to_string(),
+            ));
+        }
+        Ok(response)
+    }
+}
+
+impl ChatProvider for OpenAi {
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        Box::pin(async move { self.post(&self.request(messages, tools, None)).await })
+    }
+
+    fn chat_with_choice<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+        choice: &'a ToolChoice,
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        Box::pin(async move {
+            self.post(&self.request(messages, tools, Some(choice)))
+                .await
+        })
+    }
+}
+
+/// The tokens a chat completions API reports using, from a response's `usage`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Tokens in the prompts.
+    pub prompt_tokens: u64,
+    /// Tokens generated.
+    pub completion_tokens: u64,
+}
+
+impl Usage {
+    /// Reads the `usage` of a chat completion response. Missing fields count as zero.
+    pub fn from_response(response: &Value) -> Self {
+        let count = |field: &str| response["usage"][field].as_u64().unwrap_or_default();
+        Self {
+            prompt_tokens: count("prompt_tokens"),
+            completion_tokens: count("completion_tokens"),
+        }
+    }
+
+    /// Prompt and generated tokens together.
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+}
+
+/// The model's answer to a [`ChatSession::send`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    /// The text of the answer.
+    pub content: String,
+    /// The assistant message as the API returned it.
+    pub message: Value,
+    /// The number of tool calls run before the model answered.
+    pub tool_calls: usize,
+    /// The number of requests made to the model, one per round of tool calls plus the
+    /// answer.
+    pub rounds: usize,
+    /// Why the model stopped writing the answer: `stop` when it finished, `length` when it
+    /// ran out of tokens.
+    pub finish_reason: Option<String>,
+    /// The tokens used by every request of this turn.
+    pub usage: Usage,
+}
+
+/// Builds a [`ChatSession`]; see [`ChatSession::builder`].
+#[derive(Default)]
+pub struct ChatSessionBuilder {
+    model: Option<String>,
+    base_url: Option<String>,
+    api_key: Option<String>,
+    client: Option<reqwest::Client>,
+    headers: Vec<(String, String)>,
+    params: Map<String, Value>,
+    system: Option<String>,
+    history: Vec<Value>,
+    handler: Option<ToolHandler>,
+    context: ToolContext,
+    max_rounds: Option<usize>,
+    tool_choice: ToolChoice,
+    events: Option<ChatEventFn>,
+}
+
+impl ChatSessionBuilder {
+    /// The model to talk to, e.g. `gpt-4o-mini`. Required.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// The base URL of the API to use instead of [`DEFAULT_BASE_URL`], e.g.
+    /// `http://localhost:8000/v1` for a local vLLM server.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Authenticates every request with `api_key` as a bearer token.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Sends requests with `client`; see [`OpenAi::with_client`].
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Adds a header sent with every request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sends `value` as the request field `name` with every request; see
+    /// [`OpenAi::with_param`].
+    pub fn param(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.params.insert(name.into(), value);
+        self
+    }
+
+    /// Samples with this `temperature`.
+    pub fn temperature(self, temperature: f64) -> Self {
+        self.param("temperature", json!(temperature))
+    }
+
+    /// Starts the conversation with this system message.
+    pub fn system(mut self, prompt: impl Into<String>) -> Self {
+        self.system = Some(prompt.into());
+        self
+    }
+
+    /// Continues an earlier conversation: `messages` follow the system message, as if they
+    /// had been sent in this session.
+    pub fn history(mut self, messages: Vec<Value>) -> Self {
+        self.history = messages;
+        self
+    }
+
+    /// Offers the tools of `handler` to the model and runs its calls with it. Without one
+    /// the model is offered no tools.
+    pub fn tools(mut self, handler: ToolHandler) -> Self {
+        self.handler = Some(handler);
+        self
+    }
+
+    /// Runs every tool call with this request-scoped context.
+    pub fn context(mut self, context: ToolContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Fails a [`ChatSession::send`] with [`ChatError::TooManyRounds`] when the model still
+    /// asks for tools after `rounds` requests. Defaults to [`DEFAULT_MAX_ROUNDS`].
+    pub fn max_rounds(mut self, rounds: usize) -> Self {
+        self.max_rounds = Some(rounds);
+        self
+    }
+
+    /// Asks the model to follow `choice` in its first reply to every message, sent as the
+    /// request's `tool_choice`.
+    pub fn tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.tool_choice = choice;
+        self
+    }
+
+    /// Calls `hook` with every [`ChatEvent`] of the conversation.
+    pub fn on_event(mut self, hook: impl Fn(&ChatEvent<'_>) + Send + Sync + 'static) -> Self {
+        self.events = Some(Arc::new(hook));
+        self
+    }
+
+    /// Creates the session, failing with [`ChatError::Config`] when no model was given.
+    pub fn build(self) -> Result<ChatSession, ChatError> {
+        let model = self
+            .model
+            .ok_or_else(|| ChatError::Config("a model is required".to_string()))?;
+        let mut provider = OpenAi::new(model);
+        if let Some(base_url) = self.base_url {
+            provider = provider.with_base_url(base_url);
+        }
+        if let Some(api_key) = self.api_key {
+            provider = provider.with_api_key(api_key);
+        }
+        if let Some(client) = self.client {
+            provider = provider.with_client(client);
+        }
+        for (name, value) in self.headers {
+            provider = provider.with_header(name, value);
+        }
+        provider.params = self.params;
+        let messages = self
+            .system
+            .map(|prompt| json!({ "role": "system", "content": prompt }))
+            .into_iter()
+            .chain(self.history)
+            .collect();
+        Ok(ChatSession {
+            provider,
+            handler: self.handler.unwrap_or_else(ToolHandler::empty),
+            context: self.context,
+            max_rounds: self.max_rounds.unwrap_or(DEFAULT_MAX_ROUNDS),
+            tool_choice: self.tool_choice,
+            events: self.events,
+            messages,
+            usage: Usage::default(),
+        })
+    }
+}
+
+/// A conversation with a model behind an OpenAI-compatible API, with tools.
+pub struct ChatSession {
+    provider: OpenAi,
+    handler: ToolHandler,
+    context: ToolContext,
+    max_rounds: usize,
+    tool_choice: ToolChoice,
+    events: Option<ChatEventFn>,
+    messages: Vec<Value>,
+    usage: Usage,
+}
+
+impl ChatSession {
+    /// Starts building a session.
+    pub fn builder() -> ChatSessionBuilder {
+        ChatSessionBuilder::default()
+    }
+
+    /// Sends a user message and returns the model's answer. Every round of tool calls the
+    /// model makes is run and answered, until it replies without calling tools or the
+    /// session's [`ChatSessionBuilder::max_rounds`] are used up. The message, the tool rounds
+    /// and the answer are added to the history; a turn that fails leaves it as it was, so the
+    /// message can be sent again.
+    pub async fn send(&mut self, user_msg: impl Into<String>) -> Result<Reply, ChatError> {
+        self.send_message(ChatMessage::user(user_msg)).await
+    }
+
+    /// Like [`ChatSession::send`], sending a message built with [`ChatMessage`].
+    pub async fn send_message(&mut self, message: ChatMessage) -> Result<Reply, ChatError> {
+        let start = self.messages.len();
+        self.messages.push(message.to_json());
+        let before = self.messages.len();
+        let provider = Metered::new(&self.provider, Usage::from_response);
+        let mut chat = ChatLoop::new(&self.handler, &provider)
+            .with_context(self.context.clone())
+            .with_max_rounds(self.max_rounds)
+            .with_tool_choice(self.tool_choice.clone());
+        if let Some(events) = &self.events {
+            chat = chat.with_events(Arc::clone(events));
+        }
+        let response = chat.run(&mut self.messages).await;
+        // A round counts once its request is sent, whether or not it got an answer
+        let rounds = provider.requests();
+        let usage = provider.into_usage();
+        self.usage += usage;
+        let response = match response {
+            Ok(response) => response,
+            Err(err) => {
+                self.messages.truncate(start);
+                return Err(err);
+            }
+        };
+        let message = reply_message(&response).clone();
+        let tool_calls = self.messages[before..]
+            .iter()
+            .filter(|m| m["role"] == "tool")
+            .count();
+        self.messages.push(message.clone());
+        Ok(Reply {
+            content: message["content"].as_str().unwrap_or_default().to_string(),
+            message,
+            tool_calls,
+            rounds,
+            finish_reason: response["choices"][0]["finish_reason"]
+                .as_str()
+                .map(str::to_string),
+            usage,
+        })
+    }
+
+    /// The tokens used by every request of the session so far, failed turns included.
+    pub fn usage(&self) -> Usage {
+        self.usage
+    }
+
+    /// Every message of the conversation so far, oldest first.
+    pub fn history(&self) -> &[Value] {
+        &self.messages
+    }
+
+    /// The handler running the model's tool calls.
+    pub fn handler(&self) -> &ToolHandler {
+        &self.handler
+    }
+}
@@ -0,0 +1,192 @@
+//! Describing tools as an OpenAPI 3.1 document.
+//!
+//! [`ToolHandler::openapi_spec`] documents each tool as a `POST /tools/{name}` operation,
+//! the endpoints served by `tool_calling::axum::router`: the operation id is the tool's
+//! name, its summary and description come from the tool's description, and the request body
+//! is the tool's parameter schema. Responses reference the shared `ToolCallResult` and
+//! `ToolError` component schemas.
+//!
+//! ```rust
+//! use tool_calling::openapi::OpenApiOptions;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers. Overflow wraps around.
+//! fn add(a: i32, b: i32) -> String {
+//!     a.wrapping_add(b).to_string()
+//! }
+//!
+//! let handler = ToolHandler::default();
+//! let spec = handler.openapi_spec_with(
+//!     &OpenApiOptions::new("Calculator", "1.2.0").with_server("https://tools.example.com"),
+//! );
+//! assert_eq!(spec["openapi"], "3.1.0");
+//! let operation = &spec["paths"]["/tools/add"]["post"];
+//! assert_eq!(operation["operationId"], "add");
+//! assert_eq!(operation["summary"], "Adds two integers.");
+//! ```
+
+use crate::schema::first_sentence;
+use crate::ToolHandler;
+use serde_json::{json, Map, Value};
+
+/// The OpenAPI version of generated documents.
+pub const OPENAPI_VERSION: &str = "3.1.0";
+
+/// Document-level settings for [`ToolHandler::openapi_spec_with`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenApiOptions {
+    title: String,
+    version: String,
+    description: Option<String>,
+    servers: Vec<String>,
+    path_prefix: String,
+}
+
+impl Default for OpenApiOptions {
+    fn default() -> Self {
+        Self::new("Tools", env!("CARGO_PKG_VERSION"))
+    }
+}
+
+impl OpenApiOptions {
+    /// Options for a document with the given `info.title` and `info.version`.
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            version: version.into(),
+            description: None,
+            servers: Vec::new(),
+            path_prefix: String::new(),
+        }
+    }
+
+    /// Sets `info.description`.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Adds a server URL the tools are reachable at.
+    pub fn with_server(mut self, url: impl Into<String>) -> Self {
+        self.servers.push(url.into());
+        self
+    }
+
+    /// Prefixes every path, for routers nested under e.g. `/api`.
+    pub fn with_path_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.path_prefix = prefix.into().trim_end_matches('/').to_string();
+        self
+    }
+}
+
+impl ToolHandler {
+    /// An OpenAPI 3.1 document describing every tool, with default [`OpenApiOptions`].
+    pub fn openapi_spec(&self) -> Value {
+        self.openapi_spec_with(&OpenApiOptions::default())
+    }
+
+    /// An OpenAPI 3.1 document describing every tool, with the given document settings.
+    pub fn openapi_spec_with(&self, options: &OpenApiOptions) -> Value {
+        let paths = self
+            .tools()
+            .map(|tool| {
+                let mut operation = json!({
+                    "operationId": tool.name,
+                    "summary": first_sentence(&tool.description),
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": tool.parameter_schema } }
+                    },
+                    "responses": {
+                        "200": response("The tool's output", "ToolCallResult"),
+                        "404": response("The tool is not registered", "ToolError"),
+                        "422": response("The arguments do not match the schema", "ToolError"),
+                        "500": response("The tool failed while running", "ToolError")
+                    }
+                });
+                if !tool.description.is_empty() {
+                    operation["description"] = json!(tool.description);
+                }
+                if !tool.tags.is_empty() {
+                    operation["tags"] = json!(tool.tags);
+                }
+                let path = format!("{}/tools/{}", options.path_prefix, tool.name);
+                (path, json!({ "post": operation }))
+            })
+            .collect::<Map<_, _>>();
+
+        let mut info = json!({ "title": options.title, "version": options.version });
+        if let Some(description) = &options.description {
+            info["description"] = json!(description);
+        }
+        let mut spec = json!({
+            "openapi": OPENAPI_VERSION,
+            "info": info,
+            "paths": paths,
+            "components": { "schemas": component_schemas() }
+        });
+        if !options.servers.is_empty() {
+            let servers = options
+                .servers
+                .iter()
+                .map(|url| json!({ "url": url }))
+                .collect::<Vec<_>>();
+            spec["servers"] = json!(servers);
+        }
+        spec
+    }
+}
+
+fn response(description: &str, schema: &str) -> Value {
+    json!({
+        "description": description,
+        "content": {
+            "application/json": {
+                "schema": { "$ref": format!("#/components/schemas/{}", schema) }
+            }
+        }
+    })
+}
+
+// Schemas of the serialized `ToolCallResult` and of error bodies
+fn component_schemas() -> Value {
+    json!({
+        "ToolCallResult": {
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "id": { "type": ["string", "null"] },
+                "output": { "type": "string" },
+                "duration": {
+                    "type": "object",
+                    "properties": {
+                        "secs": { "type": "integer" },
+                        "nanos": { "type": "integer" }
+                    }
+                },
+                "fallback": { "type": ["string", "null"] },
+                "truncated": { "type": "boolean" },
+                "deduplicated": { "type": "boolean" }
+            },
+            "required": ["name", "output"]
+        },
+        "ToolError": {
+            "type": "object",
+            "properties": {
+                "error": {
+                    "type": "object",
+                    "properties": {
+                        "kind": {
+                            "type": "string",
+                            "enum": ["not_found", "bad_args", "execution", "decode"]
+                        },
+                        "message": { "type": "string" }
+                    },
+                    "required": ["kind", "message"]
+                }
+            },
+            "required": ["error"]
+        }
+    })
+}
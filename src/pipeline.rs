@@ -0,0 +1,243 @@
+//! Composing tools into a single tool where each step's output feeds the next.
+//!
+//! A [`Pipeline`] is built from registered tools and becomes one [`Tool`] whose schema
+//! merges the steps' parameters, so the model can run the whole sequence in one call.
+//! The first step receives its arguments from the call; every later step receives the
+//! previous step's output as one of its parameters and the rest from the call.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::pipeline::Pipeline;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Fetch a page
+//! fn fetch_url(url: String) -> String {
+//!     format!("<contents of {}>", url)
+//! }
+//!
+//! #[tool]
+//! /// Summarize some text
+//! fn summarize(text: String, max_words: u32) -> String {
+//!     format!("summary of {} in {} words", text, max_words)
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let mut handler = ToolHandler::default();
+//!     let pipeline = Pipeline::new("summarize_url")
+//!         .then("fetch_url")
+//!         .then("summarize")
+//!         .build(&handler)
+//!         .unwrap();
+//!     handler.register(pipeline);
+//!
+//!     // `text` is fed from `fetch_url`, so only `url` and `max_words` are asked for
+//!     let schema = &handler.get_tool("summarize_url").unwrap().parameter_schema;
+//!     assert_eq!(schema["required"], json!(["url", "max_words"]));
+//!
+//!     let res = handler
+//!         .call_with_args("summarize_url", &["example.com".into(), "10".into()])
+//!         .await
+//!         .unwrap();
+//!     assert_eq!(res, "summary of <contents of example.com> in 10 words");
+//! }
+//! ```
+
+use crate::{Tool, ToolError, ToolFn, ToolHandler};
+use futures::TryStreamExt;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+
+/// Errors raised while building a pipeline.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum PipelineError {
+    #[error("pipeline '{0}' has no steps")]
+    Empty(String),
+    #[error("pipeline step '{0}' is not a registered tool")]
+    UnknownTool(String),
+    #[error("pipeline step '{tool}' has no parameter '{param}'")]
+    UnknownParam { tool: String, param: String },
+    #[error("pipeline step '{0}' takes no parameters to receive the previous output")]
+    NoInput(String),
+}
+
+/// A builder for a composite tool that runs other tools in sequence.
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    name: String,
+    description: Option<String>,
+    steps: Vec<(String, Option<String>)>,
+}
+
+impl Pipeline {
+    /// Starts a pipeline that will be registered under `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Sets the description shown to models. Defaults to one listing the steps.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Appends a step. After the first step, the previous output is passed as the tool's
+    /// first parameter.
+    pub fn then(mut self, tool: impl Into<String>) -> Self {
+        self.steps.push((tool.into(), None));
+        self
+    }
+
+    /// Appends a step that receives the previous output as the parameter `input`.
+    pub fn then_with(mut self, tool: impl Into<String>, input: impl Into<String>) -> Self {
+        self.steps.push((tool.into(), Some(input.into())));
+        self
+    }
+
+    /// Builds the composite tool from the steps registered in `handler`.
+    ///
+    /// The steps are captured as they are now; re-registering a step later does not change
+    /// an already built pipeline. Parameters shared by several steps appear once in the
+    /// merged schema and are passed to each of them.
+    pub fn build(&self, handler: &ToolHandler) -> Result<Tool, PipelineError> {
+        if self.steps.is_empty() {
+            return Err(PipelineError::Empty(self.name.clone()));
+        }
+
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        let mut secret_params = Vec::new();
+        let mut steps = Vec::new();
+        for (index, (name, input)) in self.steps.iter().enumerate() {
+            let tool = handler
+                .index
+                .get(name)
+                .map(|&idx| Arc::clone(handler.tools[idx].tool()))
+                .ok_or_else(|| PipelineError::UnknownTool(name.clone()))?;
+            let step_properties = tool
+                .parameter_schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .cloned()
+                .unwrap_or_default();
+
+            // The first step reads everything from the call
+            let input = match (index, input) {
+                (0, _) => None,
+                (_, Some(param)) if step_properties.contains_key(param) => Some(param.clone()),
+                (_, Some(param)) => {
+                    return Err(PipelineError::UnknownParam {
+                        tool: name.clone(),
+                        param: param.clone(),
+                    })
+                }
+                (_, None) => Some(
+                    step_properties
+                        .keys()
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| PipelineError::NoInput(name.clone()))?,
+                ),
+            };
+
+            let step_required = tool
+                .parameter_schema
+                .get("required")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default();
+            for (param, schema) in &step_properties {
+                if Some(param) == input.as_ref() || properties.contains_key(param) {
+                    continue;
+                }
+                properties.insert(param.clone(), schema.clone());
+                if step_required.contains(&json!(param)) {
+                    required.push(json!(param));
+                }
+                if tool.is_secret(param) {
+                    secret_params.push(param.clone());
+                }
+            }
+            steps.push(Step {
+                tool,
+                params: step_properties.into_iter().collect(),
+                input,
+            });
+        }
+
+        let names = properties.keys().cloned().collect::<Vec<_>>();
+        let description = self.description.clone().unwrap_or_else(|| {
+            let names = self.steps.iter().map(|(name, _)| name.as_str());
+            format!(
+                "Runs {} in sequence, passing each step's output to the next.",
+                names.collect::<Vec<_>>().join(", then ")
+            )
+        });
+        let steps = Arc::new(steps);
+        let function = ToolFn::Async(Box::new(move |args: Vec<Value>| {
+            let steps = steps.clone();
+            let named = names.iter().cloned().zip(args).collect::<Map<_, _>>();
+            Box::pin(async move { run(&steps, &named).await })
+        }));
+        let schema = json!({ "type": "object", "properties": properties, "required": required });
+        Ok(Tool::new(&self.name, description, schema, function).with_secret_params(secret_params))
+    }
+}
+
+struct Step {
+    tool: Arc<Tool>,
+    // The step's parameters and their schemas, in positional order
+    params: Vec<(String, Value)>,
+    // The parameter receiving the previous output, `None` for the first step
+    input: Option<String>,
+}
+
+async fn run(steps: &[Step], named: &Map<String, Value>) -> Result<String, ToolError> {
+    let mut output = String::new();
+    for step in steps {
+        let args = step
+            .params
+            .iter()
+            .map(|(param, schema)| match &step.input {
+                Some(input) if input == param => output_value(&output, schema),
+                _ => named.get(param).cloned().unwrap_or(Value::Null),
+            })
+            .collect::<Vec<_>>();
+        output = match &step.tool.function {
+            ToolFn::Async(func) => func(args).await,
+            ToolFn::Stream(func) => {
+                func(args)
+                    .try_fold(String::new(), |mut acc, chunk| async move {
+                        acc.push_str(&chunk);
+                        Ok(acc)
+                    })
+                    .await
+            }
+        }
+        .map_err(|err| match err {
+            ToolError::Execution(msg) => {
+                ToolError::Execution(format!("step '{}': {}", step.tool.name, msg))
+            }
+            other => other,
+        })?;
+    }
+    Ok(output)
+}
+
+// Pass string outputs as they are, and parse them as JSON for other parameter types
+fn output_value(output: &str, schema: &Value) -> Value {
+    let is_string = match schema.get("type") {
+        Some(Value::String(ty)) => ty == "string",
+        Some(Value::Array(types)) => types.contains(&json!("string")),
+        _ => false,
+    };
+    if is_string {
+        return Value::String(output.to_string());
+    }
+    serde_json::from_str(output).unwrap_or_else(|_| Value::String(output.to_string()))
+}
@@ -0,0 +1,260 @@
+use super::{PluginDeclaration, PluginRegistrar, CORE_VERSION, PLUGIN_SYMBOL};
+use crate::{AsyncToolFn, StreamToolFn, Tool, ToolFn, ToolFuture, ToolHandler, ToolStream};
+use futures::Stream;
+use libloading::Library;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::SystemTime;
+
+/// Errors returned while loading or unloading plugins.
+#[derive(Debug, thiserror::Error)]
+pub enum PluginError {
+    #[error("plugin io error for {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to load plugin {path}: {source}")]
+    Load {
+        path: PathBuf,
+        source: libloading::Error,
+    },
+    #[error("plugin {path} was built against tool_calling {found}, host is {expected}")]
+    VersionMismatch {
+        path: PathBuf,
+        expected: &'static str,
+        // Owned, since the plugin's strings go away with the library
+        found: String,
+    },
+    #[error("plugin not loaded: {0}")]
+    NotLoaded(PathBuf),
+}
+
+struct LoadedPlugin {
+    tool_names: Vec<String>,
+    modified: Option<SystemTime>,
+}
+
+/// Loads plugins onto a [`ToolHandler`] and tracks them for unloading and reloading.
+///
+/// Each tool keeps its library alive, so unloading a plugin while one of its calls is
+/// still running is safe; the library is closed once the last tool and call are gone.
+#[derive(Default)]
+pub struct PluginHost {
+    plugins: HashMap<PathBuf, LoadedPlugin>,
+}
+
+impl PluginHost {
+    /// Creates a host with no plugins loaded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the plugin at `path` and registers its tools on `handler`.
+    ///
+    /// Loading a path that is already loaded replaces its tools. Returns the names of the
+    /// registered tools.
+    pub fn load(
+        &mut self,
+        path: impl AsRef<Path>,
+        handler: &mut ToolHandler,
+    ) -> Result<Vec<String>, PluginError> {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified_time(&path)?;
+        let tools = open_plugin(&path)?;
+
+        if let Some(previous) = self.plugins.remove(&path) {
+            for name in &previous.tool_names {
+                handler.unregister(name);
+            }
+        }
+        let tool_names = tools
+            .iter()
+            .map(|tool| tool.name.clone())
+            .collect::<Vec<_>>();
+        for tool in tools {
+            handler.register(tool);
+        }
+        self.plugins.insert(
+            path,
+            LoadedPlugin {
+                tool_names: tool_names.clone(),
+                modified,
+            },
+        );
+        Ok(tool_names)
+    }
+
+    /// Unregisters the tools of the plugin loaded from `path`.
+    pub fn unload(
+        &mut self,
+        path: impl AsRef<Path>,
+        handler: &mut ToolHandler,
+    ) -> Result<(), PluginError> {
+        let path = path.as_ref();
+        let plugin = self
+            .plugins
+            .remove(path)
+            .ok_or_else(|| PluginError::NotLoaded(path.to_path_buf()))?;
+        for name in &plugin.tool_names {
+            handler.unregister(name);
+        }
+        Ok(())
+    }
+
+    /// Reloads every plugin whose file changed since it was loaded.
+    ///
+    /// Returns the paths that were reloaded. Call this periodically (or from a file
+    /// watcher) to pick up rebuilt plugins without restarting the host.
+    pub fn reload_changed(
+        &mut self,
+        handler: &mut ToolHandler,
+    ) -> Result<Vec<PathBuf>, PluginError> {
+        let mut changed = Vec::new();
+        for (path, plugin) in &self.plugins {
+            if modified_time(path)? != plugin.modified {
+                changed.push(path.clone());
+            }
+        }
+        for path in &changed {
+            self.load(path, handler)?;
+        }
+        Ok(changed)
+    }
+
+    /// Paths of the currently loaded plugins.
+    pub fn loaded(&self) -> impl Iterator<Item = &Path> + '_ {
+        self.plugins.keys().map(PathBuf::as_path)
+    }
+}
+
+fn modified_time(path: &Path) -> Result<Option<SystemTime>, PluginError> {
+    let metadata = std::fs::metadata(path).map_err(|source| PluginError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(metadata.modified().ok())
+}
+
+// Copy the library to a unique path before opening it, so a rebuilt file at the same
+// path is loaded fresh instead of being served from the loader's cache
+fn open_plugin(path: &Path) -> Result<Vec<Tool>, PluginError> {
+    static LOAD_COUNT: AtomicUsize = AtomicUsize::new(0);
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "plugin".into());
+    let copy = std::env::temp_dir().join(format!(
+        "tool_calling_{}_{}_{}",
+        std::process::id(),
+        LOAD_COUNT.fetch_add(1, Ordering::Relaxed),
+        file_name
+    ));
+    std::fs::copy(path, &copy).map_err(|source| PluginError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    // SAFETY: loading a library runs its initializers; plugins are trusted code built
+    // against this crate, as documented at the module level.
+    let library = unsafe { Library::new(&copy) };
+    // The loader keeps its own handle, so the copy can be removed straight away where the
+    // platform allows it
+    let _ = std::fs::remove_file(&copy);
+    let library = Arc::new(library.map_err(|source| PluginError::Load {
+        path: path.to_path_buf(),
+        source,
+    })?);
+
+    // SAFETY: the symbol is the `PluginDeclaration` static emitted by `export_plugin!`.
+    let declaration = unsafe {
+        library
+            .get::<*const PluginDeclaration>(format!("{}\0", PLUGIN_SYMBOL).as_bytes())
+            .map(|symbol| &**symbol)
+            .map_err(|source| PluginError::Load {
+                path: path.to_path_buf(),
+                source,
+            })?
+    };
+    if declaration.core_version != CORE_VERSION {
+        return Err(PluginError::VersionMismatch {
+            path: path.to_path_buf(),
+            expected: CORE_VERSION,
+            found: declaration.core_version.to_string(),
+        });
+    }
+
+    let mut registrar = PluginRegistrar::default();
+    (declaration.register)(&mut registrar);
+    Ok(registrar
+        .tools
+        .into_iter()
+        .map(|tool| keep_library_alive(tool, &library))
+        .collect())
+}
+
+// Rewrap a plugin tool's function so it, and every call it starts, holds the library open
+fn keep_library_alive(mut tool: Tool, library: &Arc<Library>) -> Tool {
+    let placeholder = ToolFn::Async(Box::new(|_: Vec<Value>| -> ToolFuture {
+        Box::pin(futures::future::ready(Ok(String::new())))
+    }));
+    tool.function = match std::mem::replace(&mut tool.function, placeholder) {
+        ToolFn::Async(inner) => {
+            let guarded = Guarded {
+                inner,
+                library: Arc::clone(library),
+            };
+            let func: AsyncToolFn = Box::new(move |args: Vec<Value>| -> ToolFuture {
+                Box::pin(Guarded {
+                    inner: (guarded.inner)(args),
+                    library: Arc::clone(&guarded.library),
+                })
+            });
+            ToolFn::Async(func)
+        }
+        ToolFn::Stream(inner) => {
+            let guarded = Guarded {
+                inner,
+                library: Arc::clone(library),
+            };
+            let func: StreamToolFn = Box::new(move |args: Vec<Value>| -> ToolStream {
+                Box::pin(Guarded {
+                    inner: (guarded.inner)(args),
+                    library: Arc::clone(&guarded.library),
+                })
+            });
+            ToolFn::Stream(func)
+        }
+    };
+    tool
+}
+
+// Fields drop in declaration order, so `inner` (whose code lives in the library) is
+// always dropped before the library handle
+struct Guarded<T> {
+    inner: T,
+    #[allow(dead_code)]
+    library: Arc<Library>,
+}
+
+impl Future for Guarded<ToolFuture> {
+    type Output = <ToolFuture as Future>::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.inner.as_mut().poll(cx)
+    }
+}
+
+impl Stream for Guarded<ToolStream> {
+    type Item = <ToolStream as Stream>::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
@@ -0,0 +1,103 @@
+//! Loading tools from shared libraries at runtime.
+//!
+//! A plugin is a `cdylib` crate that depends on `tool_calling` and exports a
+//! [`PluginDeclaration`] with [`export_plugin!`](crate::export_plugin). The host loads it
+//! with a [`PluginHost`], which registers the plugin's tools on a [`ToolHandler`] and can
+//! later unload them or reload them when the library file changes.
+//!
+//! Plugins must be built with the same compiler and `tool_calling` version as the host,
+//! since tools cross the library boundary as Rust types. Only the host needs the
+//! `plugins` feature; plugin crates use the always-available declaration types, and like
+//! any crate using `#[tool]` also depend on `linkme` and `serde_json`.
+//!
+//! ```rust,ignore
+//! // In the plugin crate (crate-type = ["cdylib"]):
+//! use tool_calling::tool;
+//!
+//! #[tool]
+//! /// Reverse a string
+//! fn reverse(text: String) -> String {
+//!     text.chars().rev().collect()
+//! }
+//!
+//! // Registers every `#[tool]` in the plugin
+//! tool_calling::export_plugin!();
+//! ```
+//!
+//! ```rust,ignore
+//! // In the host:
+//! use tool_calling::plugin::PluginHost;
+//! use tool_calling::ToolHandler;
+//!
+//! let mut handler = ToolHandler::default();
+//! let mut host = PluginHost::new();
+//! host.load("target/release/libmy_tools.so", &mut handler).unwrap();
+//!
+//! // Later, e.g. on a timer: pick up rebuilt plugins
+//! host.reload_changed(&mut handler).unwrap();
+//! ```
+
+use crate::Tool;
+
+#[cfg(feature = "plugins")]
+mod host;
+
+#[cfg(feature = "plugins")]
+pub use host::{PluginError, PluginHost};
+
+/// The name of the symbol every plugin exports.
+pub const PLUGIN_SYMBOL: &str = "TOOL_CALLING_PLUGIN";
+
+/// The `tool_calling` version plugins are checked against.
+pub const CORE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The static a plugin exports under [`PLUGIN_SYMBOL`].
+///
+/// Use [`export_plugin!`](crate::export_plugin) rather than constructing this directly.
+pub struct PluginDeclaration {
+    /// The `tool_calling` version the plugin was built against.
+    pub core_version: &'static str,
+    /// Registers the plugin's tools.
+    pub register: fn(&mut PluginRegistrar),
+}
+
+/// Collects the tools a plugin registers while it is being loaded.
+#[derive(Default)]
+pub struct PluginRegistrar {
+    tools: Vec<Tool>,
+}
+
+impl PluginRegistrar {
+    /// Adds a tool to the plugin's tool set.
+    pub fn register(&mut self, tool: Tool) {
+        self.tools.push(tool);
+    }
+
+    /// Adds every `#[tool]` function compiled into the calling library.
+    pub fn register_macro_tools(&mut self) {
+        for factory in crate::TOOL_FACTORIES {
+            self.register((factory.build)());
+        }
+    }
+}
+
+/// Exports a [`PluginDeclaration`] from a plugin library.
+///
+/// With no arguments every `#[tool]` in the plugin is registered; otherwise pass a
+/// `fn(&mut PluginRegistrar)` that registers tools itself.
+#[macro_export]
+macro_rules! export_plugin {
+    () => {
+        $crate::export_plugin!(|registrar: &mut $crate::plugin::PluginRegistrar| {
+            registrar.register_macro_tools()
+        });
+    };
+    ($register:expr) => {
+        #[no_mangle]
+        pub static TOOL_CALLING_PLUGIN: $crate::plugin::PluginDeclaration =
+            $crate::plugin::PluginDeclaration {
+                core_version: $crate::plugin::CORE_VERSION,
+                register: $register,
+            };
+    };
+}
@@ -0,0 +1,219 @@
+//! [`proptest`](::proptest) strategies generating tool arguments from parameter schemas.
+//!
+//! [`arguments`] turns a tool's parameter schema into a [`Strategy`] producing argument
+//! objects that conform to it, so a tool's implementation can be property-tested directly,
+//! and failing cases shrink to small arguments. [`value`] does the same for any schema.
+//!
+//! The strategies honour `type`, `enum`, `const`, `anyOf`, numeric bounds, string lengths
+//! and `pattern`, array `items` and lengths, and object `properties`, `required` and
+//! `additionalProperties`. Integers without bounds are drawn from the `i32` range, the
+//! default for Rust integer parameters, and numbers without bounds from `-1e6` to `1e6`.
+//!
+//! ```rust
+//! use proptest::prelude::*;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Clamps a value between two bounds
+//! fn clamp(value: i64, low: i64, high: i64) -> String {
+//!     value.max(low).min(high.max(low)).to_string()
+//! }
+//!
+//! let handler = ToolHandler::default();
+//! let tool = handler.get_tool("clamp").unwrap();
+//! proptest!(|(args in tool_calling::proptest::arguments(tool))| {
+//!     let (low, high) = (args["low"].as_i64().unwrap(), args["high"].as_i64().unwrap());
+//!     let call = serde_json::json!({ "type": "function", "function": { "name": "clamp", "arguments": args } });
+//!     let output = futures::executor::block_on(handler.call_tool(&call)).unwrap().output;
+//!     let clamped: i64 = output.parse().unwrap();
+//!     prop_assert!(clamped >= low);
+//!     prop_assert!(clamped <= high.max(low));
+//! });
+//! ```
+
+use crate::Tool;
+use ::proptest::prelude::*;
+use ::proptest::strategy::Union;
+use serde_json::{json, Map, Number, Value};
+
+// Array lengths without a `maxItems` range over this many items past the minimum
+const MAX_EXTRA_ITEMS: usize = 8;
+
+// Strings without a `maxLength` range over this many characters past the minimum
+const MAX_EXTRA_CHARS: usize = 16;
+
+/// Argument objects conforming to the parameter schema of `tool`.
+pub fn arguments(tool: &Tool) -> BoxedStrategy<Value> {
+    value(&tool.parameter_schema)
+}
+
+/// Values conforming to `schema`.
+///
+/// # Panics
+///
+/// If `schema` has a `pattern` that is not a valid regular expression.
+pub fn value(schema: &Value) -> BoxedStrategy<Value> {
+    if let Some(options) = schema["enum"].as_array().filter(|o| !o.is_empty()) {
+        return ::proptest::sample::select(options.clone()).boxed();
+    }
+    if let Some(value) = schema.get("const") {
+        return Just(value.clone()).boxed();
+    }
+    if let Some(branches) = schema["anyOf"].as_array().filter(|b| !b.is_empty()) {
+        return Union::new(branches.iter().map(value)).boxed();
+    }
+    match &schema["type"] {
+        Value::String(kind) => typed(schema, kind),
+        Value::Array(kinds) if !kinds.is_empty() => Union::new(
+            kinds
+                .iter()
+                .map(|kind| typed(schema, kind.as_str().unwrap_or("null"))),
+        )
+        .boxed(),
+        _ if schema.get("properties").is_some() => typed(schema, "object"),
+        _ => any_json(),
+    }
+}
+
+// Values of the JSON type `kind`, within the constraints of `schema`
+fn typed(schema: &Value, kind: &str) -> BoxedStrategy<Value> {
+    match kind {
+        "boolean" => any::<bool>().prop_map(Value::Bool).boxed(),
+        "integer" => integer(schema),
+        "number" => number(schema),
+        "string" => string(schema),
+        "array" => {
+            let min = schema["minItems"].as_u64().unwrap_or(0) as usize;
+            let max = schema["maxItems"]
+                .as_u64()
+                .map_or(min + MAX_EXTRA_ITEMS, |max| max as usize);
+            let items = schema.get("items").map_or_else(any_json, value);
+            ::proptest::collection::vec(items, min..=max.max(min))
+                .prop_map(Value::Array)
+                .boxed()
+        }
+        "object" => object(schema),
+        _ => Just(Value::Null).boxed(),
+    }
+}
+
+fn integer(schema: &Value) -> BoxedStrategy<Value> {
+    let min = match (
+        schema["minimum"].as_f64(),
+        schema["exclusiveMinimum"].as_f64(),
+    ) {
+        (_, Some(bound)) => bound.floor() as i64 + 1,
+        (Some(bound), None) => bound.ceil() as i64,
+        (None, None) => i32::MIN as i64,
+    };
+    let max = match (
+        schema["maximum"].as_f64(),
+        schema["exclusiveMaximum"].as_f64(),
+    ) {
+        (_, Some(bound)) => bound.ceil() as i64 - 1,
+        (Some(bound), None) => bound.floor() as i64,
+        (None, None) => i32::MAX as i64,
+    };
+    (min..=max.max(min)).prop_map(|n| json!(n)).boxed()
+}
+
+fn number(schema: &Value) -> BoxedStrategy<Value> {
+    let exclusive_min = schema["exclusiveMinimum"].as_f64();
+    let exclusive_max = schema["exclusiveMaximum"].as_f64();
+    let min = schema["minimum"].as_f64().or(exclusive_min).unwrap_or(-1e6);
+    let max = schema["maximum"]
+        .as_f64()
+        .or(exclusive_max)
+        .unwrap_or(min.max(0.0) + 1e6);
+    (min..=max.max(min))
+        .prop_filter("outside the exclusive bounds", move |n| {
+            exclusive_min != Some(*n) && exclusive_max != Some(*n)
+        })
+        .prop_map(|n| Number::from_f64(n).map_or(Value::Null, Value::Number))
+        .boxed()
+}
+
+fn string(schema: &Value) -> BoxedStrategy<Value> {
+    let min = schema["minLength"].as_u64().unwrap_or(0) as usize;
+    let max = schema["maxLength"]
+        .as_u64()
+        .map_or(min + MAX_EXTRA_CHARS, |max| max as usize);
+    match schema["pattern"].as_str() {
+        // A string matching the whole pattern also matches it anchored, and the
+        // generator doesn't support anchors
+        Some(pattern) => ::proptest::string::string_regex(unanchored(pattern))
+            .unwrap_or_else(|err| panic!("invalid pattern {:?}: {}", pattern, err))
+            .prop_filter("outside the length bounds", move |s| {
+                (min..=max).contains(&s.chars().count())
+            })
+            .prop_map(Value::String)
+            .boxed(),
+        None => ::proptest::collection::vec(any::<char>(), min..=max.max(min))
+            .prop_map(|chars| Value::String(chars.into_iter().collect()))
+            .boxed(),
+    }
+}
+
+fn unanchored(pattern: &str) -> &str {
+    let pattern = pattern.strip_prefix('^').unwrap_or(pattern);
+    match pattern.strip_suffix('$') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => pattern,
+    }
+}
+
+fn object(schema: &Value) -> BoxedStrategy<Value> {
+    let required = schema["required"]
+        .as_array()
+        .map(|names| names.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+        .unwrap_or_default();
+    // Required properties are always present, the others only sometimes
+    let properties = schema["properties"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, property)| {
+            let is_required = required.contains(&name.as_str());
+            let name = name.clone();
+            let entry = value(property).prop_map(move |value| (name.clone(), value));
+            if is_required {
+                entry.prop_map(Some).boxed()
+            } else {
+                ::proptest::option::of(entry).boxed()
+            }
+        })
+        .collect::<Vec<_>>();
+    let extra = match schema.get("additionalProperties").filter(|e| e.is_object()) {
+        Some(extra) => ::proptest::collection::btree_map("[a-z]{1,8}", value(extra), 0..4)
+            .prop_map(|map| map.into_iter().collect::<Vec<_>>())
+            .boxed(),
+        None => Just(Vec::new()).boxed(),
+    };
+    (properties, extra)
+        .prop_map(|(properties, extra)| {
+            let mut object = Map::new();
+            for (name, value) in extra.into_iter().chain(properties.into_iter().flatten()) {
+                object.insert(name, value);
+            }
+            Value::Object(object)
+        })
+        .boxed()
+}
+
+// Arbitrary JSON, for schemas that don't constrain it
+fn any_json() -> BoxedStrategy<Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i32>().prop_map(|n| json!(n)),
+        "\\PC{0,16}".prop_map(Value::String),
+    ];
+    leaf.prop_recursive(3, 16, 4, |inner| {
+        prop_oneof![
+            ::proptest::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+            ::proptest::collection::btree_map("[a-z]{1,8}", inner, 0..4)
+                .prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+    .boxed()
+}
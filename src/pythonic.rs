@@ -0,0 +1,308 @@
+//! Calls written as Python function calls, such as `get_user_info(user_id=1)`.
+//!
+//! Some models, among them Llama 3.2 and several of its fine-tunes, call tools by writing a
+//! Python expression: a single call, or a list of calls like
+//! `[get_weather(city='Paris'), get_time()]`. Arguments are Python literals: strings,
+//! numbers, `True`/`False`/`None`, lists, tuples and dicts. [`parse_calls`] reads such
+//! text, and [`ToolHandler::parse_pythonic_calls`] turns it into call payloads, matching
+//! positional arguments to the tool's parameters and validating them against its schema.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Get user info from database
+//! fn get_user_info(user_id: u32, verbose: Option<bool>) -> String {
+//!     format!("user {} ({:?})", user_id, verbose)
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let handler = ToolHandler::default();
+//!     let calls = handler.parse_pythonic_calls("[get_user_info(1, verbose=True)]").unwrap();
+//!     assert_eq!(calls[0]["function"]["arguments"], json!({ "user_id": 1, "verbose": true }));
+//!     let result = handler.call_tool(&calls[0]).await.unwrap();
+//!     assert_eq!(result.output, "user 1 (Some(true))");
+//! }
+//! ```
+
+use crate::{ToolError, ToolHandler};
+use serde_json::{json, Map, Number, Value};
+
+/// A call parsed from Python syntax, before it is matched against a tool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PythonicCall {
+    /// The name of the called function.
+    pub name: String,
+    /// The positional arguments, in order.
+    pub args: Vec<Value>,
+    /// The keyword arguments, in the order they were written.
+    pub kwargs: Map<String, Value>,
+}
+
+/// Parses a Python function call, or a list of calls, into [`PythonicCall`]s.
+pub fn parse_calls(text: &str) -> Result<Vec<PythonicCall>, ToolError> {
+    let mut parser = Parser {
+        chars: text.trim().chars().collect(),
+        pos: 0,
+    };
+    let calls = if parser.eat('[') {
+        parser.list(']', Parser::call)?
+    } else {
+        vec![parser.call()?]
+    };
+    parser.skip_whitespace();
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error("unexpected text after the call"));
+    }
+    Ok(calls)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> ToolError {
+        ToolError::BadArgs(format!(
+            "Invalid Python call at character {}: {}",
+            self.pos, message
+        ))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    // Consumes `expected`, after any whitespace, if it comes next
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(expected);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ToolError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+
+    // Items separated by commas up to `close`, allowing a trailing comma
+    fn list<T>(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<T, ToolError>,
+    ) -> Result<Vec<T>, ToolError> {
+        let mut items = Vec::new();
+        while !self.eat(close) {
+            items.push(item(self)?);
+            if !self.eat(',') {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    fn identifier(&mut self) -> Result<String, ToolError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.')
+        {
+            self.pos += 1;
+        }
+        if start == self.pos || self.chars[start].is_ascii_digit() {
+            return Err(self.error("expected a name"));
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn call(&mut self) -> Result<PythonicCall, ToolError> {
+        let name = self.identifier()?;
+        self.expect('(')?;
+        let mut call = PythonicCall {
+            name,
+            args: Vec::new(),
+            kwargs: Map::new(),
+        };
+        self.list(')', |parser| {
+            // A keyword argument is a name followed by a single `=`
+            let start = parser.pos;
+            if let Ok(keyword) = parser.identifier() {
+                if parser.eat('=') && parser.peek() != Some('=') {
+                    let value = parser.value()?;
+                    if call.kwargs.insert(keyword.clone(), value).is_some() {
+                        return Err(parser.error(&format!("repeated argument '{}'", keyword)));
+                    }
+                    return Ok(());
+                }
+            }
+            parser.pos = start;
+            if !call.kwargs.is_empty() {
+                return Err(parser.error("positional argument after keyword arguments"));
+            }
+            call.args.push(parser.value()?);
+            Ok(())
+        })?;
+        Ok(call)
+    }
+
+    fn value(&mut self) -> Result<Value, ToolError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                Ok(Value::Array(self.list(']', Self::value)?))
+            }
+            Some('(') => {
+                self.pos += 1;
+                Ok(Value::Array(self.list(')', Self::value)?))
+            }
+            Some('{') => {
+                self.pos += 1;
+                let entries = self.list('}', |parser| {
+                    let key = match parser.value()? {
+                        Value::String(key) => key,
+                        key => key.to_string(),
+                    };
+                    parser.expect(':')?;
+                    Ok((key, parser.value()?))
+                })?;
+                Ok(Value::Object(entries.into_iter().collect()))
+            }
+            Some(quote @ ('\'' | '"')) => self.string(quote),
+            Some(c) if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => self.number(),
+            _ => match self.identifier()?.as_str() {
+                "True" | "true" => Ok(json!(true)),
+                "False" | "false" => Ok(json!(false)),
+                "None" | "null" => Ok(Value::Null),
+                name => Err(self.error(&format!("'{}' is not a literal", name))),
+            },
+        }
+    }
+
+    fn string(&mut self, quote: char) -> Result<Value, ToolError> {
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+            match c {
+                _ if c == quote => return Ok(Value::String(text)),
+                '\\' => {
+                    let Some(escaped) = self.peek() else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.pos += 1;
+                    match escaped {
+                        'n' => text.push('\n'),
+                        't' => text.push('\t'),
+                        'r' => text.push('\r'),
+                        '0' => text.push('\0'),
+                        'u' => {
+                            let hex = self.chars.get(self.pos..self.pos + 4).unwrap_or_default();
+                            let code = u32::from_str_radix(&hex.iter().collect::<String>(), 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid \\u escape"))?;
+                            self.pos += 4;
+                            text.push(code);
+                        }
+                        other => text.push(other),
+                    }
+                }
+                _ => text.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, ToolError> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.' | '_'))
+        {
+            self.pos += 1;
+        }
+        let literal = self.chars[start..self.pos]
+            .iter()
+            .filter(|c| **c != '_')
+            .collect::<String>();
+        if let Ok(int) = literal.parse::<i64>() {
+            return Ok(json!(int));
+        }
+        literal
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| self.error(&format!("invalid number '{}'", literal)))
+    }
+}
+
+impl ToolHandler {
+    /// Parses Python call syntax into call payloads accepted by [`ToolHandler::call_tool`].
+    ///
+    /// Positional arguments are matched to the tool's parameters in order. Fails with
+    /// [`ToolError::NotFound`] for an unknown tool, and with [`ToolError::BadArgs`] for
+    /// text that is not a call, extra positional arguments, or arguments the tool's schema
+    /// rejects.
+    pub fn parse_pythonic_calls(&self, text: &str) -> Result<Vec<Value>, ToolError> {
+        parse_calls(text)?
+            .into_iter()
+            .map(|call| {
+                let tool = self
+                    .get_tool(&call.name)
+                    .ok_or_else(|| ToolError::NotFound(call.name.clone()))?;
+                let params = tool.parameter_schema["properties"]
+                    .as_object()
+                    .map(|properties| properties.keys().collect::<Vec<_>>())
+                    .unwrap_or_default();
+                if call.args.len() > params.len() {
+                    return Err(ToolError::BadArgs(format!(
+                        "'{}' takes {} arguments but {} were given",
+                        call.name,
+                        params.len(),
+                        call.args.len()
+                    )));
+                }
+                let mut arguments = Map::new();
+                for (param, value) in params.into_iter().zip(call.args) {
+                    arguments.insert(param.clone(), value);
+                }
+                for (keyword, value) in call.kwargs {
+                    if arguments.contains_key(&keyword) {
+                        return Err(ToolError::BadArgs(format!(
+                            "'{}' got multiple values for argument '{}'",
+                            call.name, keyword
+                        )));
+                    }
+                    arguments.insert(keyword, value);
+                }
+                let payload = json!({
+                    "type": "function",
+                    "function": { "name": call.name, "arguments": arguments }
+                });
+                self.validate_tool_call(&payload)?;
+                Ok(payload)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,594 @@
+//! Tools served by another process over HTTP.
+//!
+//! A [`RemoteToolProvider`] fetches a manifest of tools from a URL and registers a proxy
+//! [`Tool`] for each one. Calling a proxy POSTs the arguments, as a JSON object, to the
+//! tool's endpoint and returns the response body as the tool's output; a non-success
+//! status fails the call with [`ToolError::Execution`].
+//!
+//! The manifest is a JSON array (or an object with a `tools` array) whose entries are
+//! either serialized [`Tool`]s or OpenAI-style `{"type": "function", "function": {..}}`
+//! entries, so a server can return [`ToolHandler::all_tools_schema`] as-is. A tool is
+//! called at its `url` if the entry has one, and at `{manifest url}/{name}` otherwise.
+//!
+//! An [`OpenApiToolProvider`] instead exposes an existing REST API: it turns the operations
+//! of an OpenAPI 3 document into tools whose calls send the described HTTP request.
+//!
+//! ```rust,no_run
+//! use tool_calling::remote::{OpenApiToolProvider, RemoteToolProvider};
+//! use tool_calling::ToolHandler;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let mut handler = ToolHandler::default();
+//!     let names = RemoteToolProvider::new("http://tools.internal:8080/tools")
+//!         .with_header("Authorization", "Bearer secret")
+//!         .select(["search", "fetch_page"])
+//!         .load(&mut handler)
+//!         .await
+//!         .unwrap();
+//!     println!("proxying {:?}", names);
+//!
+//!     let spec: serde_json::Value =
+//!         serde_json::from_str(&std::fs::read_to_string("petstore.json").unwrap()).unwrap();
+//!     OpenApiToolProvider::new(spec, "https://petstore.example.com/v1")
+//!         .select(["listPets", "showPetById"])
+//!         .load(&mut handler)
+//!         .unwrap();
+//! }
+//! ```
+
+use crate::{Tool, ToolError, ToolFn, ToolFuture, ToolHandler};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+/// Errors returned while fetching a remote manifest.
+#[derive(Debug, thiserror::Error)]
+pub enum RemoteError {
+    #[error("failed to fetch tool manifest: {0}")]
+    Http(String),
+    #[error("invalid tool manifest: {0}")]
+    Manifest(String),
+    #[error("invalid OpenAPI document: {0}")]
+    Spec(String),
+}
+
+/// Registers proxies for the tools listed in a remote manifest.
+#[derive(Debug, Clone)]
+pub struct RemoteToolProvider {
+    manifest_url: String,
+    headers: Vec<(String, String)>,
+    selected: Option<Vec<String>>,
+    timeout: Option<Duration>,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Manifest {
+    List(Vec<ManifestEntry>),
+    Wrapped { tools: Vec<ManifestEntry> },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ManifestEntry {
+    OpenAi { function: RemoteTool },
+    Plain(RemoteTool),
+}
+
+#[derive(Deserialize)]
+struct RemoteTool {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default, alias = "parameter_schema")]
+    parameters: Option<Value>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+impl RemoteToolProvider {
+    /// Creates a provider for the manifest at `manifest_url`.
+    pub fn new(manifest_url: impl Into<String>) -> Self {
+        Self {
+            manifest_url: manifest_url.into(),
+            headers: Vec::new(),
+            selected: None,
+            timeout: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Adds a header sent with the manifest request and every tool call.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Only proxies the named tools; by default every tool in the manifest is proxied.
+    pub fn select<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.selected = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Fails requests, including tool calls, that take longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fetches the manifest and builds a proxy for each selected tool.
+    pub async fn fetch(&self) -> Result<Vec<Tool>, RemoteError> {
+        let response = self
+            .request(reqwest::Method::GET, &self.manifest_url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|err| RemoteError::Http(err.to_string()))?;
+        let manifest = response
+            .json::<Manifest>()
+            .await
+            .map_err(|err| RemoteError::Manifest(err.to_string()))?;
+        let entries = match manifest {
+            Manifest::List(entries) | Manifest::Wrapped { tools: entries } => entries,
+        };
+        Ok(entries
+            .into_iter()
+            .map(|entry| match entry {
+                ManifestEntry::OpenAi { function } => function,
+                ManifestEntry::Plain(tool) => tool,
+            })
+            .filter(|tool| match &self.selected {
+                Some(selected) => selected.contains(&tool.name),
+                None => true,
+            })
+            .map(|tool| self.proxy(tool))
+            .collect())
+    }
+
+    /// Fetches the manifest and registers its proxies on `handler`, replacing local tools
+    /// with the same name.
+    ///
+    /// Returns the names of the registered tools.
+    pub async fn load(&self, handler: &mut ToolHandler) -> Result<Vec<String>, RemoteError> {
+        let tools = self.fetch().await?;
+        let names = tools.iter().map(|tool| tool.name.clone()).collect();
+        for tool in tools {
+            handler.register(tool);
+        }
+        Ok(names)
+    }
+
+    fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        let mut request = self.client.request(method, url);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        request
+    }
+
+    fn proxy(&self, tool: RemoteTool) -> Tool {
+        let parameters = tool
+            .parameters
+            .unwrap_or_else(|| json!({ "type": "object", "properties": {}, "required": [] }));
+        let parameter_names = parameters
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|props| props.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        let url = tool.url.unwrap_or_else(|| {
+            format!("{}/{}", self.manifest_url.trim_end_matches('/'), tool.name)
+        });
+        let provider = self.clone();
+        let function = ToolFn::Async(Box::new(move |args: Vec<Value>| -> ToolFuture {
+            let body = parameter_names
+                .iter()
+                .zip(args)
+                .filter(|(_, value)| !value.is_null())
+                .map(|(name, value)| (name.clone(), value))
+                .collect::<Map<_, _>>();
+            let request = provider.request(reqwest::Method::POST, &url).json(&body);
+            Box::pin(send(request))
+        }));
+        Tool::new(tool.name, tool.description, parameters, function).with_tags(tool.tags)
+    }
+}
+
+// Runs a proxied call, failing it on transport errors and non-success statuses
+async fn send(request: reqwest::RequestBuilder) -> Result<String, ToolError> {
+    let response = request
+        .send()
+        .await
+        .map_err(|err| ToolError::Execution(err.to_string()))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|err| ToolError::Execution(err.to_string()))?;
+    if status.is_success() {
+        Ok(body)
+    } else {
+        Err(ToolError::Execution(format!("HTTP {}: {}", status, body)))
+    }
+}
+
+/// The HTTP methods an OpenAPI path item can define operations for.
+const OPERATION_METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Registers tools that call the operations of an OpenAPI 3 document.
+///
+/// Each operation becomes a tool named after its `operationId` (or its method and path when
+/// it has none) and described by its summary and description. The tool's parameters are the
+/// operation's path, query and header parameters, followed by the fields of its JSON request
+/// body. A body that is not an object, or whose fields clash with a parameter, is passed
+/// whole as a `body` argument instead. Calling the tool sends the request to the provider's
+/// base URL and returns the response body.
+#[derive(Debug, Clone)]
+pub struct OpenApiToolProvider {
+    spec: Value,
+    base_url: String,
+    headers: Vec<(String, String)>,
+    selected: Option<Vec<String>>,
+    timeout: Option<Duration>,
+    client: reqwest::Client,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ArgLocation {
+    Path,
+    Query,
+    Header,
+    BodyField,
+    Body,
+}
+
+impl OpenApiToolProvider {
+    /// Creates a provider for the operations of `spec`, sending requests to `base_url`.
+    pub fn new(spec: Value, base_url: impl Into<String>) -> Self {
+        Self {
+            spec,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            headers: Vec::new(),
+            selected: None,
+            timeout: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Adds a header sent with every request, for example for authentication.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Only imports the operations with the given tool names; by default every operation
+    /// is imported.
+    pub fn select<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.selected = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Fails tool calls that take longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Builds a tool for each selected operation, in document order.
+    pub fn tools(&self) -> Result<Vec<Tool>, RemoteError> {
+        let paths = self
+            .spec
+            .get("paths")
+            .and_then(Value::as_object)
+            .ok_or_else(|| RemoteError::Spec("missing `paths` object".to_string()))?;
+        let mut tools = Vec::new();
+        for (path, item) in paths {
+            let item = self.resolve(item);
+            let shared = item.get("parameters").and_then(Value::as_array);
+            for method in OPERATION_METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+                let name = match operation.get("operationId").and_then(Value::as_str) {
+                    Some(id) => id.to_string(),
+                    None => operation_name(method, path),
+                };
+                let selected = match &self.selected {
+                    Some(selected) => selected.contains(&name),
+                    None => true,
+                };
+                if selected {
+                    tools.push(self.operation_tool(name, method, path, operation, shared)?);
+                }
+            }
+        }
+        Ok(tools)
+    }
+
+    /// Registers the selected operations' tools on `handler`, replacing tools with the same
+    /// name.
+    ///
+    /// Returns the names of the registered tools.
+    pub fn load(&self, handler: &mut ToolHandler) -> Result<Vec<String>, RemoteError> {
+        let tools = self.tools()?;
+        let names = tools.iter().map(|tool| tool.name.clone()).collect();
+        for tool in tools {
+            handler.register(tool);
+        }
+        Ok(names)
+    }
+
+    fn operation_tool(
+        &self,
+        name: String,
+        method: &str,
+        path: &str,
+        operation: &Value,
+        shared: Option<&Vec<Value>>,
+    ) -> Result<Tool, RemoteError> {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        let mut args = Vec::new();
+
+        // Operation parameters override path-level ones with the same name and location
+        let own = operation.get("parameters").and_then(Value::as_array);
+        let own = own
+            .into_iter()
+            .flatten()
+            .map(|param| self.resolve(param))
+            .collect::<Vec<_>>();
+        let inherited = shared
+            .into_iter()
+            .flatten()
+            .map(|param| self.resolve(param))
+            .filter(|param| {
+                !own.iter()
+                    .any(|o| o["name"] == param["name"] && o["in"] == param["in"])
+            })
+            .collect::<Vec<_>>();
+        for param in inherited.iter().chain(&own) {
+            let Some(param_name) = param.get("name").and_then(Value::as_str) else {
+                return Err(RemoteError::Spec(format!(
+                    "a parameter of `{}` has no name",
+                    name
+                )));
+            };
+            let location = match param.get("in").and_then(Value::as_str) {
+                Some("path") => ArgLocation::Path,
+                Some("query") => ArgLocation::Query,
+                Some("header") => ArgLocation::Header,
+                _ => continue,
+            };
+            if properties.contains_key(param_name) {
+                continue;
+            }
+            let mut schema = param
+                .get("schema")
+                .cloned()
+                .unwrap_or_else(|| json!({ "type": "string" }));
+            if let (Some(description), Some(fields)) =
+                (param.get("description"), schema.as_object_mut())
+            {
+                fields.insert("description".to_string(), description.clone());
+            }
+            properties.insert(param_name.to_string(), schema);
+            let is_required = param.get("required").and_then(Value::as_bool) == Some(true);
+            if is_required || location == ArgLocation::Path {
+                required.push(json!(param_name));
+            }
+            args.push((param_name.to_string(), location));
+        }
+
+        let request_body = operation.get("requestBody").map(|body| self.resolve(body));
+        let body_schema = request_body.as_ref().and_then(json_body_schema);
+        let has_body = body_schema.is_some();
+        if let (Some(body), Some(schema)) = (&request_body, body_schema) {
+            let body_required = body.get("required").and_then(Value::as_bool) == Some(true);
+            let fields = schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .filter(|fields| fields.keys().all(|key| !properties.contains_key(key)));
+            match fields {
+                Some(fields) if schema.get("type").is_none_or(|ty| ty == "object") => {
+                    let field_required = schema
+                        .get("required")
+                        .and_then(Value::as_array)
+                        .cloned()
+                        .unwrap_or_default();
+                    for (field, field_schema) in fields {
+                        properties.insert(field.clone(), field_schema.clone());
+                        if body_required && field_required.iter().any(|r| r == field) {
+                            required.push(json!(field));
+                        }
+                        args.push((field.clone(), ArgLocation::BodyField));
+                    }
+                }
+                _ => {
+                    let field = if properties.contains_key("body") {
+                        "request_body"
+                    } else {
+                        "body"
+                    };
+                    properties.insert(field.to_string(), schema);
+                    if body_required {
+                        required.push(json!(field));
+                    }
+                    args.push((field.to_string(), ArgLocation::Body));
+                }
+            }
+        }
+
+        let description = [operation.get("summary"), operation.get("description")]
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter(|text| !text.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let tags: Vec<String> = operation
+            .get("tags")
+            .and_then(Value::as_array)
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(Value::as_str)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let parameters =
+            json!({ "type": "object", "properties": properties, "required": required });
+
+        let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+            .map_err(|err| RemoteError::Spec(err.to_string()))?;
+        let url = format!("{}{}", self.base_url, path);
+        let provider = self.clone();
+        let function = ToolFn::Async(Box::new(move |values: Vec<Value>| -> ToolFuture {
+            let mut url = url.clone();
+            let mut query = Vec::new();
+            let mut headers = Vec::new();
+            let mut fields = Map::new();
+            let mut whole = None;
+            for ((arg, location), value) in args.iter().zip(values) {
+                if value.is_null() {
+                    continue;
+                }
+                match location {
+                    ArgLocation::Path => {
+                        let segment = encode_segment(&plain_text(&value));
+                        url = url.replace(&format!("{{{}}}", arg), &segment);
+                    }
+                    ArgLocation::Query => match value {
+                        Value::Array(items) => {
+                            query.extend(items.iter().map(|item| (arg.clone(), plain_text(item))))
+                        }
+                        _ => query.push((arg.clone(), plain_text(&value))),
+                    },
+                    ArgLocation::Header => headers.push((arg.clone(), plain_text(&value))),
+                    ArgLocation::BodyField => {
+                        fields.insert(arg.clone(), value);
+                    }
+                    ArgLocation::Body => whole = Some(value),
+                }
+            }
+            let mut request = provider.request(method.clone(), &url);
+            if !query.is_empty() {
+                request = request.query(&query);
+            }
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            if let Some(body) = whole {
+                request = request.json(&body);
+            } else if has_body {
+                request = request.json(&fields);
+            }
+            Box::pin(send(request))
+        }));
+        Ok(Tool::new(name, description, parameters, function).with_tags(tags))
+    }
+
+    fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        let mut request = self.client.request(method, url);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        request
+    }
+
+    // Inlines the local `$ref`s in `value`; a reference to itself becomes `{}`
+    fn resolve(&self, value: &Value) -> Value {
+        self.resolve_with(value, &mut Vec::new())
+    }
+
+    fn resolve_with(&self, value: &Value, seen: &mut Vec<String>) -> Value {
+        match value {
+            Value::Object(fields) => {
+                if let Some(reference) = fields.get("$ref").and_then(Value::as_str) {
+                    let target = reference
+                        .strip_prefix('#')
+                        .and_then(|pointer| self.spec.pointer(pointer));
+                    return match target {
+                        Some(target) if !seen.iter().any(|r| r == reference) => {
+                            seen.push(reference.to_string());
+                            let resolved = self.resolve_with(target, seen);
+                            seen.pop();
+                            resolved
+                        }
+                        _ => json!({}),
+                    };
+                }
+                let fields = fields
+                    .iter()
+                    .map(|(key, value)| (key.clone(), self.resolve_with(value, seen)))
+                    .collect();
+                Value::Object(fields)
+            }
+            Value::Array(items) => items
+                .iter()
+                .map(|item| self.resolve_with(item, seen))
+                .collect(),
+            _ => value.clone(),
+        }
+    }
+}
+
+// The schema of a request body's JSON content, if it has one
+fn json_body_schema(body: &Value) -> Option<Value> {
+    let content = body.get("content")?.as_object()?;
+    let media = content.get("application/json").or_else(|| {
+        content
+            .iter()
+            .find(|(ty, _)| ty.contains("json"))
+            .map(|(_, media)| media)
+    })?;
+    Some(media.get("schema").cloned().unwrap_or_else(|| json!({})))
+}
+
+// `get` `/pets/{petId}` becomes `get_pets_petId`
+fn operation_name(method: &str, path: &str) -> String {
+    let mut name = method.to_string();
+    for part in path.split(|c: char| !c.is_ascii_alphanumeric()) {
+        if !part.is_empty() {
+            name.push('_');
+            name.push_str(part);
+        }
+    }
+    name
+}
+
+fn plain_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn encode_segment(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
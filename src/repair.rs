@@ -0,0 +1,224 @@
+//! Recovery of nearly-valid JSON, as small models often write it.
+//!
+//! [`repair_json`] parses text as JSON, first fixing the mistakes models commonly make: a
+//! Markdown code fence around the value, trailing commas, single-quoted strings, unquoted
+//! object keys, Python's `True`/`False`/`None`, and strings or brackets left open when
+//! generation stopped. The fixes are returned with the value so callers can log them.
+//! [`crate::ToolHandler::with_argument_repair`] applies it to call arguments.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::repair::{repair_json, Repair};
+//!
+//! let repaired = repair_json("```json\n{city: 'Paris', days: [1, 2,],}\n```").unwrap();
+//! assert_eq!(repaired.value, json!({ "city": "Paris", "days": [1, 2] }));
+//! assert_eq!(
+//!     repaired.repairs,
+//!     vec![Repair::CodeFence, Repair::UnquotedKeys, Repair::SingleQuotes, Repair::TrailingCommas]
+//! );
+//! ```
+
+use crate::ToolError;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// A fix made by [`repair_json`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Repair {
+    /// Took the value out of a Markdown code fence.
+    CodeFence,
+    /// Removed commas before a closing bracket.
+    TrailingCommas,
+    /// Rewrote single-quoted strings with double quotes.
+    SingleQuotes,
+    /// Quoted bare object keys.
+    UnquotedKeys,
+    /// Replaced `True`, `False` and `None` with their JSON spelling.
+    PythonLiterals,
+    /// Closed strings and brackets left open at the end of the text.
+    UnclosedBrackets,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Repair::CodeFence => "removed a code fence",
+            Repair::TrailingCommas => "removed trailing commas",
+            Repair::SingleQuotes => "replaced single quotes",
+            Repair::UnquotedKeys => "quoted object keys",
+            Repair::PythonLiterals => "replaced Python literals",
+            Repair::UnclosedBrackets => "closed unterminated strings and brackets",
+        })
+    }
+}
+
+/// A value recovered by [`repair_json`], with the fixes it took, in the order they were
+/// first needed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repaired {
+    /// The parsed value.
+    pub value: Value,
+    /// The fixes applied; empty when the text was valid JSON.
+    pub repairs: Vec<Repair>,
+}
+
+/// Parses `text` as JSON, repairing it first if it is not valid.
+///
+/// Fails with [`ToolError::BadArgs`] when the text is still invalid after every repair.
+pub fn repair_json(text: &str) -> Result<Repaired, ToolError> {
+    if let Ok(value) = serde_json::from_str(text) {
+        return Ok(Repaired {
+            value,
+            repairs: Vec::new(),
+        });
+    }
+    let mut repairs = Vec::new();
+    let text = match fenced(text) {
+        Some(inner) => {
+            repairs.push(Repair::CodeFence);
+            inner
+        }
+        None => text,
+    };
+    let rewritten = rewrite(text, &mut repairs);
+    serde_json::from_str(&rewritten)
+        .map(|value| Repaired { value, repairs })
+        .map_err(|err| ToolError::BadArgs(format!("Could not repair JSON: {}", err)))
+}
+
+// The contents of the first Markdown code fence in `text`
+fn fenced(text: &str) -> Option<&str> {
+    let start = text.find("```")?;
+    let body = &text[start + 3..];
+    // Skip the info string, such as `json`
+    let body = &body[body.find('\n')? + 1..];
+    Some(body.find("```").map_or(body, |end| &body[..end]))
+}
+
+fn note(repairs: &mut Vec<Repair>, repair: Repair) {
+    if !repairs.contains(&repair) {
+        repairs.push(repair);
+    }
+}
+
+// Rewrites near-JSON into JSON in a single pass, tracking the open brackets and whether an
+// object key comes next
+fn rewrite(text: &str, repairs: &mut Vec<Repair>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut open = Vec::new();
+    let mut expect_key = false;
+    let mut chars = text.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                if c == '\'' {
+                    note(repairs, Repair::SingleQuotes);
+                }
+                let (value, closed) = read_string(&mut chars, c);
+                if !closed {
+                    note(repairs, Repair::UnclosedBrackets);
+                }
+                out.push_str(&Value::String(value).to_string());
+            }
+            '{' | '[' => {
+                open.push(if c == '{' { '}' } else { ']' });
+                expect_key = c == '{';
+                out.push(c);
+            }
+            '}' | ']' => {
+                if drop_trailing_comma(&mut out) {
+                    note(repairs, Repair::TrailingCommas);
+                }
+                open.pop();
+                expect_key = false;
+                out.push(c);
+            }
+            ',' => {
+                expect_key = open.last() == Some(&'}');
+                out.push(c);
+            }
+            ':' => {
+                expect_key = false;
+                out.push(c);
+            }
+            _ if c.is_alphabetic() || c == '_' || c == '$' => {
+                let mut word = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_alphanumeric() || next == '_' || next == '$' || next == '-') {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                if expect_key {
+                    note(repairs, Repair::UnquotedKeys);
+                    out.push_str(&Value::String(word).to_string());
+                } else {
+                    let literal = match word.as_str() {
+                        "True" => "true",
+                        "False" => "false",
+                        "None" => "null",
+                        _ => {
+                            out.push_str(&word);
+                            continue;
+                        }
+                    };
+                    note(repairs, Repair::PythonLiterals);
+                    out.push_str(literal);
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    if !open.is_empty() {
+        note(repairs, Repair::UnclosedBrackets);
+        while let Some(close) = open.pop() {
+            if drop_trailing_comma(&mut out) {
+                note(repairs, Repair::TrailingCommas);
+            }
+            out.push(close);
+        }
+    }
+    out
+}
+
+// Reads a string up to its closing `quote`, decoding escapes; false if it never closes
+fn read_string(chars: &mut impl Iterator<Item = char>, quote: char) -> (String, bool) {
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('r') => value.push('\r'),
+                Some('b') => value.push('\u{8}'),
+                Some('f') => value.push('\u{c}'),
+                Some('u') => {
+                    let hex = chars.by_ref().take(4).collect::<String>();
+                    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        Some(decoded) => value.push(decoded),
+                        None => value.push_str(&hex),
+                    }
+                }
+                Some(escaped) => value.push(escaped),
+                None => return (value, false),
+            },
+            _ if c == quote => return (value, true),
+            _ => value.push(c),
+        }
+    }
+    (value, false)
+}
+
+// Removes a comma ending `out`, ignoring whitespace after it
+fn drop_trailing_comma(out: &mut String) -> bool {
+    let trimmed = out.trim_end().len();
+    if out[..trimmed].ends_with(',') {
+        out.truncate(trimmed - 1);
+        true
+    } else {
+        false
+    }
+}
@@ -0,0 +1,217 @@
+//! An interactive prompt for calling the tools of a handler by hand.
+//!
+//! [`repl`] reads a tool name and its JSON arguments per line, validates and runs the call,
+//! and prints the output, pretty-printed when it is JSON, or the error. Tab completes tool
+//! names. Lines starting with `:` are commands:
+//!
+//! ```text
+//! tools> add {"a": 1, "b": 2}
+//! 3
+//! tools> add {"a": "one"}
+//! error: invalid arguments: ...
+//! tools> :tools                List every tool with its description
+//! tools> :schema add           Print the JSON schema of a tool
+//! tools> :quit                 Leave, as does Ctrl-D
+//! ```
+//!
+//! ```rust,no_run
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> std::io::Result<()> {
+//!     tool_calling::repl(&ToolHandler::default()).await
+//! }
+//! ```
+
+use crate::{cli, ToolHandler};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+/// The prompt shown before each line.
+pub const PROMPT: &str = "tools> ";
+
+const HELP: &str = "\
+<tool> [{json arguments}]    Call a tool and print its output
+:tools                       List every tool with its description
+:schema <tool>               Print the JSON schema of a tool
+:help                        Show this help
+:quit                        Leave, as does Ctrl-D
+";
+
+const COMMANDS: [&str; 5] = [":tools", ":schema", ":help", ":quit", ":exit"];
+
+/// Runs an interactive prompt on the terminal calling the tools of `handler`, until `:quit`
+/// or the end of input.
+pub async fn repl(handler: &ToolHandler) -> std::io::Result<()> {
+    let mut editor: Editor<ToolCompleter, DefaultHistory> =
+        Editor::new().map_err(std::io::Error::other)?;
+    editor.set_helper(Some(ToolCompleter::new(handler)));
+    let mut stdout = std::io::stdout();
+    loop {
+        let line = match editor.readline(PROMPT) {
+            Ok(line) => line,
+            // Ctrl-C abandons the line being typed
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(err) => return Err(std::io::Error::other(err)),
+        };
+        if !line.trim().is_empty() {
+            let _ = editor.add_history_entry(line.as_str());
+        }
+        if !eval(handler, &line, &mut stdout).await? {
+            return Ok(());
+        }
+    }
+}
+
+/// Runs the lines of `input` as [`repl`] would, writing what it prints to `output`, for
+/// scripted sessions and tests. Stops at `:quit` or the end of input.
+pub async fn repl_with(
+    handler: &ToolHandler,
+    input: impl BufRead,
+    mut output: impl Write + Send,
+) -> std::io::Result<()> {
+    for line in input.lines() {
+        if !eval(handler, &line?, &mut output).await? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Runs one line, returning whether to read another
+async fn eval(
+    handler: &ToolHandler,
+    line: &str,
+    out: &mut (dyn Write + Send),
+) -> std::io::Result<bool> {
+    let line = line.trim();
+    let (head, rest) = line
+        .split_once(char::is_whitespace)
+        .map_or((line, ""), |(head, rest)| (head, rest.trim()));
+    let command = match head {
+        "" => return Ok(true),
+        ":quit" | ":exit" => return Ok(false),
+        ":help" => {
+            out.write_all(HELP.as_bytes())?;
+            return Ok(true);
+        }
+        ":tools" => vec!["tools", "list"],
+        ":schema" => vec!["tools", "describe", rest],
+        _ if head.starts_with(':') => {
+            writeln!(out, "error: unknown command {}, see :help", head)?;
+            return Ok(true);
+        }
+        name => {
+            call(handler, name, rest, out).await?;
+            return Ok(true);
+        }
+    };
+    match cli::run(handler, command, out).await {
+        Ok(()) => {}
+        Err(cli::CliError::Io(err)) => return Err(err),
+        Err(err) => writeln!(out, "error: {}", err)?,
+    }
+    Ok(true)
+}
+
+// Calls the tool `name` with the JSON `arguments`, printing its output or error
+async fn call(
+    handler: &ToolHandler,
+    name: &str,
+    arguments: &str,
+    out: &mut (dyn Write + Send),
+) -> std::io::Result<()> {
+    let arguments = match arguments {
+        "" => json!({}),
+        arguments => match serde_json::from_str::<Value>(arguments) {
+            Ok(arguments) => arguments,
+            Err(err) => return writeln!(out, "error: arguments are not valid JSON: {}", err),
+        },
+    };
+    let call = json!({ "type": "function", "function": { "name": name, "arguments": arguments } });
+    let output = match handler.validate_tool_call(&call) {
+        Ok(_) => handler.call_tool(&call).await.map(|result| result.output),
+        Err(err) => Err(err),
+    };
+    match output {
+        Ok(output) => match serde_json::from_str::<Value>(&output) {
+            Ok(json @ (Value::Object(_) | Value::Array(_))) => {
+                writeln!(out, "{}", serde_json::to_string_pretty(&json).unwrap())
+            }
+            _ => writeln!(out, "{}", output),
+        },
+        Err(err) => writeln!(out, "error: {}", err),
+    }
+}
+
+/// Completes tool names and commands at the [`repl`] prompt.
+pub struct ToolCompleter {
+    names: Vec<String>,
+}
+
+impl ToolCompleter {
+    /// Completes the names of the tools of `handler`.
+    pub fn new(handler: &ToolHandler) -> Self {
+        Self {
+            names: handler.tools().map(|tool| tool.name.clone()).collect(),
+        }
+    }
+
+    /// The start of the word being typed at `pos` in `line` and the names it may complete
+    /// to: tool names as the first word or after `:schema`, commands for a first word
+    /// starting with `:`.
+    pub fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let before = &line[..pos];
+        let start = before.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &before[start..];
+        let previous = before[..start].split_whitespace().collect::<Vec<_>>();
+        let options: Vec<&str> = match previous.as_slice() {
+            [] if word.starts_with(':') => COMMANDS.to_vec(),
+            [] | [":schema"] => self.names.iter().map(String::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let candidates = options
+            .into_iter()
+            .filter(|option| option.starts_with(word))
+            .map(str::to_string)
+            .collect();
+        (start, candidates)
+    }
+}
+
+impl Completer for ToolCompleter {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl Hinter for ToolCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for ToolCompleter {}
+
+impl Validator for ToolCompleter {}
+
+impl Helper for ToolCompleter {}
@@ -0,0 +1,357 @@
+//! Running untrusted tools in a separate worker process.
+//!
+//! A tool moved into a [`Sandbox`] with [`ToolHandler::sandbox`] no longer runs in the host
+//! process. Each call spawns a worker — by default the current executable — that receives
+//! the tool name and arguments as JSON on stdin, runs the tool and writes the result to
+//! stdout. A panicking, crashing or memory-hogging tool then only takes down its worker,
+//! and the call fails with `ToolError::Execution`.
+//!
+//! Workers are bounded by [`SandboxLimits`]: wall-clock time, the size of their output,
+//! and on Unix address space and CPU time. [`Sandbox::kill_all`] stops every worker still running.
+//!
+//! The worker runs tools registered with `#[tool]` in the same binary, so only those can be
+//! sandboxed, and the program has to hand control to the worker before doing anything else:
+//!
+//! ```rust,no_run
+//! use tool_calling::sandbox::{self, Sandbox, SandboxLimits};
+//! use tool_calling::{tool, ToolHandler};
+//! use std::time::Duration;
+//!
+//! #[tool]
+//! /// Parse an untrusted document
+//! fn parse_document(bytes: Vec<u8>) -> String {
+//!     format!("{} bytes", bytes.len())
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     // In a worker, this runs the requested tool and exits
+//!     sandbox::run_worker_if_requested();
+//!
+//!     let limits = SandboxLimits {
+//!         timeout: Some(Duration::from_secs(5)),
+//!         max_memory_bytes: Some(512 * 1024 * 1024),
+//!         max_cpu_seconds: Some(2),
+//!         ..SandboxLimits::default()
+//!     };
+//!     let mut handler = ToolHandler::default();
+//!     handler
+//!         .sandbox("parse_document", &Sandbox::new().with_limits(limits))
+//!         .unwrap();
+//! }
+//! ```
+
+use crate::{Tool, ToolError, ToolFn, ToolHandler};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::sync::watch;
+
+// Set in a worker's environment so `run_worker_if_requested` takes over
+const WORKER_ENV: &str = "TOOL_CALLING_SANDBOX_WORKER";
+// Prefixes the worker's response, so tools printing to stdout cannot corrupt it
+const RESPONSE_MARKER: &str = "TOOL_CALLING_SANDBOX_RESULT:";
+
+/// Resource limits of a sandboxed call. `None` means unlimited; by default only the output
+/// is limited, to 1 MiB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SandboxLimits {
+    /// Wall-clock time after which the worker is killed.
+    pub timeout: Option<Duration>,
+    /// Maximum address space of the worker, in bytes. Unix only.
+    pub max_memory_bytes: Option<u64>,
+    /// Maximum CPU time of the worker, in seconds. Unix only.
+    pub max_cpu_seconds: Option<u64>,
+    /// Maximum number of bytes the worker may write to stdout, tool output included.
+    pub max_output_bytes: Option<usize>,
+}
+
+impl Default for SandboxLimits {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            max_memory_bytes: None,
+            max_cpu_seconds: None,
+            max_output_bytes: Some(1024 * 1024),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WorkerRequest {
+    tool: String,
+    args: Vec<Value>,
+}
+
+/// How sandboxed tools are run: the worker program and its limits.
+///
+/// Clones share the kill switch, so [`Sandbox::kill_all`] on any clone stops the workers
+/// of every tool using it.
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    program: Option<PathBuf>,
+    args: Vec<String>,
+    limits: SandboxLimits,
+    kill: Arc<watch::Sender<u64>>,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sandbox {
+    /// A sandbox that runs the current executable as its worker, with no limits.
+    pub fn new() -> Self {
+        Self {
+            program: None,
+            args: Vec::new(),
+            limits: SandboxLimits::default(),
+            kill: Arc::new(watch::channel(0).0),
+        }
+    }
+
+    /// Runs `program` with `args` as the worker instead of the current executable.
+    ///
+    /// The program must call [`run_worker_if_requested`] and have the sandboxed tools
+    /// registered.
+    pub fn with_program<I, S>(mut self, program: impl Into<PathBuf>, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.program = Some(program.into());
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the limits of each call.
+    pub fn with_limits(mut self, limits: SandboxLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Kills every worker currently running; their calls fail with `ToolError::Execution`.
+    pub fn kill_all(&self) {
+        self.kill.send_modify(|generation| *generation += 1);
+    }
+
+    /// Returns a copy of `tool` that runs in this sandbox.
+    ///
+    /// Streaming tools are run to completion and return their whole output as one chunk.
+    pub fn wrap(&self, tool: &Tool) -> Tool {
+        let sandbox = self.clone();
+        let name = tool.name.clone();
+        let function = ToolFn::Async(Box::new(move |args: Vec<Value>| {
+            let sandbox = sandbox.clone();
+            let request = WorkerRequest {
+                tool: name.clone(),
+                args,
+            };
+            Box::pin(async move { sandbox.run(request).await })
+        }));
+        Tool::new(
+            &tool.name,
+            &tool.description,
+            tool.parameter_schema.clone(),
+            function,
+        )
+        .with_tags(tool.tags.clone())
+        .with_secret_params(tool.secret_params.clone())
+    }
+
+    async fn run(&self, request: WorkerRequest) -> Result<String, ToolError> {
+        let name = request.tool.clone();
+        let failed =
+            |reason: String| ToolError::Execution(format!("sandboxed tool '{}' {}", name, reason));
+
+        let program = match &self.program {
+            Some(program) => program.clone(),
+            None => {
+                std::env::current_exe().map_err(|e| failed(format!("could not start: {}", e)))?
+            }
+        };
+        let mut command = Command::new(program);
+        command
+            .args(&self.args)
+            .env(WORKER_ENV, "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
+        apply_limits(&mut command, self.limits);
+
+        // Subscribe before spawning so a kill sent while starting is not missed
+        let mut kill = self.kill.subscribe();
+        let mut child = command
+            .spawn()
+            .map_err(|e| failed(format!("could not start: {}", e)))?;
+
+        let payload = serde_json::to_vec(&request).map_err(|e| failed(e.to_string()))?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let max_output = self.limits.max_output_bytes;
+        let io = async move {
+            // The worker may exit without reading its input; its status explains why
+            let _ = stdin.write_all(&payload).await;
+            drop(stdin);
+            // Read one byte past the limit to tell a full buffer from an overflowing one.
+            // Returning early closes the pipe, so a flooding worker stops on its next write
+            let mut output = Vec::new();
+            stdout
+                .take(max_output.map_or(u64::MAX, |max| max as u64 + 1))
+                .read_to_end(&mut output)
+                .await
+                .map_err(|e| format!("could not read the output: {}", e))?;
+            match max_output {
+                Some(max) if output.len() > max => {
+                    Err(format!("wrote more than {} bytes of output", max))
+                }
+                _ => Ok(output),
+            }
+        };
+
+        let finished = async {
+            let (output, status) = tokio::join!(io, child.wait());
+            (output, status)
+        };
+        let timeout = async {
+            match self.limits.timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+        let (output, status) = tokio::select! {
+            result = finished => result,
+            _ = timeout => {
+                return Err(failed(format!(
+                    "timed out after {:?}",
+                    self.limits.timeout.unwrap_or_default()
+                )));
+            }
+            _ = kill.changed() => return Err(failed("was killed".to_string())),
+        };
+        let status = status.map_err(|e| failed(e.to_string()))?;
+        let output = output.map_err(failed)?;
+
+        // Tools may print anything; the response line itself is always valid JSON
+        match String::from_utf8_lossy(&output)
+            .lines()
+            .rev()
+            .find_map(|line| line.strip_prefix(RESPONSE_MARKER))
+        {
+            Some(response) => serde_json::from_str::<Result<String, ToolError>>(response)
+                .map_err(|e| failed(format!("sent an invalid response: {}", e)))?,
+            None => Err(failed(describe_exit(status))),
+        }
+    }
+}
+
+fn describe_exit(status: ExitStatus) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return match signal {
+                libc::SIGXCPU => "exceeded its CPU limit".to_string(),
+                _ => format!("was terminated by signal {}", signal),
+            };
+        }
+    }
+    format!("exited with {} before responding", status)
+}
+
+#[cfg(unix)]
+fn apply_limits(command: &mut Command, limits: SandboxLimits) {
+    if limits.max_memory_bytes.is_none() && limits.max_cpu_seconds.is_none() {
+        return;
+    }
+    let set = |resource, value: u64| {
+        let limit = libc::rlimit {
+            rlim_cur: value as libc::rlim_t,
+            rlim_max: value as libc::rlim_t,
+        };
+        // SAFETY: `setrlimit` only reads the struct passed by reference
+        if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    };
+    // SAFETY: the closure runs in the forked child before `exec` and only calls
+    // `setrlimit`, which is async-signal-safe
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(bytes) = limits.max_memory_bytes {
+                set(libc::RLIMIT_AS, bytes)?;
+            }
+            if let Some(seconds) = limits.max_cpu_seconds {
+                set(libc::RLIMIT_CPU, seconds)?;
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_limits(_command: &mut Command, _limits: SandboxLimits) {}
+
+/// Runs the requested tool and exits if this process was started as a sandbox worker;
+/// otherwise returns immediately.
+///
+/// Call it first thing in `main`, before any other work, in every program whose tools are
+/// sandboxed.
+pub fn run_worker_if_requested() {
+    if std::env::var_os(WORKER_ENV).is_none() {
+        return;
+    }
+    let response = run_worker();
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(
+        stdout,
+        "\n{}{}",
+        RESPONSE_MARKER,
+        serde_json::to_string(&response).unwrap_or_default()
+    );
+    let _ = stdout.flush();
+    std::process::exit(0);
+}
+
+fn run_worker() -> Result<String, ToolError> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| ToolError::Execution(format!("worker could not read its request: {}", e)))?;
+    let request: WorkerRequest = serde_json::from_str(&input)
+        .map_err(|e| ToolError::Execution(format!("worker received an invalid request: {}", e)))?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| ToolError::Execution(format!("worker could not start: {}", e)))?;
+    let handler = ToolHandler::default();
+    runtime.block_on(handler.call_with_values(&request.tool, request.args))
+}
+
+impl ToolHandler {
+    /// Moves the registered tool `name` into `sandbox`, so it runs in a worker process.
+    ///
+    /// Only `#[tool]` functions can be sandboxed, since the worker looks the tool up among
+    /// them; tools added with [`ToolHandler::register`] or [`ToolHandler::with_tool`] cannot.
+    /// Fails with `ToolError::NotFound` if no such tool is registered or it is not the
+    /// `#[tool]` of that name. See the [`sandbox`](crate::sandbox) module.
+    pub fn sandbox(&mut self, name: &str, sandbox: &Sandbox) -> Result<(), ToolError> {
+        let tool = self
+            .get_tool(name)
+            .filter(|tool| crate::get_tool(name).is_some_and(|found| std::ptr::eq(*tool, found)))
+            .ok_or_else(|| ToolError::NotFound(name.to_string()))?;
+        let wrapped = sandbox.wrap(tool);
+        self.register(wrapped);
+        Ok(())
+    }
+}
@@ -0,0 +1,751 @@
+use crate::Tool;
+use serde_json::{json, Map, Value};
+
+/// The provider-specific shape used when rendering a tool definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaFlavor {
+    /// `{"type": "function", "function": {"name", "description", "parameters"}}`
+    OpenAi,
+    /// Same shape as [`SchemaFlavor::OpenAi`], as accepted by Ollama's chat API.
+    Ollama,
+    /// Same shape as [`SchemaFlavor::OpenAi`], as accepted by Mistral's chat API.
+    Mistral,
+    /// `{"name", "description", "input_schema"}`
+    Anthropic,
+    /// A Gemini function declaration, with parameters converted to its OpenAPI subset.
+    Gemini,
+    /// `{"name", "description", "parameter_definitions"}`, the tool shape of Cohere's v1 chat
+    /// API, with each parameter typed by a Python type name.
+    Cohere,
+    /// `{"toolSpec": {"name", "description", "inputSchema": {"json"}}}`, a tool of an AWS
+    /// Bedrock Converse request's `toolConfig`.
+    Bedrock,
+    /// `{"name", "description", "args_schema"}`, the JSON form of a LangChain tool, with
+    /// pydantic-style titles added to the schema.
+    LangChain,
+}
+
+/// A JSON Schema draft, used to validate tool arguments and to render parameter schemas.
+///
+/// Schemas generated by `#[tool]` mean the same in every draft. For hand-written schemas,
+/// [`Tool::schema_for_draft`] rewrites the keywords whose spelling differs between drafts:
+/// tuple `items` and `prefixItems`, `additionalItems`, `definitions` and `$defs` (with the
+/// `$ref`s pointing into them), and `dependencies`.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::{json, Value};
+/// use tool_calling::{SchemaDraft, Tool, ToolFn};
+///
+/// let tool = Tool::new(
+///     "move_to",
+///     "Moves to a point",
+///     json!({
+///         "type": "object",
+///         "properties": {
+///             "point": { "type": "array", "items": [{ "type": "number" }, { "type": "number" }], "additionalItems": false }
+///         },
+///         "required": ["point"]
+///     }),
+///     ToolFn::Async(Box::new(|_: Vec<Value>| Box::pin(async { Ok(String::new()) }))),
+/// );
+/// let point = &tool.schema_for_draft(SchemaDraft::Draft202012)["properties"]["point"];
+/// assert_eq!(point["prefixItems"], json!([{ "type": "number" }, { "type": "number" }]));
+/// assert_eq!(point["items"], false);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SchemaDraft {
+    /// JSON Schema Draft 4
+    Draft4,
+    /// JSON Schema Draft 6
+    Draft6,
+    /// JSON Schema Draft 7, used unless another draft is chosen.
+    Draft7,
+    /// JSON Schema Draft 2019-09
+    Draft201909,
+    /// JSON Schema Draft 2020-12
+    Draft202012,
+}
+
+impl SchemaDraft {
+    /// The URI of the draft's meta-schema, as used in `$schema`.
+    pub fn meta_schema(self) -> &'static str {
+        match self {
+            Self::Draft4 => "http://json-schema.org/draft-04/schema#",
+            Self::Draft6 => "http://json-schema.org/draft-06/schema#",
+            Self::Draft7 => "http://json-schema.org/draft-07/schema#",
+            Self::Draft201909 => "https://json-schema.org/draft/2019-09/schema",
+            Self::Draft202012 => "https://json-schema.org/draft/2020-12/schema",
+        }
+    }
+
+    pub(crate) fn validator(self) -> jsonschema::Draft {
+        match self {
+            Self::Draft4 => jsonschema::Draft::Draft4,
+            Self::Draft6 => jsonschema::Draft::Draft6,
+            Self::Draft7 => jsonschema::Draft::Draft7,
+            Self::Draft201909 => jsonschema::Draft::Draft201909,
+            Self::Draft202012 => jsonschema::Draft::Draft202012,
+        }
+    }
+
+    // Whether the draft spells definitions `$defs` and splits `dependencies`
+    fn is_modern(self) -> bool {
+        matches!(self, Self::Draft201909 | Self::Draft202012)
+    }
+}
+
+impl Tool {
+    /// This tool's parameter schema with draft-specific keywords rewritten for `draft`.
+    pub fn schema_for_draft(&self, draft: SchemaDraft) -> Value {
+        to_draft(&self.parameter_schema, draft)
+    }
+
+    /// Like [`Tool::render_schema`], with the parameter schema rewritten for `draft`.
+    pub fn render_schema_for_draft(&self, flavor: SchemaFlavor, draft: SchemaDraft) -> Value {
+        render(
+            flavor,
+            &self.name,
+            &self.description,
+            &self.schema_for_draft(draft),
+        )
+    }
+
+    /// Renders this tool's definition in the shape expected by the given provider.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use tool_calling::{tool, SchemaFlavor, ToolHandler};
+    ///
+    /// #[tool]
+    /// /// Adds two integers
+    /// fn add(a: i32, b: i32) -> String {
+    ///     (a + b).to_string()
+    /// }
+    ///
+    /// let handler = ToolHandler::default();
+    /// let tool = handler.get_tool("add").unwrap();
+    /// let rendered = tool.render_schema(SchemaFlavor::Anthropic);
+    /// assert_eq!(rendered["name"], "add");
+    /// assert_eq!(rendered["input_schema"]["required"][0], "a");
+    /// ```
+    pub fn render_schema(&self, flavor: SchemaFlavor) -> Value {
+        render(
+            flavor,
+            &self.name,
+            &self.description,
+            &self.parameter_schema,
+        )
+    }
+
+    /// Renders a minimal version of this tool's definition for token-constrained prompts.
+    ///
+    /// Descriptions are cut to their first sentence, `default`s are removed, and `null` is
+    /// dropped from type unions. Only the rendered copy is compacted; arguments are still
+    /// validated against the full [`Tool::parameter_schema`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use tool_calling::{tool, SchemaFlavor, ToolHandler};
+    ///
+    /// #[tool]
+    /// /// Searches the web. Results are ranked by relevance and deduplicated.
+    /// fn search(query: String, limit: Option<u32>) -> String {
+    ///     format!("{} {:?}", query, limit)
+    /// }
+    ///
+    /// let handler = ToolHandler::default();
+    /// let rendered = handler
+    ///     .get_tool("search")
+    ///     .unwrap()
+    ///     .render_compact_schema(SchemaFlavor::OpenAi);
+    /// assert_eq!(rendered["function"]["description"], "Searches the web.");
+    /// assert_eq!(
+    ///     rendered["function"]["parameters"]["properties"]["limit"],
+    ///     json!({ "type": "integer" })
+    /// );
+    /// ```
+    pub fn render_compact_schema(&self, flavor: SchemaFlavor) -> Value {
+        render(
+            flavor,
+            &self.name,
+            first_sentence(&self.description),
+            &compact(&self.parameter_schema),
+        )
+    }
+
+    /// Renders this tool's definition for the OpenAI API, optionally in strict mode.
+    ///
+    /// In strict mode the definition carries `"strict": true`, every object schema gets
+    /// `additionalProperties: false` and lists all of its properties as `required`, and
+    /// properties that were optional accept `null` instead. `default`s are removed, as
+    /// strict mode rejects them. Without strict mode this is
+    /// `render_schema(SchemaFlavor::OpenAi)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use tool_calling::{tool, ToolHandler};
+    ///
+    /// #[tool]
+    /// /// Searches the web
+    /// fn search(query: String, limit: Option<u32>) -> String {
+    ///     format!("{} {:?}", query, limit)
+    /// }
+    ///
+    /// let handler = ToolHandler::default();
+    /// let rendered = handler.get_tool("search").unwrap().render_openai_schema(true);
+    /// assert_eq!(rendered["function"]["strict"], true);
+    /// let parameters = &rendered["function"]["parameters"];
+    /// assert_eq!(parameters["required"], json!(["query", "limit"]));
+    /// assert_eq!(parameters["additionalProperties"], false);
+    /// ```
+    pub fn render_openai_schema(&self, strict: bool) -> Value {
+        if !strict {
+            return self.render_schema(SchemaFlavor::OpenAi);
+        }
+        let mut rendered = render(
+            SchemaFlavor::OpenAi,
+            &self.name,
+            &self.description,
+            &to_strict(&self.parameter_schema),
+        );
+        rendered["function"]["strict"] = json!(true);
+        rendered
+    }
+}
+
+fn render(flavor: SchemaFlavor, name: &str, description: &str, parameters: &Value) -> Value {
+    match flavor {
+        SchemaFlavor::OpenAi | SchemaFlavor::Ollama | SchemaFlavor::Mistral => json!({
+            "type": "function",
+            "function": {
+                "name": name,
+                "description": description,
+                "parameters": parameters
+            }
+        }),
+        SchemaFlavor::Anthropic => json!({
+            "name": name,
+            "description": description,
+            "input_schema": parameters
+        }),
+        SchemaFlavor::Gemini => json!({
+            "name": name,
+            "description": description,
+            "parameters": to_openapi_subset(parameters)
+        }),
+        SchemaFlavor::Cohere => json!({
+            "name": name,
+            "description": description,
+            "parameter_definitions": to_parameter_definitions(parameters)
+        }),
+        SchemaFlavor::Bedrock => {
+            let mut spec = json!({ "name": name, "inputSchema": { "json": parameters } });
+            // Bedrock rejects an empty description, but accepts none
+            if !description.is_empty() {
+                spec["description"] = json!(description);
+            }
+            json!({ "toolSpec": spec })
+        }
+        SchemaFlavor::LangChain => json!({
+            "name": name,
+            "description": description,
+            "args_schema": to_args_schema(name, parameters)
+        }),
+    }
+}
+
+// The text up to and including the first `.`, `!` or `?` followed by whitespace, or the
+// first line
+pub(crate) fn first_sentence(text: &str) -> &str {
+    let text = text.trim();
+    let line = text.lines().next().unwrap_or_default();
+    let end = line
+        .char_indices()
+        .zip(line.chars().skip(1).map(Some).chain([None]))
+        .find(|((_, c), next)| matches!(c, '.' | '!' | '?') && next.is_none_or(char::is_whitespace))
+        .map_or(line.len(), |((i, c), _)| i + c.len_utf8());
+    &line[..end]
+}
+
+// Keywords whose value is a map of names to schemas
+const SCHEMA_MAPS: &[&str] = &["properties", "$defs", "definitions", "patternProperties"];
+// Keywords whose value is a list of schemas
+const SCHEMA_LISTS: &[&str] = &["anyOf", "oneOf", "allOf", "prefixItems"];
+// Keywords whose value is a single schema
+const SCHEMA_VALUES: &[&str] = &["items", "additionalProperties", "not"];
+
+/// Strips `default`s, cuts descriptions to their first sentence and removes `null` from
+/// type unions, recursing into nested schemas.
+pub(crate) fn compact(schema: &Value) -> Value {
+    let Some(obj) = schema.as_object() else {
+        return schema.clone();
+    };
+    let mut out = Map::new();
+    for (key, value) in obj {
+        let value = match key.as_str() {
+            "default" => continue,
+            "description" => match value.as_str() {
+                Some(text) => json!(first_sentence(text)),
+                None => value.clone(),
+            },
+            "type" => match value.as_array() {
+                Some(types) => {
+                    let non_null = types
+                        .iter()
+                        .filter(|ty| *ty != "null")
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    match non_null.len() {
+                        0 => value.clone(),
+                        1 => non_null[0].clone(),
+                        _ => Value::Array(non_null),
+                    }
+                }
+                None => value.clone(),
+            },
+            k if SCHEMA_MAPS.contains(&k) => match value.as_object() {
+                Some(map) => Value::Object(
+                    map.iter()
+                        .map(|(name, schema)| (name.clone(), compact(schema)))
+                        .collect(),
+                ),
+                None => value.clone(),
+            },
+            k if SCHEMA_LISTS.contains(&k) => match value.as_array() {
+                Some(list) => {
+                    let kept = list
+                        .iter()
+                        .filter(|schema| k == "prefixItems" || !is_null(schema))
+                        .map(compact)
+                        .collect::<Vec<_>>();
+                    // A union left with one member is replaced by that member
+                    if kept.len() == 1 && (k == "anyOf" || k == "oneOf") {
+                        if let Value::Object(member) = &kept[0] {
+                            for (key, value) in member {
+                                out.entry(key.clone()).or_insert_with(|| value.clone());
+                            }
+                            continue;
+                        }
+                    }
+                    Value::Array(kept)
+                }
+                None => value.clone(),
+            },
+            k if SCHEMA_VALUES.contains(&k) => compact(value),
+            _ => value.clone(),
+        };
+        out.insert(key.clone(), value);
+    }
+    Value::Object(out)
+}
+
+// Keywords whose value is a single schema or, for tuple `items`, a list of schemas
+const DRAFT_SUBSCHEMAS: &[&str] = &[
+    "items",
+    "additionalItems",
+    "additionalProperties",
+    "not",
+    "contains",
+    "propertyNames",
+    "if",
+    "then",
+    "else",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// Rewrites the keywords whose spelling differs between drafts into the spelling of
+/// `draft`, recursing into nested schemas.
+pub(crate) fn to_draft(schema: &Value, draft: SchemaDraft) -> Value {
+    let Some(obj) = schema.as_object() else {
+        return schema.clone();
+    };
+    let convert_list = |value: &Value| match value.as_array() {
+        Some(list) => list.iter().map(|schema| to_draft(schema, draft)).collect(),
+        None => value.clone(),
+    };
+    let convert_map = |value: &Value| match value.as_object() {
+        Some(map) => Value::Object(
+            map.iter()
+                .map(|(name, schema)| (name.clone(), to_draft(schema, draft)))
+                .collect(),
+        ),
+        None => value.clone(),
+    };
+    let tuple = obj.get("prefixItems").is_some() || obj.get("items").is_some_and(Value::is_array);
+    let mut out = Map::new();
+    let mut dependencies = Map::new();
+    let mut dependent_required = Map::new();
+    let mut dependent_schemas = Map::new();
+    for (key, value) in obj {
+        match key.as_str() {
+            "definitions" | "$defs" => {
+                let key = if draft.is_modern() {
+                    "$defs"
+                } else {
+                    "definitions"
+                };
+                out.insert(key.into(), convert_map(value));
+            }
+            "$ref" => {
+                let reference = value.as_str().map(|reference| match draft.is_modern() {
+                    true => reference.replacen("#/definitions/", "#/$defs/", 1),
+                    false => reference.replacen("#/$defs/", "#/definitions/", 1),
+                });
+                out.insert(
+                    key.clone(),
+                    reference.map_or_else(|| value.clone(), Value::from),
+                );
+            }
+            // The tuple members: `prefixItems` in 2020-12, an `items` list before
+            "prefixItems" | "items" if value.is_array() => {
+                let key = match draft {
+                    SchemaDraft::Draft202012 => "prefixItems",
+                    _ => "items",
+                };
+                out.insert(key.into(), convert_list(value));
+            }
+            // The schema of items after the tuple: `items` in 2020-12, `additionalItems` before
+            "items" | "additionalItems" if tuple => {
+                let key = match draft {
+                    SchemaDraft::Draft202012 => "items",
+                    _ => "additionalItems",
+                };
+                out.insert(key.into(), to_draft(value, draft));
+            }
+            "dependencies" | "dependentRequired" | "dependentSchemas" => {
+                for (name, dependency) in value.as_object().into_iter().flatten() {
+                    let dependency = match dependency {
+                        Value::Array(_) => {
+                            dependent_required.insert(name.clone(), dependency.clone());
+                            dependency.clone()
+                        }
+                        _ => {
+                            let dependency = to_draft(dependency, draft);
+                            dependent_schemas.insert(name.clone(), dependency.clone());
+                            dependency
+                        }
+                    };
+                    dependencies.insert(name.clone(), dependency);
+                }
+            }
+            k if SCHEMA_MAPS.contains(&k) => {
+                out.insert(key.clone(), convert_map(value));
+            }
+            k if SCHEMA_LISTS.contains(&k) => {
+                out.insert(key.clone(), convert_list(value));
+            }
+            k if DRAFT_SUBSCHEMAS.contains(&k) => {
+                out.insert(key.clone(), to_draft(value, draft));
+            }
+            _ => {
+                out.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    if draft.is_modern() {
+        if !dependent_required.is_empty() {
+            out.insert(
+                "dependentRequired".into(),
+                Value::Object(dependent_required),
+            );
+        }
+        if !dependent_schemas.is_empty() {
+            out.insert("dependentSchemas".into(), Value::Object(dependent_schemas));
+        }
+    } else if !dependencies.is_empty() {
+        out.insert("dependencies".into(), Value::Object(dependencies));
+    }
+    Value::Object(out)
+}
+
+// Whether a schema only accepts `null`
+fn is_null(schema: &Value) -> bool {
+    schema.get("type") == Some(&json!("null"))
+}
+
+// Make every property required and closed to extra keys, as OpenAI's strict mode demands
+pub(crate) fn to_strict(schema: &Value) -> Value {
+    let Some(obj) = schema.as_object() else {
+        return schema.clone();
+    };
+    let mut out = Map::new();
+    for (key, value) in obj {
+        let value = match key.as_str() {
+            "default" => continue,
+            "properties" => match value.as_object() {
+                Some(props) => {
+                    let required = obj
+                        .get("required")
+                        .and_then(Value::as_array)
+                        .cloned()
+                        .unwrap_or_default();
+                    let props = props
+                        .iter()
+                        .map(|(name, prop)| {
+                            let prop = to_strict(prop);
+                            if required.iter().any(|r| r == name) {
+                                (name.clone(), prop)
+                            } else {
+                                (name.clone(), nullable(prop))
+                            }
+                        })
+                        .collect::<Map<_, _>>();
+                    Value::Object(props)
+                }
+                None => value.clone(),
+            },
+            k if SCHEMA_MAPS.contains(&k) => match value.as_object() {
+                Some(map) => Value::Object(
+                    map.iter()
+                        .map(|(name, schema)| (name.clone(), to_strict(schema)))
+                        .collect(),
+                ),
+                None => value.clone(),
+            },
+            k if SCHEMA_LISTS.contains(&k) => match value.as_array() {
+                Some(list) => Value::Array(list.iter().map(to_strict).collect()),
+                None => value.clone(),
+            },
+            "additionalProperties" => continue,
+            k if SCHEMA_VALUES.contains(&k) => to_strict(value),
+            _ => value.clone(),
+        };
+        out.insert(key.clone(), value);
+    }
+    if let Some(props) = out.get("properties").and_then(Value::as_object) {
+        let required = props.keys().cloned().map(Value::String).collect::<Vec<_>>();
+        out.insert("required".into(), Value::Array(required));
+        out.insert("additionalProperties".into(), json!(false));
+    }
+    Value::Object(out)
+}
+
+// Let a schema also accept `null`, unless it already does
+fn nullable(schema: Value) -> Value {
+    let Value::Object(mut obj) = schema else {
+        return schema;
+    };
+    match obj.get_mut("type") {
+        Some(Value::String(ty)) if ty != "null" => {
+            let ty = std::mem::take(ty);
+            obj.insert("type".into(), json!([ty, "null"]));
+            return Value::Object(obj);
+        }
+        Some(Value::Array(types)) => {
+            if !types.iter().any(|t| t == "null") {
+                types.push(json!("null"));
+            }
+            return Value::Object(obj);
+        }
+        Some(_) => return Value::Object(obj),
+        None => {}
+    }
+    match obj.get_mut("anyOf") {
+        Some(Value::Array(members)) => {
+            if !members.iter().any(is_null) {
+                members.push(json!({ "type": "null" }));
+            }
+            Value::Object(obj)
+        }
+        _ => json!({ "anyOf": [obj, { "type": "null" }] }),
+    }
+}
+
+// Keywords Gemini accepts in a parameter schema; everything else is dropped
+const GEMINI_KEYWORDS: &[&str] = &[
+    "format",
+    "description",
+    "nullable",
+    "required",
+    "minItems",
+    "maxItems",
+];
+
+/// Converts a JSON Schema into the OpenAPI subset used by Gemini: uppercase type names,
+/// `nullable` instead of `null` in type unions, string-only enums, and no unsupported
+/// keywords.
+pub(crate) fn to_openapi_subset(schema: &Value) -> Value {
+    let Some(obj) = schema.as_object() else {
+        return schema.clone();
+    };
+    // A union of one schema with `null` becomes that schema, marked nullable
+    for union in ["anyOf", "oneOf"] {
+        let Some(members) = obj.get(union).and_then(Value::as_array) else {
+            continue;
+        };
+        let non_null = members.iter().filter(|m| !is_null(m)).collect::<Vec<_>>();
+        if let [member] = non_null.as_slice() {
+            let mut merged = obj.clone();
+            merged.remove(union);
+            if let Some(member) = member.as_object() {
+                for (key, value) in member {
+                    merged.entry(key.clone()).or_insert_with(|| value.clone());
+                }
+            }
+            let mut out = to_openapi_subset(&Value::Object(merged));
+            if non_null.len() < members.len() {
+                out["nullable"] = json!(true);
+            }
+            return out;
+        }
+    }
+    let mut out = Map::new();
+    match obj.get("type") {
+        Some(Value::String(ty)) => {
+            out.insert("type".into(), json!(ty.to_uppercase()));
+        }
+        Some(Value::Array(types)) => {
+            let mut non_null = types
+                .iter()
+                .filter_map(|t| t.as_str())
+                .filter(|t| *t != "null");
+            if let Some(ty) = non_null.next() {
+                out.insert("type".into(), json!(ty.to_uppercase()));
+            }
+            if types.iter().any(|t| t == "null") {
+                out.insert("nullable".into(), json!(true));
+            }
+        }
+        _ => {}
+    }
+    for (key, value) in obj {
+        match key.as_str() {
+            "properties" => {
+                if let Some(props) = value.as_object() {
+                    let converted = props
+                        .iter()
+                        .map(|(name, prop)| (name.clone(), to_openapi_subset(prop)))
+                        .collect::<Map<_, _>>();
+                    out.insert(key.clone(), Value::Object(converted));
+                }
+            }
+            "items" => {
+                out.insert(key.clone(), to_openapi_subset(value));
+            }
+            // Gemini only takes enums of strings, declared with the `enum` format
+            "enum" => {
+                let values = value.as_array().map(Vec::as_slice).unwrap_or_default();
+                if out.get("type") == Some(&json!("STRING")) && values.iter().all(Value::is_string)
+                {
+                    out.insert(key.clone(), value.clone());
+                    out.insert("format".into(), json!("enum"));
+                }
+            }
+            k if GEMINI_KEYWORDS.contains(&k) => {
+                out.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+            _ => {}
+        }
+    }
+    Value::Object(out)
+}
+
+/// Adds the `title`s pydantic puts in a model's schema: the tool name on the schema, and
+/// each property's name in title case (`user_id` becomes `User Id`), unless one is set.
+pub(crate) fn to_args_schema(name: &str, schema: &Value) -> Value {
+    let mut schema = schema.clone();
+    if let Some(object) = schema.as_object_mut() {
+        object.entry("title").or_insert_with(|| json!(name));
+        let properties = object.get_mut("properties").and_then(Value::as_object_mut);
+        for (key, property) in properties.into_iter().flatten() {
+            if let Some(property) = property.as_object_mut() {
+                property
+                    .entry("title")
+                    .or_insert_with(|| json!(title_case(key)));
+            }
+        }
+    }
+    schema
+}
+
+fn title_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Converts an object schema into Cohere's `parameter_definitions`: a map from parameter
+/// name to its description, Python type name and whether it is required.
+pub(crate) fn to_parameter_definitions(schema: &Value) -> Value {
+    let required = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let definitions = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .map(|(name, property)| {
+            let mut definition = Map::new();
+            let mut description = property
+                .get("description")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            // Cohere has no enums, so the allowed values are spelled out
+            if let Some(values) = property.get("enum").and_then(Value::as_array) {
+                let values = values.iter().map(Value::to_string).collect::<Vec<_>>();
+                if !description.is_empty() {
+                    description.push(' ');
+                }
+                description.push_str(&format!("One of: {}.", values.join(", ")));
+            }
+            if !description.is_empty() {
+                definition.insert("description".into(), json!(description));
+            }
+            definition.insert("type".into(), json!(python_type(property)));
+            definition.insert("required".into(), json!(required.iter().any(|r| r == name)));
+            (name.clone(), Value::Object(definition))
+        })
+        .collect::<Map<_, _>>();
+    Value::Object(definitions)
+}
+
+// The Python type name of a schema: `str`, `List[int]`, `Dict`, ...
+fn python_type(schema: &Value) -> String {
+    let types = match schema.get("type") {
+        Some(Value::String(ty)) => vec![ty.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => {
+            // Optional parameters are `anyOf` the schema and `null`
+            let member = ["anyOf", "oneOf"]
+                .iter()
+                .filter_map(|key| schema.get(key).and_then(Value::as_array))
+                .flatten()
+                .find(|member| !is_null(member));
+            return match member {
+                Some(member) => python_type(member),
+                None => "str".to_string(),
+            };
+        }
+    };
+    match types.into_iter().find(|ty| *ty != "null") {
+        Some("integer") => "int".to_string(),
+        Some("number") => "float".to_string(),
+        Some("boolean") => "bool".to_string(),
+        Some("array") => match schema.get("items") {
+            Some(items) if items.is_object() => format!("List[{}]", python_type(items)),
+            _ => "List".to_string(),
+        },
+        Some("object") => "Dict".to_string(),
+        _ => "str".to_string(),
+    }
+}
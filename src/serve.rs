@@ -0,0 +1,278 @@
+//! An OpenAI-compatible chat completions proxy that runs tool calls itself.
+//!
+//! A [`ChatProxy`] serves `POST /v1/chat/completions`. Each request is forwarded to an
+//! upstream model with the handler's tools added to its `tools`; whenever the model answers
+//! with `tool_calls` for those tools, the proxy runs them with the [`ToolHandler`], appends
+//! the assistant message and one `tool` message per call to the conversation, and asks the
+//! model again, until it replies without tool calls. The client only sees the final
+//! response, so any OpenAI client gains the handler's tools without a tool loop of its own.
+//!
+//! Tools the client declares in its own request take precedence over the handler's, and a
+//! response calling any of them is returned to the client as-is. The request's `user`
+//! becomes the [`ToolContext`] user id. Streaming requests are rejected.
+//!
+//! ```rust,no_run
+//! use tool_calling::serve::ChatProxy;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Looks up the weather in a city
+//! fn weather(city: String) -> String {
+//!     format!("Sunny in {}", city)
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     ChatProxy::new(ToolHandler::default(), "https://api.openai.com/v1")
+//!         .with_api_key(std::env::var("OPENAI_API_KEY").unwrap())
+//!         .serve("127.0.0.1:8080")
+//!         .await
+//!         .unwrap();
+//! }
+//! ```
+
+use crate::chat::{ChatError, ChatLoop, ChatProvider};
+use crate::{ToolContext, ToolHandler};
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use crate::chat::DEFAULT_MAX_ROUNDS;
+
+/// Errors returned while completing a chat request.
+#[derive(Debug, Clone, thiserror::Error, PartialEq)]
+pub enum ServeError {
+    #[error("invalid request: {0}")]
+    BadRequest(String),
+    #[error("upstream request failed: {0}")]
+    Http(String),
+    #[error("upstream returned HTTP {status}: {body}")]
+    Upstream { status: u16, body: String },
+    #[error("model still requested tools after {0} rounds")]
+    TooManyRounds(usize),
+}
+
+impl From<ChatError> for ServeError {
+    fn from(err: ChatError) -> Self {
+        match err {
+            ChatError::Http(message)
+            | ChatError::BadResponse(message)
+            | ChatError::Config(message) => ServeError::Http(message),
+            ChatError::Upstream { status, body } => ServeError::Upstream { status, body },
+            ChatError::TooManyRounds(rounds) => ServeError::TooManyRounds(rounds),
+            ChatError::ToolFailed { name, message } => {
+                ServeError::Http(format!("tool '{}' failed: {}", name, message))
+            }
+            err @ ChatError::RepeatedCall { .. } => ServeError::Http(err.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for ServeError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            ServeError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ServeError::Http(_) | ServeError::TooManyRounds(_) => StatusCode::BAD_GATEWAY,
+            // Pass the upstream's own error through untouched
+            ServeError::Upstream { status, body } => {
+                let status = StatusCode::from_u16(*status).unwrap_or(StatusCode::BAD_GATEWAY);
+                return (
+                    status,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    body.clone(),
+                )
+                    .into_response();
+            }
+        };
+        let body = json!({ "error": { "message": self.to_string(), "type": "tool_proxy_error" } });
+        (status, Json(body)).into_response()
+    }
+}
+
+/// Forwards chat completions to an upstream model, running its tool calls in between.
+pub struct ChatProxy {
+    handler: ToolHandler,
+    upstream: String,
+    api_key: Option<String>,
+    max_rounds: usize,
+    timeout: Option<Duration>,
+    client: reqwest::Client,
+}
+
+impl ChatProxy {
+    /// Creates a proxy for the OpenAI-compatible API at `upstream`, such as
+    /// `https://api.openai.com/v1`; requests go to `{upstream}/chat/completions`.
+    pub fn new(handler: ToolHandler, upstream: impl Into<String>) -> Self {
+        Self {
+            handler,
+            upstream: upstream.into(),
+            api_key: None,
+            max_rounds: DEFAULT_MAX_ROUNDS,
+            timeout: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Authenticates upstream requests with this key. Without one, the client's own
+    /// `Authorization` header is forwarded.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Fails a completion whose model still asks for tools after `rounds` requests.
+    pub fn with_max_rounds(mut self, rounds: usize) -> Self {
+        self.max_rounds = rounds.max(1);
+        self
+    }
+
+    /// Fails upstream requests that take longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The handler whose tools are offered to the model.
+    pub fn handler(&self) -> &ToolHandler {
+        &self.handler
+    }
+
+    /// A router serving `POST /v1/chat/completions`, to mount in an existing app.
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/v1/chat/completions", post(chat_completions))
+            .with_state(Arc::new(self))
+    }
+
+    /// Serves the proxy on `addr` until the process exits.
+    pub async fn serve(self, addr: impl tokio::net::ToSocketAddrs) -> std::io::Result<()> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        axum::serve(listener, self.router()).await
+    }
+
+    /// Completes a chat request, running the handler's tools until the model answers, and
+    /// returns the final upstream response.
+    pub async fn complete(&self, request: Value) -> Result<Value, ServeError> {
+        self.run(request, None).await
+    }
+
+    async fn run(
+        &self,
+        mut request: Value,
+        authorization: Option<&str>,
+    ) -> Result<Value, ServeError> {
+        let Some(body) = request.as_object_mut() else {
+            return Err(ServeError::BadRequest(
+                "request body must be a JSON object".into(),
+            ));
+        };
+        if body.get("stream") == Some(&json!(true)) {
+            return Err(ServeError::BadRequest(
+                "streaming responses are not supported".into(),
+            ));
+        }
+        let Some(Value::Array(mut messages)) = body.remove("messages") else {
+            return Err(ServeError::BadRequest("missing 'messages' array".into()));
+        };
+        let context = match body.get("user").and_then(Value::as_str) {
+            Some(user) => ToolContext::new().with_user_id(user),
+            None => ToolContext::new(),
+        };
+        // The client's own tools are offered next to the handler's
+        let client_tools = match body.remove("tools") {
+            Some(Value::Array(tools)) => tools,
+            _ => Vec::new(),
+        };
+
+        let upstream = Upstream {
+            proxy: self,
+            request: &request,
+            authorization,
+        };
+        let response = ChatLoop::new(&self.handler, &upstream)
+            .with_context(context)
+            .with_external_tools(client_tools)
+            .with_max_rounds(self.max_rounds)
+            .run(&mut messages)
+            .await?;
+        Ok(response)
+    }
+}
+
+// The upstream model, asked with the client's request and the current conversation
+struct Upstream<'a> {
+    proxy: &'a ChatProxy,
+    request: &'a Value,
+    authorization: Option<&'a str>,
+}
+
+impl ChatProvider for Upstream<'_> {
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        Box::pin(async move {
+            let mut request = self.request.clone();
+            request["messages"] = json!(messages);
+            if !tools.is_empty() {
+                request["tools"] = json!(tools);
+            }
+            self.send(&request).await
+        })
+    }
+}
+
+impl Upstream<'_> {
+    async fn send(&self, request: &Value) -> Result<Value, ChatError> {
+        let proxy = self.proxy;
+        let url = format!("{}/chat/completions", proxy.upstream.trim_end_matches('/'));
+        let mut builder = proxy.client.post(url).json(request);
+        match (&proxy.api_key, self.authorization) {
+            (Some(key), _) => builder = builder.bearer_auth(key),
+            (None, Some(authorization)) => {
+                builder = builder.header(header::AUTHORIZATION.as_str(), authorization)
+            }
+            (None, None) => {}
+        }
+        if let Some(timeout) = proxy.timeout {
+            builder = builder.timeout(timeout);
+        }
+        let response = builder
+            .send()
+            .await
+            .map_err(|err| ChatError::Http(err.to_string()))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|err| ChatError::Http(err.to_string()))?;
+        if !status.is_success() {
+            return Err(ChatError::Upstream {
+                status: status.as_u16(),
+                body,
+            });
+        }
+        serde_json::from_str(&body).map_err(|err| ChatError::BadResponse(err.to_string()))
+    }
+}
+
+async fn chat_completions(
+    State(proxy): State<Arc<ChatProxy>>,
+    headers: HeaderMap,
+    Json(request): Json<Value>,
+) -> Response {
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    match proxy.run(request, authorization).await {
+        Ok(response) => Json(response).into_response(),
+        Err(err) => err.into_response(),
+    }
+}
@@ -0,0 +1,155 @@
+//! Per-conversation state that tools can read and write across calls.
+//!
+//! Every [`ToolHandler`](crate::ToolHandler) manages a [`SessionStore`]. When a call is made
+//! with a [`ToolContext`](crate::ToolContext) carrying a conversation id, the handler opens
+//! that conversation's [`Session`] and makes it available through
+//! [`ToolContext::session`](crate::ToolContext::session). Values are stored as JSON, so any
+//! `Serialize` type can be kept and read back as any `Deserialize` type.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::{tool, ToolContext, ToolHandler};
+//!
+//! #[tool]
+//! /// Remember the user's preferred unit
+//! fn set_unit(unit: String, #[param(context)] ctx: ToolContext) -> String {
+//!     match ctx.session() {
+//!         Some(session) => {
+//!             session.set("unit", &unit).unwrap();
+//!             format!("will use {}", unit)
+//!         }
+//!         None => "no conversation to remember it in".to_string(),
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let handler = ToolHandler::default();
+//!     let ctx = ToolContext::new().with_conversation_id("conv-1");
+//!     let call = json!({
+//!         "type": "function",
+//!         "function": { "name": "set_unit", "arguments": { "unit": "celsius" } }
+//!     });
+//!     handler.call_tool_with_context(&call, &ctx).await.unwrap();
+//!
+//!     let session = handler.session_store().get("conv-1").unwrap();
+//!     assert_eq!(session.get::<String>("unit").as_deref(), Some("celsius"));
+//! }
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Sessions keyed by conversation id.
+#[derive(Debug, Default)]
+pub struct SessionStore {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl SessionStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the session for `id`, creating an empty one if there is none yet.
+    pub fn session(&self, id: &str) -> Session {
+        self.sessions
+            .lock()
+            .unwrap()
+            .entry(id.to_string())
+            .or_insert_with(|| Session::new(id))
+            .clone()
+    }
+
+    /// Returns the session for `id`, if one was created.
+    pub fn get(&self, id: &str) -> Option<Session> {
+        self.sessions.lock().unwrap().get(id).cloned()
+    }
+
+    /// Removes and returns the session for `id`, for example when the conversation ends.
+    pub fn remove(&self, id: &str) -> Option<Session> {
+        self.sessions.lock().unwrap().remove(id)
+    }
+
+    /// Ids of the stored sessions, in no particular order.
+    pub fn ids(&self) -> Vec<String> {
+        self.sessions.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Number of stored sessions.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+
+    /// Whether no sessions are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The state of one conversation.
+///
+/// Sessions are handles: clones share the same values.
+#[derive(Debug, Clone)]
+pub struct Session {
+    id: String,
+    values: Arc<Mutex<Map<String, Value>>>,
+}
+
+impl Session {
+    fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            values: Arc::default(),
+        }
+    }
+
+    /// The conversation id this session belongs to.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Reads `key` as a `T`, or `None` if it is unset or holds a value of another shape.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.get_value(key)
+            .and_then(|value| serde_json::from_value(value).ok())
+    }
+
+    /// Reads `key` as raw JSON.
+    pub fn get_value(&self, key: &str) -> Option<Value> {
+        self.values.lock().unwrap().get(key).cloned()
+    }
+
+    /// Stores `value` under `key`, replacing the previous value.
+    ///
+    /// Fails only if `value` cannot be represented as JSON.
+    pub fn set<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> serde_json::Result<()> {
+        let value = serde_json::to_value(value)?;
+        self.values.lock().unwrap().insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Removes and returns the value under `key`.
+    pub fn remove(&self, key: &str) -> Option<Value> {
+        self.values.lock().unwrap().remove(key)
+    }
+
+    /// Removes every value.
+    pub fn clear(&self) {
+        self.values.lock().unwrap().clear();
+    }
+
+    /// A copy of every value in the session, for example to persist it.
+    pub fn snapshot(&self) -> Map<String, Value> {
+        self.values.lock().unwrap().clone()
+    }
+
+    /// Replaces every value with those in `values`, for example to restore a snapshot.
+    pub fn restore(&self, values: Map<String, Value>) {
+        *self.values.lock().unwrap() = values;
+    }
+}
@@ -0,0 +1,95 @@
+//! Structured output driven by tool definitions.
+//!
+//! A tool's parameter schema describes a JSON object, which is also what a structured-output
+//! request asks the model to produce. [`Tool::response_format`] renders that schema as the
+//! `response_format` of an OpenAI chat request, and
+//! [`ToolHandler::parse_structured_output`] checks the reply against the same schema, so a
+//! single `#[tool]` definition can be offered as a tool or demanded as the answer.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Records a support ticket
+//! fn file_ticket(title: String, priority: Option<u8>) -> String {
+//!     format!("{} {:?}", title, priority)
+//! }
+//!
+//! let handler = ToolHandler::default();
+//! let format = handler.response_format("file_ticket", true).unwrap();
+//! assert_eq!(format["type"], "json_schema");
+//! assert_eq!(format["json_schema"]["strict"], true);
+//! assert_eq!(format["json_schema"]["schema"]["required"], json!(["title", "priority"]));
+//!
+//! let reply = json!({ "role": "assistant", "content": "{\"title\": \"Broken login\", \"priority\": null}" });
+//! let call = handler.parse_structured_output("file_ticket", &reply).unwrap();
+//! assert_eq!(call.arguments["title"], "Broken login");
+//! ```
+
+use crate::schema::to_strict;
+use crate::{Tool, ToolError, ToolHandler, ValidatedCall};
+use serde_json::{json, Map, Value};
+
+impl Tool {
+    /// This tool's parameter schema as the `response_format` of an OpenAI chat request.
+    ///
+    /// With `strict` the schema is rewritten as [`Tool::render_openai_schema`] does for
+    /// strict tool calls, and the format carries `"strict": true`.
+    pub fn response_format(&self, strict: bool) -> Value {
+        let schema = if strict {
+            to_strict(&self.parameter_schema)
+        } else {
+            self.parameter_schema.clone()
+        };
+        let mut json_schema = Map::new();
+        json_schema.insert("name".into(), json!(self.name));
+        if !self.description.is_empty() {
+            json_schema.insert("description".into(), json!(self.description));
+        }
+        json_schema.insert("schema".into(), schema);
+        json_schema.insert("strict".into(), json!(strict));
+        json!({ "type": "json_schema", "json_schema": json_schema })
+    }
+}
+
+impl ToolHandler {
+    /// The `response_format` asking for the arguments of the tool `name`.
+    ///
+    /// See [`Tool::response_format`].
+    pub fn response_format(&self, name: &str, strict: bool) -> Result<Value, ToolError> {
+        let tool = self
+            .get_tool(name)
+            .ok_or_else(|| ToolError::NotFound(name.to_string()))?;
+        Ok(tool.response_format(strict))
+    }
+
+    /// Validates structured output against the parameter schema of the tool `name`.
+    ///
+    /// `output` is the JSON object itself, its text, or the assistant message or chat
+    /// completion response carrying it as `content`. The object is checked as the arguments
+    /// of a call to `name`, with the same rules as [`ToolHandler::validate_tool_call`], so the
+    /// result can also be passed on to the tool.
+    pub fn parse_structured_output(
+        &self,
+        name: &str,
+        output: &Value,
+    ) -> Result<ValidatedCall, ToolError> {
+        let content = if output.get("choices").is_some() {
+            &output["choices"][0]["message"]["content"]
+        } else if output.get("role").is_some() {
+            &output["content"]
+        } else {
+            output
+        };
+        if !matches!(content, Value::Object(_) | Value::String(_)) {
+            return Err(ToolError::BadArgs(
+                "Structured output is not a JSON object".to_string(),
+            ));
+        }
+        self.validate_tool_call(&json!({
+            "type": "function",
+            "function": { "name": name, "arguments": content }
+        }))
+    }
+}
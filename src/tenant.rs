@@ -0,0 +1,263 @@
+//! Separate tool registries per tenant.
+//!
+//! A [`TenantedHandler`] holds one [`ToolHandler`] per tenant, so each customer gets its own
+//! tool set, audit sink and other handler settings, plus an optional [`RateLimit`]. Every
+//! call names its tenant, either directly or through a resolver reading the call's
+//! [`ToolContext`]; by default the resolver reads a [`TenantId`] extension.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use serde_json::json;
+//! use tool_calling::audit::MemoryAuditLog;
+//! use tool_calling::tenant::{RateLimit, Tenant, TenantError, TenantId, TenantedHandler};
+//! use tool_calling::{tool, ToolContext, ToolHandler};
+//!
+//! #[tool]
+//! fn ping() -> String {
+//!     "pong".to_string()
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let acme_log = Arc::new(MemoryAuditLog::with_capacity(100));
+//!     let acme = ToolHandler::default().with_audit(acme_log.clone());
+//!     let tenants = TenantedHandler::new()
+//!         .with_tenant("acme", Tenant::new(acme).with_rate_limit(RateLimit::new(1, Duration::from_secs(60))))
+//!         .with_tenant("globex", Tenant::new(ToolHandler::empty()));
+//!
+//!     let call = json!({ "type": "function", "function": { "name": "ping", "arguments": {} } });
+//!     let ctx = ToolContext::new().with_extension(TenantId::new("acme"));
+//!     assert_eq!(tenants.call_tool_with_context(&call, &ctx).await.unwrap().output, "pong");
+//!     assert_eq!(acme_log.len(), 1);
+//!
+//!     // Acme has used its one call this minute, and Globex has no tools
+//!     assert!(matches!(
+//!         tenants.call_tool("acme", &call).await,
+//!         Err(TenantError::RateLimited { .. })
+//!     ));
+//!     assert!(tenants.call_tool("globex", &call).await.is_err());
+//! }
+//! ```
+
+use crate::{ToolCallResult, ToolContext, ToolError, ToolHandler};
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Errors raised while routing a call to a tenant.
+#[derive(Debug, Clone, thiserror::Error, PartialEq)]
+pub enum TenantError {
+    #[error("no tenant could be resolved for the call")]
+    Unresolved,
+    #[error("unknown tenant '{0}'")]
+    UnknownTenant(String),
+    #[error("tenant '{tenant}' exceeded its rate limit; retry in {retry_after:?}")]
+    RateLimited {
+        tenant: String,
+        retry_after: Duration,
+    },
+    #[error(transparent)]
+    Tool(#[from] ToolError),
+}
+
+/// The tenant of a call, read from the [`ToolContext`] by the default resolver.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TenantId(pub String);
+
+impl TenantId {
+    /// Wraps a tenant id.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+}
+
+/// A hook naming the tenant of a call from its context, as set with
+/// [`TenantedHandler::with_tenant_resolver`].
+pub type TenantResolver = Arc<dyn Fn(&ToolContext) -> Option<String> + Send + Sync>;
+
+/// At most `calls` tool calls in any window of `per`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The number of calls allowed per window.
+    pub calls: usize,
+    /// The length of the sliding window.
+    pub per: Duration,
+}
+
+impl RateLimit {
+    /// Allows `calls` calls in any window of `per`.
+    pub fn new(calls: usize, per: Duration) -> Self {
+        Self { calls, per }
+    }
+}
+
+/// One tenant's tools and limits.
+#[derive(Clone)]
+pub struct Tenant {
+    handler: ToolHandler,
+    rate_limit: Option<RateLimit>,
+    // Start times of the calls within the current window, shared by clones
+    recent: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+impl Tenant {
+    /// A tenant served by `handler`, with no rate limit.
+    pub fn new(handler: ToolHandler) -> Self {
+        Self {
+            handler,
+            rate_limit: None,
+            recent: Arc::default(),
+        }
+    }
+
+    /// Limits how often the tenant may call tools.
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// The handler serving the tenant.
+    pub fn handler(&self) -> &ToolHandler {
+        &self.handler
+    }
+
+    // Record a call, or return how long until one is allowed
+    fn admit(&self) -> Result<(), Duration> {
+        let Some(limit) = self.rate_limit else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        let mut recent = self.recent.lock().unwrap();
+        while recent
+            .front()
+            .is_some_and(|started| now.duration_since(*started) >= limit.per)
+        {
+            recent.pop_front();
+        }
+        if recent.len() >= limit.calls {
+            let retry_after = recent
+                .front()
+                .map(|oldest| limit.per.saturating_sub(now.duration_since(*oldest)))
+                .unwrap_or(limit.per);
+            return Err(retry_after);
+        }
+        recent.push_back(now);
+        Ok(())
+    }
+}
+
+/// Routes tool calls to per-tenant handlers.
+///
+/// See the [`tenant`](crate::tenant) module.
+#[derive(Clone)]
+pub struct TenantedHandler {
+    tenants: BTreeMap<String, Tenant>,
+    resolver: TenantResolver,
+}
+
+impl Default for TenantedHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TenantedHandler {
+    /// Creates a handler with no tenants, resolving tenants from a [`TenantId`] extension.
+    pub fn new() -> Self {
+        Self {
+            tenants: BTreeMap::new(),
+            resolver: Arc::new(|context: &ToolContext| {
+                context.get::<TenantId>().map(|tenant| tenant.0.clone())
+            }),
+        }
+    }
+
+    /// Adds a tenant, replacing any tenant with the same id.
+    pub fn with_tenant(mut self, id: impl Into<String>, tenant: Tenant) -> Self {
+        self.insert_tenant(id, tenant);
+        self
+    }
+
+    /// Adds a tenant at runtime, replacing and returning any tenant with the same id.
+    pub fn insert_tenant(&mut self, id: impl Into<String>, tenant: Tenant) -> Option<Tenant> {
+        self.tenants.insert(id.into(), tenant)
+    }
+
+    /// Removes and returns a tenant.
+    pub fn remove_tenant(&mut self, id: &str) -> Option<Tenant> {
+        self.tenants.remove(id)
+    }
+
+    /// Looks up a tenant.
+    pub fn tenant(&self, id: &str) -> Option<&Tenant> {
+        self.tenants.get(id)
+    }
+
+    /// The ids of every tenant, in order.
+    pub fn tenant_ids(&self) -> impl Iterator<Item = &str> + '_ {
+        self.tenants.keys().map(String::as_str)
+    }
+
+    /// Names the tenant of each call made through
+    /// [`TenantedHandler::call_tool_with_context`], for example from its user id.
+    pub fn with_tenant_resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&ToolContext) -> Option<String> + Send + Sync + 'static,
+    {
+        self.resolver = Arc::new(resolver);
+        self
+    }
+
+    /// The schema of every tool of a tenant, as [`ToolHandler::all_tools_schema`] returns it.
+    pub fn tools_schema(&self, tenant: &str) -> Result<Value, TenantError> {
+        Ok(self.resolve(tenant)?.handler.all_tools_schema())
+    }
+
+    /// Calls a tool of `tenant`, as [`ToolHandler::call_tool`] does, within its rate limit.
+    pub async fn call_tool(
+        &self,
+        tenant: &str,
+        input: &Value,
+    ) -> Result<ToolCallResult, TenantError> {
+        self.call_tenant(tenant, input, &ToolContext::default())
+            .await
+    }
+
+    /// Calls a tool of the tenant named by the resolver for `context`, as
+    /// [`ToolHandler::call_tool_with_context`] does, within the tenant's rate limit.
+    pub async fn call_tool_with_context(
+        &self,
+        input: &Value,
+        context: &ToolContext,
+    ) -> Result<ToolCallResult, TenantError> {
+        let tenant = (self.resolver)(context).ok_or(TenantError::Unresolved)?;
+        self.call_tenant(&tenant, input, context).await
+    }
+
+    async fn call_tenant(
+        &self,
+        id: &str,
+        input: &Value,
+        context: &ToolContext,
+    ) -> Result<ToolCallResult, TenantError> {
+        let tenant = self.resolve(id)?;
+        tenant
+            .admit()
+            .map_err(|retry_after| TenantError::RateLimited {
+                tenant: id.to_string(),
+                retry_after,
+            })?;
+        Ok(tenant
+            .handler
+            .call_tool_with_context(input, context)
+            .await?)
+    }
+
+    fn resolve(&self, id: &str) -> Result<&Tenant, TenantError> {
+        self.tenants
+            .get(id)
+            .ok_or_else(|| TenantError::UnknownTenant(id.to_string()))
+    }
+}
@@ -0,0 +1,519 @@
+//! Test doubles for code that calls tools.
+//!
+//! A [`MockToolHandler`] wraps a [`ToolHandler`] whose tools never run: every call is still
+//! resolved and validated against the tool's schema, then recorded with its arguments and
+//! answered with the responses programmed for that tool. Hand [`MockToolHandler::handler`]
+//! to the code under test, such as a [`ChatLoop`](crate::chat::ChatLoop), then check what it
+//! called with the assertion helpers.
+//!
+//! A [`Cassette`] holds tool calls recorded from real executions with a
+//! [`CassetteRecorder`]. Saved to a file, it can later be replayed in place of the tools, so
+//! conversation-level tests run the same way every time, offline.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tool_calling::testing::MockToolHandler;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let mock = MockToolHandler::new()
+//!         .with_tool(
+//!             "get_weather",
+//!             "Get the current weather for a city",
+//!             json!({ "type": "object", "properties": { "city": { "type": "string" } }, "required": ["city"] }),
+//!         )
+//!         .with_response("get_weather", "sunny");
+//!
+//!     let call = json!({
+//!         "type": "function",
+//!         "function": { "name": "get_weather", "arguments": { "city": "Oslo" } }
+//!     });
+//!     let result = mock.handler().call_tool(&call).await.unwrap();
+//!     assert_eq!(result.output, "sunny");
+//!     mock.assert_called_with("get_weather", json!({ "city": "Oslo" }));
+//! }
+//! ```
+
+use crate::audit::{AuditRecord, AuditSink, Tee};
+use crate::{wrap_sync, Tool, ToolError, ToolFn, ToolHandler, REDACTED};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// A call made to a [`MockToolHandler`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCall {
+    /// The name of the tool called.
+    pub name: String,
+    /// The arguments of the call, by parameter name. Omitted optional parameters are left
+    /// out.
+    pub arguments: Map<String, Value>,
+}
+
+// A function computing a tool's response from the call's arguments
+type Responder = Arc<dyn Fn(&Map<String, Value>) -> Result<String, ToolError> + Send + Sync>;
+
+#[derive(Clone)]
+enum Response {
+    Fixed(Result<String, ToolError>),
+    Computed(Responder),
+}
+
+#[derive(Default)]
+struct State {
+    calls: Vec<RecordedCall>,
+    responses: BTreeMap<String, VecDeque<Response>>,
+}
+
+/// A [`ToolHandler`] double recording every call and answering with canned responses.
+///
+/// Each tool answers with the responses programmed for it in order, repeating the last one
+/// once the others are used up. A call to a tool with no responses fails with
+/// `ToolError::Execution`.
+#[derive(Clone)]
+pub struct MockToolHandler {
+    handler: ToolHandler,
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for MockToolHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockToolHandler {
+    /// Creates a mock with no tools; add them with [`MockToolHandler::with_tool`].
+    pub fn new() -> Self {
+        Self::from_handler(ToolHandler::empty())
+    }
+
+    /// Creates a mock offering the tools of `handler`, such as [`ToolHandler::default`] for
+    /// every `#[tool]` in the binary. The tools keep their schemas and the handler its
+    /// settings, but none of them runs.
+    pub fn from_handler(handler: ToolHandler) -> Self {
+        let mut mock = Self {
+            handler,
+            state: Arc::default(),
+        };
+        let names = mock
+            .handler
+            .tools()
+            .map(|tool| tool.name.clone())
+            .collect::<Vec<_>>();
+        for name in names {
+            mock.intercept(name);
+        }
+        mock
+    }
+
+    /// Adds a tool called `name` with this description and parameter schema.
+    pub fn with_tool(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameter_schema: Value,
+    ) -> Self {
+        let name = name.into();
+        let unreachable = Arc::new(|_: &[Value]| -> Result<String, ToolError> {
+            Err(ToolError::Execution("mock tools don't run".to_string()))
+        });
+        self.handler.register(Tool::new(
+            name.clone(),
+            description,
+            parameter_schema,
+            ToolFn::Async(wrap_sync(unreachable)),
+        ));
+        self.intercept(name);
+        self
+    }
+
+    /// Answers a call to the tool `name` with `output`.
+    ///
+    /// # Panics
+    ///
+    /// If the mock has no tool called `name`.
+    pub fn with_response(self, name: &str, output: impl Into<String>) -> Self {
+        self.push(name, Response::Fixed(Ok(output.into())))
+    }
+
+    /// Fails a call to the tool `name` with `err`.
+    ///
+    /// # Panics
+    ///
+    /// If the mock has no tool called `name`.
+    pub fn with_error(self, name: &str, err: ToolError) -> Self {
+        self.push(name, Response::Fixed(Err(err)))
+    }
+
+    /// Answers a call to the tool `name` with what `responder` returns for its arguments.
+    ///
+    /// # Panics
+    ///
+    /// If the mock has no tool called `name`.
+    pub fn with_responder<F>(self, name: &str, responder: F) -> Self
+    where
+        F: Fn(&Map<String, Value>) -> Result<String, ToolError> + Send + Sync + 'static,
+    {
+        self.push(name, Response::Computed(Arc::new(responder)))
+    }
+
+    /// The handler to give to the code under test.
+    pub fn handler(&self) -> &ToolHandler {
+        &self.handler
+    }
+
+    /// Every call made so far, oldest first.
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// The arguments of every call to the tool `name` so far, oldest first.
+    pub fn calls_to(&self, name: &str) -> Vec<Map<String, Value>> {
+        self.state
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .filter(|call| call.name == name)
+            .map(|call| call.arguments.clone())
+            .collect()
+    }
+
+    /// The number of calls to the tool `name` so far.
+    pub fn call_count(&self, name: &str) -> usize {
+        self.calls_to(name).len()
+    }
+
+    /// Forgets the calls made so far, keeping the programmed responses.
+    pub fn clear_calls(&self) {
+        self.state.lock().unwrap().calls.clear();
+    }
+
+    /// Panics unless the tool `name` was called.
+    #[track_caller]
+    pub fn assert_called(&self, name: &str) {
+        assert!(
+            self.call_count(name) > 0,
+            "expected a call to '{}', got calls to {:?}",
+            name,
+            self.call_names()
+        );
+    }
+
+    /// Panics if the tool `name` was called.
+    #[track_caller]
+    pub fn assert_not_called(&self, name: &str) {
+        let calls = self.calls_to(name);
+        assert!(
+            calls.is_empty(),
+            "expected no call to '{}', got {} with arguments {:?}",
+            name,
+            calls.len(),
+            calls
+        );
+    }
+
+    /// Panics unless the tool `name` was called exactly `times` times.
+    #[track_caller]
+    pub fn assert_called_times(&self, name: &str, times: usize) {
+        let count = self.call_count(name);
+        assert_eq!(
+            count, times,
+            "expected {} call(s) to '{}', got {}",
+            times, name, count
+        );
+    }
+
+    /// Panics unless some call to the tool `name` had exactly these `arguments`, given as a
+    /// JSON object.
+    #[track_caller]
+    pub fn assert_called_with(&self, name: &str, arguments: Value) {
+        let calls = self.calls_to(name);
+        assert!(
+            calls.iter().any(|call| arguments.as_object() == Some(call)),
+            "expected a call to '{}' with {}, got calls with {:?}",
+            name,
+            arguments,
+            calls
+        );
+    }
+
+    /// Panics unless the tools called so far were exactly `names`, in this order.
+    #[track_caller]
+    pub fn assert_call_order(&self, names: &[&str]) {
+        let called = self.call_names();
+        assert_eq!(
+            called, names,
+            "expected calls to {:?} in order, got {:?}",
+            names, called
+        );
+    }
+
+    fn call_names(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .map(|call| call.name.clone())
+            .collect()
+    }
+
+    #[track_caller]
+    fn push(self, name: &str, response: Response) -> Self {
+        assert!(
+            self.handler.get_tool(name).is_some(),
+            "the mock has no tool called '{}'",
+            name
+        );
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .entry(name.to_string())
+            .or_default()
+            .push_back(response);
+        self
+    }
+
+    // Stub the tool `name` to record its calls and answer them from the state
+    fn intercept(&mut self, name: String) {
+        let parameters = self
+            .handler
+            .get_tool(&name)
+            .map(parameter_names)
+            .unwrap_or_default();
+        let state = Arc::clone(&self.state);
+        self.handler.stub(name.clone(), move |args: &[Value]| {
+            let arguments = named_arguments(&parameters, args);
+            let response = {
+                let mut state = state.lock().unwrap();
+                state.calls.push(RecordedCall {
+                    name: name.clone(),
+                    arguments: arguments.clone(),
+                });
+                state.responses.get_mut(&name).and_then(|responses| {
+                    if responses.len() > 1 {
+                        responses.pop_front()
+                    } else {
+                        responses.front().cloned()
+                    }
+                })
+            };
+            match response {
+                Some(Response::Fixed(result)) => result,
+                Some(Response::Computed(responder)) => responder(&arguments),
+                None => Err(ToolError::Execution(format!(
+                    "no response programmed for mock tool '{}'",
+                    name
+                ))),
+            }
+        });
+    }
+}
+
+/// One tool call recorded on a [`Cassette`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CassetteEntry {
+    /// The name of the tool called.
+    pub name: String,
+    /// The arguments of the call, by parameter name, with secret values redacted. Omitted
+    /// optional parameters are left out.
+    pub arguments: Map<String, Value>,
+    /// The tool's output or error.
+    pub result: Result<String, ToolError>,
+}
+
+/// Tool calls recorded from real executions, to be replayed without running the tools.
+///
+/// Record a cassette by running the code under test against a [`CassetteRecorder`] and
+/// saving it, then replay it with [`Cassette::replay`] to make later runs deterministic and
+/// offline:
+///
+/// ```rust,no_run
+/// use tool_calling::testing::{Cassette, CassetteRecorder};
+/// use tool_calling::ToolHandler;
+///
+/// # async fn conversation(handler: &ToolHandler) {}
+/// #[tokio::main]
+/// async fn main() -> std::io::Result<()> {
+///     let path = "tests/cassettes/weather.json";
+///     let handler = match Cassette::load(path) {
+///         Ok(cassette) => cassette.replay(ToolHandler::default()),
+///         Err(_) => {
+///             let recorder = CassetteRecorder::new(ToolHandler::default());
+///             conversation(recorder.handler()).await;
+///             recorder.save(path)?;
+///             return Ok(());
+///         }
+///     };
+///     conversation(&handler).await;
+///     Ok(())
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Cassette {
+    /// The recorded calls, oldest first.
+    pub calls: Vec<CassetteEntry>,
+}
+
+impl Cassette {
+    /// Creates a cassette with no calls.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a cassette written by [`Cassette::save`].
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = std::fs::read(path)?;
+        serde_json::from_slice(&file).map_err(std::io::Error::other)
+    }
+
+    /// Writes the cassette to `path` as JSON, creating its directory if needed.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self).expect("cassettes serialize");
+        std::fs::write(path, json)
+    }
+
+    /// Stubs every tool of `handler` to answer from the cassette instead of running.
+    ///
+    /// A call is answered with the recorded result of a call to the same tool with the same
+    /// arguments. Calls recorded more than once are answered with their results in turn,
+    /// repeating the last one once the others are used up. A call that was never recorded
+    /// fails with `ToolError::Execution`.
+    pub fn replay(&self, mut handler: ToolHandler) -> ToolHandler {
+        // The results of each distinct call, in the order they were recorded
+        let mut tapes: Vec<(String, Map<String, Value>, VecDeque<_>)> = Vec::new();
+        for entry in &self.calls {
+            match tapes
+                .iter_mut()
+                .find(|(name, arguments, _)| *name == entry.name && *arguments == entry.arguments)
+            {
+                Some((_, _, results)) => results.push_back(entry.result.clone()),
+                None => tapes.push((
+                    entry.name.clone(),
+                    entry.arguments.clone(),
+                    VecDeque::from([entry.result.clone()]),
+                )),
+            }
+        }
+        let tapes = Arc::new(Mutex::new(tapes));
+        let tools = handler
+            .tools()
+            .map(|tool| {
+                (
+                    tool.name.clone(),
+                    parameter_names(tool),
+                    tool.secret_params.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        for (name, parameters, secret_params) in tools {
+            let tapes = Arc::clone(&tapes);
+            handler.stub(name.clone(), move |args: &[Value]| {
+                let mut arguments = named_arguments(&parameters, args);
+                for secret in &secret_params {
+                    if let Some(value) = arguments.get_mut(secret) {
+                        *value = Value::String(REDACTED.into());
+                    }
+                }
+                let mut tapes = tapes.lock().unwrap();
+                let (_, _, results) = tapes
+                    .iter_mut()
+                    .find(|(recorded, recorded_arguments, _)| {
+                        *recorded == name && *recorded_arguments == arguments
+                    })
+                    .ok_or_else(|| {
+                        ToolError::Execution(format!(
+                            "no recorded call to '{}' with arguments {}",
+                            name,
+                            Value::Object(arguments.clone())
+                        ))
+                    })?;
+                match results.len() {
+                    1 => results[0].clone(),
+                    _ => results.pop_front().expect("recorded calls are never empty"),
+                }
+            });
+        }
+        handler
+    }
+}
+
+/// Records the calls a [`ToolHandler`] executes onto a [`Cassette`].
+#[derive(Clone)]
+pub struct CassetteRecorder {
+    handler: ToolHandler,
+    calls: Arc<Mutex<Vec<CassetteEntry>>>,
+}
+
+impl CassetteRecorder {
+    /// Records every call `handler` executes. The tools run as usual, and any audit sink of
+    /// the handler keeps receiving its records.
+    pub fn new(mut handler: ToolHandler) -> Self {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let tape: Arc<dyn AuditSink> = Arc::new(Tape(Arc::clone(&calls)));
+        handler.audit = Some(match handler.audit.take() {
+            Some(audit) => Arc::new(Tee(audit, tape)),
+            None => tape,
+        });
+        Self { handler, calls }
+    }
+
+    /// The handler to give to the code under test.
+    pub fn handler(&self) -> &ToolHandler {
+        &self.handler
+    }
+
+    /// The calls recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        Cassette {
+            calls: self.calls.lock().unwrap().clone(),
+        }
+    }
+
+    /// Writes the calls recorded so far to `path`; see [`Cassette::save`].
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        self.cassette().save(path)
+    }
+}
+
+// An audit sink keeping calls for a cassette
+struct Tape(Arc<Mutex<Vec<CassetteEntry>>>);
+
+impl AuditSink for Tape {
+    fn record(&self, record: &AuditRecord) {
+        let mut arguments = record.arguments.clone();
+        arguments.retain(|_, value| !value.is_null());
+        self.0.lock().unwrap().push(CassetteEntry {
+            name: record.name.clone(),
+            arguments,
+            result: record.result.clone(),
+        });
+    }
+}
+
+// The names of a tool's parameters, in the order of its positional arguments
+fn parameter_names(tool: &Tool) -> Vec<String> {
+    tool.parameter_schema["properties"]
+        .as_object()
+        .map(|properties| properties.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+// Key positional arguments by parameter name, leaving out omitted ones
+fn named_arguments(parameters: &[String], args: &[Value]) -> Map<String, Value> {
+    parameters
+        .iter()
+        .zip(args)
+        .filter(|(_, value)| !value.is_null())
+        .map(|(parameter, value)| (parameter.clone(), value.clone()))
+        .collect()
+}
@@ -0,0 +1,161 @@
+//! Estimating how many prompt tokens tool schemas take.
+//!
+//! Tool definitions are sent with every request, so they compete with the conversation for
+//! the context window. [`ToolHandler::estimate_schema_tokens`] estimates their size for a
+//! [`ModelFamily`], using the handler's [`Tokenizer`]. The default [`HeuristicTokenizer`]
+//! divides character counts by a per-family ratio; plug in an exact tokenizer by
+//! implementing [`Tokenizer`].
+//!
+//! ```rust
+//! use tool_calling::tokens::ModelFamily;
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: i32) -> String {
+//!     (a + b).to_string()
+//! }
+//!
+//! let handler = ToolHandler::default().with_schema_token_limit(2_000);
+//! let tokens = handler.estimate_schema_tokens(ModelFamily::Gpt);
+//! assert!(tokens > 0 && tokens < 2_000);
+//! assert_eq!(handler.tools_within_budget(ModelFamily::Gpt, tokens).len(), 1);
+//! ```
+
+use crate::{SchemaFlavor, Tool, ToolHandler};
+use std::sync::Arc;
+
+/// A family of models sharing a tokenizer and tool schema shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFamily {
+    /// OpenAI GPT models.
+    Gpt,
+    /// Anthropic Claude models.
+    Claude,
+    /// Google Gemini models.
+    Gemini,
+    /// Llama and other open models served through Ollama.
+    Llama,
+}
+
+impl ModelFamily {
+    /// The shape this family's API expects tool definitions in.
+    pub fn flavor(self) -> SchemaFlavor {
+        match self {
+            ModelFamily::Gpt => SchemaFlavor::OpenAi,
+            ModelFamily::Claude => SchemaFlavor::Anthropic,
+            ModelFamily::Gemini => SchemaFlavor::Gemini,
+            ModelFamily::Llama => SchemaFlavor::Ollama,
+        }
+    }
+
+    // Rough characters per token for JSON text
+    fn chars_per_token(self) -> f64 {
+        match self {
+            ModelFamily::Gpt => 3.5,
+            ModelFamily::Claude => 3.2,
+            ModelFamily::Gemini => 3.8,
+            ModelFamily::Llama => 3.3,
+        }
+    }
+}
+
+/// Counts the tokens a text takes for a model family.
+pub trait Tokenizer: Send + Sync {
+    /// Returns the number of tokens in `text`.
+    fn count_tokens(&self, text: &str, family: ModelFamily) -> usize;
+}
+
+/// Estimates tokens from the character count, with a rough ratio per model family.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenizer;
+
+impl Tokenizer for HeuristicTokenizer {
+    fn count_tokens(&self, text: &str, family: ModelFamily) -> usize {
+        (text.chars().count() as f64 / family.chars_per_token()).ceil() as usize
+    }
+}
+
+// Token estimation settings, shared by a handler's clones
+#[derive(Clone)]
+pub(crate) struct TokenBudget {
+    tokenizer: Arc<dyn Tokenizer>,
+    limit: Option<usize>,
+}
+
+impl Default for TokenBudget {
+    fn default() -> Self {
+        Self {
+            tokenizer: Arc::new(HeuristicTokenizer),
+            limit: None,
+        }
+    }
+}
+
+impl ToolHandler {
+    /// Uses `tokenizer` for token estimates instead of [`HeuristicTokenizer`].
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.token_budget.tokenizer = tokenizer;
+        self
+    }
+
+    /// Logs a warning whenever [`ToolHandler::estimate_schema_tokens`] exceeds `limit`.
+    pub fn with_schema_token_limit(mut self, limit: usize) -> Self {
+        self.token_budget.limit = Some(limit);
+        self
+    }
+
+    /// Estimates the tokens taken by the schema of every tool, rendered for `family`.
+    pub fn estimate_schema_tokens(&self, family: ModelFamily) -> usize {
+        let schema = self
+            .tools()
+            .map(|tool| tool.render_schema(family.flavor()))
+            .collect::<Vec<_>>();
+        let tokens = self
+            .token_budget
+            .tokenizer
+            .count_tokens(&serde_json::Value::Array(schema).to_string(), family);
+        if let Some(limit) = self.token_budget.limit.filter(|limit| tokens > *limit) {
+            tracing::warn!(
+                tokens,
+                limit,
+                ?family,
+                "tool schemas exceed the token limit"
+            );
+        }
+        tokens
+    }
+
+    /// Estimates the tokens taken by each tool's schema, rendered for `family`, in
+    /// registry order.
+    pub fn estimate_tool_tokens(&self, family: ModelFamily) -> Vec<(&Tool, usize)> {
+        self.tools()
+            .map(|tool| {
+                let schema = tool.render_schema(family.flavor()).to_string();
+                (
+                    tool,
+                    self.token_budget.tokenizer.count_tokens(&schema, family),
+                )
+            })
+            .collect()
+    }
+
+    /// The tools, in registry order, whose schemas fit together within `budget` tokens.
+    ///
+    /// Tools that would overflow the budget are skipped, so smaller tools after them can
+    /// still fit.
+    pub fn tools_within_budget(&self, family: ModelFamily, budget: usize) -> Vec<&Tool> {
+        let mut remaining = budget;
+        self.estimate_tool_tokens(family)
+            .into_iter()
+            .filter(|(_, tokens)| {
+                let fits = *tokens <= remaining;
+                if fits {
+                    remaining -= tokens;
+                }
+                fits
+            })
+            .map(|(tool, _)| tool)
+            .collect()
+    }
+}
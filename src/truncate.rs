@@ -0,0 +1,118 @@
+/// Which part of an oversized output is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruncationStrategy {
+    /// Keep the beginning and drop the end.
+    #[default]
+    Head,
+    /// Keep the end and drop the beginning.
+    Tail,
+    /// Keep the beginning and the end, dropping the middle.
+    Middle,
+}
+
+/// The maximum size of a tool's output, and how to cut outputs that exceed it.
+///
+/// Truncated outputs carry a marker saying how many bytes were dropped, and stay within
+/// `max_bytes` including the marker.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use tool_calling::{tool, OutputLimit, ToolHandler, TruncationStrategy};
+///
+/// #[tool]
+/// fn list_files() -> String {
+///     (0..10_000).map(|i| format!("file_{}.txt\n", i)).collect()
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let limit = OutputLimit::new(1_000).with_strategy(TruncationStrategy::Middle);
+///     let handler = ToolHandler::default().with_output_limit(limit);
+///     let call = json!({ "type": "function", "function": { "name": "list_files", "arguments": {} } });
+///     let res = handler.call_tool(&call).await.unwrap();
+///     assert!(res.truncated);
+///     assert!(res.output.len() <= 1_000);
+///     assert!(res.output.starts_with("file_0.txt") && res.output.ends_with("file_9999.txt\n"));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputLimit {
+    /// The maximum output size, in bytes.
+    pub max_bytes: usize,
+    /// Which part of an oversized output is kept.
+    pub strategy: TruncationStrategy,
+}
+
+impl OutputLimit {
+    /// Limits outputs to `max_bytes`, keeping their beginning.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            strategy: TruncationStrategy::default(),
+        }
+    }
+
+    /// Sets which part of an oversized output is kept.
+    pub fn with_strategy(mut self, strategy: TruncationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Cuts `output` down to the limit, returning whether anything was dropped.
+    pub fn apply(&self, output: String) -> (String, bool) {
+        if output.len() <= self.max_bytes {
+            return (output, false);
+        }
+        // The marker's own length depends on the count, so size it for the worst case
+        let marker_len = marker(output.len()).len();
+        let Some(budget) = self.max_bytes.checked_sub(marker_len) else {
+            return (
+                output[..floor_boundary(&output, self.max_bytes)].to_string(),
+                true,
+            );
+        };
+
+        let truncated = match self.strategy {
+            TruncationStrategy::Head => {
+                let end = floor_boundary(&output, budget);
+                format!("{}{}", &output[..end], marker(output.len() - end))
+            }
+            TruncationStrategy::Tail => {
+                let start = ceil_boundary(&output, output.len() - budget);
+                format!("{}{}", marker(start), &output[start..])
+            }
+            TruncationStrategy::Middle => {
+                let end = floor_boundary(&output, budget.div_ceil(2));
+                let start = ceil_boundary(&output, output.len() - (budget - end));
+                format!(
+                    "{}{}{}",
+                    &output[..end],
+                    marker(start - end),
+                    &output[start..]
+                )
+            }
+        };
+        (truncated, true)
+    }
+}
+
+fn marker(dropped: usize) -> String {
+    format!("\n[... {} bytes truncated ...]\n", dropped)
+}
+
+// The largest char boundary at or before `index`
+fn floor_boundary(text: &str, index: usize) -> usize {
+    (0..=index.min(text.len()))
+        .rev()
+        .find(|i| text.is_char_boundary(*i))
+        .unwrap_or(0)
+}
+
+// The smallest char boundary at or after `index`
+fn ceil_boundary(text: &str, index: usize) -> usize {
+    (index..=text.len())
+        .find(|i| text.is_char_boundary(*i))
+        .unwrap_or(text.len())
+}
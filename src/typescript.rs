@@ -0,0 +1,241 @@
+//! TypeScript type definitions for the registered tools.
+//!
+//! [`ToolHandler::typescript_definitions`] derives a `.d.ts` module from the tools'
+//! parameter schemas: an `{Name}Args` interface per tool, a `ToolName` union of every tool
+//! name, a `ToolArgs` map from names to argument types, and a `ToolCall` union of
+//! `{ name, arguments }` pairs for typing tool-call events. Schema constructs without a
+//! TypeScript equivalent become `unknown`.
+//!
+//! ```rust
+//! use tool_calling::{tool, ToolHandler};
+//!
+//! #[tool]
+//! /// Adds two integers
+//! fn add(a: i32, b: Option<i32>) -> String {
+//!     (a + b.unwrap_or(0)).to_string()
+//! }
+//!
+//! let ts = ToolHandler::default().typescript_definitions();
+//! assert!(ts.contains("export interface AddArgs {\n  a: number;\n  b?: number | null;\n}"));
+//! assert!(ts.contains("export type ToolName = \"add\";"));
+//! ```
+
+use crate::ToolHandler;
+use serde_json::Value;
+use std::path::Path;
+
+impl ToolHandler {
+    /// TypeScript declarations for every tool's arguments, as the contents of a `.d.ts` file.
+    pub fn typescript_definitions(&self) -> String {
+        let mut out = String::from("// Generated by tool_calling. Do not edit.\n");
+        let mut names = Vec::new();
+        for tool in self.tools() {
+            let interface = interface_name(&tool.name);
+            out.push('\n');
+            out.push_str(&doc_comment(&tool.description, ""));
+            out.push_str(&format!("export interface {} ", interface));
+            out.push_str(&object_body(&tool.parameter_schema, ""));
+            out.push('\n');
+            names.push((tool.name.as_str(), interface));
+        }
+
+        let union = match names.is_empty() {
+            true => "never".to_string(),
+            false => names
+                .iter()
+                .map(|(name, _)| string_literal(name))
+                .collect::<Vec<_>>()
+                .join(" | "),
+        };
+        out.push_str(&format!("\nexport type ToolName = {};\n", union));
+        out.push_str("\nexport interface ToolArgs {");
+        if !names.is_empty() {
+            out.push('\n');
+        }
+        for (name, interface) in &names {
+            out.push_str(&format!("  {}: {};\n", property_key(name), interface));
+        }
+        out.push_str("}\n");
+        out.push_str(
+            "\nexport type ToolCall = {\n  [K in ToolName]: { name: K; arguments: ToolArgs[K] };\n}[ToolName];\n",
+        );
+        out
+    }
+
+    /// Writes [`ToolHandler::typescript_definitions`] to `path`, typically a `.d.ts` file.
+    pub fn write_typescript_definitions(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.typescript_definitions())
+    }
+}
+
+// `get_weather` and `get-weather` become `GetWeatherArgs`
+fn interface_name(tool: &str) -> String {
+    let mut name = String::new();
+    for part in tool.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            name.push(first.to_ascii_uppercase());
+            name.extend(chars);
+        }
+    }
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name.push_str("Args");
+    name
+}
+
+fn doc_comment(text: &str, indent: &str) -> String {
+    let text = text.trim();
+    if text.is_empty() {
+        return String::new();
+    }
+    let text = text.replace("*/", "*\\/");
+    let lines = text.lines().collect::<Vec<_>>();
+    if lines.len() == 1 {
+        return format!("{}/** {} */\n", indent, lines[0]);
+    }
+    let mut out = format!("{}/**\n", indent);
+    for line in lines {
+        out.push_str(&format!("{} * {}\n", indent, line).replace(" * \n", " *\n"));
+    }
+    out.push_str(&format!("{} */\n", indent));
+    out
+}
+
+fn string_literal(text: &str) -> String {
+    Value::String(text.to_string()).to_string()
+}
+
+fn property_key(name: &str) -> String {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if valid {
+        name.to_string()
+    } else {
+        string_literal(name)
+    }
+}
+
+// `{ ... }` listing an object schema's properties, one per line at `indent` + 2 spaces
+fn object_body(schema: &Value, indent: &str) -> String {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let Some(properties) = properties.filter(|props| !props.is_empty()) else {
+        return "{}".to_string();
+    };
+    let required = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let inner = format!("{}  ", indent);
+    let mut out = String::from("{\n");
+    for (name, property) in properties {
+        if let Some(description) = property.get("description").and_then(Value::as_str) {
+            out.push_str(&doc_comment(description, &inner));
+        }
+        let optional = if required.iter().any(|r| r == name) {
+            ""
+        } else {
+            "?"
+        };
+        out.push_str(&format!(
+            "{}{}{}: {};\n",
+            inner,
+            property_key(name),
+            optional,
+            ts_type(property, &inner)
+        ));
+    }
+    out.push_str(indent);
+    out.push('}');
+    out
+}
+
+// The TypeScript type accepted by a JSON Schema
+fn ts_type(schema: &Value, indent: &str) -> String {
+    let Some(obj) = schema.as_object() else {
+        // `true` accepts anything, `false` nothing
+        return match schema {
+            Value::Bool(false) => "never".to_string(),
+            _ => "unknown".to_string(),
+        };
+    };
+    if let Some(value) = obj.get("const") {
+        return value.to_string();
+    }
+    if let Some(values) = obj.get("enum").and_then(Value::as_array) {
+        return union(values.iter().map(Value::to_string).collect());
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(members) = obj.get(key).and_then(Value::as_array) {
+            return union(members.iter().map(|m| ts_type(m, indent)).collect());
+        }
+    }
+    if let Some(members) = obj.get("allOf").and_then(Value::as_array) {
+        let parts = members
+            .iter()
+            .map(|m| parenthesize(ts_type(m, indent)))
+            .collect::<Vec<_>>();
+        return parts.join(" & ");
+    }
+    match obj.get("type") {
+        Some(Value::String(ty)) => single_type(ty, schema, indent),
+        Some(Value::Array(types)) => union(
+            types
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|ty| single_type(ty, schema, indent))
+                .collect(),
+        ),
+        _ if obj.contains_key("properties") => single_type("object", schema, indent),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn single_type(ty: &str, schema: &Value, indent: &str) -> String {
+    match ty {
+        "string" => "string".to_string(),
+        "integer" | "number" => "number".to_string(),
+        "boolean" => "boolean".to_string(),
+        "null" => "null".to_string(),
+        "array" => match schema.get("items") {
+            Some(items) => format!("{}[]", parenthesize(ts_type(items, indent))),
+            None => "unknown[]".to_string(),
+        },
+        "object" => {
+            if schema.get("properties").is_some() {
+                return object_body(schema, indent);
+            }
+            match schema.get("additionalProperties") {
+                Some(values @ Value::Object(_)) => {
+                    format!("Record<string, {}>", ts_type(values, indent))
+                }
+                _ => "Record<string, unknown>".to_string(),
+            }
+        }
+        _ => "unknown".to_string(),
+    }
+}
+
+fn union(mut members: Vec<String>) -> String {
+    members.dedup();
+    match members.len() {
+        0 => "never".to_string(),
+        _ => members.join(" | "),
+    }
+}
+
+// Wrap unions and intersections so they bind correctly inside `T[]` and `A & B`
+fn parenthesize(ty: String) -> String {
+    if ty.contains(" | ") || ty.contains(" & ") {
+        format!("({})", ty)
+    } else {
+        ty
+    }
+}
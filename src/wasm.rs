@@ -0,0 +1,306 @@
+//! Running tools compiled to WebAssembly.
+//!
+//! A [`WasmRuntime`] compiles modules and turns their exported functions into [`Tool`]s.
+//! Every call runs in a fresh instance with no filesystem, network, environment or
+//! stdio access, and is bounded by the runtime's [`WasmLimits`].
+//!
+//! Modules may be core WebAssembly or WASI preview 1 reactors, in binary or text form,
+//! and must export:
+//!
+//! - `memory`: the module's linear memory
+//! - `alloc(len: i32) -> i32`: returns a buffer of `len` bytes for the arguments
+//! - one function per tool, named after it, taking `(ptr: i32, len: i32) -> i64`
+//!
+//! The tool function receives the call's arguments as a UTF-8 JSON object and returns
+//! the location of its UTF-8 output, packed as `(ptr << 32) | len`. A trap fails the
+//! call with [`ToolError::Execution`](crate::ToolError::Execution).
+//!
+//! ```rust,ignore
+//! use serde_json::json;
+//! use tool_calling::wasm::WasmRuntime;
+//! use tool_calling::ToolHandler;
+//!
+//! let runtime = WasmRuntime::new().unwrap();
+//! let module = runtime.compile_file("tools.wasm").unwrap();
+//! let tool = module
+//!     .tool(
+//!         "word_count",
+//!         "Count the words in a text",
+//!         json!({
+//!             "type": "object",
+//!             "properties": { "text": { "type": "string" } },
+//!             "required": ["text"]
+//!         }),
+//!     )
+//!     .unwrap();
+//! let handler = ToolHandler::empty().with_tool(tool);
+//! ```
+
+use crate::{Tool, ToolError, ToolFn, ToolFuture};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use wasmtime::{
+    Config, Engine, ExternType, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap,
+    ValType,
+};
+use wasmtime_wasi::p1::WasiP1Ctx;
+use wasmtime_wasi::WasiCtxBuilder;
+
+/// Errors returned while compiling modules or creating tools from them.
+#[derive(Debug, thiserror::Error)]
+pub enum WasmError {
+    #[error("wasm io error for {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to set up the wasm runtime: {0}")]
+    Runtime(String),
+    #[error("invalid wasm module: {0}")]
+    Module(String),
+    #[error("wasm module has no usable export '{export}': {reason}")]
+    Export { export: String, reason: String },
+}
+
+/// Resource limits applied to every call of a wasm tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasmLimits {
+    /// Fuel available to a call; roughly one unit per executed instruction.
+    pub fuel: u64,
+    /// The largest linear memory a call may grow to, in bytes.
+    pub max_memory_bytes: usize,
+    /// The largest output a call may return, in bytes.
+    pub max_output_bytes: usize,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 1_000_000_000,
+            max_memory_bytes: 64 * 1024 * 1024,
+            max_output_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// Compiles wasm modules and runs their tools under a set of [`WasmLimits`].
+#[derive(Clone)]
+pub struct WasmRuntime {
+    engine: Engine,
+    linker: Arc<Linker<CallState>>,
+    limits: WasmLimits,
+}
+
+impl WasmRuntime {
+    /// Creates a runtime with the default limits.
+    pub fn new() -> Result<Self, WasmError> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine =
+            Engine::new(&config).map_err(|err| WasmError::Runtime(format!("{:#}", err)))?;
+        let mut linker = Linker::new(&engine);
+        wasmtime_wasi::p1::add_to_linker_sync(&mut linker, |state: &mut CallState| &mut state.wasi)
+            .map_err(|err| WasmError::Runtime(format!("{:#}", err)))?;
+        Ok(Self {
+            engine,
+            linker: Arc::new(linker),
+            limits: WasmLimits::default(),
+        })
+    }
+
+    /// Sets the limits applied to tools created from this runtime afterwards.
+    pub fn with_limits(mut self, limits: WasmLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Compiles a module from its binary or text form.
+    pub fn compile(&self, bytes: impl AsRef<[u8]>) -> Result<WasmModule, WasmError> {
+        let module = Module::new(&self.engine, bytes)
+            .map_err(|err| WasmError::Module(format!("{:#}", err)))?;
+        check_func(&module, "alloc", &[ValType::I32], &[ValType::I32])?;
+        match module.get_export("memory") {
+            Some(ExternType::Memory(_)) => {}
+            _ => {
+                return Err(WasmError::Export {
+                    export: "memory".into(),
+                    reason: "expected an exported memory".into(),
+                })
+            }
+        }
+        Ok(WasmModule {
+            runtime: self.clone(),
+            module,
+        })
+    }
+
+    /// Reads and compiles the module at `path`.
+    pub fn compile_file(&self, path: impl AsRef<Path>) -> Result<WasmModule, WasmError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|source| WasmError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        self.compile(bytes)
+    }
+}
+
+/// A compiled module whose exported functions can be registered as tools.
+#[derive(Clone)]
+pub struct WasmModule {
+    runtime: WasmRuntime,
+    module: Module,
+}
+
+impl WasmModule {
+    /// Creates a tool that calls the module's export named `name`.
+    pub fn tool(
+        &self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameter_schema: Value,
+    ) -> Result<Tool, WasmError> {
+        let name = name.into();
+        check_func(
+            &self.module,
+            &name,
+            &[ValType::I32, ValType::I32],
+            &[ValType::I64],
+        )?;
+
+        let parameter_names = parameter_schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|props| props.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        let module = self.clone();
+        let export = name.clone();
+        let function = ToolFn::Async(Box::new(move |args: Vec<Value>| -> ToolFuture {
+            // Turn the positional arguments back into the object the module expects
+            let object = parameter_names
+                .iter()
+                .zip(args)
+                .filter(|(_, value)| !value.is_null())
+                .map(|(name, value)| (name.clone(), value))
+                .collect::<Map<_, _>>();
+            let input = Value::Object(object).to_string().into_bytes();
+            let module = module.clone();
+            let export = export.clone();
+            Box::pin(async move {
+                tokio::task::spawn_blocking(move || module.invoke(&export, &input))
+                    .await
+                    .map_err(|err| ToolError::Execution(err.to_string()))?
+            })
+        }));
+        Ok(Tool::new(name, description, parameter_schema, function))
+    }
+
+    fn invoke(&self, export: &str, input: &[u8]) -> Result<String, ToolError> {
+        let limits = self.runtime.limits;
+        let mut store = Store::new(
+            &self.runtime.engine,
+            CallState {
+                wasi: WasiCtxBuilder::new().build_p1(),
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(limits.max_memory_bytes)
+                    .build(),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(limits.fuel).map_err(call_error)?;
+
+        let instance = self
+            .runtime
+            .linker
+            .instantiate(&mut store, &self.module)
+            .map_err(call_error)?;
+        // WASI reactors initialise their runtime before any other export is called
+        if let Ok(initialize) = instance.get_typed_func::<(), ()>(&mut store, "_initialize") {
+            initialize.call(&mut store, ()).map_err(call_error)?;
+        }
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| ToolError::Execution("module has no memory".into()))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "alloc")
+            .map_err(call_error)?;
+        let call = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, export)
+            .map_err(call_error)?;
+
+        let len = i32::try_from(input.len())
+            .map_err(|_| ToolError::Execution("arguments are too large".into()))?;
+        let ptr = alloc.call(&mut store, len).map_err(call_error)?;
+        memory
+            .write(&mut store, ptr as u32 as usize, input)
+            .map_err(|err| ToolError::Execution(err.to_string()))?;
+        let packed = call.call(&mut store, (ptr, len)).map_err(call_error)? as u64;
+
+        let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        if out_len > limits.max_output_bytes {
+            return Err(ToolError::Execution(format!(
+                "output of {} bytes exceeds the limit of {} bytes",
+                out_len, limits.max_output_bytes
+            )));
+        }
+        let mut output = vec![0; out_len];
+        memory
+            .read(&store, out_ptr, &mut output)
+            .map_err(|err| ToolError::Execution(err.to_string()))?;
+        String::from_utf8(output)
+            .map_err(|_| ToolError::Execution("output is not valid UTF-8".into()))
+    }
+}
+
+struct CallState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+fn check_func(
+    module: &Module,
+    export: &str,
+    params: &[ValType],
+    results: &[ValType],
+) -> Result<(), WasmError> {
+    let error = |reason: &str| WasmError::Export {
+        export: export.to_string(),
+        reason: reason.to_string(),
+    };
+    match module.get_export(export) {
+        Some(ExternType::Func(func)) => {
+            let matches = |actual: &mut dyn ExactSizeIterator<Item = ValType>,
+                           expected: &[ValType]| {
+                actual.len() == expected.len()
+                    && actual.zip(expected).all(|(a, e)| ValType::eq(&a, e))
+            };
+            if matches(&mut func.params(), params) && matches(&mut func.results(), results) {
+                Ok(())
+            } else {
+                Err(error(&format!(
+                    "expected a function ({}) -> ({})",
+                    type_list(params),
+                    type_list(results)
+                )))
+            }
+        }
+        Some(_) => Err(error("expected a function")),
+        None => Err(error("not exported")),
+    }
+}
+
+fn type_list(types: &[ValType]) -> String {
+    types
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn call_error(err: wasmtime::Error) -> ToolError {
+    match err.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => ToolError::Execution("tool exceeded its fuel limit".into()),
+        _ => ToolError::Execution(format!("{:#}", err)),
+    }
+}
@@ -0,0 +1,102 @@
+use serde_json::{json, Value};
+use tool_calling::{tool, ToolCallAccumulator, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Adds two integers
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+#[tool]
+pub fn now() -> String {
+    "noon".to_string()
+}
+
+fn chunk(delta: Value) -> Value {
+    json!({
+        "object": "chat.completion.chunk",
+        "choices": [{ "index": 0, "delta": { "tool_calls": [delta] }, "finish_reason": null }]
+    })
+}
+
+// Tests
+
+#[tokio::test]
+async fn interleaved_fragments_are_reassembled() {
+    let handler = ToolHandler::default();
+    let mut calls = ToolCallAccumulator::new();
+    let stream = [
+        json!({ "index": 0, "id": "call_a", "type": "function", "function": { "name": "add", "arguments": "" } }),
+        json!({ "index": 1, "id": "call_b", "type": "function", "function": { "name": "add", "arguments": "{\"a\":" } }),
+        json!({ "index": 0, "function": { "arguments": "{\"a\": 2" } }),
+        json!({ "index": 1, "function": { "arguments": "10, \"b\": 5}" } }),
+        json!({ "index": 0, "function": { "arguments": ", \"b\": 3}" } }),
+    ];
+    let mut complete = Vec::new();
+    for (i, delta) in stream.into_iter().enumerate() {
+        let done = calls.push(&chunk(delta));
+        if i < 3 {
+            assert!(done.is_empty());
+        }
+        complete.extend(done);
+    }
+    assert_eq!(calls.pending(), 0);
+
+    let ids = complete
+        .iter()
+        .map(|call| call["id"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["call_b", "call_a"]);
+    let outputs =
+        futures::future::join_all(complete.iter().map(|call| handler.call_tool(call))).await;
+    assert_eq!(outputs[0].as_ref().unwrap().output, "15");
+    assert_eq!(outputs[1].as_ref().unwrap().output, "5");
+}
+
+#[test]
+fn ollama_calls_arrive_whole() {
+    let mut calls = ToolCallAccumulator::new();
+    let complete = calls.push(&json!({
+        "model": "llama3.1",
+        "message": {
+            "role": "assistant",
+            "content": "",
+            "tool_calls": [
+                { "function": { "name": "add", "arguments": { "a": 1, "b": 2 } } },
+                { "function": { "name": "now", "arguments": {} } }
+            ]
+        },
+        "done": false
+    }));
+    assert_eq!(
+        complete,
+        vec![
+            json!({ "type": "function", "function": { "name": "add", "arguments": { "a": 1, "b": 2 } } }),
+            json!({ "type": "function", "function": { "name": "now", "arguments": {} } }),
+        ]
+    );
+    assert!(calls
+        .push(&json!({ "message": { "content": "" }, "done": true }))
+        .is_empty());
+}
+
+#[tokio::test]
+async fn finish_returns_incomplete_calls() {
+    let handler = ToolHandler::default();
+    let mut calls = ToolCallAccumulator::new();
+    calls.push(&chunk(
+        json!({ "index": 0, "id": "call_1", "function": { "name": "now" } }),
+    ));
+    calls.push(&chunk(json!({ "index": 1, "id": "call_2", "function": { "name": "add", "arguments": "{\"a\": 1" } })));
+    assert_eq!(calls.pending(), 2);
+
+    let rest = calls.finish();
+    assert_eq!(rest.len(), 2);
+    assert_eq!(rest[0]["function"]["arguments"], json!({}));
+    assert_eq!(handler.call_tool(&rest[0]).await.unwrap().output, "noon");
+
+    let err = handler.call_tool(&rest[1]).await.unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+}
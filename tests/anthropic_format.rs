@@ -0,0 +1,74 @@
+use serde_json::json;
+use tool_calling::anthropic::{parse_tool_use, tool_result};
+use tool_calling::{tool, ToolContext, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up a word in the dictionary
+pub fn define(word: String) -> String {
+    format!("{}: a word", word)
+}
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    (a / b).to_string()
+}
+
+// Tests
+
+#[test]
+fn tools_render_with_input_schemas() {
+    let handler = ToolHandler::default();
+    let tools = handler.anthropic_tools();
+    let define = tools
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|tool| tool["name"] == "define")
+        .unwrap();
+    assert_eq!(define["description"], "Look up a word in the dictionary");
+    assert_eq!(define["input_schema"]["required"], json!(["word"]));
+    assert!(define.get("function").is_none());
+}
+
+#[tokio::test]
+async fn tool_use_blocks_are_answered_with_tool_results() {
+    let handler = ToolHandler::default().with_error_mapper(|err: &ToolError| err.kind().into());
+    let content = json!([
+        { "type": "text", "text": "Checking." },
+        { "type": "tool_use", "id": "toolu_1", "name": "define", "input": { "word": "cat" } },
+        { "type": "tool_use", "id": "toolu_2", "name": "divide", "input": { "a": 1, "b": 0 } },
+        { "type": "tool_use", "id": "toolu_3", "name": "missing", "input": {} }
+    ]);
+    let results = handler
+        .answer_tool_uses(&content, &ToolContext::default())
+        .await;
+    assert_eq!(
+        results,
+        vec![
+            tool_result("toolu_1", "cat: a word", false),
+            json!({ "type": "tool_result", "tool_use_id": "toolu_2", "content": "execution", "is_error": true }),
+            tool_result("toolu_3", "not_found", true),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn tool_use_blocks_parse_into_calls() {
+    let block = json!({ "type": "tool_use", "id": "toolu_9", "name": "define", "input": { "word": "dog" } });
+    let call = parse_tool_use(&block).unwrap();
+    assert_eq!(call["function"]["name"], "define");
+    assert_eq!(call["id"], "toolu_9");
+
+    let handler = ToolHandler::default();
+    let res = handler
+        .call_tool_use(&block, &ToolContext::default())
+        .await
+        .unwrap();
+    assert_eq!(res.id.as_deref(), Some("toolu_9"));
+    assert_eq!(res.output, "dog: a word");
+
+    let err = parse_tool_use(&json!({ "type": "text", "text": "hi" })).unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+}
@@ -1,5 +1,5 @@
 use serde_json::json;
-use tool_calling::{tool, ToolError, ToolHandler};
+use tool_calling::{tool, ToolError, ToolHandler, UnknownArgs};
 
 // Define necessary tools for these tests
 
@@ -70,7 +70,7 @@ async fn type_mismatch() {
         .await
         .unwrap_err();
     assert!(
-        matches!(err, ToolError::BadArgs(msg) if msg.contains("Failed to parse argument 'foo' for parameter 'a'"))
+        matches!(err, ToolError::BadArgs(msg) if msg.contains("Failed to parse argument for parameter 'a'") && msg.contains("string \"foo\""))
     );
 }
 
@@ -98,5 +98,52 @@ async fn argument_ordering() {
     });
     // Use call_tool here as call_with_args bypasses JSON parsing/ordering logic
     let res = handler.call_tool(&input).await.unwrap();
-    assert_eq!(res, "first,second,third");
+    assert_eq!(res.output, "first,second,third");
+}
+
+#[tokio::test]
+async fn unknown_json_args_fail_by_default() {
+    let handler = ToolHandler::default();
+    let input = json!({
+        "type": "function",
+        "function": { "name": "add", "arguments": {"a": 1, "b": 2, "c": 3} }
+    });
+    let err = handler.call_tool(&input).await.unwrap_err();
+    assert!(
+        matches!(err, ToolError::BadArgs(msg) if msg == "Unknown argument(s) for tool 'add': c")
+    );
+}
+
+#[tokio::test]
+async fn unknown_args_stripped() {
+    let handler = ToolHandler::default().with_unknown_args(UnknownArgs::Strip);
+    let input = json!({
+        "type": "function",
+        "function": { "name": "add", "arguments": {"a": 1, "b": 2, "c": 3} }
+    });
+    assert_eq!(
+        handler.call_tool(&input).await.map(|r| r.output),
+        Ok("3".into())
+    );
+    let res = handler
+        .call_with_args("add", &["1".into(), "2".into(), "3".into()])
+        .await;
+    assert_eq!(res, Ok("3".into()));
+}
+
+#[tokio::test]
+async fn unknown_args_warned() {
+    let handler = ToolHandler::default().with_unknown_args(UnknownArgs::Warn);
+    let input = json!({
+        "type": "function",
+        "function": { "name": "opt", "arguments": {"y": 7} }
+    });
+    assert_eq!(
+        handler.call_tool(&input).await.map(|r| r.output),
+        Ok("none".into())
+    );
+    let res = handler
+        .call_with_args("opt", &["5".into(), "6".into()])
+        .await;
+    assert_eq!(res, Ok("5".into()));
 }
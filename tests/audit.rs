@@ -0,0 +1,147 @@
+use futures::{Stream, StreamExt};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tool_calling::audit::{AuditQuery, AuditRecord, AuditSink, FileAuditLog, MemoryAuditLog};
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    // Panics, and so fails the call, when `b` is zero
+    (a / b).to_string()
+}
+
+#[tool(stream)]
+pub fn letters(word: String) -> impl Stream<Item = Result<String, ToolError>> + Send {
+    futures::stream::iter(word.chars().map(|c| Ok(c.to_string())).collect::<Vec<_>>())
+}
+
+// Collects record names, to check custom sinks are called
+#[derive(Default)]
+struct NameSink(Mutex<Vec<String>>);
+
+impl AuditSink for NameSink {
+    fn record(&self, record: &AuditRecord) {
+        self.0.lock().unwrap().push(record.name.clone());
+    }
+}
+
+// Tests
+
+#[tokio::test]
+async fn calls_are_recorded_with_metadata() {
+    let log = Arc::new(MemoryAuditLog::with_capacity(10));
+    let handler = ToolHandler::default().with_audit(log.clone());
+    let alice = handler.clone().with_caller_metadata("user", "alice");
+
+    alice
+        .call_with_args("add", &["1".into(), "2".into()])
+        .await
+        .unwrap();
+    let call = json!({
+        "type": "function",
+        "id": "call_1",
+        "function": { "name": "divide", "arguments": { "a": 1, "b": 0 } }
+    });
+    assert!(handler.call_tool(&call).await.is_err());
+
+    let records = log.query(&AuditQuery::new());
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].name, "add");
+    assert_eq!(
+        records[0].arguments,
+        *json!({ "a": 1, "b": 2 }).as_object().unwrap()
+    );
+    assert_eq!(records[0].result, Ok("3".into()));
+    assert_eq!(records[0].metadata["user"], "alice");
+    assert_eq!(records[1].call_id.as_deref(), Some("call_1"));
+    assert!(records[1].metadata.is_empty());
+
+    let errors = log.query(&AuditQuery::new().errors_only());
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].name, "divide");
+    let by_alice = log.query(&AuditQuery::new().metadata("user", "alice"));
+    assert_eq!(by_alice.len(), 1);
+    assert!(log
+        .query(&AuditQuery::new().since(SystemTime::now()))
+        .is_empty());
+}
+
+#[tokio::test]
+async fn memory_log_keeps_the_most_recent_records() {
+    let log = Arc::new(MemoryAuditLog::with_capacity(2));
+    let handler = ToolHandler::default().with_audit(log.clone());
+    for i in 0..3 {
+        handler
+            .call_with_args("add", &[i.to_string(), "0".into()])
+            .await
+            .unwrap();
+    }
+    assert_eq!(log.len(), 2);
+    let outputs = log
+        .query(&AuditQuery::new())
+        .into_iter()
+        .map(|record| record.result.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(outputs, vec!["1", "2"]);
+    let latest = log.query(&AuditQuery::new().limit(1));
+    assert_eq!(latest[0].result, Ok("2".into()));
+}
+
+#[tokio::test]
+async fn streams_are_recorded_when_they_finish() {
+    let log = Arc::new(MemoryAuditLog::with_capacity(10));
+    let handler = ToolHandler::default().with_audit(log.clone());
+
+    let chunks = handler
+        .call_streaming("letters", &["abc".into()])
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(chunks.len(), 3);
+    let mut dropped = handler.call_streaming("letters", &["xyz".into()]).unwrap();
+    dropped.next().await;
+    drop(dropped);
+
+    let records = log.query(&AuditQuery::new().tool("letters"));
+    assert_eq!(records[0].result, Ok("abc".into()));
+    assert_eq!(
+        records[1].result,
+        Err(ToolError::Execution(
+            "stream dropped before completion".into()
+        ))
+    );
+}
+
+#[tokio::test]
+async fn file_and_custom_sinks() {
+    let path =
+        std::env::temp_dir().join(format!("tool_calling_audit_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let file = Arc::new(FileAuditLog::open(&path).unwrap());
+    let names = Arc::new(NameSink::default());
+
+    let handler = ToolHandler::default().with_audit(file.clone());
+    handler
+        .call_with_args("add", &["2".into(), "2".into()])
+        .await
+        .unwrap();
+    let handler = handler.with_audit(names.clone());
+    handler
+        .call_with_args("divide", &["4".into(), "2".into()])
+        .await
+        .unwrap();
+
+    let records = file.query(&AuditQuery::new());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].result, Ok("4".into()));
+    assert_eq!(*names.0.lock().unwrap(), vec!["divide"]);
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "axum")]
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use serde_json::{json, Value};
+use tool_calling::{tool, ToolContext, ToolFilter, ToolHandler};
+use tower::ServiceExt;
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Adds two integers
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    (a / b).to_string()
+}
+
+#[tool]
+pub fn whoami(#[param(context)] ctx: ToolContext) -> String {
+    ctx.user_id.clone().unwrap_or_default()
+}
+
+async fn send(app: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    let body = match body {
+        Some(body) => {
+            request = request.header("content-type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let request = request.header("x-user", "ada").body(body).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+// Tests
+
+#[tokio::test]
+async fn lists_and_runs_tools() {
+    let app = tool_calling::axum::router(ToolHandler::default());
+
+    let (status, tools) = send(&app, "GET", "/tools", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(tools
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|tool| tool["function"]["name"] == "add"));
+
+    let (status, add) = send(&app, "GET", "/tools/add", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(add["function"]["description"], "Adds two integers");
+
+    let (status, result) = send(&app, "POST", "/tools/add", Some(json!({ "a": 2, "b": 3 }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["name"], "add");
+    assert_eq!(result["output"], "5");
+}
+
+#[tokio::test]
+async fn errors_map_to_statuses() {
+    let app = tool_calling::axum::router(ToolHandler::default());
+    let cases = [
+        (
+            "/tools/missing",
+            json!({}),
+            StatusCode::NOT_FOUND,
+            "not_found",
+        ),
+        (
+            "/tools/add",
+            json!({ "a": 1 }),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "bad_args",
+        ),
+        (
+            "/tools/add",
+            json!([1, 2]),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "bad_args",
+        ),
+        (
+            "/tools/divide",
+            json!({ "a": 1, "b": 0 }),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "execution",
+        ),
+    ];
+    for (uri, body, expected, kind) in cases {
+        let (status, error) = send(&app, "POST", uri, Some(body)).await;
+        assert_eq!(status, expected, "{}", uri);
+        assert_eq!(error["error"]["kind"], kind);
+    }
+    let (status, _) = send(&app, "GET", "/tools/missing", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn context_comes_from_headers() {
+    let handler =
+        ToolHandler::default().with_tool_filter(|ctx: &ToolContext| match ctx.user_id.as_deref() {
+            Some("ada") => ToolFilter::only(["whoami"]),
+            _ => ToolFilter::all(),
+        });
+    let app = tool_calling::axum::router_with_context(handler, |headers| {
+        match headers.get("x-user").and_then(|user| user.to_str().ok()) {
+            Some(user) => ToolContext::new().with_user_id(user),
+            None => ToolContext::new(),
+        }
+    });
+
+    let (_, tools) = send(&app, "GET", "/tools", None).await;
+    assert_eq!(tools.as_array().unwrap().len(), 1);
+    let (status, result) = send(&app, "POST", "/tools/whoami", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["output"], "ada");
+    let (status, _) = send(&app, "POST", "/tools/add", Some(json!({ "a": 1, "b": 2 }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -32,6 +32,17 @@ pub fn mix(flag: bool, x: f64) -> String {
     format!("{}:{}", flag, x)
 }
 
+#[tool]
+pub fn point(x: i32, y: i32) -> String {
+    format!(r#"{{"x":{},"y":{}}}"#, x, y)
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
 // Tests
 
 #[tokio::test]
@@ -90,3 +101,15 @@ async fn bool_and_float() {
         .await;
     assert_eq!(res, Ok("true:3.14".into()));
 }
+
+#[tokio::test]
+async fn typed_result() {
+    let handler = ToolHandler::default();
+    let p: Point = handler
+        .call_typed("point", &["1".into(), "-2".into()])
+        .await
+        .unwrap();
+    assert_eq!(p, Point { x: 1, y: -2 });
+    let err = handler.call_typed::<Point>("hello", &[]).await.unwrap_err();
+    assert!(matches!(err, ToolError::Decode(msg) if msg.contains("output of tool 'hello'")));
+}
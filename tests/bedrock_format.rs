@@ -0,0 +1,120 @@
+use serde_json::json;
+use tool_calling::bedrock::{parse_tool_use, parse_tool_uses, tool_result, ToolChoice};
+use tool_calling::{tool, SchemaFlavor, Tool, ToolContext, ToolError, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up the weather
+pub fn weather(city: String) -> String {
+    format!("sunny in {}", city)
+}
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    (a / b).to_string()
+}
+
+// Tests
+
+#[test]
+fn tool_config_lists_tool_specs() {
+    let handler = ToolHandler::default();
+    let config = handler.bedrock_tool_config(None);
+    assert!(config.get("toolChoice").is_none());
+    assert_eq!(
+        config["tools"][1],
+        json!({
+            "toolSpec": {
+                "name": "weather",
+                "inputSchema": { "json": handler.get_tool("weather").unwrap().parameter_schema },
+                "description": "Look up the weather"
+            }
+        })
+    );
+    // `divide` has no doc comment, so no description is sent
+    assert!(config["tools"][0]["toolSpec"].get("description").is_none());
+
+    let config = handler.bedrock_tool_config(Some(ToolChoice::Tool("weather".into())));
+    assert_eq!(
+        config["toolChoice"],
+        json!({ "tool": { "name": "weather" } })
+    );
+    assert_eq!(ToolChoice::default().to_json(), json!({ "auto": {} }));
+
+    let tool = Tool::new(
+        "noop",
+        "Does nothing",
+        json!({ "type": "object", "properties": {} }),
+        ToolFn::Async(Box::new(|_| unreachable!())),
+    );
+    assert_eq!(
+        tool.render_schema(SchemaFlavor::Bedrock)["toolSpec"]["description"],
+        "Does nothing"
+    );
+}
+
+#[tokio::test]
+async fn tool_uses_are_answered_with_tool_results() {
+    let handler = ToolHandler::default();
+    let response = json!({
+        "output": {
+            "message": {
+                "role": "assistant",
+                "content": [
+                    { "text": "Let me check." },
+                    { "toolUse": { "toolUseId": "tooluse_a", "name": "weather", "input": { "city": "Lima" } } },
+                    { "toolUse": { "toolUseId": "tooluse_b", "name": "divide", "input": { "a": 1, "b": 0 } } }
+                ]
+            }
+        },
+        "stopReason": "tool_use"
+    });
+    let results = handler
+        .answer_bedrock_tool_uses(&response, &ToolContext::default())
+        .await;
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0], tool_result("tooluse_a", "sunny in Lima", false));
+    assert_eq!(results[1]["toolResult"]["toolUseId"], "tooluse_b");
+    assert_eq!(results[1]["toolResult"]["status"], "error");
+    assert!(results[1]["toolResult"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("panicked"));
+
+    // The message or its content can be passed instead of the whole response
+    let message = &response["output"]["message"];
+    assert_eq!(
+        handler
+            .answer_bedrock_tool_uses(message, &ToolContext::default())
+            .await,
+        results
+    );
+    assert_eq!(parse_tool_uses(&message["content"]).unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn tool_use_blocks_become_calls() {
+    let handler = ToolHandler::default();
+    let call = parse_tool_use(&json!({
+        "toolUse": { "toolUseId": "tooluse_c", "name": "weather", "input": { "city": "Oslo" } }
+    }))
+    .unwrap();
+    assert_eq!(
+        call,
+        json!({ "type": "function", "id": "tooluse_c", "function": { "name": "weather", "arguments": { "city": "Oslo" } } })
+    );
+    let result = handler.call_tool(&call).await.unwrap();
+    assert_eq!(result.id.as_deref(), Some("tooluse_c"));
+
+    let err = parse_tool_use(&json!({ "toolUse": { "input": {} } })).unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+    let err = handler
+        .call_bedrock_tool_use(
+            &json!({ "toolUse": { "toolUseId": "x", "name": "missing" } }),
+            &ToolContext::default(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::NotFound(_)));
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::call;
+use serde_json::json;
+use tool_calling::{bench, tool, Tool, ToolError, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Signs in to the broker
+pub fn log_in(user: String, #[param(secret)] pin: u32) -> String {
+    format!("{} signed in with {}", user, pin)
+}
+
+fn broken() -> Tool {
+    Tool::new(
+        "broken",
+        "Has an invalid schema",
+        json!({ "type": "object", "properties": { "x": { "type": 7 } }, "required": [] }),
+        ToolFn::Async(Box::new(|_| Box::pin(async { Ok(String::new()) }))),
+    )
+}
+
+// Tests
+
+#[test]
+fn compiles_schemas() {
+    let handler = ToolHandler::default().with_tool(broken());
+    assert_eq!(bench::compile_schema(&handler, "log_in"), Ok(()));
+    match bench::compile_schema(&handler, "broken") {
+        Err(ToolError::Execution(message)) => {
+            assert!(message.contains("Failed to compile schema for tool 'broken'"))
+        }
+        other => panic!("expected a compilation error, got {:?}", other),
+    }
+    assert_eq!(
+        bench::compile_schema(&handler, "missing"),
+        Err(ToolError::NotFound("missing".to_string()))
+    );
+}
+
+#[test]
+fn validates_arguments_against_the_schema() {
+    let handler = ToolHandler::default();
+    let valid = json!({ "user": "ada", "pin": 1234 });
+    assert_eq!(
+        bench::validate_arguments(&handler, "log_in", &valid),
+        Ok(())
+    );
+
+    let err = bench::validate_arguments(
+        &handler,
+        "log_in",
+        &json!({ "user": "ada", "pin": "98765" }),
+    )
+    .unwrap_err();
+    let ToolError::BadArgs(message) = err else {
+        panic!("expected invalid arguments, got {:?}", err);
+    };
+    assert!(message.contains("Argument validation failed for tool 'log_in'"));
+    // Secrets stay out of the message, as in a call
+    assert!(!message.contains("98765"), "{}", message);
+}
+
+#[test]
+fn agrees_with_validate_tool_call() {
+    let handler = ToolHandler::default();
+    for arguments in [
+        json!({ "user": "ada", "pin": 1 }),
+        json!({ "user": "ada" }),
+        json!({ "user": 5, "pin": 1 }),
+    ] {
+        let call = call("log_in", arguments.clone());
+        assert_eq!(
+            bench::validate_arguments(&handler, "log_in", &arguments).is_ok(),
+            handler.validate_tool_call(&call).is_ok(),
+            "{}",
+            arguments
+        );
+    }
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use common::call;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tool_calling::audit::{AuditSink, MemoryAuditLog};
+use tool_calling::testing::{Cassette, CassetteEntry, CassetteRecorder};
+use tool_calling::{tool, ToolError, ToolHandler, REDACTED};
+
+// Define necessary tools for these tests
+
+static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+
+#[tool]
+/// Looks up a stock price
+pub fn quote(symbol: String, exchange: Option<String>) -> String {
+    let n = LOOKUPS.fetch_add(1, Ordering::SeqCst);
+    if symbol == "NONE" {
+        panic!("unknown symbol");
+    }
+    format!("{} on {} #{}", symbol, exchange.unwrap_or("NYSE".into()), n)
+}
+
+#[tool]
+/// Signs in to the broker
+pub fn sign_in(user: String, #[param(secret)] password: String) -> String {
+    format!("{} signed in with {} characters", user, password.len())
+}
+
+// Tests
+
+#[tokio::test]
+async fn records_and_replays_calls() {
+    let recorder = CassetteRecorder::new(ToolHandler::default());
+    let handler = recorder.handler();
+    let first = handler
+        .call_tool(&call("quote", json!({ "symbol": "ACME" })))
+        .await
+        .unwrap()
+        .output;
+    let err = handler
+        .call_tool(&call("quote", json!({ "symbol": "NONE" })))
+        .await
+        .unwrap_err();
+
+    let cassette = recorder.cassette();
+    assert_eq!(cassette.calls.len(), 2);
+    assert_eq!(
+        cassette.calls[0],
+        CassetteEntry {
+            name: "quote".to_string(),
+            arguments: json!({ "symbol": "ACME" }).as_object().unwrap().clone(),
+            result: Ok(first.clone()),
+        }
+    );
+
+    let replay = cassette.replay(ToolHandler::default());
+    let lookups = LOOKUPS.load(Ordering::SeqCst);
+    let res = replay
+        .call_tool(&call("quote", json!({ "symbol": "ACME" })))
+        .await
+        .unwrap();
+    assert_eq!(res.output, first);
+    assert_eq!(
+        replay
+            .call_tool(&call("quote", json!({ "symbol": "NONE" })))
+            .await
+            .unwrap_err(),
+        err
+    );
+    // Nothing ran
+    assert_eq!(LOOKUPS.load(Ordering::SeqCst), lookups);
+}
+
+#[tokio::test]
+async fn repeated_calls_replay_in_turn() {
+    let cassette = Cassette {
+        calls: ["1.00", "2.00"]
+            .into_iter()
+            .map(|price| CassetteEntry {
+                name: "quote".to_string(),
+                arguments: json!({ "exchange": "LSE", "symbol": "ACME" })
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+                result: Ok(price.to_string()),
+            })
+            .collect(),
+    };
+    let replay = cassette.replay(ToolHandler::default());
+    // Argument order doesn't matter
+    let acme = call("quote", json!({ "symbol": "ACME", "exchange": "LSE" }));
+    assert_eq!(replay.call_tool(&acme).await.unwrap().output, "1.00");
+    assert_eq!(replay.call_tool(&acme).await.unwrap().output, "2.00");
+    assert_eq!(replay.call_tool(&acme).await.unwrap().output, "2.00");
+}
+
+#[tokio::test]
+async fn unrecorded_calls_fail() {
+    let replay = Cassette::new().replay(ToolHandler::default());
+    let err = replay
+        .call_tool(&call("quote", json!({ "symbol": "ACME" })))
+        .await
+        .unwrap_err();
+    match err {
+        ToolError::Execution(message) => {
+            assert!(
+                message.contains("no recorded call to 'quote'"),
+                "{}",
+                message
+            )
+        }
+        other => panic!("expected an execution error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn secrets_stay_out_of_cassettes() {
+    let recorder = CassetteRecorder::new(ToolHandler::default());
+    let sign_in = call("sign_in", json!({ "user": "ada", "password": "hunter2" }));
+    let output = recorder.handler().call_tool(&sign_in).await.unwrap().output;
+
+    let cassette = recorder.cassette();
+    assert_eq!(cassette.calls[0].arguments["password"], REDACTED);
+    let replay = cassette.replay(ToolHandler::default());
+    assert_eq!(replay.call_tool(&sign_in).await.unwrap().output, output);
+}
+
+#[tokio::test]
+async fn recording_keeps_the_audit_log() {
+    let log = Arc::new(MemoryAuditLog::with_capacity(10));
+    let recorder = CassetteRecorder::new(ToolHandler::default().with_audit(log.clone()));
+    recorder
+        .handler()
+        .call_tool(&call("quote", json!({ "symbol": "ACME" })))
+        .await
+        .unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(recorder.cassette().calls.len(), 1);
+    assert_eq!(log.query(&Default::default())[0].name, "quote");
+}
+
+#[tokio::test]
+async fn cassettes_round_trip_through_files() {
+    let recorder = CassetteRecorder::new(ToolHandler::default());
+    recorder
+        .handler()
+        .call_tool(&call(
+            "quote",
+            json!({ "symbol": "ACME", "exchange": "LSE" }),
+        ))
+        .await
+        .unwrap();
+    let path = std::env::temp_dir()
+        .join(format!("tool_calling_cassette_{}", std::process::id()))
+        .join("quotes.json");
+    recorder.save(&path).unwrap();
+
+    let cassette = Cassette::load(&path).unwrap();
+    assert_eq!(cassette, recorder.cassette());
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+    assert!(Cassette::load(&path).is_err());
+}
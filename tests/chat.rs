@@ -0,0 +1,351 @@
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tool_calling::chat::{ChatError, ChatEvent, ChatLoop, ChatProvider, ToolChoice};
+use tool_calling::{tool, SchemaFlavor, ToolContext, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up the weather
+pub fn weather(city: String) -> String {
+    format!("sunny in {}", city)
+}
+
+// A provider replying with a fixed script, recording what it was sent
+struct Scripted {
+    replies: Mutex<Vec<Value>>,
+    sent: Mutex<Vec<(usize, Vec<Value>)>>,
+    flavor: SchemaFlavor,
+}
+
+impl Scripted {
+    fn new(replies: Vec<Value>) -> Self {
+        Self {
+            replies: Mutex::new(replies),
+            sent: Mutex::new(Vec::new()),
+            flavor: SchemaFlavor::OpenAi,
+        }
+    }
+}
+
+// A provider that records the tool choice of every request
+struct Choosing(Scripted, Mutex<Vec<Option<ToolChoice>>>);
+
+impl ChatProvider for Choosing {
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        self.1.lock().unwrap().push(None);
+        self.0.chat(messages, tools)
+    }
+
+    fn chat_with_choice<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+        choice: &'a ToolChoice,
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        self.1.lock().unwrap().push(Some(choice.clone()));
+        self.0.chat(messages, tools)
+    }
+}
+
+impl ChatProvider for Scripted {
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [Value],
+        tools: &'a [Value],
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((messages.len(), tools.to_vec()));
+        let mut replies = self.replies.lock().unwrap();
+        let reply = if replies.is_empty() {
+            Err(ChatError::BadResponse("script finished".into()))
+        } else {
+            Ok(replies.remove(0))
+        };
+        Box::pin(async move { reply })
+    }
+
+    fn flavor(&self) -> SchemaFlavor {
+        self.flavor
+    }
+}
+
+fn weather_call(id: &str, city: &str) -> Value {
+    json!({
+        "role": "assistant",
+        "tool_calls": [{ "id": id, "type": "function", "function": { "name": "weather", "arguments": json!({ "city": city }).to_string() } }]
+    })
+}
+
+// Tests
+
+#[tokio::test]
+async fn tool_calls_are_run_until_the_model_answers() {
+    let handler = ToolHandler::default();
+    let provider = Scripted::new(vec![
+        weather_call("call_1", "Oslo"),
+        json!({ "choices": [{ "message": weather_call("call_2", "Rome"), "finish_reason": "tool_calls" }] }),
+        json!({ "choices": [{ "message": { "role": "assistant", "content": "Sunny in both." } }] }),
+    ]);
+    let mut messages = vec![json!({ "role": "user", "content": "Weather in Oslo and Rome?" })];
+    let response = ChatLoop::new(&handler, &provider)
+        .run(&mut messages)
+        .await
+        .unwrap();
+    assert_eq!(
+        response["choices"][0]["message"]["content"],
+        "Sunny in both."
+    );
+    assert_eq!(messages.len(), 5);
+    assert_eq!(
+        messages[2],
+        json!({ "role": "tool", "tool_call_id": "call_1", "content": "sunny in Oslo" })
+    );
+    assert_eq!(messages[4]["content"], "sunny in Rome");
+
+    let sent = provider.sent.lock().unwrap();
+    assert_eq!(
+        sent.iter().map(|(len, _)| *len).collect::<Vec<_>>(),
+        vec![1, 3, 5]
+    );
+    assert_eq!(Value::Array(sent[0].1.clone()), handler.all_tools_schema());
+}
+
+#[tokio::test]
+async fn external_tools_end_the_loop() {
+    let handler = ToolHandler::default();
+    let external = json!({ "type": "function", "function": { "name": "weather", "description": "Client-side weather", "parameters": {} } });
+    let provider = Scripted::new(vec![weather_call("call_1", "Oslo")]);
+    let mut messages = vec![json!({ "role": "user", "content": "Weather?" })];
+    let reply = ChatLoop::new(&handler, &provider)
+        .with_external_tools(vec![external.clone()])
+        .run(&mut messages)
+        .await
+        .unwrap();
+    assert_eq!(reply, weather_call("call_1", "Oslo"));
+    assert_eq!(messages.len(), 1);
+    // The external definition replaces the handler's
+    assert_eq!(provider.sent.lock().unwrap()[0].1, vec![external]);
+}
+
+#[tokio::test]
+async fn rounds_are_limited_and_errors_returned() {
+    let handler = ToolHandler::default();
+    let provider = Scripted::new(vec![
+        weather_call("call_1", "Oslo"),
+        weather_call("call_2", "Oslo"),
+    ]);
+    let err = ChatLoop::new(&handler, &provider)
+        .with_max_rounds(2)
+        .run(&mut vec![])
+        .await
+        .unwrap_err();
+    assert_eq!(err, ChatError::TooManyRounds(2));
+
+    let err = ChatLoop::new(&handler, &provider)
+        .run(&mut vec![])
+        .await
+        .unwrap_err();
+    assert_eq!(err, ChatError::BadResponse("script finished".into()));
+}
+
+#[tokio::test]
+async fn failing_tools_end_the_loop_when_asked() {
+    let handler = ToolHandler::empty();
+    let provider = Scripted::new(vec![
+        weather_call("call_1", "Oslo"),
+        json!({ "role": "assistant", "content": "Sorry." }),
+    ]);
+    let mut messages = vec![];
+    let reply = ChatLoop::new(&handler, &provider)
+        .run(&mut messages)
+        .await
+        .unwrap();
+    assert_eq!(reply["content"], "Sorry.");
+    assert_eq!(messages.len(), 2);
+
+    let provider = Scripted::new(vec![weather_call("call_1", "Oslo")]);
+    let err = ChatLoop::new(&handler, &provider)
+        .fail_on_tool_errors()
+        .run(&mut vec![])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ChatError::ToolFailed { name, .. } if name == "weather"));
+}
+
+#[tokio::test]
+async fn tools_use_the_provider_flavor_and_handler_settings() {
+    let handler = ToolHandler::default().with_tolerant_parsing();
+    let mut provider = Scripted::new(vec![
+        json!({ "role": "assistant", "content": "{\"name\": \"weather\", \"arguments\": {\"city\": \"Lima\"}}" }),
+        json!({ "role": "assistant", "content": "Sunny." }),
+    ]);
+    provider.flavor = SchemaFlavor::Anthropic;
+    let mut messages = vec![];
+    ChatLoop::new(&handler, &provider)
+        .with_context(ToolContext::default().with_user_id("alice"))
+        .run(&mut messages)
+        .await
+        .unwrap();
+    assert_eq!(messages[0]["tool_calls"][0]["id"], "call_0");
+    assert_eq!(messages[1]["content"], "sunny in Lima");
+    assert_eq!(
+        provider.sent.lock().unwrap()[0].1,
+        handler.anthropic_tools().as_array().unwrap().clone()
+    );
+}
+
+#[tokio::test]
+async fn events_report_messages_calls_and_results() {
+    let handler = ToolHandler::default();
+    let provider = Scripted::new(vec![
+        weather_call("call_1", "Oslo"),
+        json!({ "role": "assistant", "content": "It is sunny." }),
+    ]);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let hook = {
+        let events = Arc::clone(&events);
+        Arc::new(move |event: &ChatEvent<'_>| {
+            let entry = match event {
+                ChatEvent::MessageReceived(message) => ("message", (*message).clone()),
+                ChatEvent::ToolCalled(call) => ("call", call["function"]["name"].clone()),
+                ChatEvent::ToolResult(result) => ("result", result["content"].clone()),
+            };
+            events.lock().unwrap().push(entry);
+        })
+    };
+    let mut messages = vec![json!({ "role": "user", "content": "Weather?" })];
+    ChatLoop::new(&handler, &provider)
+        .with_events(hook)
+        .run(&mut messages)
+        .await
+        .unwrap();
+
+    let events = events.lock().unwrap();
+    let kinds = events.iter().map(|(kind, _)| *kind).collect::<Vec<_>>();
+    assert_eq!(kinds, ["message", "call", "result", "message"]);
+    assert_eq!(events[1].1, "weather");
+    assert_eq!(events[2].1, "sunny in Oslo");
+    assert_eq!(events[3].1["content"], "It is sunny.");
+}
+
+#[test]
+fn tool_choices_render_per_provider() {
+    let search = ToolChoice::Tool("search".into());
+    assert_eq!(ToolChoice::Auto.render(SchemaFlavor::OpenAi), None);
+    assert_eq!(
+        ToolChoice::Required.render(SchemaFlavor::OpenAi),
+        Some(json!("required"))
+    );
+    assert_eq!(
+        search.render(SchemaFlavor::OpenAi),
+        Some(json!({ "type": "function", "function": { "name": "search" } }))
+    );
+    assert_eq!(
+        ToolChoice::Required.render(SchemaFlavor::Mistral),
+        Some(json!("any"))
+    );
+    assert_eq!(
+        ToolChoice::None.render(SchemaFlavor::Anthropic),
+        Some(json!({ "type": "none" }))
+    );
+    assert_eq!(
+        search.render(SchemaFlavor::Anthropic),
+        Some(json!({ "type": "tool", "name": "search" }))
+    );
+    assert_eq!(
+        search.render(SchemaFlavor::Gemini),
+        Some(
+            json!({ "function_calling_config": { "mode": "ANY", "allowed_function_names": ["search"] } })
+        )
+    );
+    assert_eq!(
+        search.render(SchemaFlavor::Bedrock),
+        Some(json!({ "tool": { "name": "search" } }))
+    );
+    assert_eq!(ToolChoice::None.render(SchemaFlavor::Bedrock), None);
+    assert_eq!(search.render(SchemaFlavor::Ollama), None);
+}
+
+#[tokio::test]
+async fn tool_choice_applies_to_the_first_request() {
+    let handler = ToolHandler::default();
+    let provider = Choosing(
+        Scripted::new(vec![
+            weather_call("call_1", "Oslo"),
+            json!({ "role": "assistant", "content": "Sunny." }),
+        ]),
+        Mutex::default(),
+    );
+    let choice = ToolChoice::Tool("weather".into());
+    let reply = ChatLoop::new(&handler, &provider)
+        .with_tool_choice(choice.clone())
+        .run(&mut vec![])
+        .await
+        .unwrap();
+    assert_eq!(reply["content"], "Sunny.");
+    assert_eq!(
+        *provider.1.lock().unwrap(),
+        vec![Some(choice.clone()), None]
+    );
+    {
+        let sent = provider.0.sent.lock().unwrap();
+        assert_eq!(sent[0].1.len(), 1);
+        assert_eq!(sent[0].1[0]["function"]["name"], "weather");
+        assert_eq!(sent[1].1.len(), handler.tools().count());
+    }
+
+    let provider = Scripted::new(vec![json!({ "role": "assistant", "content": "No idea." })]);
+    let err = ChatLoop::new(&handler, &provider)
+        .with_tool_choice(ToolChoice::Required)
+        .run(&mut vec![])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ChatError::BadResponse(_)));
+
+    let provider = Scripted::new(vec![json!({ "role": "assistant", "content": "Hi." })]);
+    ChatLoop::new(&handler, &provider)
+        .with_tool_choice(ToolChoice::None)
+        .run(&mut vec![])
+        .await
+        .unwrap();
+    assert!(provider.sent.lock().unwrap()[0].1.is_empty());
+}
+
+#[tokio::test]
+async fn repeated_calls_are_stopped() {
+    let handler = ToolHandler::default();
+    let same_args_as_object = json!({
+        "role": "assistant",
+        "tool_calls": [{ "id": "call_3", "type": "function", "function": { "name": "weather", "arguments": { "city": "Oslo" } } }]
+    });
+    let provider = Scripted::new(vec![
+        weather_call("call_1", "Oslo"),
+        weather_call("call_2", "Rome"),
+        weather_call("call_2", "Oslo"),
+        same_args_as_object,
+    ]);
+    let mut messages = vec![];
+    let err = ChatLoop::new(&handler, &provider)
+        .with_max_repeated_calls(2)
+        .run(&mut messages)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ChatError::RepeatedCall {
+            name: "weather".into(),
+            arguments: json!({ "city": "Oslo" }).to_string(),
+            times: 3,
+        }
+    );
+    // The repeated call was not run
+    assert_eq!(messages.len(), 6);
+}
@@ -0,0 +1,96 @@
+use tool_calling::cli::{self, CliError};
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Adds two integers
+///
+/// Both may be negative.
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+#[tool]
+/// Says hello
+pub fn greet(name: Option<String>) -> String {
+    format!("Hello, {}!", name.unwrap_or("world".into()))
+}
+
+async fn run(args: &[&str]) -> Result<String, CliError> {
+    let mut out = Vec::new();
+    cli::run(&ToolHandler::default(), args.iter().copied(), &mut out).await?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+// Tests
+
+#[tokio::test]
+async fn lists_tools() {
+    let out = run(&["tools", "list"]).await.unwrap();
+    assert_eq!(out, "add    Adds two integers\ngreet  Says hello\n");
+}
+
+#[tokio::test]
+async fn describes_a_tool() {
+    let out = run(&["tools", "describe", "add"]).await.unwrap();
+    let schema: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(schema["function"]["name"], "add");
+    assert_eq!(
+        schema["function"]["parameters"]["required"],
+        serde_json::json!(["a", "b"])
+    );
+}
+
+#[tokio::test]
+async fn calls_a_tool() {
+    let out = run(&["tools", "call", "add", "--json", r#"{"a": 1, "b": 2}"#])
+        .await
+        .unwrap();
+    assert_eq!(out, "3\n");
+    // Without arguments the tool gets an empty object
+    let out = run(&["tools", "call", "greet"]).await.unwrap();
+    assert_eq!(out, "Hello, world!\n");
+}
+
+#[tokio::test]
+async fn reports_tool_errors() {
+    let err = run(&["tools", "describe", "missing"]).await.unwrap_err();
+    assert!(
+        matches!(err, CliError::Tool(ToolError::NotFound(_))),
+        "{:?}",
+        err
+    );
+
+    let err = run(&["tools", "call", "add", "--json", r#"{"a": 1}"#])
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, CliError::Tool(ToolError::BadArgs(_))),
+        "{:?}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn rejects_bad_command_lines() {
+    let err = run(&["tools", "remove", "add"]).await.unwrap_err();
+    assert!(matches!(err, CliError::Usage(_)), "{:?}", err);
+    assert!(err.to_string().contains("Usage:"));
+
+    let err = run(&["tools", "call", "add", "--json", "{"])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, CliError::Usage(_)), "{:?}", err);
+
+    let err = run(&["tools", "call", "add", "--yaml", "a: 1"])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, CliError::Usage(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn prints_usage() {
+    assert_eq!(run(&[]).await.unwrap(), cli::USAGE);
+    assert_eq!(run(&["--help"]).await.unwrap(), cli::USAGE);
+}
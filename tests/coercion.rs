@@ -0,0 +1,71 @@
+mod common;
+
+use common::call;
+use serde_json::json;
+use tool_calling::{tool, Coercion, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn typed(count: u32, ratio: f64, label: String, flag: Option<bool>) -> String {
+    format!("{}|{}|{}|{:?}", count, ratio, label, flag)
+}
+
+// Tests
+
+#[tokio::test]
+async fn strict_by_default() {
+    let handler = ToolHandler::default();
+    let input = call("typed", json!({"count": "3", "ratio": 0.5, "label": "x"}));
+    let res = handler.call_tool(&input).await;
+    assert!(
+        matches!(res, Err(ToolError::BadArgs(msg)) if msg.contains("Argument validation failed"))
+    );
+}
+
+#[tokio::test]
+async fn lenient_coerces_all_rules() {
+    let handler = ToolHandler::default().with_coercion(Coercion::lenient());
+    let input = call(
+        "typed",
+        json!({"count": " 3 ", "ratio": "0.5", "label": 7, "flag": "1"}),
+    );
+    let call = handler.validate_tool_call(&input).unwrap();
+    assert_eq!(call.arguments.get("count"), Some(&json!(3)));
+    assert_eq!(call.arguments.get("label"), Some(&json!("7")));
+    assert_eq!(
+        handler.call_tool(&input).await.map(|r| r.output),
+        Ok("3|0.5|7|Some(true)".into())
+    );
+}
+
+#[tokio::test]
+async fn individual_rules_are_respected() {
+    let handler = ToolHandler::default().with_coercion(Coercion {
+        to_boolean: true,
+        ..Coercion::strict()
+    });
+    let ok = call(
+        "typed",
+        json!({"count": 1, "ratio": 1.0, "label": "x", "flag": "false"}),
+    );
+    assert_eq!(
+        handler.call_tool(&ok).await.map(|r| r.output),
+        Ok("1|1|x|Some(false)".into())
+    );
+    let still_strict = call("typed", json!({"count": "1", "ratio": 1.0, "label": "x"}));
+    assert!(handler.call_tool(&still_strict).await.is_err());
+}
+
+#[tokio::test]
+async fn unparseable_values_still_fail_validation() {
+    let handler = ToolHandler::default().with_coercion(Coercion::lenient());
+    let input = call(
+        "typed",
+        json!({"count": "many", "ratio": 1.0, "label": "x", "flag": "yes"}),
+    );
+    assert!(matches!(
+        handler.call_tool(&input).await.map(|r| r.output),
+        Err(ToolError::BadArgs(msg)) if msg.contains("Argument validation failed")
+    ));
+}
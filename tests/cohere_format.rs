@@ -0,0 +1,112 @@
+mod common;
+
+use common::order_tool;
+use serde_json::json;
+use tool_calling::cohere::{parse_tool_call, parse_tool_calls, tool_plan, tool_result};
+use tool_calling::{tool, SchemaFlavor, ToolContext, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up a word in the dictionary
+pub fn define(word: String) -> String {
+    format!("{}: a word", word)
+}
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    (a / b).to_string()
+}
+
+// Tests
+
+#[test]
+fn parameters_become_parameter_definitions() {
+    let rendered = order_tool().render_schema(SchemaFlavor::Cohere);
+    assert_eq!(rendered["name"], "order");
+    assert_eq!(rendered["description"], "Orders a drink");
+    assert_eq!(
+        rendered["parameter_definitions"],
+        json!({
+            "size": { "description": "Cup size", "type": "str", "required": false },
+            "shots": { "description": "One of: 1, 2.", "type": "int", "required": true },
+            "extras": { "type": "List[str]", "required": false },
+            "price": { "type": "float", "required": false },
+            "options": { "type": "Dict", "required": false }
+        })
+    );
+
+    let handler = ToolHandler::default();
+    let tools = handler.cohere_tools();
+    assert_eq!(tools.as_array().unwrap().len(), 2);
+    assert_eq!(
+        tools[0]["parameter_definitions"]["word"],
+        json!({ "type": "str", "required": true })
+    );
+}
+
+#[tokio::test]
+async fn v1_tool_calls_are_answered_with_tool_results() {
+    let handler = ToolHandler::default();
+    let response = json!({
+        "text": "I will look up the word, then divide.",
+        "tool_calls": [
+            { "name": "define", "parameters": { "word": "tool" } },
+            { "name": "divide", "parameters": { "a": 1, "b": 0 } },
+            { "name": "missing", "parameters": {} }
+        ]
+    });
+    assert_eq!(
+        tool_plan(&response),
+        Some("I will look up the word, then divide.")
+    );
+    let results = handler
+        .answer_cohere_tool_calls(&response, &ToolContext::default())
+        .await;
+    assert_eq!(
+        results[0],
+        tool_result(
+            &json!({ "name": "define", "parameters": { "word": "tool" } }),
+            vec![json!({ "output": "tool: a word" })]
+        )
+    );
+    assert!(results[1]["outputs"][0]["error"]
+        .as_str()
+        .unwrap()
+        .contains("panicked"));
+    assert_eq!(
+        results[2]["outputs"][0]["error"],
+        handler.error_message(&ToolError::NotFound("missing".into()))
+    );
+
+    assert_eq!(tool_plan(&json!({ "text": "Hello!" })), None);
+    assert!(parse_tool_call(&json!({ "parameters": {} })).is_err());
+}
+
+#[tokio::test]
+async fn v2_responses_are_understood() {
+    let handler = ToolHandler::default();
+    let response = json!({
+        "id": "c14c80c3",
+        "finish_reason": "TOOL_CALL",
+        "message": {
+            "role": "assistant",
+            "tool_plan": "I will define the word.",
+            "tool_calls": [{
+                "id": "define_jm7zd6f9tqfp",
+                "type": "function",
+                "function": { "name": "define", "arguments": "{\"word\":\"plan\"}" }
+            }]
+        }
+    });
+    assert_eq!(tool_plan(&response), Some("I will define the word."));
+    let calls = parse_tool_calls(&response).unwrap();
+    let result = handler.call_tool(&calls[0]).await.unwrap();
+    assert_eq!(result.output, "plan: a word");
+    assert_eq!(result.id.as_deref(), Some("define_jm7zd6f9tqfp"));
+
+    let answers = handler
+        .answer_tool_calls(&response["message"], &ToolContext::default())
+        .await;
+    assert_eq!(answers[0]["tool_call_id"], "define_jm7zd6f9tqfp");
+}
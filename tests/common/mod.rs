@@ -0,0 +1,51 @@
+// Helpers shared by the integration tests; each test crate uses a few of them
+#![allow(dead_code)]
+
+use futures::future;
+use serde_json::{json, Value};
+use tool_calling::{Tool, ToolFn};
+
+// A tool call in the OpenAI shape
+pub fn call(name: &str, arguments: Value) -> Value {
+    json!({ "type": "function", "function": { "name": name, "arguments": arguments } })
+}
+
+// The same call carrying an id
+pub fn call_with_id(id: &str, name: &str, arguments: Value) -> Value {
+    json!({ "type": "function", "id": id, "function": { "name": name, "arguments": arguments } })
+}
+
+// A tool taking `parameters` that reports how many arguments it was called with
+pub fn schema_tool(name: &str, description: &str, parameters: Value) -> Tool {
+    Tool::new(
+        name,
+        description,
+        parameters,
+        ToolFn::Async(Box::new(|args| {
+            Box::pin(future::ready(Ok(format!("{} args", args.len()))))
+        })),
+    )
+}
+
+// A drink order whose parameters cover nullable, enum, array, number and object types
+pub fn order_tool() -> Tool {
+    schema_tool(
+        "order",
+        "Orders a drink",
+        json!({
+            "type": "object",
+            "properties": {
+                "size": {
+                    "anyOf": [{ "type": "string", "enum": ["small", "large"] }, { "type": "null" }],
+                    "description": "Cup size"
+                },
+                "shots": { "type": "integer", "enum": [1, 2], "minimum": 1 },
+                "extras": { "type": "array", "items": { "type": "string" }, "uniqueItems": true },
+                "price": { "type": ["number", "null"] },
+                "options": { "type": "object" }
+            },
+            "required": ["shots"],
+            "additionalProperties": false
+        }),
+    )
+}
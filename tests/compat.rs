@@ -0,0 +1,143 @@
+use serde_json::json;
+use tool_calling::compat::{normalize_message, normalize_response, split_thinking};
+use tool_calling::{tool, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Adds two integers
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+#[tool]
+pub fn now() -> String {
+    "noon".to_string()
+}
+
+// Tests
+
+#[test]
+fn calls_in_content_are_moved_to_tool_calls() {
+    let message = normalize_message(&json!({
+        "role": "assistant",
+        "content": "<|python_tag|>[{\"name\": \"add\", \"arguments\": {\"a\": 1, \"b\": 2}}, {\"name\": \"now\", \"parameters\": {}}]"
+    }));
+    assert_eq!(
+        message,
+        json!({
+            "role": "assistant",
+            "content": null,
+            "tool_calls": [
+                { "id": "call_0", "type": "function", "function": { "name": "add", "arguments": "{\"a\":1,\"b\":2}" } },
+                { "id": "call_1", "type": "function", "function": { "name": "now", "arguments": "{}" } }
+            ]
+        })
+    );
+
+    let wrapped = normalize_message(&json!({
+        "content": "{\"tool_calls\": [{\"id\": \"x\", \"function\": {\"name\": \"now\", \"arguments\": \"\"}}]}"
+    }));
+    assert_eq!(wrapped["tool_calls"][0]["id"], "x");
+    assert_eq!(wrapped["tool_calls"][0]["function"]["arguments"], "{}");
+
+    // Text and JSON that is not a call are left alone
+    for content in ["The answer is 3.", "{\"answer\": 3}", "[1, 2]"] {
+        let message = json!({ "role": "assistant", "content": content });
+        assert_eq!(normalize_message(&message), message);
+    }
+}
+
+#[test]
+fn structured_calls_are_completed() {
+    let message = normalize_message(&json!({
+        "role": "assistant",
+        "content": "",
+        "tool_calls": [
+            { "id": "", "function": { "name": "add", "arguments": "\"{\\\"a\\\": 1, \\\"b\\\": 2}\"" } },
+            { "id": "chatcmpl-tool-9", "type": "function", "function": { "name": "now", "arguments": null } }
+        ]
+    }));
+    assert_eq!(message["tool_calls"][0]["id"], "call_0");
+    assert_eq!(message["tool_calls"][0]["type"], "function");
+    assert_eq!(
+        message["tool_calls"][0]["function"]["arguments"],
+        "{\"a\": 1, \"b\": 2}"
+    );
+    assert_eq!(message["tool_calls"][1]["id"], "chatcmpl-tool-9");
+    assert_eq!(message["tool_calls"][1]["function"]["arguments"], "{}");
+
+    let legacy = normalize_message(&json!({
+        "role": "assistant",
+        "function_call": { "name": "now", "arguments": "{}" }
+    }));
+    assert!(legacy.get("function_call").is_none());
+    assert_eq!(legacy["tool_calls"][0]["function"]["name"], "now");
+}
+
+#[test]
+fn finish_reasons_are_normalized() {
+    let response = normalize_response(&json!({
+        "object": "chat.completion",
+        "choices": [
+            { "index": 0, "message": { "role": "assistant", "content": "{\"name\": \"now\", \"arguments\": {}}" }, "finish_reason": "stop" },
+            { "index": 1, "message": { "role": "assistant", "content": "Hi" }, "finish_reason": "eos" },
+            { "index": 2, "message": { "role": "assistant", "content": "Hi" }, "finish_reason": "max_tokens" },
+            { "index": 3, "message": { "role": "assistant", "content": "Hi" }, "finish_reason": null }
+        ]
+    }));
+    let reasons = response["choices"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|choice| choice["finish_reason"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        reasons,
+        vec![
+            json!("tool_calls"),
+            json!("stop"),
+            json!("length"),
+            json!(null)
+        ]
+    );
+    assert_eq!(response["object"], "chat.completion");
+}
+
+#[tokio::test]
+async fn tolerant_handlers_run_calls_from_content() {
+    let response = json!({
+        "choices": [{
+            "message": { "role": "assistant", "content": "{\"name\": \"add\", \"arguments\": {\"a\": 2, \"b\": 2}}" },
+            "finish_reason": "stop"
+        }]
+    });
+    assert!(ToolHandler::default().call_all(&response).await.is_empty());
+
+    let handler = ToolHandler::default().with_tolerant_parsing();
+    let results = handler.call_all(&response).await;
+    assert_eq!(results, vec![("call_0".to_string(), Ok("4".to_string()))]);
+}
+
+#[test]
+fn thinking_is_split_from_the_answer() {
+    assert_eq!(
+        split_thinking("<think>First.</think>One <think>Second.</think>two"),
+        ("One two".to_string(), Some("First.\nSecond.".to_string()))
+    );
+    // Opened by the chat template
+    assert_eq!(
+        split_thinking("Hmm.\n</think>\n\nYes."),
+        ("Yes.".to_string(), Some("Hmm.".to_string()))
+    );
+    // Cut off while thinking
+    assert_eq!(
+        split_thinking("<think>Still going"),
+        (String::new(), Some("Still going".to_string()))
+    );
+    assert_eq!(split_thinking("Plain."), ("Plain.".to_string(), None));
+    assert_eq!(
+        split_thinking("<think>\n</think>Empty."),
+        ("Empty.".to_string(), None)
+    );
+}
@@ -0,0 +1,118 @@
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tool_calling::{Tool, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[derive(Default)]
+struct Gauge {
+    running: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+// Sleeps for `ms` milliseconds and echoes it, recording how many calls overlapped
+fn nap(gauge: Arc<Gauge>) -> Tool {
+    Tool::new(
+        "nap",
+        "Sleeps for a while",
+        json!({
+            "type": "object",
+            "properties": { "ms": { "type": "integer" } },
+            "required": ["ms"]
+        }),
+        ToolFn::Async(Box::new(move |args| {
+            let gauge = gauge.clone();
+            Box::pin(async move {
+                let ms = args[0].as_u64().unwrap_or_default();
+                let running = gauge.running.fetch_add(1, Ordering::SeqCst) + 1;
+                gauge.peak.fetch_max(running, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(ms)).await;
+                gauge.running.fetch_sub(1, Ordering::SeqCst);
+                Ok(ms.to_string())
+            })
+        })),
+    )
+}
+
+// Later calls sleep for less, so they finish first
+fn message(naps: &[u64]) -> Value {
+    let calls = naps
+        .iter()
+        .enumerate()
+        .map(|(i, ms)| {
+            json!({
+                "id": format!("call_{}", i),
+                "type": "function",
+                "function": { "name": "nap", "arguments": { "ms": ms } }
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "role": "assistant", "tool_calls": calls })
+}
+
+fn outputs(results: Vec<(String, Result<String, tool_calling::ToolError>)>) -> Vec<String> {
+    results
+        .into_iter()
+        .map(|(id, result)| format!("{}={}", id, result.unwrap()))
+        .collect()
+}
+
+// Tests
+
+#[tokio::test]
+async fn calls_run_together_by_default() {
+    let gauge = Arc::new(Gauge::default());
+    let handler = ToolHandler::empty().with_tool(nap(gauge.clone()));
+    assert_eq!(handler.max_concurrent_calls(), None);
+
+    let results = handler.call_all(&message(&[60, 40, 20])).await;
+    assert_eq!(outputs(results), ["call_0=60", "call_1=40", "call_2=20"]);
+    assert_eq!(gauge.peak.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn the_limit_caps_calls_in_flight_and_keeps_their_order() {
+    let gauge = Arc::new(Gauge::default());
+    let handler = ToolHandler::empty()
+        .with_tool(nap(gauge.clone()))
+        .with_max_concurrent_calls(2);
+
+    let results = handler.call_all(&message(&[50, 40, 30, 20, 10])).await;
+    assert_eq!(
+        outputs(results),
+        [
+            "call_0=50",
+            "call_1=40",
+            "call_2=30",
+            "call_3=20",
+            "call_4=10"
+        ]
+    );
+    assert_eq!(gauge.peak.load(Ordering::SeqCst), 2);
+    assert_eq!(gauge.running.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn sequential_calls_are_a_limit_of_one() {
+    let gauge = Arc::new(Gauge::default());
+    let handler = ToolHandler::empty()
+        .with_tool(nap(gauge.clone()))
+        .with_sequential_calls();
+    assert_eq!(handler.max_concurrent_calls(), Some(1));
+
+    let messages = handler
+        .answer_tool_calls(&message(&[20, 10, 0]), &Default::default())
+        .await;
+    let ids = messages
+        .iter()
+        .map(|m| m["tool_call_id"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, ["call_0", "call_1", "call_2"]);
+    assert_eq!(gauge.peak.load(Ordering::SeqCst), 1);
+
+    // A limit of 0 would never run anything
+    let zero = ToolHandler::empty().with_max_concurrent_calls(0);
+    assert_eq!(zero.max_concurrent_calls(), Some(1));
+}
@@ -0,0 +1,279 @@
+#![cfg(feature = "config")]
+
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tool_calling::config::{ConfigError, ToolConfig};
+use tool_calling::{ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+const COMMANDS: &str = r#"
+[[tools]]
+name = "greet"
+description = "Greet someone"
+command = "echo 'Hello,' {name}{suffix}"
+tags = ["demo"]
+
+[tools.parameters]
+type = "object"
+properties = { name = { type = "string" }, suffix = { type = ["string", "null"] } }
+required = ["name"]
+
+[[tools]]
+name = "echo_all"
+command = "echo {args}"
+parameters = { type = "object", properties = { a = { type = "string" }, b = { type = "integer" } } }
+
+[[tools]]
+name = "fails"
+command = "false"
+"#;
+
+fn handler_for(config: ToolConfig) -> ToolHandler {
+    let mut handler = ToolHandler::empty();
+    for tool in config.into_tools().unwrap() {
+        handler.register(tool);
+    }
+    handler
+}
+
+// Serve a single HTTP request with a canned body; the handle resolves to the raw request
+async fn serve_once(body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/run", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        // Read the head, then as much body as it announces
+        while !complete(&request) {
+            let n = socket.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+        }
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8_lossy(&request).into_owned()
+    });
+    (url, server)
+}
+
+fn complete(request: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(request);
+    let Some((head, body)) = text.split_once("\r\n\r\n") else {
+        return false;
+    };
+    let length = head
+        .lines()
+        .find_map(|line| {
+            line.to_lowercase()
+                .strip_prefix("content-length: ")
+                .map(str::to_owned)
+        })
+        .and_then(|len| len.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    body.len() >= length
+}
+
+// Tests
+
+#[tokio::test]
+async fn command_tools_substitute_arguments() {
+    let handler = handler_for(ToolConfig::from_toml(COMMANDS).unwrap());
+    assert_eq!(handler.get_tool("greet").unwrap().tags, vec!["demo"]);
+
+    let call = json!({
+        "type": "function",
+        "function": { "name": "greet", "arguments": { "name": "Ada; rm -rf /", "suffix": "!" } }
+    });
+    let result = handler.call_tool(&call).await.unwrap();
+    // Arguments are passed as single words, never interpreted by a shell
+    assert_eq!(result.output, "Hello, Ada; rm -rf /!\n");
+    assert_eq!(
+        handler
+            .call_with_values("echo_all", &[json!("x"), json!(3)])
+            .await,
+        Ok("x 3\n".into())
+    );
+    let err = handler.call_with_values("fails", &[]).await.unwrap_err();
+    assert!(matches!(err, ToolError::Execution(msg) if msg.contains("exited")));
+}
+
+#[tokio::test]
+async fn yaml_configs_are_supported() {
+    let yaml = r#"
+tools:
+  - name: shout
+    description: Upper-case a word
+    command: "echo {word}"
+    parameters:
+      type: object
+      properties:
+        word: { type: string }
+      required: [word]
+"#;
+    let handler = handler_for(ToolConfig::from_yaml(yaml).unwrap());
+    assert_eq!(
+        handler.call_with_values("shout", &[json!("hey")]).await,
+        Ok("hey\n".into())
+    );
+}
+
+#[tokio::test]
+async fn literal_braces_are_not_placeholders() {
+    let config = ToolConfig::from_toml(
+        r#"
+[[tools]]
+name = "braces"
+description = "Echo around literal braces"
+command = "echo {} {word} {{}} {not-a-name}"
+parameters = { type = "object", properties = { word = { type = "string" } }, required = ["word"] }
+"#,
+    )
+    .unwrap();
+    let handler = handler_for(config);
+    assert_eq!(
+        handler.call_with_values("braces", &[json!("hi")]).await,
+        Ok("{} hi {{}} {not-a-name}\n".into())
+    );
+}
+
+#[tokio::test]
+async fn load_config_reads_files() {
+    let path = std::env::temp_dir().join(format!("tool_calling_{}.toml", std::process::id()));
+    std::fs::write(&path, COMMANDS).unwrap();
+    let mut handler = ToolHandler::empty();
+    let names = handler.load_config(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(names, vec!["greet", "echo_all", "fails"]);
+    assert!(handler.get_tool("echo_all").is_some());
+}
+
+#[test]
+fn invalid_definitions_are_rejected() {
+    let config = ToolConfig::from_toml(
+        r#"
+[[tools]]
+name = "broken"
+command = "echo {missing}"
+"#,
+    )
+    .unwrap();
+    let err = config.into_tools().unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid { tool, .. } if tool == "broken"));
+    assert!(matches!(
+        ToolConfig::from_toml("tools = 3"),
+        Err(ConfigError::Parse(_))
+    ));
+}
+
+#[test]
+fn programs_must_be_fixed() {
+    for command in ["{tool} --version", "''", "/usr/bin/{name}"] {
+        let config = ToolConfig::from_toml(&format!(
+            r#"
+[[tools]]
+name = "run"
+command = "{}"
+parameters = {{ type = "object", properties = {{ tool = {{ type = "string" }}, name = {{ type = "string" }} }} }}
+"#,
+            command
+        ))
+        .unwrap();
+        let err = config.into_tools().unwrap_err();
+        assert!(
+            matches!(err, ConfigError::Invalid { ref tool, .. } if tool == "run"),
+            "{}: {:?}",
+            command,
+            err
+        );
+    }
+    let empty = ToolConfig::from_toml("[[tools]]\nname = \"run\"\ncommand = \"\"").unwrap();
+    assert!(matches!(
+        empty.into_tools(),
+        Err(ConfigError::Invalid { reason, .. }) if reason == "command is empty"
+    ));
+}
+
+#[tokio::test]
+async fn arguments_cannot_become_flags() {
+    let handler = handler_for(ToolConfig::from_toml(COMMANDS).unwrap());
+    for value in ["-n", "--help"] {
+        let err = handler
+            .call_with_values("echo_all", &[json!(value), json!(1)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::BadArgs(msg) if msg.contains(value)));
+    }
+    let err = handler
+        .call_with_values("greet", &[json!("-e"), json!("x")])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+    // Dashes are fine anywhere but the start of a word
+    assert_eq!(
+        handler
+            .call_with_values("echo_all", &[json!("a-b"), json!(-1)])
+            .await,
+        Err(ToolError::BadArgs(
+            "argument '-1' would be read as a flag".into()
+        ))
+    );
+    assert_eq!(
+        handler
+            .call_with_values("greet", &[json!("Ada"), json!("-Lovelace")])
+            .await,
+        Ok("Hello, Ada-Lovelace\n".into())
+    );
+}
+
+#[tokio::test]
+async fn values_are_substituted_once() {
+    let handler = handler_for(ToolConfig::from_toml(COMMANDS).unwrap());
+    // A value that looks like a placeholder is passed through as is
+    assert_eq!(
+        handler
+            .call_with_values("greet", &[json!("{suffix}"), json!("!")])
+            .await,
+        Ok("Hello, {suffix}!\n".into())
+    );
+    assert_eq!(
+        handler
+            .call_with_values("greet", &[json!("Ada"), json!("{name}")])
+            .await,
+        Ok("Hello, Ada{name}\n".into())
+    );
+}
+
+#[tokio::test]
+async fn http_tools_send_arguments() {
+    let (url, server) = serve_once("sunny").await;
+    let config = ToolConfig::from_yaml(&format!(
+        r#"
+tools:
+  - name: weather
+    url: "{}"
+    headers:
+      X-Test: "token-${{TOOL_CALLING_TEST_TOKEN}}"
+    parameters:
+      type: object
+      properties:
+        city: {{ type: string }}
+"#,
+        url
+    ))
+    .unwrap();
+    std::env::set_var("TOOL_CALLING_TEST_TOKEN", "abc");
+    let handler = handler_for(config);
+    assert_eq!(
+        handler.call_with_values("weather", &[json!("Oslo")]).await,
+        Ok("sunny".into())
+    );
+    let request = server.await.unwrap();
+    assert!(request.starts_with("POST /run"));
+    assert!(request.contains("x-test: token-abc"));
+    assert!(request.ends_with(r#"{"city":"Oslo"}"#));
+}
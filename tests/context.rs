@@ -0,0 +1,137 @@
+use futures::{Stream, StreamExt};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tool_calling::audit::{AuditQuery, AuditSink, MemoryAuditLog};
+use tool_calling::{tool, ToolContext, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+struct Tenant(String);
+
+#[tool]
+/// Greet someone on behalf of the current user
+pub fn greet(name: String, #[param(context)] ctx: ToolContext) -> String {
+    let tenant = ctx
+        .get::<Tenant>()
+        .map_or("none", |tenant| tenant.0.as_str());
+    format!(
+        "{} greets {} for {}",
+        ctx.user_id.as_deref().unwrap_or_default(),
+        name,
+        tenant
+    )
+}
+
+#[tool]
+pub async fn slow(millis: u64) -> String {
+    tokio::time::sleep(Duration::from_millis(millis)).await;
+    "done".to_string()
+}
+
+#[tool(stream)]
+pub fn conversation() -> impl Stream<Item = Result<String, ToolError>> + Send {
+    futures::stream::iter(0..2).map(|_| {
+        Ok(ToolContext::current()
+            .conversation_id
+            .unwrap_or_else(|| "-".into()))
+    })
+}
+
+// Tests
+
+#[test]
+fn context_params_are_left_out_of_the_schema() {
+    let handler = ToolHandler::default();
+    let schema = &handler.get_tool("greet").unwrap().parameter_schema;
+    assert_eq!(
+        *schema,
+        json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" } },
+            "required": ["name"]
+        })
+    );
+}
+
+#[tokio::test]
+async fn context_is_passed_to_context_params() {
+    let handler = ToolHandler::default();
+    let call = json!({
+        "type": "function",
+        "function": { "name": "greet", "arguments": { "name": "bob" } }
+    });
+    let ctx = ToolContext::new()
+        .with_user_id("alice")
+        .with_extension(Tenant("acme".into()));
+    let res = handler.call_tool_with_context(&call, &ctx).await.unwrap();
+    assert_eq!(res.output, "alice greets bob for acme");
+
+    // Calls without a context get an empty one
+    let res = handler.call_tool(&call).await.unwrap();
+    assert_eq!(res.output, " greets bob for none");
+}
+
+#[tokio::test]
+async fn deadline_fails_slow_calls() {
+    let handler = ToolHandler::default();
+    let call = |millis: u64| {
+        json!({
+            "type": "function",
+            "function": { "name": "slow", "arguments": { "millis": millis } }
+        })
+    };
+    let ctx = ToolContext::new().with_timeout(Duration::from_millis(50));
+    assert!(ctx.remaining().unwrap() <= Duration::from_millis(50));
+
+    let res = handler
+        .call_tool_with_context(&call(0), &ctx)
+        .await
+        .unwrap();
+    assert_eq!(res.output, "done");
+    let err = handler
+        .call_tool_with_context(&call(5_000), &ctx)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ToolError::Execution("tool 'slow' exceeded its deadline".into())
+    );
+}
+
+#[tokio::test]
+async fn context_ids_are_added_to_audit_metadata() {
+    let log = Arc::new(MemoryAuditLog::with_capacity(10));
+    let handler = ToolHandler::default()
+        .with_audit(log.clone())
+        .with_caller_metadata("app", "cli");
+    let call = json!({
+        "type": "function",
+        "function": { "name": "greet", "arguments": { "name": "bob" } }
+    });
+    let ctx = ToolContext::new()
+        .with_user_id("alice")
+        .with_conversation_id("conv-7");
+    handler.call_tool_with_context(&call, &ctx).await.unwrap();
+
+    let records = log.query(&AuditQuery::new().metadata("user_id", "alice"));
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].metadata["conversation_id"], "conv-7");
+    assert_eq!(records[0].metadata["app"], "cli");
+}
+
+#[tokio::test]
+async fn current_context_is_set_while_streaming() {
+    let handler = ToolHandler::default();
+    let call = json!({
+        "type": "function",
+        "function": { "name": "conversation", "arguments": {} }
+    });
+    let res = handler
+        .call_tool_with_context(&call, &ToolContext::new().with_conversation_id("c1"))
+        .await
+        .unwrap();
+    assert_eq!(res.output, "c1c1");
+    // Outside a call there is no current context
+    assert!(ToolContext::current().conversation_id.is_none());
+}
@@ -0,0 +1,132 @@
+use serde_json::json;
+use tool_calling::conversation::{ChatMessage, Conversation, TrimStrategy};
+use tool_calling::tokens::ModelFamily;
+use tool_calling::{tool, ToolContext, ToolHandler, ToolResultContent};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up the weather
+pub fn weather(city: String) -> String {
+    format!("sunny in {}", city)
+}
+
+fn weather_call(id: &str, city: &str) -> serde_json::Value {
+    json!({
+        "role": "assistant",
+        "content": null,
+        "tool_calls": [{ "id": id, "type": "function", "function": { "name": "weather", "arguments": json!({ "city": city }).to_string() } }]
+    })
+}
+
+// Tests
+
+#[test]
+fn messages_round_trip_through_json() {
+    let messages = vec![
+        json!({ "role": "system", "content": "Be brief." }),
+        json!({ "role": "user", "content": [{ "type": "text", "text": "Hi" }] }),
+        weather_call("call_1", "Oslo"),
+        json!({ "role": "tool", "tool_call_id": "call_1", "name": "weather", "content": "sunny" }),
+    ];
+    let conversation = Conversation::from_json(&messages).unwrap();
+    assert_eq!(conversation.len(), 4);
+    assert_eq!(conversation.messages()[3].extra["name"], "weather");
+    assert_eq!(conversation.messages()[2].tool_calls.len(), 1);
+    let mut expected = messages.clone();
+    expected[2].as_object_mut().unwrap().remove("content");
+    assert_eq!(conversation.to_json(), expected);
+
+    let stored = serde_json::to_value(&conversation).unwrap();
+    assert_eq!(stored, json!(expected));
+    assert_eq!(
+        serde_json::from_value::<Conversation>(stored).unwrap(),
+        conversation
+    );
+    assert!(Conversation::from_json(&[json!({ "content": "no role" })]).is_err());
+}
+
+#[test]
+fn images_are_attached_to_messages() {
+    let message = ChatMessage::user("What's in these?")
+        .with_image("aGVsbG8=")
+        .with_images(["d29ybGQ="]);
+    assert_eq!(
+        message.to_json(),
+        json!({ "role": "user", "content": "What's in these?", "images": ["aGVsbG8=", "d29ybGQ="] })
+    );
+    assert_eq!(ChatMessage::from_json(&message.to_json()).unwrap(), message);
+    assert!(ChatMessage::user("Hi").to_json().get("images").is_none());
+
+    let content = [
+        ToolResultContent::text("A chart"),
+        ToolResultContent::image("iVBORw0KGgo=", "image/png"),
+    ];
+    let message = ChatMessage::tool("call_1", "A chart").with_result_images(&content);
+    assert_eq!(message.images, ["iVBORw0KGgo="]);
+}
+
+#[tokio::test]
+async fn tool_calls_are_answered_in_place() {
+    let handler = ToolHandler::default();
+    let mut conversation = Conversation::new();
+    conversation.push(ChatMessage::user("Weather in Oslo?"));
+    assert_eq!(
+        conversation
+            .answer_tool_calls(&handler, &ToolContext::default())
+            .await,
+        0
+    );
+    conversation
+        .push_json(&weather_call("call_1", "Oslo"))
+        .unwrap();
+    let added = conversation
+        .answer_tool_calls(&handler, &ToolContext::default())
+        .await;
+    assert_eq!(added, 1);
+    let answer = &conversation.messages()[2];
+    assert_eq!(answer.role, "tool");
+    assert_eq!(answer.tool_call_id.as_deref(), Some("call_1"));
+    assert_eq!(answer.text(), Some("sunny in Oslo"));
+
+    conversation.push_tool_result("call_2", "cloudy");
+    assert_eq!(
+        conversation.messages()[3],
+        ChatMessage::tool("call_2", "cloudy")
+    );
+}
+
+#[test]
+fn trimming_keeps_system_messages_and_tool_groups() {
+    let mut conversation = Conversation::new();
+    conversation.push(ChatMessage::system("Be brief."));
+    conversation.push(ChatMessage::user("Weather?"));
+    conversation
+        .push_json(&weather_call("call_1", "Oslo"))
+        .unwrap();
+    conversation.push_tool_result("call_1", "sunny");
+    conversation.push(ChatMessage::assistant("Sunny."));
+    conversation.push(ChatMessage::user("Thanks"));
+
+    let mut by_count = conversation.clone();
+    assert_eq!(by_count.trim(&TrimStrategy::MaxMessages(4)), 3);
+    let roles = by_count
+        .messages()
+        .iter()
+        .map(|m| m.role.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(roles, ["system", "assistant", "user"]);
+
+    let mut unchanged = conversation.clone();
+    assert_eq!(unchanged.trim(&TrimStrategy::MaxMessages(10)), 0);
+    assert_eq!(unchanged, conversation);
+
+    let mut by_tokens = conversation.clone();
+    let strategy = TrimStrategy::MaxTokens {
+        limit: 1,
+        family: ModelFamily::Gpt,
+    };
+    assert_eq!(by_tokens.trim(&strategy), 4);
+    assert_eq!(by_tokens.messages()[0].role, "system");
+    assert_eq!(by_tokens.messages()[1].text(), Some("Thanks"));
+}
@@ -0,0 +1,139 @@
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tool_calling::audit::{AuditQuery, AuditSink, MemoryAuditLog};
+use tool_calling::dataset::{write_jsonl, DatasetExporter, DatasetFormat};
+use tool_calling::{tool, ToolContext, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up the weather
+pub fn weather(city: String) -> String {
+    format!("sunny in {}", city)
+}
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    (a / b).to_string()
+}
+
+fn conversation() -> Vec<Value> {
+    vec![
+        json!({ "role": "user", "content": "Weather in Oslo and Rome?" }),
+        json!({ "role": "assistant", "content": null, "tool_calls": [
+            { "id": "call_1", "function": { "name": "weather", "arguments": { "city": "Oslo" } } },
+            { "id": "call_2", "type": "function", "function": { "name": "weather", "arguments": "{\"city\":\"Rome\"}" } }
+        ] }),
+        json!({ "role": "tool", "tool_call_id": "call_1", "content": "sunny in Oslo" }),
+        json!({ "role": "tool", "tool_call_id": "call_2", "content": "sunny in Rome" }),
+        json!({ "role": "assistant", "content": "Sunny in both." }),
+    ]
+}
+
+// Tests
+
+#[test]
+fn conversations_in_openai_format() {
+    let handler = ToolHandler::default();
+    let example = DatasetExporter::new(&handler, DatasetFormat::OpenAi)
+        .with_system_prompt("You are a weather bot.")
+        .conversation(&conversation());
+    assert_eq!(example["tools"], handler.all_tools_schema());
+    let messages = example["messages"].as_array().unwrap();
+    assert_eq!(
+        messages[0],
+        json!({ "role": "system", "content": "You are a weather bot." })
+    );
+    assert_eq!(
+        messages[2]["tool_calls"][0],
+        json!({ "id": "call_1", "type": "function", "function": { "name": "weather", "arguments": "{\"city\":\"Oslo\"}" } })
+    );
+    assert_eq!(messages.len(), 6);
+}
+
+#[test]
+fn conversations_in_sharegpt_format() {
+    let handler = ToolHandler::default();
+    let mut messages = conversation();
+    messages.insert(0, json!({ "role": "system", "content": "Be brief." }));
+    let example = DatasetExporter::new(&handler, DatasetFormat::ShareGpt)
+        .with_system_prompt("Ignored, the conversation has its own.")
+        .conversation(&messages);
+    assert_eq!(example["system"], "Be brief.");
+    assert_eq!(
+        example["conversations"],
+        json!([
+            { "from": "human", "value": "Weather in Oslo and Rome?" },
+            { "from": "function_call", "value": "[{\"name\":\"weather\",\"arguments\":{\"city\":\"Oslo\"}},{\"name\":\"weather\",\"arguments\":{\"city\":\"Rome\"}}]" },
+            { "from": "observation", "value": "[\"sunny in Oslo\",\"sunny in Rome\"]" },
+            { "from": "gpt", "value": "Sunny in both." }
+        ])
+    );
+    let tools: Value = serde_json::from_str(example["tools"].as_str().unwrap()).unwrap();
+    assert_eq!(tools[1]["name"], "weather");
+    assert!(tools[1].get("type").is_none());
+}
+
+#[tokio::test]
+async fn audit_records_become_examples() {
+    let log = Arc::new(MemoryAuditLog::with_capacity(100));
+    let handler = ToolHandler::default().with_audit(log.clone());
+    let first = ToolContext::default().with_conversation_id("conv-1");
+    let second = ToolContext::default().with_conversation_id("conv-2");
+    for (call, context) in [
+        (
+            json!({ "id": "a", "name": "weather", "arguments": { "city": "Oslo" } }),
+            &first,
+        ),
+        (
+            json!({ "id": "b", "name": "weather", "arguments": { "city": "Lima" } }),
+            &second,
+        ),
+        (
+            json!({ "id": "c", "name": "divide", "arguments": { "a": 1, "b": 0 } }),
+            &first,
+        ),
+        (
+            json!({ "id": "d", "name": "weather", "arguments": { "city": "Rome" } }),
+            &first,
+        ),
+    ] {
+        let _ = handler.call_tool_with_context(&call, context).await;
+    }
+    let records = log.query(&AuditQuery::new());
+
+    let exporter = DatasetExporter::new(&handler, DatasetFormat::OpenAi);
+    let examples = exporter.audit_records(&records);
+    assert_eq!(examples.len(), 2);
+    let messages = examples[0]["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 4);
+    assert_eq!(
+        messages[0]["tool_calls"][0]["function"]["arguments"],
+        "{\"city\":\"Oslo\"}"
+    );
+    assert_eq!(
+        messages[1],
+        json!({ "role": "tool", "tool_call_id": "a", "content": "sunny in Oslo" })
+    );
+    assert_eq!(messages[3]["content"], "sunny in Rome");
+
+    let examples = exporter.include_errors().audit_records(&records);
+    let messages = examples[0]["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 6);
+    assert!(messages[3]["content"]
+        .as_str()
+        .unwrap()
+        .contains("panicked"));
+
+    let path =
+        std::env::temp_dir().join(format!("tool_calling_dataset_{}.jsonl", std::process::id()));
+    write_jsonl(&examples, &path).unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let lines = written.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(
+        serde_json::from_str::<Value>(lines[1]).unwrap(),
+        examples[1]
+    );
+}
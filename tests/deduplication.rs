@@ -0,0 +1,93 @@
+mod common;
+
+use common::call;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tool_calling::{tool, ToolContext, ToolHandler};
+
+// Define necessary tools for these tests
+
+static SLOW_RUNS: AtomicUsize = AtomicUsize::new(0);
+static FLAKY_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+#[tool]
+pub async fn slow_echo(text: String) -> String {
+    SLOW_RUNS.fetch_add(1, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    text
+}
+
+#[tool]
+pub async fn sleepy(text: String) -> String {
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    text
+}
+
+#[tool]
+pub async fn flaky(n: i32) -> String {
+    FLAKY_RUNS.fetch_add(1, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    panic!("flaky failed on {}", n)
+}
+
+// Tests
+
+// One test drives the counters so parallel tests cannot disturb them
+#[tokio::test]
+async fn identical_concurrent_calls_share_one_execution() {
+    let handler = ToolHandler::default().with_deduplication();
+    let same = call("slow_echo", json!({ "text": "hi" }));
+    let other = call("slow_echo", json!({ "text": "bye" }));
+    let clone = handler.clone();
+    let (a, b, c, d) = tokio::join!(
+        handler.call_tool(&same),
+        handler.call_tool(&same),
+        clone.call_tool(&same),
+        handler.call_tool(&other)
+    );
+    let results = [a.unwrap(), b.unwrap(), c.unwrap(), d.unwrap()];
+    assert_eq!(SLOW_RUNS.load(Ordering::SeqCst), 2);
+    assert!(results[..3].iter().all(|res| res.output == "hi"));
+    assert_eq!(results.iter().filter(|res| res.deduplicated).count(), 2);
+    assert!(!results[3].deduplicated);
+
+    // Calls for different users are kept apart
+    let alice = ToolContext::new().with_user_id("alice");
+    let bob = ToolContext::new().with_user_id("bob");
+    let (a, b) = tokio::join!(
+        handler.call_tool_with_context(&same, &alice),
+        handler.call_tool_with_context(&same, &bob)
+    );
+    assert!(!a.unwrap().deduplicated && !b.unwrap().deduplicated);
+    assert_eq!(SLOW_RUNS.load(Ordering::SeqCst), 4);
+
+    // Finished calls are not reused
+    handler.call_tool(&same).await.unwrap();
+    assert_eq!(SLOW_RUNS.load(Ordering::SeqCst), 5);
+}
+
+#[tokio::test]
+async fn errors_are_shared_too() {
+    let handler = ToolHandler::default().with_deduplication();
+    let payload = call("flaky", json!({ "n": 1 }));
+    let (a, b) = tokio::join!(handler.call_tool(&payload), handler.call_tool(&payload));
+    assert_eq!(a.unwrap_err(), b.unwrap_err());
+    assert_eq!(FLAKY_RUNS.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn followers_take_over_from_a_cancelled_call() {
+    let handler = ToolHandler::default().with_deduplication();
+    let payload = call("sleepy", json!({ "text": "again" }));
+    let leader = tokio::time::timeout(Duration::from_millis(20), handler.call_tool(&payload));
+    let follower = async {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        handler.call_tool(&payload).await
+    };
+    let (leader, follower) = tokio::join!(leader, follower);
+    assert!(leader.is_err());
+    let res = follower.unwrap();
+    assert_eq!(res.output, "again");
+    assert!(!res.deduplicated);
+}
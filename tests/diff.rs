@@ -0,0 +1,268 @@
+use serde_json::{json, Value};
+use tool_calling::diff::{diff_schemas, ChangeKind, SchemaChange};
+use tool_calling::{tool, Tool, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Searches the web
+pub fn search(query: String, limit: Option<u32>) -> String {
+    format!("{} {:?}", query, limit)
+}
+
+#[tool]
+/// Gets the weather for a city
+pub fn weather(city: String) -> String {
+    format!("Sunny in {}", city)
+}
+
+fn define(name: &str, description: &str, parameters: Value) -> Tool {
+    Tool::new(
+        name,
+        description,
+        parameters,
+        ToolFn::Async(Box::new(|_| Box::pin(async { Ok(String::new()) }))),
+    )
+}
+
+fn registry(tools: Vec<Tool>) -> Value {
+    tools
+        .into_iter()
+        .fold(ToolHandler::empty(), ToolHandler::with_tool)
+        .all_tools_schema()
+}
+
+fn kinds(old: &Value, new: &Value) -> Vec<(Option<String>, ChangeKind)> {
+    diff_schemas(old, new)
+        .changes
+        .into_iter()
+        .map(|change| (change.parameter, change.kind))
+        .collect()
+}
+
+fn param(name: &str) -> Option<String> {
+    Some(name.to_string())
+}
+
+// Tests
+
+#[test]
+fn identical_registries_have_no_changes() {
+    let schema = ToolHandler::default().all_tools_schema();
+    let diff = diff_schemas(&schema, &schema);
+    assert!(diff.is_empty());
+    assert!(!diff.is_breaking());
+}
+
+#[test]
+fn tools_added_and_removed() {
+    let handler = ToolHandler::default();
+    let old = handler.tools_schema_for(&["search"]);
+    let new = handler.tools_schema_for(&["weather"]);
+    let diff = diff_schemas(&old, &new);
+    assert_eq!(
+        diff.changes,
+        vec![
+            SchemaChange {
+                tool: "search".to_string(),
+                parameter: None,
+                kind: ChangeKind::ToolRemoved
+            },
+            SchemaChange {
+                tool: "weather".to_string(),
+                parameter: None,
+                kind: ChangeKind::ToolAdded
+            },
+        ]
+    );
+    assert!(diff.is_breaking());
+    assert_eq!(diff.breaking().count(), 1);
+    assert_eq!(diff.additive().count(), 1);
+}
+
+#[test]
+fn parameter_changes() {
+    let old = registry(vec![define(
+        "search",
+        "Searches the web",
+        json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "limit": { "type": "integer" },
+                "safe": { "type": "boolean" },
+                "region": { "type": "string" }
+            },
+            "required": ["query", "limit"]
+        }),
+    )]);
+    let new = registry(vec![define(
+        "search",
+        "Searches the web",
+        json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "limit": { "type": "integer" },
+                "safe": { "type": "boolean" },
+                "page": { "type": "integer" },
+                "lang": { "type": "string" }
+            },
+            "required": ["query", "safe", "lang"]
+        }),
+    )]);
+    let diff = diff_schemas(&old, &new);
+    assert_eq!(
+        kinds(&old, &new),
+        vec![
+            (param("limit"), ChangeKind::BecameOptional),
+            (param("safe"), ChangeKind::BecameRequired),
+            (param("region"), ChangeKind::ParameterRemoved),
+            (
+                param("page"),
+                ChangeKind::ParameterAdded { required: false }
+            ),
+            (param("lang"), ChangeKind::ParameterAdded { required: true }),
+        ]
+    );
+    let breaking = diff
+        .breaking()
+        .map(|change| change.parameter.as_deref().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(breaking, ["safe", "region", "lang"]);
+}
+
+#[test]
+fn type_changes_break_unless_widened() {
+    let tool = |kind: Value| {
+        registry(vec![define(
+            "set",
+            "Sets a value",
+            json!({ "type": "object", "properties": { "value": { "type": kind } }, "required": [] }),
+        )])
+    };
+    let changed = |old: Value, new: Value| diff_schemas(&tool(old), &tool(new));
+
+    assert!(changed(json!("integer"), json!("string")).is_breaking());
+    assert!(changed(json!("number"), json!("integer")).is_breaking());
+    assert!(changed(json!(["string", "null"]), json!("string")).is_breaking());
+    assert!(!changed(json!("integer"), json!("number")).is_breaking());
+    assert!(!changed(json!("string"), json!(["string", "null"])).is_breaking());
+    assert_eq!(
+        changed(json!("integer"), json!("string")).changes[0].kind,
+        ChangeKind::TypeChanged {
+            old: json!("integer"),
+            new: json!("string")
+        }
+    );
+}
+
+#[test]
+fn enum_changes() {
+    let tool = |values: Option<Value>| {
+        let mut unit = json!({ "type": "string" });
+        if let Some(values) = values {
+            unit["enum"] = values;
+        }
+        registry(vec![define(
+            "convert",
+            "Converts a temperature",
+            json!({ "type": "object", "properties": { "unit": unit }, "required": ["unit"] }),
+        )])
+    };
+    assert_eq!(
+        kinds(
+            &tool(Some(json!(["C", "F"]))),
+            &tool(Some(json!(["C", "K"])))
+        ),
+        vec![
+            (
+                param("unit"),
+                ChangeKind::EnumValuesRemoved(vec![json!("F")])
+            ),
+            (param("unit"), ChangeKind::EnumValuesAdded(vec![json!("K")])),
+        ]
+    );
+    let restricted = diff_schemas(&tool(None), &tool(Some(json!(["C"]))));
+    assert_eq!(
+        restricted.changes[0].kind,
+        ChangeKind::RestrictedToEnum(vec![json!("C")])
+    );
+    assert!(restricted.is_breaking());
+    let lifted = diff_schemas(&tool(Some(json!(["C"]))), &tool(None));
+    assert_eq!(lifted.changes[0].kind, ChangeKind::EnumLifted);
+    assert!(!lifted.is_breaking());
+}
+
+#[test]
+fn nested_parameters_are_compared_by_path() {
+    let tool = |required: Value, item: Value| {
+        registry(vec![define(
+            "order",
+            "Places an order",
+            json!({
+                "type": "object",
+                "properties": {
+                    "shipping": {
+                        "type": "object",
+                        "properties": { "address": { "type": "string" }, "express": { "type": "boolean" } },
+                        "required": required
+                    },
+                    "items": { "type": "array", "items": item }
+                },
+                "required": ["shipping", "items"]
+            }),
+        )])
+    };
+    let old = tool(
+        json!(["address"]),
+        json!({ "type": "object", "properties": { "sku": { "type": "string" } } }),
+    );
+    let new = tool(
+        json!(["address", "express"]),
+        json!({ "type": "object", "properties": { "sku": { "type": "integer" } } }),
+    );
+    let paths = diff_schemas(&old, &new)
+        .changes
+        .into_iter()
+        .map(|change| change.parameter.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(paths, ["shipping.express", "items[].sku"]);
+}
+
+#[test]
+fn descriptions_are_additive() {
+    let old = registry(vec![define(
+        "ping",
+        "Pings a host",
+        json!({ "type": "object", "properties": {}, "required": [] }),
+    )]);
+    let new = registry(vec![define(
+        "ping",
+        "Pings a host twice",
+        json!({ "type": "object", "properties": {}, "required": [] }),
+    )]);
+    let diff = diff_schemas(&old, &new);
+    assert_eq!(diff.changes[0].kind, ChangeKind::DescriptionChanged);
+    assert!(!diff.is_breaking());
+}
+
+#[test]
+fn reads_other_providers_shapes() {
+    let handler = ToolHandler::default();
+    let openai = handler.tools_schema_for(&["search"]);
+    let anthropic = json!([{
+        "name": "search",
+        "description": "Searches the web",
+        "input_schema": {
+            "type": "object",
+            "properties": { "query": { "type": "string" } },
+            "required": ["query"]
+        }
+    }]);
+    let diff = diff_schemas(&openai, &anthropic);
+    assert_eq!(
+        diff.to_string(),
+        "breaking: tool 'search': parameter 'limit' removed\n"
+    );
+}
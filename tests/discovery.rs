@@ -0,0 +1,55 @@
+use tool_calling::{tool, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool(tags("web", "network"))]
+/// Fetch a page over HTTP
+pub fn fetch_url(url: String) -> String {
+    url
+}
+
+#[tool(tags("files"))]
+/// Read a local file, or fetch it from the cache
+pub fn read_file(path: String) -> String {
+    path
+}
+
+#[tool]
+/// Search the web for a query
+pub fn search(query: String) -> String {
+    query
+}
+
+// Tests
+
+#[tokio::test]
+async fn tags_are_registered() {
+    let handler = ToolHandler::default();
+    let tool = handler.get_tool("fetch_url").unwrap();
+    assert_eq!(tool.tags, vec!["web".to_string(), "network".to_string()]);
+    assert!(handler.get_tool("search").unwrap().tags.is_empty());
+}
+
+#[tokio::test]
+async fn find_ranks_name_over_tag_over_description() {
+    let handler = ToolHandler::default();
+    let names = |query: &str| {
+        handler
+            .find_tools(query)
+            .iter()
+            .map(|t| t.name.clone())
+            .collect::<Vec<_>>()
+    };
+    // "search" exact name, then tag "web" on fetch_url beats description match
+    assert_eq!(names("search web"), vec!["search", "fetch_url"]);
+    // Name substring beats description substring
+    assert_eq!(names("FETCH"), vec!["fetch_url", "read_file"]);
+    assert_eq!(names("files"), vec!["read_file"]);
+}
+
+#[tokio::test]
+async fn find_without_matches_is_empty() {
+    let handler = ToolHandler::default();
+    assert!(handler.find_tools("database").is_empty());
+    assert!(handler.find_tools("   ").is_empty());
+}
@@ -0,0 +1,110 @@
+use futures::future::{self, BoxFuture};
+use std::sync::{Arc, Mutex};
+use tool_calling::embedding::{Embedder, EmbeddingError, HashingEmbedder};
+use tool_calling::{tool, Tool, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Send an email to a recipient
+pub fn send_email(to: String, body: String) -> String {
+    format!("sent {} to {}", body, to)
+}
+
+#[tool]
+/// Look up the stock price of a company
+pub fn stock_price(ticker: String) -> String {
+    format!("{}: 100", ticker)
+}
+
+#[tool(tags("calendar"))]
+/// Create a meeting
+pub fn schedule(title: String) -> String {
+    title
+}
+
+// Wraps the hashing embedder and records every batch it embeds
+struct RecordingEmbedder(Mutex<Vec<Vec<String>>>, HashingEmbedder);
+
+impl Default for RecordingEmbedder {
+    fn default() -> Self {
+        Self(Mutex::default(), HashingEmbedder::new(128))
+    }
+}
+
+impl Embedder for RecordingEmbedder {
+    fn embed(&self, texts: Vec<String>) -> BoxFuture<'_, Result<Vec<Vec<f32>>, EmbeddingError>> {
+        self.0.lock().unwrap().push(texts.clone());
+        self.1.embed(texts)
+    }
+}
+
+struct BrokenEmbedder;
+
+impl Embedder for BrokenEmbedder {
+    fn embed(&self, _texts: Vec<String>) -> BoxFuture<'_, Result<Vec<Vec<f32>>, EmbeddingError>> {
+        Box::pin(future::ready(Ok(Vec::new())))
+    }
+}
+
+fn names(tools: Vec<&Tool>) -> Vec<&str> {
+    tools.into_iter().map(|tool| tool.name.as_str()).collect()
+}
+
+// Tests
+
+#[tokio::test]
+async fn tools_are_ranked_by_similarity() {
+    let handler = ToolHandler::default().with_embedder(Arc::new(HashingEmbedder::new(512)));
+    let top = handler.top_k_tools("email my recipient", 1).await.unwrap();
+    assert_eq!(names(top), vec!["send_email"]);
+    let top = handler.top_k_tools("price of a stock", 2).await.unwrap();
+    assert_eq!(names(top)[0], "stock_price");
+    // Tags are part of a tool's text
+    let top = handler.top_k_tools("calendar", 1).await.unwrap();
+    assert_eq!(names(top), vec!["schedule"]);
+    assert_eq!(handler.top_k_tools("anything", 10).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn embeddings_are_cached_until_a_tool_changes() {
+    let embedder = Arc::new(RecordingEmbedder::default());
+    let mut handler = ToolHandler::default().with_embedder(embedder.clone());
+    handler.top_k_tools("email", 1).await.unwrap();
+    handler.top_k_tools("stock", 1).await.unwrap();
+
+    let original = handler.get_tool("schedule").unwrap();
+    let ToolFn::Async(_) = &original.function else {
+        panic!("expected an async tool");
+    };
+    handler.register(Tool::new(
+        "schedule",
+        "Book a room",
+        original.parameter_schema.clone(),
+        ToolFn::Async(Box::new(|_| Box::pin(future::ready(Ok(String::new()))))),
+    ));
+    let top = handler.top_k_tools("book a room", 1).await.unwrap();
+    assert_eq!(names(top), vec!["schedule"]);
+
+    let batches = embedder.0.lock().unwrap();
+    // Every tool and the query, then only the query, then the changed tool and the query
+    assert_eq!(
+        batches.iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![4, 1, 2]
+    );
+    assert_eq!(batches[2][0], "schedule: Book a room");
+}
+
+#[tokio::test]
+async fn embedder_errors_are_reported() {
+    let handler = ToolHandler::default();
+    assert_eq!(
+        handler.top_k_tools("email", 1).await.unwrap_err(),
+        EmbeddingError::NotConfigured
+    );
+    let handler = handler.with_embedder(Arc::new(BrokenEmbedder));
+    assert_eq!(
+        handler.top_k_tools("email", 1).await.unwrap_err(),
+        EmbeddingError::Embedder("expected 4 embeddings, got 0".into())
+    );
+}
@@ -1,3 +1,4 @@
+use futures::StreamExt;
 use serde_json::json;
 use tool_calling::{tool, ToolError, ToolHandler};
 
@@ -8,6 +9,26 @@ pub fn boom() -> String {
     panic!("oh no")
 }
 
+#[tool]
+pub async fn async_boom(code: u32) -> String {
+    panic!("failed with code {}", code)
+}
+
+#[tool]
+pub fn opaque_boom() -> String {
+    std::panic::panic_any(42)
+}
+
+#[tool(stream)]
+pub fn stream_boom() -> impl futures::Stream<Item = Result<String, ToolError>> + Send {
+    futures::stream::iter([1, 0]).map(|n| {
+        if n == 0 {
+            panic!("ran dry");
+        }
+        Ok(n.to_string())
+    })
+}
+
 // Need add for schema injection test
 #[tool]
 pub fn add(a: i32, b: i32) -> String {
@@ -20,7 +41,46 @@ pub fn add(a: i32, b: i32) -> String {
 async fn panic_in_tool() {
     let handler = ToolHandler::default();
     let err = handler.call_with_args("boom", &[]).await.unwrap_err();
-    assert!(matches!(err, ToolError::Execution(msg) if msg == "panic in tool"));
+    assert_eq!(
+        err,
+        ToolError::Execution("tool 'boom' panicked: oh no".into())
+    );
+}
+
+#[tokio::test]
+async fn panics_in_async_and_streaming_tools_are_caught() {
+    let handler = ToolHandler::default();
+    let err = handler
+        .call_with_args("async_boom", &["7".into()])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ToolError::Execution("tool 'async_boom' panicked: failed with code 7".into())
+    );
+    let err = handler
+        .call_with_args("opaque_boom", &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ToolError::Execution("tool 'opaque_boom' panicked".into())
+    );
+
+    let chunks = handler
+        .call_streaming("stream_boom", &[])
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(
+        chunks,
+        vec![
+            Ok("1".into()),
+            Err(ToolError::Execution(
+                "tool 'stream_boom' panicked: ran dry".into()
+            ))
+        ]
+    );
 }
 
 #[tokio::test]
@@ -42,3 +102,29 @@ async fn ignore_schema_injection() {
         matches!(res, Err(ToolError::BadArgs(msg)) if msg.contains("Argument validation failed for tool 'add':"))
     );
 }
+
+#[tokio::test]
+async fn invalid_schema_reported_on_every_call() {
+    use std::sync::Arc;
+    use tool_calling::{wrap_sync, Tool, ToolFn};
+
+    let broken = Tool::new(
+        "broken",
+        "Has an invalid schema",
+        json!({ "type": 5, "properties": {}, "required": [] }),
+        ToolFn::Async(wrap_sync(Arc::new(|_: &[serde_json::Value]| {
+            Ok("never".into())
+        }))),
+    );
+    let handler = ToolHandler::empty().with_tool(broken);
+    let input = json!({
+        "type": "function",
+        "function": { "name": "broken", "arguments": {} }
+    });
+    for _ in 0..2 {
+        let err = handler.call_tool(&input).await.unwrap_err();
+        assert!(
+            matches!(err, ToolError::Execution(msg) if msg.starts_with("Failed to compile schema for tool 'broken'"))
+        );
+    }
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::call;
+use serde_json::json;
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    (a / b).to_string()
+}
+
+// Tests
+
+#[tokio::test]
+async fn errors_are_described_by_default() {
+    let handler = ToolHandler::default();
+    let err = handler
+        .call_tool(&call("divide", json!({ "a": 1 })))
+        .await
+        .unwrap_err();
+    assert_eq!(handler.error_message(&err), format!("Error: {}", err));
+}
+
+#[tokio::test]
+async fn mappers_can_hide_internal_details() {
+    let handler = ToolHandler::default().with_error_mapper(|err: &ToolError| match err {
+        ToolError::Execution(_) => json!({ "error": err.kind() }).to_string(),
+        other => format!("Please fix the call: {}", other.message()),
+    });
+
+    let err = handler
+        .call_tool(&call("divide", json!({ "a": 1, "b": 0 })))
+        .await
+        .unwrap_err();
+    assert!(err.message().contains("divide by zero"));
+    assert_eq!(handler.error_message(&err), r#"{"error":"execution"}"#);
+
+    let err = ToolError::NotFound("multiply".into());
+    assert_eq!(handler.error_message(&err), "Please fix the call: multiply");
+}
+
+#[test]
+fn errors_expose_their_kind_and_message() {
+    let err = ToolError::BadArgs("missing 'b'".into());
+    assert_eq!(err.kind(), "bad_args");
+    assert_eq!(err.message(), "missing 'b'");
+    assert_eq!(ToolError::Decode(String::new()).kind(), "decode");
+}
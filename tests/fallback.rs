@@ -0,0 +1,95 @@
+mod common;
+
+use common::{call, call_with_id};
+use serde_json::json;
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn primary(query: String) -> String {
+    panic!("primary is down, dropping '{}'", query)
+}
+
+#[tool]
+pub fn secondary(query: String) -> String {
+    if query == "hard" {
+        panic!("secondary cannot answer");
+    }
+    format!("secondary: {}", query)
+}
+
+#[tool]
+pub fn last_resort(query: String) -> String {
+    format!("last resort: {}", query)
+}
+
+#[tool]
+pub fn numeric(n: i32) -> String {
+    n.to_string()
+}
+
+// Tests
+
+#[tokio::test]
+async fn failed_calls_are_answered_by_the_fallback() {
+    let handler = ToolHandler::default().with_fallback("primary", "secondary");
+    let res = handler
+        .call_tool(&call_with_id(
+            "call_1",
+            "primary",
+            json!({ "query": "rust" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.name, "primary");
+    assert_eq!(res.id.as_deref(), Some("call_1"));
+    assert_eq!(res.output, "secondary: rust");
+    assert_eq!(res.fallback.as_deref(), Some("secondary"));
+
+    // Successful calls are not annotated
+    let res = handler
+        .call_tool(&call("secondary", json!({ "query": "rust" })))
+        .await
+        .unwrap();
+    assert_eq!(res.fallback, None);
+}
+
+#[tokio::test]
+async fn fallbacks_chain() {
+    let handler = ToolHandler::default()
+        .with_fallback("primary", "secondary")
+        .with_fallback("secondary", "last_resort");
+    let res = handler
+        .call_tool(&call("primary", json!({ "query": "hard" })))
+        .await
+        .unwrap();
+    assert_eq!(res.output, "last resort: hard");
+    assert_eq!(res.fallback.as_deref(), Some("last_resort"));
+}
+
+#[tokio::test]
+async fn the_original_error_is_kept_when_every_fallback_fails() {
+    let handler = ToolHandler::default()
+        .with_fallback("primary", "numeric")
+        .with_fallback("numeric", "primary");
+    // `numeric` rejects the arguments, and the cycle back to `primary` is not retried
+    let err = handler
+        .call_tool(&call("primary", json!({ "query": "rust" })))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ToolError::Execution("tool 'primary' panicked: primary is down, dropping 'rust'".into())
+    );
+}
+
+#[tokio::test]
+async fn invalid_arguments_do_not_trigger_the_fallback() {
+    let handler = ToolHandler::default().with_fallback("numeric", "last_resort");
+    let err = handler
+        .call_tool(&call("numeric", json!({ "n": "many" })))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::call;
+use serde_json::{json, Value};
+use tool_calling::{tool, ToolContext, ToolError, ToolFilter, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool(tags("admin"))]
+pub fn delete_user(name: String) -> String {
+    format!("deleted {}", name)
+}
+
+#[tool]
+pub fn list_users() -> String {
+    "ada, bob".to_string()
+}
+
+#[tool]
+pub fn ping() -> String {
+    "pong".to_string()
+}
+
+struct Role(&'static str);
+
+fn admin_filter() -> ToolHandler {
+    ToolHandler::default().with_tool_filter(|ctx: &ToolContext| match ctx.get::<Role>() {
+        Some(Role("admin")) => ToolFilter::all(),
+        Some(_) => ToolFilter::untagged("admin"),
+        None => ToolFilter::only(["ping"]),
+    })
+}
+
+fn names(schema: &Value) -> Vec<&str> {
+    schema
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["function"]["name"].as_str().unwrap())
+        .collect()
+}
+
+// Tests
+
+#[test]
+fn schemas_show_each_request_its_own_tools() {
+    let handler = admin_filter();
+    let admin = ToolContext::new().with_extension(Role("admin"));
+    let member = ToolContext::new().with_extension(Role("member"));
+    assert_eq!(
+        names(&handler.tools_schema_with_context(&admin)),
+        vec!["delete_user", "list_users", "ping"]
+    );
+    assert_eq!(
+        names(&handler.tools_schema_with_context(&member)),
+        vec!["list_users", "ping"]
+    );
+    assert_eq!(
+        names(&handler.tools_schema_with_context(&ToolContext::new())),
+        vec!["ping"]
+    );
+    // The unfiltered schema still lists every tool
+    assert_eq!(names(&handler.all_tools_schema()).len(), 3);
+}
+
+#[tokio::test]
+async fn hidden_tools_cannot_be_called() {
+    let handler = admin_filter();
+    let member = ToolContext::new().with_extension(Role("member"));
+    // Hidden tools look unregistered, even when the arguments are invalid
+    for arguments in [json!({ "name": "bob" }), json!({})] {
+        let err = handler
+            .call_tool_with_context(&call("delete_user", arguments), &member)
+            .await
+            .unwrap_err();
+        assert_eq!(err, ToolError::NotFound("delete_user".into()));
+    }
+    let res = handler
+        .call_tool_with_context(&call("list_users", json!({})), &member)
+        .await
+        .unwrap();
+    assert_eq!(res.output, "ada, bob");
+
+    // Calls without a context see the filter for an empty context
+    assert!(handler
+        .call_tool(&call("list_users", json!({})))
+        .await
+        .is_err());
+    assert!(handler
+        .call_tool_streaming(&call("list_users", json!({})))
+        .is_err());
+    assert!(handler.start_job(&call("list_users", json!({}))).is_err());
+    assert_eq!(
+        handler
+            .call_tool(&call("ping", json!({})))
+            .await
+            .unwrap()
+            .output,
+        "pong"
+    );
+}
+
+#[test]
+fn filters_combine() {
+    let handler = ToolHandler::default();
+    let filter = ToolFilter::except(["ping"]).and(ToolFilter::untagged("admin"));
+    let allowed = handler
+        .tools()
+        .filter(|tool| filter.allows(tool))
+        .map(|tool| tool.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(allowed, vec!["list_users"]);
+    assert!(handler.tools().all(|tool| !ToolFilter::none().allows(tool)));
+    assert_eq!(
+        handler
+            .tools()
+            .filter(|tool| ToolFilter::tagged("admin").allows(tool))
+            .count(),
+        1
+    );
+}
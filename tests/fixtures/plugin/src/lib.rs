@@ -0,0 +1,35 @@
+use std::time::Duration;
+use tool_calling::tool;
+
+#[tool]
+/// Echoes a word back
+pub fn echo(word: String) -> String {
+    format!("plugin says {}", word)
+}
+
+#[tool]
+/// Answers after a delay
+pub async fn slow(ms: u64) -> String {
+    // A thread rather than a timer: the plugin has its own copy of tokio, outside the
+    // host's runtime
+    let (done, wait) = futures::channel::oneshot::channel();
+    let sleeper = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(ms));
+        let _ = done.send(());
+    });
+    let _ = wait.await;
+    // The thread runs the plugin's code until it exits, so the call must outlast it
+    let _ = sleeper.join();
+    format!("waited {}ms", ms)
+}
+
+#[cfg(not(feature = "mismatch"))]
+tool_calling::export_plugin!();
+
+#[cfg(feature = "mismatch")]
+#[no_mangle]
+pub static TOOL_CALLING_PLUGIN: tool_calling::plugin::PluginDeclaration =
+    tool_calling::plugin::PluginDeclaration {
+        core_version: "0.0.0",
+        register: |registrar| registrar.register_macro_tools(),
+    };
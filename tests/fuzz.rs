@@ -0,0 +1,139 @@
+use serde_json::json;
+use tool_calling::fuzz::{FindingKind, Fuzzer};
+use tool_calling::{tool, Coercion, Tool, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Shouts a message
+pub fn shout(message: String, times: Option<i64>, loud: Option<bool>) -> String {
+    let times = times.unwrap_or(1).clamp(0, 3) as usize;
+    let message = if loud.unwrap_or(true) {
+        message.to_uppercase()
+    } else {
+        message
+    };
+    message.repeat(times)
+}
+
+#[tool]
+/// Sets the volume
+pub fn set_volume(level: u8) -> String {
+    format!("volume {}", level)
+}
+
+#[tool]
+/// Divides two integers
+pub fn divide(a: i32, b: i32) -> String {
+    (a / b).to_string()
+}
+
+#[tool]
+/// Counts the words in a list
+pub fn count_words(words: Vec<String>) -> String {
+    words.len().to_string()
+}
+
+// Tests
+
+#[tokio::test]
+async fn well_behaved_tools_are_clean() {
+    let report = Fuzzer::new()
+        .with_tools(["shout", "count_words"])
+        .with_cases(100)
+        .run(&ToolHandler::default())
+        .await;
+    assert!(report.is_clean(), "{:#?}", report.findings);
+    assert_eq!(report.valid_cases, 200);
+    assert_eq!(report.invalid_cases, 200);
+}
+
+#[tokio::test]
+async fn finds_arguments_the_tool_cannot_parse() {
+    let report = Fuzzer::new()
+        .with_tools(["set_volume"])
+        .run(&ToolHandler::default())
+        .await;
+    let finding = report
+        .findings_for("set_volume")
+        .find(|finding| finding.kind == FindingKind::Unparseable)
+        .expect("an out of range level");
+    let level = finding.arguments["level"].as_i64().unwrap();
+    assert!(!(0..=255).contains(&level), "{}", finding);
+    assert!(finding
+        .to_string()
+        .starts_with("set_volume: validated arguments not parsed for"));
+}
+
+#[tokio::test]
+async fn finds_panics() {
+    let report = Fuzzer::new()
+        .with_tools(["divide"])
+        .with_cases(200)
+        .run(&ToolHandler::default())
+        .await;
+    let panics = report
+        .findings_for("divide")
+        .filter(|finding| finding.kind == FindingKind::Panic)
+        .collect::<Vec<_>>();
+    assert!(!panics.is_empty(), "{:#?}", report.findings);
+    assert!(panics
+        .iter()
+        .any(|finding| finding.arguments["b"] == 0 && finding.message.contains("divide by zero")));
+}
+
+#[tokio::test]
+async fn finds_invalid_arguments_that_pass_validation() {
+    let handler = ToolHandler::default().with_coercion(Coercion::lenient());
+    let report = Fuzzer::new()
+        .with_tools(["shout"])
+        .with_cases(200)
+        .run(&handler)
+        .await;
+    // Coercion accepts quoted numbers and booleans
+    assert!(report
+        .findings
+        .iter()
+        .all(|finding| finding.kind == FindingKind::AcceptedInvalid));
+    assert!(!report.is_clean());
+}
+
+#[tokio::test]
+async fn generates_arguments_within_the_schema() {
+    let booking = Tool::new(
+        "book",
+        "Books seats",
+        json!({
+            "type": "object",
+            "properties": {
+                "seats": { "type": "integer", "minimum": 1, "maximum": 8 },
+                "price": { "type": "number", "exclusiveMinimum": 0, "maximum": 500 },
+                "code": { "type": "string", "minLength": 3, "maxLength": 5 },
+                "class": { "enum": ["economy", "business"] },
+                "names": { "type": "array", "items": { "type": "string" }, "minItems": 1, "maxItems": 3 },
+                "note": { "type": ["string", "null"] },
+                "extras": { "type": "object", "additionalProperties": { "type": "boolean" } }
+            },
+            "required": ["seats", "code", "class", "names"]
+        }),
+        ToolFn::Async(Box::new(|_| Box::pin(async { Ok("booked".to_string()) }))),
+    );
+    let handler = ToolHandler::empty().with_tool(booking);
+    let report = Fuzzer::new().with_cases(300).run(&handler).await;
+    assert!(report.is_clean(), "{:#?}", report.findings);
+    assert_eq!(report.invalid_cases, 300);
+}
+
+#[tokio::test]
+async fn runs_are_repeatable() {
+    let fuzzer = Fuzzer::new().with_tools(["set_volume"]).with_seed(7);
+    let first = fuzzer.run(&ToolHandler::default()).await;
+    let second = fuzzer.run(&ToolHandler::default()).await;
+    assert_eq!(first, second);
+    let other = Fuzzer::new()
+        .with_tools(["set_volume"])
+        .with_seed(8)
+        .run(&ToolHandler::default())
+        .await;
+    assert_ne!(first.findings, other.findings);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::order_tool;
+use serde_json::json;
+use tool_calling::gemini::{function_response, parse_function_call};
+use tool_calling::{tool, SchemaFlavor, ToolContext, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up a word in the dictionary
+pub fn define(word: String) -> String {
+    format!("{}: a word", word)
+}
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    (a / b).to_string()
+}
+
+// Tests
+
+#[test]
+fn declarations_use_the_openapi_subset() {
+    assert_eq!(
+        order_tool().render_schema(SchemaFlavor::Gemini)["parameters"],
+        json!({
+            "type": "OBJECT",
+            "properties": {
+                "size": {
+                    "type": "STRING",
+                    "description": "Cup size",
+                    "enum": ["small", "large"],
+                    "format": "enum",
+                    "nullable": true
+                },
+                "shots": { "type": "INTEGER" },
+                "extras": { "type": "ARRAY", "items": { "type": "STRING" } },
+                "price": { "type": "NUMBER", "nullable": true },
+                "options": { "type": "OBJECT" }
+            },
+            "required": ["shots"]
+        })
+    );
+
+    let handler = ToolHandler::default();
+    let tools = handler.gemini_tools();
+    assert_eq!(tools.as_array().unwrap().len(), 1);
+    let define = tools[0]["functionDeclarations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|decl| decl["name"] == "define")
+        .unwrap();
+    assert_eq!(define["description"], "Look up a word in the dictionary");
+    assert_eq!(define["parameters"]["properties"]["word"]["type"], "STRING");
+}
+
+#[tokio::test]
+async fn function_calls_are_answered_with_function_responses() {
+    let handler = ToolHandler::default().with_error_mapper(|err: &ToolError| err.kind().into());
+    let parts = json!([
+        { "text": "Checking." },
+        { "functionCall": { "name": "define", "args": { "word": "cat" } } },
+        { "functionCall": { "id": "call_2", "name": "divide", "args": { "a": 1, "b": 0 } } },
+        { "functionCall": { "name": "missing" } }
+    ]);
+    let responses = handler
+        .answer_function_calls(&parts, &ToolContext::default())
+        .await;
+    assert_eq!(
+        responses,
+        vec![
+            function_response("define", None, json!({ "output": "cat: a word" })),
+            json!({ "functionResponse": { "name": "divide", "response": { "error": "execution" }, "id": "call_2" } }),
+            function_response("missing", None, json!({ "error": "not_found" })),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn function_call_parts_parse_into_calls() {
+    let part =
+        json!({ "functionCall": { "id": "call_9", "name": "define", "args": { "word": "dog" } } });
+    let call = parse_function_call(&part).unwrap();
+    assert_eq!(call["function"]["name"], "define");
+    assert_eq!(call["id"], "call_9");
+    assert_eq!(parse_function_call(&part["functionCall"]).unwrap(), call);
+
+    let handler = ToolHandler::default();
+    let res = handler
+        .call_function_call(&part, &ToolContext::default())
+        .await
+        .unwrap();
+    assert_eq!(res.id.as_deref(), Some("call_9"));
+    assert_eq!(res.output, "dog: a word");
+
+    let err = parse_function_call(&json!({ "text": "hi" })).unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use common::schema_tool;
+use serde_json::{json, Value};
+use tool_calling::{tool, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up the weather
+pub fn get_weather(city: String, days: Option<u32>, unit: Option<String>) -> String {
+    format!("{} {:?} {:?}", city, days, unit)
+}
+
+#[tool]
+pub fn now() -> String {
+    "noon".to_string()
+}
+
+fn rules(grammar: &str) -> Vec<&str> {
+    grammar.lines().collect()
+}
+
+// Tests
+
+#[test]
+fn grammar_lists_a_call_rule_per_tool() {
+    let grammar = ToolHandler::default().tool_call_grammar();
+    let rules = rules(&grammar);
+    assert_eq!(rules[0], "root ::= get-weather-call | now-call");
+    assert!(rules.contains(
+        &r#"get-weather-call ::= "{" space "\"name\"" space ":" space "\"get_weather\"" space "," space "\"arguments\"" space ":" space get-weather-args "}" space"#
+    ));
+    // Optional properties can each be left out
+    assert!(rules.contains(
+        &r#"get-weather-args ::= "{" space "\"city\"" space ":" space string ("," space "\"days\"" space ":" space (integer | null) get-weather-args-2 | get-weather-args-2) "}" space"#
+    ));
+    assert!(rules.contains(
+        &r#"get-weather-args-2 ::= ("," space "\"unit\"" space ":" space (string | null))?"#
+    ));
+    assert!(rules.contains(
+        &r#"now-call ::= "{" space "\"name\"" space ":" space "\"now\"" space "," space "\"arguments\"" space ":" space "{" space "}" space "}" space"#
+    ));
+    for primitive in [
+        "space", "string", "integer", "number", "boolean", "null", "value",
+    ] {
+        assert!(
+            grammar.contains(&format!("\n{} ::= ", primitive)),
+            "{}",
+            primitive
+        );
+    }
+}
+
+#[test]
+fn nested_schemas_get_their_own_rules() {
+    // Drinks are defined once and referenced
+    let order = schema_tool(
+        "order",
+        "Orders drinks",
+        json!({
+            "type": "object",
+            "properties": {
+                "drinks": { "type": "array", "items": { "$ref": "#/definitions/drink" } },
+                "note": { "type": "string" }
+            },
+            "required": ["drinks"],
+            "definitions": {
+                "drink": {
+                    "type": "object",
+                    "properties": {
+                        "size": { "enum": ["small", "large"] },
+                        "shots": { "type": "integer" }
+                    },
+                    "required": ["size"]
+                }
+            }
+        }),
+    );
+    let handler = ToolHandler::empty().with_tool(order);
+    let grammar = handler.tool_call_grammar();
+    let rules = rules(&grammar);
+    assert!(rules.contains(
+        &r#"order-args-drinks ::= "[" space (order-args-drinks-item ("," space order-args-drinks-item)*)? "]" space"#
+    ));
+    assert!(rules.contains(
+        &r#"order-args-drinks-item ::= "{" space "\"size\"" space ":" space ("\"small\"" space | "\"large\"" space) ("," space "\"shots\"" space ":" space integer)? "}" space"#
+    ));
+    assert!(rules.contains(
+        &r#"order-args ::= "{" space "\"drinks\"" space ":" space order-args-drinks ("," space "\"note\"" space ":" space string)? "}" space"#
+    ));
+
+    // Optional properties first: any subset may be written, in order
+    let tool = schema_tool(
+        "opt",
+        "",
+        json!({
+            "type": "object",
+            "properties": { "a": { "type": "boolean" }, "b": { "type": "boolean" } },
+            "required": []
+        }),
+    );
+    let grammar = ToolHandler::empty().with_tool(tool).tool_call_grammar();
+    let rules = self::rules(&grammar);
+    assert!(rules.contains(
+        &r#"opt-args ::= "{" space ("\"a\"" space ":" space boolean opt-args-1 | ("\"b\"" space ":" space boolean)?) "}" space"#
+    ));
+    assert!(rules.contains(&r#"opt-args-1 ::= ("," space "\"b\"" space ":" space boolean)?"#));
+}
+
+#[test]
+fn combined_schema_accepts_only_registered_calls() {
+    let handler = ToolHandler::default();
+    let schema = handler.tool_call_schema();
+    assert_eq!(schema["oneOf"].as_array().unwrap().len(), 2);
+    let compiled = jsonschema::JSONSchema::compile(&schema).unwrap();
+    let valid = json!({ "name": "get_weather", "arguments": { "city": "Oslo", "days": 2 } });
+    assert!(compiled.is_valid(&valid));
+    for invalid in [
+        json!({ "name": "get_weather", "arguments": { "days": 2 } }),
+        json!({ "name": "missing", "arguments": {} }),
+        json!({ "name": "now" }),
+        Value::Null,
+    ] {
+        assert!(!compiled.is_valid(&invalid), "{}", invalid);
+    }
+}
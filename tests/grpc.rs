@@ -0,0 +1,116 @@
+#![cfg(feature = "grpc")]
+
+use futures::StreamExt;
+use serde_json::{json, Value};
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::{Channel, Server};
+use tonic::Code;
+use tool_calling::grpc::proto::tool_registry_client::ToolRegistryClient;
+use tool_calling::grpc::proto::{CallToolRequest, ListToolsRequest};
+use tool_calling::grpc::ToolRegistryService;
+use tool_calling::{tool, ToolContext, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Adds two integers
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+#[tool]
+pub fn whoami(#[param(context)] ctx: ToolContext) -> String {
+    ctx.user_id.clone().unwrap_or_default()
+}
+
+#[tool(stream)]
+pub fn count_to(n: u32) -> impl futures::Stream<Item = Result<String, ToolError>> {
+    futures::stream::iter((1..=n).map(|i| {
+        if i < 3 {
+            Ok(i.to_string())
+        } else {
+            Err(ToolError::Execution("too far".to_string()))
+        }
+    }))
+}
+
+async fn client() -> ToolRegistryClient<Channel> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let service = ToolRegistryService::new(ToolHandler::default());
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(service.into_server())
+            .serve_with_incoming(TcpIncoming::from(listener))
+            .await
+    });
+    ToolRegistryClient::connect(format!("http://{}", addr))
+        .await
+        .unwrap()
+}
+
+fn call(name: &str, arguments: Value) -> CallToolRequest {
+    CallToolRequest {
+        name: name.to_string(),
+        arguments_json: arguments.to_string(),
+        ..Default::default()
+    }
+}
+
+// Tests
+
+#[tokio::test]
+async fn lists_and_calls_tools() {
+    let mut client = client().await;
+    let tools = client
+        .list_tools(ListToolsRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .tools;
+    let add = tools.iter().find(|tool| tool.name == "add").unwrap();
+    assert_eq!(add.description, "Adds two integers");
+    let schema: Value = serde_json::from_str(&add.parameters_json).unwrap();
+    assert_eq!(schema["required"], json!(["a", "b"]));
+
+    let mut request = call("add", json!({ "a": 2, "b": 3 }));
+    request.id = "call_1".to_string();
+    let response = client.call_tool(request).await.unwrap().into_inner();
+    assert_eq!(response.output, "5");
+    assert_eq!(response.id, "call_1");
+
+    let mut request = call("whoami", json!({}));
+    request.user_id = "ada".to_string();
+    let response = client.call_tool(request).await.unwrap().into_inner();
+    assert_eq!(response.output, "ada");
+}
+
+#[tokio::test]
+async fn errors_map_to_status_codes() {
+    let mut client = client().await;
+    let missing = client.call_tool(call("missing", json!({}))).await;
+    assert_eq!(missing.unwrap_err().code(), Code::NotFound);
+
+    let bad_args = client.call_tool(call("add", json!({ "a": 1 }))).await;
+    assert_eq!(bad_args.unwrap_err().code(), Code::InvalidArgument);
+
+    let mut unparsable = call("add", json!({}));
+    unparsable.arguments_json = "{nope".to_string();
+    let status = client.call_tool(unparsable).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn streams_tool_output() {
+    let mut client = client().await;
+    let mut stream = client
+        .call_tool_stream(call("count_to", json!({ "n": 5 })))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(stream.next().await.unwrap().unwrap().chunk, "1");
+    assert_eq!(stream.next().await.unwrap().unwrap().chunk, "2");
+    let status = stream.next().await.unwrap().unwrap_err();
+    assert_eq!(status.code(), Code::Internal);
+    assert!(status.message().contains("too far"));
+}
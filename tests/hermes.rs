@@ -0,0 +1,85 @@
+use serde_json::json;
+use tool_calling::compat::normalize_message;
+use tool_calling::hermes::{extract_tool_calls, tool_response};
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up the weather
+pub fn weather(city: String) -> String {
+    format!("sunny in {}", city)
+}
+
+#[tool]
+pub fn now() -> String {
+    "noon".to_string()
+}
+
+// Tests
+
+#[tokio::test]
+async fn blocks_are_extracted_and_text_is_kept() {
+    let handler = ToolHandler::default();
+    let reply = "I'll check both.\n<tool_call>\n{\"name\": \"weather\", \"arguments\": {\"city\": \"Rome\"}}\n</tool_call>\nOne moment.\n<tool_call>{\"name\": \"now\"}</tool_call>";
+    let extracted = extract_tool_calls(reply).unwrap();
+    assert_eq!(extracted.text, "I'll check both.\n\nOne moment.");
+    assert_eq!(
+        extracted.tool_calls,
+        vec![
+            json!({ "type": "function", "function": { "name": "weather", "arguments": { "city": "Rome" } } }),
+            json!({ "type": "function", "function": { "name": "now", "arguments": {} } }),
+        ]
+    );
+    let outputs = futures::future::join_all(
+        extracted
+            .tool_calls
+            .iter()
+            .map(|call| handler.call_tool(call)),
+    )
+    .await;
+    assert_eq!(outputs[0].as_ref().unwrap().output, "sunny in Rome");
+    assert_eq!(outputs[1].as_ref().unwrap().output, "noon");
+
+    let plain = extract_tool_calls("  Just text.  ").unwrap();
+    assert!(plain.tool_calls.is_empty());
+    assert_eq!(plain.text, "Just text.");
+}
+
+#[test]
+fn unclosed_and_malformed_blocks() {
+    let extracted =
+        extract_tool_calls("<tool_call>{\"name\": \"now\", \"arguments\": {}}").unwrap();
+    assert_eq!(extracted.tool_calls.len(), 1);
+    assert_eq!(extracted.text, "");
+
+    let err = extract_tool_calls("<tool_call>{\"name\": \"now\",</tool_call>").unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+    let err = extract_tool_calls("<tool_call>{\"arguments\": {}}</tool_call>").unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+
+    assert_eq!(
+        tool_response("now", "noon"),
+        "<tool_response>\n{\"name\":\"now\",\"content\":\"noon\"}\n</tool_response>"
+    );
+}
+
+#[tokio::test]
+async fn tolerant_parsing_reads_blocks() {
+    let message = json!({
+        "role": "assistant",
+        "content": "Checking.<tool_call>{\"name\": \"weather\", \"arguments\": {\"city\": \"Oslo\"}}</tool_call>"
+    });
+    let normalized = normalize_message(&message);
+    assert_eq!(normalized["content"], "Checking.");
+    assert_eq!(normalized["tool_calls"][0]["id"], "call_0");
+
+    let handler = ToolHandler::default().with_tolerant_parsing();
+    let answers = handler
+        .answer_tool_calls(&message, &Default::default())
+        .await;
+    assert_eq!(
+        answers,
+        vec![json!({ "role": "tool", "tool_call_id": "call_0", "content": "sunny in Oslo" })]
+    );
+}
@@ -0,0 +1,89 @@
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tool_calling::{tool, Inject, ToolContext, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+// Stands in for a connection pool shared by tools
+#[derive(Default)]
+pub struct Counter(AtomicUsize);
+
+#[derive(Clone)]
+pub struct Prefix(String);
+
+// Resolved by cloning the shared prefix out of the handler's resources
+impl Inject for Prefix {
+    fn inject(context: &ToolContext) -> Option<Self> {
+        context.resource::<Prefix>().map(|prefix| (*prefix).clone())
+    }
+}
+
+#[tool]
+/// Count a visit
+pub fn visit(#[inject] counter: Arc<Counter>, page: String) -> String {
+    let n = counter.0.fetch_add(1, Ordering::SeqCst) + 1;
+    format!("{} visit #{}", page, n)
+}
+
+#[tool]
+pub async fn label(text: String, #[inject] prefix: Prefix) -> String {
+    format!("{}{}", prefix.0, text)
+}
+
+// Tests
+
+#[test]
+fn injected_params_are_left_out_of_the_schema() {
+    let handler = ToolHandler::default();
+    let schema = &handler.get_tool("visit").unwrap().parameter_schema;
+    assert_eq!(
+        schema["properties"],
+        json!({ "page": { "type": "string" } })
+    );
+    assert_eq!(schema["required"], json!(["page"]));
+}
+
+#[tokio::test]
+async fn provided_resources_are_shared_between_calls() {
+    let counter = Arc::new(Counter::default());
+    let mut handler = ToolHandler::default();
+    handler.provide_shared(counter.clone());
+
+    handler
+        .call_with_args("visit", &["home".into()])
+        .await
+        .unwrap();
+    let call = json!({
+        "type": "function",
+        "function": { "name": "visit", "arguments": { "page": "about" } }
+    });
+    let res = handler.call_tool(&call).await.unwrap();
+    assert_eq!(res.output, "about visit #2");
+    assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn custom_inject_impls_are_resolved() {
+    let mut handler = ToolHandler::default();
+    handler.provide(Prefix("> ".into()));
+    let res = handler
+        .call_with_args("label", &["hi".into()])
+        .await
+        .unwrap();
+    assert_eq!(res, "> hi");
+}
+
+#[tokio::test]
+async fn missing_resources_fail_the_call() {
+    let handler = ToolHandler::default();
+    let err = handler
+        .call_with_args("visit", &["home".into()])
+        .await
+        .unwrap_err();
+    let ToolError::Execution(msg) = err else {
+        panic!("expected Execution, got {:?}", err);
+    };
+    assert!(msg.contains("Counter"), "{}", msg);
+    assert!(msg.contains("parameter 'counter'"), "{}", msg);
+}
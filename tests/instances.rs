@@ -0,0 +1,124 @@
+use serde_json::json;
+use std::sync::Arc;
+use tool_calling::{tool, wrap_sync, Tool, ToolError, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+fn constant(name: &str, output: &'static str) -> Tool {
+    Tool::new(
+        name,
+        "Returns a constant",
+        json!({ "type": "object", "properties": {}, "required": [] }),
+        ToolFn::Async(wrap_sync(Arc::new(move |_: &[serde_json::Value]| {
+            Ok(output.to_string())
+        }))),
+    )
+}
+
+// Tests
+
+#[tokio::test]
+async fn empty_handler_has_no_tools() {
+    let handler = ToolHandler::empty();
+    assert_eq!(handler.tools().count(), 0);
+    let err = handler.call_with_args("add", &[]).await.unwrap_err();
+    assert_eq!(err, ToolError::NotFound("add".into()));
+}
+
+#[tokio::test]
+async fn runtime_registration() {
+    let mut handler = ToolHandler::default();
+    assert!(handler.register(constant("pi", "3.14")).is_none());
+    assert_eq!(handler.call_with_args("pi", &[]).await, Ok("3.14".into()));
+    // Re-registering replaces the existing tool
+    let previous = handler.register(constant("pi", "3.14159")).unwrap();
+    assert_eq!(previous.name, "pi");
+    assert_eq!(
+        handler.call_with_args("pi", &[]).await,
+        Ok("3.14159".into())
+    );
+    assert!(handler.unregister("pi").is_some());
+    assert!(handler.get_tool("pi").is_none());
+}
+
+#[tokio::test]
+async fn handlers_are_isolated() {
+    let base = ToolHandler::default();
+    let mut restricted = base.clone();
+    restricted.retain(|tool| tool.name != "add");
+    let extended = base.clone().with_tool(constant("extra", "x"));
+
+    assert!(base.get_tool("add").is_some());
+    assert!(base.get_tool("extra").is_none());
+    assert!(restricted.get_tool("add").is_none());
+    assert!(extended.get_tool("extra").is_some());
+    assert_eq!(
+        extended
+            .call_with_args("add", &["1".into(), "1".into()])
+            .await,
+        Ok("2".into())
+    );
+}
+
+#[tokio::test]
+async fn tools_sorted_by_name() {
+    let handler = ToolHandler::empty()
+        .with_tool(constant("zeta", "z"))
+        .with_tool(constant("alpha", "a"))
+        .with_tool(constant("mid", "m"));
+    let names: Vec<_> = handler.tools().map(|t| t.name.clone()).collect();
+    assert_eq!(names, vec!["alpha", "mid", "zeta"]);
+    let schema_names: Vec<_> = handler
+        .all_tools_schema()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["function"]["name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(schema_names, names);
+}
+
+#[tokio::test]
+async fn custom_ordering_applies_to_later_registrations() {
+    let handler = ToolHandler::empty()
+        .with_tool(constant("bb", "b"))
+        .with_ordering(|a, b| a.name.len().cmp(&b.name.len()).then(b.name.cmp(&a.name)))
+        .with_tool(constant("a", "a"))
+        .with_tool(constant("ccc", "c"))
+        .with_tool(constant("zz", "z"));
+    let names: Vec<_> = handler.tools().map(|t| t.name.clone()).collect();
+    assert_eq!(names, vec!["a", "zz", "bb", "ccc"]);
+}
+
+#[tokio::test]
+async fn lookups_follow_every_change_to_the_tool_list() {
+    let mut handler = ToolHandler::empty()
+        .with_tool(constant("c", "3"))
+        .with_tool(constant("a", "1"))
+        .with_tool(constant("b", "2"));
+    assert_eq!(handler.call_with_args("c", &[]).await, Ok("3".into()));
+
+    // Replacing keeps one tool per name
+    handler.register(constant("a", "one"));
+    assert_eq!(handler.tools().count(), 3);
+    assert_eq!(handler.call_with_args("a", &[]).await, Ok("one".into()));
+
+    // Removing and filtering shift the remaining tools
+    handler.unregister("a");
+    assert!(handler.get_tool("a").is_none());
+    assert_eq!(handler.call_with_args("b", &[]).await, Ok("2".into()));
+    handler.retain(|tool| tool.name != "b");
+    assert_eq!(handler.call_with_args("c", &[]).await, Ok("3".into()));
+
+    // Reordering moves them too
+    let handler = handler
+        .with_tool(constant("d", "4"))
+        .with_ordering(|a, b| b.name.cmp(&a.name));
+    assert_eq!(handler.get_tool("c").unwrap().name, "c");
+    assert_eq!(handler.call_with_args("d", &[]).await, Ok("4".into()));
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{call, call_with_id};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tool_calling::jobs::{JobId, JobStatus};
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub async fn wait(millis: u64) -> String {
+    tokio::time::sleep(Duration::from_millis(millis)).await;
+    format!("waited {}ms", millis)
+}
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    // Panics, and so fails the job, when `b` is zero
+    (a / b).to_string()
+}
+
+// Tests
+
+#[tokio::test]
+async fn jobs_report_running_then_the_result() {
+    let handler = ToolHandler::default();
+    let id = handler
+        .start_job(&call_with_id("call_9", "wait", json!({ "millis": 50 })))
+        .unwrap();
+    assert_eq!(id.to_string(), "job-1");
+
+    let running = handler.job(id).unwrap();
+    assert_eq!(running.status, JobStatus::Running);
+    let placeholder = running.to_result();
+    assert_eq!(placeholder.name, "wait");
+    assert_eq!(placeholder.id.as_deref(), Some("call_9"));
+    let output: Value = serde_json::from_str(&placeholder.output).unwrap();
+    assert_eq!(output["job_id"], "job-1");
+    assert_eq!(output["status"], "running");
+
+    let done = handler.wait_job(id).await.unwrap();
+    assert_eq!(done.to_result().output, "waited 50ms");
+    assert_eq!(handler.jobs().len(), 1);
+    assert!(handler.remove_job(id).is_some());
+    assert!(handler.job(id).is_none());
+}
+
+#[tokio::test]
+async fn invalid_calls_are_rejected_before_starting() {
+    let handler = ToolHandler::default();
+    assert_eq!(
+        handler.start_job(&call("nope", json!({}))),
+        Err(ToolError::NotFound("nope".into()))
+    );
+    assert!(matches!(
+        handler.start_job(&call("wait", json!({ "millis": "soon" }))),
+        Err(ToolError::BadArgs(_))
+    ));
+    assert!(handler.jobs().is_empty());
+}
+
+#[tokio::test]
+async fn failed_jobs_keep_their_error() {
+    let handler = ToolHandler::default();
+    let id = handler
+        .start_job(&call("divide", json!({ "a": 1, "b": 0 })))
+        .unwrap();
+    let job = handler.wait_job(id).await.unwrap();
+    assert!(matches!(
+        job.status,
+        JobStatus::Failed(ToolError::Execution(_))
+    ));
+    let output: Value = serde_json::from_str(&job.to_result().output).unwrap();
+    assert_eq!(output["status"], "failed");
+}
+
+#[tokio::test]
+async fn jobs_can_be_cancelled() {
+    let handler = ToolHandler::default();
+    let id = handler
+        .start_job(&call("wait", json!({ "millis": 10_000 })))
+        .unwrap();
+    assert!(handler.cancel_job(id));
+    assert!(!handler.cancel_job(id));
+    let job = tokio::time::timeout(Duration::from_secs(1), handler.wait_job(id))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(job.status, JobStatus::Cancelled);
+    assert!(!handler.cancel_job(JobId(99)));
+}
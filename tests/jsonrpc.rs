@@ -0,0 +1,121 @@
+use serde_json::{json, Value};
+use tool_calling::jsonrpc::{JsonRpcServer, EXECUTION_ERROR};
+use tool_calling::{tool, ToolContext, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn concat(first: String, second: String) -> String {
+    format!("{}{}", first, second)
+}
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    (a / b).to_string()
+}
+
+#[tool]
+pub fn whoami(#[param(context)] ctx: ToolContext) -> String {
+    ctx.user_id.clone().unwrap_or_default()
+}
+
+// Tests
+
+#[tokio::test]
+async fn named_and_positional_params() {
+    let server = JsonRpcServer::new(ToolHandler::default());
+    let named = json!({ "jsonrpc": "2.0", "id": "a", "method": "concat", "params": { "first": "x", "second": "y" } });
+    assert_eq!(
+        server.handle_message(&named).await,
+        Some(json!({ "jsonrpc": "2.0", "id": "a", "result": "xy" }))
+    );
+
+    let positional = json!({ "jsonrpc": "2.0", "id": 2, "method": "concat", "params": ["p", "q"] });
+    assert_eq!(
+        server.handle_message(&positional).await.unwrap()["result"],
+        "pq"
+    );
+
+    let surplus =
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "concat", "params": ["1", "2", "3"] });
+    assert_eq!(
+        server.handle_message(&surplus).await.unwrap()["error"]["code"],
+        -32602
+    );
+}
+
+#[tokio::test]
+async fn failures_map_to_error_codes() {
+    let server = JsonRpcServer::new(ToolHandler::default());
+    let cases = [
+        (
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "missing" }),
+            -32601,
+            "not_found",
+        ),
+        (
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "divide", "params": { "a": 1 } }),
+            -32602,
+            "bad_args",
+        ),
+        (
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "divide", "params": { "a": 1, "b": 0 } }),
+            EXECUTION_ERROR,
+            "execution",
+        ),
+    ];
+    for (request, code, kind) in cases {
+        let response = server.handle_message(&request).await.unwrap();
+        assert_eq!(response["id"], request["id"]);
+        assert_eq!(response["error"]["code"], code);
+        assert_eq!(response["error"]["data"]["kind"], kind);
+    }
+
+    let invalid = server
+        .handle_message(&json!({ "id": 4, "method": "divide" }))
+        .await;
+    assert_eq!(invalid.unwrap()["error"]["code"], -32600);
+    let unparsable = server.handle_str("{oops").await.unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(&unparsable).unwrap()["error"]["code"],
+        -32700
+    );
+}
+
+#[tokio::test]
+async fn batches_and_notifications() {
+    let server = JsonRpcServer::new(ToolHandler::default()).with_context(|request| {
+        match request["user"].as_str() {
+            Some(user) => ToolContext::new().with_user_id(user),
+            None => ToolContext::new(),
+        }
+    });
+    let notification = json!({ "jsonrpc": "2.0", "method": "concat", "params": ["a", "b"] });
+    assert_eq!(server.handle_message(&notification).await, None);
+    assert_eq!(server.handle_message(&json!([notification])).await, None);
+
+    let batch = json!([
+        { "jsonrpc": "2.0", "id": 1, "method": "concat", "params": ["a", "b"] },
+        notification,
+        { "jsonrpc": "2.0", "id": 2, "method": "whoami", "user": "ada" }
+    ]);
+    assert_eq!(
+        server.handle_message(&batch).await,
+        Some(json!([
+            { "jsonrpc": "2.0", "id": 1, "result": "ab" },
+            { "jsonrpc": "2.0", "id": 2, "result": "ada" }
+        ]))
+    );
+
+    let input = [
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "concat", "params": ["1", "2"] }).to_string(),
+        json!({ "jsonrpc": "2.0", "method": "concat", "params": ["3", "4"] }).to_string(),
+    ]
+    .join("\n");
+    let mut output = Vec::new();
+    server.serve(input.as_bytes(), &mut output).await.unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":\"12\"}\n"
+    );
+}
@@ -0,0 +1,49 @@
+use serde_json::json;
+use tool_calling::{tool, SchemaFlavor, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Get user info from database
+pub fn get_user_info(user_id: u32, include_email: Option<bool>) -> String {
+    format!("user {} {:?}", user_id, include_email)
+}
+
+// Tests
+
+#[test]
+fn tools_carry_an_args_schema_with_titles() {
+    let handler = ToolHandler::default();
+    let tool = handler.get_tool("get_user_info").unwrap();
+    let rendered = tool.render_schema(SchemaFlavor::LangChain);
+    assert_eq!(rendered["name"], "get_user_info");
+    assert_eq!(rendered["description"], "Get user info from database");
+
+    let args = &rendered["args_schema"];
+    assert_eq!(args["title"], "get_user_info");
+    assert_eq!(args["type"], "object");
+    assert_eq!(args["required"], json!(["user_id"]));
+    assert_eq!(args["properties"]["user_id"]["title"], "User Id");
+    assert_eq!(
+        args["properties"]["include_email"]["title"],
+        "Include Email"
+    );
+    assert_eq!(
+        args["properties"]["user_id"]["type"],
+        tool.render_schema(SchemaFlavor::OpenAi)["function"]["parameters"]["properties"]["user_id"]
+            ["type"]
+    );
+
+    assert_eq!(handler.langchain_tools(), json!([rendered]));
+}
+
+#[test]
+fn tools_are_written_as_json() {
+    let handler = ToolHandler::default();
+    let path = std::env::temp_dir().join(format!("langchain_tools_{}.json", std::process::id()));
+    handler.write_langchain_tools(&path).unwrap();
+    let written: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(written, handler.langchain_tools());
+}
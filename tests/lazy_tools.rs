@@ -0,0 +1,58 @@
+use linkme::distributed_slice;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tool_calling::{tool, Tool, ToolFactory, ToolFn, ToolHandler, TOOL_FACTORIES};
+
+// Define necessary tools for these tests
+
+#[tool]
+fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+// A registry tool that counts how often it is built
+static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+#[distributed_slice(TOOL_FACTORIES)]
+static COUNTED: ToolFactory = ToolFactory {
+    name: "counted",
+    build: build_counted,
+};
+
+fn build_counted() -> Tool {
+    BUILDS.fetch_add(1, Ordering::SeqCst);
+    Tool::new(
+        "counted",
+        "Counts its builds",
+        json!({ "type": "object", "properties": {} }),
+        ToolFn::Async(Box::new(|_| Box::pin(async { Ok("built".to_string()) }))),
+    )
+}
+
+// Tests
+
+// One test drives the counter so parallel tests cannot disturb it
+#[tokio::test]
+async fn registry_tools_are_built_on_first_lookup() {
+    let handler = ToolHandler::default();
+    assert_eq!(
+        handler
+            .call_with_args("add", &["1".into(), "2".into()])
+            .await,
+        Ok("3".into())
+    );
+    let mut other = ToolHandler::default();
+    assert!(other.unregister("add").is_some());
+    assert_eq!(BUILDS.load(Ordering::SeqCst), 0);
+
+    assert_eq!(
+        handler.get_tool("counted").unwrap().description,
+        "Counts its builds"
+    );
+    assert_eq!(BUILDS.load(Ordering::SeqCst), 1);
+
+    // Listing builds the rest, and every handler shares the one build
+    assert_eq!(other.tools().count(), 1);
+    assert!(ToolHandler::default().get_tool("counted").is_some());
+    assert_eq!(BUILDS.load(Ordering::SeqCst), 1);
+}
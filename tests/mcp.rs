@@ -0,0 +1,213 @@
+use serde_json::{json, Value};
+use tool_calling::mcp::{McpClient, McpServer, PROTOCOL_VERSION};
+use tool_calling::{tool, ToolContext, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Adds two integers
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    (a / b).to_string()
+}
+
+#[tool]
+pub fn whoami(#[param(context)] ctx: ToolContext) -> String {
+    ctx.user_id.clone().unwrap_or_default()
+}
+
+fn request(id: i64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+// Tests
+
+#[tokio::test]
+async fn initialize_and_list_tools() {
+    let server = McpServer::new(ToolHandler::default())
+        .with_server_info("test", "0.0.1")
+        .with_instructions("Use add for sums");
+
+    let init = server
+        .handle_message(&request(
+            1,
+            "initialize",
+            json!({ "protocolVersion": "2024-11-05" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(init["id"], 1);
+    assert_eq!(init["result"]["protocolVersion"], "2024-11-05");
+    assert_eq!(
+        init["result"]["serverInfo"],
+        json!({ "name": "test", "version": "0.0.1" })
+    );
+    assert_eq!(init["result"]["instructions"], "Use add for sums");
+    assert!(init["result"]["capabilities"]["tools"].is_object());
+
+    let newer = server
+        .handle_message(&request(
+            2,
+            "initialize",
+            json!({ "protocolVersion": "2099-01-01" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(newer["result"]["protocolVersion"], PROTOCOL_VERSION);
+
+    let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+    assert_eq!(server.handle_message(&initialized).await, None);
+
+    let list = server
+        .handle_message(&request(3, "tools/list", json!({})))
+        .await
+        .unwrap();
+    let add = list["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|tool| tool["name"] == "add")
+        .unwrap();
+    assert_eq!(add["description"], "Adds two integers");
+    assert_eq!(add["inputSchema"]["required"], json!(["a", "b"]));
+}
+
+#[tokio::test]
+async fn tools_call_reports_outputs_and_errors() {
+    let server = McpServer::new(ToolHandler::default()).with_context(|params| {
+        match params["_meta"]["user"].as_str() {
+            Some(user) => ToolContext::new().with_user_id(user),
+            None => ToolContext::new(),
+        }
+    });
+
+    let ok = server
+        .handle_message(&request(
+            1,
+            "tools/call",
+            json!({ "name": "add", "arguments": { "a": 2, "b": 3 } }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(
+        ok["result"],
+        json!({ "content": [{ "type": "text", "text": "5" }], "isError": false })
+    );
+
+    let failed = server
+        .handle_message(&request(
+            2,
+            "tools/call",
+            json!({ "name": "divide", "arguments": { "a": 1, "b": 0 } }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(failed["result"]["isError"], true);
+    assert!(failed["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .starts_with("Error:"));
+
+    let user = server
+        .handle_message(&request(
+            3,
+            "tools/call",
+            json!({ "name": "whoami", "_meta": { "user": "ada" } }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(user["result"]["content"][0]["text"], "ada");
+
+    let unknown = server
+        .handle_message(&request(4, "tools/call", json!({ "name": "missing" })))
+        .await
+        .unwrap();
+    assert_eq!(unknown["error"]["code"], -32602);
+
+    let method = server
+        .handle_message(&request(5, "resources/list", json!({})))
+        .await
+        .unwrap();
+    assert_eq!(method["error"]["code"], -32601);
+}
+
+#[tokio::test]
+async fn serves_newline_delimited_messages() {
+    let input = [
+        request(1, "ping", json!({})).to_string(),
+        json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }).to_string(),
+        String::new(),
+        "{not json".to_string(),
+        request(
+            2,
+            "tools/call",
+            json!({ "name": "add", "arguments": { "a": 1, "b": 1 } }),
+        )
+        .to_string(),
+    ]
+    .join("\n");
+    let mut output = Vec::new();
+    McpServer::new(ToolHandler::default())
+        .serve(input.as_bytes(), &mut output)
+        .await
+        .unwrap();
+
+    let responses = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(responses.len(), 3);
+    assert_eq!(
+        responses[0],
+        json!({ "jsonrpc": "2.0", "id": 1, "result": {} })
+    );
+    assert_eq!(responses[1]["error"]["code"], -32700);
+    assert_eq!(responses[2]["result"]["content"][0]["text"], "2");
+}
+
+#[tokio::test]
+async fn client_imports_and_calls_a_servers_tools() {
+    let (client_end, server_end) = tokio::io::duplex(4096);
+    let (server_read, server_write) = tokio::io::split(server_end);
+    tokio::spawn(async move {
+        McpServer::new(ToolHandler::default())
+            .with_server_info("remote", "2.0.0")
+            .serve(tokio::io::BufReader::new(server_read), server_write)
+            .await
+    });
+
+    let (client_read, client_write) = tokio::io::split(client_end);
+    let client = McpClient::connect(client_read, client_write)
+        .await
+        .unwrap()
+        .with_prefix("remote_");
+    assert_eq!(client.server_info()["name"], "remote");
+
+    let mut handler = ToolHandler::empty();
+    let names = client.load(&mut handler).await.unwrap();
+    assert!(names.contains(&"remote_add".to_string()));
+    assert_eq!(
+        handler.get_tool("remote_add").unwrap().parameter_schema["required"],
+        json!(["a", "b"])
+    );
+
+    let out = handler
+        .call_with_args("remote_add", &["2".into(), "40".into()])
+        .await
+        .unwrap();
+    assert_eq!(out, "42");
+
+    let err = handler
+        .call_with_args("remote_divide", &["1".into(), "0".into()])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::Execution(msg) if msg.starts_with("Error:")));
+
+    let err = client.call_tool("missing", json!({})).await.unwrap_err();
+    assert!(matches!(err, ToolError::Execution(msg) if msg.contains("-32602")));
+}
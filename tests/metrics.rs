@@ -0,0 +1,83 @@
+#![cfg(feature = "prometheus")]
+
+use std::sync::Arc;
+use std::time::Duration;
+use tool_calling::audit::{AuditQuery, AuditSink, MemoryAuditLog};
+use tool_calling::metrics::ToolMetrics;
+use tool_calling::{tool, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    // Panics, and so fails the call, when `b` is zero
+    (a / b).to_string()
+}
+
+#[tool]
+pub async fn nap(millis: u64) -> String {
+    tokio::time::sleep(Duration::from_millis(millis)).await;
+    String::new()
+}
+
+// Tests
+
+#[tokio::test]
+async fn calls_and_errors_are_counted() {
+    let metrics = Arc::new(ToolMetrics::new());
+    let log = Arc::new(MemoryAuditLog::with_capacity(10));
+    let handler = ToolHandler::default()
+        .with_audit(log.clone())
+        .with_metrics(metrics.clone());
+    for b in ["1", "2", "0"] {
+        let _ = handler
+            .call_with_args("divide", &["4".into(), b.into()])
+            .await;
+    }
+
+    let text = metrics.render();
+    assert!(
+        text.contains("# TYPE tool_calls_total counter\n"),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains("tool_calls_total{tool=\"divide\"} 3\n"),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains("tool_call_errors_total{tool=\"divide\",kind=\"execution\"} 1\n"),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains("tool_call_duration_seconds_count{tool=\"divide\"} 3\n"),
+        "{}",
+        text
+    );
+    // The audit log still receives every call
+    assert_eq!(log.query(&AuditQuery::new()).len(), 3);
+}
+
+#[tokio::test]
+async fn latencies_fill_cumulative_buckets() {
+    let metrics = Arc::new(ToolMetrics::with_buckets(vec![1.0, 0.02]));
+    let handler = ToolHandler::default().with_metrics(metrics.clone());
+    handler.call_with_args("nap", &["0".into()]).await.unwrap();
+    handler.call_with_args("nap", &["50".into()]).await.unwrap();
+
+    let text = metrics.render();
+    let buckets = text
+        .lines()
+        .filter(|line| line.starts_with("tool_call_duration_seconds_bucket"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        buckets,
+        vec![
+            "tool_call_duration_seconds_bucket{tool=\"nap\",le=\"0.02\"} 1",
+            "tool_call_duration_seconds_bucket{tool=\"nap\",le=\"1\"} 2",
+            "tool_call_duration_seconds_bucket{tool=\"nap\",le=\"+Inf\"} 2",
+        ]
+    );
+}
@@ -0,0 +1,179 @@
+use serde_json::{json, Value};
+use tool_calling::mistral::{
+    is_valid_tool_call_id, normalize_tool_call_id, normalize_tool_call_ids, parse_raw_tool_calls,
+    raw_tool_results, tool_message,
+};
+use tool_calling::{tool, SchemaFlavor, ToolContext, ToolError, ToolHandler, ToolResultContent};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up the weather
+pub fn weather(city: String) -> String {
+    format!("sunny in {}", city)
+}
+
+#[tool]
+pub fn divide(a: i32, b: i32) -> String {
+    (a / b).to_string()
+}
+
+#[tool]
+pub fn chart(series: String) -> String {
+    ToolResultContent::to_output(&[
+        ToolResultContent::text(format!("Chart of {}", series)),
+        ToolResultContent::json(json!({ "points": 3 })),
+    ])
+}
+
+// Tests
+
+#[test]
+fn tools_use_the_openai_shape() {
+    let handler = ToolHandler::default();
+    let tools = handler.mistral_tools();
+    assert_eq!(tools, handler.all_tools_schema());
+    let weather = handler.get_tool("weather").unwrap();
+    assert_eq!(
+        weather.render_schema(SchemaFlavor::Mistral),
+        weather.render_schema(SchemaFlavor::OpenAi)
+    );
+
+    let prompt = handler.mistral_available_tools();
+    assert!(prompt.starts_with("[AVAILABLE_TOOLS][{\"type\":\"function\""));
+    assert!(prompt.ends_with("[/AVAILABLE_TOOLS]"));
+}
+
+#[test]
+fn ids_are_normalized_to_nine_alphanumerics() {
+    assert!(is_valid_tool_call_id("D681PevKs"));
+    assert!(!is_valid_tool_call_id("call_123"));
+    assert!(!is_valid_tool_call_id("toolu_01A09q90qw90lq917835lq9"));
+    assert_eq!(normalize_tool_call_id("D681PevKs"), "D681PevKs");
+
+    let id = normalize_tool_call_id("toolu_01A09q90qw90lq917835lq9");
+    assert!(is_valid_tool_call_id(&id));
+    assert_eq!(normalize_tool_call_id("toolu_01A09q90qw90lq917835lq9"), id);
+    assert_ne!(
+        normalize_tool_call_id("call_1"),
+        normalize_tool_call_id("call_2")
+    );
+
+    let mut history = vec![
+        json!({ "role": "user", "content": "Weather in Oslo?" }),
+        json!({ "role": "assistant", "tool_calls": [{ "id": "call_1", "function": { "name": "weather", "arguments": "{}" } }] }),
+        json!({ "role": "tool", "tool_call_id": "call_1", "content": "sunny" }),
+    ];
+    normalize_tool_call_ids(&mut history);
+    let id = normalize_tool_call_id("call_1");
+    assert_eq!(history[1]["tool_calls"][0]["id"], id);
+    assert_eq!(history[2]["tool_call_id"], id);
+    assert_eq!(
+        tool_message("call_1", "weather", "sunny"),
+        json!({ "role": "tool", "tool_call_id": id, "name": "weather", "content": "sunny" })
+    );
+}
+
+#[tokio::test]
+async fn assistant_calls_are_answered_with_named_tool_messages() {
+    let handler = ToolHandler::default();
+    let message = json!({
+        "role": "assistant",
+        "content": "",
+        "tool_calls": [
+            { "id": "a1b2c3d4e", "type": "function", "function": { "name": "weather", "arguments": "{\"city\": \"Paris\"}" } },
+            { "id": "f5g6h7i8j", "type": "function", "function": { "name": "divide", "arguments": "{\"a\": 1, \"b\": 0}" } }
+        ]
+    });
+    let answers = handler
+        .answer_mistral_tool_calls(&message, &ToolContext::default())
+        .await;
+    assert_eq!(
+        answers[0],
+        json!({ "role": "tool", "tool_call_id": "a1b2c3d4e", "name": "weather", "content": "sunny in Paris" })
+    );
+    assert_eq!(answers[1]["tool_call_id"], "f5g6h7i8j");
+    assert_eq!(answers[1]["name"], "divide");
+    assert!(answers[1]["content"].as_str().unwrap().contains("panicked"));
+}
+
+#[tokio::test]
+async fn answers_pair_each_normalized_call_with_its_result() {
+    // Calls without ids each get their own, the one their assistant message is given
+    let handler = ToolHandler::default();
+    let mut message = json!({ "role": "assistant", "tool_calls": [
+        { "function": { "name": "weather", "arguments": { "city": "Oslo" } } },
+        { "function": { "name": "chart", "arguments": { "series": "sales" } } }
+    ] });
+    let answers = handler
+        .answer_mistral_tool_calls(&message, &ToolContext::default())
+        .await;
+    normalize_tool_call_ids(std::slice::from_mut(&mut message));
+    assert_ne!(answers[0]["tool_call_id"], answers[1]["tool_call_id"]);
+    for (answer, call) in answers
+        .iter()
+        .zip(message["tool_calls"].as_array().unwrap())
+    {
+        assert!(is_valid_tool_call_id(
+            answer["tool_call_id"].as_str().unwrap()
+        ));
+        assert_eq!(answer["tool_call_id"], call["id"]);
+    }
+    assert_eq!(answers[0]["content"], "sunny in Oslo");
+    // Rich results are answered with their text, as for other providers
+    assert_eq!(answers[1]["name"], "chart");
+    assert_eq!(answers[1]["content"], "Chart of sales\n{\"points\":3}");
+
+    // Calls found in the content are named after the tools they call
+    let handler = ToolHandler::default().with_tolerant_parsing();
+    let message = json!({
+        "role": "assistant",
+        "content": "<tool_call>{\"name\": \"divide\", \"arguments\": {\"a\": 9, \"b\": 3}}</tool_call>"
+    });
+    let answers = handler
+        .answer_mistral_tool_calls(&message, &ToolContext::default())
+        .await;
+    assert_eq!(answers.len(), 1);
+    assert_eq!(answers[0]["name"], "divide");
+    assert_eq!(answers[0]["content"], "3");
+    assert_eq!(answers[0]["tool_call_id"], normalize_tool_call_id("call_0"));
+}
+
+#[tokio::test]
+async fn raw_output_is_parsed_into_calls() {
+    let handler = ToolHandler::default();
+    let legacy = r#"[TOOL_CALLS] [{"name": "weather", "arguments": {"city": "Rome"}, "id": "Ab3dE6gH9"}]</s>"#;
+    let calls = parse_raw_tool_calls(legacy).unwrap();
+    assert_eq!(
+        calls,
+        vec![
+            json!({ "type": "function", "id": "Ab3dE6gH9", "function": { "name": "weather", "arguments": { "city": "Rome" } } })
+        ]
+    );
+    assert_eq!(
+        handler.call_tool(&calls[0]).await.unwrap().output,
+        "sunny in Rome"
+    );
+
+    let current = r#"Let me check.[TOOL_CALLS]weather[CALL_ID]x1y2z3w4v[ARGS]{"city": "Oslo"}[TOOL_CALLS]divide[ARGS]{"a": 9, "b": 3}"#;
+    let calls = parse_raw_tool_calls(current).unwrap();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0]["id"], "x1y2z3w4v");
+    assert!(calls[1].get("id").is_none());
+    let outputs = futures::future::join_all(calls.iter().map(|call| handler.call_tool(call))).await;
+    assert_eq!(outputs[1].as_ref().unwrap().output, "3");
+
+    assert_eq!(
+        parse_raw_tool_calls("No tools needed.").unwrap(),
+        Vec::<Value>::new()
+    );
+    let err = parse_raw_tool_calls("[TOOL_CALLS]weather{\"city\": \"Oslo\"}").unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+    let err = parse_raw_tool_calls("[TOOL_CALLS][{\"name\": \"weather\", ").unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+
+    assert_eq!(
+        raw_tool_results("x1y2z3w4v", "sunny"),
+        r#"[TOOL_RESULTS]{"content":"sunny","call_id":"x1y2z3w4v"}[/TOOL_RESULTS]"#
+    );
+}
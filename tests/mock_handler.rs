@@ -0,0 +1,195 @@
+mod common;
+
+use common::call;
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use tool_calling::chat::{ChatError, ChatLoop, ChatProvider};
+use tool_calling::testing::{MockToolHandler, RecordedCall};
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Charges a card
+pub fn charge(card: String, cents: u32, memo: Option<String>) -> String {
+    panic!("charged {} {} {:?} for real", card, cents, memo)
+}
+
+fn mock() -> MockToolHandler {
+    MockToolHandler::new()
+        .with_tool(
+            "search",
+            "Searches the web",
+            json!({ "type": "object", "properties": { "query": { "type": "string" } }, "required": ["query"] }),
+        )
+        .with_tool(
+            "fetch",
+            "Fetches a page",
+            json!({ "type": "object", "properties": { "url": { "type": "string" } }, "required": ["url"] }),
+        )
+}
+
+// Tests
+
+#[tokio::test]
+async fn records_calls_in_order() {
+    let mock = mock()
+        .with_response("search", "3 results")
+        .with_response("fetch", "<html>");
+    let handler = mock.handler();
+    handler
+        .call_tool(&call("search", json!({ "query": "rust" })))
+        .await
+        .unwrap();
+    handler
+        .call_tool(&call("fetch", json!({ "url": "https://rust-lang.org" })))
+        .await
+        .unwrap();
+    handler
+        .call_tool(&call("search", json!({ "query": "tokio" })))
+        .await
+        .unwrap();
+
+    assert_eq!(mock.calls().len(), 3);
+    assert_eq!(
+        mock.calls()[1],
+        RecordedCall {
+            name: "fetch".to_string(),
+            arguments: json!({ "url": "https://rust-lang.org" })
+                .as_object()
+                .unwrap()
+                .clone(),
+        }
+    );
+    assert_eq!(mock.calls_to("search")[1]["query"], "tokio");
+    mock.assert_call_order(&["search", "fetch", "search"]);
+    mock.assert_called_times("search", 2);
+    mock.assert_called_with("search", json!({ "query": "rust" }));
+    mock.assert_not_called("charge");
+
+    mock.clear_calls();
+    mock.assert_not_called("search");
+}
+
+#[tokio::test]
+async fn answers_with_programmed_responses_in_turn() {
+    let mock = mock()
+        .with_response("search", "first")
+        .with_error("search", ToolError::Execution("rate limited".into()))
+        .with_response("search", "last");
+    let search = call("search", json!({ "query": "rust" }));
+    let handler = mock.handler();
+
+    assert_eq!(handler.call_tool(&search).await.unwrap().output, "first");
+    assert_eq!(
+        handler.call_tool(&search).await.unwrap_err(),
+        ToolError::Execution("rate limited".into())
+    );
+    // The last response repeats
+    assert_eq!(handler.call_tool(&search).await.unwrap().output, "last");
+    assert_eq!(handler.call_tool(&search).await.unwrap().output, "last");
+    mock.assert_called_times("search", 4);
+}
+
+#[tokio::test]
+async fn responders_see_the_arguments() {
+    let mock = mock().with_responder("search", |args| {
+        Ok(format!("results for {}", args["query"].as_str().unwrap()))
+    });
+    let res = mock
+        .handler()
+        .call_tool(&call("search", json!({ "query": "rust" })))
+        .await
+        .unwrap();
+    assert_eq!(res.output, "results for rust");
+}
+
+#[tokio::test]
+async fn unprogrammed_tools_fail() {
+    let mock = mock();
+    let err = mock
+        .handler()
+        .call_tool(&call("fetch", json!({ "url": "https://example.com" })))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::Execution(_)), "{:?}", err);
+    mock.assert_called("fetch");
+}
+
+#[tokio::test]
+async fn invalid_calls_are_rejected_before_recording() {
+    let mock = mock().with_response("search", "3 results");
+    let err = mock
+        .handler()
+        .call_tool(&call("search", json!({ "query": 42 })))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)), "{:?}", err);
+    mock.assert_not_called("search");
+}
+
+#[tokio::test]
+async fn mocks_registered_tools_without_running_them() {
+    let mock =
+        MockToolHandler::from_handler(ToolHandler::default()).with_response("charge", "charged");
+    let res = mock
+        .handler()
+        .call_tool(&call("charge", json!({ "card": "4242", "cents": 500 })))
+        .await
+        .unwrap();
+    assert_eq!(res.output, "charged");
+    // The omitted memo isn't recorded
+    mock.assert_called_with("charge", json!({ "card": "4242", "cents": 500 }));
+}
+
+#[test]
+#[should_panic(expected = "expected calls to [\"fetch\"] in order")]
+fn failed_assertions_describe_the_calls() {
+    mock().assert_call_order(&["fetch"]);
+}
+
+#[test]
+#[should_panic(expected = "no tool called 'missing'")]
+fn responses_need_a_tool() {
+    let _ = mock().with_response("missing", "?");
+}
+
+// A provider asking for a search, then answering with its result
+struct Searching(Mutex<usize>);
+
+impl ChatProvider for Searching {
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [Value],
+        _tools: &'a [Value],
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        Box::pin(async move {
+            let mut round = self.0.lock().unwrap();
+            *round += 1;
+            let message = if *round == 1 {
+                json!({
+                    "role": "assistant",
+                    "tool_calls": [{ "id": "call_1", "type": "function", "function": { "name": "search", "arguments": "{\"query\":\"rust\"}" } }]
+                })
+            } else {
+                json!({ "role": "assistant", "content": messages.last().unwrap()["content"] })
+            };
+            Ok(json!({ "choices": [{ "message": message }] }))
+        })
+    }
+}
+
+#[tokio::test]
+async fn drives_a_chat_loop() {
+    let mock = mock().with_response("search", "3 results");
+    let provider = Searching(Mutex::new(0));
+    let mut messages = vec![json!({ "role": "user", "content": "Search for rust" })];
+    let response = ChatLoop::new(mock.handler(), &provider)
+        .run(&mut messages)
+        .await
+        .unwrap();
+    assert_eq!(response["choices"][0]["message"]["content"], "3 results");
+    mock.assert_call_order(&["search"]);
+    mock.assert_called_with("search", json!({ "query": "rust" }));
+}
@@ -0,0 +1,816 @@
+#![cfg(feature = "ollama")]
+
+use futures::TryStreamExt;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tool_calling::chat::{ChatEvent, ToolChoice};
+use tool_calling::conversation::ChatMessage;
+use tool_calling::ollama::{
+    ChatSession, KeepAlive, ModelOptions, Ollama, OllamaError, RetryPolicy, Usage,
+};
+use tool_calling::{tool, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Adds two integers
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+// A fake Ollama server: asks for `add` until it sees a tool message (two, when told to add
+// twice), then answers with the last tool output. Records every request body it receives.
+async fn ollama_server(requests: Arc<Mutex<Vec<Value>>>) -> String {
+    ollama_server_with_headers(requests, Arc::default()).await
+}
+
+// The path and headers of a request
+type Head = (String, Vec<(String, String)>);
+
+// Like `ollama_server`, also recording the path and headers of every request
+async fn ollama_server_with_headers(
+    requests: Arc<Mutex<Vec<Value>>>,
+    heads: Arc<Mutex<Vec<Head>>>,
+) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let host = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).await.unwrap();
+            let path = request_line.split(' ').nth(1).unwrap().to_string();
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    headers.push((name.to_lowercase(), value.trim().to_string()));
+                }
+            }
+            heads.lock().unwrap().push((path, headers));
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).await.unwrap();
+            let body: Value = serde_json::from_slice(&body).unwrap();
+            requests.lock().unwrap().push(body.clone());
+
+            let messages = body["messages"].as_array().unwrap();
+            let stream = reader.get_mut();
+            if messages.is_empty() {
+                // Loading or unloading the model
+                let response = json!({ "model": body["model"], "message": { "role": "assistant", "content": "" }, "done": true, "done_reason": "load" }).to_string();
+                let reply = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                );
+                stream.write_all(reply.as_bytes()).await.unwrap();
+                continue;
+            }
+            let last = messages.last().unwrap().clone();
+            let answered = messages.iter().filter(|m| m["role"] == "tool").count();
+            let rounds = if messages.iter().any(|m| m["content"] == "Add it twice.") {
+                2
+            } else {
+                1
+            };
+            let message = if answered == rounds {
+                json!({ "role": "assistant", "content": format!("The answer is {}", last["content"].as_str().unwrap()) })
+            } else {
+                json!({
+                    "role": "assistant",
+                    "content": "",
+                    "tool_calls": [{ "function": { "name": "add", "arguments": { "a": 20, "b": 22 } } }]
+                })
+            };
+            if body["stream"] == true {
+                // One chunk per word of the content, then the tool calls, then a final chunk
+                let head = "HTTP/1.1 200 OK\r\ncontent-type: application/x-ndjson\r\nconnection: close\r\n\r\n";
+                stream.write_all(head.as_bytes()).await.unwrap();
+                let content = message["content"].as_str().unwrap();
+                let mut chunks = content
+                    .split_inclusive(' ')
+                    .map(|word| json!({ "message": { "role": "assistant", "content": word }, "done": false }))
+                    .collect::<Vec<_>>();
+                if let Some(calls) = message.get("tool_calls") {
+                    chunks.push(json!({ "message": { "role": "assistant", "content": "", "tool_calls": calls }, "done": false }));
+                }
+                chunks.push(
+                    json!({ "message": { "role": "assistant", "content": "" }, "done": true, "done_reason": "stop", "prompt_eval_count": 10, "eval_count": 5, "eval_duration": 500_000_000 }),
+                );
+                for chunk in chunks {
+                    stream
+                        .write_all(format!("{}\n", chunk).as_bytes())
+                        .await
+                        .unwrap();
+                    stream.flush().await.unwrap();
+                }
+                continue;
+            }
+            let response =
+                json!({ "model": body["model"], "message": message, "done": true, "done_reason": "stop", "prompt_eval_count": 10, "eval_count": 5, "total_duration": 2_000_000_000, "eval_duration": 500_000_000 }).to_string();
+            let reply = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                response.len(),
+                response
+            );
+            stream.write_all(reply.as_bytes()).await.unwrap();
+        }
+    });
+    host
+}
+
+// Tests
+
+#[tokio::test]
+async fn sessions_run_tools_and_keep_the_history() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server(Arc::clone(&requests)).await;
+    let called = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&called);
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host)
+        .system("Be brief.")
+        .tools(ToolHandler::default())
+        .on_event(move |event| {
+            if let ChatEvent::ToolCalled(call) = event {
+                seen.lock().unwrap().push(call["function"]["name"].clone());
+            }
+        })
+        .build()
+        .unwrap();
+
+    let reply = session.send("What is 20 + 22?").await.unwrap();
+    assert_eq!(reply.content, "The answer is 42");
+    assert_eq!(reply.tool_calls, 1);
+    assert_eq!(*called.lock().unwrap(), ["add"]);
+
+    let history = session.history();
+    let roles = history
+        .iter()
+        .map(|m| m["role"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(roles, ["system", "user", "assistant", "tool", "assistant"]);
+    assert_eq!(history[0]["content"], "Be brief.");
+    // Ollama's calls have no id, so the result names the tool instead
+    assert_eq!(
+        history[3],
+        json!({ "role": "tool", "content": "42", "tool_name": "add" })
+    );
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0]["model"], "llama3.1");
+    assert_eq!(requests[0]["stream"], false);
+    assert_eq!(requests[0]["tools"][0]["function"]["name"], "add");
+    assert_eq!(requests[1]["messages"][3]["content"], "42");
+}
+
+#[tokio::test]
+async fn sessions_need_a_model() {
+    let err = ChatSession::builder().build().err().unwrap();
+    assert!(matches!(err, OllamaError::Config(_)));
+
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host("http://127.0.0.1:1")
+        .build()
+        .unwrap();
+    let err = session.send("hi").await.unwrap_err();
+    assert!(matches!(err, OllamaError::Transport(_)));
+    assert!(session.history().is_empty());
+}
+
+#[tokio::test]
+async fn streamed_replies_reach_the_callback() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server(Arc::clone(&requests)).await;
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host.clone())
+        .tools(ToolHandler::default())
+        .build()
+        .unwrap();
+
+    let mut tokens = Vec::new();
+    let reply = session
+        .send_streaming("What is 20 + 22?", |token| tokens.push(token.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(tokens, ["The ", "answer ", "is ", "42"]);
+    assert_eq!(reply.done_reason.as_deref(), Some("stop"));
+    assert_eq!(reply.content, "The answer is 42");
+    assert_eq!(reply.tool_calls, 1);
+    assert_eq!(
+        session.history()[1]["tool_calls"][0]["function"]["name"],
+        "add"
+    );
+    assert!(requests.lock().unwrap().iter().all(|r| r["stream"] == true));
+
+    let ollama = Ollama::new("llama3.1").with_host(host);
+    let messages = [json!({ "role": "user", "content": "hi" })];
+    let chunks = ollama
+        .chat_stream(&messages, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks[0]["message"]["tool_calls"].is_array());
+    assert_eq!(chunks[1]["done"], true);
+}
+
+#[tokio::test]
+async fn sessions_are_seeded_with_examples_and_history() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server(Arc::clone(&requests)).await;
+    let earlier = vec![
+        json!({ "role": "user", "content": "My name is Ada." }),
+        json!({ "role": "assistant", "content": "Hello Ada." }),
+    ];
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host)
+        .system("Answer with a number.")
+        .example("What is 1 + 1?", "2")
+        .history(earlier.clone())
+        .tools(ToolHandler::default())
+        .build()
+        .unwrap();
+    assert_eq!(session.history().len(), 5);
+    assert_eq!(session.history()[3..], earlier[..]);
+
+    session.send("What is 20 + 22?").await.unwrap();
+    let requests = requests.lock().unwrap();
+    let sent = requests[0]["messages"].as_array().unwrap();
+    let contents = sent
+        .iter()
+        .map(|m| m["content"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        contents,
+        [
+            "Answer with a number.",
+            "What is 1 + 1?",
+            "2",
+            "My name is Ada.",
+            "Hello Ada.",
+            "What is 20 + 22?"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn tool_rounds_continue_until_the_model_answers() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server(Arc::clone(&requests)).await;
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host.clone())
+        .tools(ToolHandler::default())
+        .build()
+        .unwrap();
+    let reply = session.send("Add it twice.").await.unwrap();
+    assert_eq!(reply.content, "The answer is 42");
+    assert_eq!(reply.tool_calls, 2);
+    assert_eq!(reply.rounds, 3);
+    assert_eq!(reply.done_reason.as_deref(), Some("stop"));
+    assert_eq!(requests.lock().unwrap().len(), 3);
+
+    let mut capped = ChatSession::builder()
+        .model("llama3.1")
+        .host(host)
+        .tools(ToolHandler::default())
+        .max_rounds(2)
+        .build()
+        .unwrap();
+    let err = capped.send("Add it twice.").await.unwrap_err();
+    assert_eq!(err, OllamaError::MaxIterations(2));
+}
+
+#[tokio::test]
+async fn failing_tools_can_end_the_session() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server(Arc::clone(&requests)).await;
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host)
+        .tools(ToolHandler::empty())
+        .fail_on_tool_errors()
+        .build()
+        .unwrap();
+    let err = session.send("What is 20 + 22?").await.unwrap_err();
+    match err {
+        OllamaError::ToolExecution { name, .. } => assert_eq!(name, "add"),
+        other => panic!("unexpected error: {other}"),
+    }
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn tool_choice_narrows_the_first_request() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server(Arc::clone(&requests)).await;
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host.clone())
+        .tools(ToolHandler::default())
+        .tool_choice(ToolChoice::Tool("add".into()))
+        .build()
+        .unwrap();
+    session.send("What is 20 + 22?").await.unwrap();
+    {
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0]["tools"].as_array().unwrap().len(), 1);
+        assert_eq!(requests[0]["tools"][0]["function"]["name"], "add");
+        assert_eq!(requests[1]["tools"], requests[0]["tools"]);
+    }
+
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server(Arc::clone(&requests)).await;
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host)
+        .tools(ToolHandler::default())
+        .tool_choice(ToolChoice::None)
+        .build()
+        .unwrap();
+    session.send("What is 20 + 22?").await.unwrap();
+    assert!(requests.lock().unwrap()[0].get("tools").is_none());
+}
+
+#[tokio::test]
+async fn usage_adds_up_across_rounds_and_turns() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server(Arc::clone(&requests)).await;
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host)
+        .tools(ToolHandler::default())
+        .build()
+        .unwrap();
+    let reply = session.send("Add it twice.").await.unwrap();
+    assert_eq!(reply.usage.prompt_eval_count, 30);
+    assert_eq!(reply.usage.eval_count, 15);
+    assert_eq!(reply.usage.total_tokens(), 45);
+    assert_eq!(reply.usage.total_duration, Duration::from_secs(6));
+    assert_eq!(reply.usage.tokens_per_second(), Some(10.0));
+
+    // The history already answers the calls, so the model replies at once
+    let reply = session.send_streaming("Thanks.", |_| {}).await.unwrap();
+    assert_eq!(reply.rounds, 1);
+    assert_eq!(reply.usage.eval_count, 5);
+    assert_eq!(reply.usage.total_duration, Duration::ZERO);
+    assert_eq!(session.usage().eval_count, 20);
+    assert_eq!(session.usage().prompt_eval_count, 40);
+    assert_eq!(session.usage().eval_duration, Duration::from_secs(2));
+
+    let usage = Usage::from_response(&json!({ "eval_count": 3 }));
+    assert_eq!(usage.eval_count, 3);
+    assert_eq!(usage.prompt_eval_count, 0);
+    assert_eq!(usage.tokens_per_second(), None);
+}
+
+#[tokio::test]
+async fn images_are_sent_with_the_message() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server(Arc::clone(&requests)).await;
+    let mut session = ChatSession::builder()
+        .model("llava")
+        .host(host)
+        .build()
+        .unwrap();
+    let message = ChatMessage::user("What is 20 + 22?").with_image("aGVsbG8=");
+    session.send_message(message).await.unwrap();
+    let requests = requests.lock().unwrap();
+    assert_eq!(
+        requests[0]["messages"][0],
+        json!({ "role": "user", "content": "What is 20 + 22?", "images": ["aGVsbG8="] })
+    );
+}
+
+#[tokio::test]
+async fn models_can_be_kept_loaded() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server(Arc::clone(&requests)).await;
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host.clone())
+        .keep_alive(KeepAlive::Forever)
+        .build()
+        .unwrap();
+    session.preload_model().await.unwrap();
+    session.send("What is 20 + 22?").await.unwrap();
+    {
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0]["messages"], json!([]));
+        assert_eq!(requests[0]["keep_alive"], -1);
+        assert_eq!(requests[1]["keep_alive"], -1);
+    }
+
+    let ollama = Ollama::new("llama3.1")
+        .with_host(host)
+        .with_keep_alive(KeepAlive::For(Duration::from_secs(600)));
+    ollama.unload_model().await.unwrap();
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.last().unwrap()["keep_alive"], "0ms");
+    assert_eq!(
+        KeepAlive::For(Duration::from_secs(600)).to_json(),
+        "600000ms"
+    );
+}
+
+#[tokio::test]
+async fn sessions_stop_runaway_tool_loops() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server(Arc::clone(&requests)).await;
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host.clone())
+        .tools(ToolHandler::default())
+        .max_repeated_calls(1)
+        .build()
+        .unwrap();
+    let err = session.send("Add it twice.").await.unwrap_err();
+    assert!(matches!(err, OllamaError::LoopDetected { ref name, times: 2, .. } if name == "add"));
+
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host)
+        .tools(ToolHandler::default())
+        .max_session_rounds(3)
+        .build()
+        .unwrap();
+    session.send("What is 20 + 22?").await.unwrap();
+    assert_eq!(session.rounds_used(), 2);
+    let err = session.send("Add it twice.").await.unwrap_err();
+    assert_eq!(err, OllamaError::MaxIterations(3));
+    assert_eq!(session.rounds_used(), 3);
+    let sent = requests.lock().unwrap().len();
+    let err = session.send("Thanks.").await.unwrap_err();
+    assert_eq!(err, OllamaError::MaxIterations(3));
+    assert_eq!(requests.lock().unwrap().len(), sent);
+}
+
+#[tokio::test]
+async fn model_options_are_sent_with_every_request() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server(Arc::clone(&requests)).await;
+    let mut extra = serde_json::Map::new();
+    extra.insert("top_p".into(), json!(0.9));
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host.clone())
+        .tools(ToolHandler::default())
+        .options(ModelOptions {
+            extra,
+            ..ModelOptions::default()
+        })
+        .temperature(0.0)
+        .num_ctx(8192)
+        .seed(7)
+        .stop(["</answer>"])
+        .build()
+        .unwrap();
+    session.send("What is 20 + 22?").await.unwrap();
+
+    let mut plain = ChatSession::builder()
+        .model("llama3.1")
+        .host(host)
+        .build()
+        .unwrap();
+    plain.send("hi").await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    let expected = json!({ "temperature": 0.0, "num_ctx": 8192, "seed": 7, "stop": ["</answer>"], "top_p": 0.9 });
+    assert_eq!(requests[0]["options"], expected);
+    assert_eq!(requests[1]["options"], expected);
+    assert!(requests[2].get("options").is_none());
+}
+
+#[tokio::test]
+async fn requests_carry_the_configured_client_auth_and_headers() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let heads = Arc::new(Mutex::new(Vec::new()));
+    let host = ollama_server_with_headers(Arc::clone(&requests), Arc::clone(&heads)).await;
+    let client = reqwest::Client::builder()
+        .user_agent("tool-calling-tests")
+        .build()
+        .unwrap();
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(format!("{}/ollama/", host))
+        .client(client)
+        .api_key("secret")
+        .header("x-team", "search")
+        .build()
+        .unwrap();
+    session.send("hi").await.unwrap();
+
+    let heads = heads.lock().unwrap();
+    let (path, headers) = &heads[0];
+    assert_eq!(path, "/ollama/api/chat");
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    assert_eq!(header("authorization"), Some("Bearer secret"));
+    assert_eq!(header("x-team"), Some("search"));
+    assert_eq!(header("user-agent"), Some("tool-calling-tests"));
+}
+
+// Drops the first `failures` connections unanswered, then answers every request with
+// `body`. Counts the connections it accepts.
+async fn flaky_server(failures: usize, body: &'static str, attempts: Arc<AtomicUsize>) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let host = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            if attempts.fetch_add(1, Ordering::SeqCst) < failures {
+                drop(stream);
+                continue;
+            }
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            reader.read_exact(&mut vec![0; length]).await.unwrap();
+            let reply = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+        }
+    });
+    host
+}
+
+#[tokio::test]
+async fn failed_turns_leave_the_history_unchanged() {
+    // Fails after a round of tool calls
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(ollama_server(Arc::clone(&requests)).await)
+        .tools(ToolHandler::default())
+        .max_repeated_calls(1)
+        .build()
+        .unwrap();
+    let err = session.send("Add it twice.").await.unwrap_err();
+    assert!(matches!(err, OllamaError::LoopDetected { .. }));
+    assert!(session.history().is_empty());
+    assert_eq!(session.rounds_used(), requests.lock().unwrap().len());
+
+    // Fails on the first request, which still counts as a round
+    let answer = r#"{"message": {"role": "assistant", "content": "hello"}, "done": true, "done_reason": "stop"}"#;
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(flaky_server(1, answer, Arc::clone(&attempts)).await)
+        .retry(RetryPolicy {
+            max_retries: 0,
+            ..RetryPolicy::default()
+        })
+        .build()
+        .unwrap();
+    let err = session.send("hi").await.unwrap_err();
+    assert!(matches!(err, OllamaError::Transport(_)));
+    assert!(session.history().is_empty());
+    assert_eq!(session.rounds_used(), 1);
+
+    // Sending again asks the question once
+    let reply = session.send("hi").await.unwrap();
+    assert_eq!(reply.content, "hello");
+    assert_eq!(reply.rounds, 1);
+    let users = session
+        .history()
+        .iter()
+        .filter(|message| message["role"] == "user")
+        .count();
+    assert_eq!(users, 1);
+}
+
+#[tokio::test]
+async fn transport_failures_are_retried_with_backoff() {
+    let policy = RetryPolicy {
+        max_retries: 2,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(15),
+        multiplier: 2.0,
+    };
+    assert_eq!(policy.delay(0), Duration::from_millis(10));
+    assert_eq!(policy.delay(1), Duration::from_millis(15));
+    assert_eq!(RetryPolicy::default().delay(2), Duration::from_secs(1));
+
+    let answer = r#"{"message": {"role": "assistant", "content": "hello"}, "done": true, "done_reason": "stop"}"#;
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let host = flaky_server(2, answer, Arc::clone(&attempts)).await;
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host.clone())
+        .retry(policy)
+        .build()
+        .unwrap();
+    assert_eq!(session.send("hi").await.unwrap().content, "hello");
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let host = flaky_server(3, answer, Arc::clone(&attempts)).await;
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host)
+        .retry(policy)
+        .build()
+        .unwrap();
+    let err = session.send("hi").await.unwrap_err();
+    assert!(matches!(err, OllamaError::Transport(_)));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let host = flaky_server(0, "not json", Arc::clone(&attempts)).await;
+    let mut session = ChatSession::builder()
+        .model("llama3.1")
+        .host(host)
+        .retry(policy)
+        .build()
+        .unwrap();
+    let err = session.send("hi").await.unwrap_err();
+    assert!(matches!(err, OllamaError::Deserialize(_)));
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
+
+// A fake Ollama server with three installed models, of which `gemma` can't call tools.
+// Counts the chat requests it receives.
+async fn catalog_server(chats: Arc<AtomicUsize>) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let host = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).await.unwrap();
+            let path = request_line.split(' ').nth(1).unwrap().to_string();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).await.unwrap();
+            let response = match path.as_str() {
+                "/api/tags" => json!({ "models": [
+                    { "name": "llama3.1:latest", "size": 4920753328u64, "details": { "family": "llama" } },
+                    { "name": "gemma:2b" },
+                    { "name": "qwen3:8b" }
+                ] }),
+                "/api/show" => {
+                    let body: Value = serde_json::from_slice(&body).unwrap();
+                    match body["model"].as_str().unwrap() {
+                        "gemma:2b" => json!({ "capabilities": ["completion"] }),
+                        "legacy" => json!({ "details": {} }),
+                        _ => json!({ "capabilities": ["completion", "tools"] }),
+                    }
+                }
+                _ => {
+                    chats.fetch_add(1, Ordering::SeqCst);
+                    json!({ "message": { "role": "assistant", "content": "Hi." }, "done": true })
+                }
+            }
+            .to_string();
+            let reply = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                response.len(),
+                response
+            );
+            reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+        }
+    });
+    host
+}
+
+#[tokio::test]
+async fn models_are_listed_and_checked_for_tool_support() {
+    let chats = Arc::new(AtomicUsize::new(0));
+    let host = catalog_server(Arc::clone(&chats)).await;
+    let models = Ollama::new("llama3.1")
+        .with_host(host.clone())
+        .list_models()
+        .await
+        .unwrap();
+    assert_eq!(models.len(), 3);
+    assert_eq!(models[0].name, "llama3.1:latest");
+    assert_eq!(models[0].size, 4920753328);
+    assert_eq!(models[0].details["family"], "llama");
+
+    let ollama = Ollama::new("llama3.1").with_host(host.clone());
+    ollama.check_tool_support().await.unwrap();
+    let capabilities = ollama.model_capabilities("gemma:2b").await.unwrap();
+    assert_eq!(capabilities, Some(vec!["completion".to_string()]));
+    let legacy = Ollama::new("legacy").with_host(host.clone());
+    assert_eq!(legacy.model_capabilities("legacy").await.unwrap(), None);
+    legacy.check_tool_support().await.unwrap();
+
+    let mut session = ChatSession::builder()
+        .model("gemma:2b")
+        .host(host)
+        .check_tool_support()
+        .build()
+        .unwrap();
+    let err = session.send("hi").await.unwrap_err();
+    assert_eq!(
+        err,
+        OllamaError::ToolsUnsupported {
+            model: "gemma:2b".into(),
+            compatible: vec!["llama3.1:latest".into(), "qwen3:8b".into()],
+        }
+    );
+    assert!(err.to_string().contains("llama3.1:latest, qwen3:8b"));
+    assert_eq!(chats.load(Ordering::SeqCst), 0);
+    assert!(session.history().is_empty());
+}
+
+#[tokio::test]
+async fn thinking_is_kept_out_of_the_answer() {
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let inline = r#"{"message":{"role":"assistant","content":"<think>Adding 20 and 22.</think>\n\nIt's 42."},"done":true}"#;
+    let host = flaky_server(0, inline, Arc::clone(&attempts)).await;
+    let mut session = ChatSession::builder()
+        .model("cogito")
+        .host(host)
+        .build()
+        .unwrap();
+    let reply = session.send("What is 20 + 22?").await.unwrap();
+    assert_eq!(reply.content, "It's 42.");
+    assert_eq!(reply.thinking.as_deref(), Some("Adding 20 and 22."));
+    assert_eq!(session.history().last().unwrap()["content"], "It's 42.");
+
+    let separate =
+        r#"{"message":{"role":"assistant","content":"It's 42.","thinking":"Adding."},"done":true}"#;
+    let host = flaky_server(0, separate, Arc::clone(&attempts)).await;
+    let mut session = ChatSession::builder()
+        .model("qwen3")
+        .host(host)
+        .think(true)
+        .build()
+        .unwrap();
+    let reply = session.send("What is 20 + 22?").await.unwrap();
+    assert_eq!(reply.content, "It's 42.");
+    assert_eq!(reply.thinking.as_deref(), Some("Adding."));
+
+    // Tags split across chunks
+    let streamed = concat!(
+        r#"{"message":{"role":"assistant","content":"<thi"},"done":false}"#,
+        "\n",
+        r#"{"message":{"role":"assistant","content":"nk>Add"},"done":false}"#,
+        "\n",
+        r#"{"message":{"role":"assistant","content":"ing.</th"},"done":false}"#,
+        "\n",
+        r#"{"message":{"role":"assistant","content":"ink>\n\nIt's"},"done":false}"#,
+        "\n",
+        r#"{"message":{"role":"assistant","content":" 42."},"done":false}"#,
+        "\n",
+        r#"{"message":{"role":"assistant","content":""},"done":true,"done_reason":"stop"}"#,
+        "\n"
+    );
+    let host = flaky_server(0, streamed, attempts).await;
+    let mut session = ChatSession::builder()
+        .model("cogito")
+        .host(host)
+        .build()
+        .unwrap();
+    let mut tokens = String::new();
+    let reply = session
+        .send_streaming("What is 20 + 22?", |token| tokens.push_str(token))
+        .await
+        .unwrap();
+    assert_eq!(tokens, "It's 42.");
+    assert_eq!(reply.content, "It's 42.");
+    assert_eq!(reply.thinking.as_deref(), Some("Adding."));
+}
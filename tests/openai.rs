@@ -0,0 +1,257 @@
+#![cfg(feature = "openai")]
+
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tool_calling::chat::{ChatError, ChatLoop, ToolChoice};
+use tool_calling::openai::{ChatSession, OpenAi, Usage};
+use tool_calling::{tool, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Adds two integers
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+// The path and headers of a request
+type Head = (String, Vec<(String, String)>);
+
+// A fake OpenAI-compatible server: asks for `add` until it sees a tool message, then
+// answers with its output. Records the path, headers and body of every request, and answers
+// requests for the model `missing` with a 404.
+async fn openai_server(requests: Arc<Mutex<Vec<Value>>>, heads: Arc<Mutex<Vec<Head>>>) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).await.unwrap();
+            let path = request_line.split(' ').nth(1).unwrap().to_string();
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    headers.push((name.to_lowercase(), value.trim().to_string()));
+                }
+            }
+            heads.lock().unwrap().push((path, headers));
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).await.unwrap();
+            let body: Value = serde_json::from_slice(&body).unwrap();
+            requests.lock().unwrap().push(body.clone());
+
+            let (status, response) = if body["model"] == "missing" {
+                (
+                    "404 Not Found",
+                    json!({ "error": { "message": "model not found" } }),
+                )
+            } else {
+                let messages = body["messages"].as_array().unwrap();
+                let (message, finish_reason) = match messages.iter().find(|m| m["role"] == "tool") {
+                    Some(tool) => (
+                        json!({ "role": "assistant", "content": format!("The answer is {}", tool["content"].as_str().unwrap()) }),
+                        "stop",
+                    ),
+                    None => (
+                        json!({
+                            "role": "assistant",
+                            "content": null,
+                            "tool_calls": [{ "id": "call_1", "type": "function", "function": { "name": "add", "arguments": "{\"a\":20,\"b\":22}" } }]
+                        }),
+                        "tool_calls",
+                    ),
+                };
+                (
+                    "200 OK",
+                    json!({
+                        "id": "chatcmpl-1",
+                        "object": "chat.completion",
+                        "model": body["model"],
+                        "choices": [{ "index": 0, "message": message, "finish_reason": finish_reason }],
+                        "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+                    }),
+                )
+            };
+            let response = response.to_string();
+            let reply = format!(
+                "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                response.len(),
+                response
+            );
+            reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+        }
+    });
+    base_url
+}
+
+#[tokio::test]
+async fn session_runs_tool_calls_and_answers() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let heads = Arc::new(Mutex::new(Vec::new()));
+    let base_url = openai_server(requests.clone(), heads.clone()).await;
+
+    let mut session = ChatSession::builder()
+        .model("gpt-test")
+        .base_url(base_url)
+        .api_key("sk-test")
+        .system("You add numbers.")
+        .temperature(0.0)
+        .tools(ToolHandler::default())
+        .build()
+        .unwrap();
+    let reply = session.send("What is 20 + 22?").await.unwrap();
+
+    assert_eq!(reply.content, "The answer is 42");
+    assert_eq!(reply.tool_calls, 1);
+    assert_eq!(reply.rounds, 2);
+    assert_eq!(reply.finish_reason.as_deref(), Some("stop"));
+
+    // The tool result answers the call by its id
+    let history = session.history();
+    assert_eq!(history.len(), 5);
+    assert_eq!(history[0]["role"], "system");
+    assert_eq!(history[2]["tool_calls"][0]["id"], "call_1");
+    assert_eq!(history[3]["role"], "tool");
+    assert_eq!(history[3]["tool_call_id"], "call_1");
+    assert_eq!(history[3]["content"], "42");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0]["model"], "gpt-test");
+    assert_eq!(requests[0]["temperature"], 0.0);
+    assert_eq!(requests[0]["tools"][0]["function"]["name"], "add");
+    assert!(requests[0].get("tool_choice").is_none());
+
+    let heads = heads.lock().unwrap();
+    let (path, headers) = &heads[0];
+    assert_eq!(path, "/v1/chat/completions");
+    assert!(headers.contains(&("authorization".to_string(), "Bearer sk-test".to_string())));
+}
+
+#[tokio::test]
+async fn session_adds_up_usage() {
+    let base_url = openai_server(Arc::default(), Arc::default()).await;
+    let mut session = ChatSession::builder()
+        .model("gpt-test")
+        .base_url(base_url)
+        .tools(ToolHandler::default())
+        .build()
+        .unwrap();
+
+    let reply = session.send("What is 20 + 22?").await.unwrap();
+    let expected = Usage {
+        prompt_tokens: 20,
+        completion_tokens: 10,
+    };
+    assert_eq!(reply.usage, expected);
+    assert_eq!(reply.usage.total_tokens(), 30);
+
+    // The model already has a tool result in the history, so it answers right away
+    let reply = session.send("Thanks.").await.unwrap();
+    assert_eq!(reply.rounds, 1);
+    assert_eq!(session.usage().prompt_tokens, 30);
+    assert_eq!(session.usage().completion_tokens, 15);
+}
+
+#[tokio::test]
+async fn session_sends_tool_choice_and_headers() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let heads = Arc::new(Mutex::new(Vec::new()));
+    let base_url = openai_server(requests.clone(), heads.clone()).await;
+
+    let mut session = ChatSession::builder()
+        .model("gpt-test")
+        .base_url(base_url)
+        .header("x-team", "tools")
+        .tools(ToolHandler::default())
+        .tool_choice(ToolChoice::Tool("add".into()))
+        .build()
+        .unwrap();
+    session.send("What is 20 + 22?").await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(
+        requests[0]["tool_choice"],
+        json!({ "type": "function", "function": { "name": "add" } })
+    );
+    let heads = heads.lock().unwrap();
+    assert!(heads[0]
+        .1
+        .contains(&("x-team".to_string(), "tools".to_string())));
+}
+
+#[tokio::test]
+async fn session_reports_upstream_errors() {
+    let base_url = openai_server(Arc::default(), Arc::default()).await;
+    let mut session = ChatSession::builder()
+        .model("missing")
+        .base_url(base_url)
+        .build()
+        .unwrap();
+
+    let err = session.send("Hello").await.unwrap_err();
+    match err {
+        ChatError::Upstream { status, body } => {
+            assert_eq!(status, 404);
+            assert!(body.contains("model not found"));
+        }
+        other => panic!("expected an upstream error, got {:?}", other),
+    }
+    assert!(session.history().is_empty());
+}
+
+#[tokio::test]
+async fn failed_turns_leave_the_history_unchanged() {
+    let base_url = openai_server(Arc::default(), Arc::default()).await;
+    let mut session = ChatSession::builder()
+        .model("gpt-test")
+        .base_url(base_url)
+        .system("You add numbers.")
+        .tools(ToolHandler::default())
+        .max_rounds(1)
+        .build()
+        .unwrap();
+    let history = session.history().to_vec();
+
+    // The tool round runs, then the model runs out of rounds before it answers
+    let err = session.send("What is 20 + 22?").await.unwrap_err();
+    assert!(matches!(err, ChatError::TooManyRounds(1)));
+    assert_eq!(session.history(), history);
+    assert_eq!(session.usage().total_tokens(), 15);
+}
+
+#[test]
+fn session_requires_a_model() {
+    let err = ChatSession::builder().build().err().unwrap();
+    assert!(matches!(err, ChatError::Config(_)));
+}
+
+#[tokio::test]
+async fn provider_drives_a_chat_loop() {
+    let base_url = openai_server(Arc::default(), Arc::default()).await;
+    let provider = OpenAi::new("gpt-test").with_base_url(base_url);
+    assert_eq!(provider.model(), "gpt-test");
+
+    let handler = ToolHandler::default();
+    let mut messages = vec![json!({ "role": "user", "content": "What is 20 + 22?" })];
+    let response = ChatLoop::new(&handler, &provider)
+        .run(&mut messages)
+        .await
+        .unwrap();
+    assert_eq!(
+        response["choices"][0]["message"]["content"],
+        "The answer is 42"
+    );
+}
@@ -0,0 +1,190 @@
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tool_calling::{tool, wrap_sync, Tool, ToolError, ToolFilter, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn weather(city: String, days: Option<u32>) -> String {
+    format!("{} for {} days", city, days.unwrap_or(1))
+}
+
+#[tool]
+pub fn now() -> String {
+    "noon".to_string()
+}
+
+// Tests
+
+#[tokio::test]
+async fn string_encoded_arguments_are_decoded() {
+    let handler = ToolHandler::default();
+    let call = json!({
+        "id": "call_abc",
+        "type": "function",
+        "function": { "name": "weather", "arguments": "{\"city\":\"Oslo\",\"days\":3}" }
+    });
+    let res = handler.call_tool(&call).await.unwrap();
+    assert_eq!(res.output, "Oslo for 3 days");
+    assert_eq!(res.id.as_deref(), Some("call_abc"));
+
+    // Tools without parameters may receive an empty string
+    let call = json!({ "type": "function", "function": { "name": "now", "arguments": "" } });
+    assert_eq!(handler.call_tool(&call).await.unwrap().output, "noon");
+
+    let call = json!({ "type": "function", "function": { "name": "now", "arguments": "{oops" } });
+    let err = handler.call_tool(&call).await.unwrap_err();
+    assert!(
+        matches!(&err, ToolError::BadArgs(msg) if msg.starts_with("'arguments' is not valid JSON")),
+        "{:?}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn bare_function_objects_are_accepted() {
+    let handler = ToolHandler::default();
+    let function = json!({ "name": "weather", "arguments": "{\"city\":\"Lima\"}" });
+    let res = handler.call_tool(&function).await.unwrap();
+    assert_eq!(res.output, "Lima for 1 days");
+
+    let call = handler.validate_tool_call(&function).unwrap();
+    assert_eq!(call.args, vec![json!("Lima")]);
+
+    // Filters apply to the bare shape as well
+    let handler = handler.with_tool_filter(|_: &_| ToolFilter::except(["weather"]));
+    let err = handler.call_tool(&function).await.unwrap_err();
+    assert_eq!(err, ToolError::NotFound("weather".into()));
+}
+
+#[test]
+fn strict_schemas_require_every_property() {
+    let handler = ToolHandler::default();
+    let schema = handler.all_tools_schema_openai(true);
+    let weather = schema
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|tool| tool["function"]["name"] == "weather")
+        .unwrap();
+    assert_eq!(weather["type"], "function");
+    assert_eq!(weather["function"]["strict"], true);
+    let parameters = &weather["function"]["parameters"];
+    assert_eq!(parameters["required"], json!(["city", "days"]));
+    assert_eq!(parameters["additionalProperties"], false);
+    assert_eq!(parameters["properties"]["city"]["type"], "string");
+    assert_eq!(
+        parameters["properties"]["days"]["type"],
+        json!(["integer", "null"])
+    );
+
+    // Without strict mode the regular schema is returned
+    assert_eq!(
+        handler.all_tools_schema_openai(false),
+        handler.all_tools_schema()
+    );
+}
+
+#[test]
+fn strict_mode_reaches_nested_objects() {
+    let tool = Tool::new(
+        "book",
+        "Book a trip",
+        json!({
+            "type": "object",
+            "properties": {
+                "traveller": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "seat": { "enum": ["aisle", "window"], "default": "aisle" }
+                    },
+                    "required": ["name"]
+                },
+                "stops": {
+                    "type": "array",
+                    "items": { "type": "object", "properties": { "city": { "type": "string" } } }
+                }
+            },
+            "required": ["traveller", "stops"]
+        }),
+        ToolFn::Async(wrap_sync(Arc::new(|_: &[Value]| Ok(String::new())))),
+    );
+    let parameters = tool.render_openai_schema(true)["function"]["parameters"].clone();
+    let traveller = &parameters["properties"]["traveller"];
+    assert_eq!(traveller["required"], json!(["name", "seat"]));
+    assert_eq!(traveller["additionalProperties"], false);
+    assert_eq!(
+        traveller["properties"]["seat"],
+        json!({ "anyOf": [{ "enum": ["aisle", "window"] }, { "type": "null" }] })
+    );
+    let stop = &parameters["properties"]["stops"]["items"];
+    assert_eq!(stop["required"], json!(["city"]));
+    assert_eq!(
+        stop["properties"]["city"]["type"],
+        json!(["string", "null"])
+    );
+}
+
+fn assistant_message() -> Value {
+    json!({
+        "role": "assistant",
+        "content": null,
+        "tool_calls": [
+            { "id": "call_1", "type": "function", "function": { "name": "weather", "arguments": "{\"city\":\"Oslo\"}" } },
+            { "id": "call_2", "type": "function", "function": { "name": "now", "arguments": "{}" } },
+            { "id": "call_3", "type": "function", "function": { "name": "missing", "arguments": "{}" } }
+        ]
+    })
+}
+
+#[tokio::test]
+async fn every_parallel_call_is_answered_with_its_id() {
+    let handler = ToolHandler::default();
+    let results = handler.call_all(&assistant_message()).await;
+    assert_eq!(
+        results,
+        vec![
+            ("call_1".to_string(), Ok("Oslo for 1 days".to_string())),
+            ("call_2".to_string(), Ok("noon".to_string())),
+            (
+                "call_3".to_string(),
+                Err(ToolError::NotFound("missing".to_string()))
+            ),
+        ]
+    );
+
+    // A whole response works too, and so does the sequential mode
+    let response = json!({ "choices": [{ "message": assistant_message() }] });
+    let sequential = ToolHandler::default().with_sequential_calls();
+    assert_eq!(sequential.call_all(&response).await, results);
+
+    // Ollama sends calls without ids
+    let ollama = json!({ "tool_calls": [{ "function": { "name": "now", "arguments": {} } }] });
+    assert_eq!(
+        handler.call_all(&ollama).await,
+        vec![(String::new(), Ok("noon".to_string()))]
+    );
+    assert!(handler
+        .call_all(&json!({ "content": "hi" }))
+        .await
+        .is_empty());
+}
+
+#[tokio::test]
+async fn parallel_calls_become_tool_messages() {
+    let handler = ToolHandler::default();
+    let messages = handler
+        .answer_tool_calls(&assistant_message(), &Default::default())
+        .await;
+    assert_eq!(messages.len(), 3);
+    assert_eq!(
+        messages[1],
+        json!({ "role": "tool", "tool_call_id": "call_2", "content": "noon" })
+    );
+    assert_eq!(messages[2]["tool_call_id"], "call_3");
+    assert_eq!(
+        messages[2]["content"],
+        handler.error_message(&ToolError::NotFound("missing".to_string()))
+    );
+}
@@ -0,0 +1,69 @@
+use serde_json::json;
+use tool_calling::openapi::OpenApiOptions;
+use tool_calling::{tool, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool(tags("text"))]
+/// Shouts a message. Every letter is uppercased.
+pub fn shout(message: String, times: Option<u32>) -> String {
+    message.to_uppercase().repeat(times.unwrap_or(1) as usize)
+}
+
+#[tool]
+pub fn noop() -> String {
+    String::new()
+}
+
+// Tests
+
+#[test]
+fn every_tool_becomes_a_post_operation() {
+    let handler = ToolHandler::default();
+    let spec = handler.openapi_spec();
+    assert_eq!(spec["openapi"], "3.1.0");
+    assert_eq!(spec["info"]["title"], "Tools");
+    assert!(spec.get("servers").is_none());
+    assert_eq!(
+        spec["paths"].as_object().unwrap().len(),
+        handler.tools().count()
+    );
+
+    let shout = &spec["paths"]["/tools/shout"]["post"];
+    assert_eq!(shout["operationId"], "shout");
+    assert_eq!(shout["summary"], "Shouts a message.");
+    assert_eq!(
+        shout["description"],
+        "Shouts a message. Every letter is uppercased."
+    );
+    assert_eq!(shout["tags"], json!(["text"]));
+    assert_eq!(
+        shout["requestBody"]["content"]["application/json"]["schema"],
+        handler.get_tool("shout").unwrap().parameter_schema
+    );
+    assert_eq!(
+        shout["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/ToolCallResult"
+    );
+    assert!(spec["components"]["schemas"]["ToolError"].is_object());
+
+    let noop = &spec["paths"]["/tools/noop"]["post"];
+    assert!(noop.get("description").is_none());
+    assert!(noop.get("tags").is_none());
+}
+
+#[test]
+fn document_options() {
+    let options = OpenApiOptions::new("Agent tools", "2.0.0")
+        .with_description("Tools for the support agent")
+        .with_server("https://a.example.com")
+        .with_server("https://b.example.com")
+        .with_path_prefix("/api/");
+    let spec = ToolHandler::default().openapi_spec_with(&options);
+    assert_eq!(
+        spec["info"],
+        json!({ "title": "Agent tools", "version": "2.0.0", "description": "Tools for the support agent" })
+    );
+    assert_eq!(spec["servers"][1]["url"], "https://b.example.com");
+    assert!(spec["paths"]["/api/tools/shout"]["post"].is_object());
+}
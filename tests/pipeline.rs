@@ -0,0 +1,124 @@
+use serde_json::json;
+use tool_calling::pipeline::{Pipeline, PipelineError};
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Split text into words
+pub fn words(text: String) -> String {
+    serde_json::to_string(&text.split_whitespace().collect::<Vec<_>>()).unwrap()
+}
+
+#[tool]
+/// Count the items of a list
+pub fn count(items: Vec<String>) -> String {
+    items.len().to_string()
+}
+
+#[tool]
+pub fn scale(factor: i64, value: i64) -> String {
+    (factor * value).to_string()
+}
+
+#[tool]
+pub fn fail(_value: String) -> String {
+    panic!("always fails")
+}
+
+#[tool]
+pub fn nothing() -> String {
+    String::new()
+}
+
+// Tests
+
+#[tokio::test]
+async fn outputs_feed_the_next_step() {
+    let mut handler = ToolHandler::default();
+    let tool = Pipeline::new("scaled_word_count")
+        .then("words")
+        .then("count")
+        .then_with("scale", "value")
+        .build(&handler)
+        .unwrap();
+    assert_eq!(
+        tool.description,
+        "Runs words, then count, then scale in sequence, passing each step's output to the next."
+    );
+    handler.register(tool);
+
+    let schema = &handler
+        .get_tool("scaled_word_count")
+        .unwrap()
+        .parameter_schema;
+    assert_eq!(
+        schema["properties"],
+        json!({ "text": { "type": "string" }, "factor": { "type": "integer" } })
+    );
+    let call = json!({
+        "type": "function",
+        "function": {
+            "name": "scaled_word_count",
+            "arguments": { "text": "one two three", "factor": 10 }
+        }
+    });
+    // `words` returns a JSON list, which `count` receives as `Vec<String>`
+    let res = handler.call_tool(&call).await.unwrap();
+    assert_eq!(res.output, "30");
+}
+
+#[tokio::test]
+async fn step_failures_name_the_step() {
+    let mut handler = ToolHandler::default();
+    let tool = Pipeline::new("doomed")
+        .with_description("Always fails")
+        .then("words")
+        .then("fail")
+        .build(&handler)
+        .unwrap();
+    handler.register(tool);
+    let err = handler
+        .call_with_args("doomed", &["a b".into()])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ToolError::Execution("tool 'doomed' panicked: always fails".into())
+    );
+}
+
+#[test]
+fn invalid_pipelines_are_rejected() {
+    let handler = ToolHandler::default();
+    assert_eq!(
+        Pipeline::new("empty").build(&handler).unwrap_err(),
+        PipelineError::Empty("empty".into())
+    );
+    assert_eq!(
+        Pipeline::new("p")
+            .then("missing")
+            .build(&handler)
+            .unwrap_err(),
+        PipelineError::UnknownTool("missing".into())
+    );
+    assert_eq!(
+        Pipeline::new("p")
+            .then("words")
+            .then_with("scale", "amount")
+            .build(&handler)
+            .unwrap_err(),
+        PipelineError::UnknownParam {
+            tool: "scale".into(),
+            param: "amount".into()
+        }
+    );
+    assert_eq!(
+        Pipeline::new("p")
+            .then("words")
+            .then("nothing")
+            .build(&handler)
+            .unwrap_err(),
+        PipelineError::NoInput("nothing".into())
+    );
+}
@@ -0,0 +1,181 @@
+#![cfg(feature = "plugins")]
+
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tool_calling::plugin::{PluginError, PluginHost};
+use tool_calling::{ToolFn, ToolHandler};
+
+// Build the plugins for these tests
+
+struct Fixtures {
+    plugin: PathBuf,
+    mismatched: PathBuf,
+}
+
+// The fixture crate in tests/fixtures/plugin, built once as is and once claiming another
+// tool_calling version
+fn fixtures() -> &'static Fixtures {
+    static FIXTURES: OnceLock<Fixtures> = OnceLock::new();
+    FIXTURES.get_or_init(|| Fixtures {
+        plugin: build_plugin(&[]),
+        mismatched: build_plugin(&["mismatch"]),
+    })
+}
+
+fn build_plugin(features: &[&str]) -> PathBuf {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/plugin/Cargo.toml");
+    let target = Path::new(env!("CARGO_TARGET_TMPDIR")).join("plugin_fixture");
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let status = Command::new(cargo)
+        .arg("build")
+        .arg("--manifest-path")
+        .arg(&manifest)
+        .arg("--target-dir")
+        .arg(&target)
+        .arg("--features")
+        .arg(features.join(","))
+        .status()
+        .expect("cargo runs");
+    assert!(status.success(), "building the fixture plugin failed");
+
+    let file_name = format!(
+        "{}plugin_fixture{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_SUFFIX
+    );
+    // The next build overwrites the library, so keep a copy per feature set
+    let copy = target.join(format!("{}-{}", features.join("-"), file_name));
+    std::fs::copy(target.join("debug").join(&file_name), &copy).unwrap();
+    copy
+}
+
+// A copy of `library` this test can replace without affecting the others
+fn private_copy(library: &Path) -> PathBuf {
+    static COPIES: AtomicUsize = AtomicUsize::new(0);
+    let copy = library.with_file_name(format!(
+        "copy{}-{}",
+        COPIES.fetch_add(1, Ordering::Relaxed),
+        library.file_name().unwrap().to_string_lossy()
+    ));
+    std::fs::copy(library, &copy).unwrap();
+    copy
+}
+
+// Tests
+
+#[tokio::test]
+async fn plugins_are_loaded_and_called() {
+    let path = private_copy(&fixtures().plugin);
+    let mut handler = ToolHandler::empty();
+    let mut host = PluginHost::new();
+
+    let mut names = host.load(&path, &mut handler).unwrap();
+    names.sort();
+    assert_eq!(names, vec!["echo", "slow"]);
+    assert_eq!(host.loaded().collect::<Vec<_>>(), vec![path.as_path()]);
+    assert_eq!(
+        handler.get_tool("echo").unwrap().description,
+        "Echoes a word back"
+    );
+
+    let call = json!({ "type": "function", "function": { "name": "echo", "arguments": { "word": "hi" } } });
+    assert_eq!(
+        handler.call_tool(&call).await.unwrap().output,
+        "plugin says hi"
+    );
+    assert_eq!(
+        handler.call_with_args("slow", &["10".into()]).await,
+        Ok("waited 10ms".into())
+    );
+
+    host.unload(&path, &mut handler).unwrap();
+    assert_eq!(handler.tools().count(), 0);
+    assert!(matches!(
+        host.unload(&path, &mut handler),
+        Err(PluginError::NotLoaded(_))
+    ));
+}
+
+#[tokio::test]
+async fn calls_in_flight_outlive_an_unload() {
+    let path = private_copy(&fixtures().plugin);
+    let mut handler = ToolHandler::empty();
+    let mut host = PluginHost::new();
+    host.load(&path, &mut handler).unwrap();
+
+    // Start a call, then drop every tool: only the call itself holds the library open
+    let tool = handler.unregister("slow").unwrap();
+    let ToolFn::Async(function) = &tool.function else {
+        panic!("slow is an async tool");
+    };
+    let call = function(vec![json!(200)]);
+    drop(tool);
+    host.unload(&path, &mut handler).unwrap();
+    assert_eq!(call.await, Ok("waited 200ms".into()));
+}
+
+#[tokio::test]
+async fn changed_plugins_are_reloaded_while_calls_run() {
+    let path = private_copy(&fixtures().plugin);
+    let mut handler = ToolHandler::empty();
+    let mut host = PluginHost::new();
+    host.load(&path, &mut handler).unwrap();
+    assert!(host.reload_changed(&mut handler).unwrap().is_empty());
+
+    let running = handler.clone();
+    let call = tokio::spawn(async move { running.call_with_args("slow", &["200".into()]).await });
+
+    // Rewrite the file, making sure its modification time moves on
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    std::fs::copy(&fixtures().plugin, &path).unwrap();
+    assert_eq!(
+        host.reload_changed(&mut handler).unwrap(),
+        vec![path.clone()]
+    );
+
+    assert_eq!(
+        handler.call_with_args("echo", &["again".into()]).await,
+        Ok("plugin says again".into())
+    );
+    assert_eq!(call.await.unwrap(), Ok("waited 200ms".into()));
+    host.unload(&path, &mut handler).unwrap();
+}
+
+#[test]
+fn plugins_built_against_another_version_are_rejected() {
+    let mut handler = ToolHandler::empty();
+    let mut host = PluginHost::new();
+    let err = host.load(&fixtures().mismatched, &mut handler).unwrap_err();
+    match err {
+        PluginError::VersionMismatch {
+            expected, found, ..
+        } => {
+            assert_eq!(expected, tool_calling::plugin::CORE_VERSION);
+            assert_eq!(found, "0.0.0");
+        }
+        other => panic!("expected a version mismatch, got {:?}", other),
+    }
+    assert_eq!(handler.tools().count(), 0);
+    assert_eq!(host.loaded().count(), 0);
+}
+
+#[test]
+fn missing_or_invalid_libraries_are_errors() {
+    let mut handler = ToolHandler::empty();
+    let mut host = PluginHost::new();
+    let missing = Path::new(env!("CARGO_TARGET_TMPDIR")).join("no_such_plugin.so");
+    assert!(matches!(
+        host.load(&missing, &mut handler),
+        Err(PluginError::Io { .. })
+    ));
+    let invalid = Path::new(env!("CARGO_TARGET_TMPDIR")).join("not_a_plugin.so");
+    std::fs::write(&invalid, b"not a library").unwrap();
+    assert!(matches!(
+        host.load(&invalid, &mut handler),
+        Err(PluginError::Load { .. })
+    ));
+}
@@ -0,0 +1,59 @@
+use serde_json::json;
+use tool_calling::{tool, ToolContext, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Download some files
+pub async fn download(files: u32) -> String {
+    let ctx = ToolContext::current();
+    for i in 1..=files {
+        ctx.progress(
+            i as f64 / files as f64,
+            format!("downloaded {}/{}", i, files),
+        );
+    }
+    "done".to_string()
+}
+
+#[tool]
+pub fn overshoot(#[param(context)] ctx: ToolContext) -> String {
+    ctx.progress(1.5, "past the end");
+    "ok".to_string()
+}
+
+// Tests
+
+#[tokio::test]
+async fn progress_updates_reach_subscribers() {
+    let handler = ToolHandler::default();
+    let mut updates = handler.subscribe_progress();
+    let call = json!({
+        "type": "function",
+        "id": "call_1",
+        "function": { "name": "download", "arguments": { "files": 2 } }
+    });
+    let ctx = ToolContext::new().with_conversation_id("conv");
+    handler.call_tool_with_context(&call, &ctx).await.unwrap();
+
+    let first = updates.recv().await.unwrap();
+    assert_eq!(first.tool, "download");
+    assert_eq!(first.call_id.as_deref(), Some("call_1"));
+    assert_eq!(first.conversation_id.as_deref(), Some("conv"));
+    assert_eq!(first.fraction, 0.5);
+    assert_eq!(first.message, "downloaded 1/2");
+    let second = updates.recv().await.unwrap();
+    assert_eq!(second.fraction, 1.0);
+    assert!(updates.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn fractions_are_clamped_and_reporting_outside_calls_is_ignored() {
+    let handler = ToolHandler::default();
+    let mut updates = handler.clone().subscribe_progress();
+    handler.call_with_args("overshoot", &[]).await.unwrap();
+    assert_eq!(updates.recv().await.unwrap().fraction, 1.0);
+
+    ToolContext::current().progress(0.5, "not in a call");
+    assert!(updates.try_recv().is_err());
+}
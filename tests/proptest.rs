@@ -0,0 +1,96 @@
+#![cfg(feature = "proptest")]
+
+mod common;
+
+use common::call;
+use proptest::prelude::*;
+use serde_json::{json, Value};
+use tool_calling::{tool, Tool, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Repeats a word
+pub fn repeat(word: String, times: i32, separator: Option<String>) -> String {
+    vec![word; times.clamp(0, 4) as usize].join(&separator.unwrap_or_default())
+}
+
+#[tool]
+/// Averages some readings
+pub fn average(readings: Vec<f64>, precise: bool) -> String {
+    let mean = readings.iter().sum::<f64>() / readings.len().max(1) as f64;
+    if precise {
+        mean.to_string()
+    } else {
+        format!("{:.1}", mean)
+    }
+}
+
+fn constrained() -> Tool {
+    Tool::new(
+        "book",
+        "Books a room",
+        json!({
+            "type": "object",
+            "properties": {
+                "room": { "type": "string", "pattern": "^[A-C][0-9]{3}$" },
+                "guests": { "type": "integer", "minimum": 1, "maximum": 4 },
+                "rate": { "type": "number", "exclusiveMinimum": 0, "maximum": 500 },
+                "board": { "enum": ["none", "breakfast", "full"] },
+                "nights": { "type": "array", "items": { "type": "string", "minLength": 3 }, "minItems": 1, "maxItems": 7 }
+            },
+            "required": ["room", "guests", "nights"],
+            "additionalProperties": false
+        }),
+        ToolFn::Async(Box::new(|_| Box::pin(async { Ok("booked".to_string()) }))),
+    )
+}
+
+fn handler() -> ToolHandler {
+    ToolHandler::default().with_tool(constrained())
+}
+
+// Tests
+
+proptest! {
+    #[test]
+    fn macro_tool_arguments_validate(args in tool_calling::proptest::arguments(handler().get_tool("repeat").unwrap())) {
+        prop_assert!(handler().validate_tool_call(&call("repeat", args)).is_ok());
+    }
+
+    #[test]
+    fn nested_arguments_validate(args in tool_calling::proptest::arguments(handler().get_tool("average").unwrap())) {
+        prop_assert!(args["readings"].is_array());
+        prop_assert!(handler().validate_tool_call(&call("average", args)).is_ok());
+    }
+
+    #[test]
+    fn constrained_arguments_validate(args in tool_calling::proptest::arguments(&constrained())) {
+        let guests = args["guests"].as_i64().unwrap();
+        prop_assert!((1..=4).contains(&guests));
+        let nights = args["nights"].as_array().unwrap();
+        prop_assert!((1..=7).contains(&nights.len()));
+        if let Some(rate) = args.get("rate") {
+            prop_assert!(rate.as_f64().unwrap() > 0.0);
+        }
+        prop_assert!(handler().validate_tool_call(&call("book", args)).is_ok());
+    }
+
+    #[test]
+    fn tool_output_holds_a_property(args in tool_calling::proptest::arguments(handler().get_tool("repeat").unwrap())) {
+        let output = futures::executor::block_on(handler().call_tool(&call("repeat", args.clone())))
+            .unwrap()
+            .output;
+        let word = args["word"].as_str().unwrap();
+        let times = args["times"].as_i64().unwrap().clamp(0, 4) as usize;
+        let separator = args.get("separator").and_then(Value::as_str).unwrap_or_default();
+        prop_assert_eq!(output, vec![word; times].join(separator));
+    }
+
+    #[test]
+    fn values_follow_any_of(value in tool_calling::proptest::value(&json!({
+        "anyOf": [{ "type": "integer", "minimum": 10 }, { "type": "null" }]
+    }))) {
+        prop_assert!(value.is_null() || value.as_i64().unwrap() >= 10);
+    }
+}
@@ -0,0 +1,92 @@
+use serde_json::json;
+use tool_calling::pythonic::{parse_calls, PythonicCall};
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Get user info from database
+pub fn get_user_info(user_id: u32, fields: Option<Vec<String>>) -> String {
+    format!("user {} {:?}", user_id, fields.unwrap_or_default())
+}
+
+#[tool]
+pub fn now() -> String {
+    "noon".to_string()
+}
+
+// Tests
+
+#[test]
+fn python_literals_are_parsed() {
+    let calls = parse_calls(
+        r#"[search(query='it\'s "here"', limit=10, ratio=-1.5e2, exact=False, tags=("a", "b"), extra={'k': [None, True]}), now()]"#,
+    )
+    .unwrap();
+    assert_eq!(calls.len(), 2);
+    let search = &calls[0];
+    assert_eq!(search.name, "search");
+    assert!(search.args.is_empty());
+    assert_eq!(
+        serde_json::Value::Object(search.kwargs.clone()),
+        json!({
+            "query": "it's \"here\"",
+            "limit": 10,
+            "ratio": -150.0,
+            "exact": false,
+            "tags": ["a", "b"],
+            "extra": { "k": [null, true] }
+        })
+    );
+    assert_eq!(
+        calls[1],
+        PythonicCall {
+            name: "now".into(),
+            args: vec![],
+            kwargs: Default::default()
+        }
+    );
+
+    let call = &parse_calls("  functions.lookup(1, 'x',) ").unwrap()[0];
+    assert_eq!(call.name, "functions.lookup");
+    assert_eq!(call.args, vec![json!(1), json!("x")]);
+
+    for bad in [
+        "get_user_info(user_id=1",
+        "get_user_info(user_id=1) and more",
+        "get_user_info(user_id=1, 2)",
+        "get_user_info(user_id=1, user_id=2)",
+        "get_user_info(user_id=os.getcwd())",
+        "'not a call'",
+    ] {
+        let err = parse_calls(bad).unwrap_err();
+        assert!(matches!(err, ToolError::BadArgs(_)), "{}", bad);
+    }
+}
+
+#[tokio::test]
+async fn calls_are_matched_to_tools_and_validated() {
+    let handler = ToolHandler::default();
+    let calls = handler
+        .parse_pythonic_calls("[get_user_info(7, fields=['name']), now()]")
+        .unwrap();
+    assert_eq!(
+        calls[0],
+        json!({ "type": "function", "function": { "name": "get_user_info", "arguments": { "user_id": 7, "fields": ["name"] } } })
+    );
+    let outputs = futures::future::join_all(calls.iter().map(|call| handler.call_tool(call))).await;
+    assert_eq!(outputs[0].as_ref().unwrap().output, "user 7 [\"name\"]");
+    assert_eq!(outputs[1].as_ref().unwrap().output, "noon");
+
+    let err = handler.parse_pythonic_calls("missing()").unwrap_err();
+    assert_eq!(err, ToolError::NotFound("missing".into()));
+    for bad in [
+        "get_user_info(user_id='seven')",
+        "get_user_info(1, ['a'], 3)",
+        "get_user_info(1, user_id=2)",
+        "get_user_info()",
+    ] {
+        let err = handler.parse_pythonic_calls(bad).unwrap_err();
+        assert!(matches!(err, ToolError::BadArgs(_)), "{}", bad);
+    }
+}
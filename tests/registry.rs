@@ -1,4 +1,4 @@
-use tool_calling::{tool, tools};
+use tool_calling::{get_tool, tool, tools, ToolHandler};
 
 // Define a tool to ensure it's registered
 #[tool]
@@ -7,6 +7,12 @@ pub fn add(a: i32, b: i32) -> String {
     (a + b).to_string()
 }
 
+#[tool(tags("admin"))]
+/// Deletes everything.
+pub fn wipe() -> String {
+    "gone".into()
+}
+
 // Tests
 
 #[tokio::test]
@@ -18,3 +24,53 @@ async fn registry_enumerator() {
         // We could also check parameter schema here if needed
     }));
 }
+
+#[test]
+fn lookup_by_name() {
+    let add = get_tool("add").unwrap();
+    assert_eq!(add.name, "add");
+    assert_eq!(
+        add.parameter_schema["required"],
+        serde_json::json!(["a", "b"])
+    );
+    assert!(get_tool("subtract").is_none());
+    // Lookups and enumeration share one instance of each tool
+    assert!(std::ptr::eq(add, get_tool("add").unwrap()));
+    assert!(tools().iter().any(|tool| std::ptr::eq(*tool, add)));
+}
+
+#[test]
+fn handlers_share_the_registry_tools() {
+    let first = ToolHandler::default();
+    let second = ToolHandler::default();
+    let add = get_tool("add").unwrap();
+    assert!(std::ptr::eq(first.get_tool("add").unwrap(), add));
+    assert!(std::ptr::eq(second.get_tool("add").unwrap(), add));
+}
+
+#[tokio::test]
+async fn schema_for_whitelist() {
+    let handler = ToolHandler::default();
+    let schema = handler.tools_schema_for(&["wipe", "missing"]);
+    let funcs = schema.as_array().unwrap();
+    assert_eq!(funcs.len(), 1);
+    assert_eq!(funcs[0]["function"]["name"], "wipe");
+    assert_eq!(funcs[0]["type"], "function");
+}
+
+#[tokio::test]
+async fn schema_matching_predicate() {
+    let handler = ToolHandler::default();
+    let schema = handler.tools_schema_matching(|t| !t.tags.iter().any(|tag| tag == "admin"));
+    let names = schema
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["function"]["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["add"]);
+    assert_eq!(
+        handler.tools_schema_matching(|_| true),
+        handler.all_tools_schema()
+    );
+}
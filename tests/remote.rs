@@ -0,0 +1,339 @@
+#![cfg(feature = "remote")]
+
+mod common;
+
+use common::call;
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tool_calling::remote::{OpenApiToolProvider, RemoteError, RemoteToolProvider};
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Add two numbers locally
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+// Serve a small tool server: the manifest at `/tools`, `upper` at `/tools/upper` and a
+// failing `/tools/broken`
+async fn tool_server(manifest: Value) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}/tools", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let manifest = manifest.clone();
+            tokio::spawn(async move {
+                let (head, body) = read_request(&mut socket).await;
+                let path = head
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap_or_default()
+                    .to_string();
+                let (status, reply) = match path.as_str() {
+                    "/tools" => ("200 OK", manifest.to_string()),
+                    "/tools/upper" => {
+                        let args: Value = serde_json::from_str(&body).unwrap();
+                        ("200 OK", args["text"].as_str().unwrap().to_uppercase())
+                    }
+                    _ => ("500 Internal Server Error", "boom".to_string()),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    reply.len(),
+                    reply
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+    base
+}
+
+async fn read_request(socket: &mut tokio::net::TcpStream) -> (String, String) {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    loop {
+        let text = String::from_utf8_lossy(&request).into_owned();
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    line.to_lowercase()
+                        .strip_prefix("content-length: ")
+                        .map(str::to_owned)
+                })
+                .and_then(|len| len.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            if body.len() >= length {
+                return (head.to_string(), body.to_string());
+            }
+        }
+        let n = socket.read(&mut buf).await.unwrap();
+        if n == 0 {
+            return (String::new(), String::new());
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+}
+
+// Serve an API that echoes each request's method, target, `x-trace` header and body
+async fn echo_server() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}/v1", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let (head, body) = read_request(&mut socket).await;
+                let mut request_line = head.split_whitespace();
+                let method = request_line.next().unwrap_or_default();
+                let target = request_line.next().unwrap_or_default();
+                let trace = head
+                    .lines()
+                    .find_map(|line| {
+                        line.to_lowercase()
+                            .strip_prefix("x-trace: ")
+                            .map(str::to_owned)
+                    })
+                    .unwrap_or_default();
+                let (status, reply) = match target == "/v1/pets/missing" {
+                    true => ("404 Not Found", "no such pet".to_string()),
+                    false => (
+                        "200 OK",
+                        format!("{} {} [{}] {}", method, target, trace, body),
+                    ),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    reply.len(),
+                    reply
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+    base
+}
+
+fn petstore() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": { "title": "Petstore", "version": "1.0.0" },
+        "paths": {
+            "/pets": {
+                "get": {
+                    "operationId": "listPets",
+                    "summary": "List all pets",
+                    "tags": ["pets"],
+                    "parameters": [
+                        { "name": "limit", "in": "query", "description": "How many to return", "schema": { "type": "integer" } },
+                        { "name": "x-trace", "in": "header", "schema": { "type": "string" } }
+                    ]
+                },
+                "post": {
+                    "operationId": "createPet",
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } } }
+                    }
+                }
+            },
+            "/pets/{petId}": {
+                "parameters": [{ "$ref": "#/components/parameters/PetId" }],
+                "get": { "summary": "Info for a specific pet" },
+                "put": {
+                    "operationId": "replacePet",
+                    "requestBody": {
+                        "content": { "application/json": { "schema": { "type": "array", "items": { "type": "string" } } } }
+                    }
+                }
+            }
+        },
+        "components": {
+            "parameters": {
+                "PetId": { "name": "petId", "in": "path", "required": true, "schema": { "type": "string" } }
+            },
+            "schemas": {
+                "Pet": {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" }, "tag": { "type": "string" } },
+                    "required": ["name"]
+                }
+            }
+        }
+    })
+}
+
+fn manifest() -> Value {
+    json!([
+        {
+            "type": "function",
+            "function": {
+                "name": "upper",
+                "description": "Upper-case a text",
+                "parameters": {
+                    "type": "object",
+                    "properties": { "text": { "type": "string" } },
+                    "required": ["text"]
+                }
+            }
+        },
+        { "name": "broken", "description": "Always fails", "parameter_schema": { "type": "object", "properties": {} } }
+    ])
+}
+
+// Tests
+
+#[tokio::test]
+async fn remote_tools_are_proxied() {
+    let base = tool_server(manifest()).await;
+    let mut handler = ToolHandler::default();
+    let names = RemoteToolProvider::new(&base)
+        .load(&mut handler)
+        .await
+        .unwrap();
+    assert_eq!(names, vec!["upper", "broken"]);
+    assert_eq!(
+        handler.get_tool("upper").unwrap().description,
+        "Upper-case a text"
+    );
+
+    let call = json!({
+        "type": "function",
+        "function": { "name": "upper", "arguments": { "text": "hi" } }
+    });
+    assert_eq!(handler.call_tool(&call).await.unwrap().output, "HI");
+    // Local tools are still served alongside the proxies
+    assert_eq!(
+        handler
+            .call_with_args("add", &["1".into(), "2".into()])
+            .await,
+        Ok("3".into())
+    );
+    let err = handler.call_with_args("broken", &[]).await.unwrap_err();
+    assert_eq!(
+        err,
+        ToolError::Execution("HTTP 500 Internal Server Error: boom".into())
+    );
+}
+
+#[tokio::test]
+async fn only_selected_tools_are_proxied() {
+    let base = tool_server(json!({ "tools": manifest() })).await;
+    let tools = RemoteToolProvider::new(&base)
+        .select(["upper"])
+        .fetch()
+        .await
+        .unwrap();
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0].name, "upper");
+}
+
+#[tokio::test]
+async fn bad_manifests_are_reported() {
+    let base = tool_server(json!({ "not": "a manifest" })).await;
+    let err = RemoteToolProvider::new(&base).fetch().await.unwrap_err();
+    assert!(matches!(err, RemoteError::Manifest(_)));
+
+    let err = RemoteToolProvider::new(format!("{}/missing", base))
+        .fetch()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, RemoteError::Http(_)));
+}
+
+#[test]
+fn openapi_operations_become_tools() {
+    let tools = OpenApiToolProvider::new(petstore(), "http://localhost/v1")
+        .tools()
+        .unwrap();
+    let names = tools
+        .iter()
+        .map(|tool| tool.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["listPets", "createPet", "get_pets_petId", "replacePet"]
+    );
+
+    let list = &tools[0];
+    assert_eq!(list.description, "List all pets");
+    assert_eq!(list.tags, vec!["pets"]);
+    assert_eq!(
+        list.parameter_schema,
+        json!({
+            "type": "object",
+            "properties": {
+                "limit": { "type": "integer", "description": "How many to return" },
+                "x-trace": { "type": "string" }
+            },
+            "required": []
+        })
+    );
+    // Object bodies are flattened into the arguments, others passed as `body`
+    assert_eq!(
+        tools[1].parameter_schema["properties"]["name"],
+        json!({ "type": "string" })
+    );
+    assert_eq!(tools[1].parameter_schema["required"], json!(["name"]));
+    assert_eq!(tools[3].parameter_schema["required"], json!(["petId"]));
+    assert_eq!(
+        tools[3].parameter_schema["properties"]["body"]["type"],
+        "array"
+    );
+
+    let selected = OpenApiToolProvider::new(petstore(), "http://localhost/v1")
+        .select(["createPet"])
+        .tools()
+        .unwrap();
+    assert_eq!(selected.len(), 1);
+
+    let err = OpenApiToolProvider::new(json!({ "openapi": "3.1.0" }), "http://localhost")
+        .tools()
+        .unwrap_err();
+    assert!(matches!(err, RemoteError::Spec(_)));
+}
+
+#[tokio::test]
+async fn openapi_tools_send_http_requests() {
+    let base = echo_server().await;
+    let mut handler = ToolHandler::default();
+    OpenApiToolProvider::new(petstore(), &base)
+        .load(&mut handler)
+        .unwrap();
+
+    let result = handler
+        .call_tool(&call("listPets", json!({ "limit": 2, "x-trace": "abc" })))
+        .await
+        .unwrap();
+    assert_eq!(result.output, "GET /v1/pets?limit=2 [abc] ");
+
+    let result = handler
+        .call_tool(&call("createPet", json!({ "name": "Rex" })))
+        .await
+        .unwrap();
+    assert_eq!(result.output, r#"POST /v1/pets [] {"name":"Rex"}"#);
+
+    let result = handler
+        .call_tool(&call(
+            "replacePet",
+            json!({ "petId": "a b", "body": ["x"] }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(result.output, r#"PUT /v1/pets/a%20b [] ["x"]"#);
+
+    let err = handler
+        .call_tool(&call("get_pets_petId", json!({ "petId": "missing" })))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ToolError::Execution("HTTP 404 Not Found: no such pet".into())
+    );
+}
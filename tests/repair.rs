@@ -0,0 +1,101 @@
+mod common;
+
+use common::call;
+use serde_json::json;
+use tool_calling::repair::{repair_json, Repair};
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up the weather
+pub fn weather(city: String, days: Option<u32>) -> String {
+    format!("sunny in {} for {} days", city, days.unwrap_or(1))
+}
+
+// Tests
+
+#[test]
+fn common_mistakes_are_repaired() {
+    let valid = repair_json("{\"a\": [1, 2]}").unwrap();
+    assert_eq!(valid.value, json!({ "a": [1, 2] }));
+    assert!(valid.repairs.is_empty());
+
+    let repaired = repair_json(
+        "Sure! Here are the arguments:\n```json\n{'note': \"it's here\", ok: True, skip: None, 'quote': 'say \\'hi\\''}\n```",
+    )
+    .unwrap();
+    assert_eq!(
+        repaired.value,
+        json!({ "note": "it's here", "ok": true, "skip": null, "quote": "say 'hi'" })
+    );
+    assert_eq!(
+        repaired.repairs,
+        vec![
+            Repair::CodeFence,
+            Repair::SingleQuotes,
+            Repair::UnquotedKeys,
+            Repair::PythonLiterals
+        ]
+    );
+
+    let truncated = repair_json("{\"city\": \"Par").unwrap();
+    assert_eq!(truncated.value, json!({ "city": "Par" }));
+    assert_eq!(truncated.repairs, vec![Repair::UnclosedBrackets]);
+    let truncated = repair_json("{\"days\": [1, 2,").unwrap();
+    assert_eq!(truncated.value, json!({ "days": [1, 2] }));
+
+    // Text inside strings is left alone
+    let repaired = repair_json("{'text': 'a, b,] True'}").unwrap();
+    assert_eq!(repaired.value, json!({ "text": "a, b,] True" }));
+
+    let err = repair_json("{\"city\" \"Paris\"}").unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+    assert_eq!(
+        Repair::TrailingCommas.to_string(),
+        "removed trailing commas"
+    );
+}
+
+#[tokio::test]
+async fn handlers_repair_arguments_when_asked() {
+    let strict = ToolHandler::default();
+    let err = strict
+        .call_tool(&call("weather", json!("{city: 'Oslo', days: 3,}")))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+
+    let handler = ToolHandler::default().with_argument_repair();
+    let result = handler
+        .call_tool(&call("weather", json!("{city: 'Oslo', days: 3,}")))
+        .await
+        .unwrap();
+    assert_eq!(result.output, "sunny in Oslo for 3 days");
+    assert_eq!(
+        result.repairs,
+        vec![
+            Repair::UnquotedKeys,
+            Repair::SingleQuotes,
+            Repair::TrailingCommas
+        ]
+    );
+
+    let result = handler
+        .call_tool(&call("weather", json!("{\"city\": \"Oslo\"}")))
+        .await
+        .unwrap();
+    assert!(result.repairs.is_empty());
+
+    let validated = handler
+        .validate_tool_call(&json!({ "name": "weather", "arguments": "{'city': 'Rome'}" }))
+        .unwrap();
+    assert_eq!(validated.args, vec![json!("Rome")]);
+    assert_eq!(validated.repairs, vec![Repair::SingleQuotes]);
+
+    let err = handler
+        .call_tool(&call("weather", json!("{city Oslo}")))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+}
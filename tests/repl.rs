@@ -0,0 +1,105 @@
+#![cfg(feature = "repl")]
+
+use tool_calling::repl::{repl_with, ToolCompleter};
+use tool_calling::{tool, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Adds two integers
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+#[tool]
+/// Describes a user
+pub fn user_info(id: u32) -> String {
+    format!(r#"{{"id":{},"name":"Ada"}}"#, id)
+}
+
+#[tool]
+/// Fails every time
+pub fn always_fails() -> String {
+    panic!("out of order")
+}
+
+async fn session(input: &str) -> String {
+    let mut out = Vec::new();
+    repl_with(&ToolHandler::default(), input.as_bytes(), &mut out)
+        .await
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+// Tests
+
+#[tokio::test]
+async fn calls_tools_and_prints_results() {
+    let out = session("add {\"a\": 1, \"b\": 2}\n\nuser_info {\"id\": 7}\n").await;
+    assert_eq!(out, "3\n{\n  \"id\": 7,\n  \"name\": \"Ada\"\n}\n");
+}
+
+#[tokio::test]
+async fn prints_errors_and_keeps_going() {
+    let out = session(
+        "add {\"a\": \"one\", \"b\": 2}\nadd {\nmissing\nalways_fails\nadd {\"a\": 2, \"b\": 2}\n",
+    )
+    .await;
+    let lines = out.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("error: invalid arguments"), "{}", out);
+    assert!(
+        lines[1].starts_with("error: arguments are not valid JSON"),
+        "{}",
+        out
+    );
+    assert_eq!(lines[2], "error: tool not found: missing");
+    assert!(lines[3].contains("out of order"), "{}", out);
+    assert_eq!(lines[4], "4");
+}
+
+#[tokio::test]
+async fn runs_commands() {
+    let out = session(":tools\n").await;
+    assert!(out.contains("add           Adds two integers\n"), "{}", out);
+
+    let out = session(":schema add\n").await;
+    let schema: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(schema["function"]["name"], "add");
+
+    let out = session(":help\n:frobnicate\n").await;
+    assert!(out.contains(":schema <tool>"), "{}", out);
+    assert!(
+        out.ends_with("error: unknown command :frobnicate, see :help\n"),
+        "{}",
+        out
+    );
+}
+
+#[tokio::test]
+async fn stops_at_quit() {
+    let out = session("add {\"a\": 1, \"b\": 1}\n:quit\nadd {\"a\": 2, \"b\": 2}\n").await;
+    assert_eq!(out, "2\n");
+}
+
+#[test]
+fn completes_tool_names_and_commands() {
+    let completer = ToolCompleter::new(&ToolHandler::default());
+    assert_eq!(
+        completer.candidates("a", 1),
+        (0, vec!["add".to_string(), "always_fails".to_string()])
+    );
+    assert_eq!(
+        completer.candidates("us", 2),
+        (0, vec!["user_info".to_string()])
+    );
+    assert_eq!(
+        completer.candidates(":schema ad", 10),
+        (8, vec!["add".to_string()])
+    );
+    assert_eq!(
+        completer.candidates(":s", 2),
+        (0, vec![":schema".to_string()])
+    );
+    // Arguments aren't completed
+    assert_eq!(completer.candidates("add a", 5), (4, Vec::new()));
+}
@@ -0,0 +1,168 @@
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use tool_calling::chat::{ChatError, ChatLoop, ChatProvider};
+use tool_calling::mcp::McpServer;
+use tool_calling::{
+    anthropic, bedrock, tool, SchemaFlavor, ToolContext, ToolHandler, ToolResultContent,
+};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Renders a chart of the given series
+pub fn chart(series: String) -> String {
+    ToolResultContent::to_output(&[
+        ToolResultContent::text(format!("Chart of {}", series)),
+        ToolResultContent::image("iVBORw0KGgo=", "image/png"),
+        ToolResultContent::json(json!({ "points": 3 })),
+        ToolResultContent::Resource {
+            uri: "file:///charts/1.png".to_string(),
+            name: Some("chart.png".to_string()),
+            mime_type: Some("image/png".to_string()),
+        },
+    ])
+}
+
+#[tool]
+pub fn echo(text: String) -> String {
+    text
+}
+
+// A model that calls `chart` once, then answers
+struct Charting(SchemaFlavor);
+
+impl ChatProvider for Charting {
+    fn chat<'a>(
+        &'a self,
+        messages: &'a [Value],
+        _tools: &'a [Value],
+    ) -> BoxFuture<'a, Result<Value, ChatError>> {
+        let reply = match messages.last() {
+            Some(last) if last["role"] == "tool" => {
+                json!({ "role": "assistant", "content": "Done." })
+            }
+            _ => json!({ "role": "assistant", "tool_calls": [
+                { "id": "call_1", "type": "function", "function": { "name": "chart", "arguments": "{\"series\":\"sales\"}" } }
+            ] }),
+        };
+        Box::pin(async move { Ok(reply) })
+    }
+
+    fn flavor(&self) -> SchemaFlavor {
+        self.0
+    }
+}
+
+// Tests
+
+#[test]
+fn content_round_trips_through_the_output() {
+    let content = vec![
+        ToolResultContent::text("hi"),
+        ToolResultContent::resource("https://example.com/a"),
+    ];
+    let output = ToolResultContent::to_output(&content);
+    assert_eq!(ToolResultContent::from_output(&output), content);
+    assert_eq!(
+        ToolResultContent::from_output("{\"other\": 1}"),
+        vec![ToolResultContent::text("{\"other\": 1}")]
+    );
+    assert_eq!(
+        serde_json::to_value(&content[1]).unwrap(),
+        json!({ "type": "resource", "uri": "https://example.com/a" })
+    );
+}
+
+#[tokio::test]
+async fn providers_receive_their_own_blocks() {
+    let handler = ToolHandler::default();
+    let context = ToolContext::default();
+
+    let content = json!([
+        { "type": "tool_use", "id": "toolu_1", "name": "chart", "input": { "series": "sales" } },
+        { "type": "tool_use", "id": "toolu_2", "name": "echo", "input": { "text": "plain" } }
+    ]);
+    let answers = handler.answer_tool_uses(&content, &context).await;
+    let blocks = &answers[0]["content"];
+    assert_eq!(
+        blocks[0],
+        json!({ "type": "text", "text": "Chart of sales" })
+    );
+    assert_eq!(
+        blocks[1],
+        json!({ "type": "image", "source": { "type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo=" } })
+    );
+    assert_eq!(blocks[2]["text"], "{\"points\":3}");
+    assert_eq!(
+        answers[1],
+        anthropic::tool_result("toolu_2", "plain", false)
+    );
+
+    let response = json!({ "output": { "message": { "content": [
+        { "toolUse": { "toolUseId": "t1", "name": "chart", "input": { "series": "sales" } } }
+    ] } } });
+    let answers = handler.answer_bedrock_tool_uses(&response, &context).await;
+    let blocks = &answers[0]["toolResult"]["content"];
+    assert_eq!(
+        blocks[1],
+        json!({ "image": { "format": "png", "source": { "bytes": "iVBORw0KGgo=" } } })
+    );
+    assert_eq!(blocks[2], json!({ "json": { "points": 3 } }));
+    assert_eq!(
+        bedrock::tool_result_content("t1", &[ToolResultContent::text("ok")]),
+        bedrock::tool_result("t1", "ok", false)
+    );
+
+    let message = json!({ "role": "assistant", "tool_calls": [
+        { "id": "call_1", "type": "function", "function": { "name": "chart", "arguments": "{\"series\": \"sales\"}" } }
+    ] });
+    let answers = handler.answer_tool_calls(&message, &context).await;
+    assert_eq!(
+        answers[0]["content"],
+        "Chart of sales\n[image: image/png]\n{\"points\":3}\nfile:///charts/1.png"
+    );
+}
+
+#[tokio::test]
+async fn mcp_results_carry_images_and_resource_links() {
+    let server = McpServer::new(ToolHandler::default());
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": { "name": "chart", "arguments": { "series": "sales" } }
+    });
+    let response = server.handle_message(&request).await.unwrap();
+    let blocks = &response["result"]["content"];
+    assert_eq!(
+        blocks[1],
+        json!({ "type": "image", "data": "iVBORw0KGgo=", "mimeType": "image/png" })
+    );
+    assert_eq!(
+        blocks[3],
+        json!({ "type": "resource_link", "uri": "file:///charts/1.png", "name": "chart.png", "mimeType": "image/png" })
+    );
+    assert_eq!(response["result"]["isError"], false);
+}
+
+#[tokio::test]
+async fn result_images_reach_ollama_tool_messages() {
+    let handler = ToolHandler::default();
+    let mut messages = vec![];
+    ChatLoop::new(&handler, &Charting(SchemaFlavor::Ollama))
+        .run(&mut messages)
+        .await
+        .unwrap();
+    assert_eq!(messages[1]["images"], json!(["iVBORw0KGgo="]));
+    assert!(messages[1]["content"]
+        .as_str()
+        .unwrap()
+        .starts_with("Chart of sales"));
+
+    let mut messages = vec![];
+    ChatLoop::new(&handler, &Charting(SchemaFlavor::OpenAi))
+        .run(&mut messages)
+        .await
+        .unwrap();
+    assert!(messages[1].get("images").is_none());
+}
@@ -0,0 +1,185 @@
+#![cfg(all(feature = "sandbox", unix))]
+
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tool_calling::sandbox::{run_worker_if_requested, Sandbox, SandboxLimits};
+use tool_calling::{tool, Tool, ToolError, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn pid() -> String {
+    // Noise on stdout does not confuse the host
+    println!("working...");
+    std::process::id().to_string()
+}
+
+#[tool]
+pub fn crash() -> String {
+    std::process::abort()
+}
+
+#[tool]
+pub fn hog(megabytes: usize) -> String {
+    let block = vec![1u8; megabytes * 1024 * 1024];
+    block.iter().map(|b| *b as usize).sum::<usize>().to_string()
+}
+
+#[tool]
+pub fn hang() -> String {
+    std::thread::sleep(Duration::from_secs(30));
+    String::new()
+}
+
+#[tool]
+pub fn flood(kilobytes: usize) -> String {
+    print!("{}", "x".repeat(kilobytes * 1024));
+    "done".to_string()
+}
+
+#[tool]
+pub fn boom() -> String {
+    panic!("oh no")
+}
+
+// Workers re-run this test binary, filtered down to this test, which serves the request
+#[test]
+fn sandbox_worker() {
+    run_worker_if_requested();
+}
+
+fn sandbox(limits: SandboxLimits) -> Sandbox {
+    Sandbox::new()
+        .with_program(
+            std::env::current_exe().unwrap(),
+            [
+                "--exact",
+                "sandbox_worker",
+                "--nocapture",
+                "--test-threads=1",
+            ],
+        )
+        .with_limits(limits)
+}
+
+fn sandboxed(names: &[&str], sandbox: &Sandbox) -> ToolHandler {
+    let mut handler = ToolHandler::default();
+    for name in names {
+        handler.sandbox(name, sandbox).unwrap();
+    }
+    handler
+}
+
+fn execution_error(result: Result<String, ToolError>) -> String {
+    match result {
+        Err(ToolError::Execution(msg)) => msg,
+        other => panic!("expected an execution error, got {:?}", other),
+    }
+}
+
+// Tests
+
+#[tokio::test]
+async fn tools_run_in_a_separate_process() {
+    let handler = sandboxed(&["pid", "boom"], &sandbox(SandboxLimits::default()));
+    let call = json!({ "type": "function", "function": { "name": "pid", "arguments": {} } });
+    let res = handler.call_tool(&call).await.unwrap();
+    assert_ne!(res.output, std::process::id().to_string());
+
+    // Errors from the worker are passed through unchanged
+    let err = handler.call_with_args("boom", &[]).await.unwrap_err();
+    assert_eq!(
+        err,
+        ToolError::Execution("tool 'boom' panicked: oh no".into())
+    );
+}
+
+#[tokio::test]
+async fn crashes_only_take_down_the_worker() {
+    let handler = sandboxed(&["crash"], &sandbox(SandboxLimits::default()));
+    let msg = execution_error(handler.call_with_args("crash", &[]).await);
+    assert_eq!(msg, "sandboxed tool 'crash' was terminated by signal 6");
+}
+
+#[tokio::test]
+async fn memory_limits_stop_hogs() {
+    let limits = SandboxLimits {
+        max_memory_bytes: Some(1024 * 1024 * 1024),
+        ..SandboxLimits::default()
+    };
+    let handler = sandboxed(&["hog"], &sandbox(limits));
+    assert_eq!(
+        handler.call_with_args("hog", &["1".into()]).await.unwrap(),
+        (1024 * 1024).to_string()
+    );
+    let msg = execution_error(handler.call_with_args("hog", &["4096".into()]).await);
+    assert!(msg.starts_with("sandboxed tool 'hog'"), "{}", msg);
+}
+
+#[tokio::test]
+async fn timeouts_and_the_kill_switch_stop_workers() {
+    let limits = SandboxLimits {
+        timeout: Some(Duration::from_millis(300)),
+        ..SandboxLimits::default()
+    };
+    let handler = sandboxed(&["hang"], &sandbox(limits));
+    let msg = execution_error(handler.call_with_args("hang", &[]).await);
+    assert_eq!(msg, "sandboxed tool 'hang' timed out after 300ms");
+
+    let sandbox = sandbox(SandboxLimits::default());
+    let handler = sandboxed(&["hang"], &sandbox);
+    let started = Instant::now();
+    let call = tokio::spawn(async move { handler.call_with_args("hang", &[]).await });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    sandbox.kill_all();
+    let msg = execution_error(call.await.unwrap());
+    assert_eq!(msg, "sandboxed tool 'hang' was killed");
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn output_limits_stop_floods() {
+    let limits = SandboxLimits {
+        max_output_bytes: Some(64 * 1024),
+        ..SandboxLimits::default()
+    };
+    let handler = sandboxed(&["flood"], &sandbox(limits));
+    assert_eq!(
+        handler
+            .call_with_args("flood", &["1".into()])
+            .await
+            .unwrap(),
+        "done"
+    );
+    let msg = execution_error(handler.call_with_args("flood", &["1024".into()]).await);
+    assert_eq!(
+        msg,
+        "sandboxed tool 'flood' wrote more than 65536 bytes of output"
+    );
+}
+
+#[test]
+fn only_registered_tools_can_be_sandboxed() {
+    let mut handler = ToolHandler::default();
+    assert_eq!(
+        handler.sandbox("missing", &Sandbox::new()),
+        Err(ToolError::NotFound("missing".into()))
+    );
+
+    // The worker only knows `#[tool]` functions, so tools added at runtime stay in process
+    let runtime = Tool::new(
+        "runtime",
+        "Added at runtime",
+        json!({ "type": "object", "properties": {} }),
+        ToolFn::Async(Box::new(|_| Box::pin(async { Ok("ok".to_string()) }))),
+    );
+    handler.register(runtime);
+    assert_eq!(
+        handler.sandbox("runtime", &Sandbox::new()),
+        Err(ToolError::NotFound("runtime".into()))
+    );
+    assert_eq!(
+        handler.get_tool("runtime").unwrap().description,
+        "Added at runtime"
+    );
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::call;
+use serde_json::{json, Value};
+use tool_calling::{SchemaDraft, Tool, ToolError, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+// A tool written against Draft 2020-12
+fn move_tool() -> Tool {
+    Tool::new(
+        "move_to",
+        "Moves to a point",
+        json!({
+            "type": "object",
+            "properties": {
+                "point": {
+                    "type": "array",
+                    "prefixItems": [{ "type": "number" }, { "type": "number" }],
+                    "items": false
+                },
+                "speed": { "$ref": "#/$defs/speed" }
+            },
+            "required": ["point"],
+            "dependentRequired": { "speed": ["point"] },
+            "$defs": { "speed": { "type": "integer", "minimum": 1 } }
+        }),
+        ToolFn::Async(Box::new(|args: Vec<Value>| {
+            let point = args[0].to_string();
+            Box::pin(async move { Ok(point) })
+        })),
+    )
+}
+
+// Tests
+
+#[test]
+fn keywords_are_rewritten_between_drafts() {
+    let tool = move_tool();
+    let draft7 = tool.schema_for_draft(SchemaDraft::Draft7);
+    assert_eq!(
+        draft7,
+        json!({
+            "type": "object",
+            "properties": {
+                "point": {
+                    "type": "array",
+                    "items": [{ "type": "number" }, { "type": "number" }],
+                    "additionalItems": false
+                },
+                "speed": { "$ref": "#/definitions/speed" }
+            },
+            "required": ["point"],
+            "definitions": { "speed": { "type": "integer", "minimum": 1 } },
+            "dependencies": { "speed": ["point"] }
+        })
+    );
+
+    let round_trip = Tool::new(
+        "move_to",
+        "",
+        draft7,
+        ToolFn::Async(Box::new(|_| unreachable!())),
+    );
+    assert_eq!(
+        round_trip.schema_for_draft(SchemaDraft::Draft202012),
+        tool.parameter_schema
+    );
+    // 2019-09 keeps tuple `items` but uses `$defs`
+    let draft2019 = tool.schema_for_draft(SchemaDraft::Draft201909);
+    assert!(draft2019["properties"]["point"]["items"].is_array());
+    assert_eq!(draft2019["properties"]["speed"]["$ref"], "#/$defs/speed");
+    assert_eq!(
+        SchemaDraft::Draft202012.meta_schema(),
+        "https://json-schema.org/draft/2020-12/schema"
+    );
+}
+
+#[tokio::test]
+async fn arguments_are_validated_in_the_chosen_draft() {
+    let handler = ToolHandler::empty()
+        .with_tool(move_tool())
+        .with_schema_draft(SchemaDraft::Draft202012);
+    let res = handler
+        .call_tool(&call("move_to", json!({ "point": [1, 2] })))
+        .await;
+    assert_eq!(res.unwrap().output, "[1,2]");
+
+    for bad in [
+        json!({ "point": ["north", 2] }),
+        json!({ "point": [1, 2, 3] }),
+        json!({ "point": [1, 2], "speed": 0 }),
+    ] {
+        let err = handler.call_tool(&call("move_to", bad)).await.unwrap_err();
+        assert!(matches!(err, ToolError::BadArgs(_)), "{:?}", err);
+    }
+
+    assert_eq!(ToolHandler::default().schema_draft(), SchemaDraft::Draft7);
+}
+
+#[test]
+fn schemas_are_rendered_in_the_chosen_draft() {
+    let handler = ToolHandler::empty().with_tool(move_tool());
+    let written = handler.all_tools_schema();
+    assert_eq!(
+        written[0]["function"]["parameters"],
+        move_tool().parameter_schema
+    );
+
+    let handler = handler.with_schema_draft(SchemaDraft::Draft7);
+    let point = &handler.all_tools_schema()[0]["function"]["parameters"]["properties"]["point"];
+    assert!(point["items"].is_array());
+    let point = &handler.anthropic_tools()[0]["input_schema"]["properties"]["point"];
+    assert_eq!(point["additionalItems"], false);
+}
@@ -0,0 +1,131 @@
+use futures::future;
+use serde_json::json;
+use tool_calling::{tool, SchemaFlavor, Tool, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up the weather
+pub fn weather(city: String, days: Option<u32>) -> String {
+    format!("{} {:?}", city, days)
+}
+
+fn verbose_tool() -> Tool {
+    Tool::new(
+        "book",
+        "Books a table. Tables are held for 15 minutes!\nCancel with `cancel`.",
+        json!({
+            "type": "object",
+            "properties": {
+                "guests": {
+                    "type": ["integer", "null"],
+                    "description": "How many people are coming. Defaults to two.",
+                    "default": 2
+                },
+                "seating": {
+                    "anyOf": [{ "type": "string", "enum": ["inside", "outside"] }, { "type": "null" }],
+                    "description": "Where to sit"
+                },
+                "notes": {
+                    "type": "array",
+                    "items": { "type": "string", "description": "One note. Keep it short.", "default": "" }
+                }
+            },
+            "required": []
+        }),
+        ToolFn::Async(Box::new(|args| {
+            Box::pin(future::ready(Ok(format!("{} args", args.len()))))
+        })),
+    )
+}
+
+// Tests
+
+#[tokio::test]
+async fn openai_and_ollama_shapes() {
+    let handler = ToolHandler::default();
+    let tool = handler.get_tool("weather").unwrap();
+    let openai = tool.render_schema(SchemaFlavor::OpenAi);
+    assert_eq!(openai["type"], "function");
+    assert_eq!(openai["function"]["name"], "weather");
+    assert_eq!(openai["function"]["parameters"], tool.parameter_schema);
+    assert_eq!(tool.render_schema(SchemaFlavor::Ollama), openai);
+    assert_eq!(handler.all_tools_schema()[0], openai);
+}
+
+#[tokio::test]
+async fn anthropic_shape() {
+    let handler = ToolHandler::default();
+    let tool = handler.get_tool("weather").unwrap();
+    assert_eq!(
+        tool.render_schema(SchemaFlavor::Anthropic),
+        json!({
+            "name": "weather",
+            "description": "Look up the weather",
+            "input_schema": tool.parameter_schema
+        })
+    );
+}
+
+#[tokio::test]
+async fn gemini_shape_uses_openapi_subset() {
+    let handler = ToolHandler::default();
+    let tool = handler.get_tool("weather").unwrap();
+    assert_eq!(
+        tool.render_schema(SchemaFlavor::Gemini),
+        json!({
+            "name": "weather",
+            "description": "Look up the weather",
+            "parameters": {
+                "type": "OBJECT",
+                "properties": {
+                    "city": { "type": "STRING" },
+                    "days": { "type": "INTEGER", "nullable": true }
+                },
+                "required": ["city"]
+            }
+        })
+    );
+}
+
+#[tokio::test]
+async fn compact_schemas_drop_optional_detail() {
+    let tool = verbose_tool();
+    assert_eq!(
+        tool.render_compact_schema(SchemaFlavor::Anthropic),
+        json!({
+            "name": "book",
+            "description": "Books a table.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "guests": { "type": "integer", "description": "How many people are coming." },
+                    "seating": { "type": "string", "enum": ["inside", "outside"], "description": "Where to sit" },
+                    "notes": { "type": "array", "items": { "type": "string", "description": "One note." } }
+                },
+                "required": []
+            }
+        })
+    );
+}
+
+#[tokio::test]
+async fn compact_rendering_keeps_the_full_schema_for_validation() {
+    let handler = ToolHandler::empty().with_tool(verbose_tool());
+    let compact = handler.all_tools_schema_compact();
+    assert_eq!(
+        compact[0]["function"]["parameters"]["properties"]["guests"]["type"],
+        "integer"
+    );
+    assert_eq!(
+        handler.get_tool("book").unwrap().parameter_schema["properties"]["guests"]["default"],
+        2
+    );
+
+    // `null` is still accepted even though the compact schema no longer mentions it
+    let call = json!({
+        "type": "function",
+        "function": { "name": "book", "arguments": { "guests": null, "seating": null } }
+    });
+    assert!(handler.call_tool(&call).await.is_ok());
+}
@@ -0,0 +1,80 @@
+use serde_json::json;
+use std::sync::Arc;
+use tool_calling::audit::{AuditQuery, AuditSink, MemoryAuditLog};
+use tool_calling::{tool, ToolError, ToolHandler, REDACTED};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Connect to a service
+pub fn connect(host: String, #[param(secret)] api_key: String) -> String {
+    format!(
+        "connected to {} with a {}-character key",
+        host,
+        api_key.len()
+    )
+}
+
+#[tool]
+pub fn unlock(#[param(secret)] pin: u32) -> String {
+    (pin % 10).to_string()
+}
+
+// Tests
+
+#[test]
+fn secret_params_are_recorded_on_the_tool() {
+    let handler = ToolHandler::default();
+    assert_eq!(
+        handler.get_tool("connect").unwrap().secret_params,
+        vec!["api_key"]
+    );
+    // The marker does not affect the schema sent to models
+    let schema = &handler.get_tool("connect").unwrap().parameter_schema;
+    assert_eq!(schema["properties"]["api_key"], json!({ "type": "string" }));
+}
+
+#[tokio::test]
+async fn secrets_are_masked_in_validation_errors() {
+    let handler = ToolHandler::default();
+    let call = json!({
+        "type": "function",
+        "function": { "name": "connect", "arguments": { "host": 5, "api_key": 123456789 } }
+    });
+    let err = handler.call_tool(&call).await.unwrap_err();
+    let ToolError::BadArgs(msg) = err else {
+        panic!("expected BadArgs, got {:?}", err);
+    };
+    assert!(!msg.contains("123456789"), "{}", msg);
+    assert!(msg.contains(REDACTED), "{}", msg);
+    // Other arguments are reported as usual
+    assert!(msg.contains("5 is not of type"), "{}", msg);
+}
+
+#[tokio::test]
+async fn secrets_are_masked_in_parse_errors() {
+    let handler = ToolHandler::default();
+    let err = handler
+        .call_with_args("unlock", &["my-secret-pin".into()])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ToolError::BadArgs(
+            "Failed to parse argument '[REDACTED]' for parameter 'pin': expected u32".into()
+        )
+    );
+}
+
+#[tokio::test]
+async fn secrets_are_masked_in_audit_records() {
+    let log = Arc::new(MemoryAuditLog::with_capacity(10));
+    let handler = ToolHandler::default().with_audit(log.clone());
+    handler
+        .call_with_args("connect", &["db.internal".into(), "sk-live-123".into()])
+        .await
+        .unwrap();
+    let record = &log.query(&AuditQuery::new())[0];
+    assert_eq!(record.arguments["host"], "db.internal");
+    assert_eq!(record.arguments["api_key"], REDACTED);
+}
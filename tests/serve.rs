@@ -0,0 +1,151 @@
+#![cfg(feature = "serve")]
+
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tool_calling::serve::{ChatProxy, ServeError};
+use tool_calling::{tool, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Adds two integers
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+// A fake model: asks for `add` (or the client's `lookup`) until it sees a tool message,
+// then answers with the tool's output. Records every request it receives.
+async fn model_server(requests: Arc<Mutex<Vec<(HeaderMap, Value)>>>) -> String {
+    let app = Router::new().route(
+        "/v1/chat/completions",
+        post(move |headers: HeaderMap, Json(body): Json<Value>| {
+            let requests = Arc::clone(&requests);
+            async move {
+                requests.lock().unwrap().push((headers, body.clone()));
+                let messages = body["messages"].as_array().unwrap();
+                let last = messages.last().unwrap();
+                if last["content"] == "fail" {
+                    let error = json!({ "error": { "message": "bad key" } });
+                    return (StatusCode::UNAUTHORIZED, Json(error));
+                }
+                let message = if last["role"] == "tool" {
+                    json!({ "role": "assistant", "content": format!("The answer is {}", last["content"].as_str().unwrap()) })
+                } else {
+                    let name = if last["content"] == "lookup" { "lookup" } else { "add" };
+                    json!({
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": { "name": name, "arguments": "{\"a\": 20, \"b\": 22}" }
+                        }]
+                    })
+                };
+                (StatusCode::OK, Json(json!({ "choices": [{ "index": 0, "message": message }] })))
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}/v1", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    base
+}
+
+fn chat(content: &str) -> Value {
+    json!({ "model": "test", "messages": [{ "role": "user", "content": content }] })
+}
+
+// Tests
+
+#[tokio::test]
+async fn tool_calls_are_run_until_the_model_answers() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let upstream = model_server(Arc::clone(&requests)).await;
+    let proxy = ChatProxy::new(ToolHandler::default(), upstream).with_api_key("sk-test");
+
+    let response = proxy.complete(chat("add please")).await.unwrap();
+    assert_eq!(
+        response["choices"][0]["message"]["content"],
+        "The answer is 42"
+    );
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].0["authorization"], "Bearer sk-test");
+    assert_eq!(requests[0].1["tools"][0]["function"]["name"], "add");
+    let messages = requests[1].1["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 3);
+    assert_eq!(
+        messages[2],
+        json!({ "role": "tool", "tool_call_id": "call_1", "content": "42" })
+    );
+}
+
+#[tokio::test]
+async fn client_tool_calls_and_errors_are_returned() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let upstream = model_server(Arc::clone(&requests)).await;
+    let proxy = ChatProxy::new(ToolHandler::default(), upstream).with_max_rounds(1);
+
+    let mut request = chat("lookup");
+    request["tools"] =
+        json!([{ "type": "function", "function": { "name": "lookup", "parameters": {} } }]);
+    let response = proxy.complete(request).await.unwrap();
+    assert_eq!(
+        response["choices"][0]["message"]["tool_calls"][0]["function"]["name"],
+        "lookup"
+    );
+
+    assert_eq!(
+        proxy.complete(chat("add please")).await,
+        Err(ServeError::TooManyRounds(1))
+    );
+    assert!(matches!(
+        proxy.complete(chat("fail")).await,
+        Err(ServeError::Upstream { status: 401, .. })
+    ));
+    let mut streaming = chat("hi");
+    streaming["stream"] = json!(true);
+    assert!(matches!(
+        proxy.complete(streaming).await,
+        Err(ServeError::BadRequest(_))
+    ));
+}
+
+#[tokio::test]
+async fn serves_chat_completions_over_http() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let upstream = model_server(Arc::clone(&requests)).await;
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!(
+        "http://{}/v1/chat/completions",
+        listener.local_addr().unwrap()
+    );
+    let app = ChatProxy::new(ToolHandler::default(), upstream).router();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(&url)
+        .bearer_auth("sk-client")
+        .json(&chat("add please"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["choices"][0]["message"]["content"], "The answer is 42");
+    assert_eq!(
+        requests.lock().unwrap()[0].0["authorization"],
+        "Bearer sk-client"
+    );
+
+    let response = client.post(&url).json(&chat("fail")).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["message"], "bad key");
+}
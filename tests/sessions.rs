@@ -0,0 +1,99 @@
+mod common;
+
+use common::call;
+use serde_json::json;
+use std::sync::Arc;
+use tool_calling::session::SessionStore;
+use tool_calling::{tool, ToolContext, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Remember a note for this conversation
+pub fn remember(note: String, #[param(context)] ctx: ToolContext) -> String {
+    let Some(session) = ctx.session() else {
+        return "no session".to_string();
+    };
+    let mut notes: Vec<String> = session.get("notes").unwrap_or_default();
+    notes.push(note);
+    session.set("notes", &notes).unwrap();
+    notes.len().to_string()
+}
+
+#[tool]
+/// Recall this conversation's notes
+pub fn recall() -> String {
+    ToolContext::current()
+        .session()
+        .and_then(|session| session.get::<Vec<String>>("notes"))
+        .unwrap_or_default()
+        .join(", ")
+}
+
+// Tests
+
+#[tokio::test]
+async fn sessions_persist_across_calls_per_conversation() {
+    let handler = ToolHandler::default();
+    let first = ToolContext::new().with_conversation_id("a");
+    let second = ToolContext::new().with_conversation_id("b");
+
+    for note in ["milk", "eggs"] {
+        handler
+            .call_tool_with_context(&call("remember", json!({ "note": note })), &first)
+            .await
+            .unwrap();
+    }
+    handler
+        .call_tool_with_context(&call("remember", json!({ "note": "tea" })), &second)
+        .await
+        .unwrap();
+
+    let recalled = handler
+        .call_tool_with_context(&call("recall", json!({})), &first)
+        .await
+        .unwrap();
+    assert_eq!(recalled.output, "milk, eggs");
+    let recalled = handler
+        .call_tool_with_context(&call("recall", json!({})), &second)
+        .await
+        .unwrap();
+    assert_eq!(recalled.output, "tea");
+
+    let mut ids = handler.session_store().ids();
+    ids.sort();
+    assert_eq!(ids, vec!["a", "b"]);
+}
+
+#[tokio::test]
+async fn calls_without_a_conversation_have_no_session() {
+    let handler = ToolHandler::default();
+    let res = handler
+        .call_tool(&call("remember", json!({ "note": "milk" })))
+        .await
+        .unwrap();
+    assert_eq!(res.output, "no session");
+    assert!(handler.session_store().is_empty());
+}
+
+#[tokio::test]
+async fn stores_can_be_shared_and_restored() {
+    let store = Arc::new(SessionStore::new());
+    store
+        .session("a")
+        .restore(json!({ "notes": ["saved"] }).as_object().unwrap().clone());
+
+    let handler = ToolHandler::default().with_session_store(store.clone());
+    let ctx = ToolContext::new().with_conversation_id("a");
+    handler
+        .call_tool_with_context(&call("remember", json!({ "note": "new" })), &ctx)
+        .await
+        .unwrap();
+
+    let session = store.get("a").unwrap();
+    assert_eq!(session.snapshot()["notes"], json!(["saved", "new"]));
+    // Values of another shape read as missing
+    assert_eq!(session.get::<u32>("notes"), None);
+    assert!(store.remove("a").is_some());
+    assert!(store.get("a").is_none());
+}
@@ -0,0 +1,84 @@
+use futures::{Stream, StreamExt};
+use serde_json::json;
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool(stream)]
+pub fn count(n: u32) -> impl Stream<Item = Result<String, ToolError>> + Send {
+    futures::stream::iter((1..=n).map(|i| Ok(format!("{};", i))))
+}
+
+#[tool(stream)]
+pub fn fail_midway() -> impl Stream<Item = Result<String, ToolError>> + Send {
+    futures::stream::iter(vec![
+        Ok("partial".to_string()),
+        Err(ToolError::Execution("disk gone".into())),
+    ])
+}
+
+#[tool]
+pub fn plain() -> String {
+    "whole".into()
+}
+
+// Tests
+
+#[tokio::test]
+async fn stream_yields_chunks() {
+    let handler = ToolHandler::default();
+    let chunks: Vec<_> = handler
+        .call_streaming("count", &["3".into()])
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(
+        chunks,
+        vec![Ok("1;".into()), Ok("2;".into()), Ok("3;".into())]
+    );
+}
+
+#[tokio::test]
+async fn stream_collected_by_call_with_args() {
+    let handler = ToolHandler::default();
+    let res = handler.call_with_args("count", &["2".into()]).await;
+    assert_eq!(res, Ok("1;2;".into()));
+    let err = handler
+        .call_with_args("fail_midway", &[])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::Execution(msg) if msg == "disk gone"));
+}
+
+#[tokio::test]
+async fn stream_bad_args_yield_error_chunk() {
+    let handler = ToolHandler::default();
+    let chunks: Vec<_> = handler
+        .call_streaming("count", &["many".into()])
+        .unwrap()
+        .collect()
+        .await;
+    assert!(matches!(&chunks[..], [Err(ToolError::BadArgs(_))]));
+}
+
+#[tokio::test]
+async fn non_stream_tool_yields_single_chunk() {
+    let handler = ToolHandler::default();
+    let chunks: Vec<_> = handler
+        .call_streaming("plain", &[])
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(chunks, vec![Ok("whole".into())]);
+}
+
+#[tokio::test]
+async fn stream_from_json_payload() {
+    let handler = ToolHandler::default();
+    let input = json!({
+        "type": "function",
+        "function": { "name": "count", "arguments": { "n": 1 } }
+    });
+    let chunks: Vec<_> = handler.call_tool_streaming(&input).unwrap().collect().await;
+    assert_eq!(chunks, vec![Ok("1;".into())]);
+}
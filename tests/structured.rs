@@ -0,0 +1,76 @@
+use serde_json::json;
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Extract a contact from text
+pub fn extract_contact(name: String, age: Option<u32>) -> String {
+    format!("{} {:?}", name, age)
+}
+
+// Tests
+
+#[test]
+fn schemas_become_response_formats() {
+    let handler = ToolHandler::default();
+    let format = handler.response_format("extract_contact", false).unwrap();
+    assert_eq!(format["type"], "json_schema");
+    assert_eq!(format["json_schema"]["name"], "extract_contact");
+    assert_eq!(
+        format["json_schema"]["description"],
+        "Extract a contact from text"
+    );
+    assert_eq!(format["json_schema"]["strict"], false);
+    assert_eq!(
+        format["json_schema"]["schema"],
+        handler
+            .get_tool("extract_contact")
+            .unwrap()
+            .parameter_schema
+    );
+
+    let strict = handler.response_format("extract_contact", true).unwrap();
+    let schema = &strict["json_schema"]["schema"];
+    assert_eq!(strict["json_schema"]["strict"], true);
+    assert_eq!(schema["required"], json!(["name", "age"]));
+    assert_eq!(schema["additionalProperties"], false);
+
+    let err = handler.response_format("missing", true).unwrap_err();
+    assert!(matches!(err, ToolError::NotFound(_)));
+}
+
+#[test]
+fn output_is_validated_against_the_schema() {
+    let handler = ToolHandler::default();
+    let call = handler
+        .parse_structured_output("extract_contact", &json!({ "name": "Ada", "age": 36 }))
+        .unwrap();
+    assert_eq!(call.name, "extract_contact");
+    assert_eq!(call.args, vec![json!("Ada"), json!(36)]);
+
+    let response = json!({
+        "choices": [{ "message": { "role": "assistant", "content": "{\"name\": \"Bo\", \"age\": null}" } }]
+    });
+    let call = handler
+        .parse_structured_output("extract_contact", &response)
+        .unwrap();
+    assert_eq!(call.arguments["name"], "Bo");
+    assert_eq!(call.args[0], "Bo");
+
+    let err = handler
+        .parse_structured_output("extract_contact", &json!({ "age": 3 }))
+        .unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+    let err = handler
+        .parse_structured_output("extract_contact", &json!("not json"))
+        .unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+    let err = handler
+        .parse_structured_output(
+            "extract_contact",
+            &json!({ "role": "assistant", "content": null }),
+        )
+        .unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)));
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::call;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[derive(Deserialize)]
+pub struct Filter {
+    field: String,
+    values: Vec<String>,
+}
+
+#[tool]
+pub fn matrix_sum(rows: Vec<Vec<i32>>) -> String {
+    rows.iter().flatten().sum::<i32>().to_string()
+}
+
+#[tool]
+pub fn apply_filter(filter: Filter) -> String {
+    format!("{} in {}", filter.field, filter.values.join("|"))
+}
+
+#[tool]
+pub fn labels(map: HashMap<String, u32>) -> String {
+    let mut keys: Vec<_> = map.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    keys.sort();
+    keys.join(",")
+}
+
+#[tool]
+pub fn middle(a: u32, b: Option<u32>, c: u32) -> String {
+    format!("{} {:?} {}", a, b, c)
+}
+
+// Tests
+
+#[tokio::test]
+async fn nested_values_reach_tool_intact() {
+    let handler = ToolHandler::default();
+    let res = handler
+        .call_tool(&call("matrix_sum", json!({"rows": [[1, 2], [3]]})))
+        .await
+        .unwrap();
+    assert_eq!(res.output, "6");
+    let res = handler
+        .call_tool(&call(
+            "apply_filter",
+            json!({"filter": {"field": "tag", "values": ["a", "b"]}}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.output, "tag in a|b");
+    let res = handler.call_with_values("labels", &[json!({"x": 1})]).await;
+    assert_eq!(res, Ok("x=1".into()));
+}
+
+#[tokio::test]
+async fn string_args_parsed_as_json() {
+    let handler = ToolHandler::default();
+    let res = handler
+        .call_with_args("matrix_sum", &["[[4],[5,6]]".into()])
+        .await;
+    assert_eq!(res, Ok("15".into()));
+    let err = handler
+        .call_with_args("matrix_sum", &["not json".into()])
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, ToolError::BadArgs(msg) if msg.contains("Failed to parse argument for parameter 'rows'") && msg.contains("string \"not json\""))
+    );
+}
+
+#[tokio::test]
+async fn omitted_optional_keeps_later_args_in_place() {
+    let handler = ToolHandler::default();
+    let res = handler
+        .call_tool(&call("middle", json!({"a": 1, "c": 3})))
+        .await
+        .unwrap();
+    assert_eq!(res.output, "1 None 3");
+    let res = handler
+        .call_tool(&call("middle", json!({"a": 1, "b": null, "c": 3})))
+        .await
+        .unwrap();
+    assert_eq!(res.output, "1 None 3");
+}
+
+#[tokio::test]
+async fn schema_describes_structured_types() {
+    let handler = ToolHandler::default();
+    let props = |name: &str| handler.get_tool(name).unwrap().parameter_schema["properties"].clone();
+    assert_eq!(
+        props("matrix_sum")["rows"],
+        json!({"type": "array", "items": {"type": "array", "items": {"type": "integer"}}})
+    );
+    assert_eq!(props("apply_filter")["filter"], json!({"type": "object"}));
+    assert_eq!(
+        props("labels")["map"],
+        json!({"type": "object", "additionalProperties": {"type": "integer"}})
+    );
+    assert_eq!(props("middle")["b"], json!({"type": ["integer", "null"]}));
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::call;
+use futures::StreamExt;
+use serde_json::{json, Value};
+use tool_calling::{tool, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn fetch_price(symbol: String, quantity: u32) -> String {
+    panic!("no market data for {} x{}", symbol, quantity)
+}
+
+fn stubbed() -> ToolHandler {
+    let mut handler = ToolHandler::default();
+    handler.stub("fetch_price", |args: &[Value]| {
+        Ok(format!("{} x{} = 42", args[0].as_str().unwrap(), args[1]))
+    });
+    handler
+}
+
+// Tests
+
+#[tokio::test]
+async fn stubs_replace_execution() {
+    let handler = stubbed();
+    let res = handler
+        .call_tool(&call(
+            "fetch_price",
+            json!({ "quantity": 3, "symbol": "ACME" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.output, "ACME x3 = 42");
+    assert_eq!(
+        handler
+            .call_with_args("fetch_price", &["ACME".into(), "1".into()])
+            .await,
+        Ok("ACME x1 = 42".into())
+    );
+
+    let chunks = handler
+        .call_streaming("fetch_price", &["ACME".into(), "2".into()])
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(chunks, vec![Ok("ACME x2 = 42".to_string())]);
+}
+
+#[tokio::test]
+async fn stubbed_calls_are_still_validated() {
+    let handler = stubbed();
+    let err = handler
+        .call_tool(&call(
+            "fetch_price",
+            json!({ "symbol": "ACME", "quantity": "many" }),
+        ))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::BadArgs(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn unstubbing_restores_the_tool() {
+    let mut handler = stubbed();
+    assert!(handler.unstub("fetch_price"));
+    assert!(!handler.unstub("fetch_price"));
+    let err = handler
+        .call_tool(&call(
+            "fetch_price",
+            json!({ "symbol": "ACME", "quantity": 1 }),
+        ))
+        .await
+        .unwrap_err();
+    assert!(err.message().contains("no market data"), "{:?}", err);
+}
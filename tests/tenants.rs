@@ -0,0 +1,149 @@
+mod common;
+
+use common::call;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tool_calling::audit::{AuditQuery, AuditSink, MemoryAuditLog};
+use tool_calling::tenant::{RateLimit, Tenant, TenantError, TenantId, TenantedHandler};
+use tool_calling::{tool, ToolContext, ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn greet(name: String) -> String {
+    format!("hello {}", name)
+}
+
+#[tool]
+pub fn wipe_disk(name: String) -> String {
+    format!("wiped {}", name)
+}
+
+fn greet_only() -> ToolHandler {
+    let mut handler = ToolHandler::default();
+    handler.retain(|tool| tool.name == "greet");
+    handler
+}
+
+// Tests
+
+#[tokio::test]
+async fn tenants_have_separate_tools_and_audit_logs() {
+    let basic_log = Arc::new(MemoryAuditLog::with_capacity(10));
+    let admin_log = Arc::new(MemoryAuditLog::with_capacity(10));
+    let tenants = TenantedHandler::new()
+        .with_tenant(
+            "basic",
+            Tenant::new(greet_only().with_audit(basic_log.clone())),
+        )
+        .with_tenant(
+            "admin",
+            Tenant::new(ToolHandler::default().with_audit(admin_log.clone())),
+        );
+
+    assert_eq!(tenants.tenant_ids().collect::<Vec<_>>(), ["admin", "basic"]);
+    let schema = tenants.tools_schema("basic").unwrap();
+    assert_eq!(schema.as_array().unwrap().len(), 1);
+
+    let err = tenants
+        .call_tool("basic", &call("wipe_disk", json!({ "name": "ann" })))
+        .await;
+    assert_eq!(
+        err,
+        Err(TenantError::Tool(ToolError::NotFound("wipe_disk".into())))
+    );
+    let res = tenants
+        .call_tool("admin", &call("wipe_disk", json!({ "name": "ann" })))
+        .await
+        .unwrap();
+    assert_eq!(res.output, "wiped ann");
+    tenants
+        .call_tool("basic", &call("greet", json!({ "name": "ann" })))
+        .await
+        .unwrap();
+
+    let admin = admin_log.query(&AuditQuery::new());
+    assert_eq!(admin.len(), 1);
+    assert_eq!(admin[0].name, "wipe_disk");
+    assert_eq!(basic_log.query(&AuditQuery::new())[0].name, "greet");
+}
+
+#[tokio::test]
+async fn tenants_are_resolved_per_call() {
+    let tenants = TenantedHandler::new().with_tenant("acme", Tenant::new(greet_only()));
+
+    let ctx = ToolContext::new().with_extension(TenantId::new("acme"));
+    let res = tenants
+        .call_tool_with_context(&call("greet", json!({ "name": "ann" })), &ctx)
+        .await;
+    assert_eq!(res.unwrap().output, "hello ann");
+
+    let res = tenants
+        .call_tool_with_context(
+            &call("greet", json!({ "name": "ann" })),
+            &ToolContext::new(),
+        )
+        .await;
+    assert_eq!(res, Err(TenantError::Unresolved));
+    assert_eq!(
+        tenants
+            .call_tool("initech", &call("greet", json!({ "name": "ann" })))
+            .await,
+        Err(TenantError::UnknownTenant("initech".into()))
+    );
+
+    // A custom resolver can derive the tenant from the user
+    let tenants = tenants.with_tenant_resolver(|ctx: &ToolContext| {
+        ctx.user_id
+            .as_deref()
+            .and_then(|user| user.split_once('@'))
+            .map(|(_, domain)| domain.to_string())
+    });
+    let ctx = ToolContext::new().with_user_id("bob@acme");
+    assert!(tenants
+        .call_tool_with_context(&call("greet", json!({ "name": "ann" })), &ctx)
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn rate_limits_apply_per_tenant() {
+    let limit = RateLimit::new(2, Duration::from_millis(200));
+    let tenants = TenantedHandler::new()
+        .with_tenant("small", Tenant::new(greet_only()).with_rate_limit(limit))
+        .with_tenant("large", Tenant::new(greet_only()));
+
+    for _ in 0..2 {
+        tenants
+            .call_tool("small", &call("greet", json!({ "name": "ann" })))
+            .await
+            .unwrap();
+    }
+    match tenants
+        .call_tool("small", &call("greet", json!({ "name": "ann" })))
+        .await
+    {
+        Err(TenantError::RateLimited {
+            tenant,
+            retry_after,
+        }) => {
+            assert_eq!(tenant, "small");
+            assert!(retry_after <= Duration::from_millis(200));
+        }
+        other => panic!("expected a rate limit error, got {:?}", other),
+    }
+    // Other tenants are unaffected
+    for _ in 0..5 {
+        tenants
+            .call_tool("large", &call("greet", json!({ "name": "ann" })))
+            .await
+            .unwrap();
+    }
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert!(tenants
+        .call_tool("small", &call("greet", json!({ "name": "ann" })))
+        .await
+        .is_ok());
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use tool_calling::tokens::{HeuristicTokenizer, ModelFamily, Tokenizer};
+use tool_calling::{tool, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Adds two integers
+pub fn add(a: i32, b: i32) -> String {
+    (a + b).to_string()
+}
+
+#[tool]
+/// Searches the web for pages matching a query and returns the top results with their
+/// titles, links and a short snippet of each page
+pub fn search_web(query: String, max_results: Option<u32>, safe_search: Option<bool>) -> String {
+    format!("{} {:?} {:?}", query, max_results, safe_search)
+}
+
+// Counts one token per character, to check custom tokenizers are used
+struct CharTokenizer;
+
+impl Tokenizer for CharTokenizer {
+    fn count_tokens(&self, text: &str, _family: ModelFamily) -> usize {
+        text.chars().count()
+    }
+}
+
+// Tests
+
+#[test]
+fn heuristic_estimates_depend_on_the_family() {
+    let tokenizer = HeuristicTokenizer;
+    let text = "x".repeat(700);
+    assert_eq!(tokenizer.count_tokens(&text, ModelFamily::Gpt), 200);
+    assert!(
+        tokenizer.count_tokens(&text, ModelFamily::Claude)
+            > tokenizer.count_tokens(&text, ModelFamily::Gemini)
+    );
+}
+
+#[test]
+fn schema_estimates_cover_every_tool() {
+    let handler = ToolHandler::default();
+    let per_tool = handler.estimate_tool_tokens(ModelFamily::Gpt);
+    assert_eq!(per_tool.len(), 2);
+    assert_eq!(per_tool[0].0.name, "add");
+    assert!(per_tool[1].1 > per_tool[0].1);
+
+    // The whole schema costs about the sum of its tools
+    let total = handler.estimate_schema_tokens(ModelFamily::Gpt);
+    let sum = per_tool.iter().map(|(_, tokens)| tokens).sum::<usize>();
+    assert!(total.abs_diff(sum) <= 2, "{} vs {}", total, sum);
+}
+
+#[test]
+fn custom_tokenizers_are_used() {
+    let handler = ToolHandler::default().with_tokenizer(Arc::new(CharTokenizer));
+    let add = handler.get_tool("add").unwrap();
+    let chars = add
+        .render_schema(ModelFamily::Claude.flavor())
+        .to_string()
+        .len();
+    assert_eq!(
+        handler.estimate_tool_tokens(ModelFamily::Claude)[0].1,
+        chars
+    );
+}
+
+#[test]
+fn budgets_skip_tools_that_do_not_fit() {
+    let handler = ToolHandler::default().with_schema_token_limit(10);
+    let per_tool = handler.estimate_tool_tokens(ModelFamily::Llama);
+    let (add_tokens, search_tokens) = (per_tool[0].1, per_tool[1].1);
+
+    let fits = handler.tools_within_budget(ModelFamily::Llama, search_tokens);
+    assert_eq!(fits.len(), 1);
+    assert_eq!(fits[0].name, "add");
+    let fits = handler.tools_within_budget(ModelFamily::Llama, add_tokens + search_tokens);
+    assert_eq!(fits.len(), 2);
+    assert!(handler
+        .tools_within_budget(ModelFamily::Llama, add_tokens - 1)
+        .is_empty());
+    // Exceeding the limit only logs a warning
+    assert!(handler.estimate_schema_tokens(ModelFamily::Llama) > 10);
+}
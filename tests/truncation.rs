@@ -0,0 +1,112 @@
+mod common;
+
+use common::call;
+use serde_json::json;
+use tool_calling::{tool, OutputLimit, ToolHandler, TruncationStrategy};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn listing(lines: usize) -> String {
+    (0..lines).map(|i| format!("entry {}\n", i)).collect()
+}
+
+#[tool]
+pub fn accents(count: usize) -> String {
+    "é".repeat(count)
+}
+
+// Tests
+
+#[test]
+fn strategies_keep_different_parts() {
+    let output = (0..100).map(|i| format!("{:03}", i)).collect::<String>();
+
+    let (head, truncated) = OutputLimit::new(100).apply(output.clone());
+    assert!(truncated);
+    assert!(head.len() <= 100);
+    assert!(head.starts_with("000001002"));
+    assert!(head.ends_with("bytes truncated ...]\n"));
+
+    let tail = OutputLimit::new(100).with_strategy(TruncationStrategy::Tail);
+    let (tail, _) = tail.apply(output.clone());
+    assert!(tail.len() <= 100);
+    assert!(tail.starts_with("\n[... "));
+    assert!(tail.ends_with("097098099"));
+
+    let middle = OutputLimit::new(100).with_strategy(TruncationStrategy::Middle);
+    let (middle, _) = middle.apply(output.clone());
+    assert!(middle.len() <= 100);
+    assert!(middle.starts_with("000001"));
+    assert!(middle.ends_with("098099"));
+    assert!(middle.contains("[... 231 bytes truncated ...]"));
+
+    // Outputs within the limit are untouched
+    assert_eq!(OutputLimit::new(300).apply(output.clone()), (output, false));
+}
+
+#[test]
+fn truncation_respects_char_boundaries() {
+    for strategy in [
+        TruncationStrategy::Head,
+        TruncationStrategy::Tail,
+        TruncationStrategy::Middle,
+    ] {
+        let limit = OutputLimit::new(51).with_strategy(strategy);
+        let (output, truncated) = limit.apply("é".repeat(100));
+        assert!(truncated);
+        assert!(output.len() <= 51, "{:?}", strategy);
+    }
+    // Limits smaller than the marker cut the output without one
+    assert_eq!(
+        OutputLimit::new(5).apply("é".repeat(10)),
+        ("éé".into(), true)
+    );
+}
+
+#[tokio::test]
+async fn handlers_flag_truncated_results() {
+    let handler = ToolHandler::default().with_output_limit(OutputLimit::new(200));
+    let res = handler
+        .call_tool(&call("listing", json!({ "lines": 1000 })))
+        .await
+        .unwrap();
+    assert!(res.truncated);
+    assert!(res.output.len() <= 200);
+
+    let res = handler
+        .call_tool(&call("listing", json!({ "lines": 2 })))
+        .await
+        .unwrap();
+    assert!(!res.truncated);
+    assert_eq!(res.output, "entry 0\nentry 1\n");
+
+    let output = handler
+        .call_with_args("accents", &["1000".into()])
+        .await
+        .unwrap();
+    assert!(output.len() <= 200);
+}
+
+#[tokio::test]
+async fn per_tool_limits_override_the_handler_limit() {
+    let handler = ToolHandler::default()
+        .with_output_limit(OutputLimit::new(100))
+        .with_tool_output_limit(
+            "listing",
+            OutputLimit::new(1_000).with_strategy(TruncationStrategy::Tail),
+        );
+    let res = handler
+        .call_tool(&call("listing", json!({ "lines": 1000 })))
+        .await
+        .unwrap();
+    assert!(res.truncated);
+    assert!(res.output.len() > 100 && res.output.len() <= 1_000);
+    assert!(res.output.ends_with("entry 999\n"));
+
+    let output = handler
+        .call_with_args("accents", &["1000".into()])
+        .await
+        .unwrap();
+    assert!(output.len() <= 100);
+}
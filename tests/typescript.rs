@@ -0,0 +1,102 @@
+use futures::future;
+use serde_json::json;
+use tool_calling::{tool, Tool, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+/// Look up the weather
+pub fn get_weather(city: String, days: Option<u32>) -> String {
+    format!("{} {:?}", city, days)
+}
+
+#[tool]
+pub fn noop() -> String {
+    String::new()
+}
+
+fn book_tool() -> Tool {
+    Tool::new(
+        "book-table",
+        "Books a table.\nTables are held for 15 minutes.",
+        json!({
+            "type": "object",
+            "properties": {
+                "guests": { "type": ["integer", "null"], "description": "How many people are coming" },
+                "seating": {
+                    "anyOf": [{ "type": "string", "enum": ["inside", "outside"] }, { "type": "null" }]
+                },
+                "notes": { "type": "array", "items": { "type": ["string", "number"] } },
+                "contact": {
+                    "type": "object",
+                    "properties": { "e-mail": { "type": "string" } },
+                    "required": ["e-mail"]
+                },
+                "extras": { "type": "object", "additionalProperties": { "type": "boolean" } }
+            },
+            "required": ["guests", "contact"]
+        }),
+        ToolFn::Async(Box::new(|_| Box::pin(future::ready(Ok(String::new()))))),
+    )
+}
+
+// Tests
+
+#[test]
+fn macro_tools_get_argument_interfaces() {
+    let ts = ToolHandler::default().typescript_definitions();
+    assert!(ts.starts_with("// Generated by tool_calling. Do not edit.\n"));
+    assert!(ts.contains(
+        "/** Look up the weather */\nexport interface GetWeatherArgs {\n  city: string;\n  days?: number | null;\n}\n"
+    ));
+    assert!(ts.contains("export interface NoopArgs {}\n"));
+    assert!(ts.contains("export type ToolName = \"get_weather\" | \"noop\";\n"));
+    assert!(ts.contains(
+        "export interface ToolArgs {\n  get_weather: GetWeatherArgs;\n  noop: NoopArgs;\n}\n"
+    ));
+    assert!(ts.contains("[K in ToolName]: { name: K; arguments: ToolArgs[K] };"));
+}
+
+#[test]
+fn schema_constructs_map_to_typescript_types() {
+    let ts = ToolHandler::empty()
+        .with_tool(book_tool())
+        .typescript_definitions();
+    let expected = r#"/**
+ * Books a table.
+ * Tables are held for 15 minutes.
+ */
+export interface BookTableArgs {
+  /** How many people are coming */
+  guests: number | null;
+  seating?: "inside" | "outside" | null;
+  notes?: (string | number)[];
+  contact: {
+    "e-mail": string;
+  };
+  extras?: Record<string, boolean>;
+}
+"#;
+    assert!(ts.contains(expected), "{}", ts);
+    assert!(ts.contains("export type ToolName = \"book-table\";\n"));
+    assert!(ts.contains("  \"book-table\": BookTableArgs;\n"));
+}
+
+#[test]
+fn empty_handler_has_no_tool_names() {
+    let ts = ToolHandler::empty().typescript_definitions();
+    assert!(ts.contains("export type ToolName = never;\n"));
+    assert!(ts.contains("export interface ToolArgs {}\n"));
+}
+
+#[test]
+fn writes_a_declaration_file() {
+    let path = std::env::temp_dir().join(format!("tool_calling_{}.d.ts", std::process::id()));
+    let handler = ToolHandler::default();
+    handler.write_typescript_definitions(&path).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        handler.typescript_definitions()
+    );
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,114 @@
+use serde_json::json;
+use tool_calling::{tool, Tool, ToolError, ToolFn, ToolHandler};
+
+// Define necessary tools for these tests
+
+#[tool]
+pub fn greet(name: String, times: Option<u32>) -> String {
+    name.repeat(times.unwrap_or(1) as usize)
+}
+
+// Tests
+
+#[tokio::test]
+async fn validate_returns_normalized_call() {
+    let handler = ToolHandler::default();
+    let input = json!({
+        "type": "function",
+        "function": { "name": "greet", "arguments": { "times": 2, "name": "hi" } }
+    });
+    let call = handler.validate_tool_call(&input).unwrap();
+    assert_eq!(call.name, "greet");
+    assert_eq!(call.args, vec![json!("hi"), json!(2)]);
+    assert_eq!(call.arguments.get("times"), Some(&json!(2)));
+}
+
+#[tokio::test]
+async fn validate_omits_missing_optionals() {
+    let handler = ToolHandler::default();
+    let input = json!({
+        "type": "function",
+        "function": { "name": "greet", "arguments": { "name": "hi" } }
+    });
+    let call = handler.validate_tool_call(&input).unwrap();
+    assert_eq!(call.args, vec![json!("hi")]);
+    assert!(!call.arguments.contains_key("times"));
+}
+
+#[tokio::test]
+async fn validate_reports_errors_without_executing() {
+    let handler = ToolHandler::default();
+    let unknown = json!({
+        "type": "function",
+        "function": { "name": "nope", "arguments": {} }
+    });
+    assert_eq!(
+        handler.validate_tool_call(&unknown).unwrap_err(),
+        ToolError::NotFound("nope".into())
+    );
+    let bad = json!({
+        "type": "function",
+        "function": { "name": "greet", "arguments": { "name": 5 } }
+    });
+    assert!(matches!(
+        handler.validate_tool_call(&bad),
+        Err(ToolError::BadArgs(msg)) if msg.contains("Argument validation failed for tool 'greet'")
+    ));
+}
+
+#[tokio::test]
+async fn call_tool_returns_structured_result() {
+    let handler = ToolHandler::default();
+    let input = json!({
+        "id": "call_42",
+        "type": "function",
+        "function": { "name": "greet", "arguments": { "name": "ab", "times": 2 } }
+    });
+    let res = handler.call_tool(&input).await.unwrap();
+    assert_eq!(res.name, "greet");
+    assert_eq!(res.id.as_deref(), Some("call_42"));
+    assert_eq!(res.output, "abab");
+
+    let without_id = json!({
+        "type": "function",
+        "function": { "name": "greet", "arguments": { "name": "ab" } }
+    });
+    assert_eq!(handler.call_tool(&without_id).await.unwrap().id, None);
+}
+
+#[tokio::test]
+async fn malformed_schemas_fail_every_call() {
+    let handler = ToolHandler::empty().with_tool(Tool::new(
+        "loose",
+        "Has no required list",
+        json!({ "type": "object", "properties": { "a": { "type": "string" } } }),
+        ToolFn::Async(Box::new(|_| Box::pin(async { Ok(String::new()) }))),
+    ));
+    let input = json!({
+        "type": "function",
+        "function": { "name": "loose", "arguments": { "a": "x" } }
+    });
+    for _ in 0..2 {
+        assert_eq!(
+            handler.validate_tool_call(&input),
+            Err(ToolError::Execution(
+                "Schema for tool 'loose' missing 'required' array".to_string()
+            ))
+        );
+    }
+}
+
+#[tokio::test]
+async fn validate_orders_arguments_by_schema() {
+    let handler = ToolHandler::default();
+    let input = json!({
+        "type": "function",
+        "function": { "name": "greet", "arguments": { "times": 3, "name": "x" } }
+    });
+    for _ in 0..2 {
+        let call = handler.validate_tool_call(&input).unwrap();
+        assert_eq!(call.args, vec![json!("x"), json!(3)]);
+        let names = call.arguments.keys().collect::<Vec<_>>();
+        assert_eq!(names, ["name", "times"]);
+    }
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "wasm")]
+
+use serde_json::json;
+use tool_calling::wasm::{WasmError, WasmLimits, WasmModule, WasmRuntime};
+use tool_calling::{ToolError, ToolHandler};
+
+// Define necessary tools for these tests
+
+// `echo` returns its arguments unchanged, `spin` never returns and `fail` traps
+const MODULE: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 1024))
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (local.get $ptr))
+  (func (export "echo") (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len))))
+  (func (export "spin") (param i32 i32) (result i64)
+    (loop $forever (br $forever))
+    (i64.const 0))
+  (func (export "fail") (param i32 i32) (result i64)
+    unreachable))
+"#;
+
+fn module(limits: WasmLimits) -> WasmModule {
+    WasmRuntime::new()
+        .unwrap()
+        .with_limits(limits)
+        .compile(MODULE)
+        .unwrap()
+}
+
+fn handler(module: &WasmModule, name: &str) -> ToolHandler {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "text": { "type": "string" },
+            "count": { "type": ["integer", "null"] }
+        },
+        "required": ["text"]
+    });
+    ToolHandler::empty().with_tool(module.tool(name, "A wasm tool", schema).unwrap())
+}
+
+// Tests
+
+#[tokio::test]
+async fn wasm_tool_receives_json_arguments() {
+    let handler = handler(&module(WasmLimits::default()), "echo");
+    let call = json!({
+        "type": "function",
+        "function": { "name": "echo", "arguments": { "text": "hi", "count": 2 } }
+    });
+    let result = handler.call_tool(&call).await.unwrap();
+    assert_eq!(result.output, r#"{"text":"hi","count":2}"#);
+    // Omitted optionals are left out of the object
+    assert_eq!(
+        handler.call_with_values("echo", &[json!("hi")]).await,
+        Ok(r#"{"text":"hi"}"#.into())
+    );
+}
+
+#[tokio::test]
+async fn wasm_fuel_limit_stops_runaway_tools() {
+    let limits = WasmLimits {
+        fuel: 10_000,
+        ..WasmLimits::default()
+    };
+    let handler = handler(&module(limits), "spin");
+    let err = handler
+        .call_with_values("spin", &[json!("hi")])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ToolError::Execution("tool exceeded its fuel limit".into())
+    );
+}
+
+#[tokio::test]
+async fn wasm_traps_and_oversized_output_fail_the_call() {
+    let handler = handler(&module(WasmLimits::default()), "fail");
+    let err = handler.call_with_values("fail", &[json!("hi")]).await;
+    assert!(matches!(err, Err(ToolError::Execution(_))));
+
+    let limits = WasmLimits {
+        max_output_bytes: 4,
+        ..WasmLimits::default()
+    };
+    let handler = self::handler(&module(limits), "echo");
+    let err = handler
+        .call_with_values("echo", &[json!("hello")])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::Execution(msg) if msg.contains("exceeds the limit")));
+}
+
+#[test]
+fn wasm_exports_are_checked() {
+    let module = module(WasmLimits::default());
+    let err = module.tool("missing", "", json!({})).unwrap_err();
+    assert!(matches!(err, WasmError::Export { export, .. } if export == "missing"));
+    // `alloc` has the wrong signature for a tool export
+    assert!(module.tool("alloc", "", json!({})).is_err());
+
+    let runtime = WasmRuntime::new().unwrap();
+    assert!(matches!(
+        runtime.compile("(module)"),
+        Err(WasmError::Export { .. })
+    ));
+    assert!(matches!(
+        runtime.compile("not a module"),
+        Err(WasmError::Module(_))
+    ));
+}
@@ -3,8 +3,10 @@ use proc_macro::TokenStream;
 use proc_macro2::{Ident as Ident2, Span};
 use quote::quote;
 use serde_json::json;
+use syn::punctuated::Punctuated;
 use syn::{
-    parse_macro_input, Expr, FnArg, GenericArgument, ItemFn, Lit, Meta, Pat, PathArguments, Type,
+    parse_macro_input, Expr, FnArg, GenericArgument, ItemFn, Lit, LitStr, Meta, Pat, PathArguments,
+    Token, Type,
 };
 
 /// Helper function to deny reference types in parameters
@@ -19,8 +21,38 @@ fn deny_references(ty: &Type) -> Result<(), syn::Error> {
     }
 }
 
+/// Options accepted inside `#[tool(...)]`
+#[derive(Default)]
+struct ToolAttrs {
+    /// The function returns a `Stream` of output chunks instead of a `String`
+    stream: bool,
+    /// Keywords used by tool discovery, from `tags("a", "b")`
+    tags: Vec<String>,
+}
+
+impl ToolAttrs {
+    fn parse(&mut self, meta: syn::meta::ParseNestedMeta) -> Result<(), syn::Error> {
+        if meta.path.is_ident("stream") {
+            self.stream = true;
+            Ok(())
+        } else if meta.path.is_ident("tags") {
+            let content;
+            syn::parenthesized!(content in meta.input);
+            let tags = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+            self.tags.extend(tags.iter().map(LitStr::value));
+            Ok(())
+        } else {
+            Err(meta.error("unsupported tool option; expected `stream` or `tags(...)`"))
+        }
+    }
+}
+
 /// Attribute macro that marks a function as a tool
 ///
+/// Use `#[tool(stream)]` on a synchronous function returning
+/// `impl Stream<Item = Result<String, ToolError>> + Send` to register a streaming tool.
+/// Use `#[tool(tags("math", "util"))]` to attach keywords used by tool discovery.
+///
 /// # Example
 /// ```
 /// Get user info from database
@@ -30,7 +62,12 @@ fn deny_references(ty: &Type) -> Result<(), syn::Error> {
 /// }
 /// ```
 #[proc_macro_attribute]
-pub fn tool(_args: TokenStream, item: TokenStream) -> TokenStream {
+pub fn tool(args: TokenStream, item: TokenStream) -> TokenStream {
+    // Parse the tool options
+    let mut attrs = ToolAttrs::default();
+    let attr_parser = syn::meta::parser(|meta| attrs.parse(meta));
+    parse_macro_input!(args with attr_parser);
+
     // Parse the function itself
     let input_fn = parse_macro_input!(item as ItemFn);
     // Extract documentation comments as description
@@ -60,12 +97,22 @@ pub fn tool(_args: TokenStream, item: TokenStream) -> TokenStream {
 
     // Check if the function is async
     let is_async = sig.asyncness.is_some();
+    if attrs.stream && is_async {
+        return syn::Error::new_spanned(
+            sig.asyncness,
+            "streaming tools must be synchronous functions returning a `Stream`",
+        )
+        .to_compile_error()
+        .into();
+    }
 
     // Get input parameters for parsing arguments
     let mut param_types = Vec::new();
     let mut param_names = Vec::new();
     let mut param_is_option = Vec::new(); // Track if param is Option<T>
     let mut param_defaults = Vec::new(); // Track default values from attributes
+    let mut param_secret = Vec::new(); // Track `#[param(secret)]` parameters
+    let mut call_args = Vec::new(); // Expression passed for each fn input, in declaration order
 
     for input in &sig.inputs {
         if let FnArg::Typed(pat_type) = input {
@@ -75,7 +122,30 @@ pub fn tool(_args: TokenStream, item: TokenStream) -> TokenStream {
             }
 
             if let Pat::Ident(pat_ident) = &*pat_type.pat {
+                let options = match param_options(&pat_type.attrs) {
+                    Ok(options) => options,
+                    Err(e) => return e.to_compile_error().into(),
+                };
                 let param_name = pat_ident.ident.to_string();
+
+                // Context and injected parameters are filled in by the handler, not the model
+                if options.context {
+                    call_args.push(quote! { tool_calling::ToolContext::current() });
+                    continue;
+                }
+                if pat_type
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.path().is_ident("inject"))
+                {
+                    let ty = &pat_type.ty;
+                    call_args.push(quote! { tool_calling::__inject::<#ty>(#param_name)? });
+                    continue;
+                }
+                let var = Ident2::new(&format!("arg{}", param_names.len()), Span::call_site());
+                call_args.push(quote! { #var });
+                param_secret.push(options.secret);
+
                 param_names.push(param_name);
 
                 // Check if the type is Option<T>
@@ -113,24 +183,13 @@ pub fn tool(_args: TokenStream, item: TokenStream) -> TokenStream {
             required.push(json!(param_name.clone()));
         }
 
-        // Convert the type to a string for schema generation
-        let type_str = quote!(#param_type).to_string().replace(" ", "");
-        let base_json_type = match type_str.as_str() {
-            s if s.starts_with("u") || s.starts_with("i") || s == "usize" || s == "isize" => {
-                "integer"
-            }
-            "f32" | "f64" => "number",
-            "bool" => "boolean",
-            s if s.contains("String") => "string", // Be more specific for String
-            _ => "string",                         // Default, consider improving
-        };
-
-        let mut param_schema = serde_json::Map::new();
+        // Derive the JSON Schema for the (inner) parameter type
+        let mut param_schema = type_schema(param_type);
         if is_option {
             // Allow null for optional types
-            param_schema.insert("type".to_string(), json!([base_json_type, "null"]));
-        } else {
-            param_schema.insert("type".to_string(), json!(base_json_type));
+            if let Some(serde_json::Value::String(base_json_type)) = param_schema.remove("type") {
+                param_schema.insert("type".to_string(), json!([base_json_type, "null"]));
+            }
         }
 
         // Add default value to schema if present
@@ -168,6 +227,7 @@ pub fn tool(_args: TokenStream, item: TokenStream) -> TokenStream {
 
     // Generate a constructor function for the Tool rather than using static initialization
     let metadata_fn = syn::Ident::new(&format!("__register_tool_{}", fn_name), fn_ident.span());
+    let factory_static = syn::Ident::new(&format!("__TOOL_FACTORY_{}", fn_name), fn_ident.span());
 
     // Generate the single async closure, wrapping sync functions if needed
     let func_body = {
@@ -201,7 +261,7 @@ pub fn tool(_args: TokenStream, item: TokenStream) -> TokenStream {
         let parse_and_call_logic = if param_count == 0 {
             if is_async {
                 quote! {
-                    match #fn_ident().await {
+                    match #fn_ident(#(#call_args),*).await {
                         result => Ok(result),
                         // TODO: Consider capturing panics or mapping errors if the function returns Result
                         // Err(e) => Err(tool_calling::ToolError::Execution(e.to_string())),
@@ -209,8 +269,7 @@ pub fn tool(_args: TokenStream, item: TokenStream) -> TokenStream {
                 }
             } else {
                 quote! {
-                    // No need to capture panics explicitly for sync, wrap_sync handles the Result
-                    Ok(#fn_ident())
+                    Ok(#fn_ident(#(#call_args),*))
                 }
             }
         } else {
@@ -220,17 +279,34 @@ pub fn tool(_args: TokenStream, item: TokenStream) -> TokenStream {
                 .zip(param_types.iter()) // Use potentially inner type
                 .zip(param_is_option.iter())
                 .zip(param_defaults.iter())
+                .zip(param_secret.iter())
                 .enumerate()
-                .map(|(i, (((name, ty), is_option), default_value))| {
+                .map(|(i, ((((name, ty), is_option), default_value), is_secret))| {
                     let var = Ident2::new(&format!("arg{}", i), Span::call_site());
                     let idx = syn::Index::from(i);
 
-                    let parse_expr = quote! {
-                         owned_args[#idx].parse::<#ty>()
-                            .map_err(|_| tool_calling::ToolError::BadArgs(format!(
-                                "Failed to parse argument '{}' for parameter '{}'",
-                                owned_args[#idx], #name
-                            )))
+                    let parse_expr = if *is_secret {
+                        // serde's message quotes the value, so only name the expected type
+                        let ty_name = quote!(#ty).to_string();
+                        quote! {
+                            tool_calling::__parse_argument::<#ty>(value)
+                               .map_err(|_| tool_calling::ToolError::BadArgs(format!(
+                                   "Failed to parse argument '{}' for parameter '{}': expected {}",
+                                   tool_calling::REDACTED,
+                                   #name,
+                                   #ty_name
+                               )))
+                        }
+                    } else {
+                        // The value is moved into parsing, so serde's message describes it
+                        quote! {
+                            tool_calling::__parse_argument::<#ty>(value)
+                               .map_err(|e| tool_calling::ToolError::BadArgs(format!(
+                                   "Failed to parse argument for parameter '{}': {}",
+                                   #name,
+                                   e
+                               )))
+                        }
                     };
 
                     if *is_option {
@@ -239,32 +315,29 @@ pub fn tool(_args: TokenStream, item: TokenStream) -> TokenStream {
                             None => quote! { None },            // No default means None for Option
                         };
                         quote! {
-                            let #var: Option<#ty> = match owned_args.get(#idx) {
-                                Some(s) => Some(#parse_expr?),
-                                None => #default_branch, // Use default or None
+                            // Omitted and explicit `null` arguments both mean "not provided"
+                            let #var: Option<#ty> = match args.get_mut(#idx).map(serde_json::Value::take) {
+                                Some(serde_json::Value::Null) | None => #default_branch, // Use default or None
+                                Some(value) => Some(#parse_expr?),
                             };
                         }
                     } else {
                         // Non-optional: Must parse or fail (unless default exists? No, schema validation ensures presence if no default)
                         quote! {
-                            // Parameter is required, so owned_args[#idx] should exist due to schema validation.
-                            let #var = #parse_expr?;
+                            // Parameter is required, so args[#idx] should exist due to schema validation.
+                            let #var = {
+                                let value = args[#idx].take();
+                                #parse_expr?
+                            };
                         }
                     }
                 })
                 .collect::<Vec<_>>();
 
-            // Generate argument list for function call (variables now include options)
-            let call_args = (0..param_count)
-                .map(|i| {
-                    let var = Ident2::new(&format!("arg{}", i), Span::call_site());
-                    quote! { #var }
-                })
-                .collect::<Vec<_>>();
-
             if is_async {
                 quote! {
-                    // Parse each argument
+                    // Parse each argument, moving it out of the call
+                    let mut args = args;
                     #(#parse_stmts)*
                     // Call function with parsed arguments
                     match #fn_ident(#(#call_args),*).await {
@@ -275,60 +348,182 @@ pub fn tool(_args: TokenStream, item: TokenStream) -> TokenStream {
                 }
             } else {
                 quote! {
-                    // Parse each argument
+                    // Parse each argument, moving it out of the call
+                    let mut args = args;
                     #(#parse_stmts)*
                     // Call function with parsed arguments
-                    Ok(#fn_ident(#(#call_args),*)) // Wrap result in Ok for wrap_sync
+                    Ok(#fn_ident(#(#call_args),*))
                 }
             }
         };
 
         // The final function body expression for ToolFn
-        if is_async {
+        if attrs.stream {
+            // Parse arguments eagerly and hand back the tool's stream, or a single error chunk
+            quote! {
+                 tool_calling::ToolFn::Stream(Box::new(|args: Vec<serde_json::Value>| {
+                     let started: Result<_, tool_calling::ToolError> = (|| {
+                         #sync_check_len_stmt
+                         #parse_and_call_logic
+                     })();
+                     match started {
+                         Ok(stream) => futures::StreamExt::boxed(stream),
+                         Err(e) => futures::StreamExt::boxed(futures::stream::once(
+                             futures::future::ready(Err(e)),
+                         )),
+                     }
+                 }))
+            }
+        } else if is_async {
             quote! {
-                 tool_calling::ToolFn::Async(Box::new(|args: &[String]| {
-                     // Perform checks and clone args *before* creating the BoxFuture
+                 tool_calling::ToolFn::Async(Box::new(|args: Vec<serde_json::Value>| {
+                     // Perform checks *before* creating the BoxFuture, which takes the args
                      #async_check_len_stmt // Use async check
-                     let owned_args = args.to_vec();
                      Box::pin(async move {
                         #parse_and_call_logic
                     })
                  }))
             }
         } else {
-            // Wrap the synchronous logic using the helper
+            // Run the synchronous logic when the future is polled, like `wrap_sync`, but
+            // taking the arguments by value so they can be moved into parsing
             quote! {
-                 tool_calling::ToolFn::Async(tool_calling::wrap_sync(
-                     // Use Arc::new instead of Box::new
-                     std::sync::Arc::new(|args: &[String]| {
+                 tool_calling::ToolFn::Async(Box::new(|args: Vec<serde_json::Value>| {
+                     Box::pin(async move {
                          #sync_check_len_stmt
-                         // Clone args into a Vec for parsing logic
-                         let owned_args = args.to_vec();
-                         #parse_and_call_logic // This uses owned_args Vec
-                     }) as std::sync::Arc<dyn Fn(&[String]) -> Result<String, tool_calling::ToolError> + Send + Sync>
-                 ))
+                         #parse_and_call_logic
+                     })
+                 }))
             }
         }
     };
 
+    let tags = &attrs.tags;
+    let tag_count = tags.len();
+    let secrets = param_names
+        .iter()
+        .zip(&param_secret)
+        .filter(|(_, secret)| **secret)
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    let secret_count = secrets.len();
+
+    // `#[param(...)]` and `#[inject]` are only meaningful to this macro, so strip them
+    let mut output_fn = input_fn.clone();
+    for input in &mut output_fn.sig.inputs {
+        if let FnArg::Typed(pat_type) = input {
+            pat_type
+                .attrs
+                .retain(|attr| !attr.path().is_ident("param") && !attr.path().is_ident("inject"));
+        }
+    }
+
     let expanded = quote! {
-        #input_fn
+        #output_fn
 
+        // Listed by name, so the tool is only built when it is first looked up
         #[doc(hidden)]
+        #[allow(non_upper_case_globals)]
         #[linkme::distributed_slice(tool_calling::TOOL_FACTORIES)]
+        static #factory_static: tool_calling::ToolFactory = tool_calling::ToolFactory {
+            name: #fn_name,
+            build: #metadata_fn,
+        };
+
+        #[doc(hidden)]
         fn #metadata_fn() -> tool_calling::Tool {
-            tool_calling::Tool {
-                name: #fn_name.to_string(),
-                description: #description.to_string(),
-                parameter_schema: serde_json::from_str(#parameter_schema_str).unwrap_or(serde_json::Value::Null),
-                function: #func_body,
-            }
+            tool_calling::Tool::new(
+                #fn_name,
+                #description,
+                serde_json::from_str(#parameter_schema_str).unwrap_or(serde_json::Value::Null),
+                #func_body,
+            )
+            .with_tags([#(#tags),*] as [&str; #tag_count])
+            .with_secret_params([#(#secrets),*] as [&str; #secret_count])
         }
     };
 
     expanded.into()
 }
 
+/// Builds the JSON Schema for a parameter type.
+///
+/// Unknown types are assumed to be serde structs and described as objects.
+fn type_schema(ty: &Type) -> serde_json::Map<String, serde_json::Value> {
+    let mut schema = serde_json::Map::new();
+    match ty {
+        Type::Array(array) => {
+            schema.insert("type".to_string(), json!("array"));
+            schema.insert("items".to_string(), type_schema(&array.elem).into());
+        }
+        Type::Slice(slice) => {
+            schema.insert("type".to_string(), json!("array"));
+            schema.insert("items".to_string(), type_schema(&slice.elem).into());
+        }
+        Type::Tuple(tuple) if tuple.elems.is_empty() => {
+            schema.insert("type".to_string(), json!("null"));
+        }
+        Type::Path(type_path) if type_path.qself.is_none() => {
+            let Some(last) = type_path.path.segments.last() else {
+                return schema;
+            };
+            let generics = match &last.arguments {
+                PathArguments::AngleBracketed(params) => params
+                    .args
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        GenericArgument::Type(t) => Some(t),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>(),
+                _ => Vec::new(),
+            };
+            match last.ident.to_string().as_str() {
+                "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+                | "i128" | "isize" => {
+                    schema.insert("type".to_string(), json!("integer"));
+                }
+                "f32" | "f64" => {
+                    schema.insert("type".to_string(), json!("number"));
+                }
+                "bool" => {
+                    schema.insert("type".to_string(), json!("boolean"));
+                }
+                "String" | "char" | "PathBuf" => {
+                    schema.insert("type".to_string(), json!("string"));
+                }
+                "Vec" | "VecDeque" | "HashSet" | "BTreeSet" => {
+                    schema.insert("type".to_string(), json!("array"));
+                    if let Some(item) = generics.first() {
+                        schema.insert("items".to_string(), type_schema(item).into());
+                    }
+                }
+                "HashMap" | "BTreeMap" => {
+                    schema.insert("type".to_string(), json!("object"));
+                    if let Some(value) = generics.get(1) {
+                        schema.insert(
+                            "additionalProperties".to_string(),
+                            type_schema(value).into(),
+                        );
+                    }
+                }
+                "Option" | "Box" | "Arc" | "Rc" => {
+                    if let Some(inner) = generics.first() {
+                        return type_schema(inner);
+                    }
+                }
+                // Arbitrary JSON: leave the schema unconstrained
+                "Value" => {}
+                _ => {
+                    schema.insert("type".to_string(), json!("object"));
+                }
+            }
+        }
+        _ => {}
+    }
+    schema
+}
+
 /// Checks if a type is Option<T> and returns the inner type T if so.
 fn is_option_type(ty: &Type) -> (bool, Option<&Type>) {
     if let Type::Path(type_path) = ty {
@@ -372,6 +567,36 @@ fn find_default_attr(attrs: &[syn::Attribute]) -> Result<Option<Lit>, syn::Error
     Ok(None)
 }
 
+/// Options set with `#[param(...)]` on a tool parameter.
+#[derive(Default)]
+struct ParamOptions {
+    /// `secret`: the value is redacted from errors and audit records.
+    secret: bool,
+    /// `context`: the parameter receives the call's `ToolContext` and is not in the schema.
+    context: bool,
+}
+
+/// Reads the `#[param(...)]` options of a parameter.
+fn param_options(attrs: &[syn::Attribute]) -> Result<ParamOptions, syn::Error> {
+    let mut options = ParamOptions::default();
+    for attr in attrs {
+        if attr.path().is_ident("param") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("secret") {
+                    options.secret = true;
+                    Ok(())
+                } else if meta.path.is_ident("context") {
+                    options.context = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported param option, expected `secret` or `context`"))
+                }
+            })?;
+        }
+    }
+    Ok(options)
+}
+
 // Add a passthrough attribute macro for `default` on parameters
 #[proc_macro_attribute]
 pub fn default(_args: TokenStream, item: TokenStream) -> TokenStream {